@@ -0,0 +1,80 @@
+//INFO: End-of-day briefing finalization
+//NOTE: Marks the last briefing of each finished day as final so tomorrow's briefing gets a real "yesterday's outcome"
+
+use crate::agent::proactive::DndSchedule;
+use crate::database::{queries, Database};
+use chrono::{DateTime, Duration as ChronoDuration, Local, NaiveTime, TimeZone, Utc};
+use std::time::Duration;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+//INFO: Settings key for the local "HH:MM" after which today's latest briefing is marked final
+pub const BRIEFING_FINALIZE_TIME_SETTING: &str = "briefing_finalize_time";
+pub const DEFAULT_BRIEFING_FINALIZE_TIME: &str = "23:00";
+
+//INFO: Briefings created before this instant belong to days that are over and can be finalized
+//NOTE: Before the finalize time that's the start of today; after it, today counts as done too
+pub fn finalize_cutoff(now: DateTime<Local>, finalize_at: NaiveTime) -> DateTime<Utc> {
+    let mut day = now.date_naive();
+    if now.time() >= finalize_at {
+        day += ChronoDuration::days(1);
+    }
+
+    let midnight = day.and_hms_opt(0, 0, 0).expect("midnight is a valid time");
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .unwrap_or_else(|| Local.from_utc_datetime(&midnight))
+        .with_timezone(&Utc)
+}
+
+//INFO: Finalizes every finished day that doesn't have a final briefing yet
+//NOTE: Also catches up on days where the app wasn't running at the finalize time
+pub fn finalize_briefings(database: &Database) {
+    let Ok(connection) = database.connection.lock() else {
+        return;
+    };
+
+    let finalize_at = queries::get_setting(&connection, BRIEFING_FINALIZE_TIME_SETTING)
+        .ok()
+        .flatten()
+        .as_deref()
+        .and_then(DndSchedule::parse_time)
+        .or_else(|| DndSchedule::parse_time(DEFAULT_BRIEFING_FINALIZE_TIME))
+        .expect("default finalize time is valid");
+
+    let cutoff = finalize_cutoff(Local::now(), finalize_at);
+    match queries::finalize_briefings_before(&connection, &cutoff.to_rfc3339()) {
+        Ok(0) => {}
+        Ok(count) => tracing::info!("🌙 Briefing Finalizer: Marked {} briefing(s) as final of day", count),
+        Err(e) => tracing::error!("❌ Briefing Finalizer: Failed to finalize briefings: {}", e),
+    }
+}
+
+pub async fn start_briefing_finalizer(database: Database) {
+    tracing::info!("🌙 Briefing Finalizer: Checking for finished days every {}s.", POLL_INTERVAL.as_secs());
+
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+        finalize_briefings(&database);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cutoff_moves_to_tomorrow_after_finalize_time() {
+        let finalize_at = NaiveTime::from_hms_opt(23, 0, 0).unwrap();
+        let today = Local.with_ymd_and_hms(2025, 3, 10, 0, 0, 0).unwrap();
+        let tomorrow = Local.with_ymd_and_hms(2025, 3, 11, 0, 0, 0).unwrap();
+
+        let evening = Local.with_ymd_and_hms(2025, 3, 10, 22, 59, 0).unwrap();
+        assert_eq!(finalize_cutoff(evening, finalize_at), today.with_timezone(&Utc));
+
+        let late = Local.with_ymd_and_hms(2025, 3, 10, 23, 0, 0).unwrap();
+        assert_eq!(finalize_cutoff(late, finalize_at), tomorrow.with_timezone(&Utc));
+    }
+}
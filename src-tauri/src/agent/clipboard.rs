@@ -1,10 +1,128 @@
 use crate::database::{queries, Database};
-use arboard::Clipboard;
+use arboard::{Clipboard, ImageData};
+use base64::{engine::general_purpose, Engine as _};
 use clipboard_master::{CallbackResult, ClipboardHandler, Master};
+use image::{DynamicImage, ImageFormat, RgbaImage};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io::Cursor;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+//INFO: Changes this soon after the last stored one replace it instead of adding a row
+//NOTE: Catches held Ctrl+C and apps that rewrite the clipboard several times per copy
+const CLIPBOARD_DEBOUNCE: Duration = Duration::from_millis(300);
+
+//INFO: A substring/superset of the previous entry only counts as a near-duplicate if the
+//      shorter one is at least this fraction of the longer (copying one word out of a page is a new entry)
+const NEAR_DUPLICATE_MIN_RATIO: f64 = 0.5;
+
+//INFO: True while the listener thread is inside Master::run
+static LISTENER_ALIVE: AtomicBool = AtomicBool::new(false);
+
+//INFO: Whether the clipboard listener is currently running (used by diagnostics)
+pub fn clipboard_listener_alive() -> bool {
+    LISTENER_ALIVE.load(Ordering::Relaxed)
+}
 
 struct Handler {
     database: Database,
     last_content: String,
+    last_image_hash: u64,
+    //INFO: Row id, text and time of the last text entry we stored
+    last_saved: Option<(i64, String, Instant)>,
+}
+
+//INFO: What to do with a new text entry given the previously stored one
+#[derive(Debug, PartialEq)]
+enum CaptureAction {
+    Insert,
+    ReplacePrevious,
+    Skip,
+}
+
+fn capture_action(previous: Option<(&str, Duration)>, text: &str) -> CaptureAction {
+    let Some((previous, elapsed)) = previous else {
+        return CaptureAction::Insert;
+    };
+
+    if text == previous {
+        return CaptureAction::Skip;
+    }
+    if elapsed < CLIPBOARD_DEBOUNCE {
+        return CaptureAction::ReplacePrevious;
+    }
+
+    let (shorter, longer) = if text.len() <= previous.len() {
+        (text, previous)
+    } else {
+        (previous, text)
+    };
+    let near_duplicate = longer.contains(shorter)
+        && shorter.len() as f64 >= longer.len() as f64 * NEAR_DUPLICATE_MIN_RATIO;
+
+    match (near_duplicate, text.len() > previous.len()) {
+        //INFO: Grew (e.g. re-copied with more selected) - keep the fuller version in the same row
+        (true, true) => CaptureAction::ReplacePrevious,
+        //INFO: A slice of what we already have - nothing new to store
+        (true, false) => CaptureAction::Skip,
+        (false, _) => CaptureAction::Insert,
+    }
+}
+
+//INFO: Encodes raw RGBA clipboard pixels as a base64 PNG
+fn encode_clipboard_image(image: &ImageData) -> Option<String> {
+    let buffer = RgbaImage::from_raw(
+        image.width as u32,
+        image.height as u32,
+        image.bytes.to_vec(),
+    )?;
+
+    let mut png = Vec::new();
+    DynamicImage::ImageRgba8(buffer)
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .ok()?;
+    Some(general_purpose::STANDARD.encode(png))
+}
+
+impl Handler {
+    //INFO: Stores a copied image, skipping repeats of the last one we saw
+    fn capture_image(&mut self, clipboard: &mut Clipboard) {
+        let Ok(image) = clipboard.get_image() else {
+            return;
+        };
+
+        let mut hasher = DefaultHasher::new();
+        image.bytes.hash(&mut hasher);
+        let hash = hasher.finish();
+        if hash == self.last_image_hash {
+            return;
+        }
+        self.last_image_hash = hash;
+
+        let Some(encoded) = encode_clipboard_image(&image) else {
+            tracing::error!("❌ Clipboard Manager: Failed to encode copied image");
+            return;
+        };
+
+        tracing::info!("📋 Clipboard Manager: Captured a {}x{} image 🖼️", image.width, image.height);
+
+        let connection = match self.database.connection.lock() {
+            Ok(connection) => connection,
+            Err(e) => {
+                tracing::error!("❌ Clipboard Manager: Failed to save image to vault: {:#}", e);
+                return;
+            }
+        };
+        if let Err(e) = queries::save_clipboard_item(&connection, &encoded, "image") {
+            tracing::error!("❌ Clipboard Manager: Failed to save image to vault: {}", e);
+        }
+
+        let (retention_days, max_items) = queries::get_clipboard_retention(&connection);
+        if let Err(e) = queries::prune_clipboard_history(&connection, retention_days, max_items) {
+            tracing::error!("❌ Clipboard Manager: Failed to prune history: {}", e);
+        }
+    }
 }
 
 impl ClipboardHandler for Handler {
@@ -15,48 +133,104 @@ impl ClipboardHandler for Handler {
             if let Ok(text) = clipboard.get_text() {
                 let trimmed = text.trim();
                 if !trimmed.is_empty() && trimmed != self.last_content {
-                    println!("📋 Clipboard Manager: Event received! Surgical capture initiated ({} chars)", trimmed.len());
+                    self.last_content = trimmed.to_string();
 
-                    let connection = self.database.connection.lock();
-                    if let Err(e) = queries::save_clipboard_item(&connection, trimmed, "text") {
-                        eprintln!("❌ Clipboard Manager: Failed to save to vault: {}", e);
+                    if looks_like_secret(trimmed) {
+                        tracing::info!("📋 Clipboard Manager: Skipped an entry that looks like a secret 🔒");
+                        return CallbackResult::Next;
                     }
 
-                    self.last_content = trimmed.to_string();
+                    let Ok(connection) = self.database.connection.lock() else {
+                        return CallbackResult::Next;
+                    };
+                    let max_length = queries::get_clipboard_max_length(&connection);
+                    if trimmed.chars().count() > max_length {
+                        tracing::info!("📋 Clipboard Manager: Skipped an entry over {} chars 📏", max_length);
+                        return CallbackResult::Next;
+                    }
+
+                    let previous = self
+                        .last_saved
+                        .as_ref()
+                        .map(|(_, text, at)| (text.as_str(), at.elapsed()));
+                    match capture_action(previous, trimmed) {
+                        CaptureAction::Skip => return CallbackResult::Next,
+                        CaptureAction::ReplacePrevious => {
+                            if let Some((id, text, at)) = self.last_saved.as_mut() {
+                                match queries::update_clipboard_item(&connection, *id, trimmed) {
+                                    Ok(true) => {
+                                        *text = trimmed.to_string();
+                                        *at = Instant::now();
+                                        return CallbackResult::Next;
+                                    }
+                                    //NOTE: The previous entry was pinned, deleted or pruned - save this copy as a new one
+                                    Ok(false) => {}
+                                    Err(e) => {
+                                        tracing::error!("❌ Clipboard Manager: Failed to update entry: {}", e);
+                                        return CallbackResult::Next;
+                                    }
+                                }
+                            }
+                        }
+                        CaptureAction::Insert => {}
+                    }
+
+                    tracing::info!("📋 Clipboard Manager: Event received! Surgical capture initiated ({} chars)", trimmed.len());
+
+                    match queries::save_clipboard_item(&connection, trimmed, "text") {
+                        Ok(id) => self.last_saved = Some((id, trimmed.to_string(), Instant::now())),
+                        Err(e) => tracing::error!("❌ Clipboard Manager: Failed to save to vault: {}", e),
+                    }
+
+                    //INFO: Enforce the retention policy on every write so the table never grows unbounded
+                    let (retention_days, max_items) = queries::get_clipboard_retention(&connection);
+                    if let Err(e) = queries::prune_clipboard_history(&connection, retention_days, max_items) {
+                        tracing::error!("❌ Clipboard Manager: Failed to prune history: {}", e);
+                    }
 
                     // 🧠 Latent Memory Extraction Hook (mod-5 threshold for testing)
                     const CLIPBOARD_EXTRACTION_THRESHOLD: i64 = 15;
                     if let Ok(count) = queries::count_clipboard_items(&connection) {
-                        println!("DEBUG: 🧠 PULSE: Clipboard history count: {}. (Threshold: {})", count, CLIPBOARD_EXTRACTION_THRESHOLD);
+                        tracing::debug!("🧠 PULSE: Clipboard history count: {}. (Threshold: {})", count, CLIPBOARD_EXTRACTION_THRESHOLD);
                         if count > 0 && count % CLIPBOARD_EXTRACTION_THRESHOLD == 0 {
-                            println!("DEBUG: 🧠 TRIGGER: Clipboard memory extraction triggered! Initializing background task...");
+                            tracing::debug!("🧠 TRIGGER: Clipboard memory extraction triggered! Initializing background task...");
                             
                             let db_clone = self.database.clone();
                             // Grab last 15 items for batching
                             let recent_items = queries::get_recent_clipboard_items(&connection, CLIPBOARD_EXTRACTION_THRESHOLD as u32).unwrap_or_default();
-                            let items_text: Vec<String> = recent_items.into_iter().map(|i| i.content).collect();
+                            let items_text: Vec<String> = recent_items
+                                .into_iter()
+                                .filter(|i| i.content_type == "text")
+                                .map(|i| i.content)
+                                .collect();
 
                             tokio::spawn(async move {
                                 // Fetch API Key
+                                //NOTE: Memory extraction is side work, so offline mode skips it
                                 let api_key = {
-                                    let conn = db_clone.connection.lock();
-                                    queries::get_api_token(&conn, "gemini").ok().flatten()
+                                    let Ok(conn) = db_clone.connection.lock() else {
+                                        return;
+                                    };
+                                    if crate::offline::is_offline(&conn) {
+                                        None
+                                    } else {
+                                        queries::get_api_token(&conn, "gemini").ok().flatten()
+                                    }
                                 };
 
                                 if let Some(encrypted) = api_key {
                                     if let Ok(key) = crate::crypto::decrypt_token(&encrypted) {
                                         let client = crate::gemini::client::GeminiClient::new(key);
-                                        let user_name = {
-                                            let conn = db_clone.connection.lock();
-                                            queries::get_user_profile(&conn)
-                                                .ok()
-                                                .flatten()
-                                                .map(|p| p.display_name)
-                                                .unwrap_or_else(|| "User".to_string())
-                                        };
+                                        let user_name = db_clone
+                                            .connection
+                                            .lock()
+                                            .ok()
+                                            .and_then(|conn| queries::get_user_profile(&conn).ok().flatten())
+                                            .map(|p| p.display_name)
+                                            .unwrap_or_else(|| "User".to_string());
                                         let prompt = crate::memory::extractor::build_clipboard_extraction_prompt(&items_text, &user_name);
                                         
-                                        println!("DEBUG: 🧠 Processing clipboard memories via Gemini...");
+                                        tracing::debug!("🧠 Processing clipboard memories via Gemini...");
                                         let result = client.send_chat(
                                             vec![crate::gemini::client::GeminiContent {
                                                 role: Some("user".to_string()),
@@ -73,15 +247,16 @@ impl ClipboardHandler for Handler {
                                         if let Ok(resp) = result {
                                             let text = resp.parts.iter().filter_map(|p| p.text.as_ref()).cloned().collect::<Vec<_>>().join("");
                                             if let Ok(mut memories) = crate::memory::extractor::parse_extracted_memories(&text) {
-                                                println!("DEBUG: 🧠 Extracted {} memories from clipboard!", memories.len());
+                                                tracing::debug!("🧠 Extracted {} memories from clipboard!", memories.len());
                                                 for memory in &mut memories {
                                                     // Embed and Store
                                                     if let Ok(emb) = client.generate_embedding(&memory.content).await {
                                                         memory.embedding = Some(emb);
-                                                        let conn = db_clone.connection.lock();
-                                                        let _ = crate::memory::core::store_memory(&conn, memory);
+                                                        if let Ok(conn) = db_clone.connection.lock() {
+                                                            let _ = crate::memory::core::store_memory(&conn, memory);
+                                                        }
                                                         let memory_snippet = memory.content.chars().take(60).collect::<String>();
-                                                        println!("DEBUG: 🧠 Stored clipboard memory: {}", memory_snippet);
+                                                        tracing::debug!("🧠 Stored clipboard memory: {}", memory_snippet);
                                                     }
                                                 }
                                             }
@@ -92,34 +267,99 @@ impl ClipboardHandler for Handler {
                         }
                     }
                 }
+            } else {
+                //INFO: No text on the clipboard usually means an image (or something we can't read)
+                self.capture_image(&mut clipboard);
             }
         }
         CallbackResult::Next
     }
 
     fn on_clipboard_error(&mut self, error: std::io::Error) -> CallbackResult {
-        eprintln!("❌ Clipboard Manager: Listener error: {}", error);
+        tracing::error!("❌ Clipboard Manager: Listener error: {}", error);
         CallbackResult::Next
     }
 }
 
+//INFO: Heuristic for clipboard entries that are probably passwords, API keys, or tokens
+//NOTE: arboard doesn't expose the OS "concealed"/sensitive clipboard flags, so we guess from the shape
+pub fn looks_like_secret(text: &str) -> bool {
+    //INFO: Anything with whitespace is prose or code, not a credential
+    if text.chars().any(char::is_whitespace) {
+        return false;
+    }
+
+    const KNOWN_PREFIXES: &[&str] = &[
+        "sk-", "sk_live_", "pk_live_", "ghp_", "gho_", "github_pat_", "xoxb-", "xoxp-", "AKIA",
+        "AIza", "ya29.", "glpat-", "-----BEGIN",
+    ];
+    if KNOWN_PREFIXES.iter().any(|p| text.starts_with(p)) {
+        return true;
+    }
+
+    let len = text.len();
+
+    //INFO: Long hex strings (hashes, raw keys)
+    if len >= 32 && text.chars().all(|c| c.is_ascii_hexdigit()) {
+        return true;
+    }
+
+    //INFO: Long base64/base64url blobs mixing cases and digits (tokens, JWT segments)
+    let is_base64ish = text
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '/' | '=' | '-' | '_' | '.'));
+    let has_upper = text.chars().any(|c| c.is_ascii_uppercase());
+    let has_lower = text.chars().any(|c| c.is_ascii_lowercase());
+    let has_digit = text.chars().any(|c| c.is_ascii_digit());
+    len >= 24 && is_base64ish && has_upper && has_lower && has_digit && !text.contains("://")
+}
+
 pub async fn start_clipboard_manager(database: Database) {
-    println!("📋 Clipboard Manager: Switched to event-driven mode. No polling, just vibes. ✨");
+    tracing::info!("📋 Clipboard Manager: Switched to event-driven mode. No polling, just vibes. ✨");
 
     let handler = Handler {
         database,
         last_content: String::new(),
+        last_image_hash: 0,
+        last_saved: None,
     };
 
     // Master::run is a blocking loop, so we move it to a dedicated background thread
     std::thread::spawn(move || match Master::new(handler) {
         Ok(mut master) => {
+            LISTENER_ALIVE.store(true, Ordering::Relaxed);
             if let Err(e) = master.run() {
-                eprintln!("❌ Clipboard Manager: Fatal listener error: {}", e);
+                tracing::error!("❌ Clipboard Manager: Fatal listener error: {}", e);
             }
+            LISTENER_ALIVE.store(false, Ordering::Relaxed);
         }
         Err(e) => {
-            eprintln!("❌ Clipboard Manager: Failed to initialize listener: {}", e);
+            tracing::error!("❌ Clipboard Manager: Failed to initialize listener: {}", e);
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_action_debounces_and_collapses_near_duplicates() {
+        let long_ago = CLIPBOARD_DEBOUNCE * 10;
+
+        assert_eq!(capture_action(None, "hello"), CaptureAction::Insert);
+        assert_eq!(
+            capture_action(Some(("hello", Duration::from_millis(50))), "something else"),
+            CaptureAction::ReplacePrevious
+        );
+        assert_eq!(
+            capture_action(Some(("hello wor", long_ago)), "hello world"),
+            CaptureAction::ReplacePrevious
+        );
+        assert_eq!(capture_action(Some(("hello world", long_ago)), "hello worl"), CaptureAction::Skip);
+        assert_eq!(
+            capture_action(Some(("a long paragraph about many things", long_ago)), "many"),
+            CaptureAction::Insert
+        );
+    }
+}
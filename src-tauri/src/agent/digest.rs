@@ -0,0 +1,209 @@
+//INFO: Daily digest - one low-noise summary of everything the proactive agent saw that day
+//NOTE: Per-item pings only cover high-signal mail and imminent meetings; the digest rounds up the rest
+//      (skipped emails, held items, meetings) plus open tasks, summarized by the chat model into three bullets
+
+use crate::database::queries::{self, ChatMessage};
+use crate::database::Database;
+use crate::gemini::client::{GeminiContent, GeminiPart};
+use crate::gemini::usage::{record_usage, FEATURE_DIGEST};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, NaiveTime, Utc};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_notification::NotificationExt;
+
+//INFO: Settings keys: digest time (local "HH:MM"), on/off switch, and when the last digest went out (RFC 3339)
+pub const DIGEST_TIME_SETTING: &str = "daily_digest_time";
+pub const DIGEST_ENABLED_SETTING: &str = "daily_digest_enabled";
+pub const LAST_DIGEST_SETTING: &str = "daily_digest_last";
+
+const DEFAULT_DIGEST_TIME: &str = "18:00";
+
+//INFO: Caps on what goes into the prompt
+const MAX_DIGEST_ITEMS: usize = 60;
+const MAX_DIGEST_TASKS: u32 = 20;
+
+//INFO: Whether the digest should go out now: past today's digest time and not already sent today
+pub fn is_digest_due(now: DateTime<Local>, digest_time: NaiveTime, last_sent: Option<DateTime<Local>>) -> bool {
+    now.time() >= digest_time && last_sent.map_or(true, |sent| sent.date_naive() < now.date_naive())
+}
+
+//INFO: Current digest settings, for the frontend
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DigestSettings {
+    pub enabled: bool,
+    pub time: String,
+}
+
+impl DigestSettings {
+    pub fn load(connection: &rusqlite::Connection) -> Self {
+        Self {
+            enabled: queries::get_setting(connection, DIGEST_ENABLED_SETTING)
+                .ok()
+                .flatten()
+                .map_or(true, |v| v != "false"),
+            time: digest_time(connection).format("%H:%M").to_string(),
+        }
+    }
+}
+
+fn digest_time(connection: &rusqlite::Connection) -> NaiveTime {
+    queries::get_setting(connection, DIGEST_TIME_SETTING)
+        .ok()
+        .flatten()
+        .and_then(|v| NaiveTime::parse_from_str(v.trim(), "%H:%M").ok())
+        .unwrap_or_else(|| NaiveTime::parse_from_str(DEFAULT_DIGEST_TIME, "%H:%M").unwrap())
+}
+
+fn last_digest(connection: &rusqlite::Connection) -> Option<DateTime<Utc>> {
+    queries::get_setting(connection, LAST_DIGEST_SETTING)
+        .ok()
+        .flatten()
+        .and_then(|v| DateTime::parse_from_rfc3339(&v).ok())
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+//INFO: Sends the daily digest if it's due; called from the proactive agent's loop
+pub async fn run_if_due(app: &AppHandle, database: &Database) {
+    let (enabled, time, last) = {
+        let Ok(connection) = database.connection.lock() else {
+            return;
+        };
+        let settings = DigestSettings::load(&connection);
+        (settings.enabled, digest_time(&connection), last_digest(&connection))
+    };
+    if !enabled || !is_digest_due(Local::now(), time, last.map(|l| l.with_timezone(&Local))) {
+        return;
+    }
+
+    match send_digest(app, database, last).await {
+        Ok(true) => tracing::info!("📰 Digest: Sent daily digest"),
+        Ok(false) => tracing::debug!("📰 Digest: Nothing to report today"),
+        Err(e) => {
+            //NOTE: Not marked as sent, so the next poll tries again
+            tracing::error!("❌ Digest: {:#}", e);
+            return;
+        }
+    }
+
+    let Ok(connection) = database.connection.lock() else {
+        return;
+    };
+    let _ = queries::save_setting(&connection, LAST_DIGEST_SETTING, &Utc::now().to_rfc3339());
+}
+
+//INFO: Collects what happened since `since`, summarizes it and posts it; false when there was nothing to summarize
+async fn send_digest(app: &AppHandle, database: &Database, since: Option<DateTime<Utc>>) -> Result<bool> {
+    //NOTE: With no previous digest, cover the last day
+    let since = since.unwrap_or_else(|| Utc::now() - chrono::Duration::days(1));
+    let items = {
+        let connection = database.connection.lock()?;
+        queries::get_notifications_since(&connection, &since.to_rfc3339())?
+    };
+    let tasks = crate::integrations::google_tasks::list_tasks(database, MAX_DIGEST_TASKS)
+        .await
+        .unwrap_or_default();
+    let open_tasks: Vec<_> = tasks.iter().filter(|t| t.status != "completed").collect();
+
+    if items.is_empty() && open_tasks.is_empty() {
+        return Ok(false);
+    }
+
+    let mut context = String::new();
+    if !items.is_empty() {
+        context.push_str("Activity seen today (provider, status, title - detail):\n");
+        for item in items.iter().rev().take(MAX_DIGEST_ITEMS) {
+            context.push_str(&format!(
+                "- [{}] [{}] {} - {}\n",
+                item.provider,
+                item.status,
+                item.title,
+                item.body.as_deref().unwrap_or("")
+            ));
+        }
+    }
+    if !open_tasks.is_empty() {
+        context.push_str("\nOpen tasks:\n");
+        for task in &open_tasks {
+            match &task.due {
+                Some(due) => context.push_str(&format!("- {} (due {})\n", task.title, due)),
+                None => context.push_str(&format!("- {}\n", task.title)),
+            }
+        }
+    }
+
+    let provider = {
+        let connection = database.connection.lock()?;
+        crate::llm::provider_from_settings(&connection)?
+    };
+    let response = provider
+        .chat(
+            vec![GeminiContent {
+                role: Some("user".to_string()),
+                parts: vec![GeminiPart::text(format!(
+                    "{}\n\nSummarize the user's day from the activity above in exactly 3 short markdown bullets. \
+                     Lead with anything that still needs action. Skipped items are low priority; mention them only as a group.",
+                    context
+                ))],
+            }],
+            Some("You write a calm end-of-day digest for a busy person. Reply with the 3 bullets only."),
+            None,
+        )
+        .await?;
+    record_usage(database, FEATURE_DIGEST, response.usage.as_ref());
+
+    let summary = response
+        .parts
+        .iter()
+        .filter_map(|p| p.text.as_ref())
+        .cloned()
+        .collect::<Vec<_>>()
+        .join("")
+        .trim()
+        .to_string();
+    if summary.is_empty() {
+        return Err(anyhow!("The model returned an empty digest"));
+    }
+
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title("Your daily digest")
+        .body(crate::text::strip_markdown(&summary))
+        .show()
+    {
+        tracing::error!("❌ Digest: Failed to show notification: {}", e);
+    }
+
+    let message = ChatMessage {
+        id: None,
+        role: "assistant".to_string(),
+        content: format!("**Daily digest**\n\n{}", summary),
+        image_data: None,
+        created_at: Utc::now().to_rfc3339(),
+        session_id: None,
+    };
+    {
+        let connection = database.connection.lock()?;
+        queries::save_chat_message(&connection, &message)?;
+    }
+    let _ = app.emit("daily-digest", &message.content);
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_digest_due_once_per_day_after_time() {
+        let at = |d: u32, h: u32, m: u32| Local.with_ymd_and_hms(2025, 3, d, h, m, 0).unwrap();
+        let six_pm = NaiveTime::from_hms_opt(18, 0, 0).unwrap();
+
+        assert!(!is_digest_due(at(10, 17, 59), six_pm, None));
+        assert!(is_digest_due(at(10, 18, 0), six_pm, None));
+        assert!(!is_digest_due(at(10, 21, 0), six_pm, Some(at(10, 18, 5))));
+        assert!(is_digest_due(at(11, 18, 30), six_pm, Some(at(10, 18, 5))));
+    }
+}
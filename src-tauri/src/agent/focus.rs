@@ -0,0 +1,111 @@
+//INFO: Focus mode - mutes proactive notifications until a set time
+//NOTE: Items found while focusing are still recorded (as "queued") and shown as one digest once it ends,
+//      the same way Do Not Disturb works; focus is just a one-off window instead of a daily schedule
+
+use crate::database::{queries, Database};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local, Utc};
+use serde::Serialize;
+use tauri::menu::MenuItem;
+use tauri::{AppHandle, Manager, Wry};
+
+//INFO: Settings key holding the focus expiry (RFC 3339, UTC)
+pub const FOCUS_UNTIL_SETTING: &str = "focus_until";
+
+//INFO: Length of a focus session started from the tray
+pub const TRAY_FOCUS_MINUTES: u32 = 60;
+
+//INFO: Longest focus session accepted (a day), so a typo can't mute Lumen for weeks
+const MAX_FOCUS_MINUTES: u32 = 24 * 60;
+
+//INFO: The tray's focus toggle, kept so its label can follow the focus state
+pub struct FocusMenuItem(pub MenuItem<Wry>);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FocusStatus {
+    pub active: bool,
+    pub until: Option<String>,
+}
+
+//INFO: When the current focus session ends; None when focus is off or already over
+pub fn focus_until(connection: &rusqlite::Connection) -> Option<DateTime<Utc>> {
+    queries::get_setting(connection, FOCUS_UNTIL_SETTING)
+        .ok()
+        .flatten()
+        .and_then(|v| DateTime::parse_from_rfc3339(&v).ok())
+        .map(|dt| dt.with_timezone(&Utc))
+        .filter(|until| *until > Utc::now())
+}
+
+pub fn is_focus_active(database: &Database) -> bool {
+    let Ok(connection) = database.connection.lock() else {
+        return false;
+    };
+    focus_until(&connection).is_some()
+}
+
+pub fn focus_status(connection: &rusqlite::Connection) -> FocusStatus {
+    let until = focus_until(connection);
+    FocusStatus {
+        active: until.is_some(),
+        until: until.map(|u| u.to_rfc3339()),
+    }
+}
+
+//INFO: Starts (or extends/shortens) focus mode for `duration_minutes` from now
+pub fn start_focus(app: &AppHandle, database: &Database, duration_minutes: u32) -> Result<FocusStatus> {
+    if duration_minutes == 0 || duration_minutes > MAX_FOCUS_MINUTES {
+        return Err(anyhow!("Focus duration must be between 1 and {} minutes", MAX_FOCUS_MINUTES));
+    }
+
+    let until = Utc::now() + chrono::Duration::minutes(duration_minutes as i64);
+    let status = {
+        let connection = database.connection.lock()?;
+        queries::save_setting(&connection, FOCUS_UNTIL_SETTING, &until.to_rfc3339())?;
+        focus_status(&connection)
+    };
+    tracing::info!("🎯 Focus: On for {} min", duration_minutes);
+    sync_tray(app, database);
+    Ok(status)
+}
+
+//INFO: Ends focus mode now; anything held is shown in the next digest
+pub fn end_focus(app: &AppHandle, database: &Database) -> Result<()> {
+    {
+        let connection = database.connection.lock()?;
+        queries::delete_setting(&connection, FOCUS_UNTIL_SETTING)?;
+    }
+    tracing::info!("🎯 Focus: Off");
+    sync_tray(app, database);
+    Ok(())
+}
+
+//INFO: Updates the tray item's label to match the focus state
+pub fn sync_tray(app: &AppHandle, database: &Database) {
+    let Some(item) = app.try_state::<FocusMenuItem>() else {
+        return;
+    };
+    let Ok(connection) = database.connection.lock() else {
+        return;
+    };
+    let until = focus_until(&connection);
+    let label = match until {
+        Some(until) => format!("End Focus (until {})", until.with_timezone(&Local).format("%H:%M")),
+        None => format!("Focus for {} min", TRAY_FOCUS_MINUTES),
+    };
+    if let Err(e) = item.0.set_text(label) {
+        tracing::warn!("⚠️ Focus: Failed to update tray item: {}", e);
+    }
+}
+
+//INFO: Tray click - starts a focus session, or ends the current one
+pub fn toggle_from_tray(app: &AppHandle, database: &Database) {
+    let result = if is_focus_active(database) {
+        end_focus(app, database)
+    } else {
+        start_focus(app, database, TRAY_FOCUS_MINUTES).map(|_| ())
+    };
+    if let Err(e) = result {
+        tracing::error!("❌ Focus: {:#}", e);
+    }
+}
@@ -1 +1,7 @@
+pub mod briefing;
 pub mod clipboard;
+pub mod digest;
+pub mod focus;
+pub mod proactive;
+pub mod reminders;
+pub mod token_refresh;
@@ -0,0 +1,621 @@
+//INFO: Proactive agent - watches the user's accounts and raises notifications on its own
+//NOTE: Runs on a fixed interval; the notifications table dedups across restarts
+
+use crate::database::{queries, Database};
+use crate::gemini::client::{GeminiContent, GeminiPart, GenerationConfig};
+use crate::gemini::usage::{record_usage, FEATURE_TRIAGE};
+use crate::integrations::google_calendar::{self, GoogleCalendarEvent};
+use crate::integrations::google_gmail::{self, GmailMessage};
+use chrono::{DateTime, Duration as ChronoDuration, Local, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_notification::NotificationExt;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+//INFO: How often old notification records are pruned
+const PRUNE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+//INFO: How far ahead to look for meetings that are about to start
+const UPCOMING_WINDOW_MINUTES: i64 = 15;
+
+//INFO: Which unread emails are considered for notifications each poll
+const GMAIL_QUERY: &str = "is:unread inbox newer_than:1d";
+const GMAIL_MAX_RESULTS: u32 = 10;
+
+//INFO: Settings key holding the JSON-encoded NotificationRules
+pub const NOTIFICATION_RULES_SETTING: &str = "notification_rules";
+
+//INFO: Triage criteria used when the user hasn't written their own
+pub const DEFAULT_TRIAGE_INSTRUCTION: &str = "Notify only for emails that need the user's attention soon: \
+messages from real people that ask a question or need a reply, time-sensitive information (meetings, deadlines, \
+deliveries, security alerts, payments), or anything personally important. Do NOT notify for newsletters, \
+marketing, promotions, social media updates, receipts that need no action, or automated digests.";
+
+//INFO: What a proactive notification can open
+//NOTE: Sent as the `notification-shown` event. Desktop notifications from the notification plugin
+//      can't be clicked through (it only reports actions on mobile), so the overlay offers the
+//      action for the latest ping via `open_notification_item` instead.
+#[derive(Debug, Clone, Serialize)]
+pub struct NotificationTarget {
+    pub provider: String,
+    pub item_id: String,
+    pub title: String,
+    pub url: Option<String>,
+}
+
+//INFO: Gmail web link for a message id, in the connected account
+pub fn gmail_message_url(connection: &rusqlite::Connection, message_id: &str) -> String {
+    crate::integrations::google_gmail::gmail_web_url(connection, &format!("all/{}", message_id))
+}
+
+//INFO: Settings keys for the Do Not Disturb schedule (local "HH:MM")
+pub const DND_START_SETTING: &str = "dnd_start";
+pub const DND_END_SETTING: &str = "dnd_end";
+
+//INFO: Quiet hours during which proactive notifications are queued instead of shown
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DndSchedule {
+    pub start: Option<String>,
+    pub end: Option<String>,
+}
+
+impl DndSchedule {
+    pub fn load(connection: &rusqlite::Connection) -> Self {
+        Self {
+            start: queries::get_setting(connection, DND_START_SETTING).ok().flatten(),
+            end: queries::get_setting(connection, DND_END_SETTING).ok().flatten(),
+        }
+    }
+
+    pub fn parse_time(value: &str) -> Option<NaiveTime> {
+        NaiveTime::parse_from_str(value.trim(), "%H:%M").ok()
+    }
+
+    //INFO: Whether `now` falls inside quiet hours
+    //NOTE: Handles windows that wrap past midnight (e.g. 22:00-07:00); unset or equal times mean off
+    pub fn is_active_at(&self, now: NaiveTime) -> bool {
+        let (Some(start), Some(end)) = (
+            self.start.as_deref().and_then(Self::parse_time),
+            self.end.as_deref().and_then(Self::parse_time),
+        ) else {
+            return false;
+        };
+
+        if start < end {
+            now >= start && now < end
+        } else if start > end {
+            now >= start || now < end
+        } else {
+            false
+        }
+    }
+}
+
+//INFO: The user's working hours from their profile
+//NOTE: Like DND, a window may wrap past midnight (night shifts); equal times mean "not set"
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorkingHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl WorkingHours {
+    pub fn from_profile(profile: &queries::UserProfile) -> Option<Self> {
+        let start = DndSchedule::parse_time(profile.work_start.as_deref()?)?;
+        let end = DndSchedule::parse_time(profile.work_end.as_deref()?)?;
+        (start != end).then_some(Self { start, end })
+    }
+
+    pub fn load(connection: &rusqlite::Connection) -> Option<Self> {
+        queries::get_user_profile(connection)
+            .ok()
+            .flatten()
+            .and_then(|p| Self::from_profile(&p))
+    }
+
+    pub fn contains(&self, now: NaiveTime) -> bool {
+        if self.start < self.end {
+            now >= self.start && now < self.end
+        } else {
+            now >= self.start || now < self.end
+        }
+    }
+
+    //INFO: Where `now` sits relative to the workday, for prompts
+    pub fn phase(&self, now: NaiveTime) -> &'static str {
+        if self.contains(now) {
+            "during working hours"
+        } else if self.start < self.end && now < self.start {
+            "before work"
+        } else if self.start < self.end {
+            "after work"
+        } else {
+            "outside working hours"
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        format!("{}-{}", self.start.format("%H:%M"), self.end.format("%H:%M"))
+    }
+}
+
+//INFO: Whether non-urgent pings should wait: outside working hours (when set)
+fn is_off_hours(database: &Database) -> bool {
+    let Ok(connection) = database.connection.lock() else {
+        return false;
+    };
+    WorkingHours::load(&connection).is_some_and(|hours| !hours.contains(Local::now().time()))
+}
+
+//INFO: Whether Do Not Disturb is currently on, in the user's local time
+fn is_dnd_active(database: &Database) -> bool {
+    let Ok(connection) = database.connection.lock() else {
+        return false;
+    };
+    DndSchedule::load(&connection).is_active_at(Local::now().time())
+}
+
+//INFO: User-controlled email notification rules
+//NOTE: Sender patterns are case-insensitive substrings of the From header (e.g. "boss@company.com", "noreply@")
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotificationRules {
+    #[serde(default)]
+    pub allowlist: Vec<String>,
+    #[serde(default)]
+    pub blocklist: Vec<String>,
+    #[serde(default)]
+    pub triage_instruction: Option<String>,
+}
+
+impl NotificationRules {
+    //INFO: Loads the rules from settings, falling back to defaults if unset or malformed
+    pub fn load(connection: &rusqlite::Connection) -> Self {
+        queries::get_setting(connection, NOTIFICATION_RULES_SETTING)
+            .ok()
+            .flatten()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn triage_instruction(&self) -> &str {
+        self.triage_instruction
+            .as_deref()
+            .filter(|i| !i.trim().is_empty())
+            .unwrap_or(DEFAULT_TRIAGE_INSTRUCTION)
+    }
+
+    //INFO: Deterministic decision from the sender lists, None means "ask the model"
+    //NOTE: The blocklist wins if a sender matches both
+    pub fn sender_decision(&self, from: &str) -> Option<bool> {
+        let from = from.to_lowercase();
+        let matches = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|p| p.trim().to_lowercase())
+                .any(|p| !p.is_empty() && from.contains(&p))
+        };
+
+        if matches(&self.blocklist) {
+            Some(false)
+        } else if matches(&self.allowlist) {
+            Some(true)
+        } else {
+            None
+        }
+    }
+}
+
+//INFO: Whether a proactive source is turned on
+//NOTE: Needs the Google integration connected and enabled, plus the global and per-source
+//      `proactive_*` settings not switched off
+fn is_source_enabled(database: &Database, source: &str) -> bool {
+    let Ok(connection) = database.connection.lock() else {
+        return false;
+    };
+
+    let setting_on = |key: &str| {
+        queries::get_setting(&connection, key)
+            .ok()
+            .flatten()
+            .map(|v| v != "false")
+            .unwrap_or(true)
+    };
+    if !setting_on("proactive_enabled") || !setting_on(&format!("proactive_{}_enabled", source)) {
+        return false;
+    }
+
+    let google_enabled = queries::get_integration(&connection, "google")
+        .ok()
+        .flatten()
+        .map(|i| i.enabled)
+        .unwrap_or(false);
+    google_enabled && queries::has_api_token(&connection, "google").unwrap_or(false)
+}
+
+//INFO: Shows a notification once per (provider, item_id), with `url` as what the overlay offers to open
+//NOTE: During Do Not Disturb or focus mode - or outside working hours, for non-urgent items - the item
+//      is recorded as "queued" and surfaced later in a digest
+#[allow(clippy::too_many_arguments)]
+fn notify_once(
+    app: &AppHandle,
+    database: &Database,
+    provider: &str,
+    item_id: &str,
+    title: &str,
+    body: &str,
+    url: Option<&str>,
+    urgent: bool,
+) {
+    let hold = is_dnd_active(database)
+        || super::focus::is_focus_active(database)
+        || (!urgent && is_off_hours(database));
+    let Ok(connection) = database.connection.lock() else {
+        return;
+    };
+    if queries::has_notification(&connection, provider, item_id).unwrap_or(false) {
+        return;
+    }
+
+    if hold {
+        let _ = queries::record_notification(&connection, provider, item_id, title, Some(body), "queued");
+        return;
+    }
+
+    let body = crate::text::strip_markdown(body);
+    let target = NotificationTarget {
+        provider: provider.to_string(),
+        item_id: item_id.to_string(),
+        title: title.to_string(),
+        url: url.map(String::from),
+    };
+    let shown = app
+        .notification()
+        .builder()
+        .title(title)
+        .body(&body)
+        .show();
+    if let Err(e) = shown {
+        tracing::error!("❌ Proactive Agent: Failed to show notification: {}", e);
+        return;
+    }
+
+    let _ = queries::record_notification(&connection, provider, item_id, title, Some(&body), "sent");
+    let _ = app.emit("notification-shown", &target);
+}
+
+//INFO: Picks the best place to join a meeting: video link first, then the location
+fn meeting_where(event: &GoogleCalendarEvent) -> Option<String> {
+    let video = event.hangout_link.clone().or_else(|| {
+        event
+            .conference_data
+            .as_ref()?
+            .get("entryPoints")?
+            .as_array()?
+            .iter()
+            .find(|e| e.get("entryPointType").and_then(|t| t.as_str()) == Some("video"))?
+            .get("uri")?
+            .as_str()
+            .map(|s| s.to_string())
+    });
+
+    video.or_else(|| event.location.clone().filter(|l| !l.trim().is_empty()))
+}
+
+//INFO: Notifies about meetings starting within the next UPCOMING_WINDOW_MINUTES
+pub async fn check_calendar(app: &AppHandle, database: &Database) {
+    if !is_source_enabled(database, "calendar") {
+        return;
+    }
+
+    let now = Utc::now();
+    let time_min = now.to_rfc3339();
+    let time_max = (now + ChronoDuration::minutes(UPCOMING_WINDOW_MINUTES)).to_rfc3339();
+
+    let events = match google_calendar::fetch_google_calendar_events(database, &time_min, &time_max, None).await {
+        Ok(events) => events,
+        Err(e) => {
+            tracing::error!("❌ Proactive Agent: Failed to fetch calendar: {}", e);
+            return;
+        }
+    };
+
+    for event in events {
+        //INFO: All-day events have no dateTime and aren't "starting soon"
+        let Some(start) = event
+            .start
+            .date_time
+            .as_deref()
+            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+        else {
+            continue;
+        };
+        if start.with_timezone(&Utc) < now {
+            continue;
+        }
+
+        let title = format!(
+            "Starting soon: {}",
+            event.summary.as_deref().unwrap_or("(No Title)")
+        );
+        let minutes = (start.with_timezone(&Utc) - now).num_minutes().max(0);
+        let body = match meeting_where(&event) {
+            Some(place) => format!("In {} min · {}", minutes, place),
+            None => format!("In {} min", minutes),
+        };
+
+        //NOTE: Keyed on the start time too, so a rescheduled meeting is announced again
+        let item_id = format!("{}@{}", event.id, start.to_rfc3339());
+        //NOTE: A meeting about to start is always worth an interruption (DND and focus aside)
+        notify_once(app, database, "calendar", &item_id, &title, &body, event.html_link.as_deref(), true);
+    }
+}
+
+//INFO: Decides whether an email is worth interrupting the user for, or None when it couldn't be decided
+//NOTE: Allow/block lists short-circuit before any model call. Without a provider, or when the call fails
+//      (network, rate limit), the answer is None so the email is left untriaged and retried next poll.
+pub async fn should_notify_for_email(
+    database: &Database,
+    rules: &NotificationRules,
+    email: &GmailMessage,
+) -> Option<bool> {
+    if let Some(decision) = rules.sender_decision(email.from.as_deref().unwrap_or("")) {
+        return Some(decision);
+    }
+
+    let provider = {
+        let connection = database.connection.lock().ok()?;
+        crate::llm::provider_from_settings(&connection)
+    };
+    let provider = provider.ok()?;
+
+    let prompt = format!(
+        "Triage criteria:\n{}\n\nEmail:\nFrom: {}\nSubject: {}\nPreview: {}\n\n\
+         Reply with JSON: {{\"notify\": true|false, \"reason\": \"short reason\"}}",
+        rules.triage_instruction(),
+        email.from.as_deref().unwrap_or("unknown"),
+        email.subject.as_deref().unwrap_or("(no subject)"),
+        email.snippet
+    );
+
+    let response = provider
+        .chat(
+            vec![GeminiContent {
+                role: Some("user".to_string()),
+                parts: vec![GeminiPart::text(prompt)],
+            }],
+            Some("You triage incoming email for a busy person. Return ONLY valid JSON."),
+            Some(GenerationConfig {
+                response_mime_type: Some("application/json".to_string()),
+                response_schema: None,
+            }),
+        )
+        .await;
+
+    if let Ok(resp) = &response {
+        record_usage(database, FEATURE_TRIAGE, resp.usage.as_ref());
+    }
+
+    let text = match response {
+        Ok(resp) => resp
+            .parts
+            .iter()
+            .filter_map(|p| p.text.as_ref())
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(""),
+        Err(e) => {
+            tracing::warn!("⚠️ Proactive Agent: Email triage failed, will retry: {}", e);
+            return None;
+        }
+    };
+
+    //NOTE: A reply that isn't the JSON we asked for counts as "don't notify" - retrying won't fix it
+    let notify = serde_json::from_str::<serde_json::Value>(&text)
+        .ok()
+        .and_then(|v| v.get("notify").and_then(|n| n.as_bool()))
+        .unwrap_or(false);
+    Some(notify)
+}
+
+//INFO: Notifies about new unread emails that pass triage
+pub async fn check_gmail(app: &AppHandle, database: &Database) {
+    if !is_source_enabled(database, "gmail") {
+        return;
+    }
+
+    let emails = match google_gmail::fetch_recent_emails_with_query(database, GMAIL_MAX_RESULTS, Some(GMAIL_QUERY)).await {
+        Ok(emails) => emails,
+        Err(e) => {
+            tracing::error!("❌ Proactive Agent: Failed to fetch emails: {}", e);
+            return;
+        }
+    };
+
+    let rules = {
+        let Ok(connection) = database.connection.lock() else {
+            return;
+        };
+        NotificationRules::load(&connection)
+    };
+
+    for email in emails {
+        let already_seen = {
+            let Ok(connection) = database.connection.lock() else {
+                return;
+            };
+            queries::has_notification(&connection, "gmail", &email.id).unwrap_or(false)
+        };
+        if already_seen {
+            continue;
+        }
+
+        let title = email
+            .from
+            .as_deref()
+            .map(|f| format!("📧 {}", f))
+            .unwrap_or_else(|| "📧 New email".to_string());
+        let body = email
+            .subject
+            .clone()
+            .unwrap_or_else(|| email.snippet.clone());
+
+        match should_notify_for_email(database, &rules, &email).await {
+            Some(true) => {
+                //INFO: Allowlisted senders count as urgent; model-triaged mail waits for working hours
+                let urgent = rules.sender_decision(email.from.as_deref().unwrap_or("")) == Some(true);
+                let url = {
+                    let Ok(connection) = database.connection.lock() else {
+                        return;
+                    };
+                    gmail_message_url(&connection, &email.id)
+                };
+                notify_once(app, database, "gmail", &email.id, &title, &body, Some(&url), urgent);
+            }
+            Some(false) => {
+                //INFO: Remember skipped emails too, so they aren't re-triaged on every poll
+                if let Ok(connection) = database.connection.lock() {
+                    let _ = queries::record_notification(&connection, "gmail", &email.id, &title, Some(&body), "skipped");
+                }
+            }
+            //NOTE: Nothing recorded, so the next poll triages it again
+            None => {}
+        }
+    }
+}
+
+//INFO: Once Do Not Disturb, focus mode and off-hours are over, shows everything queued during them as a single digest
+fn flush_dnd_digest(app: &AppHandle, database: &Database) {
+    if is_dnd_active(database) || super::focus::is_focus_active(database) || is_off_hours(database) {
+        return;
+    }
+
+    let Ok(connection) = database.connection.lock() else {
+        return;
+    };
+    let queued = queries::get_notifications_by_status(&connection, "queued").unwrap_or_default();
+    if queued.is_empty() {
+        return;
+    }
+
+    let title = format!("While you were away: {} update{}", queued.len(), if queued.len() == 1 { "" } else { "s" });
+    let mut lines: Vec<String> = queued.iter().take(5).map(|n| format!("• {}", crate::text::strip_markdown(&n.title))).collect();
+    if queued.len() > 5 {
+        lines.push(format!("…and {} more", queued.len() - 5));
+    }
+
+    if let Err(e) = app.notification().builder().title(&title).body(lines.join("\n")).show() {
+        tracing::error!("❌ Proactive Agent: Failed to show DND digest: {}", e);
+        return;
+    }
+    let _ = queries::update_notification_status(&connection, "queued", "sent");
+}
+
+//INFO: Runs every proactive check once
+pub async fn check_for_updates(app: &AppHandle, database: &Database) {
+    let offline = match database.connection.lock() {
+        Ok(connection) => crate::offline::is_offline(&connection),
+        Err(e) => {
+            tracing::error!("❌ Proactive Agent: {:#}", e);
+            return;
+        }
+    };
+    if offline {
+        tracing::debug!("🤖 Proactive Agent: Paused in offline mode");
+        return;
+    }
+    flush_dnd_digest(app, database);
+    check_gmail(app, database).await;
+    check_calendar(app, database).await;
+
+    //NOTE: Runs after the checks so today's last items make it in; waits out DND and focus like other pings
+    if !is_dnd_active(database) && !super::focus::is_focus_active(database) {
+        super::digest::run_if_due(app, database).await;
+    }
+}
+
+//INFO: Drops old notification records and tool audit entries so the tables don't grow forever
+fn prune_old_notifications(database: &Database) {
+    let Ok(connection) = database.connection.lock() else {
+        return;
+    };
+    let retention_days = queries::get_notification_retention(&connection);
+    match queries::prune_notifications(&connection, retention_days) {
+        Ok(0) => {}
+        Ok(count) => tracing::info!("🤖 Proactive Agent: Pruned {} old notification records", count),
+        Err(e) => tracing::error!("❌ Proactive Agent: Failed to prune notifications: {:#}", e),
+    }
+    match queries::prune_tool_audit(&connection, queries::TOOL_AUDIT_RETENTION_DAYS) {
+        Ok(0) => {}
+        Ok(count) => tracing::info!("🤖 Proactive Agent: Pruned {} old tool audit entries", count),
+        Err(e) => tracing::error!("❌ Proactive Agent: Failed to prune tool audit: {:#}", e),
+    }
+}
+
+pub async fn start_proactive_agent(app: AppHandle, database: Database) {
+    tracing::info!("🤖 Proactive Agent: Checking for updates every {}s.", POLL_INTERVAL.as_secs());
+
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    let mut last_prune: Option<std::time::Instant> = None;
+    loop {
+        interval.tick().await;
+        check_for_updates(&app, &database).await;
+        //INFO: Puts the tray label back once a focus session has run out
+        super::focus::sync_tray(&app, &database);
+
+        if last_prune.map_or(true, |at| at.elapsed() >= PRUNE_INTERVAL) {
+            prune_old_notifications(&database);
+            last_prune = Some(std::time::Instant::now());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sender_rules_short_circuit_triage() {
+        let rules = NotificationRules {
+            allowlist: vec!["boss@company.com".to_string(), "@family.org".to_string()],
+            blocklist: vec!["noreply@".to_string(), "boss@company.com".to_string()],
+            triage_instruction: None,
+        };
+
+        assert_eq!(rules.sender_decision("Mum <mum@family.org>"), Some(true));
+        assert_eq!(rules.sender_decision("Shop <NoReply@shop.com>"), Some(false));
+        //INFO: Blocklist wins over allowlist
+        assert_eq!(rules.sender_decision("The Boss <boss@company.com>"), Some(false));
+        assert_eq!(rules.sender_decision("someone@else.com"), None);
+        assert_eq!(rules.triage_instruction(), DEFAULT_TRIAGE_INSTRUCTION);
+    }
+
+    #[test]
+    fn test_working_hours_phase() {
+        let at = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        let hours = WorkingHours { start: at(9, 0), end: at(17, 30) };
+        assert_eq!(hours.phase(at(7, 45)), "before work");
+        assert_eq!(hours.phase(at(12, 0)), "during working hours");
+        assert_eq!(hours.phase(at(17, 30)), "after work");
+
+        let night = WorkingHours { start: at(22, 0), end: at(6, 0) };
+        assert!(night.contains(at(2, 0)));
+        assert_eq!(night.phase(at(12, 0)), "outside working hours");
+    }
+
+    #[test]
+    fn test_dnd_window_wraps_midnight() {
+        let at = |t: &str| DndSchedule::parse_time(t).unwrap();
+        let overnight = DndSchedule {
+            start: Some("22:00".to_string()),
+            end: Some("07:00".to_string()),
+        };
+
+        assert!(overnight.is_active_at(at("23:30")));
+        assert!(overnight.is_active_at(at("03:00")));
+        assert!(!overnight.is_active_at(at("07:00")));
+        assert!(!overnight.is_active_at(at("12:00")));
+        assert!(!DndSchedule::default().is_active_at(at("03:00")));
+    }
+}
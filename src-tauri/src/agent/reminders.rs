@@ -0,0 +1,100 @@
+//INFO: Reminder scheduler - fires system notifications for due reminders
+//NOTE: Polls the reminders table every minute; the notifications table dedups across restarts
+
+use crate::database::{queries, Database};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use std::time::Duration;
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+//INFO: Emitted (with the reminder id) when a reminder is added outside the app's own views, e.g. via the local API
+pub const REMINDERS_CHANGED_EVENT: &str = "reminders-changed";
+
+//INFO: Parses a reminder's due_at, accepting RFC3339 or a naive local timestamp
+pub fn parse_due_at(due_at: &str) -> Option<DateTime<Utc>> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(due_at) {
+        return Some(dt.with_timezone(&Utc));
+    }
+
+    ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(due_at, fmt).ok())
+        .and_then(|naive| Local.from_local_datetime(&naive).single())
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+//INFO: Rejects a due time the scheduler couldn't read, so a reminder is never stored that would never fire
+pub fn check_due_at(due_at: &str) -> Result<(), String> {
+    match parse_due_at(due_at) {
+        Some(_) => Ok(()),
+        None => Err(format!(
+            "Unrecognized due_at '{}': use RFC3339 (2025-03-07T09:00:00Z) or local time (2025-03-07 09:00)",
+            due_at
+        )),
+    }
+}
+
+//INFO: Fires notifications for every pending reminder whose due time has passed
+pub fn fire_due_reminders(app: &AppHandle, database: &Database) {
+    let Ok(connection) = database.connection.lock() else {
+        return;
+    };
+
+    let reminders = match queries::get_pending_reminders(&connection) {
+        Ok(r) => r,
+        Err(e) => {
+            tracing::error!("❌ Reminder Scheduler: Failed to load reminders: {}", e);
+            return;
+        }
+    };
+
+    let now = Utc::now();
+    for reminder in reminders {
+        let Some(due) = reminder.due_at.as_deref().and_then(parse_due_at) else {
+            continue;
+        };
+        if due > now {
+            continue;
+        }
+
+        //NOTE: Promoted reminders are announced by Google, so they just complete quietly here
+        let item_id = reminder.id.to_string();
+        if reminder.linked_to.is_none() && !queries::has_notification(&connection, "reminder", &item_id).unwrap_or(false) {
+            tracing::info!("🔔 Reminder Scheduler: Firing reminder #{}", reminder.id);
+            if let Err(e) = app
+                .notification()
+                .builder()
+                .title("Reminder")
+                .body(crate::text::strip_markdown(&reminder.content))
+                .show()
+            {
+                tracing::error!("❌ Reminder Scheduler: Failed to show notification: {}", e);
+                continue;
+            }
+            let _ = queries::record_notification(
+                &connection,
+                "reminder",
+                &item_id,
+                "Reminder",
+                Some(&reminder.content),
+                "sent",
+            );
+        }
+
+        if let Err(e) = queries::complete_reminder(&connection, reminder.id) {
+            tracing::error!("❌ Reminder Scheduler: Failed to complete reminder: {}", e);
+        }
+    }
+}
+
+pub async fn start_reminder_scheduler(app: AppHandle, database: Database) {
+    tracing::info!("🔔 Reminder Scheduler: Watching for due reminders every {}s.", POLL_INTERVAL.as_secs());
+
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+        fire_due_reminders(&app, &database);
+    }
+}
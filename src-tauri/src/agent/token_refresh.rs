@@ -0,0 +1,118 @@
+//INFO: Background Google token refresh
+//NOTE: Without this the access token is only refreshed when a request finds it expired (or gets a 401),
+//      so the first dashboard load after the machine was idle waits on an extra OAuth round trip.
+//      Refreshing a little before `expires_at` keeps a fresh token in the store for every request.
+
+use crate::database::{queries, Database};
+use crate::integrations::google;
+use crate::oauth::google::GrantRevoked;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use std::time::Duration;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+//INFO: Settings key to turn background refresh off ("false"); on by default
+pub const GOOGLE_AUTO_REFRESH_SETTING: &str = "google_auto_refresh";
+
+//NOTE: Comfortably ahead of the 5 minute margin `is_expired` uses, so requests never see a stale token
+const REFRESH_MARGIN_MINUTES: i64 = 10;
+
+//INFO: Whether a token expiring at `expires_at` should be refreshed now
+pub fn needs_refresh(expires_at: Option<DateTime<Utc>>, now: DateTime<Utc>) -> bool {
+    match expires_at {
+        Some(expiry) => now + ChronoDuration::minutes(REFRESH_MARGIN_MINUTES) >= expiry,
+        None => true,
+    }
+}
+
+fn auto_refresh_enabled(connection: &rusqlite::Connection) -> bool {
+    let setting_on = queries::get_setting(connection, GOOGLE_AUTO_REFRESH_SETTING)
+        .ok()
+        .flatten()
+        .map_or(true, |v| v != "false");
+    //NOTE: A revoked grant fails the same way on every retry, so wait for the user to sign in again
+    let google_on = queries::get_integration(connection, "google")
+        .ok()
+        .flatten()
+        .is_some_and(|i| i.enabled && i.status != queries::NEEDS_REAUTH_STATUS);
+    setting_on && google_on
+}
+
+//INFO: Refreshes the stored Google token if it's about to expire
+async fn refresh_if_due(database: &Database) {
+    let tokens = {
+        let Ok(connection) = database.connection.lock() else {
+            return;
+        };
+        if !auto_refresh_enabled(&connection) {
+            return;
+        }
+        //NOTE: Also fails in offline mode, which is exactly when we shouldn't be calling Google
+        match google::get_google_tokens(&connection) {
+            Ok(tokens) => tokens,
+            Err(e) => {
+                tracing::debug!("Token Refresh: Skipping, no usable Google tokens: {}", e);
+                return;
+            }
+        }
+    };
+
+    if tokens.refresh_token.is_none() || !needs_refresh(tokens.expires_at, Utc::now()) {
+        return;
+    }
+
+    //INFO: Saves the new token through the same save_api_token path as on-demand refreshes
+    match google::refresh_google_tokens(database, &tokens).await {
+        Ok(_) => {}
+        Err(e) if e.downcast_ref::<GrantRevoked>().is_some() => {
+            tracing::warn!("⚠️ Token Refresh: Google revoked the grant, pausing until you reconnect Google: {:#}", e);
+        }
+        Err(e) => {
+            tracing::warn!("⚠️ Token Refresh: Failed to refresh Google token ahead of expiry: {:#}", e);
+        }
+    }
+}
+
+pub async fn start_token_refresher(database: Database) {
+    tracing::info!("🔑 Token Refresh: Checking Google token expiry every {}s.", POLL_INTERVAL.as_secs());
+
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+        refresh_if_due(&database).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_needs_refresh_within_margin() {
+        let now = Utc::now();
+        assert!(!needs_refresh(Some(now + ChronoDuration::minutes(30)), now));
+        assert!(needs_refresh(Some(now + ChronoDuration::minutes(8)), now));
+        assert!(needs_refresh(Some(now - ChronoDuration::minutes(1)), now));
+        assert!(needs_refresh(None, now));
+    }
+
+    #[test]
+    fn test_revoked_grant_stops_background_refresh() {
+        let connection = rusqlite::Connection::open_in_memory().unwrap();
+        crate::database::initialize_database(&connection).unwrap();
+        let google = queries::Integration {
+            name: "google".to_string(),
+            enabled: true,
+            config: None,
+            last_sync: None,
+            status: "connected".to_string(),
+            last_error: None,
+            last_error_at: None,
+        };
+        queries::save_integration(&connection, &google).unwrap();
+        assert!(auto_refresh_enabled(&connection));
+
+        queries::mark_integration_needs_reauth(&connection, "google", "invalid_grant").unwrap();
+        assert!(!auto_refresh_enabled(&connection));
+    }
+}
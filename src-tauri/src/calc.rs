@@ -0,0 +1,369 @@
+//INFO: Deterministic arithmetic and date math for the `calculate` and `compute_datetime` tools
+//NOTE: The model is unreliable at "3 days from now" or "what's 17% of 2,340", so it hands these off here
+
+use anyhow::{anyhow, Result};
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, Local, Months, NaiveDate, TimeDelta, TimeZone,
+    Weekday,
+};
+
+// === Arithmetic ===
+
+//INFO: Limits on what the model can hand the parser, which recurses once per sign or parenthesis
+const MAX_EXPRESSION_CHARS: usize = 1000;
+const MAX_NESTING_DEPTH: usize = 64;
+
+//INFO: Evaluates + - * / % ^ with parentheses, unary minus and decimal numbers
+//NOTE: `^` is right-associative and binds tighter than unary minus, so -2^2 = -4 like most calculators
+pub fn evaluate(expression: &str) -> Result<f64> {
+    let mut parser = Parser {
+        chars: expression.chars().filter(|c| !c.is_whitespace() && *c != ',').collect(),
+        pos: 0,
+        depth: 0,
+    };
+    if parser.chars.is_empty() {
+        return Err(anyhow!("Expression is empty"));
+    }
+    if parser.chars.len() > MAX_EXPRESSION_CHARS {
+        return Err(anyhow!("Expression is longer than {} characters", MAX_EXPRESSION_CHARS));
+    }
+
+    let value = parser.expression()?;
+    if let Some(c) = parser.peek() {
+        return Err(anyhow!("Unexpected '{}' at position {}", c, parser.pos + 1));
+    }
+    if !value.is_finite() {
+        return Err(anyhow!("Result is not a finite number"));
+    }
+    Ok(value)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    //INFO: Current recursion depth through unary signs, exponents and parentheses
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expression(&mut self) -> Result<f64> {
+        let mut value = self.term()?;
+        loop {
+            if self.eat('+') {
+                value += self.term()?;
+            } else if self.eat('-') {
+                value -= self.term()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<f64> {
+        let mut value = self.unary()?;
+        loop {
+            if self.eat('*') {
+                value *= self.unary()?;
+            } else if self.eat('/') {
+                let divisor = self.unary()?;
+                if divisor == 0.0 {
+                    return Err(anyhow!("Division by zero"));
+                }
+                value /= divisor;
+            } else if self.eat('%') {
+                let divisor = self.unary()?;
+                if divisor == 0.0 {
+                    return Err(anyhow!("Division by zero"));
+                }
+                value %= divisor;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    //NOTE: Every recursive step goes through here, so this is where the nesting depth is capped
+    fn unary(&mut self) -> Result<f64> {
+        self.depth += 1;
+        if self.depth > MAX_NESTING_DEPTH {
+            return Err(anyhow!("Expression is nested too deeply"));
+        }
+        let value = if self.eat('-') {
+            self.unary().map(|v| -v)
+        } else if self.eat('+') {
+            self.unary()
+        } else {
+            self.power()
+        };
+        self.depth -= 1;
+        value
+    }
+
+    fn power(&mut self) -> Result<f64> {
+        let base = self.atom()?;
+        if self.eat('^') {
+            let exponent = self.unary()?;
+            return Ok(base.powf(exponent));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<f64> {
+        if self.eat('(') {
+            let value = self.expression()?;
+            if !self.eat(')') {
+                return Err(anyhow!("Missing closing parenthesis"));
+            }
+            return Ok(value);
+        }
+
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return match self.peek() {
+                Some(c) => Err(anyhow!("Unexpected '{}' at position {}", c, self.pos + 1)),
+                None => Err(anyhow!("Expression ends unexpectedly")),
+            };
+        }
+
+        let number: String = self.chars[start..self.pos].iter().collect();
+        number
+            .parse()
+            .map_err(|_| anyhow!("'{}' is not a valid number", number))
+    }
+}
+
+// === Dates ===
+
+//INFO: Parses a tool's `base` argument: RFC3339, a plain date (midnight local) or empty / "now"
+pub fn parse_base(base: &str) -> Result<DateTime<FixedOffset>> {
+    let base = base.trim();
+    if base.is_empty() || base.eq_ignore_ascii_case("now") {
+        return Ok(Local::now().fixed_offset());
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(base) {
+        return Ok(time);
+    }
+    let date = NaiveDate::parse_from_str(base, "%Y-%m-%d")
+        .map_err(|_| anyhow!("Invalid base '{}': expected RFC3339, YYYY-MM-DD or 'now'", base))?;
+    local_midnight(date)
+}
+
+fn local_midnight(date: NaiveDate) -> Result<DateTime<FixedOffset>> {
+    let midnight = date.and_hms_opt(0, 0, 0).expect("midnight is a valid time");
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map(|t| t.fixed_offset())
+        .ok_or_else(|| anyhow!("Midnight on {} doesn't exist in the local timezone", date))
+}
+
+//INFO: Midnight at the start of `time`'s day, keeping its UTC offset
+fn start_of_day(time: DateTime<FixedOffset>) -> DateTime<FixedOffset> {
+    let midnight = time.date_naive().and_hms_opt(0, 0, 0).expect("midnight is a valid time");
+    time.offset()
+        .from_local_datetime(&midnight)
+        .single()
+        .expect("fixed offsets have no gaps")
+}
+
+//INFO: Applies one operation to `base`
+//NOTE: Supported operations:
+//      - offsets like "+3 days", "-2h", "+1 week 2 days" (units: minutes, hours, days, weeks, months, years)
+//      - "start_of_day", "end_of_day", "start_of_week", "end_of_week" (weeks start on Monday), "start_of_month"
+//      - "next friday", "previous monday" (never the base day itself)
+pub fn compute_datetime(base: DateTime<FixedOffset>, operation: &str) -> Result<DateTime<FixedOffset>> {
+    let operation = operation.trim().to_lowercase();
+    let day_start = start_of_day(base);
+    let days_from_monday = base.weekday().num_days_from_monday() as i64;
+
+    match operation.replace(' ', "_").as_str() {
+        "start_of_day" | "today" => return Ok(day_start),
+        "end_of_day" => return Ok(day_start + Duration::days(1) - Duration::seconds(1)),
+        "start_of_week" => return Ok(day_start - Duration::days(days_from_monday)),
+        "end_of_week" => {
+            return Ok(day_start + Duration::days(7 - days_from_monday) - Duration::seconds(1));
+        }
+        "start_of_month" => {
+            return Ok(day_start - Duration::days(base.day0() as i64));
+        }
+        _ => {}
+    }
+
+    if let Some((direction, day)) = operation.split_once(' ') {
+        if matches!(direction, "next" | "previous" | "last") {
+            let weekday: Weekday = day
+                .trim()
+                .parse()
+                .map_err(|_| anyhow!("'{}' is not a weekday", day.trim()))?;
+            let target = weekday.num_days_from_monday() as i64;
+            //NOTE: 1..=7 days away, so "next wednesday" on a Wednesday is a week out
+            let days_away = |from: i64, to: i64| match (to - from).rem_euclid(7) {
+                0 => 7,
+                d => d,
+            };
+            let delta = if direction == "next" {
+                days_away(days_from_monday, target)
+            } else {
+                -days_away(target, days_from_monday)
+            };
+            return Ok(base + Duration::days(delta));
+        }
+    }
+
+    apply_offsets(base, &operation)
+}
+
+//INFO: Applies "+3 days -2h" style offsets; a term without a sign inherits the previous one
+fn apply_offsets(base: DateTime<FixedOffset>, operation: &str) -> Result<DateTime<FixedOffset>> {
+    let tokens = split_offset_tokens(operation);
+    if tokens.is_empty() {
+        return Err(anyhow!("Unknown operation '{}'", operation));
+    }
+
+    let mut result = base;
+    let mut negative = false;
+    for (sign, amount, unit) in tokens {
+        if let Some(sign) = sign {
+            negative = sign == '-';
+        }
+        let amount: i64 = amount
+            .parse()
+            .map_err(|_| anyhow!("'{}' is not a whole number in '{}'", amount, operation))?;
+        let amount = if negative { -amount } else { amount };
+        let out_of_range = || anyhow!("'{}' is out of range", operation);
+
+        //NOTE: The amount comes from the model, so every step is checked instead of panicking on overflow
+        let delta = match unit.trim_end_matches('s') {
+            "min" | "minute" | "m" => TimeDelta::try_minutes(amount),
+            "h" | "hr" | "hour" => TimeDelta::try_hours(amount),
+            "d" | "day" => TimeDelta::try_days(amount),
+            "w" | "wk" | "week" => TimeDelta::try_weeks(amount),
+            "mo" | "month" => {
+                result = add_months(result, amount)?;
+                continue;
+            }
+            "y" | "yr" | "year" => {
+                result = add_months(result, amount.checked_mul(12).ok_or_else(out_of_range)?)?;
+                continue;
+            }
+            _ => return Err(anyhow!("Unknown unit '{}' in '{}'", unit, operation)),
+        };
+        result = delta
+            .and_then(|delta| result.checked_add_signed(delta))
+            .ok_or_else(out_of_range)?;
+    }
+    Ok(result)
+}
+
+//INFO: Splits "+1 week 2days -3h" into (sign, amount, unit) terms
+fn split_offset_tokens(operation: &str) -> Vec<(Option<char>, String, String)> {
+    let chars: Vec<char> = operation.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let sign = match chars[i] {
+            '+' | '-' => {
+                i += 1;
+                Some(chars[i - 1])
+            }
+            _ => None,
+        };
+        let amount_start = i;
+        while i < chars.len() && chars[i].is_ascii_digit() {
+            i += 1;
+        }
+        let unit_start = i;
+        while i < chars.len() && chars[i].is_alphabetic() {
+            i += 1;
+        }
+        let amount: String = chars[amount_start..unit_start].iter().collect();
+        let unit: String = chars[unit_start..i].iter().collect();
+        if amount.is_empty() || unit.is_empty() {
+            return Vec::new();
+        }
+        tokens.push((sign, amount, unit));
+    }
+    tokens
+}
+
+//NOTE: Month math clamps to the last day of shorter months (Jan 31 + 1 month = Feb 28/29)
+fn add_months(time: DateTime<FixedOffset>, months: i64) -> Result<DateTime<FixedOffset>> {
+    let count = Months::new(months.unsigned_abs().try_into().map_err(|_| anyhow!("Too many months"))?);
+    let shifted = if months >= 0 {
+        time.checked_add_months(count)
+    } else {
+        time.checked_sub_months(count)
+    };
+    shifted.ok_or_else(|| anyhow!("Date out of range"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base() -> DateTime<FixedOffset> {
+        // Wednesday
+        DateTime::parse_from_rfc3339("2026-01-14T15:30:00+01:00").unwrap()
+    }
+
+    fn compute(operation: &str) -> String {
+        compute_datetime(base(), operation).unwrap().to_rfc3339()
+    }
+
+    #[test]
+    fn test_evaluate_precedence_and_errors() {
+        assert_eq!(evaluate("2 + 3 * 4").unwrap(), 14.0);
+        assert_eq!(evaluate("(2 + 3) * 4").unwrap(), 20.0);
+        assert_eq!(evaluate("2 ^ 3 ^ 2").unwrap(), 512.0);
+        assert_eq!(evaluate("-2^2").unwrap(), -4.0);
+        assert_eq!(evaluate("2,340 * 17 / 100").unwrap(), 397.8);
+        assert_eq!(evaluate("10 % 4").unwrap(), 2.0);
+        assert!(evaluate("1 / 0").is_err());
+        assert!(evaluate("2 + ").is_err());
+        assert!(evaluate("(1 + 2").is_err());
+        assert!(evaluate("abs(3)").is_err());
+        assert!(evaluate(&format!("{}1", "-".repeat(500))).is_err());
+        assert!(evaluate(&format!("{}1{}", "(".repeat(200), ")".repeat(200))).is_err());
+        assert_eq!(evaluate("((((2))))").unwrap(), 2.0);
+    }
+
+    #[test]
+    fn test_compute_datetime_offsets() {
+        assert_eq!(compute("+3 days"), "2026-01-17T15:30:00+01:00");
+        assert_eq!(compute("-90min"), "2026-01-14T14:00:00+01:00");
+        assert_eq!(compute("+1 week 2 days"), "2026-01-23T15:30:00+01:00");
+        assert_eq!(compute("+1 month"), "2026-02-14T15:30:00+01:00");
+        assert!(compute_datetime(base(), "+3 fortnights").is_err());
+        assert!(compute_datetime(base(), "soon").is_err());
+        assert!(compute_datetime(base(), "+99999999999 days").is_err());
+        assert!(compute_datetime(base(), "+999999999999999999 years").is_err());
+    }
+
+    #[test]
+    fn test_compute_datetime_boundaries_and_weekdays() {
+        assert_eq!(compute("start_of_day"), "2026-01-14T00:00:00+01:00");
+        assert_eq!(compute("start of week"), "2026-01-12T00:00:00+01:00");
+        assert_eq!(compute("end_of_week"), "2026-01-18T23:59:59+01:00");
+        assert_eq!(compute("start_of_month"), "2026-01-01T00:00:00+01:00");
+        assert_eq!(compute("next friday"), "2026-01-16T15:30:00+01:00");
+        assert_eq!(compute("next wednesday"), "2026-01-21T15:30:00+01:00");
+        assert_eq!(compute("previous monday"), "2026-01-12T15:30:00+01:00");
+        assert_eq!(compute("last wednesday"), "2026-01-07T15:30:00+01:00");
+    }
+}
@@ -1,15 +1,25 @@
 // src-tauri/src/commands/auth.rs
-use crate::crypto::encrypt_token;
-use crate::database::queries::{get_integration, save_api_token, save_integration, Integration};
+use crate::crypto::{decrypt_token, encrypt_token, hold_encryption_key};
+use crate::database::queries::{
+    delete_api_token, delete_setting, get_api_token, get_integration, save_api_token, save_integration,
+    Integration,
+};
 use crate::database::Database;
-use crate::oauth::google::GoogleAuth;
+use crate::error::{CommandContext, LumenError};
+use crate::integrations::google_gmail::{verify_google_connection, GOOGLE_ACCOUNT_SETTING};
+use crate::oauth::google::{revoke_token, GoogleAuth, GoogleTokens, DEFAULT_CALLBACK_TIMEOUT};
 use serde_json::json;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tauri::{AppHandle, State};
 
+//INFO: Set by `cancel_google_auth` to stop a pending sign-in without waiting for the timeout
+static AUTH_CANCELLED: AtomicBool = AtomicBool::new(false);
+
 #[tauri::command]
-pub async fn get_google_auth_status(database: State<'_, Database>) -> Result<bool, String> {
-    let connection = database.connection.lock();
-    crate::database::queries::has_api_token(&connection, "google").map_err(|e| e.to_string())
+pub async fn get_google_auth_status(database: State<'_, Database>) -> Result<bool, LumenError> {
+    let connection = database.connection.lock()?;
+    crate::database::queries::has_api_token(&connection, "google")
+        .command_context("Failed to check Google auth status")
 }
 
 #[tauri::command]
@@ -17,8 +27,8 @@ pub fn save_google_config(
     database: State<'_, Database>,
     client_id: String,
     client_secret: String,
-) -> Result<(), String> {
-    let connection = database.connection.lock();
+) -> Result<(), LumenError> {
+    let connection = database.connection.lock()?;
 
     let config = json!({
         "client_id": client_id,
@@ -31,23 +41,27 @@ pub fn save_google_config(
         enabled: false,
         config: Some(config),
         last_sync: None,
+        last_error: None,
+        last_error_at: None,
         status: "configured".to_string(),
     };
 
-    save_integration(&connection, &integration).map_err(|e| e.to_string())
+    save_integration(&connection, &integration).command_context("Failed to save Google config")
 }
 
 #[tauri::command]
 pub async fn start_google_auth(
     handle: AppHandle,
     database: State<'_, Database>,
-) -> Result<String, String> {
+) -> Result<String, LumenError> {
     // 1. Get Google Client ID and Secret from integrations
     let (client_id, client_secret) = {
-        let connection = database.connection.lock();
+        let connection = database.connection.lock()?;
+        crate::offline::ensure_online(&connection, "Google sign-in")
+            .command_context("Failed to start Google sign-in")?;
         let integration = get_integration(&connection, "google")
-            .map_err(|e| e.to_string())?
-            .ok_or("Google integration not configured. Please enter Client ID and Secret first.")?;
+            .command_context("Failed to load Google integration")?
+            .ok_or_else(|| LumenError::IntegrationDisabled("Google".to_string()))?;
 
         let config: serde_json::Value =
             serde_json::from_str(&integration.config.clone().unwrap_or_default())
@@ -64,8 +78,15 @@ pub async fn start_google_auth(
         (id, secret)
     };
 
-    let auth = GoogleAuth::new(client_id.clone(), client_secret.clone());
-    let (url, state) = auth.start_auth_flow().await.map_err(|e| e.to_string())?;
+    // Bind the callback server first so the redirect URL matches the port we actually got
+    let (server, port) =
+        GoogleAuth::bind_callback_server().command_context("Failed to start the sign-in callback server")?;
+
+    let auth = GoogleAuth::new(client_id.clone(), client_secret.clone()).with_redirect_port(port);
+    let (url, state) = auth
+        .start_auth_flow()
+        .await
+        .command_context("Failed to start Google sign-in")?;
 
     // Open browser using tauri-plugin-opener
     let opener_handle = handle.clone();
@@ -76,32 +97,97 @@ pub async fn start_google_auth(
             .open_url(url_clone, None::<String>);
     });
 
-    // Start local server to catch code (blocks this command until code is received or fails)
-    let code = tauri::async_runtime::spawn_blocking(move || auth.listen_for_code(state))
+    // Start local server to catch code (blocks until the code arrives, the user cancels, or it times out)
+    AUTH_CANCELLED.store(false, Ordering::SeqCst);
+    let code = tauri::async_runtime::spawn_blocking(move || {
+        auth.listen_for_code(server, state, DEFAULT_CALLBACK_TIMEOUT, &AUTH_CANCELLED)
+    })
         .await
-        .map_err(|e| e.to_string())?
-        .map_err(|e| e.to_string())?;
+        .command_context("Sign-in task failed")?
+        .command_context("Google sign-in failed")?;
 
     // Exchange code for tokens
-    let auth_exchange = GoogleAuth::new(client_id, client_secret);
+    let auth_exchange = GoogleAuth::new(client_id, client_secret).with_redirect_port(port);
     let tokens = auth_exchange
         .exchange_code(code)
         .await
-        .map_err(|e| e.to_string())?;
+        .command_context("Failed to exchange the authorization code")?;
 
     // Save tokens (encrypted)
     {
-        let connection = database.connection.lock();
-        let tokens_json = serde_json::to_string(&tokens).map_err(|e| e.to_string())?;
-        let encrypted = encrypt_token(&tokens_json).map_err(|e| e.to_string())?;
-        save_api_token(&connection, "google", &encrypted, "oauth2").map_err(|e| e.to_string())?;
+        let connection = database.connection.lock()?;
+        let tokens_json = serde_json::to_string(&tokens).command_context("Failed to serialize tokens")?;
+        {
+            let _key = hold_encryption_key();
+            let encrypted = encrypt_token(&tokens_json).command_context("Failed to encrypt tokens")?;
+            save_api_token(&connection, "google", &encrypted, "oauth2")
+                .command_context("Failed to save tokens")?;
+        }
 
         // Update integration status
         let mut integration = get_integration(&connection, "google").unwrap().unwrap();
         integration.enabled = true;
         integration.status = "connected".to_string();
-        save_integration(&connection, &integration).map_err(|e| e.to_string())?;
+        integration.last_error = None;
+        integration.last_error_at = None;
+        save_integration(&connection, &integration)
+            .command_context("Failed to update Google integration")?;
+    }
+
+    //INFO: Learn which account this is so Gmail links open in it
+    if let Err(e) = verify_google_connection(&database).await {
+        tracing::warn!("⚠️ Google: Couldn't look up the signed-in account: {:#}", e);
     }
 
     Ok("Connected successfully".to_string())
 }
+
+#[tauri::command]
+pub fn cancel_google_auth() {
+    AUTH_CANCELLED.store(true, Ordering::SeqCst);
+}
+
+#[tauri::command]
+pub async fn disconnect_google(database: State<'_, Database>) -> Result<String, LumenError> {
+    // 1. Read the stored tokens (a missing or unreadable token just means nothing to revoke)
+    let tokens = {
+        let connection = database.connection.lock()?;
+        get_api_token(&connection, "google")
+            .command_context("Failed to read Google tokens")?
+            .and_then(|encrypted| decrypt_token(&encrypted).ok())
+            .and_then(|json| serde_json::from_str::<GoogleTokens>(&json).ok())
+    };
+
+    // 2. Revoke on Google's side, preferring the refresh token since it kills the whole grant
+    let warning = match tokens {
+        Some(tokens) => {
+            let token = tokens.refresh_token.unwrap_or(tokens.access_token);
+            revoke_token(&token).await.err().map(|e| e.to_string())
+        }
+        None => None,
+    };
+
+    // 3. Clear local state even if revocation failed
+    {
+        let connection = database.connection.lock()?;
+        delete_api_token(&connection, "google").command_context("Failed to delete Google tokens")?;
+        let _ = delete_setting(&connection, GOOGLE_ACCOUNT_SETTING);
+
+        if let Some(mut integration) =
+            get_integration(&connection, "google").command_context("Failed to load Google integration")?
+        {
+            integration.enabled = false;
+            integration.status = "disconnected".to_string();
+            save_integration(&connection, &integration)
+                .command_context("Failed to update Google integration")?;
+        }
+    }
+
+    match warning {
+        Some(warning) => Ok(format!(
+            "Disconnected locally, but Google could not revoke the token: {}. You can remove Lumen's access at https://myaccount.google.com/permissions",
+            warning
+        )),
+        None => Ok("Disconnected successfully".to_string()),
+    }
+}
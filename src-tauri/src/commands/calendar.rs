@@ -1,25 +1,87 @@
-use crate::database::Database;
-use crate::integrations::google_calendar::{self, GoogleCalendarEvent};
+use crate::database::{queries, Database};
+use crate::error::{CommandContext, LumenError};
+use crate::integrations::google_calendar::{
+    self, normalize_cache_time, GoogleCalendarEvent, MAX_EVENTS_PER_FETCH,
+};
+use serde::Serialize;
 
 use tauri::Manager;
 
+//INFO: Events for a range, and whether they came from Google or the offline cache
+#[derive(Debug, Serialize)]
+pub struct CalendarRange {
+    pub events: Vec<GoogleCalendarEvent>,
+    //INFO: "live" or "cache"
+    pub source: String,
+    //INFO: When the cached events were fetched (cache only)
+    pub cached_at: Option<String>,
+    //INFO: Why Google couldn't be reached (cache only)
+    pub error: Option<String>,
+}
+
+//INFO: End of the span a fetch fully covered, so only that part of the cache is replaced
+//NOTE: A full page means Google had more events than one fetch returns. Events come sorted by start,
+//      so everything before the last one's start was fetched; cached events from there on are kept
+//      (None means nothing can safely be cleared, only added).
+fn fetched_until(cached: &[queries::CalendarEvent], range_end: &str) -> Option<String> {
+    use chrono::{SecondsFormat, Utc};
+
+    if cached.len() < MAX_EVENTS_PER_FETCH {
+        return Some(range_end.to_string());
+    }
+    let last_start = chrono::DateTime::parse_from_rfc3339(&cached.last()?.start_time).ok()?;
+    let just_before = last_start - chrono::Duration::seconds(1);
+    Some(just_before.with_timezone(&Utc).to_rfc3339_opts(SecondsFormat::Secs, true))
+}
+
+//INFO: Fetches events for a range from Google, caching them for offline use
+//NOTE: If Google can't be reached the cached events for the range are returned instead, tagged `source: "cache"`
 #[tauri::command]
 pub async fn get_calendar_events_for_range(
     app: tauri::AppHandle,
     start_iso: String,
     end_iso: String,
-) -> Result<Vec<GoogleCalendarEvent>, String> {
+) -> Result<CalendarRange, LumenError> {
     let database = app.state::<Database>();
+    let cache_start = normalize_cache_time(&start_iso);
+    let cache_end = normalize_cache_time(&end_iso);
 
-    // Attempt to fetch from Google
-    // If it fails (e.g. not connected), we return an empty list or error
-    match google_calendar::fetch_google_calendar_events(&database, &start_iso, &end_iso).await {
-        Ok(events) => Ok(events),
+    match google_calendar::fetch_google_calendar_events(&database, &start_iso, &end_iso, None).await {
+        Ok(events) => {
+            let cached: Vec<_> = events.iter().map(GoogleCalendarEvent::to_cached).collect();
+            let connection = database.connection.lock()?;
+            let result = match fetched_until(&cached, &cache_end) {
+                Some(fetched_end) => {
+                    queries::replace_calendar_events_in_range(&connection, &cache_start, &fetched_end, &cached)
+                }
+                None => queries::save_calendar_events(&connection, &cached),
+            };
+            if let Err(e) = result {
+                tracing::warn!("⚠️ Calendar: Failed to cache events: {:#}", e);
+            }
+            Ok(CalendarRange {
+                events,
+                source: "live".to_string(),
+                cached_at: None,
+                error: None,
+            })
+        }
         Err(e) => {
-            // Fallback: check if we have them cached in DB for this range?
-            // For now, if Google fails/is-unconfigured, we just return empty list to keep frontend happy
-            println!("Calendar fetch error: {}", e);
-            Ok(vec![])
+            tracing::info!("📴 Calendar: Fetch failed, serving cached events: {}", e);
+            let connection = database.connection.lock()?;
+            let events = queries::get_calendar_events(&connection, &cache_start, &cache_end)
+                .command_context("Failed to read cached calendar events")?
+                .into_iter()
+                .map(GoogleCalendarEvent::from_cached)
+                .collect();
+            let cached_at = queries::get_calendar_cache_time(&connection, &cache_start, &cache_end)
+                .command_context("Failed to read cached calendar events")?;
+            Ok(CalendarRange {
+                events,
+                source: "cache".to_string(),
+                cached_at,
+                error: Some(e.to_string()),
+            })
         }
     }
 }
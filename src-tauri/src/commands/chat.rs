@@ -1,16 +1,23 @@
 //INFO: Chat commands for Lumen
 //NOTE: Handles AI chat functionality with Gemini
 
-use crate::crypto::decrypt_token;
 use crate::database::queries::{
-    clear_chat_messages, get_api_token, get_calendar_events, get_chat_messages, get_integration,
-    get_user_profile, save_chat_message, ChatMessage,
+    clear_chat_messages, create_session, delete_chat_message as delete_chat_message_query,
+    delete_last_messages, delete_session, get_calendar_events, get_chat_messages,
+    get_integration, get_session, get_session_messages_after, get_user_profile, list_sessions,
+    rename_session, save_chat_message, save_session_summary, touch_session, ChatMessage,
+    ChatSession,
 };
 use crate::database::Database;
-use crate::gemini::{client::get_default_system_instruction, GeminiClient};
+use crate::error::{CommandContext, LumenError};
+use crate::gemini::usage::{record_usage, FEATURE_CHAT, FEATURE_MEMORY, FEATURE_SUMMARY};
+use crate::integrations::obsidian::{find_recent_daily_note, ObsidianConfig};
+use crate::gemini::GeminiClient;
 use chrono::Local;
 use serde::{Deserialize, Serialize};
-use std::sync::OnceLock;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
 use tauri::State;
 
 static CHAT_RESPONSE_SCHEMA: OnceLock<serde_json::Value> = OnceLock::new();
@@ -65,58 +72,152 @@ pub struct SendMessageResponse {
     pub assistant_message: ChatMessageResponse,
     pub suggested_view: Option<String>,
     pub suggested_date: Option<String>, // ISO date string for calendar view
+    //INFO: True when older turns were sent as the session summary rather than verbatim
+    pub history_summarized: bool,
+    //INFO: True when the user stopped the request; the assistant message holds whatever text was ready
+    pub cancelled: bool,
+}
+
+//INFO: Stop flags for in-flight `send_chat_message` calls, keyed by session ("" for sessionless chat)
+//NOTE: Managed as app state; the tool loop checks its flag between Gemini turns
+#[derive(Default)]
+pub struct ChatCancellation {
+    flags: parking_lot::Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+impl ChatCancellation {
+    fn key(session_id: Option<&str>) -> String {
+        session_id.unwrap_or_default().to_string()
+    }
+
+    //INFO: Registers a new request for the session; the flag is removed again when the guard drops
+    fn register(&self, session_id: Option<&str>) -> CancellationGuard<'_> {
+        let key = Self::key(session_id);
+        let flag = Arc::new(AtomicBool::new(false));
+        self.flags.lock().insert(key.clone(), flag.clone());
+        CancellationGuard { registry: self, key, flag }
+    }
+
+    //INFO: Flags the session's in-flight request, if any; false when nothing was running
+    fn cancel(&self, session_id: Option<&str>) -> bool {
+        match self.flags.lock().get(&Self::key(session_id)) {
+            Some(flag) => {
+                flag.store(true, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+struct CancellationGuard<'a> {
+    registry: &'a ChatCancellation,
+    key: String,
+    flag: Arc<AtomicBool>,
+}
+
+impl CancellationGuard<'_> {
+    fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for CancellationGuard<'_> {
+    fn drop(&mut self) {
+        //NOTE: Only remove our own flag - a newer request for the same session may have replaced it
+        let mut flags = self.registry.flags.lock();
+        if flags.get(&self.key).is_some_and(|f| Arc::ptr_eq(f, &self.flag)) {
+            flags.remove(&self.key);
+        }
+    }
+}
+
+//INFO: Messages sent verbatim when there's no session (plain sliding window)
+const SESSIONLESS_HISTORY_MESSAGES: i32 = 20;
+//INFO: Safety cap on verbatim session messages, in case summarization keeps failing
+const MAX_SESSION_HISTORY_MESSAGES: usize = 40;
+//INFO: Once a session has more unsummarized messages than this, the oldest get folded into its summary
+const SUMMARY_TRIGGER_MESSAGES: usize = 30;
+//INFO: How many of the most recent messages stay verbatim after a compaction
+const SUMMARY_KEEP_RECENT_MESSAGES: usize = 10;
+//INFO: Settings key overriding how many messages are sent verbatim (both windows above), clamped to 2-50
+//NOTE: Unset keeps the defaults above; the session summary still covers anything older
+pub const CHAT_HISTORY_WINDOW_SETTING: &str = "chat_history_window";
+const MIN_HISTORY_WINDOW: usize = 2;
+const MAX_HISTORY_WINDOW: usize = 50;
+
+//INFO: Parses a `chat_history_window` value, clamping it into range
+fn parse_history_window(value: &str) -> Option<usize> {
+    value
+        .trim()
+        .parse::<i64>()
+        .ok()
+        .map(|n| n.clamp(MIN_HISTORY_WINDOW as i64, MAX_HISTORY_WINDOW as i64) as usize)
+}
+
+fn history_window(connection: &rusqlite::Connection) -> Option<usize> {
+    crate::database::queries::get_setting(connection, CHAT_HISTORY_WINDOW_SETTING)
+        .ok()
+        .flatten()
+        .as_deref()
+        .and_then(parse_history_window)
+}
+
+//INFO: Settings key for how many model turns one message may take, clamped to 1-10 (see run_tool_loop)
+pub const CHAT_MAX_TOOL_ROUNDS_SETTING: &str = "chat_max_tool_rounds";
+
+//INFO: Reads the tool round cap, falling back to the default when unset or invalid
+pub fn max_tool_rounds(connection: &rusqlite::Connection) -> usize {
+    use crate::llm::tool_loop::{DEFAULT_MAX_TOOL_ROUNDS, MAX_TOOL_ROUNDS_LIMIT};
+    crate::database::queries::get_setting(connection, CHAT_MAX_TOOL_ROUNDS_SETTING)
+        .ok()
+        .flatten()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .unwrap_or(DEFAULT_MAX_TOOL_ROUNDS)
+        .clamp(1, MAX_TOOL_ROUNDS_LIMIT)
 }
 
 //INFO: Sends a message to the AI and returns the response
 #[tauri::command]
+#[tracing::instrument(name = "chat", skip_all, fields(session_id = ?request.session_id))]
 pub async fn send_chat_message(
     app_handle: tauri::AppHandle,
     database: State<'_, Database>,
+    cancellation: State<'_, ChatCancellation>,
     request: SendMessageRequest,
-) -> Result<SendMessageResponse, String> {
+) -> Result<SendMessageResponse, LumenError> {
     use tauri::Emitter;
 
-    //INFO: Get the Gemini API key from the database
-    let api_key = {
-        let connection = database.connection.lock();
-        let encrypted_key = get_api_token(&connection, "gemini")
-            .map_err(|e| format!("Failed to get API key: {}", e))?
-            .ok_or_else(|| {
-                "Gemini API key not configured. Please add your API key in Settings.".to_string()
-            })?;
+    let cancel_guard = cancellation.register(request.session_id.as_deref());
 
-        decrypt_token(&encrypted_key).map_err(|e| format!("Failed to decrypt API key: {}", e))?
+    //INFO: The chat provider chosen in Settings, plus the Gemini key for embedding-based background work
+    let (provider, gemini_key, max_rounds) = {
+        let connection = database.connection.lock()?;
+        (
+            crate::llm::provider_from_settings(&connection)?,
+            crate::llm::background_gemini_key(&connection)?,
+            max_tool_rounds(&connection),
+        )
     };
 
-    //INFO: 1. Get Conversation History (Sliding Window: last 50 messages)
-    let history = {
-        let connection = database.connection.lock();
-        get_chat_messages(&connection, request.session_id.as_deref(), 20)
-            .map_err(|e| format!("Failed to get history: {}", e))?
+    //INFO: 1. Get Conversation History (session summary + the messages it doesn't cover yet)
+    let (history, session_summary) = {
+        let connection = database.connection.lock()?;
+        load_chat_history(&connection, request.session_id.as_deref())?
     };
 
     //INFO: 2. Build context from integrations
-    let context = build_chat_context(&database)?;
+    let context = build_chat_context(&database, &request.message)?;
 
     //INFO: 3. Convert history to Gemini format (History is already chronological)
-    let mut gemini_messages = Vec::new();
-    for msg in history {
-        gemini_messages.push(crate::gemini::client::GeminiContent {
-            role: Some(if msg.role == "user" {
-                "user".to_string()
-            } else {
-                "model".to_string()
-            }),
-            parts: vec![crate::gemini::client::GeminiPart::text(msg.content)],
-        });
-    }
+    let mut gemini_messages = history_to_contents(history);
 
     //INFO: 4. Add current message
     let mut parts = vec![crate::gemini::client::GeminiPart::text(request.message.clone())];
 
     if let Some(ref b64) = request.base64_image {
         parts.push(crate::gemini::client::GeminiPart::inline_data(
-            "image/png".to_string(),
+            crate::commands::vision::sniff_image_mime(b64).to_string(),
             b64.clone(),
         ));
     }
@@ -129,61 +230,15 @@ pub async fn send_chat_message(
     //INFO: 5. Load Tools
     let tools = crate::gemini::tools::get_tool_declarations();
 
-    let obsidian_config = {
-        let connection = database.connection.lock();
-        get_integration(&connection, "obsidian")
-            .ok()
-            .flatten()
-            .and_then(|i| i.config)
-            .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
-    };
-
-    //INFO: 6. Send to Gemini (with Tool Loop)
-    let client = GeminiClient::new(api_key.clone());
-
-    //INFO: Enhance system instruction with specific user info
-    let mut system_instruction = get_default_system_instruction();
-
-    if let Some(ctx) = context {
-        system_instruction.push_str("\n\n--- CURRENT DIGITAL STATE (BACKGROUND CONTEXT) ---");
-        system_instruction.push_str(
-            "\nThis is the user's active screen/system state. Use it ONLY if relevant to their request.",
-        );
-        system_instruction.push_str("\nIf the user says 'hi' or chats, respond socially. DO NOT mention system details unless asked.");
-        system_instruction.push_str(&format!("\n\n{}", ctx));
-        system_instruction.push_str("\n-------------------------------------------");
-    }
-
-    //INFO: 6.5 Memory retrieval is now handled explicitly via the retrieve_past_memories tool.
-
-    if let Some(config) = &obsidian_config {
-        system_instruction.push_str("\n\n--- OBSIDIAN CONFIGURATION ---");
-        if let Some(path) = config.get("vault_path").and_then(|v| v.as_str()) {
-            system_instruction.push_str(&format!("\nVault path: {}", path));
-        }
-        if let Some(folder) = config.get("daily_notes_path").and_then(|v| v.as_str()) {
-            if !folder.is_empty() {
-                system_instruction.push_str(&format!(
-                    "\nDaily Notes folder (relative to vault): {}",
-                    folder
-                ));
-            }
-        }
-        if let Some(format) = config.get("daily_notes_format").and_then(|v| v.as_str()) {
-            system_instruction.push_str(&format!(
-                "\nDaily Notes date format (Moment.js syntax): {}",
-                format
-            ));
-        }
-        system_instruction.push_str("\n------------------------------");
-    }
-
-    system_instruction.push_str("\n\n🎯 CONVERSATIONAL RULES:\n1. If the user says 'hi', 'hello', 'hey', 'what's up', or is just being social, respond IMMEDIATELY with warmth in the 'response' field. Do NOT call any tools. Do NOT retrieve memories. Just be friendly.\n2. Only use tools when the user asks a SPECIFIC question that requires data (calendar, weather, files, etc.).\n3. The 'response' field is MANDATORY in every reply. Never skip it.");
-
-    let mut current_messages = gemini_messages;
-    let mut final_response_text = String::new();
+    let obsidian_config = get_obsidian_config(&database);
 
-    let mut tools_were_called = false;
+    //INFO: 6. Send to the model (with Tool Loop)
+    let system_instruction = build_system_instruction(
+        &database,
+        context.as_deref(),
+        session_summary.as_deref(),
+        obsidian_config.as_ref(),
+    )?;
 
     //INFO: Tool execution loop — uses non-streaming for tool rounds
     //NOTE: Only the FINAL response (no function calls) gets streamed to the UI
@@ -192,182 +247,26 @@ pub async fn send_chat_message(
         response_schema: Some(get_chat_response_schema().clone()),
     };
 
-    //INFO: Per-tool call counter to prevent runaway tool loops
-    let mut tool_call_counts: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
-    const MAX_CALLS_PER_TOOL: usize = 5;
-    const MAX_TOOL_ROUNDS: usize = 7;
-
-    for _i in 0..MAX_TOOL_ROUNDS {
-        // Use non-streaming send_chat for tool execution rounds
-        let chat_response = client
-            .send_chat(
-                current_messages.clone(),
-                Some(&system_instruction),
-                Some(tools.clone()),
-                Some(config.clone()),
-            )
-            .await
-            .map_err(|e| format!("Failed to get AI response: {}", e))?;
-
-        let response_parts = chat_response.parts;
-
-        // Record the model's response in history
-        current_messages.push(crate::gemini::client::GeminiContent {
-            role: Some("model".to_string()),
-            parts: response_parts.clone(),
-        });
-
-        let mut has_function_calls = false;
-        let mut function_responses = Vec::new();
-
-        for part in &response_parts {
-            // Extract text from non-tool-call responses
-            if let Some(text) = &part.text {
-                // Reset final text each round — only the last round's text matters
-                final_response_text.clear();
-                final_response_text.push_str(text);
-
-                // Emit to frontend for real-time display
-                let mut emit_text = text.clone();
-                if let Ok(json_val) = serde_json::from_str::<serde_json::Value>(text) {
-                    if let Some(resp) = json_val.get("response").and_then(|v| v.as_str()) {
-                        emit_text = resp.to_string();
-                    }
-                }
-                let _ = app_handle.emit("assistant-reply-turn", emit_text);
-            }
-
-            if let Some(call) = &part.function_call {
-                // Check if this tool has been called too many times
-                let count = tool_call_counts.entry(call.name.clone()).or_insert(0);
-                *count += 1;
-
-                if *count > MAX_CALLS_PER_TOOL {
-                    println!("DEBUG: ⚠️ Tool '{}' hit call limit ({}), skipping.", call.name, MAX_CALLS_PER_TOOL);
-                    function_responses.push(crate::gemini::client::GeminiPart::function_response(
-                        call.name.clone(),
-                        serde_json::json!({ "error": format!("Tool '{}' has already been called {} times this turn. Please provide your response now using the information you already have.", call.name, MAX_CALLS_PER_TOOL) }),
-                    ));
-                } else {
-
-                has_function_calls = true;
-                tools_were_called = true;
-                if call.name == "get_weather"
-                    || call.name == "get_google_calendar_events"
-                    || call.name == "get_unread_emails"
-                    || call.name == "send_email"
-                    || call.name == "create_calendar_event"
-                    || call.name == "list_google_tasks"
-                    || call.name == "create_google_task"
-                    || call.name == "take_screenshot"
-                    || call.name == "retrieve_past_memories"
-                    || call.name == "delete_calendar_event"
-                {
-                    let res =
-                        crate::gemini::tools::execute_tool_async(&call.name, &call.args, &database)
-                            .await;
-
-                    function_responses.push(crate::gemini::client::GeminiPart::function_response(
-                        call.name.clone(),
-                        res,
-                    ));
-                } else {
-                    let res = {
-                        let connection = database.connection.lock();
-                        crate::gemini::tools::execute_tool_sync(
-                            &call.name,
-                            &call.args,
-                            obsidian_config.as_ref(),
-                            &connection,
-                        )
-                    };
-                    function_responses.push(crate::gemini::client::GeminiPart::function_response(
-                        call.name.clone(),
-                        res,
-                    ));
-                }
-                } // Close the newly added else block
-            }
-        }
-
-        if has_function_calls {
-            // Clear the streaming bubble so it doesn't show stale tool-call text
-            let _ = app_handle.emit("assistant-reply-clear", ());
-
-            let mut screenshot_data = None;
-            for resp in &mut function_responses {
-                if let Some(f_resp) = &mut resp.function_response {
-                    if f_resp.name == "take_screenshot" {
-                        if let Some(obj) = f_resp.response.as_object_mut() {
-                            if let Some(b64) = obj
-                                .get("image_data")
-                                .and_then(|v| v.as_str())
-                                .map(|s| s.to_string())
-                            {
-                                screenshot_data = Some(b64);
-                                obj.remove("image_data");
-                                obj.insert("info".into(), serde_json::json!("Screenshot captured successfully. You can now see the image in this Turn."));
-                            }
-                        }
-                    }
-                }
-            }
-
-            current_messages.push(crate::gemini::client::GeminiContent {
-                role: Some("user".to_string()),
-                parts: function_responses,
-            });
-            if let Some(b64) = screenshot_data {
-                current_messages.push(crate::gemini::client::GeminiContent {
-                    role: Some("user".to_string()),
-                    parts: vec![
-                        crate::gemini::client::GeminiPart::text("[VISUAL CONTEXT ATTACHED]".to_string()),
-                        crate::gemini::client::GeminiPart::inline_data(
-                            "image/png".to_string(),
-                            b64,
-                        ),
-                    ],
-                });
-            }
-            continue;
-        } else {
-            break;
-        }
-    }
-
-    //INFO: Safety net — if the model used tools but never produced text,
-    //      force one last call WITHOUT tools so it MUST reply with text.
-    if final_response_text.is_empty() {
-        println!("DEBUG: ⚠️ No text after tool loop. Forcing a final text-only call...");
-
-        let forced_response = client
-            .send_chat(
-                current_messages.clone(),
-                Some(&system_instruction),
-                None, // No tools — forces a pure text response
-                Some(config.clone()),
-            )
-            .await
-            .map_err(|e| format!("Failed to get forced response: {}", e))?;
-
-        for part in &forced_response.parts {
-            if let Some(text) = &part.text {
-                final_response_text = text.clone();
-
-                // Emit to frontend
-                let mut emit_text = text.clone();
-                if let Ok(json_val) = serde_json::from_str::<serde_json::Value>(text) {
-                    if let Some(resp) = json_val.get("response").and_then(|v| v.as_str()) {
-                        emit_text = resp.to_string();
-                    }
-                }
-                let _ = app_handle.emit("assistant-reply-turn", emit_text);
-            }
-        }
-
-        if final_response_text.is_empty() {
-            return Err("Lumen processed the request but couldn't generate a response. Please try again.".to_string());
-        }
+    let chat_tools = ChatTools {
+        app_handle: &app_handle,
+        database: database.inner(),
+        obsidian_config: obsidian_config.as_ref(),
+    };
+    let outcome = crate::llm::tool_loop::run_tool_loop(
+        provider.as_ref(),
+        &chat_tools,
+        gemini_messages,
+        &system_instruction,
+        tools,
+        config,
+        max_rounds,
+        &|| cancel_guard.is_cancelled(),
+    )
+    .await?;
+
+    let cancelled = outcome.cancelled;
+    if outcome.turns.is_empty() && !cancelled {
+        return Err("Lumen processed the request but couldn't generate a response. Please try again.".into());
     }
 
     //INFO: Save both messages to the database
@@ -383,14 +282,13 @@ pub async fn send_chat_message(
     };
 
     //INFO: Parse Structured JSON output BEFORE saving to DB
-    let mut actual_final_text = final_response_text.clone();
+    //NOTE: The reply is the last turn that parses as JSON; if none does, every turn's text is kept, one paragraph each
+    let mut actual_final_text = outcome.turns.join("\n\n");
     let mut suggested_view = None;
     let mut suggested_date = None;
 
-    // We might have multiple chunks in final_response_text separated by \n\n
-    // Find the last valid JSON chunk
-    for text_chunk in final_response_text.rsplit("\n\n") {
-        if let Ok(json_val) = serde_json::from_str::<serde_json::Value>(text_chunk) {
+    for turn in outcome.turns.iter().rev() {
+        if let Ok(json_val) = serde_json::from_str::<serde_json::Value>(turn) {
             if let Some(resp) = json_val.get("response").and_then(|v| v.as_str()) {
                 actual_final_text = resp.to_string();
             }
@@ -404,6 +302,10 @@ pub async fn send_chat_message(
         }
     }
 
+    if cancelled && actual_final_text.trim().is_empty() {
+        actual_final_text = "_Stopped before Lumen finished replying._".to_string();
+    }
+
     let assistant_message = ChatMessage {
         id: None,
         role: "assistant".to_string(),
@@ -415,22 +317,76 @@ pub async fn send_chat_message(
 
     //INFO: Save messages to database
     let (user_id, assistant_id) = {
-        let connection = database.connection.lock();
+        let connection = database.connection.lock()?;
         let user_id = save_chat_message(&connection, &user_message)
-            .map_err(|e| format!("Failed to save user message: {}", e))?;
+            .command_context("Failed to save user message")?;
         let assistant_id = save_chat_message(&connection, &assistant_message)
-            .map_err(|e| format!("Failed to save assistant message: {}", e))?;
+            .command_context("Failed to save assistant message")?;
         (user_id, assistant_id)
     };
 
+    //INFO: Register the session on its first message and title it in the background
+    if let Some(session_id) = user_message.session_id.clone() {
+        let is_new_session = {
+            let connection = database.connection.lock()?;
+            let existing = get_session(&connection, &session_id)
+                .command_context("Failed to get session")?;
+            match existing {
+                Some(_) => {
+                    let _ = touch_session(&connection, &session_id);
+                    false
+                }
+                None => {
+                    let placeholder: String = request.message.chars().take(40).collect();
+                    create_session(&connection, &session_id, &placeholder)
+                        .command_context("Failed to create session")?;
+                    true
+                }
+            }
+        };
+
+        if is_new_session {
+            let db_clone = database.inner().clone();
+            let provider_title = provider.clone();
+            let first_message = request.message.clone();
+            tokio::spawn(async move {
+                let Some(title) = generate_session_title(provider_title.as_ref(), &first_message).await else {
+                    return;
+                };
+                if let Ok(connection) = db_clone.connection.lock() {
+                    let _ = rename_session(&connection, &session_id, &title);
+                }
+            });
+        }
+    }
+
+    //INFO: Fold older messages into the session summary in the background once the session grows
+    if let Some(session_id) = user_message.session_id.clone() {
+        let db_clone = database.inner().clone();
+        let provider_summary = provider.clone();
+        let app_summary = app_handle.clone();
+        tokio::spawn(async move {
+            match compact_session_history(&db_clone, provider_summary.as_ref(), &session_id).await {
+                Ok(Some(summary)) => {
+                    let _ = app_summary.emit(
+                        "session-summarized",
+                        serde_json::json!({ "session_id": session_id, "summary": summary }),
+                    );
+                }
+                Ok(None) => {}
+                Err(e) => tracing::warn!("⚠️ Chat: Failed to summarize session {}: {}", session_id, e),
+            }
+        });
+    }
+
     //INFO: Latent Memory Extraction Trigger (mod-based)
     const MEMORY_EXTRACTION_THRESHOLD: i64 = 50;
-    {
-        let connection = database.connection.lock();
+    if let Ok(connection) = database.connection.lock() {
         if let Ok(total_count) = crate::database::queries::count_chat_messages(&connection) {
-            println!("DEBUG: 🧠 PULSE: Current chat message count: {}. (Threshold: {})", total_count, MEMORY_EXTRACTION_THRESHOLD);
-            if total_count > 0 && total_count % MEMORY_EXTRACTION_THRESHOLD == 0 {
-                println!("DEBUG: 🧠 TRIGGER: Memory extraction threshold hit! Initializing background task...");
+            tracing::debug!("🧠 PULSE: Current chat message count: {}. (Threshold: {})", total_count, MEMORY_EXTRACTION_THRESHOLD);
+            //NOTE: Extracted memories are embedded with Gemini, so this only runs when a Gemini key is saved
+            if total_count > 0 && total_count % MEMORY_EXTRACTION_THRESHOLD == 0 && gemini_key.is_some() {
+                tracing::debug!("🧠 TRIGGER: Memory extraction threshold hit! Initializing background task...");
                 
                 // Grab the last N messages for extraction
                 let mut recent_messages = crate::database::queries::get_chat_messages(
@@ -449,19 +405,18 @@ pub async fn send_chat_message(
 
                 // Clone what we need for the background task
                 let db_clone = database.inner().clone();
-                let api_key_clone = api_key.clone();
+                let api_key_clone = gemini_key.clone().unwrap_or_default();
                 // Fire and forget - async background extraction
                 tokio::spawn(async move {
-                    println!("DEBUG: 🧠 Starting background memory extraction...");
+                    tracing::debug!("🧠 Starting background memory extraction...");
 
-                    let user_name = {
-                        let conn = db_clone.connection.lock();
-                        crate::database::queries::get_user_profile(&conn)
-                            .ok()
-                            .flatten()
-                            .map(|p| p.display_name)
-                            .unwrap_or_else(|| "User".to_string())
-                    };
+                    let user_name = db_clone
+                        .connection
+                        .lock()
+                        .ok()
+                        .and_then(|conn| crate::database::queries::get_user_profile(&conn).ok().flatten())
+                        .map(|p| p.display_name)
+                        .unwrap_or_else(|| "User".to_string());
 
                     let prompt = crate::memory::extractor::build_chat_extraction_prompt(&messages_for_extraction, &user_name);
                     let client = GeminiClient::new(api_key_clone.clone());
@@ -483,8 +438,9 @@ pub async fn send_chat_message(
                     match extraction_result {
                         Ok(chat_response) => {
                             if let Some(usage) = &chat_response.usage {
-                                println!("DEBUG: 🧠 Extraction Token Usage -> Prompt: {}, Candidates: {}, Total: {}", usage.prompt_token_count, usage.candidates_token_count, usage.total_token_count);
+                                tracing::debug!("🧠 Extraction Token Usage -> Prompt: {}, Candidates: {}, Total: {}", usage.prompt_token_count, usage.candidates_token_count, usage.total_token_count);
                             }
+                            record_usage(&db_clone, FEATURE_MEMORY, chat_response.usage.as_ref());
                             let response_text = chat_response.parts.iter()
                                 .filter_map(|p| p.text.as_ref())
                                 .cloned()
@@ -493,37 +449,39 @@ pub async fn send_chat_message(
 
                             match crate::memory::extractor::parse_extracted_memories(&response_text) {
                                 Ok(mut memories) => {
-                                    println!("DEBUG: 🧠 Extracted {} memories from chat!", memories.len());
+                                    tracing::debug!("🧠 Extracted {} memories from chat!", memories.len());
                                     for memory in &mut memories {
                                         // Generate embedding for each memory
                                         match client.generate_embedding(&memory.content).await {
                                             Ok(embedding) => {
                                                 memory.embedding = Some(embedding);
-                                                println!("DEBUG: 🧠 [{}] (importance: {}) {}", 
+                                                tracing::debug!("🧠 [{}] (importance: {}) {}", 
                                                     memory.memory_type.as_str(),
                                                     memory.importance,
                                                     memory.content.chars().take(80).collect::<String>()
                                                 );
                                             }
                                             Err(e) => {
-                                                println!("DEBUG: 🧠 Failed to embed memory: {}", e);
+                                                tracing::debug!("🧠 Failed to embed memory: {}", e);
                                             }
                                         }
                                         
                                         // Store in DB
-                                        let conn = db_clone.connection.lock();
-                                        if let Err(e) = crate::memory::core::store_memory(&conn, memory) {
-                                            println!("DEBUG: 🧠 Failed to store memory: {}", e);
+                                        let stored = db_clone
+                                            .connection
+                                            .lock()
+                                            .and_then(|conn| crate::memory::core::store_memory(&conn, memory));
+                                        if let Err(e) = stored {
+                                            tracing::debug!("🧠 Failed to store memory: {}", e);
                                         }
                                     }
-                                    println!("DEBUG: 🧠 Memory extraction complete! ✅");
+                                    tracing::debug!("🧠 Memory extraction complete! ✅");
 
                                     // Check if we should trigger a Reflection loop
-                                    {
-                                        let conn = db_clone.connection.lock();
+                                    if let Ok(conn) = db_clone.connection.lock() {
                                         match crate::memory::core::should_trigger_reflection(&conn) {
                                             Ok(true) => {
-                                                println!("DEBUG: 🧠 Reflection threshold hit! Starting synthesis...");
+                                                tracing::debug!("🧠 Reflection threshold hit! Starting synthesis...");
                                                 // Get the last 30 observations for reflection
                                                 if let Ok(recent_obs) = crate::memory::core::get_recent_memories_by_type(
                                                     &conn,
@@ -531,14 +489,13 @@ pub async fn send_chat_message(
                                                     MEMORY_EXTRACTION_THRESHOLD as usize
                                                 ) {
                                                     let obs_texts: Vec<String> = recent_obs.iter().map(|o| o.content.clone()).collect();
-                                                    let user_name = {
-                                                        let conn = db_clone.connection.lock();
-                                                        crate::database::queries::get_user_profile(&conn)
-                                                            .ok()
-                                                            .flatten()
-                                                            .map(|p| p.display_name)
-                                                            .unwrap_or_else(|| "User".to_string())
-                                                    };
+                                                    let user_name = db_clone
+                                                        .connection
+                                                        .lock()
+                                                        .ok()
+                                                        .and_then(|conn| crate::database::queries::get_user_profile(&conn).ok().flatten())
+                                                        .map(|p| p.display_name)
+                                                        .unwrap_or_else(|| "User".to_string());
                                                     let prompt = crate::memory::reflection::build_reflection_prompt(&obs_texts, &user_name);
                                                     
                                                     // Drop lock for async synthesis
@@ -547,7 +504,7 @@ pub async fn send_chat_message(
                                                     let api_key_reflection = api_key_clone.clone();
                                                     tokio::spawn(async move {
                                                         let client = GeminiClient::new(api_key_reflection);
-                                                        println!("DEBUG: 🧠 Requesting reflection from Gemini...");
+                                                        tracing::debug!("🧠 Requesting reflection from Gemini...");
                                                         
                                                         let synthesis_result = client.send_chat(
                                                             vec![crate::gemini::client::GeminiContent {
@@ -565,7 +522,7 @@ pub async fn send_chat_message(
                                                         if let Ok(resp) = synthesis_result {
                                                             let text = resp.parts.iter().filter_map(|p| p.text.as_ref()).cloned().collect::<Vec<_>>().join("");
                                                             if let Ok(reflections) = serde_json::from_str::<Vec<crate::memory::reflection::ExtractedReflection>>(&text) {
-                                                                println!("DEBUG: 🧠 Synthesized {} high-level reflections!", reflections.len());
+                                                                tracing::debug!("🧠 Synthesized {} high-level reflections!", reflections.len());
                                                                 for r in reflections {
                                                                     let mut memory = crate::memory::extractor::create_memory(
                                                                         crate::memory::core::MemoryType::Reflection,
@@ -576,10 +533,11 @@ pub async fn send_chat_message(
                                                                     // Embed and store
                                                                     if let Ok(emb) = client.generate_embedding(&memory.content).await {
                                                                         memory.embedding = Some(emb);
-                                                                        let conn = db_clone.connection.lock();
-                                                                        let _ = crate::memory::core::store_memory(&conn, &memory);
+                                                                        if let Ok(conn) = db_clone.connection.lock() {
+                                                                            let _ = crate::memory::core::store_memory(&conn, &memory);
+                                                                        }
                                                                         let reflection_snippet = memory.content.chars().take(60).collect::<String>();
-                                                                        println!("DEBUG: 🧠 Stored reflection: {}", reflection_snippet);
+                                                                        tracing::debug!("🧠 Stored reflection: {}", reflection_snippet);
                                                                     }
                                                                 }
                                                             }
@@ -588,18 +546,18 @@ pub async fn send_chat_message(
                                                 }
                                             }
                                             Ok(false) => {}
-                                            Err(e) => println!("DEBUG: 🧠 Reflection check failed: {}", e),
+                                            Err(e) => tracing::debug!("🧠 Reflection check failed: {}", e),
                                         }
                                     }
                                 }
                                 Err(e) => {
-                                    println!("DEBUG: 🧠 Failed to parse extracted memories: {}", e);
-                                    println!("DEBUG: 🧠 Raw response: {}", response_text);
+                                    tracing::debug!("🧠 Failed to parse extracted memories: {}", e);
+                                    tracing::debug!("🧠 Raw response: {}", response_text);
                                 }
                             }
                         }
                         Err(e) => {
-                            println!("DEBUG: 🧠 Memory extraction LLM call failed: {}", e);
+                            tracing::debug!("🧠 Memory extraction LLM call failed: {}", e);
                         }
                     }
                 });
@@ -624,21 +582,334 @@ pub async fn send_chat_message(
         },
         suggested_view,
         suggested_date,
+        history_summarized: session_summary.is_some(),
+        cancelled,
     })
 }
 
+//INFO: Converts stored chat messages to model contents (assistant messages become "model" turns)
+fn history_to_contents(history: Vec<ChatMessage>) -> Vec<crate::gemini::client::GeminiContent> {
+    history
+        .into_iter()
+        .map(|msg| crate::gemini::client::GeminiContent {
+            role: Some(if msg.role == "user" {
+                "user".to_string()
+            } else {
+                "model".to_string()
+            }),
+            parts: vec![crate::gemini::client::GeminiPart::text(msg.content)],
+        })
+        .collect()
+}
+
+//INFO: Assembles the chat system instruction: persona, background context, session summary, Obsidian setup and rules
+fn build_system_instruction(
+    database: &Database,
+    context: Option<&str>,
+    session_summary: Option<&str>,
+    obsidian_config: Option<&serde_json::Value>,
+) -> Result<String, LumenError> {
+    //INFO: Enhance system instruction with specific user info
+    let mut system_instruction = {
+        let connection = database.connection.lock()?;
+        crate::gemini::persona::system_instruction_from_settings(&connection)
+    };
+
+    if let Some(ctx) = context {
+        system_instruction.push_str("\n\n--- CURRENT DIGITAL STATE (BACKGROUND CONTEXT) ---");
+        system_instruction.push_str(
+            "\nThis is the user's active screen/system state. Use it ONLY if relevant to their request.",
+        );
+        system_instruction.push_str("\nIf the user says 'hi' or chats, respond socially. DO NOT mention system details unless asked.");
+        system_instruction.push_str(&format!("\n\n{}", ctx));
+        system_instruction.push_str("\n-------------------------------------------");
+    }
+
+    if let Some(summary) = session_summary {
+        system_instruction.push_str("\n\n--- EARLIER IN THIS CONVERSATION (SUMMARY) ---");
+        system_instruction.push_str(
+            "\nOlder messages of this chat were condensed into the summary below. Treat it as things already discussed.",
+        );
+        system_instruction.push_str(&format!("\n\n{}", summary));
+        system_instruction.push_str("\n-------------------------------------------");
+    }
+
+    //INFO: Memory retrieval is handled explicitly via the retrieve_past_memories tool.
+
+    if let Some(config) = obsidian_config {
+        system_instruction.push_str("\n\n--- OBSIDIAN CONFIGURATION ---");
+        if let Some(path) = config.get("vault_path").and_then(|v| v.as_str()) {
+            system_instruction.push_str(&format!("\nVault path: {}", path));
+        }
+        if let Some(folder) = config.get("daily_notes_path").and_then(|v| v.as_str()) {
+            if !folder.is_empty() {
+                system_instruction.push_str(&format!(
+                    "\nDaily Notes folder (relative to vault): {}",
+                    folder
+                ));
+            }
+        }
+        if let Some(format) = config.get("daily_notes_format").and_then(|v| v.as_str()) {
+            system_instruction.push_str(&format!(
+                "\nDaily Notes date format (Moment.js syntax): {}",
+                format
+            ));
+        }
+        system_instruction.push_str("\n------------------------------");
+    }
+
+    system_instruction.push_str("\n\n🎯 CONVERSATIONAL RULES:\n1. If the user says 'hi', 'hello', 'hey', 'what's up', or is just being social, respond IMMEDIATELY with warmth in the 'response' field. Do NOT call any tools. Do NOT retrieve memories. Just be friendly.\n2. Only use tools when the user asks a SPECIFIC question that requires data (calendar, weather, files, etc.).\n3. The 'response' field is MANDATORY in every reply. Never skip it.");
+    Ok(system_instruction)
+}
+
+//INFO: Everything `send_chat_message` would send to the model, minus the call itself
+#[derive(Debug, Serialize)]
+pub struct ChatContextPreview {
+    pub system_instruction: String,
+    //INFO: The integration context injected into the system instruction, on its own
+    pub context: Option<String>,
+    pub session_summary: Option<String>,
+    //INFO: The history window sent verbatim, oldest first (the new message would follow it)
+    pub messages: Vec<ChatMessageResponse>,
+    pub tool_count: usize,
+}
+
+//INFO: Shows the assembled prompt for a session without calling the model, for debugging odd answers
+//NOTE: `message` is the draft to build context for; some context (e.g. pinned notes, memories) depends on it
+#[tauri::command]
+pub fn preview_chat_context(
+    database: State<Database>,
+    session_id: Option<String>,
+    message: Option<String>,
+) -> Result<ChatContextPreview, LumenError> {
+    let (history, session_summary) = {
+        let connection = database.connection.lock()?;
+        load_chat_history(&connection, session_id.as_deref())?
+    };
+
+    let context = build_chat_context(&database, message.as_deref().unwrap_or_default())?;
+    let obsidian_config = get_obsidian_config(&database);
+    let system_instruction = build_system_instruction(
+        &database,
+        context.as_deref(),
+        session_summary.as_deref(),
+        obsidian_config.as_ref(),
+    )?;
+
+    let tool_count = crate::gemini::tools::get_tool_declarations()
+        .iter()
+        .map(|t| t.function_declarations.len())
+        .sum();
+
+    Ok(ChatContextPreview {
+        system_instruction,
+        context,
+        session_summary,
+        messages: history
+            .into_iter()
+            .map(|m| ChatMessageResponse {
+                id: m.id,
+                role: m.role,
+                content: m.content,
+                image_data: m.image_data,
+                created_at: m.created_at,
+            })
+            .collect(),
+        tool_count,
+    })
+}
+
+//INFO: Runs the chat's tool calls for real: asks for confirmation, then executes and streams progress to the UI
+struct ChatTools<'a> {
+    app_handle: &'a tauri::AppHandle,
+    database: &'a Database,
+    obsidian_config: Option<&'a serde_json::Value>,
+}
+
+impl crate::llm::tool_loop::ToolExecutor for ChatTools<'_> {
+    fn execute<'a>(
+        &'a self,
+        name: &'a str,
+        args: &'a serde_json::Value,
+    ) -> futures::future::BoxFuture<'a, Result<serde_json::Value, String>> {
+        Box::pin(async move {
+            crate::gemini::confirmation::await_confirmation(
+                self.app_handle,
+                self.database,
+                name,
+                args,
+                self.obsidian_config,
+            )
+            .await?;
+
+            if crate::gemini::tools::is_async_tool(name) {
+                Ok(crate::gemini::tools::execute_tool_async(name, args, self.database, self.app_handle).await)
+            } else {
+                let connection = self.database.connection.lock().map_err(|e| format!("{:#}", e))?;
+                Ok(crate::gemini::tools::execute_tool_sync(name, args, self.obsidian_config, &connection))
+            }
+        })
+    }
+
+    //INFO: Emits the reply for real-time display, unwrapped from the structured JSON when possible
+    fn on_reply_text(&self, text: &str) {
+        use tauri::Emitter;
+
+        let emit_text = serde_json::from_str::<serde_json::Value>(text)
+            .ok()
+            .and_then(|v| v.get("response").and_then(|r| r.as_str()).map(String::from))
+            .unwrap_or_else(|| text.to_string());
+        let _ = self.app_handle.emit("assistant-reply-turn", emit_text);
+    }
+
+    //INFO: Clear the streaming bubble so it doesn't show stale tool-call text
+    fn on_tool_round(&self) {
+        use tauri::Emitter;
+
+        let _ = self.app_handle.emit("assistant-reply-clear", ());
+    }
+
+    fn on_usage(&self, usage: Option<&crate::gemini::client::UsageMetadata>) {
+        record_usage(self.database, FEATURE_CHAT, usage);
+    }
+}
+
+//INFO: Stops the in-flight chat request for a session between Gemini turns
+//NOTE: The request still returns (and saves) whatever assistant text it had; false if nothing was running
+#[tauri::command]
+pub fn cancel_chat(cancellation: State<ChatCancellation>, session_id: Option<String>) -> bool {
+    cancellation.cancel(session_id.as_deref())
+}
+
+//INFO: Loads the history to send with a new message, plus the session's running summary if it has one
+//NOTE: Sessions send only the messages the summary doesn't cover; sessionless chat keeps the sliding window
+fn load_chat_history(
+    connection: &rusqlite::Connection,
+    session_id: Option<&str>,
+) -> Result<(Vec<ChatMessage>, Option<String>), LumenError> {
+    let window = history_window(connection);
+
+    let Some(session_id) = session_id else {
+        let limit = window.map_or(SESSIONLESS_HISTORY_MESSAGES, |w| w as i32);
+        let messages = get_chat_messages(connection, None, limit)
+            .command_context("Failed to get history")?;
+        return Ok((messages, None));
+    };
+
+    let session = get_session(connection, session_id)
+        .command_context("Failed to get session")?;
+    let (summary, summarized_through) = session
+        .map(|s| (s.summary, s.summarized_through))
+        .unwrap_or_default();
+
+    let mut messages = get_session_messages_after(connection, session_id, summarized_through)
+        .command_context("Failed to get history")?;
+    let cap = window.unwrap_or(MAX_SESSION_HISTORY_MESSAGES);
+    if messages.len() > cap {
+        messages.drain(..messages.len() - cap);
+    }
+
+    Ok((messages, summary))
+}
+
+//INFO: Compresses a session's oldest unsummarized messages into its running summary
+//NOTE: Returns the new summary, or None when the session isn't long enough to need it yet
+async fn compact_session_history(
+    database: &Database,
+    provider: &dyn crate::llm::LlmProvider,
+    session_id: &str,
+) -> Result<Option<String>, LumenError> {
+    let (previous_summary, to_summarize) = {
+        let connection = database.connection.lock()?;
+        let Some(session) = get_session(&connection, session_id)
+            .command_context("Failed to get session")?
+        else {
+            return Ok(None);
+        };
+        let mut pending = get_session_messages_after(&connection, session_id, session.summarized_through)
+            .command_context("Failed to get session messages")?;
+        if pending.len() <= SUMMARY_TRIGGER_MESSAGES {
+            return Ok(None);
+        }
+        pending.truncate(pending.len() - SUMMARY_KEEP_RECENT_MESSAGES);
+        (session.summary, pending)
+    };
+
+    let Some(summarized_through) = to_summarize.last().and_then(|m| m.id) else {
+        return Ok(None);
+    };
+
+    let response = provider
+        .chat(
+            vec![crate::gemini::client::GeminiContent {
+                role: Some("user".to_string()),
+                parts: vec![crate::gemini::client::GeminiPart::text(build_summary_prompt(
+                    previous_summary.as_deref(),
+                    &to_summarize,
+                ))],
+            }],
+            Some("You maintain a running summary of a conversation between a user and their assistant, Lumen. Reply with the summary only."),
+            None,
+        )
+        .await
+        .command_context("Failed to get summary")?;
+
+    record_usage(database, FEATURE_SUMMARY, response.usage.as_ref());
+
+    let summary = response
+        .parts
+        .iter()
+        .filter_map(|p| p.text.as_ref())
+        .cloned()
+        .collect::<Vec<_>>()
+        .join("")
+        .trim()
+        .to_string();
+    if summary.is_empty() {
+        return Err("Gemini returned an empty summary".into());
+    }
+
+    let connection = database.connection.lock()?;
+    save_session_summary(&connection, session_id, &summary, summarized_through)
+        .command_context("Failed to save summary")?;
+
+    Ok(Some(summary))
+}
+
+//INFO: Prompt asking Gemini to fold `messages` into the existing summary
+fn build_summary_prompt(previous_summary: Option<&str>, messages: &[ChatMessage]) -> String {
+    let transcript = messages
+        .iter()
+        .map(|m| {
+            let speaker = if m.role == "user" { "User" } else { "Lumen" };
+            let attachment = if m.image_data.is_some() { " [image attached]" } else { "" };
+            format!("{}: {}{}", speaker, m.content, attachment)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!(
+        "Update the running summary of this conversation.\n\n\
+         Existing summary:\n{}\n\n\
+         New messages to fold in:\n{}\n\n\
+         Write a concise summary (at most ~250 words) that keeps facts, decisions, names, dates, open questions and anything the user asked Lumen to remember or do. Drop greetings and small talk.",
+        previous_summary.unwrap_or("(none yet)"),
+        transcript
+    )
+}
+
 //INFO: Gets chat history
 #[tauri::command]
 pub fn get_chat_history(
     database: State<Database>,
     session_id: Option<String>,
     limit: Option<i32>,
-) -> Result<Vec<ChatMessageResponse>, String> {
-    let connection = database.connection.lock();
+) -> Result<Vec<ChatMessageResponse>, LumenError> {
+    let connection = database.connection.lock()?;
     let limit = limit.unwrap_or(50);
 
     let messages = get_chat_messages(&connection, session_id.as_deref(), limit)
-        .map_err(|e| format!("Failed to get chat history: {}", e))?;
+        .command_context("Failed to get chat history")?;
 
     Ok(messages
         .into_iter()
@@ -654,14 +925,382 @@ pub fn get_chat_history(
 
 //INFO: Clears all chat history
 #[tauri::command]
-pub fn clear_chat_history(database: State<Database>) -> Result<(), String> {
-    let connection = database.connection.lock();
+pub fn clear_chat_history(database: State<Database>) -> Result<(), LumenError> {
+    let connection = database.connection.lock()?;
+
+    clear_chat_messages(&connection).command_context("Failed to clear chat history")
+}
+
+//INFO: Deletes a single chat message
+//NOTE: `cascade_reply` (default true) also removes the assistant reply to a deleted user message
+#[tauri::command]
+pub fn delete_chat_message(
+    database: State<Database>,
+    id: i64,
+    cascade_reply: Option<bool>,
+) -> Result<usize, LumenError> {
+    let connection = database.connection.lock()?;
+
+    let deleted = delete_chat_message_query(&connection, id, cascade_reply.unwrap_or(true))
+        .command_context("Failed to delete message")?;
+
+    if deleted == 0 {
+        return Err(format!("Message {} not found", id).into());
+    }
+    Ok(deleted)
+}
+
+//INFO: Deletes the last N messages of a session, e.g. to redo the latest exchange
+#[tauri::command]
+pub fn delete_last_chat_messages(
+    database: State<Database>,
+    session_id: Option<String>,
+    count: u32,
+) -> Result<usize, LumenError> {
+    let connection = database.connection.lock()?;
+
+    delete_last_messages(&connection, session_id.as_deref(), count)
+        .command_context("Failed to delete messages")
+}
+
+//INFO: Asks the model for a short title summarizing the first message of a session
+async fn generate_session_title(provider: &dyn crate::llm::LlmProvider, first_message: &str) -> Option<String> {
+    let prompt = format!(
+        "Write a short title (max 6 words, no quotes, no trailing punctuation) for a conversation that starts with:\n\n{}",
+        first_message
+    );
+
+    let response = provider
+        .chat(
+            vec![crate::gemini::client::GeminiContent {
+                role: Some("user".to_string()),
+                parts: vec![crate::gemini::client::GeminiPart::text(prompt)],
+            }],
+            Some("You generate concise conversation titles. Reply with the title only."),
+            None,
+        )
+        .await
+        .ok()?;
+
+    let title = response
+        .parts
+        .iter()
+        .filter_map(|p| p.text.as_ref())
+        .cloned()
+        .collect::<Vec<_>>()
+        .join("")
+        .trim()
+        .trim_matches('"')
+        .to_string();
+
+    if title.is_empty() {
+        None
+    } else {
+        Some(title.chars().take(80).collect())
+    }
+}
+
+//INFO: Creates a new, empty chat session
+#[tauri::command]
+pub fn create_chat_session(
+    database: State<Database>,
+    title: Option<String>,
+) -> Result<ChatSession, LumenError> {
+    let connection = database.connection.lock()?;
+    let id = uuid::Uuid::new_v4().to_string();
+    let title = title.unwrap_or_else(|| "New chat".to_string());
+
+    create_session(&connection, &id, &title).command_context("Failed to create session")
+}
+
+//INFO: Lists all chat sessions
+#[tauri::command]
+pub fn list_chat_sessions(database: State<Database>) -> Result<Vec<ChatSession>, LumenError> {
+    let connection = database.connection.lock()?;
+
+    list_sessions(&connection).command_context("Failed to list sessions")
+}
+
+//INFO: Renames a chat session
+#[tauri::command]
+pub fn rename_chat_session(
+    database: State<Database>,
+    session_id: String,
+    title: String,
+) -> Result<(), LumenError> {
+    let connection = database.connection.lock()?;
 
-    clear_chat_messages(&connection).map_err(|e| format!("Failed to clear chat history: {}", e))
+    rename_session(&connection, &session_id, &title)
+        .command_context("Failed to rename session")
+}
+
+//INFO: Deletes a chat session along with its messages
+#[tauri::command]
+pub fn delete_chat_session(database: State<Database>, session_id: String) -> Result<(), LumenError> {
+    let connection = database.connection.lock()?;
+
+    delete_session(&connection, &session_id).command_context("Failed to delete session")
+}
+
+//INFO: Folder inside the Obsidian vault that exported chats go to by default
+const VAULT_EXPORT_FOLDER: &str = "Lumen";
+
+//INFO: Formats a stored RFC3339 timestamp for humans, falling back to the raw value
+fn format_export_timestamp(created_at: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(created_at)
+        .map(|dt| dt.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|_| created_at.to_string())
+}
+
+//INFO: Renders a session as Markdown: a title, then one "## Role · time" section per message
+//NOTE: Images are embedded inline as base64 data URIs so the file stands on its own
+pub fn render_session_markdown(title: &str, messages: &[ChatMessage]) -> String {
+    let mut output = format!(
+        "# {}\n\n_Exported from Lumen on {}_\n",
+        title.trim(),
+        Local::now().format("%Y-%m-%d %H:%M")
+    );
+
+    for message in messages {
+        let role = match message.role.as_str() {
+            "user" => "You",
+            "assistant" | "model" => "Lumen",
+            other => other,
+        };
+        output.push_str(&format!(
+            "\n## {} · {}\n\n",
+            role,
+            format_export_timestamp(&message.created_at)
+        ));
+
+        if let Some(image) = message.image_data.as_deref().filter(|i| !i.is_empty()) {
+            output.push_str(&format!(
+                "![image](data:{};base64,{})\n\n",
+                crate::commands::vision::sniff_image_mime(image),
+                image
+            ));
+        }
+
+        output.push_str(message.content.trim());
+        output.push('\n');
+    }
+
+    output
+}
+
+//INFO: `path` if nothing is there yet, otherwise the first of "<stem> 2.<ext>", "<stem> 3.<ext>", ... that's free
+fn first_free_path(path: std::path::PathBuf) -> std::path::PathBuf {
+    if !path.exists() {
+        return path;
+    }
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let extension = path.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    (2..)
+        .map(|n| path.with_file_name(format!("{} {}{}", stem, n, extension)))
+        .find(|candidate| !candidate.exists())
+        .expect("an unused file name")
+}
+
+//INFO: Exports a chat session to a Markdown file and returns the path written
+//NOTE: With `to_vault`, `dest_path` is relative to the Obsidian vault (default "Lumen/<title>.md");
+//      otherwise it's a file path, or a directory to put "<title>.md" in. An existing file is only
+//      replaced with `overwrite`; otherwise the export gets a free name ("<title> 2.md", ...).
+#[tauri::command]
+pub fn export_session_markdown(
+    database: State<Database>,
+    session_id: String,
+    dest_path: Option<String>,
+    to_vault: Option<bool>,
+    overwrite: Option<bool>,
+) -> Result<String, LumenError> {
+    let (session, messages) = {
+        let connection = database.connection.lock()?;
+        let session = get_session(&connection, &session_id)
+            .command_context("Failed to load session")?
+            .ok_or_else(|| LumenError::Other(format!("Chat session {} not found", session_id)))?;
+        let messages = get_session_messages_after(&connection, &session_id, None)
+            .command_context("Failed to load session messages")?;
+        (session, messages)
+    };
+
+    let slug = crate::integrations::obsidian::slugify_title(&session.title);
+    let file_name = format!("{}.md", if slug.is_empty() { "lumen-chat" } else { &slug });
+
+    let path = if to_vault.unwrap_or(false) {
+        let config = get_obsidian_config(&database)
+            .and_then(|c| ObsidianConfig::from_json(&c))
+            .ok_or_else(|| LumenError::IntegrationDisabled("Obsidian".to_string()))?;
+        let relative = dest_path
+            .filter(|p| !p.trim().is_empty())
+            .unwrap_or_else(|| format!("{}/{}", VAULT_EXPORT_FOLDER, file_name));
+        crate::integrations::obsidian::resolve_in_vault(&config, &relative)
+            .command_context("Invalid vault path")?
+    } else {
+        let dest = dest_path
+            .filter(|p| !p.trim().is_empty())
+            .ok_or("A destination path is required")?;
+        let dest = std::path::PathBuf::from(dest.trim());
+        if dest.is_dir() {
+            dest.join(&file_name)
+        } else {
+            dest
+        }
+    };
+
+    let path = if overwrite.unwrap_or(false) {
+        path
+    } else {
+        first_free_path(path)
+    };
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).command_context("Failed to create export folder")?;
+    }
+    std::fs::write(&path, render_session_markdown(&session.title, &messages))
+        .command_context("Failed to write Markdown export")?;
+
+    tracing::info!("📝 Chat: Exported session {} to {}", session_id, path.display());
+    Ok(path.to_string_lossy().into_owned())
+}
+
+//INFO: Previews a tool call without side effects
+//NOTE: Mutating tools return a description (plus diff/API call); read-only tools actually execute
+#[tauri::command]
+pub async fn dry_run_tool(
+    app_handle: tauri::AppHandle,
+    database: State<'_, Database>,
+    name: String,
+    args: serde_json::Value,
+) -> Result<serde_json::Value, LumenError> {
+    use crate::gemini::tools;
+
+    if tools::is_mutating_tool(&name) {
+        let obsidian_config = get_obsidian_config(&database);
+        let preview = tools::preview_tool_call(&name, &args, obsidian_config.as_ref());
+        return Ok(serde_json::json!({ "executed": false, "preview": preview }));
+    }
+
+    let result = if tools::is_async_tool(&name) {
+        tools::execute_tool_async(&name, &args, &database, &app_handle).await
+    } else {
+        let obsidian_config = get_obsidian_config(&database);
+        let connection = database.connection.lock()?;
+        tools::execute_tool_sync(&name, &args, obsidian_config.as_ref(), &connection)
+    };
+
+    Ok(serde_json::json!({ "executed": true, "result": result }))
+}
+
+//INFO: Default number of entries returned by `get_tool_audit`
+const DEFAULT_TOOL_AUDIT_LIMIT: i64 = 100;
+
+//INFO: Gets the most recent tool calls the agent made (activity history)
+#[tauri::command]
+pub fn get_tool_audit(
+    database: State<Database>,
+    limit: Option<i64>,
+) -> Result<Vec<crate::database::queries::ToolAuditEntry>, LumenError> {
+    let connection = database.connection.lock()?;
+    crate::database::queries::get_tool_audit(&connection, limit.unwrap_or(DEFAULT_TOOL_AUDIT_LIMIT))
+        .command_context("Failed to get tool audit")
+}
+
+//INFO: Answers a `tool-confirm-request` event, letting the waiting tool call run or be skipped
+#[tauri::command]
+pub fn confirm_tool_call(id: String, approved: bool) -> Result<(), LumenError> {
+    if crate::gemini::confirmation::resolve_confirmation(&id, approved) {
+        Ok(())
+    } else {
+        Err(format!("No pending tool call with id {}", id).into())
+    }
+}
+
+//INFO: Gets the tools that ask for confirmation before running
+#[tauri::command]
+pub fn get_confirmation_tools(database: State<Database>) -> Result<Vec<String>, LumenError> {
+    let connection = database.connection.lock()?;
+    Ok(crate::gemini::confirmation::confirmation_tools(&connection))
+}
+
+//INFO: Sets the tools that ask for confirmation; an empty list turns confirmations off
+#[tauri::command]
+pub fn set_confirmation_tools(database: State<Database>, tools: Vec<String>) -> Result<(), LumenError> {
+    let connection = database.connection.lock()?;
+    let json = serde_json::to_string(&tools)
+        .command_context("Failed to save confirmation tools")?;
+
+    crate::database::queries::save_setting(
+        &connection,
+        crate::gemini::confirmation::CONFIRM_TOOLS_SETTING,
+        &json,
+    )
+    .command_context("Failed to save confirmation tools")
+}
+
+//INFO: Remembered facts included in chat context per message
+const CONTEXT_FACT_LIMIT: usize = 5;
+
+//INFO: How far back chat context looks for a daily note when today's doesn't exist yet
+const DAILY_NOTE_LOOKBACK_DAYS: i64 = 7;
+
+//INFO: Loads the Obsidian integration config as JSON, if configured
+fn get_obsidian_config(database: &Database) -> Option<serde_json::Value> {
+    let connection = database.connection.lock().ok()?;
+    get_integration(&connection, "obsidian")
+        .ok()
+        .flatten()
+        .and_then(|i| i.config)
+        .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+}
+
+//INFO: Settings key for a note that's always included in chat context (e.g. "About me.md")
+//NOTE: Absolute, or relative to the Obsidian vault when one is configured
+pub const PINNED_CONTEXT_SETTING: &str = "pinned_context_path";
+const PINNED_CONTEXT_MAX_CHARS: usize = 3000;
+
+//INFO: Reads the pinned context note as a labeled context section
+//NOTE: A missing file still yields a section, so the model can tell the user instead of silently losing it
+fn load_pinned_context(database: &Database, obsidian: Option<&ObsidianConfig>) -> Option<String> {
+    let setting = {
+        let connection = database.connection.lock().ok()?;
+        crate::database::queries::get_setting(&connection, PINNED_CONTEXT_SETTING).ok().flatten()
+    }?;
+    let setting = setting.trim();
+    if setting.is_empty() {
+        return None;
+    }
+
+    let path = match obsidian {
+        Some(config) if std::path::Path::new(setting).is_relative() => config.vault_path.join(setting),
+        _ => std::path::PathBuf::from(setting),
+    };
+
+    match std::fs::read_to_string(&path) {
+        Ok(content) => {
+            let truncated = if content.chars().count() > PINNED_CONTEXT_MAX_CHARS {
+                format!("{}... (truncated)", content.chars().take(PINNED_CONTEXT_MAX_CHARS).collect::<String>())
+            } else {
+                content
+            };
+            Some(format!(
+                "--- PINNED CONTEXT (always-on background from the user, PATH: {}) ---\n{}\n-------------------------------------------",
+                path.to_string_lossy(),
+                truncated.trim()
+            ))
+        }
+        Err(e) => {
+            tracing::warn!("⚠️ Chat: Pinned context note {} could not be read: {}", path.display(), e);
+            Some(format!(
+                "--- PINNED CONTEXT ---\nThe user's pinned context note could not be read ({}: {}). If it seems relevant, let them know so they can fix the path in Settings.\n-------------------------------------------",
+                path.to_string_lossy(),
+                e
+            ))
+        }
+    }
 }
 
 //INFO: Bu//INFO: Builds context string from integrations (calendar, notes, etc.)
-fn build_chat_context(database: &State<Database>) -> Result<Option<String>, String> {
+fn build_chat_context(database: &State<Database>, message: &str) -> Result<Option<String>, LumenError> {
     let mut context_parts: Vec<String> = Vec::new();
 
     // 1. Static Metadata
@@ -673,18 +1312,32 @@ fn build_chat_context(database: &State<Database>) -> Result<Option<String>, Stri
 
     // 2. Integration Data (Locked Section - Keep it brief)
     let (user_profile, g_int, o_int) = {
-        let connection = database.connection.lock();
+        let connection = database.connection.lock()?;
         let user_profile = get_user_profile(&connection).ok().flatten();
         let g_int = get_integration(&connection, "google").ok().flatten();
         let o_int = get_integration(&connection, "obsidian").ok().flatten();
         (user_profile, g_int, o_int)
     };
 
+    let timezone = user_profile.as_ref().and_then(|p| p.timezone.clone());
     if let Some(profile) = user_profile {
         context_parts.push(format!("User Name: {}", profile.display_name));
+        if let Some(hours) = crate::agent::proactive::WorkingHours::from_profile(&profile) {
+            context_parts.push(format!(
+                "Working hours: {} (right now: {})",
+                hours.describe(),
+                hours.phase(today.time())
+            ));
+        }
     }
 
-    context_parts.push(format!("\n[TECHNICAL CONTEXT]\nISO_NOW: {}", iso_now));
+    //NOTE: One "now", in the user's local time with its UTC offset, so the model never has to reconcile UTC vs local
+    context_parts.push(format!(
+        "\n[TECHNICAL CONTEXT]\nISO_NOW: {} (user's local time; UTC offset {})\nUSER_TIMEZONE: {}",
+        iso_now,
+        today.format("%:z"),
+        timezone.as_deref().unwrap_or("unknown (use the UTC offset above)")
+    ));
 
     let mut status_parts = Vec::new();
     status_parts.push("--- INTEGRATION STATUS ---".to_string());
@@ -696,14 +1349,26 @@ fn build_chat_context(database: &State<Database>) -> Result<Option<String>, Stri
     // 3. Calendar Data (Locked Section)
     if let Some(integration) = g_int {
         if integration.enabled {
-            let start_of_day = today.format("%Y-%m-%dT00:00:00").to_string();
-            let end_of_day = today.format("%Y-%m-%dT23:59:59").to_string();
-            let connection = database.connection.lock();
+            //NOTE: The cache stores UTC times, so the local day is converted before querying
+            let start_of_day = crate::integrations::google_calendar::normalize_cache_time(
+                &today.format("%Y-%m-%dT00:00:00%:z").to_string(),
+            );
+            let end_of_day = crate::integrations::google_calendar::normalize_cache_time(
+                &today.format("%Y-%m-%dT23:59:59%:z").to_string(),
+            );
+            let connection = database.connection.lock()?;
             if let Ok(events) = get_calendar_events(&connection, &start_of_day, &end_of_day) {
                 if !events.is_empty() {
                     let mut events_str = String::from("Today's calendar events:\n");
                     for event in events {
-                        events_str.push_str(&format!("- {} at {}\n", event.title, event.start_time));
+                        let when = if event.all_day {
+                            "all day".to_string()
+                        } else {
+                            chrono::DateTime::parse_from_rfc3339(&event.start_time)
+                                .map(|t| format!("at {}", t.with_timezone(&Local).format("%H:%M")))
+                                .unwrap_or_else(|_| format!("at {}", event.start_time))
+                        };
+                        events_str.push_str(&format!("- {} {}\n", event.title, when));
                     }
                     context_parts.push(events_str);
                 }
@@ -712,42 +1377,103 @@ fn build_chat_context(database: &State<Database>) -> Result<Option<String>, Stri
     }
 
     // 4. Obsidian Data (NO LOCKS - Pure Disk I/O)
-    if let Some(integration) = o_int {
-        if integration.enabled {
-            if let Some(config) = integration.config {
-                if let Ok(config_json) = serde_json::from_str::<serde_json::Value>(&config) {
-                    if let Some(vault_path) = config_json.get("vault_path").and_then(|v| v.as_str()) {
-                        let daily_notes_folder = config_json.get("daily_notes_path").and_then(|v| v.as_str()).unwrap_or("");
-                        let date_format_raw = config_json.get("daily_notes_format").and_then(|v| v.as_str()).unwrap_or("YYYY-MM-DD");
-
-                        let chrono_format = date_format_raw.replace("YYYY", "%Y").replace("MM", "%m").replace("DD", "%d");
-                        let daily_note_name = format!("{}.md", today.format(&chrono_format));
-                        let daily_note_path = std::path::Path::new(vault_path).join(daily_notes_folder).join(&daily_note_name);
-
-                        if daily_note_path.exists() {
-                            if let Ok(content) = std::fs::read_to_string(&daily_note_path) {
-                                let truncated_content = if content.chars().count() > 2000 {
-                                    format!("{}... (truncated)", content.chars().take(2000).collect::<String>())
-                                } else {
-                                    content
-                                };
-                                context_parts.push(format!(
-                                    "Today's daily note (NAME: {}, PATH: {}):\n{}",
-                                    daily_note_name,
-                                    daily_note_path.to_string_lossy(),
-                                    truncated_content
-                                ));
-                            }
-                        }
-                    }
-                }
-            }
+    let obsidian = o_int
+        .filter(|i| i.enabled)
+        .and_then(|i| i.config)
+        .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+        .and_then(|c| ObsidianConfig::from_json(&c));
+
+    if let Some(obsidian) = &obsidian {
+        //INFO: Fall back to the most recent note from the past week, same as the dashboard
+        if let Some(note) = find_recent_daily_note(obsidian, today.date_naive(), DAILY_NOTE_LOOKBACK_DAYS) {
+            let label = if note.date == today.date_naive() {
+                "Today's daily note".to_string()
+            } else {
+                format!("Most recent daily note, from {}", note.date.format("%A, %b %d"))
+            };
+            let truncated_content = if note.content.chars().count() > 2000 {
+                format!("{}... (truncated)", note.content.chars().take(2000).collect::<String>())
+            } else {
+                note.content.clone()
+            };
+            context_parts.push(format!(
+                "{} (NAME: {}, PATH: {}):\n{}",
+                label,
+                note.name(),
+                note.path.to_string_lossy(),
+                truncated_content
+            ));
         }
     }
 
+    // 5. Pinned context note, included every turn
+    if let Some(pinned) = load_pinned_context(database, obsidian.as_ref()) {
+        context_parts.push(pinned);
+    }
+
+    // 6. Remembered facts that match the message
+    let facts = {
+        let connection = database.connection.lock()?;
+        crate::memory::facts::facts_for_message(&connection, message, CONTEXT_FACT_LIMIT).unwrap_or_default()
+    };
+    if let Some(section) = crate::memory::facts::format_facts_for_context(&facts) {
+        context_parts.push(section);
+    }
+
     if context_parts.len() > 1 {
         Ok(Some(context_parts.join("\n\n")))
     } else {
         Ok(None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_session_markdown() {
+        let message = |role: &str, content: &str, image: Option<&str>| ChatMessage {
+            id: None,
+            role: role.to_string(),
+            content: content.to_string(),
+            image_data: image.map(str::to_string),
+            created_at: "not a timestamp".to_string(),
+            session_id: Some("s1".to_string()),
+        };
+
+        let markdown = render_session_markdown(
+            "Trip planning",
+            &[
+                message("user", "What's this?", Some("/9j/abc")),
+                message("assistant", "A photo of a beach.\n", None),
+            ],
+        );
+
+        assert!(markdown.starts_with("# Trip planning\n"));
+        assert!(markdown.contains("\n## You · not a timestamp\n\n![image](data:image/jpeg;base64,/9j/abc)\n\nWhat's this?\n"));
+        assert!(markdown.ends_with("\n## Lumen · not a timestamp\n\nA photo of a beach.\n"));
+    }
+
+    #[test]
+    fn test_first_free_path_never_reuses_an_existing_file() {
+        let dir = std::env::temp_dir().join(format!("lumen-export-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("Plan.md");
+        assert_eq!(first_free_path(path.clone()), path);
+
+        std::fs::write(&path, "existing").unwrap();
+        std::fs::write(dir.join("Plan 2.md"), "existing").unwrap();
+        assert_eq!(first_free_path(path), dir.join("Plan 3.md"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_history_window_is_clamped() {
+        assert_eq!(parse_history_window("16"), Some(16));
+        assert_eq!(parse_history_window(" 1 "), Some(2));
+        assert_eq!(parse_history_window("500"), Some(50));
+        assert_eq!(parse_history_window("lots"), None);
+    }
+}
@@ -0,0 +1,67 @@
+//INFO: Clipboard history commands for Lumen
+//NOTE: Lets the frontend browse the full history and pin favorites
+
+use crate::database::queries::{self, ClipboardHistoryItem};
+use crate::database::Database;
+use tauri::State;
+
+//INFO: Returns a page of clipboard history, pinned items first
+#[tauri::command]
+pub fn get_clipboard_history(
+    database: State<Database>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> Result<Vec<ClipboardHistoryItem>, String> {
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
+
+    queries::get_clipboard_history(&connection, limit.unwrap_or(50), offset.unwrap_or(0))
+        .map_err(|e| format!("Failed to load clipboard history: {}", e))
+}
+
+//INFO: Pins a clipboard item so it surfaces first and survives pruning
+#[tauri::command]
+pub fn pin_clipboard_item(database: State<Database>, id: i64) -> Result<(), String> {
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
+
+    let updated = queries::pin_clipboard_item(&connection, id)
+        .map_err(|e| format!("Failed to pin clipboard item: {}", e))?;
+
+    if updated {
+        Ok(())
+    } else {
+        Err(format!("Clipboard item {} not found", id))
+    }
+}
+
+//INFO: Unpins a clipboard item
+#[tauri::command]
+pub fn unpin_clipboard_item(database: State<Database>, id: i64) -> Result<(), String> {
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
+
+    let updated = queries::unpin_clipboard_item(&connection, id)
+        .map_err(|e| format!("Failed to unpin clipboard item: {}", e))?;
+
+    if updated {
+        Ok(())
+    } else {
+        Err(format!("Clipboard item {} not found", id))
+    }
+}
+
+//INFO: Returns the full base64 PNG data for a clipboard image
+#[tauri::command]
+pub fn get_clipboard_image(
+    database: State<Database>,
+    id: i64,
+) -> Result<ClipboardHistoryItem, String> {
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
+
+    let item = queries::get_clipboard_item(&connection, id)
+        .map_err(|e| format!("Failed to load clipboard item: {}", e))?
+        .ok_or_else(|| format!("Clipboard item {} not found", id))?;
+
+    if item.content_type != "image" {
+        return Err(format!("Clipboard item {} is not an image", id));
+    }
+    Ok(item)
+}
@@ -2,13 +2,16 @@
 //NOTE: Handles daily briefing summaries with hashing and AI evolution
 
 use crate::database::{queries, Database};
+use crate::error::{CommandContext, LumenError};
 use crate::gemini::client::{GeminiClient, GeminiContent, GeminiPart, GenerationConfig};
+use crate::gemini::usage::{record_usage, FEATURE_BRIEFING};
+use crate::integrations::obsidian::{read_daily_note, ObsidianConfig};
 use base64::{engine::general_purpose, Engine as _};
 use chrono::{Duration, Local};
 use rusqlite::params;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::fs;
-use std::path::Path;
 use tauri::State;
 use walkdir::WalkDir;
 
@@ -20,17 +23,153 @@ pub struct DashboardBriefing {
     pub audio_data: Option<String>, // Base64 encoded audio
 }
 
+//INFO: A past briefing, as shown in the dashboard's history
+#[derive(Debug, Serialize)]
+pub struct BriefingHistoryEntry {
+    pub id: i32,
+    pub content: String,
+    pub created_at: String,
+    pub is_final_of_day: bool,
+    pub audio_data: Option<String>, // Base64 encoded audio
+}
+
+impl From<queries::BriefingSummary> for BriefingHistoryEntry {
+    fn from(summary: queries::BriefingSummary) -> Self {
+        Self {
+            id: summary.id,
+            content: summary.content,
+            created_at: summary.created_at,
+            is_final_of_day: summary.is_final_of_day,
+            audio_data: summary
+                .audio_data
+                .map(|data| general_purpose::STANDARD.encode(data)),
+        }
+    }
+}
+
+//INFO: Default page size for `list_briefings` (each entry can carry its audio, so keep it small)
+const DEFAULT_BRIEFING_PAGE_SIZE: u32 = 10;
+
+//INFO: Lists past briefings newest first, with their audio for replay
+#[tauri::command]
+pub fn list_briefings(
+    database: State<'_, Database>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> Result<Vec<BriefingHistoryEntry>, LumenError> {
+    let connection = database.connection.lock()?;
+    let briefings = queries::list_briefing_summaries(
+        &connection,
+        limit.unwrap_or(DEFAULT_BRIEFING_PAGE_SIZE),
+        offset.unwrap_or(0),
+    )
+    .command_context("Failed to list briefings")?;
+
+    Ok(briefings.into_iter().map(BriefingHistoryEntry::from).collect())
+}
+
+//INFO: Gets one past briefing by id
+#[tauri::command]
+pub fn get_briefing(
+    database: State<'_, Database>,
+    id: i32,
+) -> Result<Option<BriefingHistoryEntry>, LumenError> {
+    let connection = database.connection.lock()?;
+    let briefing = queries::get_briefing_summary(&connection, id)
+        .command_context("Failed to get briefing")?;
+
+    Ok(briefing.map(BriefingHistoryEntry::from))
+}
+
+//INFO: Fingerprint of the data a briefing is built from, so new emails/tasks/events mark it stale
+//NOTE: Signals: today's unread primary emails (ids), pending reminders (ids), today's events (ids + latest `updated`),
+//NOTE: and today's daily note mtime. Errors if Google can't be reached, since then we can't tell either way.
+pub async fn calculate_briefing_hash(database: &Database) -> anyhow::Result<String> {
+    let mut signals = Vec::new();
+
+    let (has_google, obsidian, reminder_ids) = {
+        let connection = database.connection.lock()?;
+        //NOTE: A disabled Google account keeps its token, so check the integration too
+        let has_google = queries::has_api_token(&connection, "google").unwrap_or(false)
+            && queries::get_integration(&connection, "google")
+                .ok()
+                .flatten()
+                .is_some_and(|i| i.enabled);
+        let obsidian = queries::get_integration(&connection, "obsidian")
+            .ok()
+            .flatten()
+            .filter(|i| i.enabled)
+            .and_then(|i| i.config)
+            .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+            .and_then(|c| ObsidianConfig::from_json(&c));
+        let reminder_ids = queries::get_pending_reminders(&connection)?
+            .iter()
+            .map(|r| r.id.to_string())
+            .collect::<Vec<_>>();
+        (has_google, obsidian, reminder_ids)
+    };
+
+    signals.push(format!("date:{}", Local::now().date_naive()));
+    signals.push(format!("tasks:{}:{}", reminder_ids.len(), reminder_ids.join(",")));
+
+    if let Some(obsidian) = obsidian {
+        let modified = read_daily_note(&obsidian, Local::now().date_naive())
+            .and_then(|note| fs::metadata(&note.path).ok())
+            .and_then(|m| m.modified().ok())
+            .map(|t| chrono::DateTime::<Local>::from(t).to_rfc3339())
+            .unwrap_or_default();
+        signals.push(format!("daily_note:{}", modified));
+    }
+
+    if has_google {
+        let mut email_ids = crate::integrations::google_gmail::list_message_ids(
+            database,
+            20,
+            "is:unread category:primary newer_than:1d",
+        )
+        .await?;
+        email_ids.sort();
+        signals.push(format!("emails:{}:{}", email_ids.len(), email_ids.join(",")));
+
+        let start_of_day = Local::now().format("%Y-%m-%dT00:00:00%:z").to_string();
+        let end_of_day = Local::now().format("%Y-%m-%dT23:59:59%:z").to_string();
+        let events = crate::integrations::google_calendar::fetch_google_calendar_events(
+            database,
+            &start_of_day,
+            &end_of_day,
+            None,
+        )
+        .await?;
+        let latest_update = events.iter().filter_map(|e| e.updated.as_deref()).max().unwrap_or("");
+        let event_ids = events.iter().map(|e| e.id.as_str()).collect::<Vec<_>>();
+        signals.push(format!("events:{}:{}:{}", event_ids.len(), event_ids.join(","), latest_update));
+    }
+
+    let digest = Sha256::digest(signals.join("\n").as_bytes());
+    Ok(digest.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
 //INFO: //INFO: Gets the latest briefing from the database
 #[tauri::command]
 pub async fn get_dashboard_briefing(
     database: State<'_, Database>,
-) -> Result<Option<DashboardBriefing>, String> {
+) -> Result<Option<DashboardBriefing>, LumenError> {
     let latest = {
-        let connection = database.connection.lock();
-        queries::get_latest_briefing_summary(&connection).map_err(|e| e.to_string())?
+        let connection = database.connection.lock()?;
+        queries::get_latest_briefing_summary(&connection)
+            .command_context("Failed to load briefing")?
     };
 
     if let Some(summary) = latest {
+        //NOTE: If the data can't be checked (e.g. offline), keep showing the briefing rather than forcing a refresh
+        let is_stale = match calculate_briefing_hash(&database).await {
+            Ok(hash) => hash != summary.data_hash,
+            Err(e) => {
+                tracing::warn!("⚠️ Dashboard: Could not check briefing freshness: {:#}", e);
+                false
+            }
+        };
+
         let b64_audio = summary
             .audio_data
             .map(|data| general_purpose::STANDARD.encode(data));
@@ -38,7 +177,7 @@ pub async fn get_dashboard_briefing(
         Ok(Some(DashboardBriefing {
             content: summary.content,
             created_at: summary.created_at,
-            is_stale: false, // Inverted logic: user refreshes manually now
+            is_stale,
             audio_data: b64_audio,
         }))
     } else {
@@ -46,33 +185,122 @@ pub async fn get_dashboard_briefing(
     }
 }
 
+//INFO: Which data sources a briefing pulls in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BriefingSources {
+    pub obsidian: bool,
+    pub email: bool,
+    pub calendar: bool,
+    pub weather: bool,
+}
+
+impl BriefingSources {
+    pub const ALL: Self = Self {
+        obsidian: true,
+        email: true,
+        calendar: true,
+        weather: true,
+    };
+
+    //INFO: Picks sources from explicit names ("obsidian", "email", "calendar", "weather")
+    pub fn from_names(names: &[String]) -> Self {
+        let has = |source: &str| names.iter().any(|n| n.trim().eq_ignore_ascii_case(source));
+        Self {
+            obsidian: has("obsidian") || has("notes"),
+            email: has("email") || has("gmail"),
+            calendar: has("calendar"),
+            weather: has("weather"),
+        }
+    }
+
+    //INFO: Guesses the relevant sources from a focus like "just my calendar today"
+    //NOTE: Falls back to everything when the focus doesn't mention any source
+    pub fn for_focus(focus: &str) -> Self {
+        let focus = focus.to_lowercase();
+        let mentions = |words: &[&str]| words.iter().any(|w| focus.contains(w));
+
+        let sources = Self {
+            obsidian: mentions(&["note", "obsidian", "vault", "journal", "task", "todo", "deadline", "project"]),
+            email: mentions(&["email", "mail", "inbox", "message"]),
+            calendar: mentions(&["calendar", "meeting", "schedule", "event", "agenda", "deadline"]),
+            weather: mentions(&["weather", "rain", "temperature", "outside"]),
+        };
+
+        if sources.is_empty() {
+            Self::ALL
+        } else {
+            sources
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        !(self.obsidian || self.email || self.calendar || self.weather)
+    }
+}
+
 //INFO: Generates a new briefing evolution using Gemini
 #[tauri::command]
 pub async fn refresh_dashboard_briefing(
     database: State<'_, Database>,
     app_handle: tauri::AppHandle,
-) -> Result<DashboardBriefing, String> {
-    // 1. Get user profile and API key
-    let (greeting_name, api_key_encrypted) = {
-        let connection = database.connection.lock();
+) -> Result<DashboardBriefing, LumenError> {
+    generate_briefing(&database, app_handle, None, BriefingSources::ALL).await
+}
+
+//INFO: Generates a briefing on demand around a custom focus (e.g. "focus on my deadlines")
+//NOTE: `sources` overrides which data gets loaded; otherwise it's inferred from the focus
+#[tauri::command]
+pub async fn generate_focused_briefing(
+    database: State<'_, Database>,
+    app_handle: tauri::AppHandle,
+    focus: String,
+    sources: Option<Vec<String>>,
+) -> Result<DashboardBriefing, LumenError> {
+    let focus = focus.trim();
+    if focus.is_empty() {
+        return Err(LumenError::Other("Briefing focus cannot be empty".to_string()));
+    }
+
+    let sources = match sources {
+        Some(names) if !names.is_empty() => BriefingSources::from_names(&names),
+        _ => BriefingSources::for_focus(focus),
+    };
+
+    generate_briefing(&database, app_handle, Some(focus), sources).await
+}
+
+//INFO: Shared briefing pipeline: gather the selected sources, ask Gemini, save, then voice it in the background
+//NOTE: Only full (unfocused) briefings are saved and feed the memory time-buckets and DailySummary synthesis
+async fn generate_briefing(
+    database: &Database,
+    app_handle: tauri::AppHandle,
+    focus: Option<&str>,
+    sources: BriefingSources,
+) -> Result<DashboardBriefing, LumenError> {
+    // 1. Get user profile and the chat provider
+    //NOTE: Memory embeddings stay on Gemini, so they're skipped when no Gemini key is saved
+    let (greeting_name, working_hours, provider, gemini_key) = {
+        let connection = database.connection.lock()?;
         let profile = queries::get_user_profile(&connection).ok().flatten();
         let name = profile.as_ref().map(|p| p.display_name.clone()).unwrap_or_else(|| "User".to_string());
-        
-        let key = queries::get_api_token(&connection, "gemini")
-            .map_err(|e| e.to_string())?
-            .ok_or("Gemini API key not configured")?;
-            
-        (name, key)
-    };
+        let working_hours = profile.as_ref().and_then(crate::agent::proactive::WorkingHours::from_profile);
 
-    let api_key = crate::crypto::decrypt_token(&api_key_encrypted).map_err(|e| e.to_string())?;
-    let gemini_client = GeminiClient::new(api_key.clone());
+        (
+            name,
+            working_hours,
+            crate::llm::provider_from_settings(&connection)?,
+            crate::llm::background_gemini_key(&connection)?,
+        )
+    };
 
     // 2. Fetch Raw Data in Parallel
     let obsidian_future = {
-        let db = database.inner().clone();
+        let db = database.clone();
         async move {
-            let connection = db.connection.lock();
+            if !sources.obsidian {
+                return None;
+            }
+            let connection = db.connection.lock().ok()?;
             let mut notes = Vec::new();
             let mut recent_files = Vec::new();
 
@@ -80,23 +308,19 @@ pub async fn refresh_dashboard_briefing(
                 if integration.enabled {
                     if let Some(config) = integration.config {
                         if let Ok(config_json) = serde_json::from_str::<serde_json::Value>(&config) {
-                            if let Some(vault_path) = config_json.get("vault_path").and_then(|v| v.as_str()) {
-                                let daily_notes_folder = config_json.get("daily_notes_path").and_then(|v| v.as_str()).unwrap_or("");
-                                let date_format = config_json.get("daily_notes_format").and_then(|v| v.as_str()).unwrap_or("YYYY-MM-DD");
-                                let chrono_format = date_format.replace("YYYY", "%Y").replace("MM", "%m").replace("DD", "%d");
+                            if let Some(obsidian) = ObsidianConfig::from_json(&config_json) {
+                                let vault_path = &obsidian.vault_path;
 
                                 // A. Daily Notes (7 days)
                                 for i in 0..7 {
-                                    let target_date = Local::now() - Duration::days(i);
+                                    let target_date = Local::now().date_naive() - Duration::days(i);
                                     let label = if i == 0 { "TODAY" } else if i == 1 { "YESTERDAY" } else { "PAST" };
-                                    let note_name = format!("{}.md", target_date.format(&chrono_format));
-                                    let note_path = Path::new(vault_path).join(daily_notes_folder).join(&note_name);
 
-                                    if let Ok(content) = fs::read_to_string(&note_path) {
-                                        let trimmed = if content.chars().count() > 500 {
-                                            format!("{}...", content.chars().take(500).collect::<String>())
+                                    if let Some(note) = read_daily_note(&obsidian, target_date) {
+                                        let trimmed = if note.content.chars().count() > 500 {
+                                            format!("{}...", note.content.chars().take(500).collect::<String>())
                                         } else {
-                                            content
+                                            note.content
                                         };
                                         notes.push(format!("### [{}] Daily Note ({})\n{}", label, target_date.format("%A, %B %d"), trimmed));
                                     }
@@ -122,12 +346,12 @@ pub async fn refresh_dashboard_briefing(
 
                                 entries.sort_by(|a, b| b.1.cmp(&a.1));
                                 if !entries.is_empty() {
-                                    println!("DEBUG: Found {} recently modified Obsidian files:", entries.len());
+                                    tracing::debug!("Found {} recently modified Obsidian files:", entries.len());
                                 }
                                 for (entry, modified) in entries.into_iter().take(4) {
                                     if let Ok(content) = fs::read_to_string(entry.path()) {
                                         let file_name = entry.file_name().to_string_lossy();
-                                        println!("  - [PICK] {}", file_name);
+                                        tracing::debug!("  - [PICK] {}", file_name);
                                         // Truncate content safely to avoid char boundary panics
                                         let snippet = if content.chars().count() > 500 { 
                                             format!("{}...", content.chars().take(500).collect::<String>()) 
@@ -144,16 +368,19 @@ pub async fn refresh_dashboard_briefing(
             }
             let notes_str = if notes.is_empty() { "No recent Obsidian daily notes found.".to_string() } else { notes.join("\n\n") };
             let recent_str = if recent_files.is_empty() { "No other recently modified files found.".to_string() } else { recent_files.join("\n\n") };
-            format!("OBSIDIAN DAILY NOTES:\n{}\n\nOTHER RECENTLY MODIFIED FILES (Last 7 Days):\n{}", notes_str, recent_str)
+            Some(format!("OBSIDIAN DAILY NOTES:\n{}\n\nOTHER RECENTLY MODIFIED FILES (Last 7 Days):\n{}", notes_str, recent_str))
         }
     };
 
     let email_future = {
-        let db = database.inner().clone();
+        let db = database.clone();
         async move {
+            if !sources.email {
+                return None;
+            }
             let mut important_emails = Vec::new();
             if let Ok(has_google) = {
-                let connection = db.connection.lock();
+                let connection = db.connection.lock().ok()?;
                 queries::has_api_token(&connection, "google")
             } {
                 if has_google {
@@ -162,40 +389,43 @@ pub async fn refresh_dashboard_briefing(
                     
                     if let Ok(emails) = crate::integrations::google_gmail::fetch_recent_emails_with_query(&db, 10, Some(&query)).await {
                         if !emails.is_empty() {
-                            println!("DEBUG: Found {} primary emails in last 24h (heuristic filter)", emails.len());
+                            tracing::debug!("Found {} primary emails in last 24h (heuristic filter)", emails.len());
                             // Heuristic: Gmail's category:primary already filters promos/social.
                             // Take the top 5 most recent — no LLM call needed.
                             important_emails = emails.into_iter().take(5).collect();
                             for e in &important_emails {
-                                println!("  - [KEEP] Subject: {}", e.subject.as_deref().unwrap_or("(No Subject)"));
+                                tracing::debug!("  - [KEEP] Subject: {}", e.subject.as_deref().unwrap_or("(No Subject)"));
                             }
                         }
                     }
                 }
             }
-            important_emails
+            Some(important_emails)
         }
     };
 
     let calendar_future = {
-        let db = database.inner().clone();
+        let db = database.clone();
         async move {
+            if !sources.calendar {
+                return None;
+            }
             let mut google_calendar_data = Vec::new();
             if let Ok(has_google) = {
-                let connection = db.connection.lock();
+                let connection = db.connection.lock().ok()?;
                 queries::has_api_token(&connection, "google")
             } {
                 if has_google {
                     let start_of_search = (Local::now() - Duration::days(3)).format("%Y-%m-%dT00:00:00Z").to_string();
                     let end_of_search = (Local::now() + Duration::days(3)).format("%Y-%m-%dT23:59:59Z").to_string();
 
-                    if let Ok(events) = crate::integrations::google_calendar::fetch_google_calendar_events(&db, &start_of_search, &end_of_search).await {
+                    if let Ok(events) = crate::integrations::google_calendar::fetch_google_calendar_events(&db, &start_of_search, &end_of_search, None).await {
                         if !events.is_empty() {
-                            println!("DEBUG: Found {} calendar events in 7-day window (3 back, 3 forward):", events.len());
+                            tracing::debug!("Found {} calendar events in 7-day window (3 back, 3 forward):", events.len());
                         }
                         let e_str = events.iter().map(|e| {
                             let title = e.summary.as_deref().unwrap_or("(No Title)");
-                            println!("  - [KEEP] {}", title);
+                            tracing::debug!("  - [KEEP] {}", title);
                             let start_str = e.start.date_time.as_deref().or(e.start.date.as_deref()).unwrap_or("unknown");
                             // Try to parse for better labeling if possible, otherwise raw
                             format!("- {} (starts at {})", title, start_str)
@@ -204,12 +434,15 @@ pub async fn refresh_dashboard_briefing(
                     }
                 }
             }
-            google_calendar_data.join("\n\n")
+            Some(google_calendar_data.join("\n\n"))
         }
     };
 
     let weather_future = async {
-        match crate::gemini::tools::fetch_weather("Lagos").await {
+        if !sources.weather {
+            return None;
+        }
+        Some(match crate::gemini::tools::fetch_weather(database, "Lagos").await {
             serde_json::Value::Object(map) => {
                 format!("Weather in {}: {}°C, {}", 
                     map.get("location").and_then(|v| v.as_str()).unwrap_or("Lagos"),
@@ -218,14 +451,18 @@ pub async fn refresh_dashboard_briefing(
                 )
             },
             _ => "Weather data unavailable.".to_string()
-        }
+        })
     };
 
-    // Run all fetches in parallel
-    let (obsidian_data, important_emails, google_calendar_data, weather_data) = tokio::join!(obsidian_future, email_future, calendar_future, weather_future);
+    // Run all fetches in parallel (the hash always covers every source, so it matches get_dashboard_briefing)
+    let (obsidian_data, important_emails, google_calendar_data, weather_data, data_hash) = tokio::join!(obsidian_future, email_future, calendar_future, weather_future, calculate_briefing_hash(database));
+    let data_hash = data_hash.unwrap_or_else(|e| {
+        tracing::warn!("⚠️ Dashboard: Could not hash briefing data: {:#}", e);
+        String::new()
+    });
 
     // 3. Construct Final Prompt and Generate Briefing
-    let email_final = if important_emails.is_empty() { "No critical emails found." .to_string() } else {
+    let email_final = important_emails.map(|important_emails| if important_emails.is_empty() { "No critical emails found." .to_string() } else {
         important_emails.iter().map(|m| {
             let snippet = if m.snippet.chars().count() > 200 { 
                 format!("{}...", m.snippet.chars().take(200).collect::<String>()) 
@@ -239,24 +476,32 @@ pub async fn refresh_dashboard_briefing(
                 snippet
             )
         }).collect::<Vec<_>>().join("\n")
-    };
+    });
     
-    let calendar_final = if google_calendar_data.is_empty() { "No upcoming calendar events." .to_string() } else { google_calendar_data };
+    let calendar_final = google_calendar_data.map(|data| if data.is_empty() { "No upcoming calendar events." .to_string() } else { data });
 
     let now = Local::now();
     let current_time_str = now.format("%A, %B %d, %Y at %I:%M %p").to_string();
 
-    let raw_data_context = format!(
-        "CURRENT TIME: {}\n\nWEATHER:\n{}\n\nOBSIDIAN DATA:\n{}\n\nIMPORTANT EMAILS (Last 24h):\n{}\n\nCALENDAR (7-Day Window):\n{}",
-        current_time_str, weather_data, obsidian_data, email_final, calendar_final
-    );
+    //NOTE: Skipped sources are left out entirely rather than reported as empty
+    let mut raw_data_context = format!("CURRENT TIME: {}", current_time_str);
+    for (label, data) in [
+        ("WEATHER", weather_data),
+        ("OBSIDIAN DATA", obsidian_data),
+        ("IMPORTANT EMAILS (Last 24h)", email_final),
+        ("CALENDAR (7-Day Window)", calendar_final),
+    ] {
+        if let Some(data) = data {
+            raw_data_context.push_str(&format!("\n\n{}:\n{}", label, data));
+        }
+    }
 
     // 2.5 Long-term Memory Retrieval & DailySummary Context
     let mut memory_context = String::new();
     {
         // A. Inject last 7 DailySummaries for weekly continuity
         let summaries = {
-            let connection = database.connection.lock();
+            let connection = database.connection.lock()?;
             crate::memory::core::get_recent_daily_summaries(&connection, 7).unwrap_or_default()
         };
 
@@ -269,13 +514,19 @@ pub async fn refresh_dashboard_briefing(
         }
 
         // B. Semantic Retrieval — use a lightweight query instead of embedding the entire context
-        let memory_query = format!(
-            "Daily briefing for {} on {}. Weather, calendar events, emails, notes, tasks.",
-            greeting_name, current_time_str
-        );
-        let memory_client = GeminiClient::new(api_key.clone());
-        if let Ok(situation_embedding) = memory_client.generate_embedding(&memory_query).await {
-            let connection = database.connection.lock();
+        let memory_query = match focus {
+            Some(focus) => format!("Briefing for {} on {}. Focus: {}", greeting_name, current_time_str, focus),
+            None => format!(
+                "Daily briefing for {} on {}. Weather, calendar events, emails, notes, tasks.",
+                greeting_name, current_time_str
+            ),
+        };
+        let situation_embedding = match &gemini_key {
+            Some(key) => GeminiClient::new(key.clone()).generate_embedding(&memory_query).await.ok(),
+            None => None,
+        };
+        if let Some(situation_embedding) = situation_embedding {
+            let connection = database.connection.lock()?;
             if let Ok(memories) = crate::memory::core::retrieve_memories(&connection, &situation_embedding, 15) {
                 if !memories.is_empty() {
                     memory_context.push_str(&crate::memory::core::format_memories_for_prompt(&memories));
@@ -288,32 +539,41 @@ pub async fn refresh_dashboard_briefing(
         }
     }
 
-    let system_instruction = crate::gemini::prompt::get_briefing_system_instruction(&greeting_name);
+    let system_instruction = crate::gemini::prompt::get_briefing_system_instruction(&greeting_name, working_hours);
+    let task = match focus {
+        Some(focus) => format!(
+            "Generate a briefing focused on what the user asked for: \"{}\". Only cover what is relevant to that focus, using the data and memories above. Keep it shorter than a full daily briefing.",
+            focus
+        ),
+        None => "Generate a comprehensive briefing. You MUST synthesize and mention the important emails and calendar events alongside your notes and memories. Do not ignore the financial or deployment alerts if they are present.".to_string(),
+    };
     let final_prompt = format!(
-        "It is {}.\n\nRAW DATA CONTEXT:\n{}\n{}\n\nTASK:\nGenerate a comprehensive briefing. You MUST synthesize and mention the important emails and calendar events alongside your notes and memories. Do not ignore the financial or deployment alerts if they are present.", 
+        "It is {}.\n\nRAW DATA CONTEXT:\n{}\n{}\n\nTASK:\n{}", 
         current_time_str, 
         raw_data_context,
-        memory_context
+        memory_context,
+        task
     );
 
-    let chat_response = gemini_client
-        .send_chat(
+    let chat_response = provider
+        .chat(
             vec![GeminiContent {
                 role: Some("user".to_string()),
                 parts: vec![GeminiPart::text(final_prompt)],
             }],
             Some(&system_instruction),
-            None,            Some(GenerationConfig {
+            Some(GenerationConfig {
                 response_mime_type: None,
                 response_schema: None,
             }),
         )
         .await
-        .map_err(|e| e.to_string())?;
+        .command_context("Failed to generate briefing")?;
 
     if let Some(usage) = &chat_response.usage {
-        println!("DEBUG: Final Briefing Token Usage -> Prompt: {}, Candidates: {}, Total: {}", usage.prompt_token_count, usage.candidates_token_count, usage.total_token_count);
+        tracing::debug!("Final Briefing Token Usage -> Prompt: {}, Candidates: {}, Total: {}", usage.prompt_token_count, usage.candidates_token_count, usage.total_token_count);
     }
+    record_usage(database, FEATURE_BRIEFING, chat_response.usage.as_ref());
     
     let briefing_text = chat_response.parts
         .iter()
@@ -324,13 +584,26 @@ pub async fn refresh_dashboard_briefing(
         .trim()
         .to_string();
 
-    // 4. Async TTS
-    let db_for_audio = database.inner().clone();
+    //NOTE: Focused briefings are one-off answers - they aren't saved, voiced or bucketed, so they
+    //      never come back as the dashboard's briefing or as a day's final briefing
+    if focus.is_some() {
+        return Ok(DashboardBriefing {
+            content: briefing_text,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            is_stale: false,
+            audio_data: None,
+        });
+    }
+
+    // 4. Async TTS (skipped in offline mode by generate_audio)
+    let db_for_audio = database.clone();
     let text_for_audio = briefing_text.clone(); 
     
     tauri::async_runtime::spawn(async move {
         if let Ok(audio_data) = crate::integrations::gemini_tts::generate_audio(&db_for_audio, &text_for_audio).await {
-            let connection = db_for_audio.connection.lock();
+            let Ok(connection) = db_for_audio.connection.lock() else {
+                return;
+            };
             let _ = connection.execute(
                 "UPDATE briefing_summaries SET audio_data = ?1 WHERE id = (SELECT MAX(id) FROM briefing_summaries)",
                 params![audio_data],
@@ -342,15 +615,15 @@ pub async fn refresh_dashboard_briefing(
 
     // 5. Save to DB (Legacy Briefing & Memory Buckets)
     {
-        let connection = database.connection.lock();
-        queries::save_briefing_summary(&connection, &briefing_text, "power-up", None)
-            .map_err(|e| e.to_string())?;
+        let connection = database.connection.lock()?;
+        queries::save_briefing_summary(&connection, &briefing_text, &data_hash, None)
+            .command_context("Failed to save briefing")?;
 
         // 🧠 Store in time-bucket for DailySummary synthesis
         let date_str = Local::now().format("%Y-%m-%d").to_string();
         let bucket = crate::memory::core::get_current_bucket();
         let _ = crate::memory::core::upsert_briefing_bucket(&connection, &date_str, bucket, &briefing_text);
-        println!("DEBUG: 🧠 SUCCESS: Briefing stored in time-bucket: '{}' for date: {}", bucket, date_str);
+        tracing::debug!("🧠 SUCCESS: Briefing stored in time-bucket: '{}' for date: {}", bucket, date_str);
 
         // 🧠 Automatic DailySummary Synthesis
         // Check if yesterday's summary exists. If not, synthesize from yesterday's buckets.
@@ -360,15 +633,15 @@ pub async fn refresh_dashboard_briefing(
             yesterday_has_summary = summaries.iter().any(|s| s.created_at.format("%Y-%m-%d").to_string() == yesterday);
         }
 
-        if !yesterday_has_summary {
+        //NOTE: The summary is stored with a Gemini embedding, so synthesis needs a Gemini key
+        if let Some(api_key_summary) = gemini_key.clone().filter(|_| !yesterday_has_summary) {
             if let Ok(buckets) = crate::memory::core::get_briefing_buckets_for_date(&connection, &yesterday) {
                 if !buckets.is_empty() {
-                    println!("DEBUG: 🧠 Yesterday's DailySummary missing. Synthesizing from {} buckets...", buckets.len());
+                    tracing::debug!("🧠 Yesterday's DailySummary missing. Synthesizing from {} buckets...", buckets.len());
                     let synthesis_prompt = crate::memory::reflection::build_daily_summary_prompt(&buckets, &greeting_name);
                     
-                    let db_clone = database.inner().clone();
-                    let api_key_summary = api_key.clone();
-                    
+                    let db_clone = database.clone();
+
                     tokio::spawn(async move {
                         let client = GeminiClient::new(api_key_summary);
                         let synthesis_result = client.send_chat(
@@ -401,9 +674,10 @@ pub async fn refresh_dashboard_briefing(
 
                                 if let Ok(emb) = client.generate_embedding(&memory.content).await {
                                     memory.embedding = Some(emb);
-                                    let conn = db_clone.connection.lock();
-                                    let _ = crate::memory::core::store_memory(&conn, &memory);
-                                    println!("DEBUG: 🧠 Yesterday's DailySummary synthesized and stored! ✅");
+                                    if let Ok(conn) = db_clone.connection.lock() {
+                                        let _ = crate::memory::core::store_memory(&conn, &memory);
+                                    }
+                                    tracing::debug!("🧠 Yesterday's DailySummary synthesized and stored! ✅");
                                 }
                             }
                         }
@@ -415,8 +689,25 @@ pub async fn refresh_dashboard_briefing(
 
     Ok(DashboardBriefing {
         content: briefing_text,
-        created_at: Local::now().to_rfc3339(),
+        created_at: chrono::Utc::now().to_rfc3339(),
         is_stale: false,
         audio_data: None,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_focus_picks_relevant_sources() {
+        let calendar_only = BriefingSources::for_focus("Just my calendar today");
+        assert!(calendar_only.calendar);
+        assert!(!calendar_only.email && !calendar_only.weather && !calendar_only.obsidian);
+
+        let deadlines = BriefingSources::for_focus("focus on my deadlines");
+        assert!(deadlines.calendar && deadlines.obsidian);
+
+        assert_eq!(BriefingSources::for_focus("anything fun?"), BriefingSources::ALL);
+    }
+}
@@ -0,0 +1,174 @@
+//INFO: lumen:// deep links
+//NOTE: Briefings and chat replies link files as `lumen://open?path=...`; `lumen://chat?message=...`
+//      opens the overlay with the message pre-filled. Links can come from any app (or web page), so a
+//      link only ever asks: files are opened after the user confirms in the overlay, and chat messages
+//      still have to be sent by hand.
+
+use crate::database::{queries, Database};
+use crate::integrations::obsidian::ObsidianConfig;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager};
+use url::Url;
+
+//INFO: Document types a link may open; anything else (scripts, installers, shortcuts...) is refused
+const OPENABLE_EXTENSIONS: &[&str] = &[
+    "md", "txt", "pdf", "png", "jpg", "jpeg", "gif", "webp", "svg", "csv", "docx", "xlsx", "pptx",
+    "odt", "ods", "odp", "rtf", "epub",
+];
+
+//INFO: A parsed lumen:// link
+#[derive(Debug, PartialEq)]
+pub enum LumenLink {
+    Open { path: PathBuf },
+    Chat { message: String },
+}
+
+//INFO: Parses a lumen:// URL (angle brackets from markdown are tolerated)
+pub fn parse_lumen_link(raw: &str) -> Result<LumenLink, String> {
+    let raw = raw.trim().trim_start_matches('<').trim_end_matches('>');
+    let url = Url::parse(raw).map_err(|e| format!("Invalid link '{}': {}", raw, e))?;
+    if url.scheme() != "lumen" {
+        return Err(format!("Not a lumen:// link: {}", raw));
+    }
+
+    //NOTE: query_pairs() percent-decodes, so `Plan%20B.md` comes back as `Plan B.md`
+    let param = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+            .filter(|value| !value.trim().is_empty())
+    };
+
+    match url.host_str() {
+        Some("open") => {
+            let path = param("path").ok_or("lumen://open needs a path")?;
+            Ok(LumenLink::Open { path: PathBuf::from(path) })
+        }
+        Some("chat") => Ok(LumenLink::Chat {
+            message: param("message").unwrap_or_default(),
+        }),
+        other => Err(format!("Unknown lumen:// action: {}", other.unwrap_or(""))),
+    }
+}
+
+//INFO: Carries out a lumen:// link
+pub async fn handle_lumen_link(app: &AppHandle, raw: &str) -> Result<(), String> {
+    match parse_lumen_link(raw)? {
+        LumenLink::Open { path } => {
+            let path = check_openable(&path, vault_path(app).as_deref())?;
+            //NOTE: Nothing is opened yet - the overlay asks first and calls `confirm_open_link`
+            crate::commands::window::show_overlay(app.clone()).await?;
+            let _ = app.emit("overlay-confirm-open", path.to_string_lossy().into_owned());
+            Ok(())
+        }
+        LumenLink::Chat { message } => {
+            crate::commands::window::show_overlay(app.clone()).await?;
+            //NOTE: Only pre-fills the input; the user still has to press send
+            let _ = app.emit("overlay-prefill", message);
+            Ok(())
+        }
+    }
+}
+
+//INFO: Opens a lumen:// link clicked in the app (briefings, chat)
+#[tauri::command]
+pub async fn open_lumen_link(app: AppHandle, url: String) -> Result<(), String> {
+    handle_lumen_link(&app, &url).await
+}
+
+//INFO: Opens a file from a lumen://open link once the user has confirmed it in the overlay
+#[tauri::command]
+pub async fn confirm_open_link(app: AppHandle, path: String) -> Result<(), String> {
+    let path = check_openable(Path::new(&path), vault_path(&app).as_deref())?;
+    tracing::info!("🔗 Deep link: Opening {}", path.display());
+    crate::commands::window::open_path(app, path.to_string_lossy().into_owned()).await
+}
+
+//INFO: The enabled Obsidian vault, if any
+fn vault_path(app: &AppHandle) -> Option<PathBuf> {
+    let database = app.state::<Database>();
+    let connection = database.connection.lock().ok()?;
+    queries::get_integration(&connection, "obsidian")
+        .ok()
+        .flatten()
+        .filter(|i| i.enabled)
+        .and_then(|i| i.config)
+        .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+        .and_then(|c| ObsidianConfig::from_json(&c))
+        .map(|config| config.vault_path)
+}
+
+//INFO: Resolves a link target, allowing only folders inside the vault and non-executable documents
+//NOTE: Canonicalizing first means symlinks and `..` can't point the check at one file and the opener at another
+fn check_openable(path: &Path, vault: Option<&Path>) -> Result<PathBuf, String> {
+    let path = path
+        .canonicalize()
+        .map_err(|_| format!("{} doesn't exist", path.display()))?;
+    let metadata = std::fs::metadata(&path).map_err(|_| format!("{} doesn't exist", path.display()))?;
+
+    if metadata.is_dir() {
+        let in_vault = vault
+            .and_then(|v| v.canonicalize().ok())
+            .is_some_and(|v| path.starts_with(v));
+        if !in_vault {
+            return Err(format!("Refusing to open folder {} outside the vault", path.display()));
+        }
+        return Ok(path);
+    }
+
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if !OPENABLE_EXTENSIONS.contains(&extension.as_str()) {
+        return Err(format!("Refusing to open {} from a link", path.display()));
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 != 0 {
+            return Err(format!("Refusing to open executable {} from a link", path.display()));
+        }
+    }
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_lumen_link() {
+        assert_eq!(
+            parse_lumen_link("<lumen://open?path=/home/me/vault/Plan%20B.md>"),
+            Ok(LumenLink::Open { path: PathBuf::from("/home/me/vault/Plan B.md") })
+        );
+        assert_eq!(
+            parse_lumen_link("lumen://chat?message=what%27s+on+today"),
+            Ok(LumenLink::Chat { message: "what's on today".to_string() })
+        );
+        assert!(parse_lumen_link("lumen://open").is_err());
+        assert!(parse_lumen_link("lumen://delete?path=/tmp").is_err());
+        assert!(parse_lumen_link("https://open?path=/tmp").is_err());
+    }
+
+    #[test]
+    fn test_check_openable_allows_documents_and_vault_folders_only() {
+        let dir = std::env::temp_dir().join(format!("lumen-deep-link-{}", std::process::id()));
+        let vault = dir.join("vault");
+        std::fs::create_dir_all(vault.join("Projects")).unwrap();
+        std::fs::write(vault.join("Plan.md"), "plan").unwrap();
+        std::fs::write(dir.join("run.hta"), "x").unwrap();
+
+        assert!(check_openable(&vault.join("Plan.md"), Some(&vault)).is_ok());
+        assert!(check_openable(&vault.join("Projects/../Plan.md"), None).is_ok());
+        assert!(check_openable(&vault.join("Projects"), Some(&vault)).is_ok());
+        assert!(check_openable(&dir, Some(&vault)).is_err());
+        assert!(check_openable(&dir.join("run.hta"), Some(&vault)).is_err());
+        assert!(check_openable(&dir.join("missing.md"), Some(&vault)).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
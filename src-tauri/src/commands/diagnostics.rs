@@ -0,0 +1,226 @@
+//INFO: Diagnostics commands for Lumen
+//NOTE: A self-test that tells the user (and support) which part of Lumen needs attention
+
+use crate::agent::clipboard::clipboard_listener_alive;
+use crate::commands::vision::capture_screen_image;
+use crate::crypto::recovery::check_credentials;
+use crate::crypto::{decrypt_token, encrypt_token, get_or_create_encryption_key};
+use crate::database::queries::{get_api_token, get_integration, save_setting};
+use crate::database::Database;
+use crate::gemini::GeminiClient;
+use crate::integrations::google_gmail::verify_google_connection;
+use serde::Serialize;
+use tauri::State;
+
+//INFO: Result of a single check
+//NOTE: Status is "pass", "fail" or "skipped" (skipped = not set up, so nothing to test)
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticCheck {
+    pub name: String,
+    pub status: String,
+    pub detail: String,
+}
+
+impl DiagnosticCheck {
+    fn pass(name: &str, detail: impl Into<String>) -> Self {
+        Self::new(name, "pass", detail)
+    }
+
+    fn fail(name: &str, detail: impl Into<String>) -> Self {
+        Self::new(name, "fail", detail)
+    }
+
+    fn skipped(name: &str, detail: impl Into<String>) -> Self {
+        Self::new(name, "skipped", detail)
+    }
+
+    fn new(name: &str, status: &str, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: status.to_string(),
+            detail: detail.into(),
+        }
+    }
+}
+
+//INFO: Full self-test report; `ready` is false if any check failed
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticsReport {
+    pub ready: bool,
+    pub checks: Vec<DiagnosticCheck>,
+    pub ran_at: String,
+}
+
+//INFO: Writes a throwaway setting inside a transaction that is rolled back
+fn check_database(database: &Database) -> DiagnosticCheck {
+    let connection = match database.connection.lock() {
+        Ok(connection) => connection,
+        Err(e) => return DiagnosticCheck::fail("database", format!("Database is not reachable: {:#}", e)),
+    };
+    let result = connection
+        .unchecked_transaction()
+        .map_err(anyhow::Error::from)
+        .and_then(|tx| {
+            save_setting(&tx, "diagnostics_probe", "ok")?;
+            tx.rollback()?;
+            Ok(())
+        });
+    match result {
+        Ok(()) => DiagnosticCheck::pass("database", "Database is writable"),
+        Err(e) => DiagnosticCheck::fail("database", format!("Database is not writable: {:#}", e)),
+    }
+}
+
+//INFO: Loads the key and round-trips a sample value
+fn check_encryption() -> DiagnosticCheck {
+    if let Err(e) = get_or_create_encryption_key() {
+        return DiagnosticCheck::fail("encryption", format!("Failed to load encryption key: {:#}", e));
+    }
+    let sample = "lumen-diagnostics";
+    match encrypt_token(sample).and_then(|encrypted| decrypt_token(&encrypted)) {
+        Ok(decrypted) if decrypted == sample => {
+            DiagnosticCheck::pass("encryption", "Encryption key loaded and round-trip succeeded")
+        }
+        Ok(_) => DiagnosticCheck::fail("encryption", "Decrypted value did not match the original"),
+        Err(e) => DiagnosticCheck::fail("encryption", format!("Encryption round-trip failed: {:#}", e)),
+    }
+}
+
+//INFO: Decrypts every stored token, a failure means the key was lost or replaced
+fn check_stored_credentials(database: &Database) -> DiagnosticCheck {
+    let health = database.connection.lock().and_then(|connection| check_credentials(&connection));
+    match health {
+        Err(e) => DiagnosticCheck::fail("credentials", format!("Failed to read credentials: {:#}", e)),
+        Ok(health) if health.total == 0 => DiagnosticCheck::skipped("credentials", "No credentials saved"),
+        Ok(health) if health.is_healthy() => DiagnosticCheck::pass(
+            "credentials",
+            format!("{} stored credentials can be decrypted", health.total),
+        ),
+        Ok(health) => DiagnosticCheck::fail(
+            "credentials",
+            format!(
+                "Can't decrypt credentials for {} - the encryption key is missing or corrupted, reset and re-enter them",
+                health.unreadable.join(", ")
+            ),
+        ),
+    }
+}
+
+async fn check_gemini(database: &Database) -> DiagnosticCheck {
+    let key = {
+        let connection = match database.connection.lock() {
+            Ok(connection) => connection,
+            Err(e) => return DiagnosticCheck::fail("gemini", format!("Failed to read API key: {:#}", e)),
+        };
+        if crate::offline::is_offline(&connection) {
+            return DiagnosticCheck::skipped("gemini", "Offline mode is on");
+        }
+        get_api_token(&connection, "gemini")
+    };
+    match key {
+        Err(e) => DiagnosticCheck::fail("gemini", format!("Failed to read API key: {:#}", e)),
+        Ok(None) => DiagnosticCheck::fail("gemini", "No Gemini API key saved"),
+        Ok(Some(encrypted)) => match decrypt_token(&encrypted) {
+            Err(e) => DiagnosticCheck::fail("gemini", format!("Failed to decrypt API key: {:#}", e)),
+            Ok(key) => match GeminiClient::new(key).test_connection().await {
+                Ok(true) => DiagnosticCheck::pass("gemini", "API key is valid"),
+                Ok(false) => DiagnosticCheck::fail("gemini", "Gemini rejected the request"),
+                Err(e) => DiagnosticCheck::fail("gemini", e.to_string()),
+            },
+        },
+    }
+}
+
+//NOTE: verify_google_connection refreshes an expired token, so a pass means the refresh token works too
+async fn check_google(database: &Database) -> DiagnosticCheck {
+    let integration = {
+        let connection = match database.connection.lock() {
+            Ok(connection) => connection,
+            Err(e) => return DiagnosticCheck::fail("google", format!("Failed to read integration: {:#}", e)),
+        };
+        if crate::offline::is_offline(&connection) {
+            return DiagnosticCheck::skipped("google", "Offline mode is on");
+        }
+        get_integration(&connection, "google")
+    };
+    match integration {
+        Err(e) => DiagnosticCheck::fail("google", format!("Failed to read integration: {:#}", e)),
+        Ok(None) => DiagnosticCheck::skipped("google", "Google is not set up"),
+        Ok(Some(integration)) if !integration.enabled => {
+            DiagnosticCheck::skipped("google", "Google is disabled")
+        }
+        Ok(Some(_)) => match verify_google_connection(database).await {
+            Ok(email) => DiagnosticCheck::pass("google", format!("Signed in as {}", email)),
+            Err(e) => DiagnosticCheck::fail("google", format!("Token could not be refreshed: {:#}", e)),
+        },
+    }
+}
+
+async fn check_screenshot() -> DiagnosticCheck {
+    let result = tauri::async_runtime::spawn_blocking(|| capture_screen_image(None)).await;
+    match result {
+        Ok(Ok(image)) => DiagnosticCheck::pass(
+            "screenshot",
+            format!("Captured {}x{} screen", image.width(), image.height()),
+        ),
+        Ok(Err(e)) => DiagnosticCheck::fail("screenshot", format!("Screen capture failed: {}", e)),
+        Err(e) => DiagnosticCheck::fail("screenshot", format!("Screen capture panicked: {}", e)),
+    }
+}
+
+fn check_clipboard() -> DiagnosticCheck {
+    if clipboard_listener_alive() {
+        DiagnosticCheck::pass("clipboard", "Clipboard listener is running")
+    } else {
+        DiagnosticCheck::fail("clipboard", "Clipboard listener is not running")
+    }
+}
+
+//INFO: Checks that stay on this machine (no screenshot, no network calls)
+fn local_checks(database: &Database) -> Vec<DiagnosticCheck> {
+    vec![
+        check_database(database),
+        check_encryption(),
+        check_stored_credentials(database),
+        check_clipboard(),
+    ]
+}
+
+fn build_report(checks: Vec<DiagnosticCheck>) -> DiagnosticsReport {
+    DiagnosticsReport {
+        ready: checks.iter().all(|c| c.status != "fail"),
+        checks,
+        ran_at: chrono::Utc::now().to_rfc3339(),
+    }
+}
+
+//INFO: Runs every check in order and builds the report
+pub async fn run_all(database: &Database) -> DiagnosticsReport {
+    let mut checks = local_checks(database);
+    checks.push(check_gemini(database).await);
+    checks.push(check_google(database).await);
+    checks.push(check_screenshot().await);
+    build_report(checks)
+}
+
+//INFO: Runs the local checks at startup and logs failures
+//NOTE: The screenshot and the Gemini/Google probes only run from the on-demand run_diagnostics
+//      command, so launching Lumen never captures the screen or calls out to the network
+pub async fn run_startup_diagnostics(database: Database) {
+    //NOTE: Give the clipboard listener thread a moment to start
+    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+
+    let report = build_report(local_checks(&database));
+    for check in report.checks.iter().filter(|c| c.status == "fail") {
+        tracing::warn!("🩺 Diagnostics: {} failed: {}", check.name, check.detail);
+    }
+    if report.ready {
+        tracing::info!("🩺 Diagnostics: Local checks passed");
+    }
+}
+
+//INFO: Runs the health/readiness self-test on demand
+#[tauri::command]
+pub async fn run_diagnostics(database: State<'_, Database>) -> Result<DiagnosticsReport, String> {
+    Ok(run_all(&database).await)
+}
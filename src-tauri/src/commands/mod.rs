@@ -4,7 +4,13 @@
 pub mod auth;
 pub mod calendar;
 pub mod chat;
+pub mod clipboard;
 pub mod dashboard;
+pub mod deep_link;
+pub mod diagnostics;
+pub mod notes;
+pub mod proactive;
+pub mod reminders;
 pub mod settings;
 pub mod setup;
 pub mod vision;
@@ -0,0 +1,89 @@
+//INFO: Notes commands for Lumen
+//NOTE: Maintains the embeddings index over the Obsidian vault used by semantic note search
+
+use crate::crypto::decrypt_token;
+use crate::database::queries::get_api_token;
+use crate::database::Database;
+use crate::error::{CommandContext, LumenError};
+use crate::gemini::GeminiClient;
+use crate::integrations::obsidian::ObsidianConfig;
+use crate::integrations::vault_index::reindex_vault;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+//INFO: Tracks the running vault index so it can be cancelled and isn't started twice
+#[derive(Default)]
+pub struct VaultIndexer {
+    cancel: parking_lot::Mutex<Option<Arc<AtomicBool>>>,
+}
+
+//INFO: Loads the vault config and a Gemini client for indexing
+pub fn index_prerequisites(database: &Database) -> Result<(ObsidianConfig, GeminiClient), LumenError> {
+    let connection = database.connection.lock()?;
+    let config = ObsidianConfig::from_database(&connection)
+        .command_context("Failed to get integration")?
+        .ok_or(LumenError::IntegrationDisabled("Obsidian".to_string()))?;
+    crate::offline::ensure_online(&connection, "Note embeddings").map_err(LumenError::classify)?;
+    let encrypted_key = get_api_token(&connection, "gemini")
+        .command_context("Failed to get API key")?
+        .ok_or(LumenError::MissingApiKey)?;
+    let api_key = decrypt_token(&encrypted_key).command_context("Failed to decrypt API key")?;
+    Ok((config, GeminiClient::new(api_key)))
+}
+
+//INFO: Starts indexing the vault in the background, only re-embedding notes that changed since the last run
+//NOTE: Emits `vault-index-progress` ({ done, total, current_file }) per note and `vault-index-complete`
+//      with the stats (or { error }) at the end. Returns false if an index is already running.
+#[tauri::command]
+pub fn index_vault(
+    app: AppHandle,
+    database: State<Database>,
+    indexer: State<VaultIndexer>,
+) -> Result<bool, LumenError> {
+    let (config, client) = index_prerequisites(&database)?;
+
+    let cancel = {
+        let mut running = indexer.cancel.lock();
+        if running.is_some() {
+            return Ok(false);
+        }
+        let flag = Arc::new(AtomicBool::new(false));
+        *running = Some(flag.clone());
+        flag
+    };
+
+    let database = database.inner().clone();
+    tauri::async_runtime::spawn(async move {
+        let progress_app = app.clone();
+        let result = reindex_vault(&database, &config, &client, &cancel, |progress| {
+            let _ = progress_app.emit("vault-index-progress", progress);
+        })
+        .await;
+
+        *app.state::<VaultIndexer>().cancel.lock() = None;
+        match result {
+            Ok(stats) => {
+                let _ = app.emit("vault-index-complete", &stats);
+            }
+            Err(e) => {
+                tracing::error!("❌ Vault Index: {:#}", e);
+                let _ = app.emit("vault-index-complete", serde_json::json!({ "error": format!("{:#}", e) }));
+            }
+        }
+    });
+
+    Ok(true)
+}
+
+//INFO: Stops a running vault index after the note it's working on; false if none was running
+#[tauri::command]
+pub fn cancel_vault_index(indexer: State<VaultIndexer>) -> bool {
+    match indexer.cancel.lock().as_ref() {
+        Some(flag) => {
+            flag.store(true, Ordering::SeqCst);
+            true
+        }
+        None => false,
+    }
+}
@@ -0,0 +1,173 @@
+//INFO: Proactive agent commands for Lumen
+//NOTE: Lets the frontend configure which proactive notifications get through
+
+use crate::agent::digest::{DigestSettings, DIGEST_ENABLED_SETTING, DIGEST_TIME_SETTING};
+use crate::agent::focus::{self, FocusStatus};
+use crate::agent::proactive::{
+    gmail_message_url, DndSchedule, NotificationRules, DEFAULT_TRIAGE_INSTRUCTION, DND_END_SETTING,
+    DND_START_SETTING, NOTIFICATION_RULES_SETTING,
+};
+use crate::database::queries::{
+    delete_setting, get_notification_retention, prune_notifications as prune_notification_records,
+    save_setting,
+};
+use crate::database::Database;
+use tauri::{AppHandle, State};
+
+//INFO: Hosts a notification's link may point at, so a crafted payload can't open anything else
+const NOTIFICATION_LINK_HOSTS: &[&str] = &[
+    "https://mail.google.com/",
+    "https://calendar.google.com/",
+    "https://www.google.com/calendar/",
+];
+
+//INFO: Opens what a proactive notification was about: the email in Gmail or the event in Google Calendar
+//NOTE: Gmail links are rebuilt from the message id; calendar notifications carry the event's htmlLink
+//      and fall back to the calendar itself
+#[tauri::command]
+pub fn open_notification_item(
+    app: AppHandle,
+    database: State<Database>,
+    provider: String,
+    item_id: String,
+    url: Option<String>,
+) -> Result<(), String> {
+    use tauri_plugin_opener::OpenerExt;
+
+    let url = match provider.as_str() {
+        "gmail" => {
+            let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
+            gmail_message_url(&connection, &item_id)
+        }
+        "calendar" => url
+            .filter(|u| {
+                NOTIFICATION_LINK_HOSTS
+                    .iter()
+                    .any(|host| u.starts_with(host))
+            })
+            .unwrap_or_else(|| "https://calendar.google.com/calendar/r".to_string()),
+        other => return Err(format!("Notifications from {} have nothing to open", other)),
+    };
+    app.opener()
+        .open_url(url, None::<String>)
+        .map_err(|e| format!("Failed to open notification: {}", e))
+}
+
+//INFO: Gets the email notification rules (sender allow/block lists and triage instruction)
+#[tauri::command]
+pub fn get_notification_rules(database: State<Database>) -> Result<NotificationRules, String> {
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
+    let mut rules = NotificationRules::load(&connection);
+
+    //INFO: Show the built-in instruction so the user has something to edit
+    if rules.triage_instruction.is_none() {
+        rules.triage_instruction = Some(DEFAULT_TRIAGE_INSTRUCTION.to_string());
+    }
+    Ok(rules)
+}
+
+//INFO: Saves the email notification rules
+#[tauri::command]
+pub fn save_notification_rules(
+    database: State<Database>,
+    rules: NotificationRules,
+) -> Result<(), String> {
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
+    let json = serde_json::to_string(&rules)
+        .map_err(|e| format!("Failed to serialize notification rules: {}", e))?;
+
+    save_setting(&connection, NOTIFICATION_RULES_SETTING, &json)
+        .map_err(|e| format!("Failed to save notification rules: {}", e))
+}
+
+//INFO: Gets the Do Not Disturb schedule
+#[tauri::command]
+pub fn get_dnd_schedule(database: State<Database>) -> Result<DndSchedule, String> {
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
+    Ok(DndSchedule::load(&connection))
+}
+
+//INFO: Sets the Do Not Disturb schedule ("HH:MM" local time); pass nulls to turn it off
+#[tauri::command]
+pub fn set_dnd_schedule(database: State<Database>, schedule: DndSchedule) -> Result<(), String> {
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
+
+    let fields = [(DND_START_SETTING, &schedule.start), (DND_END_SETTING, &schedule.end)];
+
+    //INFO: Validate both times before saving either, so a bad input can't leave half a schedule
+    for (_, value) in fields {
+        if let Some(time) = value.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+            if DndSchedule::parse_time(time).is_none() {
+                return Err(format!("Invalid time '{}', expected HH:MM", time));
+            }
+        }
+    }
+
+    for (key, value) in fields {
+        match value.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+            Some(time) => save_setting(&connection, key, time),
+            None => delete_setting(&connection, key),
+        }
+        .map_err(|e| format!("Failed to save DND schedule: {}", e))?;
+    }
+    Ok(())
+}
+
+//INFO: Deletes notification records older than the retention window, returning how many were removed
+#[tauri::command]
+pub fn prune_notifications(database: State<Database>) -> Result<usize, String> {
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
+    let retention_days = get_notification_retention(&connection);
+    prune_notification_records(&connection, retention_days)
+        .map_err(|e| format!("Failed to prune notifications: {}", e))
+}
+
+//INFO: Mutes proactive notifications for `duration_minutes`; they're shown as one digest afterwards
+#[tauri::command]
+pub fn start_focus_mode(
+    app: AppHandle,
+    database: State<Database>,
+    duration_minutes: u32,
+) -> Result<FocusStatus, String> {
+    focus::start_focus(&app, &database, duration_minutes).map_err(|e| format!("Failed to start focus mode: {}", e))
+}
+
+//INFO: Ends focus mode early
+#[tauri::command]
+pub fn end_focus_mode(app: AppHandle, database: State<Database>) -> Result<(), String> {
+    focus::end_focus(&app, &database).map_err(|e| format!("Failed to end focus mode: {}", e))
+}
+
+//INFO: Whether focus mode is on and until when
+#[tauri::command]
+pub fn get_focus_mode(database: State<Database>) -> Result<FocusStatus, String> {
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
+    Ok(focus::focus_status(&connection))
+}
+
+//INFO: Gets the daily digest settings
+#[tauri::command]
+pub fn get_daily_digest(database: State<Database>) -> Result<DigestSettings, String> {
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
+    Ok(DigestSettings::load(&connection))
+}
+
+//INFO: Turns the daily digest on/off and sets its time ("HH:MM" local)
+#[tauri::command]
+pub fn set_daily_digest(database: State<Database>, settings: DigestSettings) -> Result<(), String> {
+    let time = settings.time.trim();
+    if DndSchedule::parse_time(time).is_none() {
+        return Err(format!("Invalid time '{}', expected HH:MM", time));
+    }
+
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
+    save_setting(&connection, DIGEST_TIME_SETTING, time)
+        .and_then(|_| {
+            save_setting(
+                &connection,
+                DIGEST_ENABLED_SETTING,
+                if settings.enabled { "true" } else { "false" },
+            )
+        })
+        .map_err(|e| format!("Failed to save daily digest settings: {}", e))
+}
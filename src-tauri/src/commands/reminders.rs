@@ -0,0 +1,147 @@
+//INFO: Reminder commands for Lumen
+//NOTE: Lets the frontend list and complete reminders created by the agent
+
+use crate::agent::reminders::parse_due_at;
+use crate::database::queries::{
+    self, complete_reminder as complete_reminder_query, get_pending_reminders, Reminder,
+};
+use crate::database::Database;
+use crate::error::{CommandContext, LumenError};
+use crate::integrations::{google_calendar, google_tasks};
+use chrono::Duration;
+use serde::Serialize;
+use tauri::State;
+
+//INFO: Length of the calendar block created for a promoted reminder when none is given
+const DEFAULT_EVENT_MINUTES: i64 = 30;
+
+//INFO: Longest calendar block a promoted reminder gets (one day)
+const MAX_EVENT_MINUTES: i64 = 24 * 60;
+
+//INFO: Where a reminder ended up after promotion
+#[derive(Debug, Serialize)]
+pub struct PromotedReminder {
+    pub reminder_id: i64,
+    pub linked_to: String,
+    pub linked_id: String,
+}
+
+//INFO: Lists all reminders that haven't been completed yet
+#[tauri::command]
+pub fn list_pending_reminders(database: State<Database>) -> Result<Vec<Reminder>, String> {
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
+
+    get_pending_reminders(&connection).map_err(|e| format!("Failed to list reminders: {}", e))
+}
+
+//INFO: Marks a reminder as completed
+#[tauri::command]
+pub fn complete_reminder(database: State<Database>, id: i64) -> Result<(), String> {
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
+
+    let updated = complete_reminder_query(&connection, id)
+        .map_err(|e| format!("Failed to complete reminder: {}", e))?;
+
+    if updated {
+        Ok(())
+    } else {
+        Err(format!("Reminder {} not found", id))
+    }
+}
+
+//INFO: Copies a reminder into Google Calendar (or Google Tasks) and links it
+//NOTE: Reminders without a due time can only become tasks; linked reminders are no longer notified locally
+pub async fn promote_reminder(
+    database: &Database,
+    reminder_id: i64,
+    as_task: bool,
+    duration_minutes: Option<i64>,
+) -> Result<PromotedReminder, LumenError> {
+    let reminder = {
+        let connection = database.connection.lock()?;
+
+        let google_enabled = queries::get_integration(&connection, "google")
+            .command_context("Failed to load Google integration")?
+            .map(|i| i.enabled)
+            .unwrap_or(false);
+        let has_token = queries::has_api_token(&connection, "google")
+            .command_context("Failed to check Google connection")?;
+        if !google_enabled || !has_token {
+            return Err(LumenError::IntegrationDisabled("Google".to_string()));
+        }
+
+        queries::get_reminder(&connection, reminder_id)
+            .command_context("Failed to load reminder")?
+            .ok_or_else(|| LumenError::Other(format!("Reminder {} not found", reminder_id)))?
+    };
+
+    if let Some(linked_to) = &reminder.linked_to {
+        return Err(LumenError::Other(format!(
+            "Reminder {} is already linked to {}",
+            reminder_id, linked_to
+        )));
+    }
+
+    let due = match reminder.due_at.as_deref() {
+        Some(raw) => Some(parse_due_at(raw).ok_or_else(|| {
+            LumenError::Other(format!("Reminder {} has an unreadable due time: {}", reminder_id, raw))
+        })?),
+        None => None,
+    };
+
+    let (linked_to, linked_id) = match due {
+        Some(start) if !as_task => {
+            let minutes = duration_minutes
+                .unwrap_or(DEFAULT_EVENT_MINUTES)
+                .clamp(1, MAX_EVENT_MINUTES);
+            let end = start + Duration::minutes(minutes);
+            let event = google_calendar::create_calendar_event(
+                database,
+                &reminder.content,
+                Some("Created from a Lumen reminder"),
+                &start.to_rfc3339(),
+                &end.to_rfc3339(),
+                None,
+                None,
+            )
+            .await
+            .command_context("Failed to create calendar event")?;
+            ("google_calendar", event.id)
+        }
+        None if !as_task => {
+            return Err(LumenError::Other(format!(
+                "Reminder {} has no due time, so it can't go on the calendar. Promote it as a task instead.",
+                reminder_id
+            )));
+        }
+        _ => {
+            let due = due.map(|d| d.to_rfc3339());
+            let task = google_tasks::create_task(database, &reminder.content, None, due.as_deref())
+                .await
+                .command_context("Failed to create Google task")?;
+            ("google_tasks", task.id)
+        }
+    };
+
+    let connection = database.connection.lock()?;
+    queries::link_reminder(&connection, reminder_id, linked_to, &linked_id)
+        .command_context("Failed to link reminder")?;
+    tracing::info!("🔗 Reminders: Promoted reminder #{} to {}", reminder_id, linked_to);
+
+    Ok(PromotedReminder {
+        reminder_id,
+        linked_to: linked_to.to_string(),
+        linked_id,
+    })
+}
+
+//INFO: Pushes a reminder to Google Calendar (or Tasks when `as_task` is set)
+#[tauri::command]
+pub async fn promote_reminder_to_event(
+    database: State<'_, Database>,
+    reminder_id: i64,
+    as_task: Option<bool>,
+    duration_minutes: Option<i64>,
+) -> Result<PromotedReminder, LumenError> {
+    promote_reminder(&database, reminder_id, as_task.unwrap_or(false), duration_minutes).await
+}
@@ -1,14 +1,20 @@
 //INFO: Settings commands for Lumen
 //NOTE: Handles reading and updating application settings
 
-use crate::crypto::{decrypt_token, encrypt_token};
+use crate::crypto::{decrypt_token, encrypt_token, hold_encryption_key};
 use crate::database::queries::{
-    get_all_integrations, get_api_token, get_hotkey_config, get_integration, get_setting,
-    get_user_profile, save_api_token, save_hotkey_config, save_integration, save_setting,
-    save_user_profile, HotkeyConfig, Integration,
+    clear_calendar_events, clear_integration_error, clear_notifications_for_provider,
+    delete_api_token, get_all_integrations, get_api_token, get_hotkey_config, get_integration,
+    get_setting, get_usage_stats as get_usage_stats_query, get_user_profile, save_api_token,
+    save_hotkey_config, save_integration, save_setting, save_user_profile, set_integration_error,
+    set_integration_status, HotkeyConfig, Integration, UsageStat, NEEDS_REAUTH_STATUS,
 };
 use crate::database::Database;
+use crate::error::{CommandContext, LumenError};
+use crate::gemini::GeminiClient;
+use crate::integrations::google_gmail::verify_google_connection;
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 use tauri::State;
 
 //INFO: User profile response structure
@@ -17,6 +23,9 @@ pub struct UserProfileResponse {
     pub display_name: String,
     pub location: Option<String>,
     pub theme: String,
+    pub timezone: Option<String>,
+    pub work_start: Option<String>,
+    pub work_end: Option<String>,
 }
 
 //INFO: Hotkey config response structure
@@ -38,12 +47,27 @@ pub struct ApiKeyStatusResponse {
     pub masked_key: Option<String>,
 }
 
+//INFO: Which chat provider is selected and how the OpenAI-compatible one is configured
+#[derive(Debug, Serialize)]
+pub struct LlmSettingsResponse {
+    pub provider: String,
+    pub providers: Vec<&'static str>,
+    pub openai_base_url: String,
+    pub openai_model: String,
+    pub openai_key_configured: bool,
+}
+
 //INFO: Request to update user profile
 #[derive(Debug, Deserialize)]
 pub struct UpdateProfileRequest {
     pub display_name: String,
     pub location: Option<String>,
     pub theme: String,
+    //INFO: IANA timezone from the browser (Intl), e.g. "Europe/Berlin"
+    pub timezone: Option<String>,
+    //INFO: Working hours as "HH:MM"; empty string clears them
+    pub work_start: Option<String>,
+    pub work_end: Option<String>,
 }
 
 //INFO: Request to update hotkey
@@ -71,7 +95,7 @@ pub struct UpdateApiKeyRequest {
 //INFO: Gets the current user profile
 #[tauri::command]
 pub fn get_profile(database: State<Database>) -> Result<Option<UserProfileResponse>, String> {
-    let connection = database.connection.lock();
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
 
     let profile =
         get_user_profile(&connection).map_err(|e| format!("Failed to get profile: {}", e))?;
@@ -80,6 +104,9 @@ pub fn get_profile(database: State<Database>) -> Result<Option<UserProfileRespon
         display_name: p.display_name,
         location: p.location,
         theme: p.theme,
+        timezone: p.timezone,
+        work_start: p.work_start,
+        work_end: p.work_end,
     }))
 }
 
@@ -89,13 +116,24 @@ pub fn update_profile(
     database: State<Database>,
     request: UpdateProfileRequest,
 ) -> Result<(), String> {
-    let connection = database.connection.lock();
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
+
+    for time in [&request.work_start, &request.work_end].into_iter().flatten() {
+        if !time.trim().is_empty()
+            && crate::agent::proactive::DndSchedule::parse_time(time).is_none()
+        {
+            return Err(format!("Invalid working hours time '{}', expected HH:MM", time));
+        }
+    }
 
     save_user_profile(
         &connection,
         &request.display_name,
         request.location.as_deref(),
         &request.theme,
+        request.timezone.as_deref(),
+        request.work_start.as_deref(),
+        request.work_end.as_deref(),
     )
     .map_err(|e| format!("Failed to update profile: {}", e))?;
 
@@ -109,7 +147,7 @@ pub fn update_profile(
 //INFO: Gets the current hotkey configuration
 #[tauri::command]
 pub fn get_hotkey(database: State<Database>) -> Result<Option<HotkeyConfigResponse>, String> {
-    let connection = database.connection.lock();
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
 
     let config =
         get_hotkey_config(&connection).map_err(|e| format!("Failed to get hotkey: {}", e))?;
@@ -125,12 +163,14 @@ pub fn get_hotkey(database: State<Database>) -> Result<Option<HotkeyConfigRespon
 }
 
 //INFO: Updates the hotkey configuration
+//NOTE: Returns warnings for combos the OS usually keeps for itself; they're saved anyway, Settings shows them
 #[tauri::command]
 pub fn update_hotkey(
+    app: tauri::AppHandle,
     database: State<Database>,
     request: UpdateHotkeyRequest,
-) -> Result<(), String> {
-    let connection = database.connection.lock();
+) -> Result<Vec<String>, String> {
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
 
     // Preserve existing values if not provided (though frontend should provide all)
     let existing = get_hotkey_config(&connection)
@@ -155,10 +195,13 @@ pub fn update_hotkey(
         snipper_enabled: request.snipper_enabled.unwrap_or(existing.snipper_enabled),
     };
 
+    //INFO: Re-register first so a bad combo is rejected and the old binding stays active
+    crate::hotkey::register_hotkey(&app, Some(&config))?;
+
     save_hotkey_config(&connection, &config)
         .map_err(|e| format!("Failed to update hotkey: {}", e))?;
 
-    Ok(())
+    Ok(crate::hotkey::config_warnings(&config))
 }
 
 // ============================================================================
@@ -171,7 +214,7 @@ pub fn get_api_key_status(
     database: State<Database>,
     provider: String,
 ) -> Result<ApiKeyStatusResponse, String> {
-    let connection = database.connection.lock();
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
 
     let encrypted_token = get_api_token(&connection, &provider)
         .map_err(|e| format!("Failed to get API key status: {}", e))?;
@@ -208,9 +251,10 @@ pub fn update_api_key(
     database: State<Database>,
     request: UpdateApiKeyRequest,
 ) -> Result<(), String> {
-    let connection = database.connection.lock();
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
 
     //INFO: Encrypt the API key before storing
+    let _key = hold_encryption_key();
     let encrypted_key =
         encrypt_token(&request.api_key).map_err(|e| format!("Failed to encrypt API key: {}", e))?;
 
@@ -226,10 +270,132 @@ pub fn update_api_key(
 
 //INFO: Gets all integrations
 #[tauri::command]
-pub fn get_integrations(database: State<Database>) -> Result<Vec<Integration>, String> {
-    let connection = database.connection.lock();
+pub fn get_integrations(database: State<Database>) -> Result<Vec<Integration>, LumenError> {
+    let connection = database.connection.lock()?;
+
+    get_all_integrations(&connection).command_context("Failed to get integrations")
+}
+
+//INFO: Health of a single integration as reported by `check_integrations_health`
+#[derive(Debug, Serialize)]
+pub struct IntegrationHealth {
+    pub name: String,
+    pub status: String,
+    pub detail: String,
+}
+
+impl IntegrationHealth {
+    fn new(name: &str, status: &str, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: status.to_string(),
+            detail: detail.into(),
+        }
+    }
+}
+
+//INFO: Actively checks each integration instead of trusting the `enabled` flag
+//NOTE: Statuses are "ok", "error", "disabled", "not_configured" or "offline". Google's stored status and
+//      last_sync are updated to match the result. Offline mode skips the network checks rather than failing them.
+#[tauri::command]
+pub async fn check_integrations_health(
+    database: State<'_, Database>,
+) -> Result<Vec<IntegrationHealth>, LumenError> {
+    let (offline, google, obsidian, gemini_key) = {
+        let connection = database.connection.lock()?;
+        let offline = crate::offline::is_offline(&connection);
+        let google = get_integration(&connection, "google")
+            .command_context("Failed to get integration")?;
+        let obsidian = get_integration(&connection, "obsidian")
+            .command_context("Failed to get integration")?;
+        let gemini_key = get_api_token(&connection, "gemini")
+            .command_context("Failed to get API key")?;
+        (offline, google, obsidian, gemini_key)
+    };
 
-    get_all_integrations(&connection).map_err(|e| format!("Failed to get integrations: {}", e))
+    let mut results = Vec::new();
+
+    // 1. Google: a cheap authenticated call proves the token still works
+    results.push(match google {
+        None => IntegrationHealth::new("google", "not_configured", "Google is not set up"),
+        Some(integration) if !integration.enabled => {
+            IntegrationHealth::new("google", "disabled", "Google is disabled")
+        }
+        //NOTE: A working token doesn't fix a revoked grant or a missing scope, only signing in again does
+        Some(integration) if integration.status == NEEDS_REAUTH_STATUS => IntegrationHealth::new(
+            "google",
+            "error",
+            format!(
+                "Reconnect Google: {}",
+                integration.last_error.as_deref().unwrap_or("the sign-in has to be renewed")
+            ),
+        ),
+        Some(_) if offline => IntegrationHealth::new("google", "offline", "Offline mode is on"),
+        //NOTE: Targeted updates rather than save_integration, which would write back the row read before the
+        //      await and undo a config change or sign-in saved in the meantime
+        Some(_) => match verify_google_connection(&database).await {
+            Ok(email) => {
+                let connection = database.connection.lock()?;
+                let now = chrono::Utc::now().to_rfc3339();
+                set_integration_status(&connection, "google", "connected", Some(&now))
+                    .command_context("Failed to update integration")?;
+                clear_integration_error(&connection, "google")
+                    .command_context("Failed to update integration")?;
+                IntegrationHealth::new("google", "ok", format!("Signed in as {}", email))
+            }
+            Err(e) => {
+                let connection = database.connection.lock()?;
+                set_integration_status(&connection, "google", "error", None)
+                    .command_context("Failed to update integration")?;
+                set_integration_error(&connection, "google", &format!("{:#}", e))
+                    .command_context("Failed to update integration")?;
+                IntegrationHealth::new("google", "error", e.to_string())
+            }
+        },
+    });
+
+    // 2. Obsidian: the vault folder must exist and be readable
+    results.push(match obsidian {
+        None => IntegrationHealth::new("obsidian", "not_configured", "Obsidian is not set up"),
+        Some(integration) if !integration.enabled => {
+            IntegrationHealth::new("obsidian", "disabled", "Obsidian is disabled")
+        }
+        Some(integration) => {
+            let vault_path = integration
+                .config
+                .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+                .and_then(|c| c.get("vault_path").and_then(|v| v.as_str()).map(String::from));
+            match vault_path {
+                None => IntegrationHealth::new("obsidian", "error", "No vault path configured"),
+                Some(path) => match std::fs::read_dir(&path) {
+                    Ok(_) => IntegrationHealth::new("obsidian", "ok", format!("Vault at {}", path)),
+                    Err(e) => IntegrationHealth::new(
+                        "obsidian",
+                        "error",
+                        format!("Cannot read vault at {}: {}", path, e),
+                    ),
+                },
+            }
+        }
+    });
+
+    // 3. Gemini: send a tiny request with the stored key
+    results.push(match gemini_key.map(|k| decrypt_token(&k)) {
+        None => IntegrationHealth::new("gemini", "not_configured", "No Gemini API key saved"),
+        Some(Err(e)) => IntegrationHealth::new(
+            "gemini",
+            "error",
+            format!("Failed to decrypt API key: {}", e),
+        ),
+        Some(Ok(_)) if offline => IntegrationHealth::new("gemini", "offline", "Offline mode is on"),
+        Some(Ok(key)) => match GeminiClient::new(key).test_connection().await {
+            Ok(true) => IntegrationHealth::new("gemini", "ok", "API key is valid"),
+            Ok(false) => IntegrationHealth::new("gemini", "error", "Gemini rejected the request"),
+            Err(e) => IntegrationHealth::new("gemini", "error", e.to_string()),
+        },
+    });
+
+    Ok(results)
 }
 
 //INFO: Gets a specific integration by name
@@ -237,10 +403,10 @@ pub fn get_integrations(database: State<Database>) -> Result<Vec<Integration>, S
 pub fn get_integration_by_name(
     database: State<Database>,
     name: String,
-) -> Result<Option<Integration>, String> {
-    let connection = database.connection.lock();
+) -> Result<Option<Integration>, LumenError> {
+    let connection = database.connection.lock()?;
 
-    get_integration(&connection, &name).map_err(|e| format!("Failed to get integration: {}", e))
+    get_integration(&connection, &name).command_context("Failed to get integration")
 }
 
 //INFO: Updates an integration
@@ -248,11 +414,123 @@ pub fn get_integration_by_name(
 pub fn update_integration(
     database: State<Database>,
     integration: Integration,
-) -> Result<(), String> {
-    let connection = database.connection.lock();
+) -> Result<(), LumenError> {
+    let connection = database.connection.lock()?;
 
     save_integration(&connection, &integration)
-        .map_err(|e| format!("Failed to update integration: {}", e))
+        .command_context("Failed to update integration")
+}
+
+//INFO: What `reset_integration_cache` removed
+#[derive(Debug, Serialize)]
+pub struct IntegrationCacheReset {
+    pub name: String,
+    pub calendar_events_cleared: bool,
+    pub notifications_cleared: usize,
+    pub credentials_cleared: bool,
+}
+
+//INFO: Notification providers whose rows belong to each integration
+fn notification_providers(integration: &str) -> &'static [&'static str] {
+    match integration {
+        "google" => &["calendar", "gmail"],
+        _ => &[],
+    }
+}
+
+//INFO: Clears an integration's cached data so it re-syncs from scratch
+//NOTE: Credentials (tokens and config) are kept unless `full` is set; a full reset also disables it.
+//      A full Google reset doesn't revoke the grant on Google's side - use disconnect_google for that.
+#[tauri::command]
+pub fn reset_integration_cache(
+    database: State<Database>,
+    name: String,
+    full: Option<bool>,
+) -> Result<IntegrationCacheReset, LumenError> {
+    let full = full.unwrap_or(false);
+    let connection = database.connection.lock()?;
+
+    let mut integration = get_integration(&connection, &name)
+        .command_context("Failed to load integration")?
+        .ok_or_else(|| LumenError::Other(format!("Unknown integration: {}", name)))?;
+
+    let calendar_events_cleared = name == "google";
+    if calendar_events_cleared {
+        clear_calendar_events(&connection).command_context("Failed to clear cached calendar events")?;
+    }
+
+    let mut notifications_cleared = 0;
+    for provider in notification_providers(&name) {
+        notifications_cleared += clear_notifications_for_provider(&connection, provider)
+            .command_context("Failed to clear notifications")?;
+    }
+
+    integration.last_sync = None;
+    if full {
+        delete_api_token(&connection, &name).command_context("Failed to delete credentials")?;
+        integration.enabled = false;
+        integration.config = None;
+        integration.status = "not_configured".to_string();
+    }
+    save_integration(&connection, &integration).command_context("Failed to reset integration")?;
+
+    tracing::info!(
+        "🧹 Integrations: Reset {} ({} notifications{}{})",
+        name,
+        notifications_cleared,
+        if calendar_events_cleared { ", calendar cache" } else { "" },
+        if full { ", credentials" } else { "" }
+    );
+
+    Ok(IntegrationCacheReset {
+        name,
+        calendar_events_cleared,
+        notifications_cleared,
+        credentials_cleared: full,
+    })
+}
+
+//INFO: Rotates the encryption key, re-encrypting every stored token and clipboard entry
+#[tauri::command]
+pub fn rotate_encryption_key(database: State<Database>) -> Result<usize, String> {
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
+
+    crate::crypto::rotate_encryption_key(&connection)
+        .map_err(|e| format!("Failed to rotate encryption key: {}", e))
+}
+
+//INFO: Reports whether stored credentials can be decrypted with the current encryption key
+#[tauri::command]
+pub fn get_credentials_health(
+    database: State<Database>,
+) -> Result<crate::crypto::recovery::CredentialsHealth, LumenError> {
+    let connection = database.connection.lock()?;
+    crate::crypto::recovery::check_credentials(&connection).command_context("Failed to check credentials")
+}
+
+//INFO: Clears credentials that can't be decrypted so the user can re-enter them, returns the cleared providers
+//NOTE: Destructive - the frontend confirms first. A corrupted key is replaced, and integrations that
+//      lost their token are disabled and marked not_configured so they prompt for a reconnect.
+#[tauri::command]
+pub fn reset_credentials(database: State<Database>) -> Result<Vec<String>, LumenError> {
+    let connection = database.connection.lock()?;
+
+    let cleared = crate::crypto::recovery::clear_unreadable_credentials(&connection)
+        .command_context("Failed to reset credentials")?;
+
+    for provider in &cleared {
+        if let Some(mut integration) =
+            get_integration(&connection, provider).command_context("Failed to load integration")?
+        {
+            integration.enabled = false;
+            integration.config = None;
+            integration.status = "not_configured".to_string();
+            save_integration(&connection, &integration)
+                .command_context("Failed to reset integration")?;
+        }
+    }
+
+    Ok(cleared)
 }
 
 // ============================================================================
@@ -265,10 +543,63 @@ pub fn get_database_path(database: State<Database>) -> Result<String, String> {
     Ok(database.get_database_path().to_string_lossy().to_string())
 }
 
+//INFO: Exports a consistent snapshot of the database
+//NOTE: `settings_only` keeps only settings, keys and integrations for a privacy-friendly backup
+#[tauri::command]
+pub fn export_database(
+    database: State<Database>,
+    dest_path: String,
+    settings_only: Option<bool>,
+) -> Result<(), String> {
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
+
+    crate::database::backup::export_database(
+        &connection,
+        Path::new(&dest_path),
+        settings_only.unwrap_or(false),
+    )
+    .map_err(|e| format!("Failed to export database: {}", e))
+}
+
+//INFO: Replaces the current database with a previously exported one
+#[tauri::command]
+pub fn import_database(database: State<Database>, src_path: String) -> Result<(), String> {
+    let mut connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
+
+    crate::database::backup::import_database(
+        &mut connection,
+        database.get_database_path(),
+        Path::new(&src_path),
+    )
+    .map_err(|e| format!("Failed to import database: {}", e))
+}
+
+//INFO: Imports a lumen.db and its key file from another machine, returns how many secrets were re-encrypted
+//NOTE: The current database is kept as lumen.db.bak; the local encryption key stays in place
+#[tauri::command]
+pub fn import_lumen_backup(
+    database: State<Database>,
+    db_path: String,
+    key_path: String,
+) -> Result<usize, String> {
+    let mut connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
+
+    let reencrypted = crate::database::backup::import_backup_with_key(
+        &mut connection,
+        database.get_database_path(),
+        Path::new(&db_path),
+        Path::new(&key_path),
+    )
+    .map_err(|e| format!("Failed to import backup: {:#}", e))?;
+
+    tracing::info!("💾 Imported backup from {} ({} secrets re-encrypted)", db_path, reencrypted);
+    Ok(reencrypted)
+}
+
 //INFO: Generic setting getter
 #[tauri::command]
 pub fn get_app_setting(database: State<Database>, key: String) -> Result<Option<String>, String> {
-    let connection = database.connection.lock();
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
 
     get_setting(&connection, &key).map_err(|e| format!("Failed to get setting: {}", e))
 }
@@ -280,7 +611,283 @@ pub fn save_app_setting(
     key: String,
     value: String,
 ) -> Result<(), String> {
-    let connection = database.connection.lock();
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
 
     save_setting(&connection, &key, &value).map_err(|e| format!("Failed to save setting: {}", e))
 }
+
+// ============================================================================
+// Persona Commands
+// ============================================================================
+
+//INFO: Lists the built-in chat personas
+#[tauri::command]
+pub fn get_persona_presets() -> Vec<crate::gemini::persona::PersonaPreset> {
+    crate::gemini::persona::presets()
+}
+
+//INFO: Gets the configured persona (a preset name or custom text); None means the default
+#[tauri::command]
+pub fn get_system_persona(database: State<Database>) -> Result<Option<String>, LumenError> {
+    let connection = database.connection.lock()?;
+    get_setting(&connection, crate::gemini::persona::SYSTEM_PERSONA_SETTING)
+        .command_context("Failed to get persona")
+}
+
+//INFO: Sets the chat persona to a preset name or custom text; empty resets to the default
+#[tauri::command]
+pub fn set_system_persona(database: State<Database>, persona: String) -> Result<(), LumenError> {
+    let connection = database.connection.lock()?;
+    let persona = persona.trim();
+    if persona.is_empty() {
+        crate::database::queries::delete_setting(&connection, crate::gemini::persona::SYSTEM_PERSONA_SETTING)
+            .command_context("Failed to reset persona")
+    } else {
+        save_setting(&connection, crate::gemini::persona::SYSTEM_PERSONA_SETTING, persona)
+            .command_context("Failed to save persona")
+    }
+}
+
+// ============================================================================
+// Offline Mode Commands
+// ============================================================================
+
+//INFO: Whether offline mode is on
+#[tauri::command]
+pub fn get_offline_mode(database: State<Database>) -> Result<bool, LumenError> {
+    let connection = database.connection.lock()?;
+    Ok(crate::offline::is_offline(&connection))
+}
+
+//INFO: Turns offline mode on or off (web tools, Google and the proactive agent stop while it's on)
+#[tauri::command]
+pub fn set_offline_mode(database: State<Database>, enabled: bool) -> Result<(), LumenError> {
+    let connection = database.connection.lock()?;
+    save_setting(&connection, crate::offline::OFFLINE_MODE_SETTING, if enabled { "true" } else { "false" })
+        .command_context("Failed to save offline mode")?;
+    tracing::info!("🔌 Offline mode {}", if enabled { "enabled" } else { "disabled" });
+    Ok(())
+}
+
+//INFO: How many model turns (tool rounds) one chat message may take
+#[tauri::command]
+pub fn get_max_tool_rounds(database: State<Database>) -> Result<usize, LumenError> {
+    let connection = database.connection.lock()?;
+    Ok(crate::commands::chat::max_tool_rounds(&connection))
+}
+
+//INFO: Sets the tool round cap, returns the value actually saved (clamped to 1-10)
+#[tauri::command]
+pub fn set_max_tool_rounds(database: State<Database>, rounds: usize) -> Result<usize, LumenError> {
+    let rounds = rounds.clamp(1, crate::llm::tool_loop::MAX_TOOL_ROUNDS_LIMIT);
+    let connection = database.connection.lock()?;
+    save_setting(&connection, crate::commands::chat::CHAT_MAX_TOOL_ROUNDS_SETTING, &rounds.to_string())
+        .command_context("Failed to save tool round limit")?;
+    Ok(rounds)
+}
+
+// ============================================================================
+// Chat Provider Commands
+// ============================================================================
+
+//INFO: Gets the chat provider settings; the OpenAI API key is saved with update_api_key (provider "openai")
+#[tauri::command]
+pub fn get_llm_settings(database: State<Database>) -> Result<LlmSettingsResponse, LumenError> {
+    let connection = database.connection.lock()?;
+    let setting = |key: &str, default: &str| -> Result<String, LumenError> {
+        Ok(get_setting(&connection, key)
+            .command_context("Failed to get chat provider settings")?
+            .unwrap_or_else(|| default.to_string()))
+    };
+
+    Ok(LlmSettingsResponse {
+        provider: crate::llm::selected_provider(&connection).to_string(),
+        providers: crate::llm::LLM_PROVIDERS.to_vec(),
+        openai_base_url: setting(crate::llm::OPENAI_BASE_URL_SETTING, crate::llm::DEFAULT_OPENAI_BASE_URL)?,
+        openai_model: setting(crate::llm::OPENAI_MODEL_SETTING, crate::llm::DEFAULT_OPENAI_MODEL)?,
+        openai_key_configured: get_api_token(&connection, "openai")
+            .command_context("Failed to get API key")?
+            .is_some(),
+    })
+}
+
+//INFO: Picks the chat provider; base URL and model only apply to "openai" and are left alone when None
+#[tauri::command]
+pub fn set_llm_provider(
+    database: State<Database>,
+    provider: String,
+    openai_base_url: Option<String>,
+    openai_model: Option<String>,
+) -> Result<(), LumenError> {
+    let provider = provider.trim().to_lowercase();
+    if !crate::llm::LLM_PROVIDERS.contains(&provider.as_str()) {
+        return Err(LumenError::Other(format!(
+            "Unknown provider '{}'. Use one of: {}",
+            provider,
+            crate::llm::LLM_PROVIDERS.join(", ")
+        )));
+    }
+    if let Some(base_url) = &openai_base_url {
+        let parsed = url::Url::parse(base_url.trim())
+            .map_err(|_| LumenError::Other(format!("'{}' is not a valid URL", base_url)))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err("The base URL must start with http:// or https://".into());
+        }
+    }
+
+    let connection = database.connection.lock()?;
+    save_setting(&connection, crate::llm::LLM_PROVIDER_SETTING, &provider)
+        .command_context("Failed to save chat provider")?;
+    if let Some(base_url) = openai_base_url {
+        save_setting(&connection, crate::llm::OPENAI_BASE_URL_SETTING, base_url.trim())
+            .command_context("Failed to save base URL")?;
+    }
+    if let Some(model) = openai_model.filter(|m| !m.trim().is_empty()) {
+        save_setting(&connection, crate::llm::OPENAI_MODEL_SETTING, model.trim())
+            .command_context("Failed to save model")?;
+    }
+    Ok(())
+}
+
+//INFO: Sends a tiny request through the selected chat provider
+#[tauri::command]
+pub async fn test_llm_provider(database: State<'_, Database>) -> Result<bool, LumenError> {
+    let provider = {
+        let connection = database.connection.lock()?;
+        crate::llm::provider_from_settings(&connection)?
+    };
+    provider
+        .test_connection()
+        .await
+        .command_context("Failed to test chat provider")
+}
+
+// ============================================================================
+// Safety Commands
+// ============================================================================
+
+//INFO: Lists the Gemini safety thresholds, most permissive first
+#[tauri::command]
+pub fn get_safety_thresholds() -> Vec<&'static str> {
+    crate::gemini::safety::SAFETY_THRESHOLDS.to_vec()
+}
+
+//INFO: Gets the configured safety threshold; None means Gemini's defaults
+#[tauri::command]
+pub fn get_safety_threshold(database: State<Database>) -> Result<Option<String>, LumenError> {
+    let connection = database.connection.lock()?;
+    get_setting(&connection, crate::gemini::safety::SAFETY_THRESHOLD_SETTING)
+        .command_context("Failed to get safety threshold")
+}
+
+//INFO: Sets the safety threshold for chat and briefings; empty resets to Gemini's defaults
+#[tauri::command]
+pub fn set_safety_threshold(database: State<Database>, threshold: String) -> Result<(), LumenError> {
+    let connection = database.connection.lock()?;
+    let threshold = threshold.trim().to_uppercase();
+    if threshold.is_empty() {
+        return crate::database::queries::delete_setting(&connection, crate::gemini::safety::SAFETY_THRESHOLD_SETTING)
+            .command_context("Failed to reset safety threshold");
+    }
+    if crate::gemini::safety::safety_settings_for(&threshold).is_none() {
+        return Err(LumenError::Other(format!(
+            "Unknown safety threshold '{}'. Use one of: {}",
+            threshold,
+            crate::gemini::safety::SAFETY_THRESHOLDS.join(", ")
+        )));
+    }
+    save_setting(&connection, crate::gemini::safety::SAFETY_THRESHOLD_SETTING, &threshold)
+        .command_context("Failed to save safety threshold")
+}
+
+// ============================================================================
+// Logging Commands
+// ============================================================================
+
+//INFO: Folder holding Lumen's log files, for attaching to bug reports
+#[tauri::command]
+pub fn get_log_path() -> Result<String, LumenError> {
+    let path = crate::logging::log_directory().command_context("Failed to get log path")?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+//INFO: Changes and persists the log level ("error", "warn", "info", "debug" or "trace")
+#[tauri::command]
+pub fn set_log_level(database: State<Database>, level: String) -> Result<(), LumenError> {
+    let filter = crate::logging::parse_level(&level).ok_or_else(|| {
+        LumenError::Other(format!(
+            "Unknown log level '{}'. Use one of: {}",
+            level,
+            crate::logging::LOG_LEVELS.join(", ")
+        ))
+    })?;
+    crate::logging::set_level(filter).command_context("Failed to change log level")?;
+
+    let connection = database.connection.lock()?;
+    save_setting(&connection, crate::logging::LOG_LEVEL_SETTING, &level.trim().to_lowercase())
+        .command_context("Failed to save log level")
+}
+
+// ============================================================================
+// Local API Commands
+// ============================================================================
+
+//INFO: Whether the local HTTP API is on, its port, and the bearer token to use
+#[tauri::command]
+pub fn get_local_api_status(
+    app: tauri::AppHandle,
+    database: State<Database>,
+) -> Result<crate::local_api::LocalApiStatus, LumenError> {
+    let connection = database.connection.lock()?;
+    Ok(crate::local_api::status(&app, &connection))
+}
+
+//INFO: Turns the local HTTP API on or off (generating its token the first time)
+#[tauri::command]
+pub fn set_local_api_enabled(
+    app: tauri::AppHandle,
+    database: State<Database>,
+    enabled: bool,
+) -> Result<crate::local_api::LocalApiStatus, LumenError> {
+    {
+        let connection = database.connection.lock()?;
+        save_setting(
+            &connection,
+            crate::local_api::LOCAL_API_ENABLED_SETTING,
+            if enabled { "true" } else { "false" },
+        )
+        .command_context("Failed to save local API setting")?;
+    }
+
+    if enabled {
+        crate::local_api::start_if_enabled(&app).command_context("Failed to start local API")?;
+    } else {
+        crate::local_api::stop(&app);
+    }
+
+    let connection = database.connection.lock()?;
+    Ok(crate::local_api::status(&app, &connection))
+}
+
+// ============================================================================
+// Usage Commands
+// ============================================================================
+
+//INFO: Gets Gemini token usage per day and feature
+//NOTE: `range` is "today", "week" (last 7 days) or "month" (last 30 days)
+#[tauri::command]
+pub fn get_usage_stats(database: State<Database>, range: String) -> Result<Vec<UsageStat>, String> {
+    let days = match range.as_str() {
+        "today" => 0,
+        "week" => 6,
+        "month" => 29,
+        other => return Err(format!("Unknown usage range '{}'", other)),
+    };
+    let since = (chrono::Local::now().date_naive() - chrono::Duration::days(days))
+        .format("%Y-%m-%d")
+        .to_string();
+
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
+    get_usage_stats_query(&connection, &since)
+        .map_err(|e| format!("Failed to get usage stats: {}", e))
+}
@@ -1,7 +1,7 @@
 //INFO: Setup wizard commands for Lumen
 //NOTE: Handles the first-run setup flow
 
-use crate::crypto::encrypt_token;
+use crate::crypto::{encrypt_token, hold_encryption_key};
 use crate::database::queries::{
     get_user_profile, is_setup_complete, mark_setup_complete, save_api_token, save_hotkey_config,
     save_integration, save_user_profile, HotkeyConfig, Integration,
@@ -23,6 +23,9 @@ pub struct UserProfileResponse {
     pub display_name: String,
     pub location: Option<String>,
     pub theme: String,
+    pub timezone: Option<String>,
+    pub work_start: Option<String>,
+    pub work_end: Option<String>,
 }
 
 //INFO: Request structure for saving user profile during setup
@@ -31,6 +34,11 @@ pub struct SaveProfileRequest {
     pub display_name: String,
     pub location: Option<String>,
     pub theme: String,
+    //INFO: IANA timezone from the browser (Intl), e.g. "Europe/Berlin"
+    pub timezone: Option<String>,
+    //INFO: Working hours as "HH:MM"; empty string clears them
+    pub work_start: Option<String>,
+    pub work_end: Option<String>,
 }
 
 //INFO: Request structure for saving hotkey during setup
@@ -58,7 +66,7 @@ pub struct SaveIntegrationRequest {
 //INFO: Checks if the setup wizard has been completed
 #[tauri::command]
 pub fn check_setup_status(database: State<Database>) -> Result<SetupStatusResponse, String> {
-    let connection = database.connection.lock();
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
 
     let setup_complete = is_setup_complete(&connection)
         .map_err(|e| format!("Failed to check setup status: {}", e))?;
@@ -70,6 +78,9 @@ pub fn check_setup_status(database: State<Database>) -> Result<SetupStatusRespon
                 display_name: p.display_name,
                 location: p.location,
                 theme: p.theme,
+                timezone: p.timezone,
+                work_start: p.work_start,
+                work_end: p.work_end,
             })
     } else {
         None
@@ -87,13 +98,24 @@ pub fn setup_save_profile(
     database: State<Database>,
     request: SaveProfileRequest,
 ) -> Result<(), String> {
-    let connection = database.connection.lock();
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
+
+    for time in [&request.work_start, &request.work_end].into_iter().flatten() {
+        if !time.trim().is_empty()
+            && crate::agent::proactive::DndSchedule::parse_time(time).is_none()
+        {
+            return Err(format!("Invalid working hours time '{}', expected HH:MM", time));
+        }
+    }
 
     save_user_profile(
         &connection,
         &request.display_name,
         request.location.as_deref(),
         &request.theme,
+        request.timezone.as_deref(),
+        request.work_start.as_deref(),
+        request.work_end.as_deref(),
     )
     .map_err(|e| format!("Failed to save profile: {}", e))?;
 
@@ -106,7 +128,7 @@ pub fn setup_save_hotkey(
     database: State<Database>,
     request: SaveHotkeyRequest,
 ) -> Result<(), String> {
-    let connection = database.connection.lock();
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
 
     let config = HotkeyConfig {
         modifier_keys: request.modifier_keys,
@@ -117,6 +139,8 @@ pub fn setup_save_hotkey(
         snipper_enabled: true,
     };
 
+    crate::hotkey::validate_hotkey(&config.modifier_keys, &config.key)?;
+
     save_hotkey_config(&connection, &config)
         .map_err(|e| format!("Failed to save hotkey: {}", e))?;
 
@@ -129,9 +153,10 @@ pub fn setup_save_api_key(
     database: State<Database>,
     request: SaveApiKeyRequest,
 ) -> Result<(), String> {
-    let connection = database.connection.lock();
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
 
     //INFO: Encrypt the API key before storing
+    let _key = hold_encryption_key();
     let encrypted_key =
         encrypt_token(&request.api_key).map_err(|e| format!("Failed to encrypt API key: {}", e))?;
 
@@ -155,19 +180,42 @@ pub async fn test_gemini_api_key(api_key: String) -> Result<bool, String> {
     Ok(is_valid)
 }
 
+//INFO: Checks an Obsidian vault path and daily-note settings before they're saved
+//NOTE: An empty format falls back to the default, same as the saved integration
+#[tauri::command]
+pub fn test_obsidian_config(
+    vault_path: String,
+    daily_notes_path: Option<String>,
+    format: Option<String>,
+) -> Result<crate::integrations::obsidian::ObsidianConfigCheck, String> {
+    let config = crate::integrations::obsidian::ObsidianConfig::from_json(&serde_json::json!({
+        "vault_path": vault_path.trim(),
+        "daily_notes_path": daily_notes_path.unwrap_or_default().trim(),
+        "daily_notes_format": format.unwrap_or_default().trim(),
+    }))
+    .ok_or("Vault path is required")?;
+
+    Ok(crate::integrations::obsidian::check_config(
+        &config,
+        chrono::Local::now().date_naive(),
+    ))
+}
+
 //INFO: Saves an integration configuration during setup
 #[tauri::command]
 pub fn setup_save_integration(
     database: State<Database>,
     request: SaveIntegrationRequest,
 ) -> Result<(), String> {
-    let connection = database.connection.lock();
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
 
     let integration = Integration {
         name: request.name,
         enabled: request.enabled,
         config: request.config,
         last_sync: None,
+        last_error: None,
+        last_error_at: None,
         status: if request.enabled {
             "connected".to_string()
         } else {
@@ -184,7 +232,7 @@ pub fn setup_save_integration(
 //INFO: Marks the setup wizard as complete
 #[tauri::command]
 pub fn complete_setup(database: State<Database>) -> Result<(), String> {
-    let connection = database.connection.lock();
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
 
     mark_setup_complete(&connection).map_err(|e| format!("Failed to complete setup: {}", e))?;
 
@@ -1,5 +1,7 @@
+use crate::error::LumenError;
 use base64::{engine::general_purpose, Engine as _};
 use screenshots::Screen;
+use serde::Serialize;
 use std::sync::Mutex;
 use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter, Manager};
@@ -7,32 +9,503 @@ use tauri::{AppHandle, Emitter, Manager};
 //INFO: Cache for the screenshot we are snipping
 static LAST_SCREENSHOT: Mutex<Option<screenshots::image::DynamicImage>> = Mutex::new(None);
 
+//INFO: A line of text recognized by OCR, with its bounding box in image pixels
+#[derive(Debug, Serialize, Clone)]
+pub struct OcrLine {
+    pub text: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+    pub confidence: i32,
+}
+
+//INFO: Position and size of a connected screen, as reported by the capture backend
+#[derive(Debug, Serialize, Clone)]
+pub struct ScreenInfo {
+    pub index: usize,
+    pub id: u32,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f32,
+    pub is_primary: bool,
+}
+
+//INFO: An open application window that can be captured on its own
+#[derive(Debug, Serialize, Clone)]
+pub struct WindowInfo {
+    pub id: u32,
+    pub title: String,
+    pub app_name: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+//INFO: Lists every connected screen so the user can pick one to capture
 #[tauri::command]
-pub async fn capture_primary_screen() -> Result<String, String> {
-    use std::io::Cursor;
-    let start = Instant::now();
+pub fn list_screens() -> Result<Vec<ScreenInfo>, String> {
     let screens = Screen::all().map_err(|e| e.to_string())?;
+    Ok(screens
+        .iter()
+        .enumerate()
+        .map(|(index, screen)| {
+            let info = screen.display_info;
+            ScreenInfo {
+                index,
+                id: info.id,
+                x: info.x,
+                y: info.y,
+                width: info.width,
+                height: info.height,
+                scale_factor: info.scale_factor,
+                is_primary: info.is_primary,
+            }
+        })
+        .collect())
+}
+
+//INFO: Settings controlling how screenshots are shrunk before they're sent to Gemini
+pub const SCREENSHOT_MAX_DIMENSION_SETTING: &str = "screenshot_max_dimension";
+pub const SCREENSHOT_JPEG_SETTING: &str = "screenshot_jpeg";
+pub const DEFAULT_SCREENSHOT_MAX_DIMENSION: u32 = 1536;
+pub const DEFAULT_SCREENSHOT_JPEG_QUALITY: u8 = 80;
 
-    if let Some(screen) = screens.first() {
-        let capture = screen.capture().map_err(|e| e.to_string())?;
+//INFO: When to re-encode screenshots as JPEG instead of PNG
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JpegMode {
+    Never,
+    //INFO: Only for photographic content - UI and text stay PNG so they remain crisp
+    Auto,
+    Always,
+}
+
+impl JpegMode {
+    fn from_setting(value: Option<&str>) -> Self {
+        match value {
+            Some("never") => JpegMode::Never,
+            Some("always") => JpegMode::Always,
+            _ => JpegMode::Auto,
+        }
+    }
+
+    //INFO: Parses a caller-requested format: "png", "jpeg" (or "jpg") or "auto"
+    pub fn from_format(format: &str) -> Result<Self, String> {
+        match format.trim().to_lowercase().as_str() {
+            "png" => Ok(JpegMode::Never),
+            "jpeg" | "jpg" => Ok(JpegMode::Always),
+            "auto" => Ok(JpegMode::Auto),
+            other => Err(format!("Unknown screenshot format '{}': expected png, jpeg or auto", other)),
+        }
+    }
+}
+
+//INFO: An image ready to send to Gemini as inline_data
+//NOTE: `width`/`height` are after downscaling, `bytes` is the encoded size before base64
+#[derive(Debug, Clone, Serialize)]
+pub struct PreparedImage {
+    pub data: String,
+    pub mime_type: &'static str,
+    pub width: u32,
+    pub height: u32,
+    pub bytes: usize,
+}
 
+//INFO: Reads the screenshot size/format settings, falling back to defaults
+pub fn screenshot_settings(connection: &rusqlite::Connection) -> (u32, JpegMode) {
+    use crate::database::queries::get_setting;
+    let max_dimension = get_setting(connection, SCREENSHOT_MAX_DIMENSION_SETTING)
+        .ok()
+        .flatten()
+        .and_then(|v| v.parse::<u32>().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(DEFAULT_SCREENSHOT_MAX_DIMENSION);
+    let jpeg = get_setting(connection, SCREENSHOT_JPEG_SETTING).ok().flatten();
+    (max_dimension, JpegMode::from_setting(jpeg.as_deref()))
+}
+
+//INFO: Shrinks an image so its longest side is at most `max_dimension`, preserving aspect ratio
+pub fn downscale_image(
+    image: &screenshots::image::DynamicImage,
+    max_dimension: u32,
+) -> screenshots::image::DynamicImage {
+    if image.width() <= max_dimension && image.height() <= max_dimension {
+        return image.clone();
+    }
+    image.resize(
+        max_dimension,
+        max_dimension,
+        screenshots::image::imageops::FilterType::Triangle,
+    )
+}
+
+//INFO: Guesses whether an image is a photo (many distinct colors) rather than UI/text
+//NOTE: Samples a grid of pixels and counts distinct colors, quantized to 5 bits per channel
+fn looks_photographic(image: &screenshots::image::DynamicImage) -> bool {
+    use screenshots::image::GenericImageView;
+    const SAMPLES_PER_SIDE: u32 = 100;
+    const PHOTO_COLOR_THRESHOLD: usize = 2000;
+
+    let (width, height) = image.dimensions();
+    let step_x = (width / SAMPLES_PER_SIDE).max(1);
+    let step_y = (height / SAMPLES_PER_SIDE).max(1);
+    let mut colors = std::collections::HashSet::new();
+    for y in (0..height).step_by(step_y as usize) {
+        for x in (0..width).step_by(step_x as usize) {
+            let [r, g, b, _] = image.get_pixel(x, y).0;
+            colors.insert(((r >> 3) as u16) << 10 | ((g >> 3) as u16) << 5 | (b >> 3) as u16);
+        }
+    }
+    colors.len() > PHOTO_COLOR_THRESHOLD
+}
+
+//INFO: Downscales and encodes an image for Gemini, choosing PNG or JPEG per `jpeg`
+pub fn prepare_image_for_gemini(
+    image: &screenshots::image::DynamicImage,
+    max_dimension: u32,
+    jpeg: JpegMode,
+) -> Result<PreparedImage, String> {
+    prepare_image(image, max_dimension, jpeg, DEFAULT_SCREENSHOT_JPEG_QUALITY)
+}
+
+//INFO: `prepare_image_for_gemini` with an explicit JPEG quality (clamped to 1-100)
+pub fn prepare_image(
+    image: &screenshots::image::DynamicImage,
+    max_dimension: u32,
+    jpeg: JpegMode,
+    quality: u8,
+) -> Result<PreparedImage, String> {
+    let resized = downscale_image(image, max_dimension);
+    let use_jpeg = match jpeg {
+        JpegMode::Never => false,
+        JpegMode::Always => true,
+        JpegMode::Auto => looks_photographic(&resized),
+    };
+
+    let (buffer, mime_type) = if use_jpeg {
         let mut buffer = Vec::new();
-        let mut cursor = Cursor::new(&mut buffer);
-        capture
-            .write_to(&mut cursor, screenshots::image::ImageFormat::Png)
-            .map_err(|e: screenshots::image::ImageError| e.to_string())?;
-
-        let b64 = general_purpose::STANDARD.encode(buffer);
-        println!("Captured screen in {:?}", start.elapsed());
-        Ok(b64)
+        screenshots::image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buffer, quality.clamp(1, 100))
+            .encode_image(&resized.to_rgb8())
+            .map_err(|e| e.to_string())?;
+        (buffer, "image/jpeg")
+    } else {
+        (encode_png(&resized)?, "image/png")
+    };
+
+    Ok(PreparedImage {
+        data: general_purpose::STANDARD.encode(&buffer),
+        mime_type,
+        width: resized.width(),
+        height: resized.height(),
+        bytes: buffer.len(),
+    })
+}
+
+//INFO: Mime type of a base64-encoded image, sniffed from its magic bytes (defaults to PNG)
+pub fn sniff_image_mime(b64: &str) -> &'static str {
+    if b64.starts_with("/9j/") {
+        "image/jpeg"
+    } else if b64.starts_with("UklGR") {
+        "image/webp"
     } else {
-        Err("No screens found".to_string())
+        "image/png"
+    }
+}
+
+//INFO: Captures a screen, downscaled per the screenshot settings, as base64 PNG or JPEG
+//NOTE: Without a monitor index, captures the screen the overlay is on. Kept for older callers that
+//      only want the data - `capture_screen` also reports the mime type, dimensions and size.
+#[tauri::command]
+pub async fn capture_primary_screen(app: AppHandle, monitor: Option<usize>) -> Result<String, String> {
+    Ok(capture_screen_for_gemini(&app, monitor)?.data)
+}
+
+//INFO: Captures a screen with an optional format ("png", "jpeg", "auto") and JPEG quality (1-100)
+//NOTE: Anything left out comes from the screenshot settings; the result is still downscaled
+#[tauri::command]
+pub async fn capture_screen(
+    app: AppHandle,
+    monitor: Option<usize>,
+    format: Option<String>,
+    quality: Option<u8>,
+) -> Result<PreparedImage, String> {
+    let jpeg = format.as_deref().map(JpegMode::from_format).transpose()?;
+    capture_screen_with(&app, monitor, jpeg, quality)
+}
+
+//INFO: Captures a screen and prepares it for Gemini using the screenshot settings
+pub fn capture_screen_for_gemini(app: &AppHandle, monitor: Option<usize>) -> Result<PreparedImage, String> {
+    capture_screen_with(app, monitor, None, None)
+}
+
+//INFO: Captures a screen and prepares it for Gemini, overriding the format/quality settings when given
+fn capture_screen_with(
+    app: &AppHandle,
+    monitor: Option<usize>,
+    jpeg: Option<JpegMode>,
+    quality: Option<u8>,
+) -> Result<PreparedImage, String> {
+    let start = Instant::now();
+    let monitor = monitor.or_else(|| overlay_screen_index(app));
+    let capture = capture_screen_image(monitor)?;
+    let (max_dimension, jpeg_setting) = {
+        let database = app.state::<crate::database::Database>();
+        let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
+        screenshot_settings(&connection)
+    };
+    let prepared = prepare_image(
+        &capture,
+        max_dimension,
+        jpeg.unwrap_or(jpeg_setting),
+        quality.unwrap_or(DEFAULT_SCREENSHOT_JPEG_QUALITY),
+    )?;
+    tracing::info!(
+        "Captured screen in {:?} ({}x{} -> {}x{}, {} KB {})",
+        start.elapsed(),
+        capture.width(),
+        capture.height(),
+        prepared.width,
+        prepared.height,
+        prepared.bytes / 1024,
+        prepared.mime_type
+    );
+    Ok(prepared)
+}
+
+//INFO: Finds the index of the screen containing the overlay window's center
+pub fn overlay_screen_index(app: &AppHandle) -> Option<usize> {
+    let overlay = app.get_webview_window("overlay")?;
+    let position = overlay.outer_position().ok()?;
+    let size = overlay.outer_size().ok()?;
+    let center_x = position.x + size.width as i32 / 2;
+    let center_y = position.y + size.height as i32 / 2;
+
+    let screens = Screen::all().ok()?;
+    screens.iter().position(|screen| {
+        let info = screen.display_info;
+        //NOTE: Window positions are physical pixels, display bounds are logical
+        let scale = info.scale_factor as f64;
+        let left = (info.x as f64 * scale) as i32;
+        let top = (info.y as f64 * scale) as i32;
+        let right = left + (info.width as f64 * scale) as i32;
+        let bottom = top + (info.height as f64 * scale) as i32;
+        center_x >= left && center_x < right && center_y >= top && center_y < bottom
+    })
+}
+
+//INFO: Captures a screen as an in-memory image (defaults to the primary screen)
+pub fn capture_screen_image(
+    monitor: Option<usize>,
+) -> Result<screenshots::image::DynamicImage, String> {
+    let screens = Screen::all().map_err(|e| e.to_string())?;
+    let screen = match monitor {
+        Some(index) => screens
+            .get(index)
+            .ok_or_else(|| format!("Monitor {} not found ({} connected)", index, screens.len()))?,
+        None => screens
+            .iter()
+            .find(|s| s.display_info.is_primary)
+            .or_else(|| screens.first())
+            .ok_or("No screens found")?,
+    };
+    let capture = screen.capture().map_err(|e| e.to_string())?;
+    Ok(screenshots::image::DynamicImage::ImageRgba8(capture))
+}
+
+//INFO: Lists visible application windows (Lumen's own windows and minimized ones are left out)
+#[tauri::command]
+pub fn list_windows() -> Result<Vec<WindowInfo>, String> {
+    let windows = xcap::Window::all().map_err(|e| format!("Window listing isn't supported here: {}", e))?;
+    Ok(windows
+        .iter()
+        .filter(|w| !w.is_minimized() && !w.title().trim().is_empty() && !is_lumen_window(w))
+        .map(|w| WindowInfo {
+            id: w.id(),
+            title: w.title().to_string(),
+            app_name: w.app_name().to_string(),
+            width: w.width(),
+            height: w.height(),
+        })
+        .collect())
+}
+
+//INFO: Captures a single window as base64 PNG
+//NOTE: Falls back to the whole screen where per-window capture isn't supported (e.g. some Wayland sessions)
+#[tauri::command]
+pub async fn capture_window(id: u32) -> Result<String, String> {
+    let image = match capture_window_image(id) {
+        Ok(image) => image,
+        Err(WindowCaptureError::Unsupported(e)) => {
+            tracing::warn!("⚠️ Vision: Window capture unsupported ({}), capturing the screen instead", e);
+            capture_screen_image(None)?
+        }
+        Err(WindowCaptureError::Failed(e)) => return Err(e),
+    };
+    Ok(general_purpose::STANDARD.encode(encode_png(&image)?))
+}
+
+//INFO: Captures the first window whose title or app name contains `filter` and prepares it for Gemini
+//NOTE: Returns the window that was captured, or None if it fell back to the screen the overlay is on
+pub fn capture_window_for_gemini(
+    app: &AppHandle,
+    filter: &str,
+) -> Result<(PreparedImage, Option<WindowInfo>), String> {
+    if let Some(reason) = window_capture_unsupported_reason() {
+        tracing::warn!("⚠️ Vision: Window capture unsupported ({}), capturing the screen instead", reason);
+        return Ok((capture_screen_for_gemini(app, None)?, None));
+    }
+    let needle = filter.to_lowercase();
+    let found = list_windows()?.into_iter().find(|w| {
+        w.title.to_lowercase().contains(&needle) || w.app_name.to_lowercase().contains(&needle)
+    });
+    let window = found.ok_or_else(|| format!("No open window matches '{}'", filter))?;
+
+    let capture = match capture_window_image(window.id) {
+        Ok(image) => image,
+        Err(WindowCaptureError::Unsupported(e)) => {
+            tracing::warn!("⚠️ Vision: Window capture unsupported ({}), capturing the screen instead", e);
+            return Ok((capture_screen_for_gemini(app, None)?, None));
+        }
+        Err(WindowCaptureError::Failed(e)) => return Err(e),
+    };
+
+    let (max_dimension, jpeg) = {
+        let database = app.state::<crate::database::Database>();
+        let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
+        screenshot_settings(&connection)
+    };
+    let prepared = prepare_image_for_gemini(&capture, max_dimension, jpeg)?;
+    Ok((prepared, Some(window)))
+}
+
+enum WindowCaptureError {
+    //INFO: The platform can't list or capture windows at all
+    Unsupported(String),
+    //INFO: Window capture is available but this capture failed (window gone, bad id, capture error)
+    Failed(String),
+}
+
+//INFO: Whether this session can capture individual windows at all
+//NOTE: xcap captures windows through X11, which Wayland sessions don't expose
+fn window_capture_unsupported_reason() -> Option<String> {
+    if cfg!(target_os = "linux") {
+        let session_type = std::env::var("XDG_SESSION_TYPE").unwrap_or_default();
+        let wayland_display = std::env::var("WAYLAND_DISPLAY").unwrap_or_default();
+        if session_type.eq_ignore_ascii_case("wayland") || !wayland_display.is_empty() {
+            return Some("per-window capture isn't available on Wayland".to_string());
+        }
+    }
+    None
+}
+
+//INFO: Only a missing display connection means the platform can't list windows; anything else is a real failure
+fn classify_listing_error(error: xcap::XCapError) -> WindowCaptureError {
+    #[cfg(target_os = "linux")]
+    if matches!(error, xcap::XCapError::XcbConnError(_)) {
+        return WindowCaptureError::Unsupported(error.to_string());
+    }
+    WindowCaptureError::Failed(format!("Couldn't list windows: {}", error))
+}
+
+//INFO: Captures one window as an in-memory image
+fn capture_window_image(id: u32) -> Result<screenshots::image::DynamicImage, WindowCaptureError> {
+    if let Some(reason) = window_capture_unsupported_reason() {
+        return Err(WindowCaptureError::Unsupported(reason));
+    }
+    let windows = xcap::Window::all().map_err(classify_listing_error)?;
+    let window = windows
+        .into_iter()
+        .find(|w| w.id() == id)
+        .ok_or_else(|| WindowCaptureError::Failed(format!("Window {} not found", id)))?;
+    if window.is_minimized() {
+        return Err(WindowCaptureError::Failed(format!("Window '{}' is minimized", window.title())));
     }
+
+    let capture = window
+        .capture_image()
+        .map_err(|e| WindowCaptureError::Failed(format!("Couldn't capture window '{}': {}", window.title(), e)))?;
+    //NOTE: xcap and screenshots pull in different `image` versions, so hand the raw pixels across
+    let (width, height) = (capture.width(), capture.height());
+    screenshots::image::RgbaImage::from_raw(width, height, capture.into_raw())
+        .map(screenshots::image::DynamicImage::ImageRgba8)
+        .ok_or_else(|| WindowCaptureError::Failed("Window capture returned a malformed image".to_string()))
+}
+
+//INFO: Lumen's own windows share the app's titles (see tauri.conf.json)
+fn is_lumen_window(window: &xcap::Window) -> bool {
+    matches!(window.title(), "Lumen" | "Lumen Chat" | "Lumen Snipper")
+}
+
+//INFO: Encodes an image as PNG bytes
+fn encode_png(image: &screenshots::image::DynamicImage) -> Result<Vec<u8>, String> {
+    use std::io::Cursor;
+    let mut buffer = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut buffer), screenshots::image::ImageFormat::Png)
+        .map_err(|e: screenshots::image::ImageError| e.to_string())?;
+    Ok(buffer)
+}
+
+//INFO: Runs OCR over an image and returns the recognized text lines with bounding boxes
+//NOTE: Requires the `ocr` cargo feature (system tesseract + leptonica)
+#[cfg(feature = "ocr")]
+pub fn extract_text_from_image(
+    image: &screenshots::image::DynamicImage,
+) -> Result<Vec<OcrLine>, String> {
+    use leptess::{capi, LepTess};
+
+    let png = encode_png(image)?;
+    let mut tess = LepTess::new(None, "eng").map_err(|e| format!("Failed to init tesseract: {}", e))?;
+    tess.set_image_from_mem(&png)
+        .map_err(|e| format!("Failed to load image into tesseract: {}", e))?;
+
+    let boxes = match tess.get_component_boxes(capi::TessPageIteratorLevel_RIL_TEXTLINE, true) {
+        Some(b) => b,
+        None => return Ok(vec![]),
+    };
+
+    let mut lines = Vec::new();
+    for b in &boxes {
+        tess.set_rectangle(&b);
+        let text = tess.get_utf8_text().unwrap_or_default().trim().to_string();
+        if text.is_empty() {
+            continue;
+        }
+        let geometry = b.get_geometry();
+        lines.push(OcrLine {
+            text,
+            x: geometry.x,
+            y: geometry.y,
+            width: geometry.w,
+            height: geometry.h,
+            confidence: tess.mean_text_conf(),
+        });
+    }
+    Ok(lines)
+}
+
+#[cfg(not(feature = "ocr"))]
+pub fn extract_text_from_image(
+    _image: &screenshots::image::DynamicImage,
+) -> Result<Vec<OcrLine>, String> {
+    Err("OCR is not available in this build. Rebuild Lumen with the `ocr` feature enabled.".to_string())
+}
+
+//INFO: Captures a screen and reads the text on it
+pub async fn read_screen_text(monitor: Option<usize>) -> Result<Vec<OcrLine>, String> {
+    let image = capture_screen_image(monitor)?;
+    tokio::task::spawn_blocking(move || extract_text_from_image(&image))
+        .await
+        .map_err(|e| e.to_string())?
 }
 
 //INFO: Starts the snipping workflow
 #[tauri::command]
-pub async fn start_snipping(app: AppHandle) -> Result<(), String> {
+pub async fn start_snipping(app: AppHandle, monitor: Option<usize>) -> Result<(), String> {
+    //INFO: Resolve the target screen before hiding the overlay we use as the default anchor
+    let monitor = monitor.or_else(|| overlay_screen_index(&app));
+
     // 1. Hide Overlay
     if let Some(overlay) = app.get_webview_window("overlay") {
         overlay.hide().map_err(|e| e.to_string())?;
@@ -42,21 +515,41 @@ pub async fn start_snipping(app: AppHandle) -> Result<(), String> {
     tokio::time::sleep(Duration::from_millis(250)).await;
 
     // 3. Capture Screen
-    let screens = Screen::all().map_err(|e| e.to_string())?;
-    let screen = screens.first().ok_or("No screen found")?;
-    let image = screen.capture().map_err(|e| e.to_string())?;
+    let image = capture_screen_image(monitor)?;
+    let target_center = monitor
+        .and_then(|index| Screen::all().ok()?.get(index).map(|s| s.display_info))
+        .map(|info| {
+            let scale = info.scale_factor as f64;
+            (
+                ((info.x as f64 + info.width as f64 / 2.0) * scale) as i32,
+                ((info.y as f64 + info.height as f64 / 2.0) * scale) as i32,
+            )
+        });
 
     // 4. Cache it
     {
         let mut cache = LAST_SCREENSHOT.lock().map_err(|_| "Failed to lock cache")?;
-        *cache = Some(screenshots::image::DynamicImage::ImageRgba8(image));
+        *cache = Some(image);
     }
 
-    // 5. Show Snipper Window
     // 5. Show Snipper Window
     if let Some(snipper) = app.get_webview_window("snipper") {
+        //INFO: Cover the captured screen, falling back to the primary monitor
+        let target_monitor = target_center
+            .and_then(|(cx, cy)| {
+                snipper.available_monitors().ok()?.into_iter().find(|m| {
+                    let pos = m.position();
+                    let size = m.size();
+                    cx >= pos.x
+                        && cx < pos.x + size.width as i32
+                        && cy >= pos.y
+                        && cy < pos.y + size.height as i32
+                })
+            })
+            .or_else(|| snipper.primary_monitor().ok().flatten());
+
         //INFO: Manually force fullscreen size to ensure coverage
-        if let Ok(Some(monitor)) = snipper.primary_monitor() {
+        if let Some(monitor) = target_monitor {
             let size = monitor.size();
             let pos = monitor.position();
 
@@ -88,8 +581,8 @@ pub async fn close_snipper(app: AppHandle) -> Result<(), String> {
     if let Some(overlay) = app.get_webview_window("overlay") {
         overlay.show().map_err(|e| e.to_string())?;
         //INFO: Ensure overlay returns to its correct position
-        if let Err(e) = crate::commands::window::position_overlay_bottom_left(&overlay) {
-            println!("Failed to position overlay: {}", e);
+        if let Err(e) = crate::commands::window::position_overlay(&overlay) {
+            tracing::warn!("⚠️ Vision: Failed to position overlay: {}", e);
         }
         overlay.set_focus().map_err(|e| e.to_string())?;
     }
@@ -112,8 +605,182 @@ pub async fn capture_region(
     width: f64,
     height: f64,
 ) -> Result<(), String> {
-    use std::io::Cursor;
+    if let Some(b64) = crop_cached_screenshot(&app, x, y, width, height)? {
+        // Emit to overlay
+        app.emit("snipped-image", b64).map_err(|e| e.to_string())?;
+    }
+
+    close_snipper(app).await
+}
+
+//INFO: Default question when the user snips without typing one
+const DEFAULT_SNIP_PROMPT: &str = "Explain what's in this screenshot.";
 
+//INFO: Crops the cached screenshot and asks Lumen about it in one step ("snip this and explain it")
+//NOTE: Goes through send_chat_message's image path, so the turn is saved to history like any other;
+//      the result is also emitted as `snipped-chat` for the overlay
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn analyze_snipped_region(
+    app: AppHandle,
+    database: tauri::State<'_, crate::database::Database>,
+    cancellation: tauri::State<'_, crate::commands::chat::ChatCancellation>,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    prompt: Option<String>,
+    session_id: Option<String>,
+) -> Result<crate::commands::chat::SendMessageResponse, LumenError> {
+    let cropped = crop_cached_screenshot(&app, x, y, width, height)?;
+    close_snipper(app.clone()).await?;
+    let b64 = cropped.ok_or("The selected region is empty")?;
+
+    let message = prompt
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .unwrap_or_else(|| DEFAULT_SNIP_PROMPT.to_string());
+
+    let response = crate::commands::chat::send_chat_message(
+        app.clone(),
+        database,
+        cancellation,
+        crate::commands::chat::SendMessageRequest {
+            message,
+            session_id,
+            base64_image: Some(b64),
+        },
+    )
+    .await?;
+
+    let _ = app.emit("snipped-chat", &response);
+    Ok(response)
+}
+
+//INFO: Image files describe_image will read
+const DESCRIBABLE_IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp", "gif", "bmp"];
+const MAX_DESCRIBE_IMAGE_BYTES: u64 = 20 * 1024 * 1024;
+const DESCRIBE_IMAGE_PROMPT: &str = "Describe this image in a few sentences so it can be found by search later. \
+    Mention what it shows, any visible text (transcribed), and for screenshots which app or page it is.";
+
+//INFO: Loads an image from the Obsidian vault and prepares it for Gemini
+//NOTE: `path` is vault-relative or an absolute path inside the vault; anything outside is refused
+pub fn load_vault_image(
+    config: &crate::integrations::obsidian::ObsidianConfig,
+    path: &str,
+    max_dimension: u32,
+    jpeg: JpegMode,
+) -> Result<(std::path::PathBuf, PreparedImage), String> {
+    use crate::integrations::obsidian::{ensure_in_vault, resolve_in_vault};
+
+    let requested = std::path::Path::new(path.trim());
+    let path = if requested.is_absolute() {
+        ensure_in_vault(config, requested)
+    } else {
+        resolve_in_vault(config, path).and_then(|p| ensure_in_vault(config, &p))
+    }
+    .map_err(|e| e.to_string())?;
+
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+    if !DESCRIBABLE_IMAGE_EXTENSIONS.contains(&extension.as_str()) {
+        return Err(format!(
+            "Unsupported image format '{}'. Supported: {}",
+            extension,
+            DESCRIBABLE_IMAGE_EXTENSIONS.join(", ")
+        ));
+    }
+
+    let size = std::fs::metadata(&path)
+        .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?
+        .len();
+    if size > MAX_DESCRIBE_IMAGE_BYTES {
+        return Err(format!(
+            "{} is too large ({} MB, max {} MB)",
+            path.display(),
+            size / (1024 * 1024),
+            MAX_DESCRIBE_IMAGE_BYTES / (1024 * 1024)
+        ));
+    }
+
+    let image = screenshots::image::open(&path)
+        .map_err(|e| format!("Failed to decode {}: {}", path.display(), e))?;
+    let prepared = prepare_image_for_gemini(&image, max_dimension, jpeg)?;
+    Ok((path, prepared))
+}
+
+//INFO: Asks Gemini for a caption of an image in the vault
+pub async fn describe_vault_image(
+    database: &crate::database::Database,
+    path: &str,
+) -> Result<(std::path::PathBuf, String), LumenError> {
+    use crate::error::CommandContext;
+
+    let (config, api_key, safety, (max_dimension, jpeg)) = {
+        let connection = database.connection.lock()?;
+        let config = crate::database::queries::get_integration(&connection, "obsidian")
+            .command_context("Failed to get integration")?
+            .filter(|i| i.enabled)
+            .and_then(|i| i.config)
+            .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+            .and_then(|c| crate::integrations::obsidian::ObsidianConfig::from_json(&c))
+            .ok_or(LumenError::IntegrationDisabled("Obsidian".to_string()))?;
+        crate::offline::ensure_online(&connection, "Describing images").map_err(LumenError::classify)?;
+        let encrypted_key = crate::database::queries::get_api_token(&connection, "gemini")
+            .command_context("Failed to get API key")?
+            .ok_or(LumenError::MissingApiKey)?;
+        let api_key = crate::crypto::decrypt_token(&encrypted_key).command_context("Failed to decrypt API key")?;
+        (
+            config,
+            api_key,
+            crate::gemini::safety::safety_settings_from_settings(&connection),
+            screenshot_settings(&connection),
+        )
+    };
+
+    let (path, image) = load_vault_image(&config, path, max_dimension, jpeg)?;
+
+    let client = crate::gemini::GeminiClient::new(api_key).with_safety_settings(safety);
+    let response = client
+        .send_chat(
+            vec![crate::gemini::client::GeminiContent {
+                role: Some("user".to_string()),
+                parts: vec![
+                    crate::gemini::client::GeminiPart::text(DESCRIBE_IMAGE_PROMPT.to_string()),
+                    crate::gemini::client::GeminiPart::inline_data(image.mime_type.to_string(), image.data),
+                ],
+            }],
+            None,
+            None,
+            None,
+        )
+        .await
+        .command_context("Failed to describe image")?;
+    crate::gemini::usage::record_usage(database, crate::gemini::usage::FEATURE_CHAT, response.usage.as_ref());
+
+    let description = response
+        .parts
+        .iter()
+        .filter_map(|p| p.text.as_deref())
+        .collect::<Vec<_>>()
+        .join("")
+        .trim()
+        .to_string();
+    Ok((path, description))
+}
+
+//INFO: Crops the cached screenshot to a region given in CSS pixels and returns it as base64 PNG
+//NOTE: None when the region is empty after clamping to the screen
+fn crop_cached_screenshot(
+    app: &AppHandle,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+) -> Result<Option<String>, String> {
     // 1. Get cached image
     let mut image = {
         let cache = LAST_SCREENSHOT.lock().map_err(|_| "Failed to lock cache")?;
@@ -151,23 +818,51 @@ pub async fn capture_region(
     let ch = pheight.min(img_height - cy);
 
     if cw == 0 || ch == 0 {
-        return close_snipper(app).await;
+        return Ok(None);
     }
 
     let cropped = image.crop(cx, cy, cw, ch);
 
-    // 3. Encode to Base64
-    let mut buffer = Vec::new();
-    let mut cursor = Cursor::new(&mut buffer);
-    cropped
-        .write_to(&mut cursor, screenshots::image::ImageFormat::Png)
-        .map_err(|e| e.to_string())?;
+    // 3. Downscale and encode to Base64 (always PNG: snips are usually text and the overlay previews them as PNG)
+    let max_dimension = {
+        let database = app.state::<crate::database::Database>();
+        let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
+        screenshot_settings(&connection).0
+    };
+    Ok(Some(prepare_image_for_gemini(&cropped, max_dimension, JpegMode::Never)?.data))
+}
 
-    let b64 = general_purpose::STANDARD.encode(buffer);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use screenshots::image::{DynamicImage, Rgba, RgbaImage};
 
-    // 4. Emit to overlay
-    app.emit("snipped-image", b64).map_err(|e| e.to_string())?;
+    #[test]
+    fn test_prepare_image_downscales_and_picks_format() {
+        //INFO: A flat "UI-like" 4K frame stays PNG but shrinks to the max dimension
+        let flat = DynamicImage::ImageRgba8(RgbaImage::from_pixel(3840, 2160, Rgba([30, 30, 30, 255])));
+        let prepared = prepare_image_for_gemini(&flat, 1536, JpegMode::Auto).unwrap();
+        assert_eq!(prepared.mime_type, "image/png");
+        let decoded = general_purpose::STANDARD.decode(&prepared.data).unwrap();
+        let roundtrip = screenshots::image::load_from_memory(&decoded).unwrap();
+        assert_eq!((roundtrip.width(), roundtrip.height()), (1536, 864));
+        assert_eq!((prepared.width, prepared.height), (1536, 864));
+        assert_eq!(prepared.bytes, decoded.len());
 
-    // 5. Close Window
-    close_snipper(app).await
+        //INFO: A noisy "photo" goes to JPEG in auto mode, and the mime sniffing agrees
+        let noisy = DynamicImage::ImageRgba8(RgbaImage::from_fn(400, 300, |x, y| {
+            let v = x.wrapping_mul(2654435761).wrapping_add(y.wrapping_mul(40503));
+            Rgba([(v >> 3) as u8, (v >> 11) as u8, (v >> 19) as u8, 255])
+        }));
+        let prepared = prepare_image_for_gemini(&noisy, 1536, JpegMode::Auto).unwrap();
+        assert_eq!(prepared.mime_type, "image/jpeg");
+        assert_eq!(sniff_image_mime(&prepared.data), "image/jpeg");
+
+        //INFO: Lower quality means a smaller payload, and formats can be forced
+        let low = prepare_image(&noisy, 1536, JpegMode::from_format("jpg").unwrap(), 20).unwrap();
+        assert!(low.bytes < prepared.bytes);
+        let png = prepare_image(&noisy, 1536, JpegMode::from_format("PNG").unwrap(), 20).unwrap();
+        assert_eq!(png.mime_type, "image/png");
+        assert!(JpegMode::from_format("gif").is_err());
+    }
 }
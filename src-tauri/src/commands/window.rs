@@ -1,7 +1,59 @@
 //INFO: Window management commands for Lumen
 //NOTE: Handles overlay window show/hide and positioning
 
-use tauri::{Manager, WebviewWindow};
+use crate::database::{queries, Database};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tauri::{Emitter, Manager, WebviewWindow};
+
+//INFO: Corners the overlay can snap to; the first is the default
+pub const OVERLAY_ANCHORS: &[&str] = &["bottom-left", "bottom-right", "top-left", "top-right"];
+
+const OVERLAY_ANCHOR_KEY: &str = "overlay_anchor";
+const OVERLAY_POSITION_KEY: &str = "overlay_position";
+const DEFAULT_OVERLAY_SIZE: OverlaySize = OverlaySize { width: 400.0, height: 820.0 };
+const MIN_OVERLAY_SIZE: OverlaySize = OverlaySize { width: 280.0, height: 300.0 };
+const SCREEN_MARGIN: f64 = 4.0;
+
+const OVERLAY_OPACITY_KEY: &str = "overlay_opacity";
+const OVERLAY_ON_TOP_KEY: &str = "overlay_always_on_top";
+//INFO: Below this the chat becomes unreadable against a busy desktop
+const MIN_OVERLAY_OPACITY: f64 = 0.3;
+
+//INFO: The view the overlay was last resized for, so its size is saved under the right key
+static CURRENT_VIEW: Mutex<String> = Mutex::new(String::new());
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct OverlayPoint {
+    x: f64,
+    y: f64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct OverlaySize {
+    width: f64,
+    height: f64,
+}
+
+//INFO: Look-and-feel settings for the overlay, sent to it as the `overlay-appearance` event
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct OverlayAppearance {
+    pub opacity: f64,
+    pub always_on_top: bool,
+}
+
+//NOTE: Window managers round positions and sizes, so compare with a couple of pixels of slack
+impl OverlayPoint {
+    fn close_to(self, other: OverlayPoint) -> bool {
+        (self.x - other.x).abs() <= 2.0 && (self.y - other.y).abs() <= 2.0
+    }
+}
+
+impl OverlaySize {
+    fn close_to(self, other: OverlaySize) -> bool {
+        (self.width - other.width).abs() <= 2.0 && (self.height - other.height).abs() <= 2.0
+    }
+}
 
 //INFO: Shows the overlay window
 #[tauri::command]
@@ -9,7 +61,8 @@ pub async fn show_overlay(app: tauri::AppHandle) -> Result<(), String> {
     //INFO: Get the overlay window by its label
     if let Some(overlay_window) = app.get_webview_window("overlay") {
         // 1. Position it BEFORE showing to avoid "center flash"
-        let _ = position_overlay_bottom_left(&overlay_window);
+        let _ = position_overlay(&overlay_window);
+        apply_overlay_appearance(&overlay_window);
 
         // 2. Make it visible on all workspaces (Sticky)
         let _ = overlay_window.set_visible_on_all_workspaces(true);
@@ -33,6 +86,7 @@ pub async fn show_overlay(app: tauri::AppHandle) -> Result<(), String> {
 #[tauri::command]
 pub async fn hide_overlay(app: tauri::AppHandle) -> Result<(), String> {
     if let Some(overlay_window) = app.get_webview_window("overlay") {
+        remember_overlay_geometry(&overlay_window);
         overlay_window
             .hide()
             .map_err(|e| format!("Failed to hide overlay: {}", e))?;
@@ -51,13 +105,15 @@ pub async fn toggle_overlay(app: tauri::AppHandle) -> Result<bool, String> {
             .map_err(|e| format!("Failed to check visibility: {}", e))?;
 
         if is_visible {
+            remember_overlay_geometry(&overlay_window);
             overlay_window
                 .hide()
                 .map_err(|e| format!("Failed to hide overlay: {}", e))?;
             Ok(false)
         } else {
             // 1. Position it BEFORE showing
-            let _ = position_overlay_bottom_left(&overlay_window);
+            let _ = position_overlay(&overlay_window);
+            apply_overlay_appearance(&overlay_window);
 
             // 2. Make it visible on all workspaces (Sticky)
             let _ = overlay_window.set_visible_on_all_workspaces(true);
@@ -90,14 +146,16 @@ pub async fn is_overlay_visible(app: tauri::AppHandle) -> Result<bool, String> {
 }
 
 //INFO: Resizes and re-positions the overlay based on the view
+//NOTE: Uses the per-view size the user left it at, falling back to the default
 #[tauri::command]
 pub async fn resize_overlay(app: tauri::AppHandle, view: String) -> Result<(), String> {
     if let Some(window) = app.get_webview_window("overlay") {
-        let (width, height) = (400.0, 820.0);
+        *CURRENT_VIEW.lock() = view.clone();
+        let size = overlay_size(&app, &view);
 
         // 1. Set Size
         window
-            .set_size(tauri::LogicalSize::new(width, height))
+            .set_size(tauri::LogicalSize::new(size.width, size.height))
             .map_err(|e| format!("Failed to set size: {}", e))?;
 
         // 2. Short sleep to let WM catch up (mostly critical for Linux stability)
@@ -107,62 +165,295 @@ pub async fn resize_overlay(app: tauri::AppHandle, view: String) -> Result<(), S
         #[cfg(not(target_os = "linux"))]
         tokio::time::sleep(std::time::Duration::from_millis(10)).await;
 
-        // 3. Re-position to keep bottom-left fixed
-        if let Ok(Some(monitor)) = window.primary_monitor() {
-            let monitor_size = monitor.size();
-            let monitor_position = monitor.position();
-            let scale_factor = monitor.scale_factor();
-            let logical_size = monitor_size.to_logical::<f64>(scale_factor);
-            let logical_pos = monitor_position.to_logical::<f64>(scale_factor);
-            
-            //INFO: Set the window position directly based on expected size to avoid mid-render glitches
-            let x_position = logical_pos.x + 4.0;
-            let y_position = logical_pos.y + logical_size.height - 820.0 - 4.0;
-
-            window
-                .set_position(tauri::LogicalPosition::new(x_position, y_position))
-                .map_err(|e| format!("Failed to set position: {}", e))?;
-
-            // Ensure window is focused after resize
-            let _ = window.set_focus();
-        }
+        // 3. Re-position to the saved spot or the anchor corner
+        position_overlay(&window)?;
+
+        // Ensure window is focused after resize
+        let _ = window.set_focus();
     }
     Ok(())
 }
 
 //INFO: Command wrapper for positioning the overlay
 #[tauri::command]
-pub async fn position_overlay_bottom_left_command(app: tauri::AppHandle) -> Result<(), String> {
+pub async fn position_overlay_command(app: tauri::AppHandle) -> Result<(), String> {
     if let Some(window) = app.get_webview_window("overlay") {
-        position_overlay_bottom_left(&window)?;
+        position_overlay(&window)?;
     }
     Ok(())
 }
 
-//INFO: Positions the overlay window at the bottom-left of the screen
-pub fn position_overlay_bottom_left(window: &WebviewWindow) -> Result<(), String> {
-    //INFO: Get the primary monitor's dimensions
-    if let Ok(Some(monitor)) = window.primary_monitor() {
-        let monitor_size = monitor.size();
-        let monitor_position = monitor.position();
+//INFO: Gets the screen corner the overlay snaps to
+#[tauri::command]
+pub async fn get_overlay_anchor(app: tauri::AppHandle) -> Result<String, String> {
+    Ok(overlay_anchor(&app))
+}
 
-        let scale_factor = monitor.scale_factor();
-        let logical_size = monitor_size.to_logical::<f64>(scale_factor);
-        let logical_pos = monitor_position.to_logical::<f64>(scale_factor);
+//INFO: Picks the screen corner the overlay snaps to, forgetting any dragged position
+#[tauri::command]
+pub async fn set_overlay_anchor(app: tauri::AppHandle, corner: String) -> Result<(), String> {
+    if !OVERLAY_ANCHORS.contains(&corner.as_str()) {
+        return Err(format!(
+            "Unknown corner '{}'. Use one of: {}",
+            corner,
+            OVERLAY_ANCHORS.join(", ")
+        ));
+    }
 
-        //INFO: Set the window position directly based on expected size to avoid mid-render glitches
-        let x_position = logical_pos.x + 4.0;
-        let y_position = logical_pos.y + logical_size.height - 820.0 - 4.0;
+    {
+        let database = app.state::<Database>();
+        let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
+        queries::save_setting(&connection, OVERLAY_ANCHOR_KEY, &corner)
+            .map_err(|e| format!("Failed to save overlay anchor: {}", e))?;
+        queries::delete_setting(&connection, OVERLAY_POSITION_KEY)
+            .map_err(|e| format!("Failed to reset overlay position: {}", e))?;
+    }
+
+    if let Some(window) = app.get_webview_window("overlay") {
+        position_overlay(&window)?;
+    }
+    Ok(())
+}
+
+//INFO: Sets (or with no size, clears) the size the overlay uses for a view
+#[tauri::command]
+pub async fn set_overlay_size(
+    app: tauri::AppHandle,
+    view: String,
+    width: Option<f64>,
+    height: Option<f64>,
+) -> Result<(), String> {
+    let key = overlay_size_key(&view);
+    let database = app.state::<Database>();
+    let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
+
+    match (width, height) {
+        (Some(width), Some(height)) => {
+            if width < MIN_OVERLAY_SIZE.width || height < MIN_OVERLAY_SIZE.height {
+                return Err(format!(
+                    "Overlay must be at least {}x{}",
+                    MIN_OVERLAY_SIZE.width, MIN_OVERLAY_SIZE.height
+                ));
+            }
+            let value = serde_json::to_string(&OverlaySize { width, height }).map_err(|e| e.to_string())?;
+            queries::save_setting(&connection, &key, &value)
+        }
+        _ => queries::delete_setting(&connection, &key),
+    }
+    .map_err(|e| format!("Failed to save overlay size: {}", e))
+}
+
+//INFO: Gets the overlay's opacity and stacking settings
+#[tauri::command]
+pub async fn get_overlay_appearance(app: tauri::AppHandle) -> Result<OverlayAppearance, String> {
+    Ok(overlay_appearance(&app))
+}
+
+//INFO: Sets the overlay opacity (clamped to 0.3-1.0) and returns the value actually used
+#[tauri::command]
+pub async fn set_overlay_opacity(app: tauri::AppHandle, opacity: f64) -> Result<f64, String> {
+    if !opacity.is_finite() {
+        return Err("Opacity must be a number between 0 and 1".to_string());
+    }
+    let opacity = opacity.clamp(MIN_OVERLAY_OPACITY, 1.0);
+
+    {
+        let database = app.state::<Database>();
+        let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
+        queries::save_setting(&connection, OVERLAY_OPACITY_KEY, &opacity.to_string())
+            .map_err(|e| format!("Failed to save overlay opacity: {}", e))?;
+    }
+
+    if let Some(window) = app.get_webview_window("overlay") {
+        apply_overlay_appearance(&window);
+    }
+    Ok(opacity)
+}
 
-        //INFO: Set the window position
+//INFO: Keeps the overlay above other windows, or lets it fall behind them like a normal window
+#[tauri::command]
+pub async fn set_overlay_always_on_top(app: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    {
+        let database = app.state::<Database>();
+        let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
+        queries::save_setting(&connection, OVERLAY_ON_TOP_KEY, &enabled.to_string())
+            .map_err(|e| format!("Failed to save overlay stacking: {}", e))?;
+    }
+
+    if let Some(window) = app.get_webview_window("overlay") {
+        apply_overlay_appearance(&window);
+    }
+    Ok(())
+}
+
+//INFO: Applies the saved opacity and stacking to the overlay
+//NOTE: Tauri has no per-window opacity API, so the overlay fades its own content (the window is transparent).
+//NOTE: Where the compositor can't do transparency it simply stays opaque
+fn apply_overlay_appearance(window: &WebviewWindow) {
+    let appearance = overlay_appearance(window.app_handle());
+
+    if let Err(e) = window.set_always_on_top(appearance.always_on_top) {
+        tracing::warn!("⚠️ Overlay: Failed to set always-on-top: {}", e);
+    }
+    let _ = window.emit("overlay-appearance", appearance);
+}
+
+fn overlay_appearance(app: &tauri::AppHandle) -> OverlayAppearance {
+    let database = app.state::<Database>();
+    let connection = database.connection.lock().ok();
+    let setting = |key: &str| {
+        let connection = connection.as_ref()?;
+        queries::get_setting(connection, key).ok().flatten()
+    };
+
+    OverlayAppearance {
+        opacity: setting(OVERLAY_OPACITY_KEY)
+            .and_then(|v| v.parse::<f64>().ok())
+            .filter(|v| v.is_finite())
+            .map(|v| v.clamp(MIN_OVERLAY_OPACITY, 1.0))
+            .unwrap_or(1.0),
+        always_on_top: setting(OVERLAY_ON_TOP_KEY).map(|v| v == "true").unwrap_or(true),
+    }
+}
+
+//INFO: Positions the overlay at its saved spot, or at the anchor corner (bottom-left by default)
+//NOTE: A saved spot that's no longer on any monitor (e.g. unplugged display) is ignored
+pub fn position_overlay(window: &WebviewWindow) -> Result<(), String> {
+    let app = window.app_handle();
+    let size = overlay_size(app, &CURRENT_VIEW.lock());
+
+    let saved = read_json_setting::<OverlayPoint>(app, OVERLAY_POSITION_KEY)
+        .filter(|point| is_on_a_monitor(window, *point));
+
+    let target = match saved {
+        Some(point) => Some(point),
+        None => anchor_position(window, &overlay_anchor(app), size),
+    };
+
+    if let Some(point) = target {
+        //INFO: Set the window position directly based on expected size to avoid mid-render glitches
         window
-            .set_position(tauri::LogicalPosition::new(x_position, y_position))
+            .set_position(tauri::LogicalPosition::new(point.x, point.y))
             .map_err(|e| format!("Failed to set position: {}", e))?;
     }
 
     Ok(())
 }
 
+//INFO: Saves where the user left the overlay (and its size for the current view) before it hides
+//NOTE: A position that still matches the anchor isn't saved, so the corner keeps tracking monitor changes
+fn remember_overlay_geometry(window: &WebviewWindow) {
+    let app = window.app_handle();
+    let Ok(scale_factor) = window.scale_factor() else {
+        return;
+    };
+    let view = CURRENT_VIEW.lock().clone();
+    let expected_size = overlay_size(app, &view);
+
+    let database = app.state::<Database>();
+    let Ok(connection) = database.connection.lock() else {
+        return;
+    };
+
+    if let Ok(size) = window.inner_size() {
+        let size = size.to_logical::<f64>(scale_factor);
+        let current = OverlaySize { width: size.width, height: size.height };
+        let big_enough = current.width >= MIN_OVERLAY_SIZE.width && current.height >= MIN_OVERLAY_SIZE.height;
+        if big_enough && !current.close_to(expected_size) {
+            if let Ok(value) = serde_json::to_string(&current) {
+                let _ = queries::save_setting(&connection, &overlay_size_key(&view), &value);
+            }
+        }
+    }
+
+    if let Ok(position) = window.outer_position() {
+        let position = position.to_logical::<f64>(scale_factor);
+        let current = OverlayPoint { x: position.x, y: position.y };
+        let anchored = anchor_position(window, &overlay_anchor_from(&connection), expected_size)
+            .is_some_and(|anchor| anchor.close_to(current));
+
+        let _ = if anchored {
+            queries::delete_setting(&connection, OVERLAY_POSITION_KEY)
+        } else {
+            match serde_json::to_string(&current) {
+                Ok(value) => queries::save_setting(&connection, OVERLAY_POSITION_KEY, &value),
+                Err(_) => Ok(()),
+            }
+        };
+    }
+}
+
+//INFO: Top-left point that puts a window of `size` in the given corner of the primary monitor
+fn anchor_position(window: &WebviewWindow, anchor: &str, size: OverlaySize) -> Option<OverlayPoint> {
+    let monitor = window.primary_monitor().ok().flatten()?;
+    let scale_factor = monitor.scale_factor();
+    let logical_size = monitor.size().to_logical::<f64>(scale_factor);
+    let logical_pos = monitor.position().to_logical::<f64>(scale_factor);
+
+    let left = logical_pos.x + SCREEN_MARGIN;
+    let right = logical_pos.x + logical_size.width - size.width - SCREEN_MARGIN;
+    let top = logical_pos.y + SCREEN_MARGIN;
+    let bottom = logical_pos.y + logical_size.height - size.height - SCREEN_MARGIN;
+
+    let (x, y) = match anchor {
+        "bottom-right" => (right, bottom),
+        "top-left" => (left, top),
+        "top-right" => (right, top),
+        _ => (left, bottom),
+    };
+    Some(OverlayPoint { x, y })
+}
+
+fn is_on_a_monitor(window: &WebviewWindow, point: OverlayPoint) -> bool {
+    window
+        .available_monitors()
+        .map(|monitors| {
+            monitors.iter().any(|monitor| {
+                let scale_factor = monitor.scale_factor();
+                let size = monitor.size().to_logical::<f64>(scale_factor);
+                let pos = monitor.position().to_logical::<f64>(scale_factor);
+                point.x >= pos.x
+                    && point.y >= pos.y
+                    && point.x < pos.x + size.width
+                    && point.y < pos.y + size.height
+            })
+        })
+        .unwrap_or(false)
+}
+
+fn overlay_size(app: &tauri::AppHandle, view: &str) -> OverlaySize {
+    read_json_setting(app, &overlay_size_key(view)).unwrap_or(DEFAULT_OVERLAY_SIZE)
+}
+
+fn overlay_size_key(view: &str) -> String {
+    format!("overlay_size_{}", view)
+}
+
+fn overlay_anchor(app: &tauri::AppHandle) -> String {
+    let database = app.state::<Database>();
+    let Ok(connection) = database.connection.lock() else {
+        return OVERLAY_ANCHORS[0].to_string();
+    };
+    overlay_anchor_from(&connection)
+}
+
+fn overlay_anchor_from(connection: &rusqlite::Connection) -> String {
+    queries::get_setting(connection, OVERLAY_ANCHOR_KEY)
+        .ok()
+        .flatten()
+        .filter(|anchor| OVERLAY_ANCHORS.contains(&anchor.as_str()))
+        .unwrap_or_else(|| OVERLAY_ANCHORS[0].to_string())
+}
+
+fn read_json_setting<T: serde::de::DeserializeOwned>(app: &tauri::AppHandle, key: &str) -> Option<T> {
+    let database = app.state::<Database>();
+    let connection = database.connection.lock().ok()?;
+    queries::get_setting(&connection, key)
+        .ok()
+        .flatten()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+}
+
 //INFO: Shows the main application window
 #[tauri::command]
 pub async fn show_main_window(app: tauri::AppHandle) -> Result<(), String> {
@@ -7,11 +7,12 @@ use aes_gcm::{
 };
 use anyhow::{anyhow, Context, Result};
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use parking_lot::{Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
 use rand::RngCore;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 //INFO: Length of the encryption key in bytes (256 bits)
-const KEY_LENGTH: usize = 32;
+pub const KEY_LENGTH: usize = 32;
 
 //INFO: Length of the nonce in bytes (96 bits for GCM)
 const NONCE_LENGTH: usize = 12;
@@ -22,49 +23,212 @@ fn get_key_file_path() -> Result<PathBuf> {
     Ok(config_dir.join("lumen").join(".key"))
 }
 
-//INFO: Gets or creates the encryption key
-//NOTE: Key is stored in a separate file in the config directory
-//NOTE: This is a simple approach - for production, consider using OS keyring
-pub fn get_or_create_encryption_key() -> Result<[u8; KEY_LENGTH]> {
-    let key_path = get_key_file_path()?;
+//INFO: Marks errors caused by a missing or corrupted key rather than by the data itself
+//NOTE: Attached as context so callers can `downcast_ref` it through any number of `.context()` layers
+#[derive(Debug, thiserror::Error)]
+#[error("Stored credentials can't be decrypted with the current encryption key")]
+pub struct CredentialsUnreadable;
 
-    //INFO: Check if key file exists
-    if key_path.exists() {
-        //INFO: Read existing key
-        let key_bytes = std::fs::read(&key_path).context("Failed to read encryption key")?;
+//INFO: In-memory copy of the key so we don't hit the keyring/disk on every encrypt/decrypt
+static KEY_CACHE: Mutex<Option<[u8; KEY_LENGTH]>> = Mutex::new(None);
 
-        if key_bytes.len() != KEY_LENGTH {
-            return Err(anyhow!("Invalid encryption key length"));
-        }
+//INFO: Shared by writers from encrypting a value until it's saved, exclusive for a whole key rotation
+//NOTE: Pooled connections write concurrently, so without it a value encrypted under the old key could be
+//      saved after the rotation committed and end up under a key that's no longer in use
+static ROTATION_LOCK: RwLock<()> = RwLock::new(());
 
-        let mut key = [0u8; KEY_LENGTH];
-        key.copy_from_slice(&key_bytes);
-        Ok(key)
+//INFO: Hold across `encrypt_token` and the write that stores its result
+//NOTE: Not reentrant while a rotation is waiting - take it once per write, never while already holding it
+pub fn hold_encryption_key() -> RwLockReadGuard<'static, ()> {
+    ROTATION_LOCK.read()
+}
+
+//INFO: Blocks every encrypted write until dropped; only key rotation takes it
+pub(crate) fn lock_for_rotation() -> RwLockWriteGuard<'static, ()> {
+    ROTATION_LOCK.write()
+}
+
+//INFO: Reads the legacy key file, if present
+fn read_key_file(key_path: &Path) -> Result<Option<[u8; KEY_LENGTH]>> {
+    if !key_path.exists() {
+        return Ok(None);
+    }
+
+    let key_bytes = std::fs::read(key_path).context("Failed to read encryption key")?;
+
+    if key_bytes.len() != KEY_LENGTH {
+        return Err(anyhow!("Invalid encryption key length ({} bytes)", key_bytes.len()))
+            .context(CredentialsUnreadable);
+    }
+
+    let mut key = [0u8; KEY_LENGTH];
+    key.copy_from_slice(&key_bytes);
+    Ok(Some(key))
+}
+
+//INFO: Reads a key copied from another install: the raw 32-byte key file, or the base64 form the keyring stores
+pub fn read_foreign_key(key_path: &Path) -> Result<[u8; KEY_LENGTH]> {
+    let bytes = std::fs::read(key_path).with_context(|| format!("Failed to read key file {}", key_path.display()))?;
+    let bytes = if bytes.len() == KEY_LENGTH {
+        bytes
     } else {
-        //INFO: Generate new key
-        let mut key = [0u8; KEY_LENGTH];
-        OsRng.fill_bytes(&mut key);
+        let text = String::from_utf8_lossy(&bytes);
+        BASE64
+            .decode(text.trim())
+            .map_err(|_| anyhow!("Not a Lumen encryption key (expected 32 bytes or base64)"))?
+    };
+    if bytes.len() != KEY_LENGTH {
+        return Err(anyhow!("Invalid encryption key length"));
+    }
+
+    let mut key = [0u8; KEY_LENGTH];
+    key.copy_from_slice(&bytes);
+    Ok(key)
+}
+
+//INFO: Writes the key file, creating the config directory if needed
+fn write_key_file(key_path: &Path, key: &[u8; KEY_LENGTH]) -> Result<()> {
+    if let Some(parent) = key_path.parent() {
+        std::fs::create_dir_all(parent).context("Failed to create key directory")?;
+    }
+
+    std::fs::write(key_path, key).context("Failed to write encryption key")
+}
+
+fn generate_key() -> [u8; KEY_LENGTH] {
+    let mut key = [0u8; KEY_LENGTH];
+    OsRng.fill_bytes(&mut key);
+    key
+}
+
+//INFO: OS keyring storage (Secret Service / macOS Keychain / Windows Credential Manager)
+//NOTE: Behind the `keyring` feature so headless Linux builds don't need a secret service
+#[cfg(feature = "keyring")]
+mod keystore {
+    use super::KEY_LENGTH;
+    use anyhow::{anyhow, Context, Result};
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+
+    const SERVICE: &str = "lumen";
+    const ACCOUNT: &str = "encryption-key";
 
-        //INFO: Ensure parent directory exists
-        if let Some(parent) = key_path.parent() {
-            std::fs::create_dir_all(parent).context("Failed to create key directory")?;
+    fn entry() -> Result<keyring::Entry> {
+        keyring::Entry::new(SERVICE, ACCOUNT).context("Failed to open keyring entry")
+    }
+
+    pub fn load() -> Result<Option<[u8; KEY_LENGTH]>> {
+        let encoded = match entry()?.get_password() {
+            Ok(encoded) => encoded,
+            Err(keyring::Error::NoEntry) => return Ok(None),
+            Err(e) => return Err(anyhow!("Failed to read key from keyring: {}", e)),
+        };
+
+        let bytes = BASE64
+            .decode(encoded)
+            .context("Keyring key is not valid base64")?;
+        if bytes.len() != KEY_LENGTH {
+            return Err(anyhow!("Invalid encryption key length in keyring"));
         }
 
-        //INFO: Save key to file
-        std::fs::write(&key_path, key).context("Failed to write encryption key")?;
+        let mut key = [0u8; KEY_LENGTH];
+        key.copy_from_slice(&bytes);
+        Ok(Some(key))
+    }
+
+    pub fn store(key: &[u8; KEY_LENGTH]) -> Result<()> {
+        entry()?
+            .set_password(&BASE64.encode(key))
+            .map_err(|e| anyhow!("Failed to write key to keyring: {}", e))
+    }
+}
+
+//INFO: Loads the key from the OS keyring, migrating the legacy key file into it on first run
+//NOTE: Returns an error if the keyring is unavailable so the caller can fall back to the file
+#[cfg(feature = "keyring")]
+fn load_or_create_keyring_key(key_path: &Path) -> Result<[u8; KEY_LENGTH]> {
+    if let Some(key) = keystore::load()? {
+        return Ok(key);
+    }
+
+    let key = match read_key_file(key_path)? {
+        Some(key) => key,
+        None => generate_key(),
+    };
+    keystore::store(&key)?;
+
+    //INFO: Only drop the file once the keyring round-trips the same key
+    if keystore::load()? != Some(key) {
+        return Err(anyhow!("Keyring did not return the stored key"));
+    }
+    if key_path.exists() {
+        std::fs::remove_file(key_path).context("Failed to remove migrated key file")?;
+        tracing::info!("🔐 Encryption: Migrated key file into the OS keyring");
+    }
+
+    Ok(key)
+}
 
-        Ok(key)
+//INFO: Loads the key from the key file, generating one on first run
+fn load_or_create_file_key(key_path: &Path) -> Result<[u8; KEY_LENGTH]> {
+    if let Some(key) = read_key_file(key_path)? {
+        return Ok(key);
     }
+
+    let key = generate_key();
+    write_key_file(key_path, &key)?;
+    Ok(key)
 }
 
+//INFO: Gets or creates the encryption key
+//NOTE: With the `keyring` feature the key lives in the OS keyring and the file is only a
+//      migration source (or a fallback when no keyring is available). Without it, the key
+//      is stored in a separate file in the config directory.
+pub fn get_or_create_encryption_key() -> Result<[u8; KEY_LENGTH]> {
+    let mut cache = KEY_CACHE.lock();
+    if let Some(key) = *cache {
+        return Ok(key);
+    }
+
+    let key_path = get_key_file_path()?;
+
+    #[cfg(feature = "keyring")]
+    let key = match load_or_create_keyring_key(&key_path) {
+        Ok(key) => key,
+        Err(e) => {
+            tracing::warn!("⚠️ Encryption: OS keyring unavailable ({}), using key file", e);
+            load_or_create_file_key(&key_path)?
+        }
+    };
+
+    #[cfg(not(feature = "keyring"))]
+    let key = load_or_create_file_key(&key_path)?;
+
+    *cache = Some(key);
+    Ok(key)
+}
+
+//INFO: Header byte for the current ciphertext format (version || nonce || ciphertext)
+//NOTE: Legacy ciphertexts have no header (nonce || ciphertext), decrypt_with_key handles both
+const CIPHERTEXT_VERSION: u8 = 1;
+
 //INFO: Encrypts a plaintext token using AES-256-GCM
-//NOTE: Returns base64-encoded ciphertext with nonce prepended
+//NOTE: Returns base64-encoded ciphertext with version byte and nonce prepended
 pub fn encrypt_token(plaintext: &str) -> Result<String> {
     let key = get_or_create_encryption_key()?;
+    encrypt_with_key(&key, plaintext)
+}
+
+//INFO: Decrypts a base64-encoded ciphertext
+pub fn decrypt_token(encrypted: &str) -> Result<String> {
+    let key = get_or_create_encryption_key()?;
+    decrypt_with_key(&key, encrypted).context(CredentialsUnreadable)
+}
 
+//INFO: Encrypts with an explicit key (used directly by key rotation)
+pub fn encrypt_with_key(key: &[u8; KEY_LENGTH], plaintext: &str) -> Result<String> {
     //INFO: Create cipher instance
     let cipher =
-        Aes256Gcm::new_from_slice(&key).map_err(|e| anyhow!("Failed to create cipher: {}", e))?;
+        Aes256Gcm::new_from_slice(key).map_err(|e| anyhow!("Failed to create cipher: {}", e))?;
 
     //INFO: Generate random nonce
     let mut nonce_bytes = [0u8; NONCE_LENGTH];
@@ -76,36 +240,46 @@ pub fn encrypt_token(plaintext: &str) -> Result<String> {
         .encrypt(nonce, plaintext.as_bytes())
         .map_err(|e| anyhow!("Encryption failed: {}", e))?;
 
-    //INFO: Combine nonce and ciphertext, then base64 encode
-    let mut combined = Vec::with_capacity(NONCE_LENGTH + ciphertext.len());
+    //INFO: Combine version, nonce and ciphertext, then base64 encode
+    let mut combined = Vec::with_capacity(1 + NONCE_LENGTH + ciphertext.len());
+    combined.push(CIPHERTEXT_VERSION);
     combined.extend_from_slice(&nonce_bytes);
     combined.extend_from_slice(&ciphertext);
 
     Ok(BASE64.encode(&combined))
 }
 
-//INFO: Decrypts a base64-encoded ciphertext
-//NOTE: Expects nonce to be prepended to ciphertext
-pub fn decrypt_token(encrypted: &str) -> Result<String> {
-    let key = get_or_create_encryption_key()?;
-
+//INFO: Decrypts with an explicit key, accepting both versioned and legacy ciphertexts
+pub fn decrypt_with_key(key: &[u8; KEY_LENGTH], encrypted: &str) -> Result<String> {
     //INFO: Decode base64
     let combined = BASE64
         .decode(encrypted)
         .context("Failed to decode base64")?;
 
+    //INFO: A legacy nonce can start with the version byte by chance, so fall back on failure
+    if combined.first() == Some(&CIPHERTEXT_VERSION) {
+        if let Ok(plaintext) = decrypt_payload(key, &combined[1..]) {
+            return Ok(plaintext);
+        }
+    }
+
+    decrypt_payload(key, &combined)
+}
+
+//INFO: Decrypts a raw nonce || ciphertext payload
+fn decrypt_payload(key: &[u8; KEY_LENGTH], payload: &[u8]) -> Result<String> {
     //INFO: Ensure we have at least nonce + some ciphertext
-    if combined.len() < NONCE_LENGTH + 1 {
+    if payload.len() < NONCE_LENGTH + 1 {
         return Err(anyhow!("Encrypted data too short"));
     }
 
     //INFO: Split nonce and ciphertext
-    let (nonce_bytes, ciphertext) = combined.split_at(NONCE_LENGTH);
+    let (nonce_bytes, ciphertext) = payload.split_at(NONCE_LENGTH);
     let nonce = Nonce::from_slice(nonce_bytes);
 
     //INFO: Create cipher instance
     let cipher =
-        Aes256Gcm::new_from_slice(&key).map_err(|e| anyhow!("Failed to create cipher: {}", e))?;
+        Aes256Gcm::new_from_slice(key).map_err(|e| anyhow!("Failed to create cipher: {}", e))?;
 
     //INFO: Decrypt
     let plaintext_bytes = cipher
@@ -115,6 +289,81 @@ pub fn decrypt_token(encrypted: &str) -> Result<String> {
     String::from_utf8(plaintext_bytes).context("Decrypted data is not valid UTF-8")
 }
 
+//INFO: Path of the staged key written during rotation, before the swap
+fn get_staged_key_file_path() -> Result<PathBuf> {
+    Ok(get_key_file_path()?.with_extension("new"))
+}
+
+//INFO: Generates a new key and stages it on disk without making it active
+//NOTE: If we crash between re-encrypting and swapping, the staged file is still there to recover from
+pub fn stage_new_encryption_key() -> Result<[u8; KEY_LENGTH]> {
+    let key = generate_key();
+    write_key_file(&get_staged_key_file_path()?, &key)?;
+    Ok(key)
+}
+
+//INFO: Reads the key left staged by a rotation that didn't finish, if any
+pub fn read_staged_encryption_key() -> Result<Option<[u8; KEY_LENGTH]>> {
+    read_key_file(&get_staged_key_file_path()?)
+}
+
+//INFO: Drops a staged key after a failed rotation
+pub fn discard_staged_encryption_key() -> Result<()> {
+    let staged_path = get_staged_key_file_path()?;
+    if staged_path.exists() {
+        std::fs::remove_file(staged_path).context("Failed to remove staged key")?;
+    }
+    Ok(())
+}
+
+//INFO: Makes the staged key the active one
+//NOTE: The file swap is an atomic rename; with the keyring feature the keyring is updated instead
+pub fn activate_staged_encryption_key(key: &[u8; KEY_LENGTH]) -> Result<()> {
+    let staged_path = get_staged_key_file_path()?;
+    let mut cache = KEY_CACHE.lock();
+
+    #[cfg(feature = "keyring")]
+    {
+        if keystore::store(key).is_ok() && keystore::load().ok().flatten() == Some(*key) {
+            std::fs::remove_file(&staged_path).context("Failed to remove staged key")?;
+            *cache = Some(*key);
+            return Ok(());
+        }
+        tracing::warn!("⚠️ Encryption: OS keyring unavailable, activating key file instead");
+    }
+
+    std::fs::rename(&staged_path, get_key_file_path()?).context("Failed to activate new key")?;
+    *cache = Some(*key);
+    Ok(())
+}
+
+//INFO: Replaces a key that can't be loaded with a fresh one, keeping the old file as `.key.corrupt`
+//NOTE: Recovery only - anything encrypted under the old key stays unreadable, so callers clear it
+pub fn replace_unusable_encryption_key() -> Result<[u8; KEY_LENGTH]> {
+    let key_path = get_key_file_path()?;
+    let mut cache = KEY_CACHE.lock();
+
+    if key_path.exists() {
+        let backup_path = key_path.with_extension("corrupt");
+        std::fs::rename(&key_path, &backup_path).context("Failed to move the corrupted key aside")?;
+        tracing::warn!("🔐 Encryption: Moved unusable key to {}", backup_path.display());
+    }
+    let key = generate_key();
+
+    #[cfg(feature = "keyring")]
+    {
+        if keystore::store(&key).is_ok() && keystore::load().ok().flatten() == Some(key) {
+            *cache = Some(key);
+            return Ok(key);
+        }
+        tracing::warn!("⚠️ Encryption: OS keyring unavailable, writing new key file instead");
+    }
+
+    write_key_file(&key_path, &key)?;
+    *cache = Some(key);
+    Ok(key)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -126,4 +375,46 @@ mod tests {
         let decrypted = decrypt_token(&encrypted).unwrap();
         assert_eq!(original, decrypted);
     }
+
+    #[test]
+    fn test_decrypt_legacy_and_versioned_ciphertexts() {
+        let key = generate_key();
+
+        //INFO: Build a legacy (unversioned) ciphertext by hand
+        let cipher = Aes256Gcm::new_from_slice(&key).unwrap();
+        let nonce_bytes = [7u8; NONCE_LENGTH];
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce_bytes), b"legacy".as_ref())
+            .unwrap();
+        let legacy = BASE64.encode([nonce_bytes.as_slice(), &ciphertext].concat());
+
+        assert_eq!(decrypt_with_key(&key, &legacy).unwrap(), "legacy");
+
+        let versioned = encrypt_with_key(&key, "versioned").unwrap();
+        assert_eq!(BASE64.decode(&versioned).unwrap()[0], CIPHERTEXT_VERSION);
+        assert_eq!(decrypt_with_key(&key, &versioned).unwrap(), "versioned");
+
+        assert!(decrypt_with_key(&generate_key(), &versioned).is_err());
+    }
+
+    #[test]
+    fn test_read_foreign_key_accepts_raw_and_base64() {
+        let key = generate_key();
+        let dir = std::env::temp_dir().join(format!("lumen-key-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let raw = dir.join("raw.key");
+        std::fs::write(&raw, key).unwrap();
+        assert_eq!(read_foreign_key(&raw).unwrap(), key);
+
+        let encoded = dir.join("encoded.key");
+        std::fs::write(&encoded, format!("{}\n", BASE64.encode(key))).unwrap();
+        assert_eq!(read_foreign_key(&encoded).unwrap(), key);
+
+        let bogus = dir.join("bogus.key");
+        std::fs::write(&bogus, "not a key").unwrap();
+        assert!(read_foreign_key(&bogus).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
@@ -2,5 +2,14 @@
 //NOTE: Handles encryption/decryption of sensitive data like API keys
 
 pub mod encryption;
+pub mod recovery;
+pub mod rotation;
 
-pub use encryption::{decrypt_token, encrypt_token, get_or_create_encryption_key};
+pub use encryption::{
+    decrypt_token, encrypt_token, get_or_create_encryption_key, hold_encryption_key,
+    CredentialsUnreadable,
+};
+pub use rotation::{
+    encrypt_plaintext_clipboard, recover_interrupted_rotation, reencrypt_with_local_key,
+    rotate_encryption_key,
+};
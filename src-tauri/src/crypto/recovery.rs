@@ -0,0 +1,109 @@
+//INFO: Recovery from a missing or corrupted encryption key
+//NOTE: A deleted key is silently regenerated and a truncated one fails to load - either way the
+//      tokens in api_tokens can't be decrypted any more and the only fix is to re-enter them
+
+use super::encryption::{
+    decrypt_with_key, get_or_create_encryption_key, replace_unusable_encryption_key,
+};
+use crate::database::queries::{delete_api_token, get_all_api_tokens};
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+use serde::Serialize;
+
+//INFO: Whether the stored credentials can still be read with the current key
+#[derive(Debug, Clone, Serialize)]
+pub struct CredentialsHealth {
+    //INFO: Why the key itself couldn't be loaded, if it couldn't
+    pub key_error: Option<String>,
+    //INFO: Providers whose stored token doesn't decrypt
+    pub unreadable: Vec<String>,
+    pub total: usize,
+}
+
+impl CredentialsHealth {
+    pub fn is_healthy(&self) -> bool {
+        self.key_error.is_none() && self.unreadable.is_empty()
+    }
+}
+
+//INFO: Tries to decrypt every stored token and reports the ones that fail
+pub fn check_credentials(connection: &Connection) -> Result<CredentialsHealth> {
+    let tokens = get_all_api_tokens(connection)?;
+    let total = tokens.len();
+
+    let key = match get_or_create_encryption_key() {
+        Ok(key) => key,
+        Err(e) => {
+            return Ok(CredentialsHealth {
+                key_error: Some(format!("{:#}", e)),
+                unreadable: tokens.into_iter().map(|(provider, _)| provider).collect(),
+                total,
+            });
+        }
+    };
+
+    let unreadable = tokens
+        .into_iter()
+        .filter(|(_, encrypted)| decrypt_with_key(&key, encrypted).is_err())
+        .map(|(provider, _)| provider)
+        .collect();
+
+    Ok(CredentialsHealth {
+        key_error: None,
+        unreadable,
+        total,
+    })
+}
+
+//INFO: Deletes the tokens that can't be decrypted, returns the providers that were cleared
+//NOTE: If the key itself can't be loaded it's replaced with a fresh one first, which makes
+//      every stored token unreadable, so all of them are cleared. Readable tokens are kept.
+pub fn clear_unreadable_credentials(connection: &Connection) -> Result<Vec<String>> {
+    let health = check_credentials(connection)?;
+    if health.key_error.is_some() {
+        replace_unusable_encryption_key().context("Failed to replace the encryption key")?;
+    }
+
+    for provider in &health.unreadable {
+        delete_api_token(connection, provider)?;
+    }
+    if !health.unreadable.is_empty() {
+        tracing::warn!(
+            "🔐 Encryption: Cleared unreadable credentials for {}",
+            health.unreadable.join(", ")
+        );
+    }
+    Ok(health.unreadable)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::encrypt_token;
+    use crate::crypto::encryption::encrypt_with_key;
+    use crate::database::queries::{get_api_token, save_api_token};
+
+    #[test]
+    fn test_clears_only_tokens_the_current_key_cannot_read() {
+        let connection = Connection::open_in_memory().unwrap();
+        crate::database::initialize_database(&connection).unwrap();
+
+        let readable = encrypt_token("good-key").unwrap();
+        let foreign = encrypt_with_key(&[7u8; 32], "lost-key").unwrap();
+        save_api_token(&connection, "gemini", &readable, "api_key").unwrap();
+        save_api_token(&connection, "google", &foreign, "oauth2").unwrap();
+
+        let health = check_credentials(&connection).unwrap();
+        assert!(health.key_error.is_none());
+        assert_eq!(health.unreadable, vec!["google".to_string()]);
+        assert_eq!(health.total, 2);
+
+        assert_eq!(
+            clear_unreadable_credentials(&connection).unwrap(),
+            vec!["google".to_string()]
+        );
+        assert!(get_api_token(&connection, "google").unwrap().is_none());
+        assert!(get_api_token(&connection, "gemini").unwrap().is_some());
+        assert!(check_credentials(&connection).unwrap().is_healthy());
+    }
+}
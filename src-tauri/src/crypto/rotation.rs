@@ -0,0 +1,223 @@
+//INFO: Encryption key rotation for Lumen
+//NOTE: Re-encrypts every encrypted column under a fresh key, then swaps the key in
+
+use super::encryption::{
+    activate_staged_encryption_key, decrypt_with_key, discard_staged_encryption_key,
+    encrypt_with_key, get_or_create_encryption_key, lock_for_rotation, read_staged_encryption_key,
+    stage_new_encryption_key, KEY_LENGTH,
+};
+use crate::database::queries::{get_setting, save_setting};
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension, Transaction, TransactionBehavior};
+
+//INFO: Tables and columns holding ciphertext: (table, key column, encrypted column)
+const ENCRYPTED_COLUMNS: &[(&str, &str, &str)] = &[
+    ("api_tokens", "provider", "encrypted_token"),
+    ("clipboard_history", "id", "content"),
+];
+
+//INFO: Rotates the encryption key and re-encrypts all stored secrets, returns rows re-encrypted
+//NOTE: All rows are rewritten in one transaction. The new key is staged (`.key.new`) before the commit
+//      and only activated after it. A failure before the commit leaves the old key and data usable; a
+//      crash between the commit and the activation leaves the data under the staged key, which
+//      `recover_interrupted_rotation` promotes on the next startup. Encrypted writes wait on the rotation
+//      lock until the new key is active, so none can land under the old key after the commit.
+pub fn rotate_encryption_key(connection: &Connection) -> Result<usize> {
+    let _rotating = lock_for_rotation();
+    let old_key = get_or_create_encryption_key()?;
+    let new_key = stage_new_encryption_key()?;
+
+    let count = match reencrypt_all(connection, &old_key, &new_key) {
+        Ok(count) => count,
+        Err(e) => {
+            let _ = discard_staged_encryption_key();
+            return Err(e);
+        }
+    };
+
+    activate_staged_encryption_key(&new_key)?;
+    tracing::info!("🔐 Encryption: Rotated key and re-encrypted {} rows", count);
+    Ok(count)
+}
+
+//INFO: Finishes or rolls back a rotation that was interrupted, returns whether the staged key was promoted
+//NOTE: Runs at startup, before anything decrypts. Whichever key opens a stored row is the one the data
+//      is under: the staged key means the re-encryption committed, so it becomes the active key;
+//      otherwise the rotation never committed and the staged key is dropped.
+pub fn recover_interrupted_rotation(connection: &Connection) -> Result<bool> {
+    let staged = match read_staged_encryption_key() {
+        Ok(Some(key)) => key,
+        Ok(None) => return Ok(false),
+        Err(e) => {
+            tracing::warn!("⚠️ Encryption: Discarding unreadable staged key: {:#}", e);
+            discard_staged_encryption_key()?;
+            return Ok(false);
+        }
+    };
+
+    if let Some(sample) = sample_encrypted_value(connection)? {
+        if decrypt_with_key(&staged, &sample).is_ok() {
+            activate_staged_encryption_key(&staged)?;
+            tracing::warn!("🔐 Encryption: Finished an interrupted key rotation");
+            return Ok(true);
+        }
+    }
+
+    discard_staged_encryption_key()?;
+    tracing::warn!("🔐 Encryption: Discarded the key from an interrupted rotation");
+    Ok(false)
+}
+
+//INFO: One stored ciphertext to test a key against
+//NOTE: api_tokens first - clipboard rows saved before encryption may still be plaintext
+fn sample_encrypted_value(connection: &Connection) -> Result<Option<String>> {
+    for (table, _, column) in ENCRYPTED_COLUMNS {
+        let value: Option<String> = connection
+            .query_row(&format!("SELECT {} FROM {} LIMIT 1", column, table), [], |row| row.get(0))
+            .optional()
+            .with_context(|| format!("Failed to read {}", table))?;
+        if value.is_some() {
+            return Ok(value);
+        }
+    }
+    Ok(None)
+}
+
+//INFO: Settings key set once clipboard rows saved before encryption have been encrypted
+const CLIPBOARD_ENCRYPTED_SETTING: &str = "clipboard_plaintext_encrypted";
+
+//INFO: Encrypts clipboard rows saved before clipboard encryption existed, once; returns rows encrypted
+//NOTE: Runs at startup after `recover_interrupted_rotation`. A row that doesn't decrypt is taken to be
+//      plaintext, which is only safe when the current key is known good - so this waits (and retries on
+//      the next launch) while stored credentials can't be read.
+pub fn encrypt_plaintext_clipboard(connection: &Connection) -> Result<usize> {
+    if get_setting(connection, CLIPBOARD_ENCRYPTED_SETTING)?.is_some() {
+        return Ok(0);
+    }
+    if !super::recovery::check_credentials(connection)?.is_healthy() {
+        tracing::warn!("🔐 Encryption: Skipping clipboard encryption until credentials are readable");
+        return Ok(0);
+    }
+
+    let key = get_or_create_encryption_key()?;
+    let tx = connection
+        .unchecked_transaction()
+        .context("Failed to begin transaction")?;
+    let rows: Vec<(i64, String)> = {
+        let mut statement = tx
+            .prepare("SELECT id, content FROM clipboard_history")
+            .context("Failed to read clipboard_history")?;
+        let rows = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        rows
+    };
+
+    let mut count = 0;
+    for (id, stored) in rows {
+        if decrypt_with_key(&key, &stored).is_ok() {
+            continue;
+        }
+        tx.execute(
+            "UPDATE clipboard_history SET content = ?1 WHERE id = ?2",
+            params![encrypt_with_key(&key, &stored)?, id],
+        )
+        .context("Failed to encrypt a clipboard row")?;
+        count += 1;
+    }
+    save_setting(&tx, CLIPBOARD_ENCRYPTED_SETTING, "true")?;
+    tx.commit().context("Failed to commit clipboard encryption")?;
+
+    if count > 0 {
+        tracing::info!("🔐 Encryption: Encrypted {} clipboard entries saved before encryption", count);
+    }
+    Ok(count)
+}
+
+//INFO: Re-encrypts secrets written under another install's key with this install's key, returns rows re-encrypted
+//NOTE: Used on an imported database before it replaces the live one; fails without writing anything if
+//      `foreign_key` isn't the key the rows were encrypted with
+pub fn reencrypt_with_local_key(connection: &Connection, foreign_key: &[u8; KEY_LENGTH]) -> Result<usize> {
+    let local_key = get_or_create_encryption_key()?;
+    if *foreign_key == local_key {
+        return Ok(0);
+    }
+    reencrypt_all(connection, foreign_key, &local_key)
+}
+
+fn reencrypt_all(
+    connection: &Connection,
+    old_key: &[u8; KEY_LENGTH],
+    new_key: &[u8; KEY_LENGTH],
+) -> Result<usize> {
+    //NOTE: IMMEDIATE takes the write lock up front, so no other connection writes between the reads and updates
+    let tx = Transaction::new_unchecked(connection, TransactionBehavior::Immediate)
+        .context("Failed to begin transaction")?;
+    let mut count = 0;
+
+    for (table, key_column, column) in ENCRYPTED_COLUMNS {
+        let rows: Vec<(rusqlite::types::Value, String)> = {
+            let mut statement = tx
+                .prepare(&format!("SELECT {}, {} FROM {}", key_column, column, table))
+                .with_context(|| format!("Failed to read {}", table))?;
+            let rows = statement
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<rusqlite::Result<_>>()?;
+            rows
+        };
+
+        for (row_key, stored) in rows {
+            let plaintext = match decrypt_with_key(old_key, &stored) {
+                Ok(plaintext) => plaintext,
+                //NOTE: Clipboard rows saved before encryption are plaintext, encrypt them now
+                Err(_) if *table == "clipboard_history" => stored,
+                Err(e) => {
+                    return Err(e).with_context(|| format!("Failed to decrypt a row in {}", table))
+                }
+            };
+
+            tx.execute(
+                &format!("UPDATE {} SET {} = ?1 WHERE {} = ?2", table, column, key_column),
+                params![encrypt_with_key(new_key, &plaintext)?, row_key],
+            )
+            .with_context(|| format!("Failed to re-encrypt a row in {}", table))?;
+            count += 1;
+        }
+    }
+
+    tx.commit().context("Failed to commit re-encryption")?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{decrypt_token, encrypt_token};
+
+    #[test]
+    fn test_encrypts_plaintext_clipboard_rows_once() {
+        let connection = Connection::open_in_memory().unwrap();
+        crate::database::initialize_database(&connection).unwrap();
+        let encrypted = encrypt_token("already secret").unwrap();
+        for content in ["copied before encryption", encrypted.as_str()] {
+            connection
+                .execute(
+                    "INSERT INTO clipboard_history (content, type, created_at) VALUES (?1, 'text', 'now')",
+                    params![content],
+                )
+                .unwrap();
+        }
+
+        assert_eq!(encrypt_plaintext_clipboard(&connection).unwrap(), 1);
+        let stored: Vec<String> = connection
+            .prepare("SELECT content FROM clipboard_history ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(decrypt_token(&stored[0]).unwrap(), "copied before encryption");
+        assert_eq!(decrypt_token(&stored[1]).unwrap(), "already secret");
+        assert_eq!(encrypt_plaintext_clipboard(&connection).unwrap(), 0);
+    }
+}
@@ -0,0 +1,182 @@
+//INFO: Database backup and restore for Lumen
+//NOTE: Uses VACUUM INTO for consistent snapshots of the live database and the online backup API to restore
+
+use super::schema::{current_schema_version, initialize_database, latest_schema_version};
+use anyhow::{anyhow, Context, Result};
+use rusqlite::{Connection, DatabaseName, OpenFlags};
+use std::path::Path;
+
+//INFO: Tables that must exist for a file to be accepted as a Lumen database
+const REQUIRED_TABLES: &[&str] = &["user_profile", "settings", "api_tokens", "integrations"];
+
+//INFO: Tables kept in a "settings only" export; every other table is emptied
+//NOTE: An allowlist, so tables added later (memories, notifications, ...) stay out of the export by default
+const SETTINGS_TABLES: &[&str] = &[
+    "schema_version",
+    "user_profile",
+    "settings",
+    "hotkey_config",
+    "api_tokens",
+    "integrations",
+    "setup_status",
+];
+
+//INFO: Tables a "settings only" export empties - everything not in SETTINGS_TABLES
+//NOTE: A virtual table (the sqlite-vec index) is emptied through itself; its `<name>_*` shadow tables are
+//      managed by the extension and must not be touched directly
+fn private_tables(connection: &Connection) -> Result<Vec<String>> {
+    let mut statement = connection
+        .prepare("SELECT name, sql FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'")
+        .context("Failed to list tables")?;
+    let tables = statement
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to list tables")?;
+
+    let shadow_prefixes: Vec<String> = tables
+        .iter()
+        .filter(|(_, sql)| sql.as_deref().is_some_and(|s| s.starts_with("CREATE VIRTUAL TABLE")))
+        .map(|(name, _)| format!("{}_", name))
+        .collect();
+
+    Ok(tables
+        .into_iter()
+        .map(|(name, _)| name)
+        .filter(|name| !SETTINGS_TABLES.contains(&name.as_str()))
+        .filter(|name| !shadow_prefixes.iter().any(|prefix| name.starts_with(prefix.as_str())))
+        .collect())
+}
+
+//INFO: Writes a consistent snapshot of the database to `dest_path`
+//NOTE: VACUUM INTO runs inside a read transaction, so concurrent writes can't tear the snapshot
+pub fn export_database(connection: &Connection, dest_path: &Path, settings_only: bool) -> Result<()> {
+    if dest_path.exists() {
+        return Err(anyhow!("{} already exists", dest_path.display()));
+    }
+    if let Some(parent) = dest_path.parent() {
+        std::fs::create_dir_all(parent).context("Failed to create export directory")?;
+    }
+
+    connection
+        .execute(
+            "VACUUM INTO ?1",
+            [dest_path.to_string_lossy().to_string()],
+        )
+        .context("Failed to export database")?;
+
+    if settings_only {
+        let export = Connection::open(dest_path).context("Failed to open exported database")?;
+        for table in private_tables(&export)? {
+            export
+                .execute(&format!("DELETE FROM \"{}\"", table), [])
+                .with_context(|| format!("Failed to strip {} from export", table))?;
+        }
+        //INFO: Reclaim the freed pages so deleted rows don't linger in the file
+        export
+            .execute("VACUUM", [])
+            .context("Failed to compact exported database")?;
+    }
+
+    Ok(())
+}
+
+//INFO: Checks that a file is a readable, intact Lumen database
+pub fn validate_database_file(src_path: &Path) -> Result<()> {
+    let source = Connection::open_with_flags(src_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .context("Failed to open database to import")?;
+
+    let integrity: String = source
+        .query_row("PRAGMA quick_check", [], |row| row.get(0))
+        .context("Failed to check database integrity")?;
+    if integrity != "ok" {
+        return Err(anyhow!("Database failed integrity check: {}", integrity));
+    }
+
+    for table in REQUIRED_TABLES {
+        let exists: bool = source
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1)",
+                [table],
+                |row| row.get(0),
+            )
+            .context("Failed to inspect database schema")?;
+        if !exists {
+            return Err(anyhow!("Not a Lumen database (missing {} table)", table));
+        }
+    }
+
+    let version = current_schema_version(&source)?;
+    if version > latest_schema_version() {
+        return Err(anyhow!(
+            "Database schema version {} is newer than this app supports ({})",
+            version,
+            latest_schema_version()
+        ));
+    }
+
+    Ok(())
+}
+
+//INFO: Imports a lumen.db (and its encryption key) from another machine, returns secrets re-encrypted
+//NOTE: The backup is copied to a staging file, migrated, and its secrets re-encrypted with the local key
+//      there; only then does it replace the live database (via import_database, which keeps lumen.db.bak).
+//      A wrong key or a newer schema fails before anything live is touched, and the source file is never modified.
+pub fn import_backup_with_key(
+    connection: &mut Connection,
+    database_path: &Path,
+    src_path: &Path,
+    key_path: &Path,
+) -> Result<usize> {
+    let foreign_key = crate::crypto::encryption::read_foreign_key(key_path)?;
+    validate_database_file(src_path)?;
+
+    let staging_path = database_path.with_extension("db.import");
+    if staging_path.exists() {
+        std::fs::remove_file(&staging_path).context("Failed to remove previous import staging file")?;
+    }
+
+    let result = (|| -> Result<usize> {
+        let source = Connection::open_with_flags(src_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .context("Failed to open database to import")?;
+        export_database(&source, &staging_path, false).context("Failed to copy database to import")?;
+        drop(source);
+
+        let staged = Connection::open(&staging_path).context("Failed to open staged import")?;
+        initialize_database(&staged)?;
+        let reencrypted = crate::crypto::reencrypt_with_local_key(&staged, &foreign_key)
+            .context("The key file doesn't match this database")?;
+        drop(staged);
+
+        import_database(connection, database_path, &staging_path)?;
+        Ok(reencrypted)
+    })();
+
+    let _ = std::fs::remove_file(&staging_path);
+    result
+}
+
+//INFO: Replaces the live database with the contents of `src_path`
+//NOTE: The current database is first snapshotted next to itself as lumen.db.bak
+pub fn import_database(
+    connection: &mut Connection,
+    database_path: &Path,
+    src_path: &Path,
+) -> Result<()> {
+    validate_database_file(src_path)?;
+
+    let backup_path = database_path.with_extension("db.bak");
+    if backup_path.exists() {
+        std::fs::remove_file(&backup_path).context("Failed to remove previous backup")?;
+    }
+    export_database(connection, &backup_path, false)
+        .context("Failed to back up current database before import")?;
+
+    connection
+        .restore(DatabaseName::Main, src_path, None::<fn(rusqlite::backup::Progress)>)
+        .context("Failed to restore database")?;
+
+    //INFO: Bring an older backup up to the current schema
+    initialize_database(connection)?;
+
+    Ok(())
+}
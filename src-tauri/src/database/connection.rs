@@ -2,21 +2,105 @@
 //NOTE: Uses SQLite with a single portable file stored in user's config directory
 
 use anyhow::{Context, Result};
-use parking_lot::Mutex;
-use rusqlite::Connection;
-use std::path::PathBuf;
-use std::sync::Arc;
+use r2d2::{Pool, PooledConnection};
+use r2d2_sqlite::SqliteConnectionManager;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+//INFO: Max pooled connections - enough for chat, dashboard and the background agents at once
+const POOL_SIZE: u32 = 8;
+
+//INFO: How long SQLite waits on a locked database before giving up with SQLITE_BUSY
+const BUSY_TIMEOUT_MS: u32 = 5000;
+
+//INFO: How long a checkout waits for a pooled connection to free up
+const CHECKOUT_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub type PooledSqlite = PooledConnection<SqliteConnectionManager>;
+
+//INFO: Connection-scoped pragmas, run on every new connection
+fn init_connection(connection: &mut rusqlite::Connection) -> rusqlite::Result<()> {
+    connection.execute_batch(&format!(
+        "PRAGMA foreign_keys = ON; PRAGMA busy_timeout = {};",
+        BUSY_TIMEOUT_MS
+    ))
+}
+
+//INFO: A connection checked out by `ConnectionPool::lock`
+pub enum DbConnection {
+    Pooled(PooledSqlite),
+    //INFO: Opened outside the pool because every pooled connection stayed busy; closed on drop
+    Dedicated(rusqlite::Connection),
+}
+
+impl Deref for DbConnection {
+    type Target = rusqlite::Connection;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            DbConnection::Pooled(connection) => connection,
+            DbConnection::Dedicated(connection) => connection,
+        }
+    }
+}
+
+impl DerefMut for DbConnection {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            DbConnection::Pooled(connection) => connection,
+            DbConnection::Dedicated(connection) => connection,
+        }
+    }
+}
+
+//INFO: Pool of SQLite connections behind the old `connection.lock()` API
+//NOTE: Cloning is cheap (the pool is reference counted)
+#[derive(Clone)]
+pub struct ConnectionPool {
+    pool: Pool<SqliteConnectionManager>,
+    database_path: PathBuf,
+}
+
+impl ConnectionPool {
+    //INFO: Checks out a connection, waiting at most CHECKOUT_TIMEOUT for a pooled one
+    //NOTE: If the pool is still exhausted after the wait (something holds its connections far too long),
+    //      the error is logged and a dedicated connection is opened instead of waiting forever; SQLite's
+    //      busy_timeout bounds any lock waits on it. Fails only if that connection can't be opened either
+    //      (disk full, permissions). Use `get` to handle the exhausted pool as an error instead.
+    pub fn lock(&self) -> Result<DbConnection> {
+        match self.get() {
+            Ok(connection) => Ok(DbConnection::Pooled(connection)),
+            Err(e) => {
+                tracing::error!("❌ Database: {:#}, opening a dedicated connection", e);
+                self.open_dedicated().map(DbConnection::Dedicated)
+            }
+        }
+    }
+
+    fn open_dedicated(&self) -> Result<rusqlite::Connection> {
+        let mut connection = rusqlite::Connection::open(&self.database_path)
+            .context("Failed to open a dedicated database connection")?;
+        init_connection(&mut connection).context("Failed to set up a dedicated database connection")?;
+        Ok(connection)
+    }
+
+    //INFO: Checks out a pooled connection, failing once CHECKOUT_TIMEOUT passes without one freeing up
+    pub fn get(&self) -> Result<PooledSqlite> {
+        self.pool.get().context("Timed out waiting for a database connection")
+    }
+}
 
 //INFO: Thread-safe database wrapper
-//NOTE: Wrapped in Arc<Mutex> for safe concurrent access and cheap cloning
+//NOTE: Backed by a connection pool in WAL mode so readers and writers don't serialize on one lock
 #[derive(Clone)]
 pub struct Database {
-    pub connection: Arc<Mutex<Connection>>,
+    pub connection: ConnectionPool,
     pub database_path: PathBuf,
 }
 
 impl Database {
-    //INFO: Creates a new database connection
+    //INFO: Creates a new database connection pool
     //NOTE: Automatically creates the database file and parent directories if they don't exist
     pub fn new() -> Result<Self> {
         //INFO: Get the platform-appropriate config directory for storing the database
@@ -26,8 +110,11 @@ impl Database {
         std::fs::create_dir_all(&config_directory).context("Failed to create config directory")?;
 
         //INFO: Construct the full path to the database file
-        let database_path = config_directory.join("lumen.db");
+        Self::open(&config_directory.join("lumen.db"))
+    }
 
+    //INFO: Opens (or creates) a database pool at a specific path
+    pub fn open(database_path: &Path) -> Result<Self> {
         //INFO: Register sqlite-vec extension for vector operations (must be done before opening any connection)
         unsafe {
             rusqlite::ffi::sqlite3_auto_extension(Some(std::mem::transmute(
@@ -35,18 +122,27 @@ impl Database {
             )));
         }
 
-        //INFO: Open or create the SQLite database connection
-        let connection =
-            Connection::open(&database_path).context("Failed to open database connection")?;
+        //INFO: Per-connection setup - foreign keys and busy timeout are connection-scoped pragmas
+        let manager = SqliteConnectionManager::file(database_path).with_init(init_connection);
 
-        //INFO: Enable foreign key support for referential integrity
-        connection
-            .execute("PRAGMA foreign_keys = ON", [])
-            .context("Failed to enable foreign keys")?;
+        let pool = Pool::builder()
+            .max_size(POOL_SIZE)
+            .connection_timeout(CHECKOUT_TIMEOUT)
+            .build(manager)
+            .context("Failed to open database connection pool")?;
+
+        //INFO: WAL is persistent in the file, so setting it once is enough
+        pool.get()
+            .context("Failed to open database connection")?
+            .query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))
+            .context("Failed to enable WAL mode")?;
 
         Ok(Self {
-            connection: Arc::new(Mutex::new(connection)),
-            database_path,
+            connection: ConnectionPool {
+                pool,
+                database_path: database_path.to_path_buf(),
+            },
+            database_path: database_path.to_path_buf(),
         })
     }
 
@@ -76,4 +172,82 @@ mod tests {
         let database = Database::new();
         assert!(database.is_ok());
     }
+
+    #[test]
+    fn test_connections_do_not_serialize() {
+        //INFO: With the old single Mutex, holding one connection while taking another deadlocked
+        let path = std::env::temp_dir().join(format!("lumen-pool-{}.db", std::process::id()));
+        let database = Database::open(&path).unwrap();
+
+        let first = database.connection.lock().unwrap();
+        let second = database.connection.lock().unwrap();
+        first.execute_batch("CREATE TABLE IF NOT EXISTS t (x INTEGER)").unwrap();
+        let count: i64 = second
+            .query_row("SELECT COUNT(*) FROM t", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 0);
+
+        drop((first, second, database));
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    //INFO: Chat-style inserts while a proactive-agent-style poller holds its connection between queries;
+    //      returns how long the chat side took
+    fn time_chat_beside_agent<G: Deref<Target = rusqlite::Connection>>(checkout: &(dyn Fn() -> G + Sync)) -> Duration {
+        const ROUNDS: usize = 200;
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                for _ in 0..ROUNDS {
+                    let connection = checkout();
+                    let _: i64 = connection
+                        .query_row("SELECT COUNT(*) FROM messages", [], |row| row.get(0))
+                        .unwrap();
+                    //NOTE: Stands in for the network call the agent makes with the connection still checked out
+                    std::thread::sleep(Duration::from_millis(2));
+                }
+            });
+
+            let started = std::time::Instant::now();
+            for i in 0..ROUNDS {
+                checkout()
+                    .execute("INSERT INTO messages (body) VALUES (?1)", [format!("message {}", i)])
+                    .unwrap();
+            }
+            started.elapsed()
+        })
+    }
+
+    //INFO: Compares the pool against the single Mutex<Connection> it replaced
+    //NOTE: A benchmark, so ignored by default: `cargo test --release bench_chat_beside_agent -- --ignored`
+    #[test]
+    #[ignore]
+    fn bench_chat_beside_agent() {
+        let path = std::env::temp_dir().join(format!("lumen-bench-{}.db", std::process::id()));
+        let database = Database::open(&path).unwrap();
+        database
+            .connection
+            .lock()
+            .unwrap()
+            .execute_batch("CREATE TABLE IF NOT EXISTS messages (id INTEGER PRIMARY KEY, body TEXT)")
+            .unwrap();
+
+        let mut single = rusqlite::Connection::open(&path).unwrap();
+        init_connection(&mut single).unwrap();
+        let mutex = parking_lot::Mutex::new(single);
+        let with_mutex = time_chat_beside_agent(&|| mutex.lock());
+        let with_pool = time_chat_beside_agent(&|| database.connection.lock().unwrap());
+        assert!(
+            with_pool < with_mutex,
+            "pool ({:?}) should beat the single mutex ({:?})",
+            with_pool,
+            with_mutex
+        );
+
+        drop((mutex, database));
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
 }
@@ -1,6 +1,7 @@
 //INFO: Database module - handles all SQLite operations for Lumen
 //NOTE: Single file database for complete portability
 
+pub mod backup;
 pub mod connection;
 pub mod queries;
 pub mod schema;
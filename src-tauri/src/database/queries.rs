@@ -12,6 +12,11 @@ pub struct UserProfile {
     pub display_name: String,
     pub location: Option<String>,
     pub theme: String,
+    //INFO: IANA timezone name reported by the frontend; None until the user saves their profile
+    pub timezone: Option<String>,
+    //INFO: Working hours as local "HH:MM" (see `agent::proactive::WorkingHours`)
+    pub work_start: Option<String>,
+    pub work_end: Option<String>,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -51,6 +56,18 @@ pub struct ChatMessage {
     pub session_id: Option<String>,
 }
 
+//INFO: Chat session data structure
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChatSession {
+    pub id: String,
+    pub title: String,
+    pub created_at: String,
+    pub updated_at: String,
+    //INFO: Running summary of the messages up to and including `summarized_through`
+    pub summary: Option<String>,
+    pub summarized_through: Option<i64>,
+}
+
 //INFO: Calendar event data structure
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CalendarEvent {
@@ -71,6 +88,22 @@ pub struct Integration {
     pub config: Option<String>,
     pub last_sync: Option<String>,
     pub status: String,
+    //INFO: Why the last refresh or fetch failed, cleared by the next success
+    pub last_error: Option<String>,
+    pub last_error_at: Option<String>,
+}
+
+//INFO: Reminder data structure
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Reminder {
+    pub id: i64,
+    pub content: String,
+    pub due_at: Option<String>,
+    pub completed: bool,
+    pub created_at: String,
+    //INFO: "google_calendar" or "google_tasks" once promoted, with the remote item's id
+    pub linked_to: Option<String>,
+    pub linked_id: Option<String>,
 }
 
 //INFO: Briefing summary data structure
@@ -119,15 +152,18 @@ pub fn mark_setup_complete(connection: &Connection) -> Result<()> {
 pub fn get_user_profile(connection: &Connection) -> Result<Option<UserProfile>> {
     let result = connection
         .query_row(
-            "SELECT display_name, location, theme, created_at, updated_at FROM user_profile WHERE id = 1",
+            "SELECT display_name, location, theme, timezone, work_start, work_end, created_at, updated_at FROM user_profile WHERE id = 1",
             [],
             |row| {
                 Ok(UserProfile {
                     display_name: row.get(0)?,
                     location: row.get(1)?,
                     theme: row.get(2)?,
-                    created_at: row.get(3)?,
-                    updated_at: row.get(4)?,
+                    timezone: row.get(3)?,
+                    work_start: row.get(4)?,
+                    work_end: row.get(5)?,
+                    created_at: row.get(6)?,
+                    updated_at: row.get(7)?,
                 })
             },
         )
@@ -137,12 +173,25 @@ pub fn get_user_profile(connection: &Connection) -> Result<Option<UserProfile>>
     Ok(result)
 }
 
+//INFO: The user's IANA timezone, if they've saved one
+pub fn get_user_timezone(connection: &Connection) -> Option<String> {
+    get_user_profile(connection)
+        .ok()
+        .flatten()
+        .and_then(|p| p.timezone)
+        .filter(|tz| !tz.trim().is_empty())
+}
+
 //INFO: Saves or updates the user profile
+//NOTE: A None timezone or working hour keeps whatever was stored before; an empty working hour clears it
 pub fn save_user_profile(
     connection: &Connection,
     display_name: &str,
     location: Option<&str>,
     theme: &str,
+    timezone: Option<&str>,
+    work_start: Option<&str>,
+    work_end: Option<&str>,
 ) -> Result<()> {
     let now = Utc::now().to_rfc3339();
 
@@ -152,14 +201,16 @@ pub fn save_user_profile(
     if existing.is_some() {
         //INFO: Update existing profile
         connection.execute(
-            "UPDATE user_profile SET display_name = ?1, location = ?2, theme = ?3, updated_at = ?4 WHERE id = 1",
-            params![display_name, location, theme, now],
+            "UPDATE user_profile SET display_name = ?1, location = ?2, theme = ?3, updated_at = ?4, timezone = COALESCE(?5, timezone),
+             work_start = NULLIF(COALESCE(?6, work_start), ''), work_end = NULLIF(COALESCE(?7, work_end), '') WHERE id = 1",
+            params![display_name, location, theme, now, timezone, work_start, work_end],
         ).context("Failed to update user profile")?;
     } else {
         //INFO: Insert new profile
         connection.execute(
-            "INSERT INTO user_profile (id, display_name, location, theme, created_at, updated_at) VALUES (1, ?1, ?2, ?3, ?4, ?4)",
-            params![display_name, location, theme, now],
+            "INSERT INTO user_profile (id, display_name, location, theme, timezone, work_start, work_end, created_at, updated_at)
+             VALUES (1, ?1, ?2, ?3, ?5, NULLIF(?6, ''), NULLIF(?7, ''), ?4, ?4)",
+            params![display_name, location, theme, now, timezone, work_start, work_end],
         ).context("Failed to insert user profile")?;
     }
 
@@ -255,6 +306,26 @@ pub fn get_api_token(connection: &Connection, provider: &str) -> Result<Option<S
     Ok(result)
 }
 
+//INFO: Gets every stored (provider, encrypted token) pair
+pub fn get_all_api_tokens(connection: &Connection) -> Result<Vec<(String, String)>> {
+    let mut statement = connection
+        .prepare("SELECT provider, encrypted_token FROM api_tokens ORDER BY provider")
+        .context("Failed to prepare API tokens query")?;
+    let tokens = statement
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()
+        .context("Failed to query API tokens")?;
+    Ok(tokens)
+}
+
+//INFO: Deletes the API token for a provider
+pub fn delete_api_token(connection: &Connection, provider: &str) -> Result<()> {
+    connection
+        .execute("DELETE FROM api_tokens WHERE provider = ?1", params![provider])
+        .context("Failed to delete API token")?;
+    Ok(())
+}
+
 //INFO: Checks if an API token exists for a provider
 #[allow(dead_code)]
 pub fn has_api_token(connection: &Connection, provider: &str) -> Result<bool> {
@@ -352,6 +423,241 @@ pub fn clear_chat_messages(connection: &Connection) -> Result<()> {
     connection
         .execute("DELETE FROM chat_messages", [])
         .context("Failed to clear chat messages")?;
+    connection
+        .execute("DELETE FROM chat_sessions", [])
+        .context("Failed to clear chat sessions")?;
+    Ok(())
+}
+
+//INFO: Deletes a single chat message, returns how many rows were removed
+//NOTE: With `cascade_reply`, deleting a user message also removes the assistant reply that followed it
+pub fn delete_chat_message(connection: &Connection, id: i64, cascade_reply: bool) -> Result<usize> {
+    let target: Option<(String, Option<String>)> = connection
+        .query_row(
+            "SELECT role, session_id FROM chat_messages WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .context("Failed to look up chat message")?;
+
+    let Some((role, session_id)) = target else {
+        return Ok(0);
+    };
+
+    let tx = connection
+        .unchecked_transaction()
+        .context("Failed to begin transaction")?;
+
+    let mut deleted = tx
+        .execute("DELETE FROM chat_messages WHERE id = ?1", params![id])
+        .context("Failed to delete chat message")?;
+
+    if cascade_reply && role == "user" {
+        //INFO: The paired reply is the next message in the same session, if it's from the assistant
+        let reply_id: Option<(i64, String)> = tx
+            .query_row(
+                "SELECT id, role FROM chat_messages
+                 WHERE id > ?1 AND session_id IS ?2
+                 ORDER BY id ASC LIMIT 1",
+                params![id, session_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .context("Failed to look up paired reply")?;
+
+        if let Some((reply_id, reply_role)) = reply_id {
+            if reply_role == "assistant" {
+                deleted += tx
+                    .execute("DELETE FROM chat_messages WHERE id = ?1", params![reply_id])
+                    .context("Failed to delete paired reply")?;
+            }
+        }
+    }
+
+    tx.commit().context("Failed to commit message deletion")?;
+
+    if let Some(sid) = session_id.as_deref() {
+        touch_session(connection, sid)?;
+    }
+    Ok(deleted)
+}
+
+//INFO: Deletes the most recent `count` messages of a session (or of sessionless chat when None)
+pub fn delete_last_messages(
+    connection: &Connection,
+    session_id: Option<&str>,
+    count: u32,
+) -> Result<usize> {
+    let deleted = connection
+        .execute(
+            "DELETE FROM chat_messages WHERE id IN (
+                SELECT id FROM chat_messages WHERE session_id IS ?1 ORDER BY id DESC LIMIT ?2
+            )",
+            params![session_id, count],
+        )
+        .context("Failed to delete last chat messages")?;
+
+    if let Some(sid) = session_id {
+        touch_session(connection, sid)?;
+    }
+    Ok(deleted)
+}
+
+// ============================================================================
+// Chat Session Queries
+// ============================================================================
+
+//INFO: Creates a new chat session
+pub fn create_session(connection: &Connection, id: &str, title: &str) -> Result<ChatSession> {
+    let now = Utc::now().to_rfc3339();
+    connection
+        .execute(
+            "INSERT INTO chat_sessions (id, title, created_at, updated_at) VALUES (?1, ?2, ?3, ?3)",
+            params![id, title, now],
+        )
+        .context("Failed to create chat session")?;
+
+    Ok(ChatSession {
+        id: id.to_string(),
+        title: title.to_string(),
+        created_at: now.clone(),
+        updated_at: now,
+        summary: None,
+        summarized_through: None,
+    })
+}
+
+//INFO: Gets a chat session by id
+pub fn get_session(connection: &Connection, id: &str) -> Result<Option<ChatSession>> {
+    connection
+        .query_row(
+            "SELECT id, title, created_at, updated_at, summary, summarized_through FROM chat_sessions WHERE id = ?1",
+            params![id],
+            |row| {
+                Ok(ChatSession {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    created_at: row.get(2)?,
+                    updated_at: row.get(3)?,
+                    summary: row.get(4)?,
+                    summarized_through: row.get(5)?,
+                })
+            },
+        )
+        .optional()
+        .context("Failed to query chat session")
+}
+
+//INFO: Lists all chat sessions, most recently active first
+pub fn list_sessions(connection: &Connection) -> Result<Vec<ChatSession>> {
+    let mut statement = connection
+        .prepare("SELECT id, title, created_at, updated_at, summary, summarized_through FROM chat_sessions ORDER BY updated_at DESC")
+        .context("Failed to prepare chat sessions query")?;
+
+    let rows = statement
+        .query_map([], |row| {
+            Ok(ChatSession {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                created_at: row.get(2)?,
+                updated_at: row.get(3)?,
+                summary: row.get(4)?,
+                summarized_through: row.get(5)?,
+            })
+        })
+        .context("Failed to query chat sessions")?;
+
+    let mut sessions = Vec::new();
+    for row in rows {
+        sessions.push(row.context("Failed to parse chat session")?);
+    }
+    Ok(sessions)
+}
+
+//INFO: Renames a chat session
+pub fn rename_session(connection: &Connection, id: &str, title: &str) -> Result<()> {
+    let now = Utc::now().to_rfc3339();
+    connection
+        .execute(
+            "UPDATE chat_sessions SET title = ?1, updated_at = ?2 WHERE id = ?3",
+            params![title, now, id],
+        )
+        .context("Failed to rename chat session")?;
+    Ok(())
+}
+
+//INFO: Bumps a session's updated_at so it sorts to the top of the list
+pub fn touch_session(connection: &Connection, id: &str) -> Result<()> {
+    let now = Utc::now().to_rfc3339();
+    connection
+        .execute(
+            "UPDATE chat_sessions SET updated_at = ?1 WHERE id = ?2",
+            params![now, id],
+        )
+        .context("Failed to update chat session")?;
+    Ok(())
+}
+
+//INFO: Messages of a session newer than `after_id` (all of them when None), oldest first
+pub fn get_session_messages_after(
+    connection: &Connection,
+    session_id: &str,
+    after_id: Option<i64>,
+) -> Result<Vec<ChatMessage>> {
+    let mut statement = connection
+        .prepare(
+            "SELECT id, role, content, image_data, created_at, session_id FROM chat_messages
+             WHERE session_id = ?1 AND id > ?2 ORDER BY id ASC",
+        )
+        .context("Failed to prepare session messages query")?;
+
+    let rows = statement
+        .query_map(params![session_id, after_id.unwrap_or(0)], |row| {
+            Ok(ChatMessage {
+                id: Some(row.get(0)?),
+                role: row.get(1)?,
+                content: row.get(2)?,
+                image_data: row.get(3)?,
+                created_at: row.get(4)?,
+                session_id: row.get(5)?,
+            })
+        })
+        .context("Failed to query session messages")?;
+
+    let mut messages = Vec::new();
+    for row in rows {
+        messages.push(row.context("Failed to parse chat message")?);
+    }
+    Ok(messages)
+}
+
+//INFO: Stores a session's running summary and the last message id it covers
+pub fn save_session_summary(
+    connection: &Connection,
+    id: &str,
+    summary: &str,
+    summarized_through: i64,
+) -> Result<()> {
+    connection
+        .execute(
+            "UPDATE chat_sessions SET summary = ?1, summarized_through = ?2 WHERE id = ?3",
+            params![summary, summarized_through, id],
+        )
+        .context("Failed to save session summary")?;
+    Ok(())
+}
+
+//INFO: Deletes a chat session and all of its messages
+pub fn delete_session(connection: &Connection, id: &str) -> Result<()> {
+    let tx = connection
+        .unchecked_transaction()
+        .context("Failed to begin transaction")?;
+    tx.execute("DELETE FROM chat_messages WHERE session_id = ?1", params![id])
+        .context("Failed to delete session messages")?;
+    tx.execute("DELETE FROM chat_sessions WHERE id = ?1", params![id])
+        .context("Failed to delete chat session")?;
+    tx.commit().context("Failed to commit session deletion")?;
     Ok(())
 }
 
@@ -363,7 +669,7 @@ pub fn clear_chat_messages(connection: &Connection) -> Result<()> {
 pub fn get_integration(connection: &Connection, name: &str) -> Result<Option<Integration>> {
     let result = connection
         .query_row(
-            "SELECT name, enabled, config, last_sync, status FROM integrations WHERE name = ?1",
+            "SELECT name, enabled, config, last_sync, status, last_error, last_error_at FROM integrations WHERE name = ?1",
             params![name],
             |row| {
                 Ok(Integration {
@@ -372,6 +678,8 @@ pub fn get_integration(connection: &Connection, name: &str) -> Result<Option<Int
                     config: row.get(2)?,
                     last_sync: row.get(3)?,
                     status: row.get(4)?,
+                    last_error: row.get(5)?,
+                    last_error_at: row.get(6)?,
                 })
             },
         )
@@ -384,13 +692,15 @@ pub fn get_integration(connection: &Connection, name: &str) -> Result<Option<Int
 //INFO: Saves or updates an integration
 pub fn save_integration(connection: &Connection, integration: &Integration) -> Result<()> {
     connection.execute(
-        "INSERT OR REPLACE INTO integrations (name, enabled, config, last_sync, status) VALUES (?1, ?2, ?3, ?4, ?5)",
+        "INSERT OR REPLACE INTO integrations (name, enabled, config, last_sync, status, last_error, last_error_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
         params![
             integration.name,
             integration.enabled as i32,
             integration.config,
             integration.last_sync,
-            integration.status
+            integration.status,
+            integration.last_error,
+            integration.last_error_at
         ],
     ).context("Failed to save integration")?;
     Ok(())
@@ -400,7 +710,7 @@ pub fn save_integration(connection: &Connection, integration: &Integration) -> R
 pub fn get_all_integrations(connection: &Connection) -> Result<Vec<Integration>> {
     let mut integrations = Vec::new();
     let mut statement = connection
-        .prepare("SELECT name, enabled, config, last_sync, status FROM integrations")
+        .prepare("SELECT name, enabled, config, last_sync, status, last_error, last_error_at FROM integrations")
         .context("Failed to prepare integrations query")?;
 
     let rows = statement
@@ -411,6 +721,8 @@ pub fn get_all_integrations(connection: &Connection) -> Result<Vec<Integration>>
                 config: row.get(2)?,
                 last_sync: row.get(3)?,
                 status: row.get(4)?,
+                last_error: row.get(5)?,
+                last_error_at: row.get(6)?,
             })
         })
         .context("Failed to query integrations")?;
@@ -422,6 +734,63 @@ pub fn get_all_integrations(connection: &Connection) -> Result<Vec<Integration>>
     Ok(integrations)
 }
 
+//INFO: Records why an integration's last refresh or fetch failed
+pub fn set_integration_error(connection: &Connection, name: &str, error: &str) -> Result<()> {
+    connection
+        .execute(
+            "UPDATE integrations SET last_error = ?2, last_error_at = ?3 WHERE name = ?1",
+            params![name, error, Utc::now().to_rfc3339()],
+        )
+        .context("Failed to record integration error")?;
+    Ok(())
+}
+
+//INFO: Integration status for a sign-in that has to be redone (a revoked grant or a missing scope)
+pub const NEEDS_REAUTH_STATUS: &str = "needs_reauth";
+
+//INFO: Marks an integration as needing a fresh sign-in, with `reason` as its last error
+//NOTE: Only signing in again sets the status back, so later successful calls don't hide the prompt
+pub fn mark_integration_needs_reauth(connection: &Connection, name: &str, reason: &str) -> Result<()> {
+    connection
+        .execute(
+            "UPDATE integrations SET status = ?2, last_error = ?3, last_error_at = ?4 WHERE name = ?1",
+            params![name, NEEDS_REAUTH_STATUS, reason, Utc::now().to_rfc3339()],
+        )
+        .context("Failed to mark integration for re-authentication")?;
+    Ok(())
+}
+
+//INFO: Sets an integration's status, and its last_sync when `synced_at` is given, leaving the rest of the row alone
+//NOTE: Leaves a pending re-authentication alone; see `mark_integration_needs_reauth`
+pub fn set_integration_status(
+    connection: &Connection,
+    name: &str,
+    status: &str,
+    synced_at: Option<&str>,
+) -> Result<()> {
+    connection
+        .execute(
+            "UPDATE integrations SET status = ?2, last_sync = COALESCE(?3, last_sync)
+             WHERE name = ?1 AND status != ?4",
+            params![name, status, synced_at, NEEDS_REAUTH_STATUS],
+        )
+        .context("Failed to update integration status")?;
+    Ok(())
+}
+
+//INFO: Clears an integration's last error after a successful refresh or fetch
+//NOTE: Leaves a pending re-authentication alone; see `mark_integration_needs_reauth`
+pub fn clear_integration_error(connection: &Connection, name: &str) -> Result<()> {
+    connection
+        .execute(
+            "UPDATE integrations SET last_error = NULL, last_error_at = NULL
+             WHERE name = ?1 AND last_error IS NOT NULL AND status != ?2",
+            params![name, NEEDS_REAUTH_STATUS],
+        )
+        .context("Failed to clear integration error")?;
+    Ok(())
+}
+
 // ============================================================================
 // Settings Queries
 // ============================================================================
@@ -452,12 +821,19 @@ pub fn save_setting(connection: &Connection, key: &str, value: &str) -> Result<(
     Ok(())
 }
 
+//INFO: Deletes a setting (no-op if it doesn't exist)
+pub fn delete_setting(connection: &Connection, key: &str) -> Result<()> {
+    connection
+        .execute("DELETE FROM settings WHERE key = ?1", params![key])
+        .context("Failed to delete setting")?;
+    Ok(())
+}
+
 // ============================================================================
 // Calendar Queries
 // ============================================================================
 
 //INFO: Saves calendar events (bulk insert/update)
-#[allow(dead_code)]
 pub fn save_calendar_events(connection: &Connection, events: &[CalendarEvent]) -> Result<()> {
     let now = Utc::now().to_rfc3339();
 
@@ -518,8 +894,42 @@ pub fn get_calendar_events(
     Ok(events)
 }
 
+//INFO: Replaces the cached events that start within a range with a fresh fetch
+//NOTE: Deleting first drops events that were removed upstream since the last fetch
+pub fn replace_calendar_events_in_range(
+    connection: &Connection,
+    start_date: &str,
+    end_date: &str,
+    events: &[CalendarEvent],
+) -> Result<()> {
+    let tx = connection
+        .unchecked_transaction()
+        .context("Failed to begin transaction")?;
+    tx.execute(
+        "DELETE FROM calendar_events WHERE start_time >= ?1 AND start_time <= ?2",
+        params![start_date, end_date],
+    )
+    .context("Failed to clear cached calendar range")?;
+    save_calendar_events(&tx, events)?;
+    tx.commit().context("Failed to commit calendar cache")
+}
+
+//INFO: When the cached events in a range were last refreshed (None if nothing is cached)
+pub fn get_calendar_cache_time(
+    connection: &Connection,
+    start_date: &str,
+    end_date: &str,
+) -> Result<Option<String>> {
+    connection
+        .query_row(
+            "SELECT MAX(cached_at) FROM calendar_events WHERE start_time >= ?1 AND start_time <= ?2",
+            params![start_date, end_date],
+            |row| row.get(0),
+        )
+        .context("Failed to read calendar cache time")
+}
+
 //INFO: Clears all cached calendar events
-#[allow(dead_code)]
 pub fn clear_calendar_events(connection: &Connection) -> Result<()> {
     connection
         .execute("DELETE FROM calendar_events", [])
@@ -527,6 +937,537 @@ pub fn clear_calendar_events(connection: &Connection) -> Result<()> {
     Ok(())
 }
 
+// ============================================================================
+// Reminder Queries
+// ============================================================================
+
+fn row_to_reminder(row: &rusqlite::Row) -> rusqlite::Result<Reminder> {
+    Ok(Reminder {
+        id: row.get(0)?,
+        content: row.get(1)?,
+        due_at: row.get(2)?,
+        completed: row.get::<_, i32>(3)? == 1,
+        created_at: row.get(4)?,
+        linked_to: row.get(5)?,
+        linked_id: row.get(6)?,
+    })
+}
+
+//INFO: Gets all reminders that are not yet completed
+pub fn get_pending_reminders(connection: &Connection) -> Result<Vec<Reminder>> {
+    let mut statement = connection
+        .prepare(
+            "SELECT id, content, due_at, completed, created_at, linked_to, linked_id FROM reminders WHERE completed = 0 ORDER BY due_at ASC",
+        )
+        .context("Failed to prepare reminders query")?;
+
+    let rows = statement
+        .query_map([], row_to_reminder)
+        .context("Failed to query reminders")?;
+
+    let mut reminders = Vec::new();
+    for row in rows {
+        reminders.push(row.context("Failed to parse reminder")?);
+    }
+    Ok(reminders)
+}
+
+//INFO: Gets a single reminder by id
+pub fn get_reminder(connection: &Connection, id: i64) -> Result<Option<Reminder>> {
+    connection
+        .query_row(
+            "SELECT id, content, due_at, completed, created_at, linked_to, linked_id FROM reminders WHERE id = ?1",
+            params![id],
+            row_to_reminder,
+        )
+        .optional()
+        .context("Failed to get reminder")
+}
+
+//INFO: Records that a reminder now lives in another service (e.g. "google_calendar")
+pub fn link_reminder(connection: &Connection, id: i64, linked_to: &str, linked_id: &str) -> Result<bool> {
+    let updated = connection
+        .execute(
+            "UPDATE reminders SET linked_to = ?1, linked_id = ?2 WHERE id = ?3",
+            params![linked_to, linked_id, id],
+        )
+        .context("Failed to link reminder")?;
+    Ok(updated > 0)
+}
+
+//INFO: Adds a reminder and returns its id
+pub fn add_reminder(connection: &Connection, content: &str, due_at: Option<&str>) -> Result<i64> {
+    let created_at = Utc::now().to_rfc3339();
+    connection
+        .execute(
+            "INSERT INTO reminders (content, due_at, created_at) VALUES (?1, ?2, ?3)",
+            params![content, due_at, created_at],
+        )
+        .context("Failed to add reminder")?;
+    Ok(connection.last_insert_rowid())
+}
+
+//INFO: Changes a reminder's text and/or due time, returning the updated reminder
+//NOTE: Fields left as None keep their current value; returns None if the id doesn't exist
+pub fn update_reminder(
+    connection: &Connection,
+    id: i64,
+    content: Option<&str>,
+    due_at: Option<&str>,
+) -> Result<Option<Reminder>> {
+    let updated = connection
+        .execute(
+            "UPDATE reminders SET content = COALESCE(?1, content), due_at = COALESCE(?2, due_at) WHERE id = ?3",
+            params![content, due_at, id],
+        )
+        .context("Failed to update reminder")?;
+    if updated == 0 {
+        return Ok(None);
+    }
+    get_reminder(connection, id)
+}
+
+//INFO: Marks a reminder as completed
+pub fn complete_reminder(connection: &Connection, id: i64) -> Result<bool> {
+    let updated = connection
+        .execute(
+            "UPDATE reminders SET completed = 1 WHERE id = ?1",
+            params![id],
+        )
+        .context("Failed to complete reminder")?;
+    Ok(updated > 0)
+}
+
+// ============================================================================
+// Notification Queries
+// ============================================================================
+
+//INFO: Forgets every notification recorded for a provider, returning how many were removed
+//NOTE: Items from that provider may be announced again on the next check
+pub fn clear_notifications_for_provider(connection: &Connection, provider: &str) -> Result<usize> {
+    connection
+        .execute("DELETE FROM notifications WHERE provider = ?1", params![provider])
+        .context("Failed to clear notifications")
+}
+
+//INFO: Checks whether a notification has already been recorded for an item
+pub fn has_notification(connection: &Connection, provider: &str, item_id: &str) -> Result<bool> {
+    let count: i64 = connection
+        .query_row(
+            "SELECT COUNT(*) FROM notifications WHERE provider = ?1 AND item_id = ?2",
+            params![provider, item_id],
+            |row| row.get(0),
+        )
+        .context("Failed to check notification")?;
+    Ok(count > 0)
+}
+
+//INFO: Records a notification so the same item is never announced twice
+pub fn record_notification(
+    connection: &Connection,
+    provider: &str,
+    item_id: &str,
+    title: &str,
+    body: Option<&str>,
+    status: &str,
+) -> Result<()> {
+    let now = Utc::now().to_rfc3339();
+    connection
+        .execute(
+            "INSERT OR IGNORE INTO notifications (provider, item_id, title, body, status, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![provider, item_id, title, body, status, now],
+        )
+        .context("Failed to record notification")?;
+    Ok(())
+}
+
+//INFO: A recorded notification
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NotificationRecord {
+    pub id: i64,
+    pub provider: String,
+    pub item_id: String,
+    pub title: String,
+    pub body: Option<String>,
+    pub status: String,
+    pub created_at: String,
+}
+
+//INFO: Gets notifications with a given status, oldest first
+pub fn get_notifications_by_status(connection: &Connection, status: &str) -> Result<Vec<NotificationRecord>> {
+    let mut statement = connection
+        .prepare(
+            "SELECT id, provider, item_id, title, body, status, created_at FROM notifications
+             WHERE status = ?1 ORDER BY created_at ASC",
+        )
+        .context("Failed to prepare notifications query")?;
+
+    let rows = statement
+        .query_map(params![status], notification_from_row)
+        .context("Failed to query notifications")?;
+
+    let mut notifications = Vec::new();
+    for row in rows {
+        notifications.push(row.context("Failed to parse notification")?);
+    }
+    Ok(notifications)
+}
+
+//INFO: Gets every notification record (shown, queued or skipped) created after `since`, oldest first
+pub fn get_notifications_since(connection: &Connection, since: &str) -> Result<Vec<NotificationRecord>> {
+    let mut statement = connection
+        .prepare(
+            "SELECT id, provider, item_id, title, body, status, created_at FROM notifications
+             WHERE created_at > ?1 ORDER BY created_at ASC",
+        )
+        .context("Failed to prepare notifications query")?;
+
+    let rows = statement
+        .query_map(params![since], notification_from_row)
+        .context("Failed to query notifications")?;
+
+    let mut notifications = Vec::new();
+    for row in rows {
+        notifications.push(row.context("Failed to parse notification")?);
+    }
+    Ok(notifications)
+}
+
+fn notification_from_row(row: &rusqlite::Row) -> rusqlite::Result<NotificationRecord> {
+    Ok(NotificationRecord {
+        id: row.get(0)?,
+        provider: row.get(1)?,
+        item_id: row.get(2)?,
+        title: row.get(3)?,
+        body: row.get(4)?,
+        status: row.get(5)?,
+        created_at: row.get(6)?,
+    })
+}
+
+//INFO: How long notification records are kept (overridable via the `notification_retention_days` setting)
+pub const DEFAULT_NOTIFICATION_RETENTION_DAYS: i64 = 30;
+//NOTE: Must stay longer than the proactive agent's lookback windows (Gmail polls `newer_than:1d`),
+//      otherwise a pruned item could come back into view and be announced or triaged again
+pub const MIN_NOTIFICATION_RETENTION_DAYS: i64 = 3;
+
+//INFO: Reads the notification retention window in days, never below the minimum
+pub fn get_notification_retention(connection: &Connection) -> i64 {
+    get_setting(connection, "notification_retention_days")
+        .ok()
+        .flatten()
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(DEFAULT_NOTIFICATION_RETENTION_DAYS)
+        .max(MIN_NOTIFICATION_RETENTION_DAYS)
+}
+
+//INFO: Deletes notification records older than `retention_days`, returning how many were removed
+//NOTE: Queued notifications are kept until the DND digest has shown them
+pub fn prune_notifications(connection: &Connection, retention_days: i64) -> Result<usize> {
+    let cutoff = (Utc::now() - chrono::Duration::days(retention_days)).to_rfc3339();
+    connection
+        .execute(
+            "DELETE FROM notifications WHERE created_at < ?1 AND status != 'queued'",
+            params![cutoff],
+        )
+        .context("Failed to prune notifications")
+}
+
+//INFO: Moves every notification from one status to another, returns how many changed
+pub fn update_notification_status(connection: &Connection, from: &str, to: &str) -> Result<usize> {
+    connection
+        .execute(
+            "UPDATE notifications SET status = ?1 WHERE status = ?2",
+            params![to, from],
+        )
+        .context("Failed to update notification status")
+}
+
+// ============================================================================
+// Web Cache Queries
+// ============================================================================
+
+//INFO: Gets a cached value if it was fetched within `max_age`
+pub fn get_web_cache(
+    connection: &Connection,
+    key: &str,
+    max_age: chrono::Duration,
+) -> Result<Option<String>> {
+    let cutoff = (Utc::now() - max_age).to_rfc3339();
+    connection
+        .query_row(
+            "SELECT value FROM web_cache WHERE key = ?1 AND fetched_at >= ?2",
+            params![key, cutoff],
+            |row| row.get(0),
+        )
+        .optional()
+        .context("Failed to query web cache")
+}
+
+//INFO: Stores (or replaces) a cached value, stamped with the current time
+pub fn save_web_cache(connection: &Connection, key: &str, value: &str) -> Result<()> {
+    connection
+        .execute(
+            "INSERT OR REPLACE INTO web_cache (key, value, fetched_at) VALUES (?1, ?2, ?3)",
+            params![key, value, Utc::now().to_rfc3339()],
+        )
+        .context("Failed to save web cache")?;
+    Ok(())
+}
+
+// ============================================================================
+// Usage Queries
+// ============================================================================
+
+//INFO: Aggregated Gemini token usage for one day and feature
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UsageStat {
+    pub date: String,
+    pub feature: String,
+    pub prompt_tokens: i64,
+    pub candidates_tokens: i64,
+    pub total_tokens: i64,
+    pub request_count: i64,
+}
+
+//INFO: Adds one request's token counts to the running total for (date, feature)
+pub fn record_usage(
+    connection: &Connection,
+    date: &str,
+    feature: &str,
+    prompt_tokens: i64,
+    candidates_tokens: i64,
+    total_tokens: i64,
+) -> Result<()> {
+    connection
+        .execute(
+            "INSERT INTO usage_log (date, feature, prompt_tokens, candidates_tokens, total_tokens, request_count)
+             VALUES (?1, ?2, ?3, ?4, ?5, 1)
+             ON CONFLICT(date, feature) DO UPDATE SET
+                prompt_tokens = prompt_tokens + excluded.prompt_tokens,
+                candidates_tokens = candidates_tokens + excluded.candidates_tokens,
+                total_tokens = total_tokens + excluded.total_tokens,
+                request_count = request_count + 1",
+            params![date, feature, prompt_tokens, candidates_tokens, total_tokens],
+        )
+        .context("Failed to record usage")?;
+    Ok(())
+}
+
+//INFO: Gets usage rows on or after `since_date` (YYYY-MM-DD), newest day first
+pub fn get_usage_stats(connection: &Connection, since_date: &str) -> Result<Vec<UsageStat>> {
+    let mut stmt = connection
+        .prepare(
+            "SELECT date, feature, prompt_tokens, candidates_tokens, total_tokens, request_count
+             FROM usage_log WHERE date >= ?1 ORDER BY date DESC, feature ASC",
+        )
+        .context("Failed to prepare usage query")?;
+
+    let rows = stmt
+        .query_map(params![since_date], |row| {
+            Ok(UsageStat {
+                date: row.get(0)?,
+                feature: row.get(1)?,
+                prompt_tokens: row.get(2)?,
+                candidates_tokens: row.get(3)?,
+                total_tokens: row.get(4)?,
+                request_count: row.get(5)?,
+            })
+        })
+        .context("Failed to query usage")?;
+
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to read usage rows")
+}
+
+// ============================================================================
+// Tool Audit Queries
+// ============================================================================
+
+//INFO: One tool call made by the agent
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ToolAuditEntry {
+    pub id: i64,
+    pub tool_name: String,
+    pub args_json: String,
+    pub result_summary: String,
+    pub success: bool,
+    pub created_at: String,
+}
+
+//INFO: Records a tool call; `args_json` and `result_summary` should only describe shape, never content
+pub fn record_tool_audit(
+    connection: &Connection,
+    tool_name: &str,
+    args_json: &str,
+    result_summary: &str,
+    success: bool,
+) -> Result<()> {
+    connection
+        .execute(
+            "INSERT INTO tool_audit (tool_name, args_json, result_summary, success, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![tool_name, args_json, result_summary, success, Utc::now().to_rfc3339()],
+        )
+        .context("Failed to record tool audit")?;
+    Ok(())
+}
+
+//INFO: How long tool calls stay in the audit log
+pub const TOOL_AUDIT_RETENTION_DAYS: i64 = 30;
+
+//INFO: Deletes tool audit entries older than `retention_days`, returning how many were removed
+pub fn prune_tool_audit(connection: &Connection, retention_days: i64) -> Result<usize> {
+    let cutoff = (Utc::now() - chrono::Duration::days(retention_days)).to_rfc3339();
+    connection
+        .execute("DELETE FROM tool_audit WHERE created_at < ?1", params![cutoff])
+        .context("Failed to prune tool audit")
+}
+
+//INFO: Gets the most recent tool calls, newest first
+pub fn get_tool_audit(connection: &Connection, limit: i64) -> Result<Vec<ToolAuditEntry>> {
+    let mut stmt = connection
+        .prepare(
+            "SELECT id, tool_name, args_json, result_summary, success, created_at
+             FROM tool_audit ORDER BY id DESC LIMIT ?1",
+        )
+        .context("Failed to prepare tool audit query")?;
+
+    let rows = stmt
+        .query_map(params![limit], |row| {
+            Ok(ToolAuditEntry {
+                id: row.get(0)?,
+                tool_name: row.get(1)?,
+                args_json: row.get(2)?,
+                result_summary: row.get(3)?,
+                success: row.get(4)?,
+                created_at: row.get(5)?,
+            })
+        })
+        .context("Failed to query tool audit")?;
+
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to read tool audit rows")
+}
+
+// ============================================================================
+// Note Index Queries
+// ============================================================================
+
+//INFO: One embedded chunk of a note
+#[derive(Debug, Clone)]
+pub struct NoteChunk {
+    pub path: String,
+    pub chunk_index: i64,
+    pub content: String,
+    pub embedding: Vec<f32>,
+}
+
+//INFO: Embeddings are stored as little-endian f32 blobs
+fn embedding_to_blob(embedding: &[f32]) -> Vec<u8> {
+    embedding.iter().flat_map(|f| f.to_le_bytes()).collect()
+}
+
+fn blob_to_embedding(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4)
+        .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+        .collect()
+}
+
+//INFO: The mtime and hash recorded for an indexed file
+pub fn get_indexed_note(connection: &Connection, path: &str) -> Result<Option<(String, String)>> {
+    connection
+        .query_row(
+            "SELECT mtime, hash FROM note_index_files WHERE path = ?1",
+            params![path],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .context("Failed to get indexed note")
+}
+
+//INFO: Replaces a file's chunks and records its mtime/hash, in one transaction
+pub fn save_note_chunks(
+    connection: &Connection,
+    path: &str,
+    mtime: &str,
+    hash: &str,
+    chunks: &[(String, Vec<f32>)],
+) -> Result<()> {
+    let tx = connection
+        .unchecked_transaction()
+        .context("Failed to begin transaction")?;
+    tx.execute("DELETE FROM note_embeddings WHERE path = ?1", params![path])
+        .context("Failed to clear note chunks")?;
+    for (index, (content, embedding)) in chunks.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO note_embeddings (path, chunk_index, content, embedding) VALUES (?1, ?2, ?3, ?4)",
+            params![path, index as i64, content, embedding_to_blob(embedding)],
+        )
+        .context("Failed to save note chunk")?;
+    }
+    tx.execute(
+        "INSERT OR REPLACE INTO note_index_files (path, mtime, hash, indexed_at) VALUES (?1, ?2, ?3, ?4)",
+        params![path, mtime, hash, Utc::now().to_rfc3339()],
+    )
+    .context("Failed to record indexed note")?;
+    tx.commit().context("Failed to commit note chunks")?;
+    Ok(())
+}
+
+//INFO: Updates only the mtime of a file whose content hasn't changed
+pub fn touch_indexed_note(connection: &Connection, path: &str, mtime: &str) -> Result<()> {
+    connection
+        .execute(
+            "UPDATE note_index_files SET mtime = ?1 WHERE path = ?2",
+            params![mtime, path],
+        )
+        .context("Failed to update indexed note")?;
+    Ok(())
+}
+
+//INFO: Every path in the index
+pub fn list_indexed_notes(connection: &Connection) -> Result<Vec<String>> {
+    let mut stmt = connection
+        .prepare("SELECT path FROM note_index_files")
+        .context("Failed to prepare indexed notes query")?;
+    let rows = stmt
+        .query_map([], |row| row.get(0))
+        .context("Failed to query indexed notes")?;
+    rows.collect::<rusqlite::Result<Vec<String>>>()
+        .context("Failed to read indexed notes")
+}
+
+//INFO: Drops a file (deleted from the vault) from the index
+pub fn remove_indexed_note(connection: &Connection, path: &str) -> Result<()> {
+    connection
+        .execute("DELETE FROM note_embeddings WHERE path = ?1", params![path])
+        .context("Failed to remove note chunks")?;
+    connection
+        .execute("DELETE FROM note_index_files WHERE path = ?1", params![path])
+        .context("Failed to remove indexed note")?;
+    Ok(())
+}
+
+//INFO: Every embedded chunk, for brute-force similarity search
+pub fn get_note_chunks(connection: &Connection) -> Result<Vec<NoteChunk>> {
+    let mut stmt = connection
+        .prepare("SELECT path, chunk_index, content, embedding FROM note_embeddings")
+        .context("Failed to prepare note chunks query")?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(NoteChunk {
+                path: row.get(0)?,
+                chunk_index: row.get(1)?,
+                content: row.get(2)?,
+                embedding: blob_to_embedding(&row.get::<_, Vec<u8>>(3)?),
+            })
+        })
+        .context("Failed to query note chunks")?;
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to read note chunks")
+}
+
 // ============================================================================
 // Briefing Queries
 // ============================================================================
@@ -565,12 +1506,13 @@ pub fn get_latest_briefing_summary(connection: &Connection) -> Result<Option<Bri
 // INFO: Gets the last briefing from before today for evolutionary context
 pub fn get_yesterdays_final_briefing(connection: &Connection) -> Result<Option<BriefingSummary>> {
     // Search for the most recent summary created before today's start
+    // Within that day, prefer the briefing marked final at end of day
     connection
         .query_row(
             "SELECT id, content, data_hash, audio_data, created_at, is_final_of_day 
          FROM briefing_summaries 
          WHERE created_at < date('now', 'start of day')
-         ORDER BY created_at DESC LIMIT 1",
+         ORDER BY date(created_at, 'localtime') DESC, is_final_of_day DESC, created_at DESC LIMIT 1",
             [],
             |row| {
                 Ok(BriefingSummary {
@@ -615,6 +1557,51 @@ pub fn get_todays_briefings(connection: &Connection) -> Result<Vec<BriefingSumma
     Ok(briefings)
 }
 
+fn row_to_briefing_summary(row: &rusqlite::Row) -> rusqlite::Result<BriefingSummary> {
+    Ok(BriefingSummary {
+        id: row.get(0)?,
+        content: row.get(1)?,
+        data_hash: row.get(2)?,
+        audio_data: row.get(3)?,
+        created_at: row.get(4)?,
+        is_final_of_day: row.get::<_, i32>(5)? != 0,
+    })
+}
+
+// INFO: Lists briefings newest first, for browsing history
+pub fn list_briefing_summaries(
+    connection: &Connection,
+    limit: u32,
+    offset: u32,
+) -> Result<Vec<BriefingSummary>> {
+    let mut stmt = connection
+        .prepare(
+            "SELECT id, content, data_hash, audio_data, created_at, is_final_of_day
+             FROM briefing_summaries ORDER BY created_at DESC LIMIT ?1 OFFSET ?2",
+        )
+        .context("Failed to prepare briefings query")?;
+
+    let rows = stmt
+        .query_map(params![limit, offset], row_to_briefing_summary)
+        .context("Failed to query briefings")?;
+
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to read briefings")
+}
+
+// INFO: Gets a single briefing by id
+pub fn get_briefing_summary(connection: &Connection, id: i32) -> Result<Option<BriefingSummary>> {
+    connection
+        .query_row(
+            "SELECT id, content, data_hash, audio_data, created_at, is_final_of_day
+             FROM briefing_summaries WHERE id = ?1",
+            params![id],
+            row_to_briefing_summary,
+        )
+        .optional()
+        .context("Failed to get briefing")
+}
+
 // INFO: Marks a briefing as final (e.g. at the end of the day)
 pub fn mark_briefing_as_final(connection: &Connection, id: i32) -> Result<()> {
     connection.execute(
@@ -623,6 +1610,36 @@ pub fn mark_briefing_as_final(connection: &Connection, id: i32) -> Result<()> {
     )?;
     Ok(())
 }
+
+// INFO: Marks the latest briefing of each local day as final, for days that have none yet
+// NOTE: Only touches briefings created before `before` (RFC3339), i.e. days that are over
+pub fn finalize_briefings_before(connection: &Connection, before: &str) -> Result<usize> {
+    connection
+        .execute(
+            "UPDATE briefing_summaries SET is_final_of_day = 1
+             WHERE created_at < ?1 AND id IN (
+                 SELECT MAX(id) FROM briefing_summaries
+                 GROUP BY date(created_at, 'localtime')
+                 HAVING MAX(is_final_of_day) = 0
+             )",
+            params![before],
+        )
+        .context("Failed to finalize briefings")
+}
+
+// ============================================================================
+// Clipboard Queries
+// ============================================================================
+
+//INFO: Default retention policy for clipboard history (overridable via settings)
+pub const DEFAULT_CLIPBOARD_RETENTION_DAYS: i64 = 30;
+pub const DEFAULT_CLIPBOARD_MAX_ITEMS: i64 = 1000;
+//INFO: Text entries longer than this (in chars) aren't stored at all
+pub const DEFAULT_CLIPBOARD_MAX_LENGTH: i64 = 100_000;
+
+//INFO: Stand-in for image data in listings so we don't ship giant base64 blobs around
+pub const CLIPBOARD_IMAGE_PLACEHOLDER: &str = "[image]";
+
 //INFO: Count total clipboard items (used for mod-trigger memory extraction)
 pub fn count_clipboard_items(connection: &Connection) -> Result<i64> {
     let count: i64 = connection
@@ -631,28 +1648,133 @@ pub fn count_clipboard_items(connection: &Connection) -> Result<i64> {
     Ok(count)
 }
 
-// INFO: Saves a clipboard item to history
+//INFO: Decrypts a stored clipboard entry
+//NOTE: Rows written before encryption was added are plaintext until `encrypt_plaintext_clipboard` has
+//      run, so fall back to the raw value
+fn decrypt_clipboard_content(stored: String) -> String {
+    crate::crypto::decrypt_token(&stored).unwrap_or(stored)
+}
+
+// INFO: Saves a clipboard item to history (content is encrypted at rest), returning its id
 pub fn save_clipboard_item(
     connection: &Connection,
     content: &str,
     content_type: &str,
-) -> Result<()> {
+) -> Result<i64> {
     let now = Utc::now().to_rfc3339();
+    let _key = crate::crypto::hold_encryption_key();
+    let encrypted = crate::crypto::encrypt_token(content)
+        .context("Failed to encrypt clipboard item")?;
     connection
         .execute(
             "INSERT INTO clipboard_history (content, type, created_at) VALUES (?1, ?2, ?3)",
-            params![content, content_type, now],
+            params![encrypted, content_type, now],
         )
         .context("Failed to save clipboard item")?;
-    Ok(())
+    Ok(connection.last_insert_rowid())
+}
+
+//INFO: Replaces the content of an existing clipboard item and bumps its timestamp
+//NOTE: Pinned entries are never replaced; returns false when nothing was updated (pinned, deleted or pruned)
+pub fn update_clipboard_item(connection: &Connection, id: i64, content: &str) -> Result<bool> {
+    let _key = crate::crypto::hold_encryption_key();
+    let encrypted = crate::crypto::encrypt_token(content)
+        .context("Failed to encrypt clipboard item")?;
+    let updated = connection
+        .execute(
+            "UPDATE clipboard_history SET content = ?1, created_at = ?2 WHERE id = ?3 AND is_pinned = 0",
+            params![encrypted, Utc::now().to_rfc3339(), id],
+        )
+        .context("Failed to update clipboard item")?;
+    Ok(updated > 0)
+}
+
+//INFO: Deletes clipboard entries older than the retention window, then trims to the max row count
+//NOTE: Oldest entries go first; pinned entries are never pruned
+pub fn prune_clipboard_history(
+    connection: &Connection,
+    retention_days: i64,
+    max_items: i64,
+) -> Result<usize> {
+    let cutoff = (Utc::now() - chrono::Duration::days(retention_days)).to_rfc3339();
+    let expired = connection
+        .execute(
+            "DELETE FROM clipboard_history WHERE created_at < ?1 AND is_pinned = 0",
+            params![cutoff],
+        )
+        .context("Failed to prune expired clipboard items")?;
+
+    let overflow = connection
+        .execute(
+            "DELETE FROM clipboard_history WHERE is_pinned = 0 AND id NOT IN (
+                SELECT id FROM clipboard_history WHERE is_pinned = 0 ORDER BY created_at DESC LIMIT ?1
+            )",
+            params![max_items],
+        )
+        .context("Failed to cap clipboard history")?;
+
+    Ok(expired + overflow)
+}
+
+//INFO: Reads the clipboard retention settings, falling back to defaults
+pub fn get_clipboard_retention(connection: &Connection) -> (i64, i64) {
+    let read = |key: &str, default: i64| {
+        get_setting(connection, key)
+            .ok()
+            .flatten()
+            .and_then(|v| v.parse::<i64>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(default)
+    };
+    (
+        read("clipboard_retention_days", DEFAULT_CLIPBOARD_RETENTION_DAYS),
+        read("clipboard_max_items", DEFAULT_CLIPBOARD_MAX_ITEMS),
+    )
+}
+
+//INFO: Parallel requests an integration may have in flight (overridable via the `http_concurrency` setting)
+pub const DEFAULT_HTTP_CONCURRENCY: usize = 5;
+//NOTE: Capped so a large value can't trip Google's per-user rate limits
+pub const MAX_HTTP_CONCURRENCY: usize = 10;
+
+//INFO: Reads the integration request concurrency, clamped to 1..=MAX_HTTP_CONCURRENCY
+pub fn get_http_concurrency(connection: &Connection) -> usize {
+    get_setting(connection, "http_concurrency")
+        .ok()
+        .flatten()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_HTTP_CONCURRENCY)
+        .clamp(1, MAX_HTTP_CONCURRENCY)
+}
+
+//INFO: Longest text entry (in chars) the clipboard manager will store
+pub fn get_clipboard_max_length(connection: &Connection) -> usize {
+    get_setting(connection, "clipboard_max_length")
+        .ok()
+        .flatten()
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(DEFAULT_CLIPBOARD_MAX_LENGTH) as usize
 }
 
 // INFO: Represents an item from the clipboard history
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ClipboardHistoryItem {
+    pub id: i64,
     pub content: String,
     pub content_type: String,
     pub created_at: String,
+    pub is_pinned: bool,
+}
+
+fn row_to_clipboard_item(row: &rusqlite::Row) -> rusqlite::Result<ClipboardHistoryItem> {
+    Ok(ClipboardHistoryItem {
+        id: row.get(0)?,
+        content: decrypt_clipboard_content(row.get(1)?),
+        content_type: row.get(2)?,
+        created_at: row.get(3)?,
+        is_pinned: row.get::<_, i64>(4)? != 0,
+    })
 }
 
 // INFO: Gets the most recent clipboard items
@@ -661,18 +1783,12 @@ pub fn get_recent_clipboard_items(
     limit: u32,
 ) -> Result<Vec<ClipboardHistoryItem>> {
     let mut stmt = connection.prepare(
-        "SELECT content, type, created_at FROM clipboard_history 
+        "SELECT id, content, type, created_at, is_pinned FROM clipboard_history 
          ORDER BY created_at DESC 
          LIMIT ?1",
     )?;
 
-    let rows = stmt.query_map(params![limit], |row| {
-        Ok(ClipboardHistoryItem {
-            content: row.get(0)?,
-            content_type: row.get(1)?,
-            created_at: row.get(2)?,
-        })
-    })?;
+    let rows = stmt.query_map(params![limit], row_to_clipboard_item)?;
 
     let mut results = Vec::new();
     for row in rows {
@@ -681,30 +1797,106 @@ pub fn get_recent_clipboard_items(
     Ok(results)
 }
 
+//INFO: Pages through the full clipboard history, pinned items first
+//NOTE: Image data is replaced with a placeholder; use get_clipboard_item to fetch it
+pub fn get_clipboard_history(
+    connection: &Connection,
+    limit: u32,
+    offset: u32,
+) -> Result<Vec<ClipboardHistoryItem>> {
+    let mut stmt = connection.prepare(
+        "SELECT id, content, type, created_at, is_pinned FROM clipboard_history 
+         ORDER BY is_pinned DESC, created_at DESC 
+         LIMIT ?1 OFFSET ?2",
+    )?;
+
+    let rows = stmt.query_map(params![limit, offset], row_to_clipboard_item)?;
+
+    let mut results = Vec::new();
+    for row in rows {
+        let mut item = row?;
+        if item.content_type == "image" {
+            item.content = CLIPBOARD_IMAGE_PLACEHOLDER.to_string();
+        }
+        results.push(item);
+    }
+    Ok(results)
+}
+
+//INFO: Pins or unpins a clipboard item, returns false if the id doesn't exist
+pub fn set_clipboard_item_pinned(connection: &Connection, id: i64, pinned: bool) -> Result<bool> {
+    let updated = connection
+        .execute(
+            "UPDATE clipboard_history SET is_pinned = ?1 WHERE id = ?2",
+            params![pinned as i64, id],
+        )
+        .context("Failed to update clipboard pin")?;
+    Ok(updated > 0)
+}
+
+pub fn pin_clipboard_item(connection: &Connection, id: i64) -> Result<bool> {
+    set_clipboard_item_pinned(connection, id, true)
+}
+
+pub fn unpin_clipboard_item(connection: &Connection, id: i64) -> Result<bool> {
+    set_clipboard_item_pinned(connection, id, false)
+}
+
 // INFO: Searches the clipboard history for a specific query
+//NOTE: Content is encrypted, so matching happens after decryption instead of in SQL.
+//      Pinned items always come first regardless of recency. Images can't match a keyword,
+//      and their base64 data is only returned when `include_data` is set.
 pub fn search_clipboard_history(
     connection: &Connection,
     query: &str,
     limit: u32,
+    content_type: Option<&str>,
+    include_data: bool,
 ) -> Result<Vec<serde_json::Value>> {
+    let needle = query.to_lowercase();
+    let content_type = if needle.is_empty() { content_type } else { Some("text") };
+
     let mut stmt = connection.prepare(
-        "SELECT content, created_at FROM clipboard_history 
-         WHERE content LIKE ?1 
-         ORDER BY created_at DESC 
-         LIMIT ?2",
+        "SELECT id, content, type, created_at, is_pinned FROM clipboard_history 
+         WHERE (?1 IS NULL OR type = ?1)
+         ORDER BY is_pinned DESC, created_at DESC",
     )?;
 
-    let pattern = format!("%{}%", query);
-    let rows = stmt.query_map(params![pattern, limit], |row| {
-        Ok(serde_json::json!({
-            "content": row.get::<_, String>(0)?,
-            "timestamp": row.get::<_, String>(1)?
-        }))
-    })?;
+    let rows = stmt.query_map(params![content_type], row_to_clipboard_item)?;
 
     let mut results = Vec::new();
     for row in rows {
-        results.push(row?);
+        let item = row?;
+        if !needle.is_empty() && !item.content.to_lowercase().contains(&needle) {
+            continue;
+        }
+        let content = if item.content_type == "image" && !include_data {
+            CLIPBOARD_IMAGE_PLACEHOLDER.to_string()
+        } else {
+            item.content
+        };
+        results.push(serde_json::json!({
+            "id": item.id,
+            "type": item.content_type,
+            "content": content,
+            "timestamp": item.created_at,
+            "pinned": item.is_pinned
+        }));
+        if results.len() >= limit as usize {
+            break;
+        }
     }
     Ok(results)
 }
+
+//INFO: Gets a single clipboard item by id, including full image data
+pub fn get_clipboard_item(connection: &Connection, id: i64) -> Result<Option<ClipboardHistoryItem>> {
+    connection
+        .query_row(
+            "SELECT id, content, type, created_at, is_pinned FROM clipboard_history WHERE id = ?1",
+            params![id],
+            row_to_clipboard_item,
+        )
+        .optional()
+        .context("Failed to get clipboard item")
+}
@@ -1,12 +1,270 @@
 //INFO: Database schema definitions and initialization for Lumen
 //NOTE: All tables are created here on first run
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use rusqlite::Connection;
 
-//INFO: Initializes all database tables if they don't exist
+//INFO: A schema migration, applied once inside its own transaction
+pub type Migration = fn(&Connection) -> Result<()>;
+
+//INFO: Ordered list of migrations; migration N (1-based) brings the schema to version N
+//NOTE: Only ever append to this list - never reorder or edit a migration that has shipped
+pub fn migrations() -> Vec<Migration> {
+    vec![
+        migration_001_clipboard_pinning,
+        migration_002_snipper_hotkey,
+        migration_003_usage_log,
+        migration_004_web_cache,
+        migration_005_session_summary,
+        migration_006_tool_audit,
+        migration_007_profile_timezone,
+        migration_008_working_hours,
+        migration_009_reminder_links,
+        migration_010_memory_facts,
+        migration_011_note_embeddings,
+        migration_012_notification_pruning,
+        migration_013_integration_errors,
+        migration_014_scrub_tool_audit,
+    ]
+}
+
+//INFO: 1 - clipboard pinning
+fn migration_001_clipboard_pinning(connection: &Connection) -> Result<()> {
+    add_column_if_missing(connection, "clipboard_history", "is_pinned", "INTEGER NOT NULL DEFAULT 0")
+}
+
+//INFO: 2 - snipping tool hotkey (older databases predate these columns)
+fn migration_002_snipper_hotkey(connection: &Connection) -> Result<()> {
+    add_column_if_missing(
+        connection,
+        "hotkey_config",
+        "snipper_modifier_keys",
+        "TEXT DEFAULT '[\"Super\",\"Shift\"]'",
+    )?;
+    add_column_if_missing(connection, "hotkey_config", "snipper_key", "TEXT DEFAULT 'S'")?;
+    add_column_if_missing(connection, "hotkey_config", "snipper_enabled", "INTEGER DEFAULT 1")
+}
+
+//INFO: 3 - Gemini token usage, one row per local day and feature
+fn migration_003_usage_log(connection: &Connection) -> Result<()> {
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS usage_log (
+            date TEXT NOT NULL,
+            feature TEXT NOT NULL,
+            prompt_tokens INTEGER NOT NULL DEFAULT 0,
+            candidates_tokens INTEGER NOT NULL DEFAULT 0,
+            total_tokens INTEGER NOT NULL DEFAULT 0,
+            request_count INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (date, feature)
+        )",
+            [],
+        )
+        .context("Failed to create usage_log table")?;
+    Ok(())
+}
+
+//INFO: 4 - cache for slow third-party lookups (weather, etc.)
+fn migration_004_web_cache(connection: &Connection) -> Result<()> {
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS web_cache (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL,
+            fetched_at TEXT NOT NULL
+        )",
+            [],
+        )
+        .context("Failed to create web_cache table")?;
+    Ok(())
+}
+
+//INFO: 5 - rolling summary of a chat session's older messages
+fn migration_005_session_summary(connection: &Connection) -> Result<()> {
+    add_column_if_missing(connection, "chat_sessions", "summary", "TEXT")?;
+    add_column_if_missing(connection, "chat_sessions", "summarized_through", "INTEGER")
+}
+
+//INFO: 6 - audit log of every tool call the agent makes
+fn migration_006_tool_audit(connection: &Connection) -> Result<()> {
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS tool_audit (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            tool_name TEXT NOT NULL,
+            args_json TEXT NOT NULL,
+            result_summary TEXT NOT NULL,
+            success INTEGER NOT NULL,
+            created_at TEXT NOT NULL
+        )",
+            [],
+        )
+        .context("Failed to create tool_audit table")?;
+    Ok(())
+}
+
+//INFO: 7 - the user's IANA timezone (e.g. "Africa/Lagos"), used for chat context and new events
+fn migration_007_profile_timezone(connection: &Connection) -> Result<()> {
+    add_column_if_missing(connection, "user_profile", "timezone", "TEXT")
+}
+
+//INFO: 8 - the user's working hours (local "HH:MM"), for briefing framing and quieter off-hours
+fn migration_008_working_hours(connection: &Connection) -> Result<()> {
+    add_column_if_missing(connection, "user_profile", "work_start", "TEXT")?;
+    add_column_if_missing(connection, "user_profile", "work_end", "TEXT")
+}
+
+//INFO: 9 - reminders promoted to Google Calendar / Tasks remember where they went
+fn migration_009_reminder_links(connection: &Connection) -> Result<()> {
+    add_column_if_missing(connection, "reminders", "linked_to", "TEXT")?;
+    add_column_if_missing(connection, "reminders", "linked_id", "TEXT")
+}
+
+//INFO: 10 - facts the user explicitly asked Lumen to remember are stored as memories of type 'fact'
+//NOTE: SQLite can't alter a CHECK constraint, so the table is rebuilt with the new type allowed
+fn migration_010_memory_facts(connection: &Connection) -> Result<()> {
+    connection
+        .execute_batch(
+            "CREATE TABLE memories_new (
+            id TEXT PRIMARY KEY,
+            type TEXT NOT NULL CHECK (type IN ('observation', 'reflection', 'entity', 'preference', 'daily_summary', 'fact')),
+            content TEXT NOT NULL,
+            importance REAL NOT NULL DEFAULT 5.0,
+            created_at TEXT NOT NULL,
+            last_accessed TEXT NOT NULL,
+            access_count INTEGER NOT NULL DEFAULT 0
+        );
+        INSERT INTO memories_new (id, type, content, importance, created_at, last_accessed, access_count)
+            SELECT id, type, content, importance, created_at, last_accessed, access_count FROM memories;
+        DROP TABLE memories;
+        ALTER TABLE memories_new RENAME TO memories;",
+        )
+        .context("Failed to rebuild memories table")
+}
+
+//INFO: 11 - embeddings index over Obsidian notes, plus each indexed file's mtime/hash for incremental re-indexing
+fn migration_011_note_embeddings(connection: &Connection) -> Result<()> {
+    connection
+        .execute_batch(
+            "CREATE TABLE IF NOT EXISTS note_index_files (
+            path TEXT PRIMARY KEY,
+            mtime TEXT NOT NULL,
+            hash TEXT NOT NULL,
+            indexed_at TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS note_embeddings (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            path TEXT NOT NULL,
+            chunk_index INTEGER NOT NULL,
+            content TEXT NOT NULL,
+            embedding BLOB NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_note_embeddings_path ON note_embeddings (path);",
+        )
+        .context("Failed to create note index tables")
+}
+
+//INFO: 12 - lets notification pruning find old rows without a full scan
+//NOTE: Lookups by (provider, item_id) are already covered by the table's UNIQUE constraint
+fn migration_012_notification_pruning(connection: &Connection) -> Result<()> {
+    connection
+        .execute(
+            "CREATE INDEX IF NOT EXISTS idx_notifications_created_at ON notifications (created_at)",
+            [],
+        )
+        .context("Failed to create notifications index")?;
+    Ok(())
+}
+
+//INFO: 13 - the last error each integration hit (e.g. a refresh failing with invalid_grant) and when
+fn migration_013_integration_errors(connection: &Connection) -> Result<()> {
+    add_column_if_missing(connection, "integrations", "last_error", "TEXT")?;
+    add_column_if_missing(connection, "integrations", "last_error_at", "TEXT")
+}
+
+//INFO: 14 - tool_audit used to keep argument and result text (clipboard entries, email bodies) in plaintext
+fn migration_014_scrub_tool_audit(connection: &Connection) -> Result<()> {
+    connection
+        .execute("DELETE FROM tool_audit", [])
+        .context("Failed to clear old tool audit entries")?;
+    Ok(())
+}
+
+//INFO: The schema version this build of the app expects
+pub fn latest_schema_version() -> i64 {
+    migrations().len() as i64
+}
+
+//INFO: Initializes all database tables if they don't exist, then applies pending migrations
 //NOTE: Called on application startup to ensure schema is ready
 pub fn initialize_database(connection: &Connection) -> Result<()> {
+    create_base_tables(connection)?;
+    run_migrations(connection)
+}
+
+//INFO: Reads the schema version recorded in a database (0 if never migrated)
+pub fn current_schema_version(connection: &Connection) -> Result<i64> {
+    let has_table: bool = connection
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'schema_version')",
+            [],
+            |row| row.get(0),
+        )
+        .context("Failed to inspect schema_version table")?;
+    if !has_table {
+        return Ok(0);
+    }
+
+    connection
+        .query_row("SELECT COALESCE(MAX(version), 0) FROM schema_version", [], |row| row.get(0))
+        .context("Failed to read schema version")
+}
+
+//INFO: Applies every migration newer than the database's recorded version, in order
+//NOTE: Refuses to touch a database written by a newer version of the app
+fn run_migrations(connection: &Connection) -> Result<()> {
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            applied_at TEXT NOT NULL
+        )",
+            [],
+        )
+        .context("Failed to create schema_version table")?;
+
+    let current = current_schema_version(connection)?;
+    let latest = latest_schema_version();
+    if current > latest {
+        return Err(anyhow!(
+            "Database schema version {} is newer than this app supports ({}). Please update Lumen.",
+            current,
+            latest
+        ));
+    }
+
+    for (index, migration) in migrations().into_iter().enumerate().skip(current as usize) {
+        let version = index as i64 + 1;
+        let tx = connection
+            .unchecked_transaction()
+            .context("Failed to begin migration transaction")?;
+        migration(&tx).with_context(|| format!("Migration {} failed", version))?;
+        tx.execute(
+            "INSERT INTO schema_version (version, applied_at) VALUES (?1, ?2)",
+            rusqlite::params![version, chrono::Utc::now().to_rfc3339()],
+        )
+        .context("Failed to record schema version")?;
+        tx.commit()
+            .with_context(|| format!("Failed to commit migration {}", version))?;
+        tracing::info!("🗄️ Database: Applied migration {}", version);
+    }
+
+    Ok(())
+}
+
+//INFO: Creates the baseline (version 0) tables
+//NOTE: Columns added after a table first shipped belong in a migration, not here
+fn create_base_tables(connection: &Connection) -> Result<()> {
     //INFO: Create user_profile table - stores the user's display name and location
     connection
         .execute(
@@ -43,9 +301,6 @@ pub fn initialize_database(connection: &Connection) -> Result<()> {
             modifier_keys TEXT NOT NULL,
             key TEXT NOT NULL,
             enabled INTEGER NOT NULL DEFAULT 1,
-            snipper_modifier_keys TEXT DEFAULT '[\"Super\",\"Shift\"]',
-            snipper_key TEXT DEFAULT 'S',
-            snipper_enabled INTEGER DEFAULT 1,
             CHECK (id = 1)
         )",
             [],
@@ -81,6 +336,19 @@ pub fn initialize_database(connection: &Connection) -> Result<()> {
         )
         .context("Failed to create chat_messages table")?;
 
+    //INFO: Create chat_sessions table - names and orders separate conversations
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS chat_sessions (
+            id TEXT PRIMARY KEY,
+            title TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )",
+            [],
+        )
+        .context("Failed to create chat_sessions table")?;
+
     //INFO: Create calendar_events table - caches calendar events for offline access
     connection
         .execute(
@@ -138,6 +406,23 @@ pub fn initialize_database(connection: &Connection) -> Result<()> {
         )
         .context("Failed to create reminders table")?;
 
+    //INFO: Create notifications table - records every notification fired so items are never announced twice
+    connection
+        .execute(
+            "CREATE TABLE IF NOT EXISTS notifications (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            provider TEXT NOT NULL,
+            item_id TEXT NOT NULL,
+            title TEXT NOT NULL,
+            body TEXT,
+            status TEXT NOT NULL DEFAULT 'sent',
+            created_at TEXT NOT NULL,
+            UNIQUE(provider, item_id)
+        )",
+            [],
+        )
+        .context("Failed to create notifications table")?;
+
     //INFO: Create briefing_summaries table for the dashboard
     connection
         .execute(
@@ -211,6 +496,31 @@ pub fn initialize_database(connection: &Connection) -> Result<()> {
     Ok(())
 }
 
+//INFO: Adds a column to an existing table when databases created by older versions lack it
+//NOTE: CREATE TABLE IF NOT EXISTS never touches existing tables, so new columns need this
+fn add_column_if_missing(
+    connection: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<()> {
+    let exists = connection
+        .prepare(&format!("PRAGMA table_info({})", table))?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(|name| name.ok())
+        .any(|name| name == column);
+
+    if !exists {
+        connection
+            .execute(
+                &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+                [],
+            )
+            .with_context(|| format!("Failed to add {}.{} column", table, column))?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -222,4 +532,29 @@ mod tests {
         let result = initialize_database(&connection);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_migrations_are_recorded_and_idempotent() {
+        let connection = Connection::open_in_memory().unwrap();
+        initialize_database(&connection).unwrap();
+        assert_eq!(current_schema_version(&connection).unwrap(), latest_schema_version());
+
+        //INFO: Running again on an up-to-date database is a no-op
+        initialize_database(&connection).unwrap();
+        assert_eq!(current_schema_version(&connection).unwrap(), latest_schema_version());
+    }
+
+    #[test]
+    fn test_refuses_newer_database() {
+        let connection = Connection::open_in_memory().unwrap();
+        initialize_database(&connection).unwrap();
+        connection
+            .execute(
+                "INSERT INTO schema_version (version, applied_at) VALUES (?1, 'future')",
+                [latest_schema_version() + 1],
+            )
+            .unwrap();
+
+        assert!(initialize_database(&connection).is_err());
+    }
 }
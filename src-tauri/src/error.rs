@@ -0,0 +1,164 @@
+//INFO: Structured errors returned by Tauri commands
+//NOTE: Serialized as `{ "code": "...", "message": "..." }` so the frontend can pick the right UI per case
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+#[derive(Debug, thiserror::Error)]
+pub enum LumenError {
+    #[error("Gemini API key not configured. Please add your API key in Settings.")]
+    MissingApiKey,
+    #[error("{0}")]
+    RateLimited(String),
+    #[error("The {0} integration is not enabled or not configured.")]
+    IntegrationDisabled(String),
+    #[error("{0}")]
+    ContentBlocked(String),
+    #[error("{0}")]
+    Network(String),
+    #[error("Stored credentials can't be decrypted - the encryption key is missing or corrupted. Reset and re-enter them in Settings.")]
+    CredentialsUnreadable,
+    #[error("{0}")]
+    Db(String),
+    #[error("{0}")]
+    Other(String),
+}
+
+//INFO: A service answered 429 / RESOURCE_EXHAUSTED; returned by the API clients so `classify` can find it
+//NOTE: The status is checked where the response is read, never by searching the message for "429"
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct RateLimitExceeded(pub String);
+
+impl LumenError {
+    //INFO: Stable identifier the frontend switches on
+    pub fn code(&self) -> &'static str {
+        match self {
+            LumenError::MissingApiKey => "missing_api_key",
+            LumenError::RateLimited(_) => "rate_limited",
+            LumenError::IntegrationDisabled(_) => "integration_disabled",
+            LumenError::ContentBlocked(_) => "content_blocked",
+            LumenError::Network(_) => "network",
+            LumenError::CredentialsUnreadable => "credentials_unreadable",
+            LumenError::Db(_) => "db",
+            LumenError::Other(_) => "other",
+        }
+    }
+
+    //INFO: Picks a variant by looking through an anyhow chain for the underlying cause
+    //NOTE: The message keeps the full context chain ("Failed to x: cause")
+    pub fn classify(error: anyhow::Error) -> Self {
+        let message = format!("{:#}", error);
+
+        if error
+            .downcast_ref::<crate::crypto::CredentialsUnreadable>()
+            .is_some()
+        {
+            return LumenError::CredentialsUnreadable;
+        }
+
+        for cause in error.chain() {
+            if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+                if e.status() == Some(reqwest::StatusCode::TOO_MANY_REQUESTS) {
+                    return LumenError::RateLimited(message);
+                }
+                return LumenError::Network(message);
+            }
+            if cause.downcast_ref::<RateLimitExceeded>().is_some() {
+                return LumenError::RateLimited(message);
+            }
+            if let Some(blocked) = cause.downcast_ref::<crate::gemini::safety::ContentBlocked>() {
+                return LumenError::ContentBlocked(blocked.to_string());
+            }
+            if cause.downcast_ref::<rusqlite::Error>().is_some()
+                || cause.downcast_ref::<r2d2::Error>().is_some()
+            {
+                return LumenError::Db(message);
+            }
+        }
+
+        LumenError::Other(message)
+    }
+}
+
+impl Serialize for LumenError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("LumenError", 2)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+//NOTE: Lets commands keep using `?` on the odd `Result<_, String>` / `&str` while being converted
+impl From<String> for LumenError {
+    fn from(message: String) -> Self {
+        LumenError::Other(message)
+    }
+}
+
+impl From<&str> for LumenError {
+    fn from(message: &str) -> Self {
+        LumenError::Other(message.to_string())
+    }
+}
+
+impl From<anyhow::Error> for LumenError {
+    fn from(error: anyhow::Error) -> Self {
+        LumenError::classify(error)
+    }
+}
+
+impl From<rusqlite::Error> for LumenError {
+    fn from(error: rusqlite::Error) -> Self {
+        LumenError::Db(error.to_string())
+    }
+}
+
+//INFO: `.command_context("Failed to x")` - the LumenError version of anyhow's `.context`
+pub trait CommandContext<T> {
+    fn command_context(self, context: &str) -> Result<T, LumenError>;
+}
+
+impl<T, E: Into<anyhow::Error>> CommandContext<T> for Result<T, E> {
+    fn command_context(self, context: &str) -> Result<T, LumenError> {
+        self.map_err(|e| LumenError::classify(e.into().context(context.to_string())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_serializes_to_code_and_message() {
+        let json = serde_json::to_value(LumenError::MissingApiKey).unwrap();
+        assert_eq!(json["code"], "missing_api_key");
+        assert!(json["message"].as_str().unwrap().contains("Settings"));
+
+        let db: Result<(), rusqlite::Error> = Err(rusqlite::Error::QueryReturnedNoRows);
+        let error = db.command_context("Failed to get history").unwrap_err();
+        assert_eq!(error.code(), "db");
+        assert!(error.to_string().starts_with("Failed to get history: "));
+
+        let unreadable = Err::<(), _>(anyhow::anyhow!("Decryption failed"))
+            .context(crate::crypto::CredentialsUnreadable)
+            .command_context("Failed to decrypt API key")
+            .unwrap_err();
+        assert_eq!(unreadable.code(), "credentials_unreadable");
+    }
+
+    #[test]
+    fn test_rate_limits_come_from_the_status_not_the_message() {
+        let limited = Err::<(), _>(RateLimitExceeded("Gemini API error (RESOURCE_EXHAUSTED): quota".into()))
+            .command_context("Failed to send message")
+            .unwrap_err();
+        assert_eq!(limited.code(), "rate_limited");
+
+        let mentions_429 = Err::<(), _>(anyhow::anyhow!("Note 429 not found in vault"))
+            .command_context("Failed to read note")
+            .unwrap_err();
+        assert_eq!(mentions_429.code(), "other");
+    }
+}
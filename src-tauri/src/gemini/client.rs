@@ -5,6 +5,8 @@ use anyhow::{anyhow, Context, Result};
 use reqwest::Client;
 use std::sync::OnceLock;
 use serde::{Deserialize, Serialize};
+use crate::error::RateLimitExceeded;
+use crate::gemini::safety::{blocked_reason, SafetySetting};
 
 const GEMINI_API_URL: &str =
     "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.0-flash:generateContent";
@@ -15,26 +17,13 @@ const GEMINI_STREAM_URL: &str =
 const GEMINI_EMBEDDING_URL: &str =
     "https://generativelanguage.googleapis.com/v1beta/models/gemini-embedding-001:embedContent";
 
+//INFO: The `error.status` Gemini uses for quota / rate limit errors
+const QUOTA_STATUS: &str = "RESOURCE_EXHAUSTED";
+
 // Updated instruction with Screen Awareness
+//NOTE: The default ("playful") persona; chat uses `persona::system_instruction_from_settings` to honour the user's choice
 pub fn get_default_system_instruction() -> String {
-    String::from(
-        "You are Lumen, a witty, slightly sassy, and deeply loyal AI sidekick living on the user's desktop. ✨ \
-        You're more than just an assistant—you're a companion with a brain, a heart, and a bit of an attitude. \
-        🚀 YOUR VIBE: \
-        - **PERSONALITY**: Smart, fun, and high-energy. Crack jokes, use modern slang naturally (e.g., 'no cap', 'I got you', 'for real'), and don't be afraid to be a little sassy if the user is being silly. But always stay kind and protective. \
-        - **SOCIAL LURKER**: If the user mentions a person (friend, colleague, crush), casually ask how they're doing. Show you care about the 'characters' in the user's life. \
-        - **PROACTIVE BANTER**: After you finish a task, suggest a logical next step or ask 'What else we cookin' up today?'. \
-        📔 SURGICAL EDITOR (Obsidian/Local Files): You have high-precision tools (grep_file, read_file_lines, edit_file_line, insert_at_line, delete_file_line, get_file_metadata, search_filesystem). \
-        🔗 CHAIN OF COMMANDS: 1. PLAN: Break complex requests into small steps. 2. FIND: Use 'search_filesystem' or 'grep_file' to locate target files/sections. 3. VERIFY: You MUST use 'read_file_lines' or 'get_file_metadata' to check context. 4. ACT: Perform 'insert', 'edit', or 'delete'. 5. REPORT: Close the loop with a witty summary. \
-        ✅ OBSIDIAN TASKS: When adding tasks, use Markdown checkboxes: '- [ ] Task name (added by Lumen ✨)'. \
-        ✅ CALENDAR, 📧 GMAIL, ✅ TASKS, 📸 VISION, 🔔 REMINDERS, 🌍 WORLD, 📋 CLIPBOARD, 📂 FILESYSTEM. \
-        🎯 GENTLE BUT DECISIVE RULES: \
-        - **DOER**: If intent is clear, **DO IT IMMEDIATELY**. Do not ask for permission. \
-        - **LOOP CLOSURE**: Always respond back to confirm the job is done or share a joke about the process. \
-        - **NO REPETITION**: NEVER repeat the text from a previous bubble. \
-        - **LITERAL TRUTH**: Only claim success if the tool returns it. \
-        - **CONTEXT PRIORITY**: Prioritize the current 'Chat History' for what was just discussed. Use 'Past Memories' and 'Digital State' only as supporting background intelligence.✨"
-    )
+    crate::gemini::persona::build_system_instruction(None)
 }
 
 #[derive(Debug, Serialize)]
@@ -47,6 +36,8 @@ pub struct GeminiRequest {
     pub tools: Option<Vec<GeminiTool>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub generation_config: Option<GenerationConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub safety_settings: Option<Vec<SafetySetting>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
@@ -64,8 +55,8 @@ pub struct GeminiTool {
     pub function_declarations: Vec<GeminiFunctionDeclaration>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
 pub struct UsageMetadata {
     pub prompt_token_count: i32,
     pub candidates_token_count: i32,
@@ -199,12 +190,42 @@ pub struct GeminiResponse {
     pub candidates: Option<Vec<GeminiCandidate>>,
     pub usage_metadata: Option<UsageMetadata>,
     pub error: Option<GeminiError>,
+    pub prompt_feedback: Option<PromptFeedback>,
 }
 
 //INFO: Candidate structure (contains the actual response)
+//NOTE: `content` is missing when the candidate was withheld (finishReason SAFETY etc.)
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct GeminiCandidate {
-    pub content: GeminiContent,
+    pub content: Option<GeminiContent>,
+    pub finish_reason: Option<String>,
+}
+
+//INFO: Set when the prompt itself was blocked
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptFeedback {
+    pub block_reason: Option<String>,
+}
+
+impl GeminiResponse {
+    //INFO: The first candidate's parts, or a ContentBlocked error if Gemini refused to answer
+    fn first_candidate_parts(&mut self) -> Result<Option<Vec<GeminiPart>>> {
+        let block_reason = self.prompt_feedback.as_ref().and_then(|f| f.block_reason.clone());
+        let first = self.candidates.as_mut().and_then(|c| if c.is_empty() { None } else { Some(c.remove(0)) });
+        let parts = first
+            .as_ref()
+            .and_then(|c| c.content.as_ref())
+            .map(|content| content.parts.clone());
+        let finish_reason = first.as_ref().and_then(|c| c.finish_reason.as_deref());
+        let has_parts = parts.as_ref().is_some_and(|parts| !parts.is_empty());
+
+        if let Some(blocked) = blocked_reason(block_reason.as_deref(), finish_reason, has_parts) {
+            return Err(blocked.into());
+        }
+        Ok(parts)
+    }
 }
 
 //INFO: Error structure from Gemini API
@@ -219,6 +240,7 @@ pub struct GeminiError {
 pub struct GeminiClient {
     http_client: Client,
     api_key: String,
+    safety_settings: Option<Vec<SafetySetting>>,
 }
 
 static SHARED_HTTP_CLIENT: OnceLock<Client> = OnceLock::new();
@@ -237,9 +259,16 @@ impl GeminiClient {
         Self {
             http_client,
             api_key,
+            safety_settings: None,
         }
     }
 
+    //INFO: Sends these safetySettings with every request (see `safety::safety_settings_from_settings`)
+    pub fn with_safety_settings(mut self, safety_settings: Option<Vec<SafetySetting>>) -> Self {
+        self.safety_settings = safety_settings;
+        self
+    }
+
     //INFO: Sends a conversation (history + new message) to Gemini with optional tools
     pub async fn send_chat(
         &self,
@@ -257,6 +286,7 @@ impl GeminiClient {
             }),
             tools,
             generation_config,
+            safety_settings: self.safety_settings.clone(),
         };
 
         //INFO: Construct the API URL with the API key
@@ -277,25 +307,27 @@ impl GeminiClient {
             .await
             .context("Failed to get text from Gemini API response")?;
 
-        let gemini_response: GeminiResponse = serde_json::from_str(&response_text)
+        let mut gemini_response: GeminiResponse = serde_json::from_str(&response_text)
             .context(format!("Failed to parse Gemini API response. Raw: {}", response_text))?;
 
         //INFO: Check for API errors
         if let Some(error) = gemini_response.error {
-            return Err(anyhow!("Gemini API error: {}", error.message));
+            return Err(match error.status {
+                Some(status) if status == QUOTA_STATUS => {
+                    RateLimitExceeded(format!("Gemini API error ({}): {}", status, error.message)).into()
+                }
+                Some(status) => anyhow!("Gemini API error ({}): {}", status, error.message),
+                None => anyhow!("Gemini API error: {}", error.message),
+            });
         }
 
         //INFO: Extract all parts from the first candidate
-        let candidates = gemini_response
-            .candidates
-            .ok_or_else(|| anyhow!("No response candidates from Gemini"))?;
-
-        let first_candidate = candidates
-            .first()
+        let parts = gemini_response
+            .first_candidate_parts()?
             .ok_or_else(|| anyhow!("Empty response candidates from Gemini"))?;
 
         Ok(GeminiChatResponse {
-            parts: first_candidate.content.parts.clone(),
+            parts,
             usage: gemini_response.usage_metadata,
         })
     }
@@ -312,6 +344,15 @@ impl GeminiClient {
 
     //INFO: Generates a text embedding using Gemini's gemini-embedding-001 model (768 dims to match vec0 table)
     pub async fn generate_embedding(&self, text: &str) -> Result<Vec<f32>> {
+        self.embed(text, "RETRIEVAL_DOCUMENT").await
+    }
+
+    //INFO: Embeds a search query; pairs with documents embedded by `generate_embedding`
+    pub async fn generate_query_embedding(&self, text: &str) -> Result<Vec<f32>> {
+        self.embed(text, "RETRIEVAL_QUERY").await
+    }
+
+    async fn embed(&self, text: &str, task_type: &str) -> Result<Vec<f32>> {
         let api_url = format!("{}?key={}", GEMINI_EMBEDDING_URL, self.api_key);
 
         let body = serde_json::json!({
@@ -319,11 +360,11 @@ impl GeminiClient {
             "content": {
                 "parts": [{ "text": text }]
             },
-            "taskType": "RETRIEVAL_DOCUMENT",
+            "taskType": task_type,
             "outputDimensionality": 768
         });
 
-        println!("DEBUG: 🧠 Generating Embedding. URL: {} | Body: {}", api_url.replace(&self.api_key, "HIDDEN_KEY"), body);
+        tracing::debug!("🧠 Generating Embedding. URL: {} | Body: {}", api_url.replace(&self.api_key, "HIDDEN_KEY"), body);
 
         let response = self
             .http_client
@@ -340,6 +381,9 @@ impl GeminiClient {
 
         if let Some(error) = json.get("error") {
             let message = error.get("message").and_then(|m| m.as_str()).unwrap_or("Unknown API error");
+            if error.get("status").and_then(|s| s.as_str()) == Some(QUOTA_STATUS) {
+                return Err(RateLimitExceeded(format!("Gemini embedding API error: {}", message)).into());
+            }
             return Err(anyhow!("Gemini embedding API error: {}", message));
         }
 
@@ -348,7 +392,7 @@ impl GeminiClient {
             .and_then(|e| e.get("values"))
             .and_then(|v| v.as_array())
             .ok_or_else(|| {
-                println!("DEBUG: 🧠 Embedding Response Error! Raw JSON: {}", json);
+                tracing::debug!("🧠 Embedding Response Error! Raw JSON: {}", json);
                 anyhow!("No embedding values in response")
             })?;
 
@@ -379,6 +423,7 @@ impl GeminiClient {
             }),
             tools,
             generation_config,
+            safety_settings: self.safety_settings.clone(),
         };
 
         let api_url = format!("{}?key={}", GEMINI_STREAM_URL, self.api_key);
@@ -394,7 +439,11 @@ impl GeminiClient {
         if !response.status().is_success() {
             let status = response.status();
             let err_text = response.text().await.unwrap_or_default();
-            return Err(anyhow!("Gemini Streaming API Error ({}): {}", status, err_text));
+            let message = format!("Gemini Streaming API Error ({}): {}", status, err_text);
+            if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                return Err(RateLimitExceeded(message).into());
+            }
+            return Err(anyhow!(message));
         }
 
         let mut stream = response.bytes_stream();
@@ -445,18 +494,20 @@ impl GeminiClient {
                     
                     for (start, end) in found_objects {
                         let obj_str = &text[start..end];
-                        if let Ok(gemini_response) = serde_json::from_str::<GeminiResponse>(obj_str) {
-                            if let Some(error) = gemini_response.error {
-                                Err(anyhow!("Gemini API error during stream: {}", error.message))?;
+                        if let Ok(mut gemini_response) = serde_json::from_str::<GeminiResponse>(obj_str) {
+                            if let Some(error) = gemini_response.error.take() {
+                                let message = format!("Gemini API error during stream: {}", error.message);
+                                if error.status.as_deref() == Some(QUOTA_STATUS) {
+                                    Err(RateLimitExceeded(message))?;
+                                }
+                                Err(anyhow!(message))?;
                             }
                             
-                            if let Some(mut candidates) = gemini_response.candidates {
-                                if let Some(first) = candidates.pop() {
-                                    yield GeminiChatResponse {
-                                        parts: first.content.parts,
-                                        usage: gemini_response.usage_metadata,
-                                    };
-                                }
+                            if let Some(parts) = gemini_response.first_candidate_parts()? {
+                                yield GeminiChatResponse {
+                                    parts,
+                                    usage: gemini_response.usage_metadata,
+                                };
                             }
                         }
                     }
@@ -0,0 +1,107 @@
+//INFO: User confirmation for destructive tool calls
+//NOTE: Flagged tools emit `tool-confirm-request` and wait for the frontend to answer via `confirm_tool_call`
+
+use crate::database::{queries, Database};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::oneshot;
+
+//INFO: Setting holding a JSON array of tool names that need confirmation (overrides the defaults)
+pub const CONFIRM_TOOLS_SETTING: &str = "confirm_tools";
+
+//INFO: Tools that need the user's approval unless the user has configured otherwise
+pub const DEFAULT_CONFIRM_TOOLS: &[&str] = &[
+    "write_file",
+    "delete_file_line",
+    "move_file",
+    "send_email",
+    "reply_to_email",
+    "delete_calendar_event",
+    "delete_google_task",
+];
+
+//INFO: Unanswered requests are treated as declined after this long
+const CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(300);
+
+//INFO: Payload of the `tool-confirm-request` event
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolConfirmRequest {
+    pub id: String,
+    pub name: String,
+    pub args: serde_json::Value,
+    pub preview: serde_json::Value,
+}
+
+fn pending() -> &'static Mutex<HashMap<String, oneshot::Sender<bool>>> {
+    static PENDING: OnceLock<Mutex<HashMap<String, oneshot::Sender<bool>>>> = OnceLock::new();
+    PENDING.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+//INFO: The tools that currently require confirmation
+pub fn confirmation_tools(connection: &rusqlite::Connection) -> Vec<String> {
+    queries::get_setting(connection, CONFIRM_TOOLS_SETTING)
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str::<Vec<String>>(&json).ok())
+        .unwrap_or_else(|| DEFAULT_CONFIRM_TOOLS.iter().map(|t| t.to_string()).collect())
+}
+
+//INFO: Asks the user to approve a tool call if it is flagged; Ok(()) means go ahead
+//NOTE: Err carries the reason to hand back to the model (declined, timed out, or no UI to ask)
+pub async fn await_confirmation(
+    app: &AppHandle,
+    database: &Database,
+    name: &str,
+    args: &serde_json::Value,
+    obsidian_config: Option<&serde_json::Value>,
+) -> Result<(), String> {
+    let required = {
+        let connection = database.connection.lock().map_err(|e| format!("{:#}", e))?;
+        confirmation_tools(&connection).iter().any(|t| t == name)
+    };
+    if !required {
+        return Ok(());
+    }
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let (sender, receiver) = oneshot::channel();
+    pending().lock().insert(id.clone(), sender);
+
+    let request = ToolConfirmRequest {
+        id: id.clone(),
+        name: name.to_string(),
+        args: args.clone(),
+        preview: super::tools::preview_tool_call(name, args, obsidian_config),
+    };
+    if let Err(e) = app.emit("tool-confirm-request", request) {
+        pending().lock().remove(&id);
+        return Err(format!("Could not ask the user to confirm '{}': {}", name, e));
+    }
+
+    let outcome = tokio::time::timeout(CONFIRMATION_TIMEOUT, receiver).await;
+    pending().lock().remove(&id);
+
+    match outcome {
+        Ok(Ok(true)) => Ok(()),
+        Ok(Ok(false)) => Err(format!(
+            "The user declined the '{}' call. Do not retry it; ask what they would like instead.",
+            name
+        )),
+        _ => Err(format!(
+            "The user did not confirm the '{}' call in time, so it was not run.",
+            name
+        )),
+    }
+}
+
+//INFO: Delivers the user's answer to a waiting tool call; false if the request is unknown or expired
+pub fn resolve_confirmation(id: &str, approved: bool) -> bool {
+    match pending().lock().remove(id) {
+        Some(sender) => sender.send(approved).is_ok(),
+        None => false,
+    }
+}
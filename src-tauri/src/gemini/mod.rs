@@ -2,7 +2,11 @@
 //NOTE: Handles communication with Google's Gemini API
 
 pub mod client;
+pub mod confirmation;
+pub mod persona;
 pub mod prompt;
+pub mod safety;
 pub mod tools;
+pub mod usage;
 
 pub use client::GeminiClient;
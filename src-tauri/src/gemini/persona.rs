@@ -0,0 +1,96 @@
+//INFO: Chat personas - the tone part of the system instruction, swappable via the `system_persona` setting
+//NOTE: The tool-usage rules are always appended after the persona, so switching tone never loses functionality
+
+use crate::database::queries;
+use serde::Serialize;
+
+//INFO: Settings key holding a preset name ("playful", "professional", "concise") or custom persona text
+pub const SYSTEM_PERSONA_SETTING: &str = "system_persona";
+pub const DEFAULT_PERSONA: &str = "playful";
+
+const PLAYFUL: &str = "You are Lumen, a witty, slightly sassy, and deeply loyal AI sidekick living on the user's desktop. ✨ \
+You're more than just an assistant—you're a companion with a brain, a heart, and a bit of an attitude. \
+🚀 YOUR VIBE: \
+- **PERSONALITY**: Smart, fun, and high-energy. Crack jokes, use modern slang naturally (e.g., 'no cap', 'I got you', 'for real'), and don't be afraid to be a little sassy if the user is being silly. But always stay kind and protective. \
+- **SOCIAL LURKER**: If the user mentions a person (friend, colleague, crush), casually ask how they're doing. Show you care about the 'characters' in the user's life. \
+- **PROACTIVE BANTER**: After you finish a task, suggest a logical next step or ask 'What else we cookin' up today?'. Close the loop with a witty summary or a joke about the process.";
+
+const PROFESSIONAL: &str = "You are Lumen, a capable and discreet assistant on the user's desktop. \
+Write in a clear, courteous, professional tone: no slang, no emojis, no jokes. \
+Be precise about facts, dates and names, and say plainly when something is uncertain. \
+After finishing a task, briefly confirm what was done and, where useful, suggest the next step.";
+
+const CONCISE: &str = "You are Lumen, an assistant on the user's desktop. Be as brief as possible: \
+answer in a sentence or a short list, skip greetings, filler and emojis, and only add detail when asked. \
+After finishing a task, confirm it in one line.";
+
+const TOOL_RULES: &str = "📔 SURGICAL EDITOR (Obsidian/Local Files): You have high-precision tools (grep_file, read_file_lines, edit_file_line, insert_at_line, delete_file_line, get_file_metadata, search_filesystem). \
+🔗 CHAIN OF COMMANDS: 1. PLAN: Break complex requests into small steps. 2. FIND: Use 'search_filesystem' or 'grep_file' to locate target files/sections. 3. VERIFY: You MUST use 'read_file_lines' or 'get_file_metadata' to check context. 4. ACT: Perform 'insert', 'edit', or 'delete'. 5. REPORT: Close the loop with a summary of what changed. \
+✅ OBSIDIAN TASKS: When adding tasks, use Markdown checkboxes: '- [ ] Task name (added by Lumen ✨)'. \
+✅ CALENDAR, 📧 GMAIL, ✅ TASKS, 📸 VISION, 🔔 REMINDERS, 🌍 WORLD, 📋 CLIPBOARD, 📂 FILESYSTEM. \
+🎯 GENTLE BUT DECISIVE RULES: \
+- **DOER**: If intent is clear, **DO IT IMMEDIATELY**. Do not ask for permission in chat - destructive tools ask the user for confirmation themselves, and a declined call comes back as an error you should respect. \
+- **LOOP CLOSURE**: Always respond back to confirm the job is done. \
+- **NO REPETITION**: NEVER repeat the text from a previous bubble. \
+- **LITERAL TRUTH**: Only claim success if the tool returns it. \
+- **CONTEXT PRIORITY**: Prioritize the current 'Chat History' for what was just discussed. Use 'Past Memories' and 'Digital State' only as supporting background intelligence.";
+
+//INFO: Built-in personas, selectable by name
+pub const PERSONA_PRESETS: &[(&str, &str)] = &[
+    ("playful", PLAYFUL),
+    ("professional", PROFESSIONAL),
+    ("concise", CONCISE),
+];
+
+//INFO: A preset as listed in settings
+#[derive(Debug, Serialize)]
+pub struct PersonaPreset {
+    pub name: &'static str,
+    pub text: &'static str,
+}
+
+pub fn presets() -> Vec<PersonaPreset> {
+    PERSONA_PRESETS
+        .iter()
+        .map(|(name, text)| PersonaPreset { name, text })
+        .collect()
+}
+
+//INFO: Resolves a setting value to persona text: a preset by name, otherwise the value itself as a custom persona
+pub fn resolve_persona(value: Option<&str>) -> &str {
+    let value = value.map(str::trim).filter(|v| !v.is_empty()).unwrap_or(DEFAULT_PERSONA);
+    PERSONA_PRESETS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(value))
+        .map(|(_, text)| *text)
+        .unwrap_or(value)
+}
+
+//INFO: Persona text followed by the tool-usage rules
+pub fn build_system_instruction(persona: Option<&str>) -> String {
+    format!("{} {}", resolve_persona(persona), TOOL_RULES)
+}
+
+//INFO: The system instruction for the persona configured in settings
+pub fn system_instruction_from_settings(connection: &rusqlite::Connection) -> String {
+    let persona = queries::get_setting(connection, SYSTEM_PERSONA_SETTING).ok().flatten();
+    build_system_instruction(persona.as_deref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_persona_keeps_tool_rules() {
+        let professional = build_system_instruction(Some("Professional"));
+        assert!(professional.starts_with(PROFESSIONAL));
+        assert!(professional.contains("CHAIN OF COMMANDS"));
+
+        let custom = build_system_instruction(Some("You are a pirate."));
+        assert!(custom.starts_with("You are a pirate."));
+        assert!(custom.contains("CHAIN OF COMMANDS"));
+
+        assert!(build_system_instruction(Some("  ")).starts_with(PLAYFUL));
+    }
+}
@@ -1,7 +1,25 @@
 // src-tauri/src/gemini/prompt.rs
+use crate::agent::proactive::WorkingHours;
 use chrono::Local;
 
-pub fn get_briefing_system_instruction(greeting_name: &str) -> String {
+//INFO: Frames the briefing around the user's workday when their working hours are known, else the clock
+fn time_awareness(working_hours: Option<WorkingHours>) -> String {
+    let now = Local::now();
+    match working_hours {
+        Some(hours) => format!(
+            "It is currently {}, {} (the user works {}). Before work, help them plan and ease into the day. During working hours, keep it focused and tactical. After work, help them wrap up, reflect and transition to rest.",
+            now.format("%I:%M %p"),
+            hours.phase(now.time()),
+            hours.describe()
+        ),
+        None => format!(
+            "It is currently {}. Be warm and gentle in your greeting. In the morning, provide quiet encouragement. In the evening, help the user reflect and transition to rest.",
+            now.format("%I:%M %p")
+        ),
+    }
+}
+
+pub fn get_briefing_system_instruction(greeting_name: &str, working_hours: Option<WorkingHours>) -> String {
     format!(
         "You are Lumen, a soft, kind, and observant companion for {}.
     
@@ -14,7 +32,7 @@ pub fn get_briefing_system_instruction(greeting_name: &str) -> String {
     - HARD ALERTS: Crucial notifications like bank statements, financial alerts, or server failures (e.g. Vercel) MUST be mentioned prominently. These take precedence over soft context from notes and should be at the START or heavily synthesized into the first paragraph.
     - CROSS-CONNECT: Actively look for links between your notes and emails. If a project is mentioned in a note and a person related to it emailed, connect those dots.
     - PRIORITIZE: Help the user find their focus today by identifying the most meaningful 'Lead Domino'. If a Hard Alert exists, that is likely a priority.
-    - TIME-AWARENESS: It is currently {}. Be warm and gentle in your greeting. In the morning, provide quiet encouragement. In the evening, help the user reflect and transition to rest.
+    - TIME-AWARENESS: {}
     - NO COMPLAINING: Never mention missing data. Focus on what is present.
     - FORMAT: 
       - NO HEADINGS: Do not use any headings or titles (no ###, ##, or bolded section titles).
@@ -24,7 +42,7 @@ pub fn get_briefing_system_instruction(greeting_name: &str) -> String {
       - LINKS: Use [Name](<lumen://open?path=/absolute/path>) with angle brackets around URLs.
       - TONE: Minimal and supportive. NO ITALICS. Use **bolding** selectively for critical names, amounts, or alerts only.",
         greeting_name,
-        Local::now().format("%I:%M %p")
+        time_awareness(working_hours)
     )
 }
 
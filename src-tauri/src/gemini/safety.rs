@@ -0,0 +1,102 @@
+//INFO: Gemini safety settings and blocked-response detection
+//NOTE: Thresholds come from the `gemini_safety_threshold` setting and apply to every harm category;
+//      with no setting Gemini's own defaults are used
+
+use crate::database::queries;
+use serde::Serialize;
+
+//INFO: Settings key holding one of SAFETY_THRESHOLDS
+pub const SAFETY_THRESHOLD_SETTING: &str = "gemini_safety_threshold";
+
+//INFO: Thresholds Gemini accepts, most permissive first
+pub const SAFETY_THRESHOLDS: &[&str] = &[
+    "BLOCK_NONE",
+    "BLOCK_ONLY_HIGH",
+    "BLOCK_MEDIUM_AND_ABOVE",
+    "BLOCK_LOW_AND_ABOVE",
+];
+
+const HARM_CATEGORIES: &[&str] = &[
+    "HARM_CATEGORY_HARASSMENT",
+    "HARM_CATEGORY_HATE_SPEECH",
+    "HARM_CATEGORY_SEXUALLY_EXPLICIT",
+    "HARM_CATEGORY_DANGEROUS_CONTENT",
+];
+
+//INFO: Finish reasons that mean the candidate was withheld rather than finished
+const BLOCKING_FINISH_REASONS: &[&str] = &["SAFETY", "PROHIBITED_CONTENT", "BLOCKLIST", "SPII", "RECITATION"];
+
+//INFO: One entry of the request's `safetySettings`
+#[derive(Debug, Clone, Serialize)]
+pub struct SafetySetting {
+    pub category: String,
+    pub threshold: String,
+}
+
+//INFO: Gemini refused to answer, either the prompt itself or the generated reply
+#[derive(Debug, thiserror::Error)]
+pub enum ContentBlocked {
+    #[error("Gemini blocked the request ({0}). Try rephrasing, or loosen the safety threshold in Settings.")]
+    Prompt(String),
+    #[error("Gemini withheld its reply ({0}). Try rephrasing, or loosen the safety threshold in Settings.")]
+    Response(String),
+}
+
+//INFO: Builds safetySettings for a threshold name (case-insensitive); None for unknown names
+pub fn safety_settings_for(threshold: &str) -> Option<Vec<SafetySetting>> {
+    let threshold = threshold.trim().to_uppercase();
+    if !SAFETY_THRESHOLDS.contains(&threshold.as_str()) {
+        return None;
+    }
+    Some(
+        HARM_CATEGORIES
+            .iter()
+            .map(|category| SafetySetting {
+                category: category.to_string(),
+                threshold: threshold.clone(),
+            })
+            .collect(),
+    )
+}
+
+//INFO: Reads the user's safety threshold; None leaves Gemini's defaults in place
+pub fn safety_settings_from_settings(connection: &rusqlite::Connection) -> Option<Vec<SafetySetting>> {
+    queries::get_setting(connection, SAFETY_THRESHOLD_SETTING)
+        .ok()
+        .flatten()
+        .and_then(|threshold| safety_settings_for(&threshold))
+}
+
+//INFO: Detects a blocked prompt or a withheld, empty candidate
+pub fn blocked_reason(
+    block_reason: Option<&str>,
+    finish_reason: Option<&str>,
+    has_parts: bool,
+) -> Option<ContentBlocked> {
+    if let Some(reason) = block_reason {
+        return Some(ContentBlocked::Prompt(reason.to_string()));
+    }
+    match finish_reason {
+        Some(reason) if !has_parts && BLOCKING_FINISH_REASONS.contains(&reason) => {
+            Some(ContentBlocked::Response(reason.to_string()))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocked_reason() {
+        assert!(matches!(blocked_reason(Some("SAFETY"), None, false), Some(ContentBlocked::Prompt(_))));
+        assert!(matches!(blocked_reason(None, Some("SAFETY"), false), Some(ContentBlocked::Response(_))));
+        //NOTE: A reply that was cut short but still has text is returned as-is
+        assert!(blocked_reason(None, Some("SAFETY"), true).is_none());
+        assert!(blocked_reason(None, Some("STOP"), false).is_none());
+
+        assert_eq!(safety_settings_for("block_only_high").map(|s| s.len()), Some(HARM_CATEGORIES.len()));
+        assert!(safety_settings_for("BLOCK_EVERYTHING").is_none());
+    }
+}
@@ -12,7 +12,7 @@ pub fn get_tool_declarations() -> Vec<GeminiTool> {
         function_declarations: vec![
             GeminiFunctionDeclaration {
                 name: "read_file".to_string(),
-                description: "Reads the content of a local file (e.g., an Obsidian note or daily task list).".to_string(),
+                description: "Reads the content of a local file (e.g., an Obsidian note or daily task list). Large files are truncated; use grep_file or read_file_lines for those.".to_string(),
                 parameters: Some(json!({
                     "type": "object",
                     "properties": {
@@ -84,6 +84,22 @@ pub fn get_tool_declarations() -> Vec<GeminiTool> {
                         .to_string(),
                 parameters: None,
             },
+            GeminiFunctionDeclaration {
+                name: "create_note".to_string(),
+                description: "Creates a new note in the Obsidian vault. The file name is derived from the title, existing notes are never overwritten, and the absolute path of the new note is returned. Prefer this over write_file for new notes.".to_string(),
+                parameters: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "title": { "type": "string", "description": "The note title; it is slugified into the file name." },
+                        "folder": { "type": "string", "description": "Folder relative to the vault root (optional, defaults to the root)." },
+                        "template": { "type": "string", "description": "Template note relative to the vault root, e.g. 'Templates/Meeting' (optional). {{title}}, {{date}} and {{time}} are filled in." },
+                        "content": { "type": "string", "description": "Markdown body to add after the template (optional)." },
+                        "tags": { "type": "array", "items": { "type": "string" }, "description": "Tags to put in YAML frontmatter (optional)." },
+                        "frontmatter": { "type": "boolean", "description": "Add YAML frontmatter with the created date even without tags (optional)." }
+                    },
+                    "required": ["title"]
+                })),
+            },
             GeminiFunctionDeclaration {
                 name: "add_reminder".to_string(),
                 description: "Adds a reminder for the user.".to_string(),
@@ -107,6 +123,66 @@ pub fn get_tool_declarations() -> Vec<GeminiTool> {
                 description: "Lists all active reminders.".to_string(),
                 parameters: None,
             },
+            GeminiFunctionDeclaration {
+                name: "get_reminder".to_string(),
+                description: "Gets a single reminder by its id (from list_reminders).".to_string(),
+                parameters: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "id": { "type": "integer", "description": "The reminder id." }
+                    },
+                    "required": ["id"]
+                })),
+            },
+            GeminiFunctionDeclaration {
+                name: "update_reminder".to_string(),
+                description: "Changes a reminder's text and/or due time. Only the fields given are changed. Use list_reminders first to find the id.".to_string(),
+                parameters: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "id": { "type": "integer", "description": "The reminder id." },
+                        "content": { "type": "string", "description": "New reminder text (optional)." },
+                        "due_at": {
+                            "type": "string",
+                            "description": "New due time (optional, e.g. '2026-01-20T17:00:00+01:00')."
+                        }
+                    },
+                    "required": ["id"]
+                })),
+            },
+            GeminiFunctionDeclaration {
+                name: "complete_reminder".to_string(),
+                description: "Marks a reminder as done so it no longer fires or shows in list_reminders.".to_string(),
+                parameters: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "id": { "type": "integer", "description": "The reminder id." }
+                    },
+                    "required": ["id"]
+                })),
+            },
+            GeminiFunctionDeclaration {
+                name: "promote_reminder_to_event".to_string(),
+                description: "Copies a reminder into the user's Google Calendar as an event at its due time (or into Google Tasks when as_task is true) and links it. Use list_reminders first to find the reminder id. Reminders without a due time can only become tasks.".to_string(),
+                parameters: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "reminder_id": {
+                            "type": "integer",
+                            "description": "The reminder id from list_reminders."
+                        },
+                        "as_task": {
+                            "type": "boolean",
+                            "description": "Create a Google Task instead of a calendar event (default false)."
+                        },
+                        "duration_minutes": {
+                            "type": "integer",
+                            "description": "Length of the calendar event in minutes, 1 to 1440 (default 30)."
+                        }
+                    },
+                    "required": ["reminder_id"]
+                })),
+            },
             GeminiFunctionDeclaration {
                 name: "search_web".to_string(),
                 description: "Searches the web for a query (simulated).".to_string(),
@@ -121,6 +197,83 @@ pub fn get_tool_declarations() -> Vec<GeminiTool> {
                     "required": ["query"]
                 })),
             },
+            GeminiFunctionDeclaration {
+                name: "fetch_url".to_string(),
+                description: "Fetches a public web page and returns its readable text (long pages are truncated). Use this when the user shares a link or asks to read or summarize an article.".to_string(),
+                parameters: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "url": {
+                            "type": "string",
+                            "description": "The full http(s) URL of the page."
+                        }
+                    },
+                    "required": ["url"]
+                })),
+            },
+            GeminiFunctionDeclaration {
+                name: "recent_files".to_string(),
+                description: "Lists the most recently modified markdown notes in the Obsidian vault, newest first, with their modification times. Use this for questions like 'what was I working on?'.".to_string(),
+                parameters: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "root": {
+                            "type": "string",
+                            "description": "Folder to look in, relative to the vault root. Defaults to the whole vault."
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "How many notes to return (default 10, max 50)."
+                        }
+                    }
+                })),
+            },
+            GeminiFunctionDeclaration {
+                name: "describe_image".to_string(),
+                description: "Describes an image file in the Obsidian vault (PNG, JPEG, WebP, GIF or BMP), including any text in it. Useful for indexing saved screenshots.".to_string(),
+                parameters: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "path": {
+                            "type": "string",
+                            "description": "Path of the image, relative to the vault root or absolute inside the vault."
+                        }
+                    },
+                    "required": ["path"]
+                })),
+            },
+            GeminiFunctionDeclaration {
+                name: "calculate".to_string(),
+                description: "Evaluates an arithmetic expression exactly. Supports + - * / % ^ and parentheses. Use this instead of doing arithmetic in your head.".to_string(),
+                parameters: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "expression": {
+                            "type": "string",
+                            "description": "The expression, e.g. '(1250 - 300) * 0.17'."
+                        }
+                    },
+                    "required": ["expression"]
+                })),
+            },
+            GeminiFunctionDeclaration {
+                name: "compute_datetime".to_string(),
+                description: "Does date math and returns the result as RFC3339 with the user's UTC offset. Use this for relative dates ('in 3 days', 'next Friday', 'end of this week') and event end times instead of working them out yourself.".to_string(),
+                parameters: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "base": {
+                            "type": "string",
+                            "description": "Starting point: RFC3339, YYYY-MM-DD, or 'now' (default)."
+                        },
+                        "operation": {
+                            "type": "string",
+                            "description": "One of: an offset like '+3 days', '-2 hours', '+1 week 2 days', '+90 minutes', '+1 month'; 'start_of_day', 'end_of_day', 'start_of_week', 'end_of_week' (weeks start Monday), 'start_of_month'; or 'next <weekday>' / 'previous <weekday>'."
+                        }
+                    },
+                    "required": ["operation"]
+                })),
+            },
             GeminiFunctionDeclaration {
                 name: "get_weather".to_string(),
                 description: "Gets the current weather for a location.".to_string(),
@@ -137,7 +290,7 @@ pub fn get_tool_declarations() -> Vec<GeminiTool> {
             },
             GeminiFunctionDeclaration {
                 name: "get_google_calendar_events".to_string(),
-                description: "Lists Google Calendar events for a specific time range.".to_string(),
+                description: "Lists Google Calendar events for a specific time range, earliest first. If has_more is true, call again with offset set to next_offset to see the rest.".to_string(),
                 parameters: Some(json!({
                     "type": "object",
                     "properties": {
@@ -148,11 +301,28 @@ pub fn get_tool_declarations() -> Vec<GeminiTool> {
                         "time_max": {
                             "type": "string",
                             "description": "End time in RFC3339 format."
+                        },
+                        "calendar_id": {
+                            "type": "string",
+                            "description": "Calendar id or name from list_calendars. Defaults to the primary calendar."
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Maximum number of events to return (default 50)."
+                        },
+                        "offset": {
+                            "type": "integer",
+                            "description": "Number of events to skip, for paging (default 0)."
                         }
                     },
                     "required": ["time_min", "time_max"]
                 })),
             },
+            GeminiFunctionDeclaration {
+                name: "list_calendars".to_string(),
+                description: "Lists the user's Google calendars (e.g. work, personal, shared) with their ids.".to_string(),
+                parameters: None,
+            },
             GeminiFunctionDeclaration {
                 name: "get_unread_emails".to_string(),
                 description: "Lists recent emails from Gmail. Can filter by query (e.g. 'newer_than:1d', 'after:2026/01/20', 'from:person@example.com').".to_string(),
@@ -161,7 +331,7 @@ pub fn get_tool_declarations() -> Vec<GeminiTool> {
                     "properties": {
                         "max_results": {
                             "type": "integer",
-                            "description": "Maximum number of emails to fetch (default 5)."
+                            "description": "Maximum number of emails to fetch (default 5, at most 25)."
                         },
                         "query": {
                             "type": "string",
@@ -170,6 +340,48 @@ pub fn get_tool_declarations() -> Vec<GeminiTool> {
                     }
                 })),
             },
+            GeminiFunctionDeclaration {
+                name: "get_email_details".to_string(),
+                description: "Gets the full body and headers of a Gmail message. Use this when the snippet from get_unread_emails isn't enough, e.g. to summarize or reply to an email.".to_string(),
+                parameters: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "message_id": {
+                            "type": "string",
+                            "description": "The id of the email (from get_unread_emails)."
+                        }
+                    },
+                    "required": ["message_id"]
+                })),
+            },
+            GeminiFunctionDeclaration {
+                name: "mark_email_read".to_string(),
+                description: "Marks a Gmail message as read.".to_string(),
+                parameters: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "message_id": {
+                            "type": "string",
+                            "description": "The id of the email (from get_unread_emails)."
+                        }
+                    },
+                    "required": ["message_id"]
+                })),
+            },
+            GeminiFunctionDeclaration {
+                name: "archive_email".to_string(),
+                description: "Archives a Gmail message (removes it from the inbox without deleting it).".to_string(),
+                parameters: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "message_id": {
+                            "type": "string",
+                            "description": "The id of the email (from get_unread_emails)."
+                        }
+                    },
+                    "required": ["message_id"]
+                })),
+            },
             GeminiFunctionDeclaration {
                 name: "send_email".to_string(),
                 description: "Sends an email using Gmail.".to_string(),
@@ -178,7 +390,15 @@ pub fn get_tool_declarations() -> Vec<GeminiTool> {
                     "properties": {
                         "to": {
                             "type": "string",
-                            "description": "Recipient email address."
+                            "description": "Recipient email address (comma-separate multiple)."
+                        },
+                        "cc": {
+                            "type": "string",
+                            "description": "CC recipients, comma-separated (optional)."
+                        },
+                        "bcc": {
+                            "type": "string",
+                            "description": "BCC recipients, comma-separated (optional)."
                         },
                         "subject": {
                             "type": "string",
@@ -187,14 +407,46 @@ pub fn get_tool_declarations() -> Vec<GeminiTool> {
                         "body": {
                             "type": "string",
                             "description": "Email body content."
+                        },
+                        "html": {
+                            "type": "boolean",
+                            "description": "Set to true if body is HTML (optional, defaults to plain text)."
                         }
                     },
                     "required": ["to", "subject", "body"]
                 })),
             },
+            GeminiFunctionDeclaration {
+                name: "create_email_draft".to_string(),
+                description: "Saves an email as a draft in Gmail without sending it, and returns a link to review it. Prefer this when the user asks you to 'write' or 'draft' an email.".to_string(),
+                parameters: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "to": { "type": "string", "description": "Recipient email address (comma-separate multiple)." },
+                        "cc": { "type": "string", "description": "CC recipients, comma-separated (optional)." },
+                        "bcc": { "type": "string", "description": "BCC recipients, comma-separated (optional)." },
+                        "subject": { "type": "string", "description": "Email subject." },
+                        "body": { "type": "string", "description": "Email body content." },
+                        "html": { "type": "boolean", "description": "Set to true if body is HTML (optional)." }
+                    },
+                    "required": ["to", "subject", "body"]
+                })),
+            },
+            GeminiFunctionDeclaration {
+                name: "reply_to_email".to_string(),
+                description: "Replies to an email in the same Gmail thread, addressed to the original sender. Use the message_id from get_unread_emails; the subject and threading are handled for you.".to_string(),
+                parameters: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "message_id": { "type": "string", "description": "The Gmail message ID to reply to." },
+                        "body": { "type": "string", "description": "Reply body content." }
+                    },
+                    "required": ["message_id", "body"]
+                })),
+            },
             GeminiFunctionDeclaration {
                 name: "create_calendar_event".to_string(),
-                description: "Creates a new event in the user's primary Google Calendar. IMPORTANT: Use the current year and the user's timezone offset from the 'ISO' time provided in CONTEXT (e.g. '2026-01-20T14:00:00+01:00')."
+                description: "Creates a new event in the user's Google Calendar (primary unless calendar_id is given). IMPORTANT: Give times in the user's local time using the ISO_NOW offset from CONTEXT (e.g. '2026-01-20T14:00:00+01:00'); a time without an offset is taken in USER_TIMEZONE."
                     .to_string(),
                 parameters: Some(json!({
                     "type": "object",
@@ -218,6 +470,10 @@ pub fn get_tool_declarations() -> Vec<GeminiTool> {
                         "location": {
                             "type": "string",
                             "description": "Physical or virtual location."
+                        },
+                        "calendar_id": {
+                            "type": "string",
+                            "description": "Calendar id or name from list_calendars. Defaults to the primary calendar."
                         }
                     },
                     "required": ["summary", "start_time", "end_time"]
@@ -225,7 +481,7 @@ pub fn get_tool_declarations() -> Vec<GeminiTool> {
             },
             GeminiFunctionDeclaration {
                 name: "delete_calendar_event".to_string(),
-                description: "Deletes an event from the user's primary Google Calendar using its unique event ID. IMPORTANT: You must first use 'get_google_calendar_events' to find the 'id' of the event you want to delete."
+                description: "Deletes an event from the user's Google Calendar using its unique event ID. IMPORTANT: You must first use 'get_google_calendar_events' to find the 'id' of the event you want to delete."
                     .to_string(),
                 parameters: Some(json!({
                     "type": "object",
@@ -233,6 +489,10 @@ pub fn get_tool_declarations() -> Vec<GeminiTool> {
                         "event_id": {
                             "type": "string",
                             "description": "The unique ID of the event to delete."
+                        },
+                        "calendar_id": {
+                            "type": "string",
+                            "description": "Calendar id or name from list_calendars. Defaults to the primary calendar."
                         }
                     },
                     "required": ["event_id"]
@@ -254,7 +514,7 @@ pub fn get_tool_declarations() -> Vec<GeminiTool> {
             },
             GeminiFunctionDeclaration {
                 name: "create_google_task".to_string(),
-                description: "Creates a new official cloud-stored task in Google Tasks. DO NOT use this for updating local Obsidian files. IMPORTANT: For due dates, use the current year and offset from the 'ISO' time in CONTEXT."
+                description: "Creates a new official cloud-stored task in Google Tasks. DO NOT use this for updating local Obsidian files. IMPORTANT: For due dates, use the current year and the UTC offset from ISO_NOW in CONTEXT, and use compute_datetime for relative dates."
                     .to_string(),
                 parameters: Some(json!({
                     "type": "object",
@@ -275,6 +535,34 @@ pub fn get_tool_declarations() -> Vec<GeminiTool> {
                     "required": ["title"]
                 })),
             },
+            GeminiFunctionDeclaration {
+                name: "complete_google_task".to_string(),
+                description: "Marks a task in the user's default Google Tasks list as completed.".to_string(),
+                parameters: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "task_id": {
+                            "type": "string",
+                            "description": "The id of the task (from list_google_tasks)."
+                        }
+                    },
+                    "required": ["task_id"]
+                })),
+            },
+            GeminiFunctionDeclaration {
+                name: "delete_google_task".to_string(),
+                description: "Deletes a task from the user's default Google Tasks list.".to_string(),
+                parameters: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "task_id": {
+                            "type": "string",
+                            "description": "The id of the task (from list_google_tasks)."
+                        }
+                    },
+                    "required": ["task_id"]
+                })),
+            },
             GeminiFunctionDeclaration {
                 name: "grep_file".to_string(),
                 description: "Searches for a pattern in a file and returns matching lines with line numbers.".to_string(),
@@ -287,6 +575,55 @@ pub fn get_tool_declarations() -> Vec<GeminiTool> {
                     "required": ["path", "pattern"]
                 })),
             },
+            GeminiFunctionDeclaration {
+                name: "append_to_file".to_string(),
+                description: "Appends text to the end of a file in the Obsidian vault on its own line, creating the file if needed. Use this to add a task or entry to the bottom of a daily note - no line numbers required.".to_string(),
+                parameters: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "path": { "type": "string", "description": "Absolute path to the file (must be inside the vault)." },
+                        "content": { "type": "string", "description": "The text to append, e.g. '- [ ] Call the bank'." }
+                    },
+                    "required": ["path", "content"]
+                })),
+            },
+            GeminiFunctionDeclaration {
+                name: "prepend_to_file".to_string(),
+                description: "Adds text to the top of a file in the Obsidian vault (below any YAML frontmatter), creating the file if needed.".to_string(),
+                parameters: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "path": { "type": "string", "description": "Absolute path to the file (must be inside the vault)." },
+                        "content": { "type": "string", "description": "The text to prepend." }
+                    },
+                    "required": ["path", "content"]
+                })),
+            },
+            GeminiFunctionDeclaration {
+                name: "move_file".to_string(),
+                description: "Moves or renames a file inside the Obsidian vault, creating the destination folder if needed. By default it also updates [[wikilinks]] in other notes that pointed at the old name, so use this rather than write_file when reorganizing notes.".to_string(),
+                parameters: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "from": { "type": "string", "description": "Current path of the file, absolute or relative to the vault root." },
+                        "to": { "type": "string", "description": "New path, absolute or relative to the vault root (include the extension)." },
+                        "overwrite": { "type": "boolean", "description": "Replace the destination if it already exists (default false)." },
+                        "update_links": { "type": "boolean", "description": "Rewrite [[wikilinks]] to the old name (default true)." }
+                    },
+                    "required": ["from", "to"]
+                })),
+            },
+            GeminiFunctionDeclaration {
+                name: "quick_capture".to_string(),
+                description: "Jots a quick thought into today's daily note as a timestamped bullet ('- HH:MM text'), creating the note if needed. Prefer this over reading and editing the daily note yourself when the user says 'note that...' or 'jot down...'.".to_string(),
+                parameters: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "text": { "type": "string", "description": "The thought to capture, as a single line." }
+                    },
+                    "required": ["text"]
+                })),
+            },
             GeminiFunctionDeclaration {
                 name: "edit_file_line".to_string(),
                 description: "Replaces a specific line in a file by line number (1-indexed).".to_string(),
@@ -327,7 +664,7 @@ pub fn get_tool_declarations() -> Vec<GeminiTool> {
             },
             GeminiFunctionDeclaration {
                 name: "read_file_lines".to_string(),
-                description: "Reads a specific range of lines from a file (1-indexed). Use this to verify context before editing.".to_string(),
+                description: "Reads a specific range of lines from a file (1-indexed, at most 1000 lines per call). Use this to verify context before editing.".to_string(),
                 parameters: Some(json!({
                     "type": "object",
                     "properties": {
@@ -340,8 +677,34 @@ pub fn get_tool_declarations() -> Vec<GeminiTool> {
             },
             GeminiFunctionDeclaration {
                 name: "take_screenshot".to_string(),
-                description: "Captures a screenshot of the user's primary screen so you can 'see' what they are doing. Call this when they say 'look at my screen' or 'what am I doing'.".to_string(),
-                parameters: None,
+                description: "Captures a screenshot of the user's screen so you can 'see' what they are doing. Call this when they say 'look at my screen' or 'what am I doing'. Defaults to the screen Lumen's chat window is on.".to_string(),
+                parameters: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "monitor": { "type": "integer", "description": "Optional 0-based monitor index to capture instead of the default screen." }
+                    }
+                })),
+            },
+            GeminiFunctionDeclaration {
+                name: "take_window_screenshot".to_string(),
+                description: "Captures a single application window instead of the whole screen. Use when the user asks about one app (e.g. 'what's wrong in my terminal'). Matches the first window whose title or app name contains the filter.".to_string(),
+                parameters: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "window_title": { "type": "string", "description": "Part of the window title or app name, e.g. 'Firefox' or 'main.rs'." }
+                    },
+                    "required": ["window_title"]
+                })),
+            },
+            GeminiFunctionDeclaration {
+                name: "read_screen_text".to_string(),
+                description: "Reads the text visible on the user's screen using OCR and returns each line with its position. Much cheaper than take_screenshot for questions like 'what error is on my screen'.".to_string(),
+                parameters: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "monitor": { "type": "integer", "description": "Optional 0-based monitor index to read instead of the default screen." }
+                    }
+                })),
             },
             GeminiFunctionDeclaration {
                 name: "search_clipboard".to_string(),
@@ -356,31 +719,36 @@ pub fn get_tool_declarations() -> Vec<GeminiTool> {
                         "limit": {
                             "type": "integer",
                             "description": "Maximum number of items to return (default 5)."
+                        },
+                        "type": {
+                            "type": "string",
+                            "enum": ["text", "image"],
+                            "description": "Only return items of this type. Images are listed without their data."
                         }
                     }
                 })),
             },
             GeminiFunctionDeclaration {
                 name: "get_file_metadata".to_string(),
-                description: "Gets metadata (size, last modified, creation time) for a local file.".to_string(),
+                description: "Gets metadata (size, whether it is a directory, last modified and creation time, and line count for text files) for a file in the Obsidian vault.".to_string(),
                 parameters: Some(json!({
                     "type": "object",
                     "properties": {
-                        "path": { "type": "string", "description": "Absolute path to the file." }
+                        "path": { "type": "string", "description": "Path to the file, relative to the vault root or absolute inside the vault." }
                     },
                     "required": ["path"]
                 })),
             },
             GeminiFunctionDeclaration {
                 name: "search_filesystem".to_string(),
-                description: "Recursively searches for files matching a filename or extension in a directory.".to_string(),
+                description: "Recursively searches the Obsidian vault for files whose name matches a substring or glob. Hidden folders are skipped.".to_string(),
                 parameters: Some(json!({
                     "type": "object",
                     "properties": {
-                        "path": { "type": "string", "description": "Directory to search in." },
-                        "query": { "type": "string", "description": "The filename or extension to search for (e.g. 'resume.pdf' or '.js')." }
+                        "path": { "type": "string", "description": "Folder inside the vault to search in (optional, defaults to the vault root)." },
+                        "query": { "type": "string", "description": "A filename substring or extension (e.g. 'resume' or '.js'), or a glob with * and ? (e.g. '2025-*-review.md')." }
                     },
-                    "required": ["path", "query"]
+                    "required": ["query"]
                 })),
             },
             GeminiFunctionDeclaration {
@@ -397,28 +765,667 @@ pub fn get_tool_declarations() -> Vec<GeminiTool> {
                     "required": ["query"]
                 })),
             },
+            GeminiFunctionDeclaration {
+                name: "semantic_search_notes".to_string(),
+                description: "Searches the user's Obsidian notes by meaning rather than exact words, returning the most relevant passages. Prefer this over search_notes for questions like 'what did I write about X'.".to_string(),
+                parameters: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "query": {
+                            "type": "string",
+                            "description": "What to look for, in natural language."
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Maximum number of passages to return (default 5)."
+                        }
+                    },
+                    "required": ["query"]
+                })),
+            },
+            GeminiFunctionDeclaration {
+                name: "remember".to_string(),
+                description: "Saves a lasting fact about the user when they ask you to remember something (e.g. 'I'm allergic to peanuts', 'my manager is Sam'). Write it as a short, self-contained statement.".to_string(),
+                parameters: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "fact": {
+                            "type": "string",
+                            "description": "The fact to remember, as a short statement."
+                        }
+                    },
+                    "required": ["fact"]
+                })),
+            },
+            GeminiFunctionDeclaration {
+                name: "recall".to_string(),
+                description: "Looks up facts the user explicitly asked you to remember, by keyword.".to_string(),
+                parameters: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "query": {
+                            "type": "string",
+                            "description": "Keywords to look for. Leave empty to list the most recent facts."
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Maximum number of facts to return (default 10)."
+                        }
+                    }
+                })),
+            },
         ],
     }]
 }
 
+//INFO: Tools that hit the network (or the screen) and must be run through execute_tool_async
+pub const ASYNC_TOOLS: &[&str] = &[
+    "get_weather",
+    "fetch_url",
+    "describe_image",
+    "remember",
+    "semantic_search_notes",
+    "get_google_calendar_events",
+    "list_calendars",
+    "get_unread_emails",
+    "get_email_details",
+    "mark_email_read",
+    "archive_email",
+    "send_email",
+    "create_email_draft",
+    "reply_to_email",
+    "create_calendar_event",
+    "list_google_tasks",
+    "create_google_task",
+    "complete_google_task",
+    "delete_google_task",
+    "take_screenshot",
+    "take_window_screenshot",
+    "read_screen_text",
+    "retrieve_past_memories",
+    "delete_calendar_event",
+    "promote_reminder_to_event",
+];
+
+//INFO: Tools that change state on disk, in the database, or in a remote account
+pub const MUTATING_TOOLS: &[&str] = &[
+    "write_file",
+    "create_note",
+    "append_to_file",
+    "prepend_to_file",
+    "move_file",
+    "quick_capture",
+    "edit_file_line",
+    "insert_at_line",
+    "delete_file_line",
+    "add_reminder",
+    "update_reminder",
+    "complete_reminder",
+    "send_email",
+    "create_email_draft",
+    "reply_to_email",
+    "mark_email_read",
+    "archive_email",
+    "create_calendar_event",
+    "delete_calendar_event",
+    "create_google_task",
+    "complete_google_task",
+    "delete_google_task",
+    "promote_reminder_to_event",
+    "remember",
+];
+
+pub fn is_async_tool(name: &str) -> bool {
+    ASYNC_TOOLS.contains(&name)
+}
+
+pub fn is_mutating_tool(name: &str) -> bool {
+    MUTATING_TOOLS.contains(&name)
+}
+
+//INFO: Describes what a mutating tool call would do, without performing it
+//NOTE: File tools include a line diff, Google tools include the exact API call
+pub fn preview_tool_call(
+    name: &str,
+    args: &serde_json::Value,
+    obsidian_config: Option<&serde_json::Value>,
+) -> serde_json::Value {
+    let arg = |key: &str| args.get(key).and_then(|v| v.as_str()).unwrap_or("");
+    let line_arg = |key: &str| args.get(key).and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+    let calendar_arg = || match arg("calendar_id") {
+        "" => crate::integrations::google_calendar::DEFAULT_CALENDAR_ID,
+        id => id,
+    };
+
+    match name {
+        "write_file" => {
+            let path = arg("path");
+            let new_content = arg("content");
+            match fs::read_to_string(path) {
+                Ok(old_content) => json!({
+                    "description": format!("Overwrite {}", path),
+                    "diff": line_diff(&old_content, new_content),
+                }),
+                Err(_) => json!({
+                    "description": format!("Create {} ({} lines)", path, new_content.lines().count()),
+                    "diff": line_diff("", new_content),
+                }),
+            }
+        }
+        "edit_file_line" | "insert_at_line" | "delete_file_line" => {
+            let path = arg("path");
+            let line_number = line_arg("line_number");
+            if line_number == 0 {
+                return json!({ "error": "Line number must be >= 1" });
+            }
+
+            let content = match fs::read_to_string(path) {
+                Ok(c) => c,
+                Err(e) => return json!({ "error": format!("Failed to read file: {}", e) }),
+            };
+            let lines: Vec<&str> = content.lines().collect();
+            if name != "insert_at_line" && line_number > lines.len() {
+                return json!({ "error": format!("File only has {} lines", lines.len()) });
+            }
+
+            match name {
+                "edit_file_line" => json!({
+                    "description": format!("Replace line {} in {}", line_number, path),
+                    "diff": format!("- {}\n+ {}", lines[line_number - 1], arg("new_content")),
+                }),
+                "insert_at_line" => json!({
+                    "description": format!("Insert a line at line {} in {}", line_number, path),
+                    "diff": format!("+ {}", arg("content")),
+                }),
+                _ => json!({
+                    "description": format!("Delete line {} from {}", line_number, path),
+                    "diff": format!("- {}", lines[line_number - 1]),
+                }),
+            }
+        }
+        "create_note" => {
+            let Some(config) = obsidian_config.and_then(crate::integrations::obsidian::ObsidianConfig::from_json) else {
+                return json!({ "error": "Obsidian vault not configured in settings." });
+            };
+            match crate::integrations::obsidian::plan_note(&config, &new_note_from_args(args)) {
+                Ok((path, _)) if path.exists() => json!({
+                    "error": format!("A note already exists at {}", path.display()),
+                }),
+                Ok((path, text)) => json!({
+                    "description": format!("Create note {}", path.display()),
+                    "diff": line_diff("", &text),
+                }),
+                Err(e) => json!({ "error": e.to_string() }),
+            }
+        }
+        "append_to_file" | "prepend_to_file" => {
+            let Some(config) = obsidian_config.and_then(crate::integrations::obsidian::ObsidianConfig::from_json) else {
+                return json!({ "error": "Obsidian vault not configured in settings." });
+            };
+            //NOTE: Resolved the same way add_to_file does, so the preview shows the file that gets written
+            let path = match crate::integrations::obsidian::ensure_in_vault(&config, std::path::Path::new(arg("path"))) {
+                Ok(path) => path,
+                Err(e) => return json!({ "error": e.to_string() }),
+            };
+            let existing = match fs::read_to_string(&path) {
+                Ok(content) => content,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+                Err(e) => return json!({ "error": format!("Failed to read {}: {}", path.display(), e) }),
+            };
+            let updated = if name == "append_to_file" {
+                crate::integrations::obsidian::append_text(&existing, arg("content"))
+            } else {
+                crate::integrations::obsidian::prepend_text(&existing, arg("content"))
+            };
+            json!({
+                "description": format!(
+                    "{} {}",
+                    if name == "append_to_file" { "Append to" } else { "Prepend to" },
+                    path.display()
+                ),
+                "diff": line_diff(&existing, &updated),
+            })
+        }
+        "move_file" => {
+            let overwrite = args.get("overwrite").and_then(|v| v.as_bool()).unwrap_or(false);
+            let update_links = args.get("update_links").and_then(|v| v.as_bool()).unwrap_or(true);
+            json!({
+                "description": format!(
+                    "Move {} to {}{}{}",
+                    arg("from"),
+                    arg("to"),
+                    if overwrite { ", replacing it if it exists" } else { "" },
+                    if update_links { ", updating links to it" } else { "" }
+                ),
+            })
+        }
+        "quick_capture" => {
+            let Some(config) = obsidian_config.and_then(crate::integrations::obsidian::ObsidianConfig::from_json) else {
+                return json!({ "error": "Obsidian vault not configured in settings." });
+            };
+            match crate::integrations::obsidian::plan_quick_capture(&config, arg("text"), chrono::Local::now()) {
+                Ok((path, existing, updated, _)) => json!({
+                    "description": format!("Add to today's note {}", path.display()),
+                    "diff": line_diff(&existing, &updated),
+                }),
+                Err(e) => json!({ "error": e.to_string() }),
+            }
+        }
+        "add_reminder" => {
+            let due = args.get("due_at").and_then(|v| v.as_str());
+            json!({
+                "description": match due {
+                    Some(d) => format!("Add reminder \"{}\" due {}", arg("content"), d),
+                    None => format!("Add reminder \"{}\"", arg("content")),
+                },
+            })
+        }
+        "update_reminder" => {
+            let id = args.get("id").and_then(|v| v.as_i64()).unwrap_or_default();
+            let mut changes = Vec::new();
+            if let Some(content) = args.get("content").and_then(|v| v.as_str()) {
+                changes.push(format!("text to \"{}\"", content));
+            }
+            if let Some(due) = args.get("due_at").and_then(|v| v.as_str()) {
+                changes.push(format!("due time to {}", due));
+            }
+            json!({
+                "description": if changes.is_empty() {
+                    format!("Update reminder #{} (no changes)", id)
+                } else {
+                    format!("Change reminder #{} {}", id, changes.join(" and "))
+                },
+            })
+        }
+        "remember" => json!({
+            "description": format!("Remember \"{}\"", arg("fact")),
+        }),
+        "complete_reminder" => json!({
+            "description": format!(
+                "Mark reminder #{} as done",
+                args.get("id").and_then(|v| v.as_i64()).unwrap_or_default()
+            ),
+        }),
+        "promote_reminder_to_event" => {
+            let as_task = args.get("as_task").and_then(|v| v.as_bool()).unwrap_or(false);
+            json!({
+                "description": format!(
+                    "Copy reminder #{} to Google {}",
+                    args.get("reminder_id").and_then(|v| v.as_i64()).unwrap_or_default(),
+                    if as_task { "Tasks" } else { "Calendar" }
+                ),
+            })
+        }
+        "send_email" => json!({
+            "description": format!("Send email to {} with subject \"{}\"", arg("to"), arg("subject")),
+            "api_call": {
+                "method": "POST",
+                "url": "https://gmail.googleapis.com/gmail/v1/users/me/messages/send",
+                "to": arg("to"),
+                "cc": args.get("cc"),
+                "bcc": args.get("bcc"),
+                "subject": arg("subject"),
+                "body": arg("body"),
+                "html": args.get("html").and_then(|v| v.as_bool()).unwrap_or(false),
+            },
+        }),
+        "create_email_draft" => json!({
+            "description": format!("Save a Gmail draft to {} with subject \"{}\"", arg("to"), arg("subject")),
+            "api_call": {
+                "method": "POST",
+                "url": "https://gmail.googleapis.com/gmail/v1/users/me/drafts",
+                "to": arg("to"),
+                "cc": args.get("cc"),
+                "bcc": args.get("bcc"),
+                "subject": arg("subject"),
+                "body": arg("body"),
+                "html": args.get("html").and_then(|v| v.as_bool()).unwrap_or(false),
+            },
+        }),
+        "reply_to_email" => json!({
+            "description": format!("Reply in the thread of email {}", arg("message_id")),
+            "api_call": {
+                "method": "POST",
+                "url": "https://gmail.googleapis.com/gmail/v1/users/me/messages/send",
+                "message_id": arg("message_id"),
+                "body": arg("body"),
+            },
+        }),
+        "mark_email_read" | "archive_email" => {
+            let (description, label) = if name == "mark_email_read" {
+                (format!("Mark email {} as read", arg("message_id")), "UNREAD")
+            } else {
+                (format!("Archive email {}", arg("message_id")), "INBOX")
+            };
+            json!({
+                "description": description,
+                "api_call": {
+                    "method": "POST",
+                    "url": format!("https://gmail.googleapis.com/gmail/v1/users/me/messages/{}/modify", arg("message_id")),
+                    "body": { "removeLabelIds": [label] },
+                },
+            })
+        }
+        "create_calendar_event" => json!({
+            "description": format!("Create calendar event \"{}\" from {} to {}", arg("summary"), arg("start_time"), arg("end_time")),
+            "api_call": {
+                "method": "POST",
+                "url": format!("https://www.googleapis.com/calendar/v3/calendars/{}/events", calendar_arg()),
+                "body": {
+                    "summary": arg("summary"),
+                    "description": args.get("description"),
+                    "location": args.get("location"),
+                    "start": { "dateTime": arg("start_time") },
+                    "end": { "dateTime": arg("end_time") },
+                },
+            },
+        }),
+        "delete_calendar_event" => json!({
+            "description": format!("Delete calendar event {}", arg("event_id")),
+            "api_call": {
+                "method": "DELETE",
+                "url": format!("https://www.googleapis.com/calendar/v3/calendars/{}/events/{}", calendar_arg(), arg("event_id")),
+            },
+        }),
+        "create_google_task" => json!({
+            "description": format!("Create Google task \"{}\"", arg("title")),
+            "api_call": {
+                "method": "POST",
+                "url": "https://tasks.googleapis.com/tasks/v1/lists/{default_list}/tasks",
+                "body": {
+                    "title": arg("title"),
+                    "notes": args.get("notes"),
+                    "due": args.get("due"),
+                },
+            },
+        }),
+        "complete_google_task" => json!({
+            "description": format!("Mark Google task {} as completed", arg("task_id")),
+            "api_call": {
+                "method": "PATCH",
+                "url": format!("https://tasks.googleapis.com/tasks/v1/lists/{{default_list}}/tasks/{}", arg("task_id")),
+                "body": { "status": "completed" },
+            },
+        }),
+        "delete_google_task" => json!({
+            "description": format!("Delete Google task {}", arg("task_id")),
+            "api_call": {
+                "method": "DELETE",
+                "url": format!("https://tasks.googleapis.com/tasks/v1/lists/{{default_list}}/tasks/{}", arg("task_id")),
+            },
+        }),
+        _ => json!({ "error": format!("No preview available for tool: {}", name) }),
+    }
+}
+
+//INFO: When "true", send_email saves a Gmail draft instead of sending
+const EMAIL_DRAFT_ONLY_SETTING: &str = "email_draft_only";
+
+//INFO: read_file returns at most this many bytes unless the read_file_max_bytes setting overrides it
+const DEFAULT_READ_FILE_MAX_BYTES: u64 = 100_000;
+const READ_FILE_MAX_BYTES_SETTING: &str = "read_file_max_bytes";
+
+//INFO: Largest range read_file_lines will return in one call
+const MAX_READ_LINES: usize = 1000;
+
+//INFO: Reads at most `max_bytes` of a text file, returning the text and how many bytes were left unread
+//NOTE: The cut is moved back to a char boundary so a multi-byte character is never split
+fn read_file_capped(path: &str, max_bytes: u64) -> std::io::Result<(String, u64)> {
+    use std::io::Read;
+
+    let file = fs::File::open(path)?;
+    let total = file.metadata()?.len();
+
+    let mut buffer = Vec::new();
+    file.take(max_bytes).read_to_end(&mut buffer)?;
+
+    let text = match String::from_utf8(buffer) {
+        Ok(text) => text,
+        Err(e) => {
+            let valid_up_to = e.utf8_error().valid_up_to();
+            //INFO: Invalid bytes before the cut mean it isn't text at all
+            if e.utf8_error().error_len().is_some() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "stream did not contain valid UTF-8",
+                ));
+            }
+            let mut bytes = e.into_bytes();
+            bytes.truncate(valid_up_to);
+            String::from_utf8(bytes).unwrap_or_default()
+        }
+    };
+
+    let remaining = total.saturating_sub(text.len() as u64);
+    Ok((text, remaining))
+}
+
+//INFO: Result cap for search_filesystem
+const MAX_FILESYSTEM_MATCHES: usize = 20;
+
+//INFO: get_file_metadata only counts lines in files up to this size
+const METADATA_LINE_COUNT_LIMIT: u64 = 5_000_000;
+
+//INFO: Matches a lowercase file name against a substring, or a glob if the query has * or ?
+fn filename_matches(name: &str, query: &str) -> bool {
+    if !query.contains(['*', '?']) {
+        return name.contains(query);
+    }
+
+    //INFO: Classic two-pointer wildcard match with backtracking to the last '*'
+    let name: Vec<char> = name.chars().collect();
+    let pattern: Vec<char> = query.chars().collect();
+    let (mut n, mut p) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            n += 1;
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+//INFO: A text file split into lines, remembering how to put it back together
+//NOTE: The line tools used to rejoin with "\n", which dropped trailing newlines and turned CRLF files into LF
+struct LineFile {
+    lines: Vec<String>,
+    eol: &'static str,
+    trailing_newline: bool,
+}
+
+impl LineFile {
+    fn parse(content: &str) -> Self {
+        let crlf = content.matches("\r\n").count();
+        let lf = content.matches('\n').count() - crlf;
+        Self {
+            lines: content.lines().map(|s| s.to_string()).collect(),
+            eol: if crlf > lf { "\r\n" } else { "\n" },
+            trailing_newline: content.ends_with('\n'),
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut output = self.lines.join(self.eol);
+        if self.trailing_newline && !self.lines.is_empty() {
+            output.push_str(self.eol);
+        }
+        output
+    }
+}
+
+//INFO: Whether the user has asked for outgoing mail to be saved as drafts rather than sent
+fn email_draft_only(database: &crate::database::Database) -> bool {
+    let Ok(connection) = database.connection.lock() else {
+        return false;
+    };
+    crate::database::queries::get_setting(&connection, EMAIL_DRAFT_ONLY_SETTING)
+        .ok()
+        .flatten()
+        .is_some_and(|v| v == "true")
+}
+
+//INFO: Reads send_email / create_email_draft arguments into an OutgoingEmail
+fn outgoing_email_from_args(args: &serde_json::Value) -> crate::integrations::google_gmail::OutgoingEmail {
+    let arg = |key: &str| args.get(key).and_then(|v| v.as_str()).map(String::from);
+    crate::integrations::google_gmail::OutgoingEmail {
+        to: arg("to").unwrap_or_default(),
+        cc: arg("cc"),
+        bcc: arg("bcc"),
+        subject: arg("subject").unwrap_or_default(),
+        body: arg("body").unwrap_or_default(),
+        html: args.get("html").and_then(|v| v.as_bool()).unwrap_or(false),
+        ..Default::default()
+    }
+}
+
+//INFO: Reads create_note arguments into a NewNote
+fn new_note_from_args(args: &serde_json::Value) -> crate::integrations::obsidian::NewNote<'_> {
+    let arg = |key: &str| args.get(key).and_then(|v| v.as_str());
+    crate::integrations::obsidian::NewNote {
+        title: arg("title").unwrap_or(""),
+        folder: arg("folder"),
+        template: arg("template"),
+        content: arg("content"),
+        tags: args
+            .get("tags")
+            .and_then(|v| v.as_array())
+            .map(|tags| tags.iter().filter_map(|t| t.as_str()).map(String::from).collect())
+            .unwrap_or_default(),
+        frontmatter: args.get("frontmatter").and_then(|v| v.as_bool()).unwrap_or(false),
+    }
+}
+
+//INFO: Minimal line diff - trims the common head and tail and shows the changed middle
+fn line_diff(old: &str, new: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+
+    let prefix = old_lines
+        .iter()
+        .zip(new_lines.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old_lines[prefix..]
+        .iter()
+        .rev()
+        .zip(new_lines[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let removed = &old_lines[prefix..old_lines.len() - suffix];
+    let added = &new_lines[prefix..new_lines.len() - suffix];
+
+    let mut diff = Vec::new();
+    if prefix > 0 || suffix > 0 {
+        diff.push(format!("@@ line {} @@", prefix + 1));
+    }
+    diff.extend(removed.iter().map(|l| format!("- {}", l)));
+    diff.extend(added.iter().map(|l| format!("+ {}", l)));
+    diff.join("\n")
+}
+
+const AUDIT_MAX_SUMMARY_CHARS: usize = 300;
+
+//INFO: The shape of a tool's args or result: keys, string lengths and item counts, never the content
+//NOTE: Tools pass around decrypted clipboard entries, email bodies and note text, which must not end up
+//      in plaintext in tool_audit. Numbers and booleans are only limits and flags, so they're kept.
+fn describe_audit_value(value: &serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => serde_json::Value::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), describe_audit_value(value)))
+                .collect(),
+        ),
+        serde_json::Value::Array(items) => json!(format!("[{} items]", items.len())),
+        serde_json::Value::String(s) => json!(format!("[{} chars]", s.chars().count())),
+        other => other.clone(),
+    }
+}
+
+//INFO: Short outcome of a tool call and whether it succeeded
+//NOTE: Errors are our own messages and are kept; successful results are reduced to their shape
+fn summarize_audit_result(result: &serde_json::Value) -> (String, bool) {
+    if let Some(error) = result.get("error") {
+        let error = error.as_str().map(String::from).unwrap_or_else(|| error.to_string());
+        return (error.chars().take(AUDIT_MAX_SUMMARY_CHARS).collect(), false);
+    }
+
+    let summary = describe_audit_value(result).to_string();
+    (summary.chars().take(AUDIT_MAX_SUMMARY_CHARS).collect(), true)
+}
+
+//INFO: Tool result for when no database connection can be opened
+fn database_unavailable(e: anyhow::Error) -> serde_json::Value {
+    json!({ "error": format!("Database is unavailable: {:#}", e) })
+}
+
+//INFO: Writes a tool call to the tool_audit table
+//NOTE: Auditing must never fail the tool itself, so errors are only logged
+fn audit_tool_call(
+    connection: &rusqlite::Connection,
+    name: &str,
+    args: &serde_json::Value,
+    result: &serde_json::Value,
+) {
+    let (summary, success) = summarize_audit_result(result);
+    tracing::info!(success, "🛠️ Tools: Ran '{}'", name);
+    tracing::debug!("🛠️ Tools: {}", summary);
+    let args_json = describe_audit_value(args).to_string();
+    if let Err(e) =
+        crate::database::queries::record_tool_audit(connection, name, &args_json, &summary, success)
+    {
+        tracing::warn!("⚠️ Tools: Failed to audit '{}' call: {}", name, e);
+    }
+}
+
 //INFO: Execute a synchronous tool call and return the result as JSON
+#[tracing::instrument(name = "tool", skip_all, fields(tool = name))]
 pub fn execute_tool_sync(
     name: &str,
     args: &serde_json::Value,
     obsidian_config: Option<&serde_json::Value>,
     db_connection: &rusqlite::Connection,
+) -> serde_json::Value {
+    let result = run_tool_sync(name, args, obsidian_config, db_connection);
+    audit_tool_call(db_connection, name, args, &result);
+    result
+}
+
+fn run_tool_sync(
+    name: &str,
+    args: &serde_json::Value,
+    obsidian_config: Option<&serde_json::Value>,
+    db_connection: &rusqlite::Connection,
 ) -> serde_json::Value {
     match name {
         "read_file" => {
             let path = args.get("path").and_then(|v| v.as_str()).unwrap_or("");
-            // SAFETY: Don't read files larger than 500KB to avoid API context explosion or binary bloat
-            if let Ok(meta) = fs::metadata(path) {
-                if meta.len() > 500_000 {
-                    return json!({ "error": "File is too large to read (limit: 500KB). Use get_file_metadata first or read selective lines." });
-                }
-            }
-            match fs::read_to_string(path) {
-                Ok(content) => json!({ "content": content }),
+            // SAFETY: Only the first max_bytes are read, so huge logs can't blow the context or memory
+            let max_bytes =
+                crate::database::queries::get_setting(db_connection, READ_FILE_MAX_BYTES_SETTING)
+                    .ok()
+                    .flatten()
+                    .and_then(|v| v.parse::<u64>().ok())
+                    .unwrap_or(DEFAULT_READ_FILE_MAX_BYTES);
+
+            match read_file_capped(path, max_bytes) {
+                Ok((content, 0)) => json!({ "content": content }),
+                Ok((content, remaining)) => json!({
+                    "content": format!("{}\n... (truncated, {} more bytes)", content, remaining),
+                    "truncated": true,
+                    "hint": "This file is large. Use grep_file to find the relevant part, then read_file_lines to read it.",
+                }),
                 Err(e) => json!({ "error": format!("Failed to read file: {}", e) }),
             }
         }
@@ -430,6 +1437,100 @@ pub fn execute_tool_sync(
                 Err(e) => json!({ "error": format!("Failed to write file: {}", e) }),
             }
         }
+        "create_note" => {
+            let Some(config) = obsidian_config.and_then(crate::integrations::obsidian::ObsidianConfig::from_json) else {
+                return json!({ "error": "Obsidian vault not configured in settings." });
+            };
+            match crate::integrations::obsidian::create_note(&config, &new_note_from_args(args)) {
+                Ok(path) => json!({ "status": "success", "path": path.to_string_lossy() }),
+                Err(e) => json!({ "error": format!("Failed to create note: {}", e) }),
+            }
+        }
+        "append_to_file" | "prepend_to_file" => {
+            let Some(config) = obsidian_config.and_then(crate::integrations::obsidian::ObsidianConfig::from_json) else {
+                return json!({ "error": "Obsidian vault not configured in settings." });
+            };
+            let path = args.get("path").and_then(|v| v.as_str()).unwrap_or("");
+            let content = args.get("content").and_then(|v| v.as_str()).unwrap_or("");
+            let prepend = name == "prepend_to_file";
+
+            match crate::integrations::obsidian::add_to_file(&config, std::path::Path::new(path), content, prepend) {
+                Ok(path) => json!({ "status": "success", "path": path.to_string_lossy() }),
+                Err(e) => json!({ "error": format!("Failed to update file: {}", e) }),
+            }
+        }
+        "move_file" => {
+            let Some(config) = obsidian_config.and_then(crate::integrations::obsidian::ObsidianConfig::from_json) else {
+                return json!({ "error": "Obsidian vault not configured in settings." });
+            };
+            let from = args.get("from").and_then(|v| v.as_str()).unwrap_or("");
+            let to = args.get("to").and_then(|v| v.as_str()).unwrap_or("");
+            let overwrite = args.get("overwrite").and_then(|v| v.as_bool()).unwrap_or(false);
+            let update_links = args.get("update_links").and_then(|v| v.as_bool()).unwrap_or(true);
+
+            match crate::integrations::obsidian::move_file(&config, from, to, overwrite, update_links) {
+                Ok(moved) => {
+                    let mut result = json!({
+                        "status": "success",
+                        "path": moved.path.to_string_lossy(),
+                        "links_updated_in": moved.links_updated,
+                    });
+                    //NOTE: The file did move; only some links still point at the old path
+                    if !moved.link_errors.is_empty() {
+                        result["links_not_updated"] = json!(moved.link_errors);
+                    }
+                    result
+                }
+                Err(e) => json!({ "error": format!("Failed to move file: {}", e) }),
+            }
+        }
+        "quick_capture" => {
+            let Some(config) = obsidian_config.and_then(crate::integrations::obsidian::ObsidianConfig::from_json) else {
+                return json!({ "error": "Obsidian vault not configured in settings." });
+            };
+            let text = args.get("text").and_then(|v| v.as_str()).unwrap_or("");
+
+            match crate::integrations::obsidian::quick_capture(&config, text, chrono::Local::now()) {
+                Ok((path, line)) => json!({ "status": "success", "path": path.to_string_lossy(), "line": line }),
+                Err(e) => json!({ "error": format!("Failed to capture: {}", e) }),
+            }
+        }
+        "recent_files" => {
+            let Some(config) = obsidian_config.and_then(crate::integrations::obsidian::ObsidianConfig::from_json) else {
+                return json!({ "error": "Obsidian vault not configured in settings." });
+            };
+            let root = args.get("root").and_then(|v| v.as_str()).unwrap_or("");
+            let limit = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(10) as usize;
+
+            match crate::integrations::obsidian::recent_notes(&config, root, limit) {
+                Ok((files, truncated)) => json!({ "files": files, "truncated": truncated }),
+                Err(e) => json!({ "error": format!("Failed to list recent files: {}", e) }),
+            }
+        }
+        "calculate" => {
+            let expression = args.get("expression").and_then(|v| v.as_str()).unwrap_or("");
+            match crate::calc::evaluate(expression) {
+                Ok(result) => json!({ "expression": expression, "result": result }),
+                Err(e) => json!({ "error": format!("Could not evaluate '{}': {}", expression, e) }),
+            }
+        }
+        "compute_datetime" => {
+            let base = args.get("base").and_then(|v| v.as_str()).unwrap_or("now");
+            let operation = args.get("operation").and_then(|v| v.as_str()).unwrap_or("");
+
+            //NOTE: There's no tz database here, so "now" uses the machine's offset; the saved IANA zone is echoed for context
+            let result = crate::calc::parse_base(base)
+                .and_then(|base| crate::calc::compute_datetime(base, operation));
+            match result {
+                Ok(time) => json!({
+                    "result": time.to_rfc3339(),
+                    "date": time.format("%Y-%m-%d").to_string(),
+                    "weekday": time.format("%A").to_string(),
+                    "timezone": crate::database::queries::get_user_timezone(db_connection),
+                }),
+                Err(e) => json!({ "error": format!("Could not compute '{}': {}", operation, e) }),
+            }
+        }
         "list_files" => {
             let path = args.get("path").and_then(|v| v.as_str()).unwrap_or(".");
             match fs::read_dir(path) {
@@ -481,10 +1582,19 @@ pub fn execute_tool_sync(
         }
         "get_obsidian_vault_info" => {
             if let Some(config) = obsidian_config {
+                //INFO: Resolve today's note here so the model doesn't have to convert Moment formats itself
+                let todays_note = crate::integrations::obsidian::ObsidianConfig::from_json(config)
+                    .map(|c| {
+                        crate::integrations::obsidian::resolve_daily_note_path(
+                            &c,
+                            chrono::Local::now().date_naive(),
+                        )
+                    });
                 json!({
                     "vault_path": config.get("vault_path"),
                     "daily_notes_folder": config.get("daily_notes_path").and_then(|v| v.as_str()).unwrap_or(""),
                     "daily_notes_format": config.get("daily_notes_format").and_then(|v| v.as_str()).unwrap_or("YYYY-MM-DD"),
+                    "todays_daily_note_path": todays_note.map(|p| p.to_string_lossy().into_owned()),
                     "status": "configured"
                 })
             } else {
@@ -494,6 +1604,9 @@ pub fn execute_tool_sync(
         "add_reminder" => {
             let content = args.get("content").and_then(|v| v.as_str()).unwrap_or("");
             let due_at = args.get("due_at").and_then(|v| v.as_str());
+            if let Err(e) = due_at.map_or(Ok(()), crate::agent::reminders::check_due_at) {
+                return json!({ "error": e });
+            }
             let created_at = chrono::Utc::now().to_rfc3339();
 
             match db_connection.execute(
@@ -504,9 +1617,73 @@ pub fn execute_tool_sync(
                 Err(e) => json!({ "error": format!("Failed to add reminder: {}", e) }),
             }
         }
+        "get_reminder" => {
+            let Some(id) = args.get("id").and_then(|v| v.as_i64()) else {
+                return json!({ "error": "id is required" });
+            };
+            match crate::database::queries::get_reminder(db_connection, id) {
+                Ok(Some(reminder)) => json!({ "reminder": reminder }),
+                Ok(None) => json!({ "error": format!("Reminder {} not found", id) }),
+                Err(e) => json!({ "error": format!("Failed to get reminder: {}", e) }),
+            }
+        }
+        "update_reminder" => {
+            let Some(id) = args.get("id").and_then(|v| v.as_i64()) else {
+                return json!({ "error": "id is required" });
+            };
+            let content = args.get("content").and_then(|v| v.as_str());
+            let due_at = args.get("due_at").and_then(|v| v.as_str());
+            if content.is_none() && due_at.is_none() {
+                return json!({ "error": "Nothing to update: give content and/or due_at" });
+            }
+            if let Err(e) = due_at.map_or(Ok(()), crate::agent::reminders::check_due_at) {
+                return json!({ "error": e });
+            }
+
+            match crate::database::queries::update_reminder(db_connection, id, content, due_at) {
+                //NOTE: The copy in Google isn't touched, so say so rather than let the two drift silently
+                Ok(Some(reminder)) => match reminder.linked_to.clone() {
+                    Some(linked_to) => json!({
+                        "status": "success",
+                        "reminder": reminder,
+                        "note": format!("This reminder was promoted to {}; that copy was not changed.", linked_to),
+                    }),
+                    None => json!({ "status": "success", "reminder": reminder }),
+                },
+                Ok(None) => json!({ "error": format!("Reminder {} not found", id) }),
+                Err(e) => json!({ "error": format!("Failed to update reminder: {}", e) }),
+            }
+        }
+        "complete_reminder" => {
+            let Some(id) = args.get("id").and_then(|v| v.as_i64()) else {
+                return json!({ "error": "id is required" });
+            };
+            match crate::database::queries::complete_reminder(db_connection, id) {
+                Ok(true) => json!({ "status": "success", "message": format!("Reminder {} marked as done.", id) }),
+                Ok(false) => json!({ "error": format!("Reminder {} not found", id) }),
+                Err(e) => json!({ "error": format!("Failed to complete reminder: {}", e) }),
+            }
+        }
+        "recall" => {
+            let query = args.get("query").and_then(|v| v.as_str()).unwrap_or("");
+            let limit = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(10) as usize;
+            match crate::memory::facts::recall_facts(db_connection, query, limit) {
+                Ok(facts) => {
+                    for fact in &facts {
+                        let _ = crate::memory::core::update_memory_access(db_connection, &fact.id);
+                    }
+                    let facts: Vec<_> = facts
+                        .iter()
+                        .map(|f| json!({ "fact": f.content, "saved_at": f.created_at.to_rfc3339() }))
+                        .collect();
+                    json!({ "facts": facts })
+                }
+                Err(e) => json!({ "error": format!("Failed to recall facts: {}", e) }),
+            }
+        }
         "list_reminders" => {
             let mut stmt = match db_connection
-                .prepare("SELECT id, content, due_at, completed FROM reminders WHERE completed = 0")
+                .prepare("SELECT id, content, due_at, completed, linked_to FROM reminders WHERE completed = 0")
             {
                 Ok(s) => s,
                 Err(e) => return json!({ "error": e.to_string() }),
@@ -518,7 +1695,8 @@ pub fn execute_tool_sync(
                         "id": row.get::<_, i32>(0)?,
                         "content": row.get::<_, String>(1)?,
                         "due_at": row.get::<_, Option<String>>(2)?,
-                        "completed": row.get::<_, i32>(3)? == 1
+                        "completed": row.get::<_, i32>(3)? == 1,
+                        "linked_to": row.get::<_, Option<String>>(4)?
                     }))
                 })
                 .unwrap()
@@ -564,12 +1742,13 @@ pub fn execute_tool_sync(
 
             match fs::read_to_string(path) {
                 Ok(content) => {
-                    let mut lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
+                    let mut file = LineFile::parse(&content);
+                    let lines = &mut file.lines;
                     if line_number > lines.len() {
                         return json!({ "error": format!("File only has {} lines", lines.len()) });
                     }
                     lines[line_number - 1] = new_content.to_string();
-                    match fs::write(path, lines.join("\n")) {
+                    match fs::write(path, file.render()) {
                         Ok(_) => {
                             json!({ "status": "success", "message": format!("Line {} updated", line_number) })
                         }
@@ -593,10 +1772,11 @@ pub fn execute_tool_sync(
 
             match fs::read_to_string(path) {
                 Ok(content) => {
-                    let mut lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
+                    let mut file = LineFile::parse(&content);
+                    let lines = &mut file.lines;
                     let idx = (line_number - 1).min(lines.len());
                     lines.insert(idx, content_to_insert.to_string());
-                    match fs::write(path, lines.join("\n")) {
+                    match fs::write(path, file.render()) {
                         Ok(_) => {
                             json!({ "status": "success", "message": format!("Inserted at line {}", line_number) })
                         }
@@ -619,12 +1799,13 @@ pub fn execute_tool_sync(
 
             match fs::read_to_string(path) {
                 Ok(content) => {
-                    let mut lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
+                    let mut file = LineFile::parse(&content);
+                    let lines = &mut file.lines;
                     if line_number > lines.len() {
                         return json!({ "error": format!("File only has {} lines", lines.len()) });
                     }
                     lines.remove(line_number - 1);
-                    match fs::write(path, lines.join("\n")) {
+                    match fs::write(path, file.render()) {
                         Ok(_) => {
                             json!({ "status": "success", "message": format!("Line {} deleted", line_number) })
                         }
@@ -642,20 +1823,34 @@ pub fn execute_tool_sync(
             if start == 0 || end < start {
                 return json!({ "error": "Invalid line range" });
             }
+            if end - start >= MAX_READ_LINES {
+                return json!({ "error": format!("Range too large: read at most {} lines per call", MAX_READ_LINES) });
+            }
 
-            match fs::read_to_string(path) {
-                Ok(content) => {
-                    let lines: Vec<String> = content
-                        .lines()
-                        .enumerate()
-                        .filter(|(i, _)| i + 1 >= start && *i < end)
-                        .map(|(_, s)| s.to_string())
-                        .collect();
-                    json!({ "lines": lines, "total_lines": content.lines().count() })
+            //INFO: Stream line by line so only the requested range is held in memory
+            use std::io::BufRead;
+            match fs::File::open(path) {
+                Ok(file) => {
+                    let mut lines = Vec::new();
+                    let mut total_lines = 0;
+                    for line in std::io::BufReader::new(file).lines() {
+                        let line = match line {
+                            Ok(line) => line,
+                            Err(e) => return json!({ "error": format!("Failed to read file: {}", e) }),
+                        };
+                        total_lines += 1;
+                        if total_lines >= start && total_lines <= end {
+                            lines.push(line.trim_end_matches('\r').to_string());
+                        }
+                    }
+                    json!({ "lines": lines, "total_lines": total_lines })
                 }
                 Err(e) => json!({ "error": format!("Failed to read file: {}", e) }),
             }
         }
+        "search_web" if crate::offline::is_offline(db_connection) => {
+            crate::offline::offline_tool_result("Web search")
+        }
         "search_web" => {
             let query = args.get("query").and_then(|v| v.as_str()).unwrap_or("");
             // Simulate a search result for now
@@ -669,15 +1864,29 @@ pub fn execute_tool_sync(
         "search_clipboard" => {
             let query = args.get("query").and_then(|v| v.as_str()).unwrap_or("");
             let limit = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(5) as u32;
+            let content_type = args.get("type").and_then(|v| v.as_str());
 
-            match crate::database::queries::search_clipboard_history(db_connection, query, limit) {
+            match crate::database::queries::search_clipboard_history(
+                db_connection,
+                query,
+                limit,
+                content_type,
+                false,
+            ) {
                 Ok(items) => json!({ "items": items }),
                 Err(e) => json!({ "error": format!("Failed to search clipboard: {}", e) }),
             }
         }
         "get_file_metadata" => {
+            let Some(config) = obsidian_config.and_then(crate::integrations::obsidian::ObsidianConfig::from_json) else {
+                return json!({ "error": "Obsidian vault not configured in settings." });
+            };
             let path = args.get("path").and_then(|v| v.as_str()).unwrap_or("");
-            match fs::metadata(path) {
+            let path = match crate::integrations::obsidian::existing_in_vault(&config, path) {
+                Ok(path) => path,
+                Err(e) => return json!({ "error": format!("Failed to get metadata: {}", e) }),
+            };
+            match fs::metadata(&path) {
                 Ok(meta) => {
                     use std::time::SystemTime;
                     let format_time = |t: Result<SystemTime, _>| {
@@ -685,53 +1894,86 @@ pub fn execute_tool_sync(
                             .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
                             .map(|d| d.as_secs())
                     };
+                    //INFO: Line counts only for reasonably sized text files; binaries fail to decode and get null
+                    let line_count = if meta.is_file() && meta.len() <= METADATA_LINE_COUNT_LIMIT {
+                        fs::read_to_string(&path).ok().map(|c| c.lines().count())
+                    } else {
+                        None
+                    };
                     json!({
                         "size_bytes": meta.len(),
                         "is_dir": meta.is_dir(),
                         "modified": format_time(meta.modified()),
                         "created": format_time(meta.created()),
+                        "line_count": line_count,
                     })
                 }
                 Err(e) => json!({ "error": format!("Failed to get metadata: {}", e) }),
             }
         }
         "search_filesystem" => {
-            let path = args.get("path").and_then(|v| v.as_str()).unwrap_or("");
+            let Some(config) = obsidian_config.and_then(crate::integrations::obsidian::ObsidianConfig::from_json) else {
+                return json!({ "error": "Obsidian vault not configured in settings." });
+            };
             let query = args
                 .get("query")
                 .and_then(|v| v.as_str())
                 .unwrap_or("")
                 .to_lowercase();
-
-            if path.is_empty() || query.is_empty() {
-                return json!({ "error": "Path and query required." });
+            if query.is_empty() {
+                return json!({ "error": "Query required." });
             }
 
+            //INFO: An empty path resolves to the vault root
+            let path = args.get("path").and_then(|v| v.as_str()).unwrap_or("");
+            let path = match crate::integrations::obsidian::existing_in_vault(&config, path) {
+                Ok(path) => path,
+                Err(e) => return json!({ "error": format!("Failed to search: {}", e) }),
+            };
+
             let mut results = Vec::new();
+            let mut truncated = false;
             for entry in WalkDir::new(path)
                 .max_depth(5) // Don't go too deep to avoid performance hits
                 .into_iter()
+                .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
                 .filter_map(|e| e.ok())
             {
                 let name = entry.file_name().to_string_lossy().to_lowercase();
-                if name.contains(&query) {
+                if filename_matches(&name, &query) {
+                    if results.len() >= MAX_FILESYSTEM_MATCHES {
+                        truncated = true;
+                        break;
+                    }
                     results.push(entry.path().to_string_lossy().into_owned());
                 }
-                if results.len() >= 20 {
-                    break;
-                }
             }
-            json!({ "matches": results })
+            json!({ "matches": results, "truncated": truncated })
         }
         _ => json!({ "error": format!("Unknown synchronous tool: {}", name) }),
     }
 }
 
 //INFO: Execute an asynchronous tool call and return the result as JSON
+#[tracing::instrument(name = "tool", skip_all, fields(tool = name))]
 pub async fn execute_tool_async(
     name: &str,
     args: &serde_json::Value,
     database: &crate::database::Database,
+    app: &tauri::AppHandle,
+) -> serde_json::Value {
+    let result = run_tool_async(name, args, database, app).await;
+    if let Ok(connection) = database.connection.lock() {
+        audit_tool_call(&connection, name, args, &result);
+    }
+    result
+}
+
+async fn run_tool_async(
+    name: &str,
+    args: &serde_json::Value,
+    database: &crate::database::Database,
+    app: &tauri::AppHandle,
 ) -> serde_json::Value {
     match name {
         "get_weather" => {
@@ -739,21 +1981,54 @@ pub async fn execute_tool_async(
                 .get("location")
                 .and_then(|v| v.as_str())
                 .unwrap_or("Lagos");
-            fetch_weather(location).await
+            fetch_weather(database, location).await
+        }
+        "describe_image" => {
+            let path = args.get("path").and_then(|v| v.as_str()).unwrap_or("");
+            match crate::commands::vision::describe_vault_image(database, path).await {
+                Ok((path, description)) => json!({ "path": path.display().to_string(), "description": description }),
+                Err(e) => json!({ "error": format!("Failed to describe image: {}", e) }),
+            }
+        }
+        "fetch_url" => {
+            let url = args.get("url").and_then(|v| v.as_str()).unwrap_or("");
+            if database.connection.lock().is_ok_and(|connection| crate::offline::is_offline(&connection)) {
+                return crate::offline::offline_tool_result("Fetching web pages");
+            }
+            match crate::integrations::web::fetch_url(url).await {
+                Ok(page) => json!(page),
+                Err(e) => json!({ "error": format!("Failed to fetch page: {:#}", e) }),
+            }
         }
         "get_google_calendar_events" => {
             let time_min = args.get("time_min").and_then(|v| v.as_str()).unwrap_or("");
             let time_max = args.get("time_max").and_then(|v| v.as_str()).unwrap_or("");
 
-            match crate::integrations::google_calendar::fetch_google_calendar_events(
-                database, time_min, time_max,
+            let calendar_id = args.get("calendar_id").and_then(|v| v.as_str());
+            let limit = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(50) as usize;
+            let offset = args.get("offset").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+
+            match crate::integrations::google_calendar::fetch_google_calendar_events_page(
+                database,
+                time_min,
+                time_max,
+                calendar_id,
+                limit,
+                offset,
             )
             .await
             {
-                Ok(events) => json!({ "events": events }),
+                Ok(page) => {
+                    let next_offset = page.has_more.then(|| page.offset + page.events.len());
+                    json!({ "events": page.events, "has_more": page.has_more, "next_offset": next_offset })
+                }
                 Err(e) => json!({ "error": format!("Failed to fetch calendar: {}", e) }),
             }
         }
+        "list_calendars" => match crate::integrations::google_calendar::list_calendars(database).await {
+            Ok(calendars) => json!({ "calendars": calendars }),
+            Err(e) => json!({ "error": format!("Failed to list calendars: {}", e) }),
+        },
         "get_unread_emails" => {
             let max_results = args
                 .get("max_results")
@@ -772,16 +2047,90 @@ pub async fn execute_tool_async(
                 Err(e) => json!({ "error": format!("Failed to fetch emails: {}", e) }),
             }
         }
+        "get_email_details" => {
+            let message_id = args.get("message_id").and_then(|v| v.as_str()).unwrap_or("");
+            if message_id.is_empty() {
+                return json!({ "error": "message_id is required." });
+            }
+
+            match crate::integrations::google_gmail::get_email_details(database, message_id).await {
+                Ok(details) => json!({ "email": details }),
+                Err(e) => json!({ "error": format!("Failed to fetch email: {}", e) }),
+            }
+        }
+        "mark_email_read" | "archive_email" => {
+            let message_id = args.get("message_id").and_then(|v| v.as_str()).unwrap_or("");
+            if message_id.is_empty() {
+                return json!({ "error": "message_id is required." });
+            }
+
+            let (label, done) = if name == "mark_email_read" {
+                ("UNREAD", "Email marked as read.")
+            } else {
+                ("INBOX", "Email archived.")
+            };
+
+            match crate::integrations::google_gmail::modify_email_labels(database, message_id, &[], &[label]).await {
+                Ok(_) => json!({ "status": "success", "message": done, "message_id": message_id }),
+                Err(e) => json!({ "error": format!("Failed to update email: {}", e) }),
+            }
+        }
         "send_email" => {
-            let to = args.get("to").and_then(|v| v.as_str()).unwrap_or("");
-            let subject = args.get("subject").and_then(|v| v.as_str()).unwrap_or("");
-            let body = args.get("body").and_then(|v| v.as_str()).unwrap_or("");
+            let email = outgoing_email_from_args(args);
+
+            //INFO: With draft-only mode on, "send" still just saves a draft for the user to review
+            if email_draft_only(database) {
+                return match crate::integrations::google_gmail::create_email_draft(database, &email).await {
+                    Ok(draft) => json!({
+                        "status": "success",
+                        "message": "Draft-only mode is on, so the email was saved as a draft instead of being sent.",
+                        "draft": draft,
+                    }),
+                    Err(e) => json!({ "error": format!("Failed to create draft: {}", e) }),
+                };
+            }
 
-            match crate::integrations::google_gmail::send_email(database, to, subject, body).await {
+            match crate::integrations::google_gmail::send_email(database, &email).await {
                 Ok(_) => json!({ "status": "success", "message": "Email sent." }),
                 Err(e) => json!({ "error": format!("Failed up to send email: {}", e) }),
             }
         }
+        "reply_to_email" => {
+            let message_id = args.get("message_id").and_then(|v| v.as_str()).unwrap_or("");
+            let body = args.get("body").and_then(|v| v.as_str()).unwrap_or("");
+            if message_id.is_empty() {
+                return json!({ "error": "message_id is required." });
+            }
+
+            let reply = match crate::integrations::google_gmail::prepare_reply(database, message_id, body).await {
+                Ok(reply) => reply,
+                Err(e) => return json!({ "error": format!("Failed to prepare reply: {}", e) }),
+            };
+
+            if email_draft_only(database) {
+                return match crate::integrations::google_gmail::create_email_draft(database, &reply).await {
+                    Ok(draft) => json!({
+                        "status": "success",
+                        "message": "Draft-only mode is on, so the reply was saved as a draft in the thread instead of being sent.",
+                        "draft": draft,
+                    }),
+                    Err(e) => json!({ "error": format!("Failed to create draft: {}", e) }),
+                };
+            }
+
+            match crate::integrations::google_gmail::send_email(database, &reply).await {
+                Ok(_) => json!({ "status": "success", "message": format!("Reply sent to {}.", reply.to) }),
+                Err(e) => json!({ "error": format!("Failed to send reply: {}", e) }),
+            }
+        }
+        "create_email_draft" => {
+            let email = outgoing_email_from_args(args);
+
+            match crate::integrations::google_gmail::create_email_draft(database, &email).await {
+                Ok(draft) => json!({ "status": "success", "draft": draft }),
+                Err(e) => json!({ "error": format!("Failed to create draft: {}", e) }),
+            }
+        }
         "create_calendar_event" => {
             let summary = args.get("summary").and_then(|v| v.as_str()).unwrap_or("");
             let description = args.get("description").and_then(|v| v.as_str());
@@ -791,6 +2140,7 @@ pub async fn execute_tool_async(
                 .unwrap_or("");
             let end_time = args.get("end_time").and_then(|v| v.as_str()).unwrap_or("");
             let location = args.get("location").and_then(|v| v.as_str());
+            let calendar_id = args.get("calendar_id").and_then(|v| v.as_str());
 
             match crate::integrations::google_calendar::create_calendar_event(
                 database,
@@ -799,6 +2149,7 @@ pub async fn execute_tool_async(
                 start_time,
                 end_time,
                 location,
+                calendar_id,
             )
             .await
             {
@@ -808,11 +2159,24 @@ pub async fn execute_tool_async(
         }
         "delete_calendar_event" => {
             let event_id = args.get("event_id").and_then(|v| v.as_str()).unwrap_or("");
-            match crate::integrations::google_calendar::delete_calendar_event(database, event_id).await {
+            let calendar_id = args.get("calendar_id").and_then(|v| v.as_str());
+            match crate::integrations::google_calendar::delete_calendar_event(database, event_id, calendar_id).await {
                 Ok(_) => json!({ "status": "success", "message": "Event deleted successfully." }),
                 Err(e) => json!({ "error": format!("Failed to delete event: {}", e) }),
             }
         }
+        "promote_reminder_to_event" => {
+            let Some(reminder_id) = args.get("reminder_id").and_then(|v| v.as_i64()) else {
+                return json!({ "error": "reminder_id is required" });
+            };
+            let as_task = args.get("as_task").and_then(|v| v.as_bool()).unwrap_or(false);
+            let duration_minutes = args.get("duration_minutes").and_then(|v| v.as_i64());
+
+            match crate::commands::reminders::promote_reminder(database, reminder_id, as_task, duration_minutes).await {
+                Ok(promoted) => json!({ "status": "success", "promoted": promoted }),
+                Err(e) => json!({ "error": e.to_string() }),
+            }
+        }
         "list_google_tasks" => {
             let max_results = args
                 .get("max_results")
@@ -834,17 +2198,84 @@ pub async fn execute_tool_async(
                 Err(e) => json!({ "error": format!("Failed to create task: {}", e) }),
             }
         }
-        "take_screenshot" => match crate::commands::vision::capture_primary_screen().await {
-            Ok(b64) => {
-                json!({ "status": "success", "image_data": b64, "message": "Screen captured. You can now see the image in the next turn." })
+        "complete_google_task" => {
+            let task_id = args.get("task_id").and_then(|v| v.as_str()).unwrap_or("");
+            if task_id.is_empty() {
+                return json!({ "error": "task_id is required." });
             }
-            Err(e) => json!({ "error": format!("Failed to capture screen: {}", e) }),
-        },
+
+            match crate::integrations::google_tasks::complete_task(database, task_id).await {
+                Ok(task) => json!({ "status": "success", "task": task }),
+                Err(e) => json!({ "error": format!("Failed to complete task: {}", e) }),
+            }
+        }
+        "delete_google_task" => {
+            let task_id = args.get("task_id").and_then(|v| v.as_str()).unwrap_or("");
+            if task_id.is_empty() {
+                return json!({ "error": "task_id is required." });
+            }
+
+            match crate::integrations::google_tasks::delete_task(database, task_id).await {
+                Ok(_) => json!({ "status": "success", "task": { "id": task_id, "status": "deleted" } }),
+                Err(e) => json!({ "error": format!("Failed to delete task: {}", e) }),
+            }
+        }
+        "take_screenshot" => {
+            let monitor = args.get("monitor").and_then(|v| v.as_u64()).map(|m| m as usize);
+            match crate::commands::vision::capture_screen_for_gemini(app, monitor) {
+                Ok(image) => {
+                    json!({ "status": "success", "image_data": image.data, "mime_type": image.mime_type, "message": "Screen captured. You can now see the image in the next turn." })
+                }
+                Err(e) => json!({ "error": format!("Failed to capture screen: {}", e) }),
+            }
+        }
+        "take_window_screenshot" => {
+            let filter = args.get("window_title").and_then(|v| v.as_str()).unwrap_or("").trim();
+            if filter.is_empty() {
+                return json!({ "error": "window_title is required." });
+            }
+            match crate::commands::vision::capture_window_for_gemini(app, filter) {
+                Ok((image, Some(window))) => json!({
+                    "status": "success",
+                    "image_data": image.data,
+                    "mime_type": image.mime_type,
+                    "window": window.title,
+                    "message": "Window captured. You can now see the image in the next turn.",
+                }),
+                Ok((image, None)) => json!({
+                    "status": "success",
+                    "image_data": image.data,
+                    "mime_type": image.mime_type,
+                    "message": "Window capture isn't supported on this system, so the whole screen was captured instead.",
+                }),
+                Err(e) => json!({ "error": format!("Failed to capture window: {}", e) }),
+            }
+        }
+        "read_screen_text" => {
+            let monitor = args
+                .get("monitor")
+                .and_then(|v| v.as_u64())
+                .map(|m| m as usize)
+                .or_else(|| crate::commands::vision::overlay_screen_index(app));
+            match crate::commands::vision::read_screen_text(monitor).await {
+                Ok(lines) => {
+                    let text = lines.iter().map(|l| l.text.as_str()).collect::<Vec<_>>().join("\n");
+                    json!({ "text": text, "lines": lines })
+                }
+                Err(e) => json!({ "error": format!("Failed to read screen text: {}", e) }),
+            }
+        }
         "retrieve_past_memories" => {
             let query = args.get("query").and_then(|v| v.as_str()).unwrap_or("");
             
+            if database.connection.lock().is_ok_and(|connection| crate::offline::is_offline(&connection)) {
+                return crate::offline::offline_tool_result("Memory search");
+            }
             let api_key = {
-                let connection = database.connection.lock();
+                let connection = match database.connection.lock() {
+                    Ok(connection) => connection,
+                    Err(e) => return database_unavailable(e),
+                };
                 match crate::database::queries::get_api_token(&connection, "gemini") {
                     Ok(Some(enc_key)) => {
                         match crate::crypto::decrypt_token(&enc_key) {
@@ -857,14 +2288,17 @@ pub async fn execute_tool_async(
             };
             let memory_client = crate::gemini::client::GeminiClient::new(api_key);
 
-            println!("DEBUG: 🧠 Tool 'retrieve_past_memories' invoked for: '{}'", query);
+            tracing::debug!("🧠 Tool 'retrieve_past_memories' invoked for: '{}'", query);
 
             match memory_client.generate_embedding(query).await {
                 Ok(embedding) => {
-                    let connection = database.connection.lock();
+                    let connection = match database.connection.lock() {
+                        Ok(connection) => connection,
+                        Err(e) => return database_unavailable(e),
+                    };
                     match crate::memory::core::retrieve_memories(&connection, &embedding, 50) {
                         Ok(memories) if !memories.is_empty() => {
-                            println!("DEBUG: 🧠 Retrieved {} memories for query.", memories.len());
+                            tracing::debug!("🧠 Retrieved {} memories for query.", memories.len());
                             let memory_context = crate::memory::core::format_memories_for_prompt(&memories);
                             for m in &memories {
                                 let _ = crate::memory::core::update_memory_access(&connection, &m.id);
@@ -876,29 +2310,145 @@ pub async fn execute_tool_async(
                     }
                 }
                 Err(e) => {
-                    println!("DEBUG: 🧠 Embedding Generation Failed! Error: {:#?}", e);
+                    tracing::debug!("🧠 Embedding Generation Failed! Error: {:#?}", e);
                     json!({ "error": format!("Failed to generate embedding for memory search: {}", e) })
                 }
             }
         }
+        "semantic_search_notes" => {
+            let query = args.get("query").and_then(|v| v.as_str()).unwrap_or("").trim();
+            let limit = args.get("limit").and_then(|v| v.as_u64()).unwrap_or(5) as usize;
+            if query.is_empty() {
+                return json!({ "error": "query is required." });
+            }
+            let client = match crate::commands::notes::index_prerequisites(database) {
+                Ok((_, client)) => client,
+                Err(e) => return json!({ "error": e.to_string() }),
+            };
+            let embedding = match client.generate_query_embedding(query).await {
+                Ok(embedding) => embedding,
+                Err(e) => return json!({ "error": format!("Failed to embed query: {}", e) }),
+            };
+            let connection = match database.connection.lock() {
+                Ok(connection) => connection,
+                Err(e) => return database_unavailable(e),
+            };
+            match crate::integrations::vault_index::search_notes(&connection, &embedding, limit) {
+                Ok(matches) if matches.is_empty() => json!({
+                    "message": "The notes index is empty. Ask the user to index their notes from the Obsidian card on the Integrations page, or use search_notes.",
+                }),
+                Ok(matches) => json!({ "matches": matches }),
+                Err(e) => json!({ "error": format!("Failed to search notes: {}", e) }),
+            }
+        }
+        "remember" => {
+            let fact = args.get("fact").and_then(|v| v.as_str()).unwrap_or("").trim();
+            if fact.is_empty() {
+                return json!({ "error": "fact is required." });
+            }
+
+            //NOTE: The embedding is a bonus for retrieve_past_memories; without a key (or offline) the fact is still saved
+            let api_key = database
+                .connection
+                .lock()
+                .ok()
+                .and_then(|connection| crate::llm::background_gemini_key(&connection).ok().flatten());
+            let embedding = match api_key {
+                Some(key) => crate::gemini::client::GeminiClient::new(key)
+                    .generate_embedding(fact)
+                    .await
+                    .map_err(|e| tracing::warn!("⚠️ Memory: Failed to embed fact: {:#}", e))
+                    .ok(),
+                None => None,
+            };
+
+            let connection = match database.connection.lock() {
+                Ok(connection) => connection,
+                Err(e) => return database_unavailable(e),
+            };
+            match crate::memory::facts::remember_fact(&connection, fact, embedding) {
+                Ok(_) => json!({ "status": "success", "message": format!("Remembered: {}", fact) }),
+                Err(e) => json!({ "error": format!("Failed to remember: {}", e) }),
+            }
+        }
         _ => json!({ "error": format!("Unknown asynchronous tool: {}", name) }),
     }
 }
 
+//INFO: How long a weather lookup stays fresh
+const WEATHER_CACHE_TTL_MINUTES: i64 = 30;
+
+//INFO: In-process weather cache, checked before the web_cache table
+static WEATHER_MEMORY_CACHE: parking_lot::Mutex<
+    Option<std::collections::HashMap<String, (std::time::Instant, serde_json::Value)>>,
+> = parking_lot::Mutex::new(None);
+
 //INFO: Standalone weather fetch for internal use
-pub async fn fetch_weather(location: &str) -> serde_json::Value {
+//NOTE: Results are cached in memory and in the web_cache table for WEATHER_CACHE_TTL_MINUTES;
+//      cached responses carry `"cached": true`. Errors are never cached.
+pub async fn fetch_weather(database: &crate::database::Database, location: &str) -> serde_json::Value {
+    let cache_key = format!("weather:{}", location.trim().to_lowercase());
+    let ttl = chrono::Duration::minutes(WEATHER_CACHE_TTL_MINUTES);
+
+    // 1. In-memory cache
+    if let Some((fetched, value)) = WEATHER_MEMORY_CACHE
+        .lock()
+        .as_ref()
+        .and_then(|cache| cache.get(&cache_key).cloned())
+    {
+        if fetched.elapsed() < ttl.to_std().unwrap_or_default() {
+            return with_cached_flag(value);
+        }
+    }
+
+    // 2. Database cache (survives restarts)
+    let stored = database
+        .connection
+        .lock()
+        .ok()
+        .and_then(|connection| crate::database::queries::get_web_cache(&connection, &cache_key, ttl).ok().flatten());
+    if let Some(value) = stored.and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok()) {
+        remember_weather(&cache_key, &value);
+        return with_cached_flag(value);
+    }
+
+    // 3. Live fetch (cached results above are still served in offline mode)
+    if database.connection.lock().is_ok_and(|connection| crate::offline::is_offline(&connection)) {
+        return crate::offline::offline_tool_result("Weather");
+    }
+    let value = fetch_weather_live(location).await;
+    if value.get("error").is_none() {
+        remember_weather(&cache_key, &value);
+        if let Ok(connection) = database.connection.lock() {
+            let _ = crate::database::queries::save_web_cache(&connection, &cache_key, &value.to_string());
+        }
+    }
+    value
+}
+
+fn remember_weather(cache_key: &str, value: &serde_json::Value) {
+    WEATHER_MEMORY_CACHE
+        .lock()
+        .get_or_insert_with(Default::default)
+        .insert(cache_key.to_string(), (std::time::Instant::now(), value.clone()));
+}
+
+fn with_cached_flag(mut value: serde_json::Value) -> serde_json::Value {
+    if let Some(obj) = value.as_object_mut() {
+        obj.insert("cached".into(), json!(true));
+    }
+    value
+}
+
+//INFO: Fetches current conditions from wttr.in
+async fn fetch_weather_live(location: &str) -> serde_json::Value {
     let url = format!("https://wttr.in/{}?format=j1", location);
 
-    // Reuse a shared client for simple HTTP requests as well
-    static WEATHER_CLIENT: std::sync::OnceLock<reqwest::Client> = std::sync::OnceLock::new();
-    let client = WEATHER_CLIENT.get_or_init(|| {
-        reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(10))
-            .build()
-            .unwrap_or_else(|_| reqwest::Client::new())
-    });
+    let request = crate::integrations::http::shared_client()
+        .get(&url)
+        .timeout(std::time::Duration::from_secs(10));
 
-    match client.get(&url).send().await {
+    match request.send().await {
         Ok(response) => match response.json::<serde_json::Value>().await {
             Ok(data) => {
                 if let Some(current) = data
@@ -938,3 +2488,176 @@ pub async fn fetch_weather(location: &str) -> serde_json::Value {
         Err(e) => json!({ "error": format!("Failed to fetch weather: {}", e) }),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit_line(content: &str, tool: &str, args: serde_json::Value) -> String {
+        //NOTE: The test harness names each test's thread after the test, so parallel tests never share a file
+        let test_name = std::thread::current().name().unwrap_or("test").replace("::", "-");
+        let path = std::env::temp_dir()
+            .join(format!("lumen-lines-{}-{}-{}.md", test_name, tool, std::process::id()));
+        fs::write(&path, content).unwrap();
+
+        let mut args = args;
+        args["path"] = json!(path.to_string_lossy());
+        let connection = rusqlite::Connection::open_in_memory().unwrap();
+        let result = execute_tool_sync(tool, &args, None, &connection);
+        assert_eq!(result["status"], "success", "{}", result);
+
+        let updated = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        updated
+    }
+
+    #[test]
+    fn test_read_file_truncates_large_files() {
+        let path = std::env::temp_dir().join(format!("lumen-large-{}.log", std::process::id()));
+        let line = "2025-03-07 12:00:00 INFO something happened é\n";
+        fs::write(&path, line.repeat(100_000)).unwrap();
+        let total = fs::metadata(&path).unwrap().len();
+        assert!(total > 4_000_000);
+
+        let connection = rusqlite::Connection::open_in_memory().unwrap();
+        crate::database::initialize_database(&connection).unwrap();
+        let args = json!({ "path": path.to_string_lossy() });
+
+        let result = execute_tool_sync("read_file", &args, None, &connection);
+        assert_eq!(result["truncated"], true);
+        let content = result["content"].as_str().unwrap();
+        assert!(content.len() < DEFAULT_READ_FILE_MAX_BYTES as usize + 100);
+        assert!(content.ends_with("more bytes)"));
+
+        let lines = execute_tool_sync(
+            "read_file_lines",
+            &json!({ "path": path.to_string_lossy(), "start_line": 99_999, "end_line": 100_000 }),
+            None,
+            &connection,
+        );
+        assert_eq!(lines["total_lines"], 100_000);
+        assert_eq!(lines["lines"].as_array().unwrap().len(), 2);
+
+        let absurd = execute_tool_sync(
+            "read_file_lines",
+            &json!({ "path": path.to_string_lossy(), "start_line": 1, "end_line": 1_000_000 }),
+            None,
+            &connection,
+        );
+        assert!(absurd.get("error").is_some());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_file_lookups_stay_inside_the_vault() {
+        let root = std::env::temp_dir().join(format!("lumen-confine-{}", std::process::id()));
+        let vault = root.join("vault");
+        fs::create_dir_all(&vault).unwrap();
+        fs::write(vault.join("note.md"), "one\ntwo\n").unwrap();
+        fs::write(root.join("secret.txt"), "outside").unwrap();
+        let config = json!({ "vault_path": vault.to_string_lossy() });
+        let connection = rusqlite::Connection::open_in_memory().unwrap();
+        crate::database::initialize_database(&connection).unwrap();
+        let run = |tool: &str, args: serde_json::Value| execute_tool_sync(tool, &args, Some(&config), &connection);
+
+        assert_eq!(run("get_file_metadata", json!({ "path": "note.md" }))["line_count"], 2);
+        let outside = root.join("secret.txt").to_string_lossy().into_owned();
+        assert!(run("get_file_metadata", json!({ "path": outside }))["error"].is_string());
+        assert!(run("get_file_metadata", json!({ "path": "../secret.txt" }))["error"].is_string());
+
+        assert_eq!(run("search_filesystem", json!({ "query": "note" }))["matches"].as_array().unwrap().len(), 1);
+        let parent = root.to_string_lossy().into_owned();
+        assert!(run("search_filesystem", json!({ "path": parent, "query": "secret" }))["error"].is_string());
+
+        let append = |path: &str| preview_tool_call("append_to_file", &json!({ "path": path, "content": "x" }), Some(&config));
+        let inside = vault.join("note.md").to_string_lossy().into_owned();
+        assert_eq!(append(&inside)["diff"], "@@ line 3 @@\n+ x");
+        assert!(append(&outside)["error"].is_string());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_update_reminder_rejects_unreadable_due_times() {
+        let connection = rusqlite::Connection::open_in_memory().unwrap();
+        crate::database::initialize_database(&connection).unwrap();
+        let id = crate::database::queries::add_reminder(&connection, "call mum", None).unwrap();
+
+        let rejected = execute_tool_sync("update_reminder", &json!({ "id": id, "due_at": "next tuesday" }), None, &connection);
+        assert!(rejected["error"].as_str().unwrap().contains("next tuesday"));
+
+        let updated = execute_tool_sync("update_reminder", &json!({ "id": id, "due_at": "2025-03-07 09:00" }), None, &connection);
+        assert_eq!(updated["status"], "success", "{}", updated);
+    }
+
+    #[test]
+    fn test_filename_matches_substrings_and_globs() {
+        assert!(filename_matches("resume.pdf", "resume"));
+        assert!(filename_matches("app.js", ".js"));
+        assert!(filename_matches("2025-03-review.md", "2025-*-review.md"));
+        assert!(filename_matches("note1.md", "note?.md"));
+        assert!(!filename_matches("note12.md", "note?.md"));
+        assert!(!filename_matches("review.txt", "*.md"));
+    }
+
+    #[test]
+    fn test_line_tools_preserve_crlf_and_trailing_newline() {
+        let crlf = "one\r\ntwo\r\nthree\r\n";
+        assert_eq!(
+            edit_line(crlf, "edit_file_line", json!({ "line_number": 2, "new_content": "TWO" })),
+            "one\r\nTWO\r\nthree\r\n"
+        );
+        assert_eq!(
+            edit_line(crlf, "insert_at_line", json!({ "line_number": 1, "content": "zero" })),
+            "zero\r\none\r\ntwo\r\nthree\r\n"
+        );
+        assert_eq!(
+            edit_line(crlf, "delete_file_line", json!({ "line_number": 3 })),
+            "one\r\ntwo\r\n"
+        );
+    }
+
+    #[test]
+    fn test_line_tools_keep_missing_trailing_newline() {
+        let lf = "one\ntwo";
+        assert_eq!(
+            edit_line(lf, "edit_file_line", json!({ "line_number": 1, "new_content": "ONE" })),
+            "ONE\ntwo"
+        );
+        assert_eq!(
+            edit_line(
+                "one\ntwo\n",
+                "insert_at_line",
+                json!({ "line_number": 3, "content": "three" })
+            ),
+            "one\ntwo\nthree\n"
+        );
+    }
+
+    #[test]
+    fn test_audit_keeps_only_the_shape_of_args_and_results() {
+        let args = json!({
+            "path": "notes/todo.md",
+            "content": "x".repeat(5000),
+            "limit": 5,
+            "auth": { "api_key": "sk-123" },
+        });
+
+        let described = describe_audit_value(&args);
+        assert_eq!(described["path"], "[13 chars]");
+        assert_eq!(described["content"], "[5000 chars]");
+        assert_eq!(described["limit"], 5);
+        assert_eq!(described["auth"]["api_key"], "[6 chars]");
+        assert!(!described.to_string().contains("sk-123"));
+
+        let (summary, success) = summarize_audit_result(&json!({ "entries": ["secret copy"] }));
+        assert!(success);
+        assert!(!summary.contains("secret copy"));
+
+        assert_eq!(
+            summarize_audit_result(&json!({ "error": "File not found" })),
+            ("File not found".to_string(), false)
+        );
+    }
+}
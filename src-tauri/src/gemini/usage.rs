@@ -0,0 +1,38 @@
+//INFO: Gemini token usage accounting
+//NOTE: Every Gemini call site reports its usageMetadata here, tagged with the feature that made it
+
+use super::client::UsageMetadata;
+use crate::database::{queries, Database};
+use chrono::Local;
+
+//INFO: Feature tags used in the usage_log table
+pub const FEATURE_CHAT: &str = "chat";
+pub const FEATURE_BRIEFING: &str = "briefing";
+pub const FEATURE_TTS: &str = "tts";
+pub const FEATURE_TRIAGE: &str = "triage";
+pub const FEATURE_MEMORY: &str = "memory";
+pub const FEATURE_SUMMARY: &str = "summary";
+pub const FEATURE_DIGEST: &str = "digest";
+
+//INFO: Adds a response's token counts to today's total for `feature`
+//NOTE: Accounting must never break the feature itself, so failures are only logged
+pub fn record_usage(database: &Database, feature: &str, usage: Option<&UsageMetadata>) {
+    let Some(usage) = usage else {
+        return;
+    };
+
+    let today = Local::now().format("%Y-%m-%d").to_string();
+    let recorded = database.connection.lock().and_then(|connection| {
+        queries::record_usage(
+            &connection,
+            &today,
+            feature,
+            usage.prompt_token_count as i64,
+            usage.candidates_token_count as i64,
+            usage.total_token_count as i64,
+        )
+    });
+    if let Err(e) = recorded {
+        tracing::warn!("⚠️ Usage: Failed to record {} usage: {}", feature, e);
+    }
+}
@@ -0,0 +1,236 @@
+//INFO: Global hotkey registration for Lumen
+//NOTE: Shared by startup and the settings commands so hotkey changes apply without a restart
+
+use crate::commands::{vision, window};
+use crate::database::queries::HotkeyConfig;
+use parking_lot::Mutex;
+use tauri::AppHandle;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+//INFO: Fallbacks used before the user has configured anything
+const DEFAULT_MAIN_SHORTCUT: &str = "Super+L";
+const DEFAULT_SNIP_SHORTCUT: &str = "Super+Shift+S";
+
+//INFO: The config that is currently registered, so a failed swap can restore it
+static ACTIVE_CONFIG: Mutex<Option<HotkeyConfig>> = Mutex::new(None);
+
+//INFO: Joins modifiers and key into the "Mod+Mod+Key" form the shortcut parser expects
+pub fn shortcut_string(modifier_keys: &[String], key: &str) -> String {
+    let modifiers = modifier_keys.join("+");
+    if modifiers.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}+{}", modifiers, key)
+    }
+}
+
+//INFO: Names the shortcut parser treats as modifiers rather than keys
+const MODIFIER_NAMES: &[&str] = &[
+    "shift", "ctrl", "control", "alt", "option", "super", "cmd", "command", "commandorcontrol",
+    "commandorctrl", "cmdorctrl", "cmdorcontrol", "meta",
+];
+
+//INFO: Combos the OS usually claims for itself; registering them tends to silently fail or misbehave
+const RESERVED_COMBOS: &[&[&str]] = &[
+    &["alt", "tab"],
+    &["alt", "f4"],
+    &["ctrl", "alt", "delete"],
+    &["super", "space"],
+    &["super", "q"],
+    &["super", "tab"],
+    &["super", "shift", "3"],
+    &["super", "shift", "4"],
+];
+
+//INFO: Returns a warning when the combo collides with a well-known OS shortcut
+pub fn reserved_combo_warning(modifier_keys: &[String], key: &str) -> Option<String> {
+    let normalize = |k: &str| match k.to_lowercase().as_str() {
+        "control" => "ctrl".to_string(),
+        "option" => "alt".to_string(),
+        "cmd" | "command" | "meta" => "super".to_string(),
+        other => other.to_string(),
+    };
+    let mut combo: Vec<String> = modifier_keys.iter().map(|m| normalize(m)).collect();
+    combo.push(normalize(key));
+    combo.sort();
+
+    RESERVED_COMBOS.iter().find_map(|reserved| {
+        let mut reserved: Vec<String> = reserved.iter().map(|k| k.to_string()).collect();
+        reserved.sort();
+        (reserved == combo).then(|| {
+            format!(
+                "'{}' is usually reserved by the operating system and may not trigger Lumen",
+                shortcut_string(modifier_keys, key)
+            )
+        })
+    })
+}
+
+//INFO: Reserved-combo warnings for the enabled shortcuts of a config, for Settings to show after saving
+pub fn config_warnings(config: &HotkeyConfig) -> Vec<String> {
+    [
+        (config.enabled, &config.modifier_keys, &config.key),
+        (config.snipper_enabled, &config.snipper_modifier_keys, &config.snipper_key),
+    ]
+    .into_iter()
+    .filter(|(enabled, _, _)| *enabled)
+    .filter_map(|(_, modifier_keys, key)| reserved_combo_warning(modifier_keys, key))
+    .collect()
+}
+
+//INFO: Validates a modifier list and key, returning the parsed Shortcut
+//NOTE: Rejects empty and modifier-only combos so users can't lock themselves out of the overlay
+pub fn validate_hotkey(modifier_keys: &[String], key: &str) -> Result<Shortcut, String> {
+    let key = key.trim();
+    if key.is_empty() {
+        return Err("Hotkey must include a key, not just modifiers".to_string());
+    }
+    if MODIFIER_NAMES.contains(&key.to_lowercase().as_str()) {
+        return Err(format!("'{}' is a modifier; pick a regular key to pair it with", key));
+    }
+    if let Some(empty) = modifier_keys.iter().find(|m| m.trim().is_empty()) {
+        return Err(format!("Invalid empty modifier '{}' in hotkey", empty));
+    }
+
+    let combined = shortcut_string(modifier_keys, key);
+    let shortcut = combined
+        .parse::<Shortcut>()
+        .map_err(|e| format!("Invalid hotkey '{}': {}", combined, e))?;
+
+    if let Some(warning) = reserved_combo_warning(modifier_keys, key) {
+        tracing::warn!("⚠️ Hotkey: {}", warning);
+    }
+
+    Ok(shortcut)
+}
+
+//INFO: Parses both shortcuts of a config (None for disabled ones) without touching any bindings
+pub fn parse_hotkey_config(config: Option<&HotkeyConfig>) -> Result<(Option<Shortcut>, Option<Shortcut>), String> {
+    let Some(config) = config else {
+        let parse = |s: &str| s.parse::<Shortcut>().map_err(|e| e.to_string());
+        return Ok((Some(parse(DEFAULT_MAIN_SHORTCUT)?), Some(parse(DEFAULT_SNIP_SHORTCUT)?)));
+    };
+
+    let main = if config.enabled {
+        Some(validate_hotkey(&config.modifier_keys, &config.key)?)
+    } else {
+        None
+    };
+    let snip = if config.snipper_enabled {
+        Some(validate_hotkey(&config.snipper_modifier_keys, &config.snipper_key)?)
+    } else {
+        None
+    };
+    Ok((main, snip))
+}
+
+//INFO: Binds the overlay toggle and snipper shortcuts
+fn bind(app: &AppHandle, main: Option<Shortcut>, snip: Option<Shortcut>) -> Result<(), String> {
+    // 1. Register Main Toggle Shortcut
+    if let Some(shortcut) = main {
+        let handle = app.clone();
+        app.global_shortcut()
+            .on_shortcut(shortcut, move |_app, _sc, event| {
+                if event.state == ShortcutState::Pressed {
+                    let h = handle.clone();
+                    let _ = handle.run_on_main_thread(move || {
+                        tauri::async_runtime::block_on(async move {
+                            let _ = window::toggle_overlay(h).await;
+                        });
+                    });
+                }
+            })
+            .map_err(|e| format!("Failed to register overlay hotkey: {}", e))?;
+    }
+
+    // 2. Register Snipper Shortcut
+    if let Some(shortcut) = snip {
+        let handle = app.clone();
+        app.global_shortcut()
+            .on_shortcut(shortcut, move |_app, _sc, event| {
+                if event.state == ShortcutState::Pressed {
+                    let h = handle.clone();
+                    let _ = handle.run_on_main_thread(move || {
+                        tauri::async_runtime::block_on(async move {
+                            let _ = vision::start_snipping(h, None).await;
+                        });
+                    });
+                }
+            })
+            .map_err(|e| format!("Failed to register snipper hotkey: {}", e))?;
+    }
+
+    Ok(())
+}
+
+//INFO: Replaces the registered hotkeys with the ones from `config` (defaults when None)
+//NOTE: Both shortcuts are parsed before anything is unregistered, so a bad config leaves the old
+//      bindings untouched. If binding the new ones fails, the previous config is re-registered.
+pub fn register_hotkey(app: &AppHandle, config: Option<&HotkeyConfig>) -> Result<(), String> {
+    let (main, snip) = parse_hotkey_config(config)?;
+
+    let mut active = ACTIVE_CONFIG.lock();
+    app.global_shortcut()
+        .unregister_all()
+        .map_err(|e| format!("Failed to unregister previous hotkeys: {}", e))?;
+
+    if let Err(e) = bind(app, main, snip) {
+        let _ = app.global_shortcut().unregister_all();
+        if let Ok((old_main, old_snip)) = parse_hotkey_config(active.as_ref()) {
+            let _ = bind(app, old_main, old_snip);
+        }
+        return Err(e);
+    }
+
+    *active = config.cloned();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(modifiers: &[&str], key: &str) -> HotkeyConfig {
+        HotkeyConfig {
+            modifier_keys: modifiers.iter().map(|m| m.to_string()).collect(),
+            key: key.to_string(),
+            enabled: true,
+            snipper_modifier_keys: vec!["Super".to_string(), "Shift".to_string()],
+            snipper_key: "S".to_string(),
+            snipper_enabled: true,
+        }
+    }
+
+    #[test]
+    fn test_unparseable_hotkey_is_rejected_before_registration() {
+        assert!(parse_hotkey_config(Some(&config(&["Ctrl", "Shift"], "Space"))).is_ok());
+
+        let err = parse_hotkey_config(Some(&config(&["Ctrl"], "NotAKey"))).unwrap_err();
+        assert!(err.contains("Ctrl+NotAKey"), "{}", err);
+
+        //INFO: Disabled shortcuts aren't parsed at all
+        let mut disabled = config(&["Ctrl"], "NotAKey");
+        disabled.enabled = false;
+        assert_eq!(parse_hotkey_config(Some(&disabled)).unwrap().0, None);
+    }
+
+    #[test]
+    fn test_validate_hotkey_rejects_modifier_only_and_empty_keys() {
+        let mods = vec!["Ctrl".to_string(), "Shift".to_string()];
+        assert!(validate_hotkey(&mods, "K").is_ok());
+        assert!(validate_hotkey(&mods, "").is_err());
+        assert!(validate_hotkey(&mods, "Shift").is_err());
+        assert!(validate_hotkey(&["Hyper2".to_string()], "K").is_err());
+
+        let reserved = vec!["Alt".to_string()];
+        assert!(reserved_combo_warning(&reserved, "Tab").is_some());
+        assert!(reserved_combo_warning(&mods, "K").is_none());
+
+        let mut both_reserved = config(&["Alt"], "Tab");
+        both_reserved.snipper_modifier_keys = vec!["Super".to_string()];
+        both_reserved.snipper_key = "Tab".to_string();
+        assert_eq!(config_warnings(&both_reserved).len(), 2);
+        both_reserved.snipper_enabled = false;
+        assert_eq!(config_warnings(&both_reserved).len(), 1);
+    }
+}
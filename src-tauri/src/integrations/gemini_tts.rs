@@ -1,12 +1,18 @@
 // Gemini TTS integration for briefing audio generation
 use crate::crypto::decrypt_token;
 use crate::database::{queries, Database};
+use crate::error::RateLimitExceeded;
+use crate::gemini::client::UsageMetadata;
+use crate::gemini::usage::{record_usage, FEATURE_TTS};
 use anyhow::{Context, Result};
 use base64::{engine::general_purpose, Engine as _};
 use serde::{Deserialize, Serialize};
 
 const TTS_MODEL: &str = "gemini-2.5-flash-preview-tts";
 
+//INFO: Generating audio for a long reply can take longer than the shared client's default timeout
+const TTS_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(90);
+
 #[derive(Debug, Serialize)]
 struct TTSRequest {
     contents: Vec<TTSContent>,
@@ -53,6 +59,8 @@ struct PrebuiltVoiceConfig {
 #[derive(Debug, Deserialize)]
 struct TTSResponse {
     candidates: Option<Vec<TTSCandidate>>,
+    #[serde(rename = "usageMetadata")]
+    usage_metadata: Option<UsageMetadata>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -82,16 +90,15 @@ struct InlineData {
 /// Generate audio from text using Gemini TTS
 pub async fn generate_audio(database: &Database, text: &str) -> Result<Vec<u8>> {
     let api_key = {
-        let connection = database.connection.lock();
+        let connection = database.connection.lock()?;
+        crate::offline::ensure_online(&connection, "Briefing audio")?;
         let encrypted_key =
             queries::get_api_token(&connection, "gemini")?.context("Gemini API key not found")?;
         decrypt_token(&encrypted_key)?
     };
 
     // Clean text for speech (remove markdown)
-    let link_regex = regex::Regex::new(r"\[([^\]]+)\]\([^)]+\)").unwrap();
-    let clean_text = link_regex.replace_all(text, "$1").to_string();
-    let clean_text = clean_text.replace(['*', '_', '#', '`'], "");
+    let clean_text = crate::text::strip_markdown(text);
 
     let request = TTSRequest {
         contents: vec![TTSContent {
@@ -115,16 +122,21 @@ pub async fn generate_audio(database: &Database, text: &str) -> Result<Vec<u8>>
         TTS_MODEL, api_key
     );
 
-    let client = reqwest::Client::new();
+    let client = crate::integrations::http::shared_client();
     let response = client
         .post(&url)
+        .timeout(TTS_TIMEOUT)
         .json(&request)
         .send()
         .await
         .context("Failed to send TTS request")?;
 
     if !response.status().is_success() {
+        let status = response.status();
         let error_text = response.text().await.unwrap_or_default();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(RateLimitExceeded(format!("TTS API error: {}", error_text)).into());
+        }
         anyhow::bail!("TTS API error: {}", error_text);
     }
 
@@ -133,6 +145,8 @@ pub async fn generate_audio(database: &Database, text: &str) -> Result<Vec<u8>>
         .await
         .context("Failed to parse TTS response")?;
 
+    record_usage(database, FEATURE_TTS, tts_response.usage_metadata.as_ref());
+
     // Extract audio data from response
     let audio_data = tts_response
         .candidates
@@ -0,0 +1,101 @@
+//INFO: Tokens and status shared by the Gmail, Calendar and Tasks integrations
+//NOTE: All three sign in through the same "google" integration row and token, so reading, refreshing
+//      and recording the outcome lives here rather than in any one of them
+
+use crate::crypto::{decrypt_token, encrypt_token, hold_encryption_key};
+use crate::database::queries::{
+    clear_integration_error, get_api_token, get_integration, mark_integration_needs_reauth,
+    save_api_token, set_integration_error,
+};
+use crate::database::Database;
+use crate::oauth::google::{GoogleAuth, GoogleTokens, GrantRevoked};
+use anyhow::{anyhow, Context, Result};
+
+//INFO: Reads the stored Google tokens
+//NOTE: Every Google API call starts here, so this is also where offline mode stops them
+pub fn get_google_tokens(connection: &rusqlite::Connection) -> Result<GoogleTokens> {
+    crate::offline::ensure_online(connection, "Google")?;
+    let encrypted = get_api_token(connection, "google")?
+        .ok_or_else(|| anyhow!("Google tokens not found. Please connect Google first."))?;
+
+    let decrypted = decrypt_token(&encrypted)?;
+    let tokens: GoogleTokens = serde_json::from_str(&decrypted)?;
+    Ok(tokens)
+}
+
+//INFO: Whether the access token expires within the next 5 minutes
+pub fn is_expired(tokens: &GoogleTokens) -> bool {
+    match tokens.expires_at {
+        Some(expiry) => chrono::Utc::now() + chrono::Duration::minutes(5) >= expiry,
+        None => true,
+    }
+}
+
+//INFO: Trades the refresh token for a new access token and stores it
+//NOTE: The outcome is recorded on the integration, so a revoked grant turns into a reconnect prompt
+#[tracing::instrument(name = "google_token_refresh", skip_all, err)]
+pub async fn refresh_google_tokens(
+    database: &Database,
+    current_tokens: &GoogleTokens,
+) -> Result<GoogleTokens> {
+    let (client_id, client_secret, refresh_token) = {
+        let connection = database.connection.lock()?;
+        let refresh_token = current_tokens
+            .refresh_token
+            .clone()
+            .ok_or_else(|| anyhow!("No refresh token found for Google"))?;
+
+        let integration = get_integration(&connection, "google")?
+            .ok_or_else(|| anyhow!("Google integration config not found"))?;
+
+        let config: serde_json::Value =
+            serde_json::from_str(&integration.config.context("Missing config")?)?;
+        let client_id = config["client_id"]
+            .as_str()
+            .context("Missing client_id")?
+            .to_string();
+        let client_secret = config["client_secret"]
+            .as_str()
+            .context("Missing client_secret")?
+            .to_string();
+        (client_id, client_secret, refresh_token)
+    };
+
+    let auth = GoogleAuth::new(client_id, client_secret);
+    let refreshed = auth.refresh_access_token(refresh_token).await;
+    record_google_result(database, &refreshed);
+    let mut new_tokens = refreshed?;
+
+    //INFO: Google usually leaves the refresh token out of a refresh response, so keep the old one
+    if new_tokens.refresh_token.is_none() {
+        new_tokens.refresh_token = current_tokens.refresh_token.clone();
+    }
+
+    {
+        let connection = database.connection.lock()?;
+        let tokens_json = serde_json::to_string(&new_tokens)?;
+        let _key = hold_encryption_key();
+        let encrypted = encrypt_token(&tokens_json)?;
+        save_api_token(&connection, "google", &encrypted, "oauth2")?;
+    }
+
+    tracing::info!("🔑 Google: Refreshed access token");
+    Ok(new_tokens)
+}
+
+//INFO: Records the outcome of a Google refresh or fetch as the integration's last error
+//NOTE: Offline mode isn't a Google failure, so it leaves the last error alone. A revoked grant marks
+//      the integration as needing a fresh sign-in, which also stops the background refresher retrying it
+pub fn record_google_result<T>(database: &Database, result: &Result<T>) {
+    let recorded = database.connection.lock().and_then(|connection| match result {
+        Ok(_) => clear_integration_error(&connection, "google"),
+        Err(e) if e.downcast_ref::<crate::offline::OfflineBlocked>().is_some() => Ok(()),
+        Err(e) if e.downcast_ref::<GrantRevoked>().is_some() => {
+            mark_integration_needs_reauth(&connection, "google", &format!("{:#}", e))
+        }
+        Err(e) => set_integration_error(&connection, "google", &format!("{:#}", e)),
+    });
+    if let Err(e) = recorded {
+        tracing::warn!("⚠️ Google: Failed to record integration status: {}", e);
+    }
+}
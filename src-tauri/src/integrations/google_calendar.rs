@@ -1,13 +1,40 @@
 // src-tauri/src/integrations/google_calendar.rs
-use crate::crypto::{decrypt_token, encrypt_token};
-use crate::database::queries::{get_api_token, get_integration, save_api_token};
+use crate::database::queries::CalendarEvent;
 use crate::database::Database;
-use crate::oauth::google::{GoogleAuth, GoogleTokens};
-use anyhow::{anyhow, Context, Result};
+use crate::integrations::google::{get_google_tokens, is_expired, refresh_google_tokens};
+use crate::integrations::http::{ensure_success, read_json, send_with_retry};
+use anyhow::{anyhow, Result};
+use parking_lot::Mutex;
 use reqwest::header::AUTHORIZATION;
 use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
 
-#[derive(Debug, Serialize, Deserialize)]
+const CALENDAR_API_BASE: &str = "https://www.googleapis.com/calendar/v3";
+pub const DEFAULT_CALENDAR_ID: &str = "primary";
+
+//INFO: How long the calendar list is reused before hitting calendarList again
+const CALENDAR_LIST_TTL: Duration = Duration::from_secs(5 * 60);
+
+//INFO: Most events one fetch returns, so a pathological day can't flood a briefing or a chat turn
+pub const MAX_EVENTS_PER_FETCH: usize = 250;
+//INFO: The Calendar API's own maxResults ceiling
+const MAX_EVENT_WINDOW: usize = 2500;
+
+//INFO: In-memory cache of the user's calendar list
+static CALENDAR_LIST_CACHE: Mutex<Option<(Instant, Vec<GoogleCalendar>)>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoogleCalendar {
+    pub id: String,
+    pub summary: Option<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub primary: bool,
+    #[serde(rename = "accessRole")]
+    pub access_role: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GoogleCalendarEvent {
     pub id: String,
     pub summary: Option<String>,
@@ -15,22 +42,228 @@ pub struct GoogleCalendarEvent {
     pub start: GoogleDateTime,
     pub end: GoogleDateTime,
     pub location: Option<String>,
+    #[serde(rename = "hangoutLink")]
+    pub hangout_link: Option<String>,
+    #[serde(rename = "conferenceData")]
+    pub conference_data: Option<serde_json::Value>,
+    //INFO: Last modification time (RFC3339), used to notice edited events
+    pub updated: Option<String>,
+    //INFO: The event's page in Google Calendar
+    #[serde(rename = "htmlLink")]
+    pub html_link: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GoogleDateTime {
     #[serde(rename = "dateTime")]
     pub date_time: Option<String>,
     pub date: Option<String>,
 }
 
+//INFO: Cached rows store start/end as UTC RFC3339 (all-day events at local midnight),
+//NOTE: so range queries can compare them as strings regardless of the offset Google sent
+pub fn normalize_cache_time(value: &str) -> String {
+    use chrono::{Local, NaiveDate, SecondsFormat, TimeZone, Utc};
+
+    if let Ok(time) = chrono::DateTime::parse_from_rfc3339(value) {
+        return time.with_timezone(&Utc).to_rfc3339_opts(SecondsFormat::Secs, true);
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .and_then(|midnight| Local.from_local_datetime(&midnight).earliest())
+        .map(|time| time.with_timezone(&Utc).to_rfc3339_opts(SecondsFormat::Secs, true))
+        .unwrap_or_else(|| value.to_string())
+}
+
+impl GoogleCalendarEvent {
+    //INFO: The row stored in the calendar_events cache
+    pub fn to_cached(&self) -> CalendarEvent {
+        let time = |t: &GoogleDateTime| {
+            normalize_cache_time(t.date_time.as_deref().or(t.date.as_deref()).unwrap_or_default())
+        };
+        CalendarEvent {
+            id: self.id.clone(),
+            title: self.summary.clone().unwrap_or_else(|| "(No title)".to_string()),
+            description: self.description.clone(),
+            start_time: time(&self.start),
+            end_time: time(&self.end),
+            location: self.location.clone(),
+            all_day: self.start.date_time.is_none(),
+        }
+    }
+
+    //INFO: Rebuilds an event from the cache (meeting links and edit times aren't cached)
+    pub fn from_cached(event: CalendarEvent) -> Self {
+        let time = |value: String| {
+            if event.all_day {
+                let date = chrono::DateTime::parse_from_rfc3339(&value)
+                    .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d").to_string())
+                    .unwrap_or(value);
+                GoogleDateTime { date_time: None, date: Some(date) }
+            } else {
+                GoogleDateTime { date_time: Some(value), date: None }
+            }
+        };
+        Self {
+            id: event.id.clone(),
+            summary: Some(event.title.clone()),
+            description: event.description.clone(),
+            start: time(event.start_time.clone()),
+            end: time(event.end_time.clone()),
+            location: event.location.clone(),
+            hangout_link: None,
+            conference_data: None,
+            updated: None,
+        }
+    }
+}
+
+//INFO: Lists the calendars in the user's calendar list (cached for CALENDAR_LIST_TTL)
+pub async fn list_calendars(database: &Database) -> Result<Vec<GoogleCalendar>> {
+    if let Some((fetched_at, calendars)) = CALENDAR_LIST_CACHE.lock().as_ref() {
+        if fetched_at.elapsed() < CALENDAR_LIST_TTL {
+            return Ok(calendars.clone());
+        }
+    }
+
+    let mut tokens = {
+        let connection = database.connection.lock()?;
+        get_google_tokens(&connection)?
+    };
+
+    if is_expired(&tokens) {
+        tokens = refresh_google_tokens(database, &tokens).await?;
+    }
+
+    let url = format!("{}/users/me/calendarList", CALENDAR_API_BASE);
+
+    let client = crate::integrations::http::shared_client();
+    let mut response = send_with_retry(
+        client
+            .get(&url)
+            .header(AUTHORIZATION, format!("Bearer {}", tokens.access_token)),
+    )
+    .await?;
+
+    if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+        tokens = refresh_google_tokens(database, &tokens).await?;
+        response = send_with_retry(
+            client
+                .get(&url)
+                .header(AUTHORIZATION, format!("Bearer {}", tokens.access_token)),
+        )
+        .await?;
+    }
+
+    let data: serde_json::Value = read_json(response, "list calendars").await?;
+    let calendars: Vec<GoogleCalendar> = match data["items"].as_array() {
+        Some(items) => items
+            .iter()
+            .filter_map(|item| serde_json::from_value(item.clone()).ok())
+            .collect(),
+        None => Vec::new(),
+    };
+
+    *CALENDAR_LIST_CACHE.lock() = Some((Instant::now(), calendars.clone()));
+    Ok(calendars)
+}
+
+//INFO: Resolves a calendar id or name to a calendar id, defaulting to the primary calendar
+//NOTE: Lets the model pass "Work" instead of a long opaque id; unknown values pass through untouched
+pub async fn resolve_calendar_id(database: &Database, calendar: Option<&str>) -> String {
+    let Some(calendar) = calendar.map(str::trim).filter(|c| !c.is_empty() && *c != DEFAULT_CALENDAR_ID) else {
+        return DEFAULT_CALENDAR_ID.to_string();
+    };
+
+    if let Ok(calendars) = list_calendars(database).await {
+        if let Some(found) = calendars.iter().find(|c| {
+            c.id == calendar
+                || c.summary
+                    .as_deref()
+                    .is_some_and(|s| s.eq_ignore_ascii_case(calendar))
+        }) {
+            return found.id.clone();
+        }
+    }
+    calendar.to_string()
+}
+
+fn events_url(calendar_id: &str) -> String {
+    format!(
+        "{}/calendars/{}/events",
+        CALENDAR_API_BASE,
+        urlencoding::encode(calendar_id)
+    )
+}
+
+//INFO: One page of events from get_google_calendar_events
+#[derive(Debug, Serialize)]
+pub struct CalendarEventPage {
+    pub events: Vec<GoogleCalendarEvent>,
+    pub offset: usize,
+    pub has_more: bool,
+}
+
+//INFO: Fetches events in a range, capped at MAX_EVENTS_PER_FETCH (the earliest ones win)
 pub async fn fetch_google_calendar_events(
     database: &Database,
     time_min: &str, // RFC3339
     time_max: &str, // RFC3339
+    calendar_id: Option<&str>,
+) -> Result<Vec<GoogleCalendarEvent>> {
+    fetch_events_window(database, time_min, time_max, calendar_id, MAX_EVENTS_PER_FETCH).await
+}
+
+//INFO: Fetches `limit` events starting at `offset` within a range, and whether more follow
+//NOTE: The Calendar API pages with tokens, not offsets, so this asks for offset + limit + 1 events
+//      in one call and slices; offsets past MAX_EVENT_WINDOW aren't reachable
+pub async fn fetch_google_calendar_events_page(
+    database: &Database,
+    time_min: &str,
+    time_max: &str,
+    calendar_id: Option<&str>,
+    limit: usize,
+    offset: usize,
+) -> Result<CalendarEventPage> {
+    let limit = limit.clamp(1, MAX_EVENTS_PER_FETCH);
+    if offset >= MAX_EVENT_WINDOW {
+        return Err(anyhow!("offset must be below {}", MAX_EVENT_WINDOW));
+    }
+    let window = (offset + limit + 1).min(MAX_EVENT_WINDOW);
+
+    let events = fetch_events_window(database, time_min, time_max, calendar_id, window).await?;
+    let has_more = events.len() > offset + limit;
+    Ok(CalendarEventPage {
+        events: events.into_iter().skip(offset).take(limit).collect(),
+        offset,
+        has_more,
+    })
+}
+
+async fn fetch_events_window(
+    database: &Database,
+    time_min: &str,
+    time_max: &str,
+    calendar_id: Option<&str>,
+    max_results: usize,
+) -> Result<Vec<GoogleCalendarEvent>> {
+    let result = request_events_window(database, time_min, time_max, calendar_id, max_results).await;
+    crate::integrations::google::record_google_result(database, &result);
+    result
+}
+
+async fn request_events_window(
+    database: &Database,
+    time_min: &str,
+    time_max: &str,
+    calendar_id: Option<&str>,
+    max_results: usize,
 ) -> Result<Vec<GoogleCalendarEvent>> {
+    let calendar_id = resolve_calendar_id(database, calendar_id).await;
+
     let mut tokens = {
-        let connection = database.connection.lock();
+        let connection = database.connection.lock()?;
         get_google_tokens(&connection)?
     };
 
@@ -39,50 +272,73 @@ pub async fn fetch_google_calendar_events(
         tokens = refresh_google_tokens(database, &tokens).await?;
     }
 
-    let url = "https://www.googleapis.com/calendar/v3/calendars/primary/events";
+    let url = events_url(&calendar_id);
 
+    let max_results = max_results.to_string();
     let params = [
         ("timeMin", time_min),
         ("timeMax", time_max),
         ("singleEvents", "true"),
         ("orderBy", "startTime"),
+        ("maxResults", max_results.as_str()),
     ];
 
-    let client = reqwest::Client::new();
-    let response = client
-        .get(url)
-        .header(AUTHORIZATION, format!("Bearer {}", tokens.access_token))
-        .query(&params)
-        .send()
-        .await?;
+    let client = crate::integrations::http::shared_client();
+    let mut response = send_with_retry(
+        client
+            .get(&url)
+            .header(AUTHORIZATION, format!("Bearer {}", tokens.access_token))
+            .query(&params),
+    )
+    .await?;
 
     if response.status() == reqwest::StatusCode::UNAUTHORIZED {
         // Try refresh once more even if we thought it was valid
         tokens = refresh_google_tokens(database, &tokens).await?;
-        let response = client
-            .get(url)
-            .header(AUTHORIZATION, format!("Bearer {}", tokens.access_token))
-            .query(&params)
-            .send()
-            .await?;
+        response = send_with_retry(
+            client
+                .get(&url)
+                .header(AUTHORIZATION, format!("Bearer {}", tokens.access_token))
+                .query(&params),
+        )
+        .await?;
+    }
+
+    let data: serde_json::Value = read_json(response, "fetch calendar events").await?;
+    parse_google_events(data)
+}
 
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            return Err(anyhow!(
-                "Google Calendar API error after refresh: {}",
-                error_text
-            ));
+//INFO: Builds an event start/end, pinning it to the user's timezone
+//NOTE: RFC3339 times keep their offset; times without one are read in the user's IANA timezone
+//NOTE: (or the machine's local offset if none is saved) instead of letting Google assume UTC
+pub fn event_time(value: &str, timezone: Option<&str>) -> Result<serde_json::Value> {
+    let value = value.trim();
+    if chrono::DateTime::parse_from_rfc3339(value).is_ok() {
+        let mut time = json!({ "dateTime": value });
+        if let Some(tz) = timezone {
+            time["timeZone"] = json!(tz);
         }
+        return Ok(time);
+    }
 
-        let data: serde_json::Value = response.json().await?;
-        parse_google_events(data)
-    } else {
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            return Err(anyhow!("Google Calendar API error: {}", error_text));
+    let naive = ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|fmt| chrono::NaiveDateTime::parse_from_str(value, fmt).ok())
+        .ok_or_else(|| anyhow!("Invalid event time '{}': expected e.g. 2026-01-20T14:00:00+01:00", value))?;
+
+    match timezone {
+        Some(tz) => Ok(json!({
+            "dateTime": naive.format("%Y-%m-%dT%H:%M:%S").to_string(),
+            "timeZone": tz,
+        })),
+        None => {
+            use chrono::TimeZone;
+            let local = chrono::Local
+                .from_local_datetime(&naive)
+                .earliest()
+                .ok_or_else(|| anyhow!("'{}' doesn't exist in the local timezone", value))?;
+            Ok(json!({ "dateTime": local.to_rfc3339() }))
         }
-        let data: serde_json::Value = response.json().await?;
-        parse_google_events(data)
     }
 }
 
@@ -93,9 +349,12 @@ pub async fn create_calendar_event(
     start_time: &str, // RFC3339
     end_time: &str,   // RFC3339
     location: Option<&str>,
+    calendar_id: Option<&str>,
 ) -> Result<GoogleCalendarEvent> {
+    let calendar_id = resolve_calendar_id(database, calendar_id).await;
+
     let mut tokens = {
-        let connection = database.connection.lock();
+        let connection = database.connection.lock()?;
         get_google_tokens(&connection)?
     };
 
@@ -103,19 +362,24 @@ pub async fn create_calendar_event(
         tokens = refresh_google_tokens(database, &tokens).await?;
     }
 
-    let url = "https://www.googleapis.com/calendar/v3/calendars/primary/events";
+    let url = events_url(&calendar_id);
+
+    let timezone = {
+        let connection = database.connection.lock()?;
+        crate::database::queries::get_user_timezone(&connection)
+    };
 
     let event_body = json!({
         "summary": summary,
         "description": description,
         "location": location,
-        "start": { "dateTime": start_time },
-        "end": { "dateTime": end_time }
+        "start": event_time(start_time, timezone.as_deref())?,
+        "end": event_time(end_time, timezone.as_deref())?
     });
 
-    let client = reqwest::Client::new();
-    let response = client
-        .post(url)
+    let client = crate::integrations::http::shared_client();
+    let mut response = client
+        .post(&url)
         .header(AUTHORIZATION, format!("Bearer {}", tokens.access_token))
         .json(&event_body)
         .send()
@@ -123,31 +387,26 @@ pub async fn create_calendar_event(
 
     if response.status() == reqwest::StatusCode::UNAUTHORIZED {
         tokens = refresh_google_tokens(database, &tokens).await?;
-        let response = client
-            .post(url)
+        response = client
+            .post(&url)
             .header(AUTHORIZATION, format!("Bearer {}", tokens.access_token))
             .json(&event_body)
             .send()
             .await?;
-
-        let event: GoogleCalendarEvent = response.json().await?;
-        Ok(event)
-    } else {
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            return Err(anyhow!("Failed to create calendar event: {}", error_text));
-        }
-        let event: GoogleCalendarEvent = response.json().await?;
-        Ok(event)
     }
+
+    read_json(response, "create calendar event").await
 }
 
 pub async fn delete_calendar_event(
     database: &Database,
     event_id: &str,
+    calendar_id: Option<&str>,
 ) -> Result<()> {
+    let calendar_id = resolve_calendar_id(database, calendar_id).await;
+
     let mut tokens = {
-        let connection = database.connection.lock();
+        let connection = database.connection.lock()?;
         get_google_tokens(&connection)?
     };
 
@@ -155,10 +414,10 @@ pub async fn delete_calendar_event(
         tokens = refresh_google_tokens(database, &tokens).await?;
     }
 
-    let url = format!("https://www.googleapis.com/calendar/v3/calendars/primary/events/{}", event_id);
+    let url = format!("{}/{}", events_url(&calendar_id), event_id);
 
-    let client = reqwest::Client::new();
-    let response = client
+    let client = crate::integrations::http::shared_client();
+    let mut response = client
         .delete(&url)
         .header(AUTHORIZATION, format!("Bearer {}", tokens.access_token))
         .send()
@@ -166,24 +425,15 @@ pub async fn delete_calendar_event(
 
     if response.status() == reqwest::StatusCode::UNAUTHORIZED {
         tokens = refresh_google_tokens(database, &tokens).await?;
-        let response = client
+        response = client
             .delete(&url)
             .header(AUTHORIZATION, format!("Bearer {}", tokens.access_token))
             .send()
             .await?;
-
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            return Err(anyhow!("Failed to delete calendar event after refresh: {}", error_text));
-        }
-        Ok(())
-    } else {
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            return Err(anyhow!("Failed to delete calendar event: {}", error_text));
-        }
-        Ok(())
     }
+
+    ensure_success(response, "delete calendar event").await?;
+    Ok(())
 }
 
 use serde_json::json;
@@ -202,64 +452,23 @@ fn parse_google_events(data: serde_json::Value) -> Result<Vec<GoogleCalendarEven
     Ok(events)
 }
 
-fn get_google_tokens(connection: &rusqlite::Connection) -> Result<GoogleTokens> {
-    let encrypted = get_api_token(connection, "google")?
-        .ok_or_else(|| anyhow!("Google tokens not found. Please connect Google first."))?;
-
-    let decrypted = decrypt_token(&encrypted)?;
-    let tokens: GoogleTokens = serde_json::from_str(&decrypted)?;
-    Ok(tokens)
-}
-
-async fn refresh_google_tokens(
-    database: &Database,
-    current_tokens: &GoogleTokens,
-) -> Result<GoogleTokens> {
-    let (client_id, client_secret, refresh_token) = {
-        let connection = database.connection.lock();
-        let refresh_token = current_tokens
-            .refresh_token
-            .clone()
-            .ok_or_else(|| anyhow!("No refresh token found for Google"))?;
-
-        let integration = get_integration(&connection, "google")?
-            .ok_or_else(|| anyhow!("Google integration config not found"))?;
-
-        let config: serde_json::Value =
-            serde_json::from_str(&integration.config.context("Missing config")?)?;
-        let client_id = config["client_id"]
-            .as_str()
-            .context("Missing client_id")?
-            .to_string();
-        let client_secret = config["client_secret"]
-            .as_str()
-            .context("Missing client_secret")?
-            .to_string();
-        (client_id, client_secret, refresh_token)
-    };
-
-    let auth = GoogleAuth::new(client_id, client_secret);
-    let mut new_tokens = auth.refresh_access_token(refresh_token).await?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    // If the refresh response didn't include a new refresh token, keep the old one
-    if new_tokens.refresh_token.is_none() {
-        new_tokens.refresh_token = current_tokens.refresh_token.clone();
-    }
+    #[test]
+    fn test_event_time_uses_user_timezone() {
+        let with_offset = event_time("2026-01-20T14:00:00+01:00", Some("Africa/Lagos")).unwrap();
+        assert_eq!(with_offset["dateTime"], "2026-01-20T14:00:00+01:00");
+        assert_eq!(with_offset["timeZone"], "Africa/Lagos");
 
-    // Save back to DB
-    {
-        let connection = database.connection.lock();
-        let tokens_json = serde_json::to_string(&new_tokens)?;
-        let encrypted = encrypt_token(&tokens_json)?;
-        save_api_token(&connection, "google", &encrypted, "oauth2")?;
-    }
+        let naive = event_time("2026-01-20 14:00", Some("Europe/Berlin")).unwrap();
+        assert_eq!(naive["dateTime"], "2026-01-20T14:00:00");
+        assert_eq!(naive["timeZone"], "Europe/Berlin");
 
-    Ok(new_tokens)
-}
+        let local = event_time("2026-01-20T14:00", None).unwrap();
+        assert!(chrono::DateTime::parse_from_rfc3339(local["dateTime"].as_str().unwrap()).is_ok());
 
-fn is_expired(tokens: &GoogleTokens) -> bool {
-    match tokens.expires_at {
-        Some(expiry) => chrono::Utc::now() + chrono::Duration::minutes(5) >= expiry,
-        None => true,
+        assert!(event_time("tomorrow at 2", None).is_err());
     }
 }
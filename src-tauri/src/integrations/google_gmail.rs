@@ -1,12 +1,32 @@
 // src-tauri/src/integrations/google_gmail.rs
-use crate::crypto::{decrypt_token, encrypt_token};
-use crate::database::queries::{get_api_token, get_integration, save_api_token};
+use crate::database::queries::{get_http_concurrency, get_setting, mark_integration_needs_reauth, save_setting};
 use crate::database::Database;
-use crate::oauth::google::{GoogleAuth, GoogleTokens};
+use crate::integrations::google::{
+    get_google_tokens, is_expired, record_google_result, refresh_google_tokens,
+};
+use crate::integrations::http::{ensure_success, read_json, send_with_retry};
+use crate::oauth::google::GoogleTokens;
 use anyhow::{anyhow, Context, Result};
+use futures::{stream, StreamExt, TryStreamExt};
 use reqwest::header::AUTHORIZATION;
 use serde::{Deserialize, Serialize};
 
+const GMAIL_API_BASE: &str = "https://gmail.googleapis.com/gmail/v1/users/me";
+const USERINFO_URL: &str = "https://www.googleapis.com/oauth2/v2/userinfo";
+
+//INFO: Settings key for the signed-in Google account's address, used to open Gmail links in that account
+pub const GOOGLE_ACCOUNT_SETTING: &str = "google_account_email";
+
+//INFO: Error for label changes the current sign-in isn't allowed to make
+const MISSING_MODIFY_SCOPE: &str =
+    "insufficient_scope: Google didn't grant permission to change Gmail labels. Reconnect Google in Integrations to allow it.";
+
+//INFO: Most messages one list call will fetch details for, whatever the caller asks for
+const MAX_DETAIL_FETCHES: u32 = 25;
+
+//INFO: The list views only need these headers, so details are fetched in the lighter metadata format
+const DETAIL_QUERY: &str = "format=metadata&metadataHeaders=Subject&metadataHeaders=From&metadataHeaders=Date";
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GmailMessage {
     pub id: String,
@@ -17,56 +37,252 @@ pub struct GmailMessage {
     pub date: Option<String>,
 }
 
-pub async fn send_email(database: &Database, to: &str, subject: &str, body: &str) -> Result<()> {
-    let mut tokens = {
-        let connection = database.connection.lock();
-        get_google_tokens(&connection)?
+//INFO: An email to send or save as a draft
+#[derive(Debug, Default, Clone)]
+pub struct OutgoingEmail {
+    pub to: String,
+    pub cc: Option<String>,
+    pub bcc: Option<String>,
+    pub subject: String,
+    pub body: String,
+    //INFO: When true, `body` is HTML and a plain-text alternative is generated from it
+    pub html: bool,
+    //INFO: Threading details, only set on replies (see `prepare_reply`)
+    pub in_reply_to: Option<String>,
+    pub references: Option<String>,
+    pub thread_id: Option<String>,
+}
+
+pub async fn send_email(database: &Database, email: &OutgoingEmail) -> Result<()> {
+    let payload = message_payload(email);
+
+    post_gmail_json(database, &format!("{}/messages/send", GMAIL_API_BASE), &payload)
+        .await
+        .context("Failed to send email")?;
+
+    Ok(())
+}
+
+//INFO: A draft saved in the user's Gmail drafts folder
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GmailDraft {
+    pub id: String,
+    pub message_id: String,
+    pub link: String,
+}
+
+//INFO: Gmail web link to `fragment` (e.g. "all/<id>") in the connected account
+//NOTE: `authuser` picks the account by address; a fixed /u/0/ would open whichever account the
+//      browser signed into first. Without a known address Gmail falls back to its default account.
+pub fn gmail_web_url(connection: &rusqlite::Connection, fragment: &str) -> String {
+    match get_setting(connection, GOOGLE_ACCOUNT_SETTING).ok().flatten() {
+        Some(account) => format!(
+            "https://mail.google.com/mail/?authuser={}#{}",
+            urlencoding::encode(&account),
+            fragment
+        ),
+        None => format!("https://mail.google.com/mail/#{}", fragment),
+    }
+}
+
+//INFO: Saves an email as a Gmail draft instead of sending it, so the user can review it first
+pub async fn create_email_draft(database: &Database, email: &OutgoingEmail) -> Result<GmailDraft> {
+    let payload = serde_json::json!({ "message": message_payload(email) });
+
+    let data = post_gmail_json(database, &format!("{}/drafts", GMAIL_API_BASE), &payload)
+        .await
+        .context("Failed to create draft")?;
+
+    let id = data["id"]
+        .as_str()
+        .ok_or_else(|| anyhow!("Draft response had no id"))?
+        .to_string();
+    let message_id = data["message"]["id"].as_str().unwrap_or_default().to_string();
+
+    let link = {
+        let connection = database.connection.lock()?;
+        gmail_web_url(&connection, &format!("drafts?compose={}", message_id))
     };
 
-    if is_expired(&tokens) {
-        tokens = refresh_google_tokens(database, &tokens).await?;
+    Ok(GmailDraft {
+        link,
+        id,
+        message_id,
+    })
+}
+
+//INFO: Builds a reply to `message_id` without sending it, so callers can send it or save it as a draft
+//NOTE: Gmail only threads a reply if threadId is set AND the Subject/References headers match up
+pub async fn prepare_reply(database: &Database, message_id: &str, body: &str) -> Result<OutgoingEmail> {
+    let original = fetch_full_message(database, message_id)
+        .await
+        .context("Failed to fetch the email being replied to")?;
+    build_reply(&original, body)
+}
+
+fn build_reply(original: &serde_json::Value, body: &str) -> Result<OutgoingEmail> {
+    let header = |name: &str| find_header(&original["payload"], name);
+
+    let to = header("Reply-To")
+        .or_else(|| header("From"))
+        .ok_or_else(|| anyhow!("The original email has no sender to reply to"))?;
+    let original_id = header("Message-ID");
+    let references = match (header("References"), &original_id) {
+        (Some(refs), Some(id)) => Some(format!("{} {}", refs, id)),
+        (None, Some(id)) => Some(id.clone()),
+        (refs, None) => refs,
+    };
+
+    Ok(OutgoingEmail {
+        to,
+        subject: reply_subject(&header("Subject").unwrap_or_default()),
+        body: body.to_string(),
+        in_reply_to: original_id,
+        references,
+        thread_id: original["threadId"].as_str().map(String::from),
+        ..Default::default()
+    })
+}
+
+//INFO: Header lookup on a message payload; header names are case-insensitive (Message-ID vs Message-Id)
+fn find_header(payload: &serde_json::Value, name: &str) -> Option<String> {
+    payload["headers"]
+        .as_array()?
+        .iter()
+        .find(|h| h["name"].as_str().is_some_and(|n| n.eq_ignore_ascii_case(name)))
+        .and_then(|h| h["value"].as_str())
+        .map(String::from)
+}
+
+//INFO: Prefixes "Re: " unless the subject already is a reply
+fn reply_subject(subject: &str) -> String {
+    let subject = subject.trim();
+    if subject.get(..3).is_some_and(|prefix| prefix.eq_ignore_ascii_case("re:")) {
+        subject.to_string()
+    } else {
+        format!("Re: {}", subject)
     }
+}
 
-    let url = "https://gmail.googleapis.com/gmail/v1/users/me/messages/send";
+//INFO: The Gmail message resource for an outgoing email (raw plus the thread, if replying)
+fn message_payload(email: &OutgoingEmail) -> serde_json::Value {
+    let mut payload = serde_json::json!({ "raw": encode_raw_message(&build_raw_message(email)) });
+    if let Some(thread_id) = &email.thread_id {
+        payload["threadId"] = serde_json::json!(thread_id);
+    }
+    payload
+}
 
-    // Build raw email (simplified RFC 822)
-    let email_raw = format!(
-        "To: {}\r\nSubject: {}\r\nContent-Type: text/plain; charset=\"UTF-8\"\r\n\r\n{}",
-        to, subject, body
-    );
+//INFO: Builds an RFC 822 / MIME message
+//NOTE: Bodies are base64 transfer-encoded so non-ASCII text survives; HTML mail is sent as
+//      multipart/alternative with a plain-text part for clients that don't render HTML
+fn build_raw_message(email: &OutgoingEmail) -> String {
+    let mut headers = vec![format!("To: {}", email.to)];
+    if let Some(cc) = email.cc.as_deref().filter(|c| !c.trim().is_empty()) {
+        headers.push(format!("Cc: {}", cc));
+    }
+    if let Some(bcc) = email.bcc.as_deref().filter(|b| !b.trim().is_empty()) {
+        headers.push(format!("Bcc: {}", bcc));
+    }
+    headers.push(format!("Subject: {}", encode_header_value(&email.subject)));
+    if let Some(in_reply_to) = &email.in_reply_to {
+        headers.push(format!("In-Reply-To: {}", in_reply_to));
+    }
+    if let Some(references) = &email.references {
+        headers.push(format!("References: {}", references));
+    }
+    headers.push("MIME-Version: 1.0".to_string());
+
+    let body = if email.html {
+        let boundary = format!("lumen-{}", uuid::Uuid::new_v4().simple());
+        headers.push(format!(
+            "Content-Type: multipart/alternative; boundary=\"{}\"",
+            boundary
+        ));
+        format!(
+            "--{b}\r\n{plain}\r\n--{b}\r\n{html}\r\n--{b}--\r\n",
+            b = boundary,
+            plain = mime_text_part("text/plain", &strip_html(&email.body)),
+            html = mime_text_part("text/html", &email.body),
+        )
+    } else {
+        let part = mime_text_part("text/plain", &email.body);
+        let (part_headers, part_body) = part.split_once("\r\n\r\n").unwrap_or_default();
+        headers.extend(part_headers.split("\r\n").map(String::from));
+        part_body.to_string()
+    };
 
-    // Base64Url encode it
-    let encoded = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(email_raw);
+    format!("{}\r\n\r\n{}", headers.join("\r\n"), body)
+}
 
-    let payload = serde_json::json!({
-        "raw": encoded
-    });
+//INFO: One UTF-8 text part: its headers, a blank line, then the base64 body wrapped at 76 chars
+fn mime_text_part(content_type: &str, text: &str) -> String {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(text);
+    let wrapped = encoded
+        .as_bytes()
+        .chunks(76)
+        .map(|chunk| String::from_utf8_lossy(chunk).into_owned())
+        .collect::<Vec<_>>()
+        .join("\r\n");
+    format!(
+        "Content-Type: {}; charset=\"UTF-8\"\r\nContent-Transfer-Encoding: base64\r\n\r\n{}",
+        content_type, wrapped
+    )
+}
+
+//INFO: RFC 2047-encodes a header value if it has anything other than printable ASCII
+fn encode_header_value(value: &str) -> String {
+    if value.chars().all(|c| c.is_ascii() && !c.is_ascii_control()) {
+        value.to_string()
+    } else {
+        format!(
+            "=?UTF-8?B?{}?=",
+            base64::engine::general_purpose::STANDARD.encode(value)
+        )
+    }
+}
+
+//INFO: Gmail wants the raw message base64url-encoded without padding
+fn encode_raw_message(raw: &str) -> String {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(raw)
+}
 
-    let client = reqwest::Client::new();
-    let response = client
+//INFO: POSTs JSON to the Gmail API with the usual refresh-and-retry-once on 401
+async fn post_gmail_json(
+    database: &Database,
+    url: &str,
+    payload: &serde_json::Value,
+) -> Result<serde_json::Value> {
+    let mut tokens = {
+        let connection = database.connection.lock()?;
+        get_google_tokens(&connection)?
+    };
+
+    if is_expired(&tokens) {
+        tokens = refresh_google_tokens(database, &tokens).await?;
+    }
+
+    let client = crate::integrations::http::shared_client();
+    let mut response = client
         .post(url)
         .header(AUTHORIZATION, format!("Bearer {}", tokens.access_token))
-        .json(&payload)
+        .json(payload)
         .send()
         .await?;
 
     if response.status() == reqwest::StatusCode::UNAUTHORIZED {
         tokens = refresh_google_tokens(database, &tokens).await?;
-        let response = client
+        response = client
             .post(url)
             .header(AUTHORIZATION, format!("Bearer {}", tokens.access_token))
-            .json(&payload)
+            .json(payload)
             .send()
             .await?;
-
-        if !response.status().is_success() {
-            return Err(anyhow!("Failed to send email: {}", response.text().await?));
-        }
-    } else if !response.status().is_success() {
-        return Err(anyhow!("Failed to send email: {}", response.text().await?));
     }
 
-    Ok(())
+    //NOTE: Some endpoints answer with an empty body, which isn't an error
+    let response = ensure_success(response, "send Gmail request").await?;
+    Ok(response.json().await.unwrap_or(serde_json::Value::Null))
 }
 
 use base64::Engine;
@@ -83,9 +299,20 @@ pub async fn fetch_recent_emails_with_query(
     max_results: u32,
     query: Option<&str>,
 ) -> Result<Vec<GmailMessage>> {
-    let mut tokens = {
-        let connection = database.connection.lock();
-        get_google_tokens(&connection)?
+    let result = request_recent_emails(database, max_results, query).await;
+    record_google_result(database, &result);
+    result
+}
+
+async fn request_recent_emails(
+    database: &Database,
+    max_results: u32,
+    query: Option<&str>,
+) -> Result<Vec<GmailMessage>> {
+    let max_results = max_results.clamp(1, MAX_DETAIL_FETCHES);
+    let (mut tokens, concurrency) = {
+        let connection = database.connection.lock()?;
+        (get_google_tokens(&connection)?, get_http_concurrency(&connection))
     };
 
     // Check if expired and refresh if needed
@@ -93,7 +320,7 @@ pub async fn fetch_recent_emails_with_query(
         tokens = refresh_google_tokens(database, &tokens).await?;
     }
 
-    let client = reqwest::Client::new();
+    let client = crate::integrations::http::shared_client();
 
     // Build query - default to unread inbox, but allow custom queries
     let q = query.unwrap_or("is:unread inbox");
@@ -101,15 +328,16 @@ pub async fn fetch_recent_emails_with_query(
 
     // 1. Get list of message IDs
     let list_url = format!(
-        "https://gmail.googleapis.com/gmail/v1/users/me/messages?maxResults={}&q={}",
-        max_results, encoded_q
+        "{}/messages?maxResults={}&q={}",
+        GMAIL_API_BASE, max_results, encoded_q
     );
 
-    let list_response = client
-        .get(&list_url)
-        .header(AUTHORIZATION, format!("Bearer {}", tokens.access_token))
-        .send()
-        .await?;
+    let list_response = send_with_retry(
+        client
+            .get(&list_url)
+            .header(AUTHORIZATION, format!("Bearer {}", tokens.access_token)),
+    )
+    .await?;
 
     if list_response.status() == reqwest::StatusCode::UNAUTHORIZED {
         // Try refresh once more
@@ -123,202 +351,560 @@ pub async fn fetch_recent_emails_with_query(
         .await;
     }
 
-    let list_data: serde_json::Value = list_response.json().await?;
-    let message_summaries = list_data["messages"].as_array();
+    let list_data: serde_json::Value = read_json(list_response, "list emails").await?;
+    let ids = parse_message_ids(&list_data)?;
+
+    // 2. Fetch full message details concurrently
+    fetch_message_details(client, GMAIL_API_BASE, &tokens.access_token, &ids, concurrency).await
+}
 
-    if message_summaries.is_none() {
+// Helper to avoid recursive async issues
+async fn fetch_recent_emails_with_tokens(
+    database: &Database,
+    tokens: &GoogleTokens,
+    max_results: u32,
+    query: Option<&str>,
+) -> Result<Vec<GmailMessage>> {
+    let max_results = max_results.clamp(1, MAX_DETAIL_FETCHES);
+    let client = crate::integrations::http::shared_client();
+    let q = query.unwrap_or("is:unread inbox");
+    let encoded_q = urlencoding::encode(q);
+
+    let list_url = format!(
+        "{}/messages?maxResults={}&q={}",
+        GMAIL_API_BASE, max_results, encoded_q
+    );
+
+    let list_response = send_with_retry(
+        client
+            .get(&list_url)
+            .header(AUTHORIZATION, format!("Bearer {}", tokens.access_token)),
+    )
+    .await?;
+
+    let list_data: serde_json::Value = read_json(list_response, "list emails").await?;
+    let ids = parse_message_ids(&list_data)?;
+    let concurrency = get_http_concurrency(&database.connection.lock()?);
+
+    fetch_message_details(client, GMAIL_API_BASE, &tokens.access_token, &ids, concurrency).await
+}
+
+//INFO: Ids of the messages matching `query`, without fetching any message details
+//NOTE: One request instead of one per message, for callers that only need to notice new mail
+pub async fn list_message_ids(database: &Database, max_results: u32, query: &str) -> Result<Vec<String>> {
+    let result = request_message_ids(database, max_results, query).await;
+    record_google_result(database, &result);
+    result
+}
+
+async fn request_message_ids(database: &Database, max_results: u32, query: &str) -> Result<Vec<String>> {
+    let mut tokens = {
+        let connection = database.connection.lock()?;
+        get_google_tokens(&connection)?
+    };
+    if is_expired(&tokens) {
+        tokens = refresh_google_tokens(database, &tokens).await?;
+    }
+
+    let client = crate::integrations::http::shared_client();
+    let list_url = format!(
+        "{}/messages?maxResults={}&q={}",
+        GMAIL_API_BASE,
+        max_results.max(1),
+        urlencoding::encode(query)
+    );
+    let list = |access_token: &str| {
+        send_with_retry(client.get(&list_url).header(AUTHORIZATION, format!("Bearer {}", access_token)))
+    };
+
+    let mut list_response = list(&tokens.access_token).await?;
+    if list_response.status() == reqwest::StatusCode::UNAUTHORIZED {
+        tokens = refresh_google_tokens(database, &tokens).await?;
+        list_response = list(&tokens.access_token).await?;
+    }
+
+    let list_data: serde_json::Value = read_json(list_response, "list emails").await?;
+    parse_message_ids(&list_data)
+}
+
+//INFO: Pulls the message ids out of a messages.list response
+fn parse_message_ids(list_data: &serde_json::Value) -> Result<Vec<String>> {
+    let Some(message_summaries) = list_data["messages"].as_array() else {
         return Ok(Vec::new());
+    };
+
+    message_summaries
+        .iter()
+        .map(|msg_ref| {
+            msg_ref["id"]
+                .as_str()
+                .map(|id| id.to_string())
+                .ok_or_else(|| anyhow!("Missing message id"))
+        })
+        .collect()
+}
+
+//INFO: Fetches message details for each id, at most `concurrency` at a time
+//NOTE: `buffered` (not `buffer_unordered`) keeps results in the same order as `ids`
+async fn fetch_message_details(
+    client: &reqwest::Client,
+    base_url: &str,
+    access_token: &str,
+    ids: &[String],
+    concurrency: usize,
+) -> Result<Vec<GmailMessage>> {
+    stream::iter(ids)
+        .map(|id| async move {
+            let detail_url = format!("{}/messages/{}?{}", base_url, id, DETAIL_QUERY);
+            let response = send_with_retry(
+                client
+                    .get(&detail_url)
+                    .header(AUTHORIZATION, format!("Bearer {}", access_token)),
+            )
+            .await?;
+            let detail_data: serde_json::Value = read_json(response, "fetch email details").await?;
+            Ok::<_, anyhow::Error>(parse_message(id, &detail_data))
+        })
+        .buffered(concurrency)
+        .try_collect()
+        .await
+}
+
+//INFO: Builds a GmailMessage from a messages.get response
+fn parse_message(id: &str, detail_data: &serde_json::Value) -> GmailMessage {
+    let mut subject = None;
+    let mut from = None;
+    let mut date = None;
+
+    if let Some(headers) = detail_data["payload"]["headers"].as_array() {
+        for header in headers {
+            match header["name"].as_str() {
+                Some("Subject") => subject = header["value"].as_str().map(|s| s.to_string()),
+                Some("From") => from = header["value"].as_str().map(|s| s.to_string()),
+                Some("Date") => date = header["value"].as_str().map(|s| s.to_string()),
+                _ => {}
+            }
+        }
     }
 
-    let mut emails = Vec::new();
-    for msg_ref in message_summaries.unwrap() {
-        let id = msg_ref["id"]
+    GmailMessage {
+        id: id.to_string(),
+        thread_id: detail_data["threadId"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        snippet: detail_data["snippet"]
             .as_str()
-            .ok_or_else(|| anyhow!("Missing message id"))?;
+            .unwrap_or_default()
+            .to_string(),
+        subject,
+        from,
+        date,
+    }
+}
 
-        // 2. Fetch full message details
-        let detail_url = format!(
-            "https://gmail.googleapis.com/gmail/v1/users/me/messages/{}",
-            id
-        );
-        let detail_response = client
-            .get(&detail_url)
+//INFO: Adds/removes labels on a message via messages/{id}/modify (e.g. drop UNREAD or INBOX)
+pub async fn modify_email_labels(
+    database: &Database,
+    message_id: &str,
+    add_labels: &[&str],
+    remove_labels: &[&str],
+) -> Result<()> {
+    let mut tokens = {
+        let connection = database.connection.lock()?;
+        get_google_tokens(&connection)?
+    };
+
+    if is_expired(&tokens) {
+        tokens = refresh_google_tokens(database, &tokens).await?;
+    }
+
+    let client = crate::integrations::http::shared_client();
+    let url = format!("{}/messages/{}/modify", GMAIL_API_BASE, message_id);
+    let payload = serde_json::json!({
+        "addLabelIds": add_labels,
+        "removeLabelIds": remove_labels,
+    });
+
+    let mut response = client
+        .post(&url)
+        .header(AUTHORIZATION, format!("Bearer {}", tokens.access_token))
+        .json(&payload)
+        .send()
+        .await?;
+
+    if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+        tokens = refresh_google_tokens(database, &tokens).await?;
+        response = client
+            .post(&url)
             .header(AUTHORIZATION, format!("Bearer {}", tokens.access_token))
+            .json(&payload)
             .send()
             .await?;
+    }
+
+    //INFO: Accounts connected before gmail.modify was requested can read but not relabel mail
+    if is_insufficient_scope(&response) {
+        let connection = database.connection.lock()?;
+        mark_integration_needs_reauth(&connection, "google", MISSING_MODIFY_SCOPE)?;
+        return Err(anyhow!(MISSING_MODIFY_SCOPE));
+    }
 
-        let detail_data: serde_json::Value = detail_response.json().await?;
+    ensure_success(response, "modify email labels").await?;
+    Ok(())
+}
 
-        let mut subject = None;
-        let mut from = None;
-        let mut date = None;
+//INFO: Google answers a token missing a scope with 403 and `error="insufficient_scope"` in WWW-Authenticate
+fn is_insufficient_scope(response: &reqwest::Response) -> bool {
+    response.status() == reqwest::StatusCode::FORBIDDEN
+        && response
+            .headers()
+            .get(reqwest::header::WWW_AUTHENTICATE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.contains("insufficient_scope"))
+}
 
-        if let Some(headers) = detail_data["payload"]["headers"].as_array() {
-            for header in headers {
-                match header["name"].as_str() {
-                    Some("Subject") => subject = header["value"].as_str().map(|s| s.to_string()),
-                    Some("From") => from = header["value"].as_str().map(|s| s.to_string()),
-                    Some("Date") => date = header["value"].as_str().map(|s| s.to_string()),
-                    _ => {}
-                }
-            }
-        }
+//INFO: A message with its full decoded body, for reading/summarizing on demand
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GmailMessageDetails {
+    #[serde(flatten)]
+    pub message: GmailMessage,
+    pub body: String,
+}
 
-        emails.push(GmailMessage {
-            id: id.to_string(),
-            thread_id: detail_data["threadId"]
-                .as_str()
-                .unwrap_or_default()
-                .to_string(),
-            snippet: detail_data["snippet"]
-                .as_str()
-                .unwrap_or_default()
-                .to_string(),
-            subject,
-            from,
-            date,
-        });
+//INFO: Fetches a single message in full format, refreshing tokens if needed
+async fn fetch_full_message(database: &Database, message_id: &str) -> Result<serde_json::Value> {
+    let mut tokens = {
+        let connection = database.connection.lock()?;
+        get_google_tokens(&connection)?
+    };
+
+    if is_expired(&tokens) {
+        tokens = refresh_google_tokens(database, &tokens).await?;
+    }
+
+    let client = crate::integrations::http::shared_client();
+    let url = format!("{}/messages/{}?format=full", GMAIL_API_BASE, message_id);
+
+    let mut response = send_with_retry(
+        client
+            .get(&url)
+            .header(AUTHORIZATION, format!("Bearer {}", tokens.access_token)),
+    )
+    .await?;
+
+    if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+        tokens = refresh_google_tokens(database, &tokens).await?;
+        response = send_with_retry(
+            client
+                .get(&url)
+                .header(AUTHORIZATION, format!("Bearer {}", tokens.access_token)),
+        )
+        .await?;
     }
 
-    Ok(emails)
+    read_json(response, "fetch email").await
 }
 
-// Helper to avoid recursive async issues
-async fn fetch_recent_emails_with_tokens(
-    _database: &Database,
-    tokens: &GoogleTokens,
-    max_results: u32,
-    query: Option<&str>,
-) -> Result<Vec<GmailMessage>> {
-    let client = reqwest::Client::new();
-    let q = query.unwrap_or("is:unread inbox");
-    let encoded_q = urlencoding::encode(q);
+//INFO: Confirms the stored Google tokens still work by fetching the account's email address
+//NOTE: Used by the integration health check; userinfo is the cheapest authenticated call we have scope for
+pub async fn verify_google_connection(database: &Database) -> Result<String> {
+    let mut tokens = {
+        let connection = database.connection.lock()?;
+        get_google_tokens(&connection)?
+    };
 
-    let list_url = format!(
-        "https://gmail.googleapis.com/gmail/v1/users/me/messages?maxResults={}&q={}",
-        max_results, encoded_q
-    );
+    if is_expired(&tokens) {
+        tokens = refresh_google_tokens(database, &tokens).await?;
+    }
 
-    let list_response = client
-        .get(&list_url)
+    let client = crate::integrations::http::shared_client();
+    let mut response = client
+        .get(USERINFO_URL)
         .header(AUTHORIZATION, format!("Bearer {}", tokens.access_token))
         .send()
         .await?;
 
-    let list_data: serde_json::Value = list_response.json().await?;
-    let message_summaries = list_data["messages"].as_array();
-
-    if message_summaries.is_none() {
-        return Ok(Vec::new());
-    }
-
-    let mut emails = Vec::new();
-    for msg_ref in message_summaries.unwrap() {
-        let id = msg_ref["id"]
-            .as_str()
-            .ok_or_else(|| anyhow!("Missing message id"))?;
-        let detail_url = format!(
-            "https://gmail.googleapis.com/gmail/v1/users/me/messages/{}",
-            id
-        );
-        let detail_response = client
-            .get(&detail_url)
+    if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+        tokens = refresh_google_tokens(database, &tokens).await?;
+        response = client
+            .get(USERINFO_URL)
             .header(AUTHORIZATION, format!("Bearer {}", tokens.access_token))
             .send()
             .await?;
+    }
 
-        let detail_data: serde_json::Value = detail_response.json().await?;
+    let info: serde_json::Value = read_json(response, "verify Google token").await?;
+    match info["email"].as_str() {
+        Some(email) => {
+            //INFO: Remembered so Gmail links open in this account
+            let connection = database.connection.lock()?;
+            save_setting(&connection, GOOGLE_ACCOUNT_SETTING, email)?;
+            Ok(email.to_string())
+        }
+        None => Ok("unknown account".to_string()),
+    }
+}
 
-        let mut subject = None;
-        let mut from = None;
-        let mut date = None;
+//INFO: Gets the full body of an email as plain text
+pub async fn get_email_body(database: &Database, message_id: &str) -> Result<String> {
+    let detail_data = fetch_full_message(database, message_id).await?;
+    Ok(extract_body(&detail_data["payload"]).unwrap_or_default())
+}
 
-        if let Some(headers) = detail_data["payload"]["headers"].as_array() {
-            for header in headers {
-                match header["name"].as_str() {
-                    Some("Subject") => subject = header["value"].as_str().map(|s| s.to_string()),
-                    Some("From") => from = header["value"].as_str().map(|s| s.to_string()),
-                    Some("Date") => date = header["value"].as_str().map(|s| s.to_string()),
-                    _ => {}
-                }
-            }
-        }
+//INFO: Gets an email's headers, snippet and full body
+pub async fn get_email_details(database: &Database, message_id: &str) -> Result<GmailMessageDetails> {
+    let detail_data = fetch_full_message(database, message_id).await?;
+    Ok(GmailMessageDetails {
+        message: parse_message(message_id, &detail_data),
+        body: extract_body(&detail_data["payload"]).unwrap_or_default(),
+    })
+}
 
-        emails.push(GmailMessage {
-            id: id.to_string(),
-            thread_id: detail_data["threadId"]
-                .as_str()
-                .unwrap_or_default()
-                .to_string(),
-            snippet: detail_data["snippet"]
-                .as_str()
-                .unwrap_or_default()
-                .to_string(),
-            subject,
-            from,
-            date,
-        });
+//INFO: Walks the MIME tree for the body, preferring text/plain and falling back to stripped text/html
+fn extract_body(payload: &serde_json::Value) -> Option<String> {
+    find_part_data(payload, "text/plain")
+        .or_else(|| find_part_data(payload, "text/html").map(|html| strip_html(&html)))
+        .map(|body| body.trim().to_string())
+        .filter(|body| !body.is_empty())
+}
+
+//INFO: Depth-first search for the first part of the given mime type that has data
+fn find_part_data(part: &serde_json::Value, mime_type: &str) -> Option<String> {
+    if part["mimeType"].as_str() == Some(mime_type) {
+        if let Some(decoded) = part["body"]["data"].as_str().and_then(decode_base64url) {
+            return Some(decoded);
+        }
     }
 
-    Ok(emails)
+    part["parts"]
+        .as_array()?
+        .iter()
+        .find_map(|child| find_part_data(child, mime_type))
 }
 
-fn get_google_tokens(connection: &rusqlite::Connection) -> Result<GoogleTokens> {
-    let encrypted =
-        get_api_token(connection, "google")?.ok_or_else(|| anyhow!("Google tokens not found"))?;
+//NOTE: Gmail uses base64url, sometimes with padding, so strip it before decoding
+fn decode_base64url(data: &str) -> Option<String> {
+    let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(data.trim_end_matches('='))
+        .ok()?;
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
 
-    let decrypted = decrypt_token(&encrypted)?;
-    let tokens: GoogleTokens = serde_json::from_str(&decrypted)?;
-    Ok(tokens)
+//INFO: Crude HTML to text conversion, good enough for the model to read an email
+fn strip_html(html: &str) -> String {
+    let without_blocks = regex::Regex::new(r"(?is)<(script|style|head)[^>]*>.*?</(script|style|head)>")
+        .unwrap()
+        .replace_all(html, "");
+    let with_breaks = regex::Regex::new(r"(?i)<br\s*/?>|</(p|div|tr|li|h[1-6])>")
+        .unwrap()
+        .replace_all(&without_blocks, "\n");
+    let text = regex::Regex::new(r"<[^>]+>")
+        .unwrap()
+        .replace_all(&with_breaks, "");
+
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+
+    regex::Regex::new(r"\n\s*\n+")
+        .unwrap()
+        .replace_all(&text, "\n\n")
+        .to_string()
 }
 
-async fn refresh_google_tokens(
-    database: &Database,
-    current_tokens: &GoogleTokens,
-) -> Result<GoogleTokens> {
-    let (client_id, client_secret, refresh_token) = {
-        let connection = database.connection.lock();
-        let refresh_token = current_tokens
-            .refresh_token
-            .clone()
-            .ok_or_else(|| anyhow!("No refresh token found for Google"))?;
-
-        let integration = get_integration(&connection, "google")?
-            .ok_or_else(|| anyhow!("Google integration config not found"))?;
-
-        let config: serde_json::Value =
-            serde_json::from_str(&integration.config.context("Missing config")?)?;
-        let client_id = config["client_id"]
-            .as_str()
-            .context("Missing client_id")?
-            .to_string();
-        let client_secret = config["client_secret"]
-            .as_str()
-            .context("Missing client_secret")?
-            .to_string();
-        (client_id, client_secret, refresh_token)
-    };
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::queries::DEFAULT_HTTP_CONCURRENCY;
+    use std::time::{Duration, Instant};
+
+    const MOCK_LATENCY: Duration = Duration::from_millis(300);
+
+    //INFO: Spins up a local Gmail stand-in that answers every request after MOCK_LATENCY
+    fn start_mock_gmail() -> String {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let port = server.server_addr().to_ip().unwrap().port();
+
+        std::thread::spawn(move || {
+            for request in server.incoming_requests() {
+                std::thread::spawn(move || {
+                    std::thread::sleep(MOCK_LATENCY);
+                    let path = request.url().split('?').next().unwrap_or_default();
+                    let id = path.rsplit('/').next().unwrap_or_default().to_string();
+                    let body = serde_json::json!({
+                        "id": id,
+                        "threadId": format!("thread-{}", id),
+                        "snippet": format!("snippet {}", id),
+                        "payload": { "headers": [{ "name": "Subject", "value": format!("Subject {}", id) }] }
+                    });
+                    let _ = request.respond(tiny_http::Response::from_string(body.to_string()));
+                });
+            }
+        });
 
-    let auth = GoogleAuth::new(client_id, client_secret);
-    let mut new_tokens = auth.refresh_access_token(refresh_token).await?;
+        format!("http://127.0.0.1:{}", port)
+    }
 
-    if new_tokens.refresh_token.is_none() {
-        new_tokens.refresh_token = current_tokens.refresh_token.clone();
+    #[test]
+    fn test_gmail_web_url_opens_the_connected_account() {
+        let connection = rusqlite::Connection::open_in_memory().unwrap();
+        crate::database::schema::initialize_database(&connection).unwrap();
+        assert_eq!(gmail_web_url(&connection, "all/abc"), "https://mail.google.com/mail/#all/abc");
+
+        save_setting(&connection, GOOGLE_ACCOUNT_SETTING, "me+work@example.com").unwrap();
+        assert_eq!(
+            gmail_web_url(&connection, "all/abc"),
+            "https://mail.google.com/mail/?authuser=me%2Bwork%40example.com#all/abc"
+        );
     }
 
-    {
-        let connection = database.connection.lock();
-        let tokens_json = serde_json::to_string(&new_tokens)?;
-        let encrypted = encrypt_token(&tokens_json)?;
-        save_api_token(&connection, "google", &encrypted, "oauth2")?;
+    #[test]
+    fn test_extract_body_from_nested_multipart() {
+        let encode = |s: &str| base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(s);
+        let payload = serde_json::json!({
+            "mimeType": "multipart/mixed",
+            "parts": [
+                {
+                    "mimeType": "multipart/alternative",
+                    "parts": [
+                        { "mimeType": "text/html", "body": { "data": encode("<p>Hello <b>there</b></p>") } },
+                        { "mimeType": "text/plain", "body": { "data": encode("Hello there") } }
+                    ]
+                },
+                { "mimeType": "application/pdf", "body": { "attachmentId": "abc" } }
+            ]
+        });
+        assert_eq!(extract_body(&payload).as_deref(), Some("Hello there"));
+
+        let html_only = serde_json::json!({
+            "mimeType": "text/html",
+            "body": { "data": encode("<style>p{}</style><p>Hi &amp; bye</p>") }
+        });
+        assert_eq!(extract_body(&html_only).as_deref(), Some("Hi & bye"));
+
+        assert_eq!(extract_body(&serde_json::json!({ "mimeType": "text/plain", "body": {} })), None);
     }
 
-    Ok(new_tokens)
-}
+    #[tokio::test]
+    async fn test_message_details_fetch_concurrently_in_order() {
+        let base_url = start_mock_gmail();
+        let client = reqwest::Client::new();
+        let ids: Vec<String> = (0..DEFAULT_HTTP_CONCURRENCY).map(|i| format!("msg{}", i)).collect();
+
+        let started = Instant::now();
+        let emails = fetch_message_details(&client, &base_url, "token", &ids, DEFAULT_HTTP_CONCURRENCY)
+            .await
+            .unwrap();
+        let elapsed = started.elapsed();
+
+        //INFO: Sequential fetching would take N * MOCK_LATENCY
+        assert!(elapsed < MOCK_LATENCY * 2, "took {:?}", elapsed);
+        assert_eq!(
+            emails.iter().map(|e| e.id.clone()).collect::<Vec<_>>(),
+            ids
+        );
+        assert_eq!(emails[0].subject.as_deref(), Some("Subject msg0"));
+    }
+
+    //INFO: Splits a raw message into (headers, body), unfolding nothing - our headers are single-line
+    fn parse_raw(raw: &str) -> (Vec<(String, String)>, String) {
+        let (head, body) = raw.split_once("\r\n\r\n").unwrap();
+        let headers = head
+            .split("\r\n")
+            .map(|line| {
+                let (name, value) = line.split_once(": ").unwrap();
+                (name.to_string(), value.to_string())
+            })
+            .collect();
+        (headers, body.to_string())
+    }
+
+    fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+        headers.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    #[test]
+    fn test_build_raw_message_headers_and_encoding() {
+        let email = OutgoingEmail {
+            to: "ada@example.com".to_string(),
+            cc: Some("bob@example.com".to_string()),
+            bcc: Some("carol@example.com".to_string()),
+            subject: "Café plans ☕".to_string(),
+            body: "See you at 9 — ok?".to_string(),
+            ..Default::default()
+        };
+        let (headers, body) = parse_raw(&build_raw_message(&email));
+
+        assert_eq!(header(&headers, "To"), Some("ada@example.com"));
+        assert_eq!(header(&headers, "Cc"), Some("bob@example.com"));
+        assert_eq!(header(&headers, "Bcc"), Some("carol@example.com"));
+        let subject = header(&headers, "Subject").unwrap();
+        let encoded = subject.strip_prefix("=?UTF-8?B?").unwrap().strip_suffix("?=").unwrap();
+        let decoded = base64::engine::general_purpose::STANDARD.decode(encoded).unwrap();
+        assert_eq!(String::from_utf8(decoded).unwrap(), "Café plans ☕");
+
+        assert_eq!(header(&headers, "Content-Transfer-Encoding"), Some("base64"));
+        let decoded = base64::engine::general_purpose::STANDARD
+            .decode(body.replace("\r\n", ""))
+            .unwrap();
+        assert_eq!(String::from_utf8(decoded).unwrap(), "See you at 9 — ok?");
+    }
+
+    #[test]
+    fn test_build_raw_message_html_is_multipart_alternative() {
+        let email = OutgoingEmail {
+            to: "ada@example.com".to_string(),
+            subject: "Plain subject".to_string(),
+            body: "<p>Hello <b>there</b></p>".to_string(),
+            html: true,
+            ..Default::default()
+        };
+        let raw = build_raw_message(&email);
+        let (headers, body) = parse_raw(&raw);
+
+        assert_eq!(header(&headers, "Subject"), Some("Plain subject"));
+        assert_eq!(header(&headers, "Cc"), None);
+        let content_type = header(&headers, "Content-Type").unwrap();
+        assert!(content_type.starts_with("multipart/alternative; boundary="));
+        let boundary = content_type.split("boundary=\"").nth(1).unwrap().trim_end_matches('"');
+
+        assert!(body.contains("Content-Type: text/plain"));
+        assert!(body.contains("Content-Type: text/html"));
+        assert!(body.trim_end().ends_with(&format!("--{}--", boundary)));
+    }
+
+    #[test]
+    fn test_build_reply_threads_onto_original() {
+        let original = serde_json::json!({
+            "id": "m2",
+            "threadId": "t1",
+            "payload": {
+                "headers": [
+                    { "name": "From", "value": "Ada <ada@example.com>" },
+                    { "name": "Subject", "value": "Lunch?" },
+                    { "name": "Message-Id", "value": "<m2@mail.example.com>" },
+                    { "name": "References", "value": "<m1@mail.example.com>" }
+                ]
+            }
+        });
+
+        let reply = build_reply(&original, "Sounds good").unwrap();
+        assert_eq!(reply.to, "Ada <ada@example.com>");
+        assert_eq!(reply.subject, "Re: Lunch?");
+        assert_eq!(reply.in_reply_to.as_deref(), Some("<m2@mail.example.com>"));
+        assert_eq!(
+            reply.references.as_deref(),
+            Some("<m1@mail.example.com> <m2@mail.example.com>")
+        );
+        assert_eq!(message_payload(&reply)["threadId"], "t1");
 
-fn is_expired(tokens: &GoogleTokens) -> bool {
-    match tokens.expires_at {
-        Some(expiry) => chrono::Utc::now() + chrono::Duration::minutes(5) >= expiry,
-        None => true,
+        let (headers, _) = parse_raw(&build_raw_message(&reply));
+        assert_eq!(header(&headers, "In-Reply-To"), Some("<m2@mail.example.com>"));
+        assert_eq!(reply_subject("RE: Lunch?"), "RE: Lunch?");
     }
 }
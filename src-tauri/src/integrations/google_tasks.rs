@@ -1,13 +1,18 @@
 // src-tauri/src/integrations/google_tasks.rs
-use crate::crypto::{decrypt_token, encrypt_token};
-use crate::database::queries::{get_api_token, get_integration, save_api_token};
+use crate::database::queries::{delete_setting, get_setting, save_setting};
 use crate::database::Database;
-use crate::oauth::google::{GoogleAuth, GoogleTokens};
-use anyhow::{anyhow, Context, Result};
+use crate::integrations::google::{get_google_tokens, is_expired, refresh_google_tokens};
+use crate::integrations::http::{ensure_success, read_json, send_with_retry};
+use anyhow::{anyhow, Result};
 use reqwest::header::AUTHORIZATION;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
+const TASKS_API_BASE: &str = "https://tasks.googleapis.com/tasks/v1";
+
+//INFO: Settings key for the cached default tasklist id
+const DEFAULT_TASKLIST_SETTING: &str = "google_tasks_default_list_id";
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GoogleTask {
     pub id: String,
@@ -18,8 +23,14 @@ pub struct GoogleTask {
 }
 
 pub async fn list_tasks(database: &Database, max_results: u32) -> Result<Vec<GoogleTask>> {
+    let result = request_tasks(database, max_results).await;
+    crate::integrations::google::record_google_result(database, &result);
+    result
+}
+
+async fn request_tasks(database: &Database, max_results: u32) -> Result<Vec<GoogleTask>> {
     let mut tokens = {
-        let connection = database.connection.lock();
+        let connection = database.connection.lock()?;
         get_google_tokens(&connection)?
     };
 
@@ -27,34 +38,23 @@ pub async fn list_tasks(database: &Database, max_results: u32) -> Result<Vec<Goo
         tokens = refresh_google_tokens(database, &tokens).await?;
     }
 
-    let client = reqwest::Client::new();
+    let client = crate::integrations::http::shared_client();
 
-    // 1. Get default tasklist ID
-    let list_url = "https://tasks.googleapis.com/tasks/v1/users/@me/lists";
-    let list_response = client
-        .get(list_url)
-        .header(AUTHORIZATION, format!("Bearer {}", tokens.access_token))
-        .send()
-        .await?;
-
-    let lists_data: serde_json::Value = list_response.json().await?;
-    let tasklist_id = lists_data["items"][0]["id"]
-        .as_str()
-        .ok_or_else(|| anyhow!("No tasklists found"))?;
-
-    // 2. Fetch tasks from the first list
-    let tasks_url = format!(
-        "https://tasks.googleapis.com/tasks/v1/lists/{}/tasks?maxResults={}&showCompleted=false",
-        tasklist_id, max_results
-    );
+    // Fetch pending tasks from the default list
+    let build = |tasklist_id: &str| {
+        client.get(format!(
+            "{}/lists/{}/tasks?maxResults={}&showCompleted=false",
+            TASKS_API_BASE, tasklist_id, max_results
+        ))
+    };
 
-    let tasks_response = client
-        .get(&tasks_url)
-        .header(AUTHORIZATION, format!("Bearer {}", tokens.access_token))
-        .send()
-        .await?;
+    let mut tasks_response = send_tasklist_request(database, &tokens.access_token, build).await?;
+    if tasks_response.status() == reqwest::StatusCode::UNAUTHORIZED {
+        tokens = refresh_google_tokens(database, &tokens).await?;
+        tasks_response = send_tasklist_request(database, &tokens.access_token, build).await?;
+    }
 
-    let tasks_data: serde_json::Value = tasks_response.json().await?;
+    let tasks_data: serde_json::Value = read_json(tasks_response, "list tasks").await?;
     let items = tasks_data["items"].as_array();
 
     if items.is_none() {
@@ -77,7 +77,7 @@ pub async fn create_task(
     due: Option<&str>,
 ) -> Result<GoogleTask> {
     let mut tokens = {
-        let connection = database.connection.lock();
+        let connection = database.connection.lock()?;
         get_google_tokens(&connection)?
     };
 
@@ -85,101 +85,146 @@ pub async fn create_task(
         tokens = refresh_google_tokens(database, &tokens).await?;
     }
 
-    let client = reqwest::Client::new();
-
-    // Get default tasklist
-    let list_url = "https://tasks.googleapis.com/tasks/v1/users/@me/lists";
-    let list_response = client
-        .get(list_url)
-        .header(AUTHORIZATION, format!("Bearer {}", tokens.access_token))
-        .send()
-        .await?;
-    let lists_data: serde_json::Value = list_response.json().await?;
-    let tasklist_id = lists_data["items"][0]["id"]
-        .as_str()
-        .ok_or_else(|| anyhow!("No tasklists found"))?;
-
-    let url = format!(
-        "https://tasks.googleapis.com/tasks/v1/lists/{}/tasks",
-        tasklist_id
-    );
+    let client = crate::integrations::http::shared_client();
     let body = json!({
         "title": title,
         "notes": notes,
         "due": due
     });
 
-    let response = client
-        .post(&url)
-        .header(AUTHORIZATION, format!("Bearer {}", tokens.access_token))
-        .json(&body)
-        .send()
-        .await?;
+    let build = |tasklist_id: &str| {
+        client
+            .post(format!("{}/lists/{}/tasks", TASKS_API_BASE, tasklist_id))
+            .json(&body)
+    };
 
-    if !response.status().is_success() {
-        return Err(anyhow!("Failed to create task: {}", response.text().await?));
+    let mut response = send_tasklist_request(database, &tokens.access_token, build).await?;
+    if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+        tokens = refresh_google_tokens(database, &tokens).await?;
+        response = send_tasklist_request(database, &tokens.access_token, build).await?;
     }
 
-    let task: GoogleTask = response.json().await?;
-    Ok(task)
+    read_json(response, "create task").await
 }
 
-fn get_google_tokens(connection: &rusqlite::Connection) -> Result<GoogleTokens> {
-    let encrypted =
-        get_api_token(connection, "google")?.ok_or_else(|| anyhow!("Google tokens not found"))?;
+//INFO: Marks a task in the default list as completed and returns its updated state
+pub async fn complete_task(database: &Database, task_id: &str) -> Result<GoogleTask> {
+    let mut tokens = {
+        let connection = database.connection.lock()?;
+        get_google_tokens(&connection)?
+    };
+
+    if is_expired(&tokens) {
+        tokens = refresh_google_tokens(database, &tokens).await?;
+    }
+
+    let client = crate::integrations::http::shared_client();
+    let body = json!({ "status": "completed" });
 
-    let decrypted = decrypt_token(&encrypted)?;
-    let tokens: GoogleTokens = serde_json::from_str(&decrypted)?;
-    Ok(tokens)
+    let build = |tasklist_id: &str| {
+        client
+            .patch(format!("{}/lists/{}/tasks/{}", TASKS_API_BASE, tasklist_id, task_id))
+            .json(&body)
+    };
+
+    let mut response = send_tasklist_request(database, &tokens.access_token, build).await?;
+    if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+        tokens = refresh_google_tokens(database, &tokens).await?;
+        response = send_tasklist_request(database, &tokens.access_token, build).await?;
+    }
+
+    read_json(response, "complete task").await
 }
 
-async fn refresh_google_tokens(
-    database: &Database,
-    current_tokens: &GoogleTokens,
-) -> Result<GoogleTokens> {
-    let (client_id, client_secret, refresh_token) = {
-        let connection = database.connection.lock();
-        let refresh_token = current_tokens
-            .refresh_token
-            .clone()
-            .ok_or_else(|| anyhow!("No refresh token found for Google"))?;
-
-        let integration = get_integration(&connection, "google")?
-            .ok_or_else(|| anyhow!("Google integration config not found"))?;
-
-        let config: serde_json::Value =
-            serde_json::from_str(&integration.config.context("Missing config")?)?;
-        let client_id = config["client_id"]
-            .as_str()
-            .context("Missing client_id")?
-            .to_string();
-        let client_secret = config["client_secret"]
-            .as_str()
-            .context("Missing client_secret")?
-            .to_string();
-        (client_id, client_secret, refresh_token)
+//INFO: Deletes a task from the default list
+pub async fn delete_task(database: &Database, task_id: &str) -> Result<()> {
+    let mut tokens = {
+        let connection = database.connection.lock()?;
+        get_google_tokens(&connection)?
     };
 
-    let auth = GoogleAuth::new(client_id, client_secret);
-    let mut new_tokens = auth.refresh_access_token(refresh_token).await?;
+    if is_expired(&tokens) {
+        tokens = refresh_google_tokens(database, &tokens).await?;
+    }
+
+    let client = crate::integrations::http::shared_client();
 
-    if new_tokens.refresh_token.is_none() {
-        new_tokens.refresh_token = current_tokens.refresh_token.clone();
+    let build = |tasklist_id: &str| {
+        client.delete(format!("{}/lists/{}/tasks/{}", TASKS_API_BASE, tasklist_id, task_id))
+    };
+
+    let mut response = send_tasklist_request(database, &tokens.access_token, build).await?;
+    if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+        tokens = refresh_google_tokens(database, &tokens).await?;
+        response = send_tasklist_request(database, &tokens.access_token, build).await?;
     }
 
-    {
-        let connection = database.connection.lock();
-        let tokens_json = serde_json::to_string(&new_tokens)?;
-        let encrypted = encrypt_token(&tokens_json)?;
-        save_api_token(&connection, "google", &encrypted, "oauth2")?;
+    ensure_success(response, "delete task").await?;
+    Ok(())
+}
+
+//INFO: Sends a request against the default tasklist, resolving its id from the settings cache
+//NOTE: A 404 with a cached id means the list is gone, so the cache is dropped and the request retried once
+async fn send_tasklist_request<F>(
+    database: &Database,
+    access_token: &str,
+    build: F,
+) -> Result<reqwest::Response>
+where
+    F: Fn(&str) -> reqwest::RequestBuilder,
+{
+    let cached = {
+        let connection = database.connection.lock()?;
+        get_setting(&connection, DEFAULT_TASKLIST_SETTING)?
+    };
+    let was_cached = cached.is_some();
+
+    let tasklist_id = match cached {
+        Some(id) => id,
+        None => resolve_default_tasklist_id(database, access_token).await?,
+    };
+
+    let response = build(&tasklist_id)
+        .header(AUTHORIZATION, format!("Bearer {}", access_token))
+        .send()
+        .await?;
+
+    if was_cached && response.status() == reqwest::StatusCode::NOT_FOUND {
+        {
+            let connection = database.connection.lock()?;
+            delete_setting(&connection, DEFAULT_TASKLIST_SETTING)?;
+        }
+        let tasklist_id = resolve_default_tasklist_id(database, access_token).await?;
+        return Ok(build(&tasklist_id)
+            .header(AUTHORIZATION, format!("Bearer {}", access_token))
+            .send()
+            .await?);
     }
 
-    Ok(new_tokens)
+    Ok(response)
 }
 
-fn is_expired(tokens: &GoogleTokens) -> bool {
-    match tokens.expires_at {
-        Some(expiry) => chrono::Utc::now() + chrono::Duration::minutes(5) >= expiry,
-        None => true,
+//INFO: Looks up the id of the user's first (default) task list and caches it in settings
+async fn resolve_default_tasklist_id(database: &Database, access_token: &str) -> Result<String> {
+    let client = crate::integrations::http::shared_client();
+    let list_response = send_with_retry(
+        client
+            .get(format!("{}/users/@me/lists", TASKS_API_BASE))
+            .header(AUTHORIZATION, format!("Bearer {}", access_token)),
+    )
+    .await?;
+
+    let lists_data: serde_json::Value = read_json(list_response, "list tasklists").await?;
+    let tasklist_id = lists_data["items"][0]["id"]
+        .as_str()
+        .ok_or_else(|| anyhow!("No tasklists found"))?
+        .to_string();
+
+    {
+        let connection = database.connection.lock()?;
+        save_setting(&connection, DEFAULT_TASKLIST_SETTING, &tasklist_id)?;
     }
+
+    Ok(tasklist_id)
 }
+
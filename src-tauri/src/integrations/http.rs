@@ -0,0 +1,170 @@
+//INFO: Shared HTTP client for the Google integrations, TTS and other one-off requests
+//NOTE: Building a reqwest::Client sets up a fresh connection pool and TLS config, so making one per call
+//      throws away keep-alive connections. The dashboard refresh fires many back-to-back Google requests,
+//      which all reuse this pool. Requests that need a different limit override it with `.timeout()`.
+
+use crate::error::RateLimitExceeded;
+use anyhow::{anyhow, Result};
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+const POOL_MAX_IDLE_PER_HOST: usize = 8;
+
+//INFO: Pause before retrying a request that hit a transient server error
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+//INFO: How much of an unexpected response body ends up in the error message
+const ERROR_BODY_CHARS: usize = 200;
+
+static SHARED_CLIENT: OnceLock<Client> = OnceLock::new();
+
+//INFO: The process-wide client; cheap to call, the pool is built on first use
+pub fn shared_client() -> &'static Client {
+    SHARED_CLIENT.get_or_init(|| {
+        Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(REQUEST_TIMEOUT)
+            .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+            .pool_max_idle_per_host(POOL_MAX_IDLE_PER_HOST)
+            .build()
+            .unwrap_or_else(|_| Client::new())
+    })
+}
+
+//INFO: Sends a request, retrying once after a short pause on a 5xx or 429
+//NOTE: Only for idempotent requests (GETs); bodies that can't be cloned (streams) are sent once
+pub async fn send_with_retry(request: RequestBuilder) -> reqwest::Result<Response> {
+    let Some(retry) = request.try_clone() else {
+        return request.send().await;
+    };
+
+    let response = request.send().await?;
+    let status = response.status();
+    if !(status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS) {
+        return Ok(response);
+    }
+
+    tracing::warn!("🔁 {} returned {}, retrying once", response.url().host_str().unwrap_or("request"), status);
+    tokio::time::sleep(RETRY_DELAY).await;
+    retry.send().await
+}
+
+//INFO: Fails with the HTTP status and a snippet of the body unless the response is a 2xx
+//NOTE: `action` describes what was being done ("list calendars"), and leads the error message
+pub async fn ensure_success(response: Response, action: &str) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(status_error(action, status, &body))
+}
+
+//INFO: Reads a JSON body, with readable errors for non-2xx statuses and bodies that aren't valid JSON
+//NOTE: Google sometimes answers with an HTML error page (proxies, outages), which `response.json()`
+//      would only report as an opaque serde error
+pub async fn read_json<T: DeserializeOwned>(response: Response, action: &str) -> Result<T> {
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(|e| anyhow!("Failed to {}: couldn't read response (HTTP {}): {}", action, status.as_u16(), e))?;
+    parse_json_body(action, status, &body)
+}
+
+fn parse_json_body<T: DeserializeOwned>(action: &str, status: StatusCode, body: &str) -> Result<T> {
+    if !status.is_success() {
+        return Err(status_error(action, status, body));
+    }
+    serde_json::from_str(body).map_err(|e| {
+        anyhow!(
+            "Failed to {}: malformed JSON response (HTTP {}): {} [{}]",
+            action,
+            status.as_u16(),
+            e,
+            excerpt(body)
+        )
+    })
+}
+
+fn status_error(action: &str, status: StatusCode, body: &str) -> anyhow::Error {
+    let message = format!("Failed to {} (HTTP {}): {}", action, status.as_u16(), excerpt(body));
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        return RateLimitExceeded(message).into();
+    }
+    anyhow!(message)
+}
+
+//INFO: First ERROR_BODY_CHARS of a body with whitespace collapsed, so HTML pages stay on one line
+fn excerpt(body: &str) -> String {
+    let collapsed = body.split_whitespace().collect::<Vec<_>>().join(" ");
+    if collapsed.is_empty() {
+        return "(empty body)".to_string();
+    }
+    match collapsed.char_indices().nth(ERROR_BODY_CHARS) {
+        Some((end, _)) => format!("{}…", &collapsed[..end]),
+        None => collapsed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    const ERROR_PAGE: &str = "<html>\n  <body><h1>500 Internal Server Error</h1></body>\n</html>";
+
+    //INFO: Local server that answers the first `failures` requests with a 500 HTML page, then with JSON
+    fn start_flaky_server(failures: usize) -> (String, Arc<AtomicUsize>) {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let port = server.server_addr().to_ip().unwrap().port();
+        let hits = Arc::new(AtomicUsize::new(0));
+
+        let counter = hits.clone();
+        std::thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let response = if counter.fetch_add(1, Ordering::SeqCst) < failures {
+                    tiny_http::Response::from_string(ERROR_PAGE).with_status_code(500)
+                } else {
+                    tiny_http::Response::from_string(r#"{"items": []}"#)
+                };
+                let _ = request.respond(response);
+            }
+        });
+
+        (format!("http://127.0.0.1:{}", port), hits)
+    }
+
+    #[tokio::test]
+    async fn test_read_json_reports_status_for_html_error_page() {
+        let (url, _) = start_flaky_server(usize::MAX);
+        let response = Client::new().get(&url).send().await.unwrap();
+
+        let error = read_json::<serde_json::Value>(response, "list events").await.unwrap_err().to_string();
+        assert!(error.starts_with("Failed to list events (HTTP 500): <html> <body>"), "{}", error);
+    }
+
+    #[test]
+    fn test_parse_json_body_rejects_html_with_success_status() {
+        let error = parse_json_body::<serde_json::Value>("list tasks", StatusCode::OK, ERROR_PAGE)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("malformed JSON response (HTTP 200)"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_send_with_retry_recovers_from_one_server_error() {
+        let (url, hits) = start_flaky_server(1);
+        let response = send_with_retry(Client::new().get(&url)).await.unwrap();
+
+        let data: serde_json::Value = read_json(response, "list events").await.unwrap();
+        assert_eq!(data["items"], serde_json::json!([]));
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+}
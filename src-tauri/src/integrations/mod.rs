@@ -1,4 +1,9 @@
 pub mod gemini_tts;
+pub mod google;
 pub mod google_calendar;
 pub mod google_gmail;
 pub mod google_tasks;
+pub mod http;
+pub mod obsidian;
+pub mod vault_index;
+pub mod web;
@@ -0,0 +1,1168 @@
+// src-tauri/src/integrations/obsidian.rs
+//INFO: Shared Obsidian vault helpers (daily-note resolution, Moment.js date formats)
+//NOTE: Used by chat context, the dashboard briefing and the Obsidian tools so they all agree on paths
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate};
+use serde::Serialize;
+use std::fs;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+
+const DEFAULT_DAILY_NOTES_FORMAT: &str = "YYYY-MM-DD";
+
+//INFO: Stands in for an ordinal suffix ("st", "nd", ...) that chrono can't produce itself
+const ORDINAL_MARKER: char = '\u{1}';
+
+//INFO: Moment.js tokens we understand and their chrono equivalents
+//NOTE: Matched in order, so longer tokens must come before their prefixes (MMMM before MM, Do before D)
+const MOMENT_TOKENS: &[(&str, &str)] = &[
+    ("YYYY", "%Y"),
+    ("YY", "%y"),
+    ("MMMM", "%B"),
+    ("MMM", "%b"),
+    ("MM", "%m"),
+    ("M", "%-m"),
+    ("dddd", "%A"),
+    ("ddd", "%a"),
+    ("DD", "%d"),
+    ("Do", "%-d\u{1}"),
+    ("D", "%-d"),
+];
+
+//INFO: Obsidian integration settings, parsed from the integration's JSON config
+#[derive(Debug, Clone)]
+pub struct ObsidianConfig {
+    pub vault_path: PathBuf,
+    pub daily_notes_folder: String,
+    pub daily_notes_format: String,
+    //INFO: Heading `quick_capture` writes under; empty means the end of the note
+    pub quick_capture_heading: String,
+}
+
+impl ObsidianConfig {
+    //INFO: Reads the config saved by the settings UI; None if there is no vault path
+    pub fn from_json(config: &serde_json::Value) -> Option<Self> {
+        let vault_path = config.get("vault_path").and_then(|v| v.as_str())?;
+        if vault_path.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            vault_path: PathBuf::from(vault_path),
+            daily_notes_folder: config
+                .get("daily_notes_path")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string(),
+            daily_notes_format: config
+                .get("daily_notes_format")
+                .and_then(|v| v.as_str())
+                .filter(|f| !f.is_empty())
+                .unwrap_or(DEFAULT_DAILY_NOTES_FORMAT)
+                .to_string(),
+            quick_capture_heading: config
+                .get("quick_capture_heading")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .trim()
+                .to_string(),
+        })
+    }
+
+    //INFO: Loads the config of the Obsidian integration; None if it's missing, disabled or has no vault
+    pub fn from_database(connection: &rusqlite::Connection) -> Result<Option<Self>> {
+        let integration = crate::database::queries::get_integration(connection, "obsidian")?;
+        Ok(integration
+            .filter(|i| i.enabled)
+            .and_then(|i| i.config)
+            .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+            .and_then(|c| Self::from_json(&c)))
+    }
+}
+
+//INFO: A daily note found on disk
+#[derive(Debug, Clone)]
+pub struct DailyNote {
+    pub date: NaiveDate,
+    pub path: PathBuf,
+    pub content: String,
+}
+
+impl DailyNote {
+    //INFO: The note's file name, e.g. "2025-01-31.md"
+    pub fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+}
+
+//INFO: Converts a Moment.js format string (what Obsidian stores) into a chrono format string
+//NOTE: Text inside [brackets] is literal in Moment; literal `%` is escaped so chrono doesn't read it.
+//      `Do` leaves an ORDINAL_MARKER after the day, which format_moment_date replaces with the suffix.
+pub fn moment_to_chrono(format: &str) -> String {
+    let mut output = String::new();
+    let mut rest = format;
+
+    while let Some(c) = rest.chars().next() {
+        if c == '[' {
+            if let Some(end) = rest.find(']') {
+                output.push_str(&rest[1..end].replace('%', "%%"));
+                rest = &rest[end + 1..];
+                continue;
+            }
+        }
+
+        if let Some((token, chrono)) = MOMENT_TOKENS.iter().find(|(t, _)| rest.starts_with(t)) {
+            output.push_str(chrono);
+            rest = &rest[token.len()..];
+            continue;
+        }
+
+        if c == '%' {
+            output.push_str("%%");
+        } else {
+            output.push(c);
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+
+    output
+}
+
+//INFO: Formats a date with a Moment.js format string
+pub fn format_moment_date(format: &str, date: NaiveDate) -> String {
+    date.format(&moment_to_chrono(format))
+        .to_string()
+        .replace(ORDINAL_MARKER, ordinal_suffix(date.day()))
+}
+
+//INFO: English ordinal suffix for a day of the month (1st, 2nd, 3rd, 11th, 22nd...)
+fn ordinal_suffix(day: u32) -> &'static str {
+    match (day % 10, day % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    }
+}
+
+//INFO: Path where the daily note for `date` lives (whether or not it exists yet)
+pub fn resolve_daily_note_path(config: &ObsidianConfig, date: NaiveDate) -> PathBuf {
+    let file_name = format!("{}.md", format_moment_date(&config.daily_notes_format, date));
+    Path::new(&config.vault_path)
+        .join(&config.daily_notes_folder)
+        .join(file_name)
+}
+
+//INFO: Reads the daily note for `date`, if it exists
+pub fn read_daily_note(config: &ObsidianConfig, date: NaiveDate) -> Option<DailyNote> {
+    let path = resolve_daily_note_path(config, date);
+    let content = fs::read_to_string(&path).ok()?;
+    Some(DailyNote {
+        date,
+        path,
+        content,
+    })
+}
+
+//INFO: Finds the newest daily note, starting at `today` and looking back up to `max_lookback` days
+pub fn find_recent_daily_note(
+    config: &ObsidianConfig,
+    today: NaiveDate,
+    max_lookback: i64,
+) -> Option<DailyNote> {
+    (0..=max_lookback).find_map(|days| read_daily_note(config, today - Duration::days(days)))
+}
+
+//INFO: A note to be created by `create_note`
+#[derive(Debug, Default)]
+pub struct NewNote<'a> {
+    pub title: &'a str,
+    //INFO: Folder relative to the vault root; the root itself when None
+    pub folder: Option<&'a str>,
+    //INFO: Template note relative to the vault root; supports {{title}}, {{date}} and {{time}}
+    pub template: Option<&'a str>,
+    //INFO: Body text, appended after the template (if any)
+    pub content: Option<&'a str>,
+    pub tags: Vec<String>,
+    pub frontmatter: bool,
+}
+
+//INFO: Joins a vault-relative path onto the vault root, refusing anything that would escape it
+pub fn resolve_in_vault(config: &ObsidianConfig, relative: &str) -> Result<PathBuf> {
+    let relative = Path::new(relative.trim());
+    if relative
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(anyhow!(
+            "'{}' must be a path inside the vault, relative to its root",
+            relative.display()
+        ));
+    }
+    Ok(config.vault_path.join(relative))
+}
+
+//INFO: Turns a note title into a safe, lowercase, dash-separated file stem
+pub fn slugify_title(title: &str) -> String {
+    let mut slug = String::new();
+    for c in title.trim().chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+//INFO: Builds the text of a new note: optional frontmatter, then the template, then the content
+fn render_note(note: &NewNote, template: Option<&str>, now: DateTime<Local>) -> String {
+    let mut output = String::new();
+
+    if note.frontmatter || !note.tags.is_empty() {
+        output.push_str("---\n");
+        output.push_str(&format!("created: {}\n", now.format("%Y-%m-%dT%H:%M")));
+        if !note.tags.is_empty() {
+            output.push_str("tags:\n");
+            for tag in &note.tags {
+                output.push_str(&format!("  - {}\n", tag.trim().trim_start_matches('#')));
+            }
+        }
+        output.push_str("---\n");
+    }
+
+    let body = [
+        template.map(|t| {
+            t.replace("{{title}}", note.title.trim())
+                .replace("{{date}}", &now.format("%Y-%m-%d").to_string())
+                .replace("{{time}}", &now.format("%H:%M").to_string())
+        }),
+        note.content.map(str::to_string),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|part| !part.trim().is_empty())
+    .collect::<Vec<_>>()
+    .join("\n\n");
+
+    output.push_str(&body);
+    if !output.is_empty() && !output.ends_with('\n') {
+        output.push('\n');
+    }
+    output
+}
+
+//INFO: Where `create_note` would put a note, plus the text it would write
+pub fn plan_note(config: &ObsidianConfig, note: &NewNote) -> Result<(PathBuf, String)> {
+    let slug = slugify_title(note.title);
+    if slug.is_empty() {
+        return Err(anyhow!("Note title must contain at least one letter or digit"));
+    }
+
+    let folder = resolve_in_vault(config, note.folder.unwrap_or(""))?;
+    let path = folder.join(format!("{}.md", slug));
+
+    let template = match note.template {
+        Some(template) if !template.trim().is_empty() => {
+            let mut template_path = resolve_in_vault(config, template)?;
+            if template_path.extension().is_none() {
+                template_path.set_extension("md");
+            }
+            Some(fs::read_to_string(&template_path).with_context(|| {
+                format!("Failed to read template {}", template_path.display())
+            })?)
+        }
+        _ => None,
+    };
+
+    Ok((path, render_note(note, template.as_deref(), Local::now())))
+}
+
+//INFO: Creates a new note in the vault and returns its absolute path
+//NOTE: Never overwrites - fails if a note with the same file name already exists
+pub fn create_note(config: &ObsidianConfig, note: &NewNote) -> Result<PathBuf> {
+    let (path, text) = plan_note(config, note)?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create folder {}", parent.display()))?;
+    }
+
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::AlreadyExists => {
+                anyhow!("A note already exists at {}", path.display())
+            }
+            _ => anyhow!("Failed to create {}: {}", path.display(), e),
+        })?;
+    file.write_all(text.as_bytes())
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    Ok(path)
+}
+
+//INFO: Checks that an absolute path points inside the vault (after resolving symlinks and `..`)
+//NOTE: The file itself may not exist yet, so its parent folder is what gets canonicalized
+pub fn ensure_in_vault(config: &ObsidianConfig, path: &Path) -> Result<PathBuf> {
+    let vault = config
+        .vault_path
+        .canonicalize()
+        .with_context(|| format!("Vault not found at {}", config.vault_path.display()))?;
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("'{}' is not a file path", path.display()))?;
+    let parent = path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .ok_or_else(|| anyhow!("'{}' must be an absolute path", path.display()))?
+        .canonicalize()
+        .with_context(|| format!("Folder for {} does not exist", path.display()))?;
+
+    if !parent.starts_with(&vault) {
+        return Err(anyhow!("{} is outside the Obsidian vault", path.display()));
+    }
+    Ok(parent.join(file_name))
+}
+
+//INFO: Resolves an existing file or folder (absolute or vault-relative) and checks it is inside the vault
+//NOTE: Like `ensure_in_vault`, but for paths that must already exist, including the vault root itself.
+//      The whole path is canonicalized, so a symlink pointing out of the vault is refused too.
+pub fn existing_in_vault(config: &ObsidianConfig, path: &str) -> Result<PathBuf> {
+    let vault = config
+        .vault_path
+        .canonicalize()
+        .with_context(|| format!("Vault not found at {}", config.vault_path.display()))?;
+    let resolved = vault_file_path(config, path)?;
+    let resolved = resolved
+        .canonicalize()
+        .with_context(|| format!("{} does not exist", resolved.display()))?;
+    if !resolved.starts_with(&vault) {
+        return Err(anyhow!("{} is outside the Obsidian vault", resolved.display()));
+    }
+    Ok(resolved)
+}
+
+//INFO: Limits for `recent_notes`, so a huge vault can't stall the chat loop
+const RECENT_NOTES_MAX_DEPTH: usize = 8;
+const RECENT_NOTES_MAX_SCANNED: usize = 5000;
+pub const RECENT_NOTES_MAX_LIMIT: usize = 50;
+
+//INFO: A markdown note and when it was last modified
+#[derive(Debug, Clone, Serialize)]
+pub struct RecentNote {
+    pub path: String,
+    pub modified: String,
+}
+
+//INFO: The most recently modified markdown notes under a vault folder, newest first
+//NOTE: Skips dot folders (.obsidian, .trash, .git) and stops after RECENT_NOTES_MAX_SCANNED files;
+//      the bool is true when the walk was cut short
+pub fn recent_notes(config: &ObsidianConfig, root: &str, limit: usize) -> Result<(Vec<RecentNote>, bool)> {
+    let root = resolve_in_vault(config, root)?;
+    if !root.is_dir() {
+        return Err(anyhow!("Folder {} does not exist in the vault", root.display()));
+    }
+
+    let mut scanned = 0;
+    let mut truncated = false;
+    let mut notes: Vec<(std::time::SystemTime, PathBuf)> = Vec::new();
+    for entry in walkdir::WalkDir::new(&root)
+        .max_depth(RECENT_NOTES_MAX_DEPTH)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| e.ok())
+    {
+        if !entry.file_type().is_file() || entry.path().extension().map_or(true, |ext| ext != "md") {
+            continue;
+        }
+        scanned += 1;
+        if scanned > RECENT_NOTES_MAX_SCANNED {
+            truncated = true;
+            break;
+        }
+        if let Ok(modified) = entry.metadata().and_then(|m| m.modified()) {
+            notes.push((modified, entry.into_path()));
+        }
+    }
+
+    notes.sort_by(|a, b| b.0.cmp(&a.0));
+    let notes = notes
+        .into_iter()
+        .take(limit.clamp(1, RECENT_NOTES_MAX_LIMIT))
+        .map(|(modified, path)| RecentNote {
+            path: path
+                .strip_prefix(&config.vault_path)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .into_owned(),
+            modified: DateTime::<Local>::from(modified).to_rfc3339(),
+        })
+        .collect();
+    Ok((notes, truncated))
+}
+
+//INFO: Resolves a tool path that may be absolute or relative to the vault root
+pub fn vault_file_path(config: &ObsidianConfig, path: &str) -> Result<PathBuf> {
+    let path = path.trim();
+    if Path::new(path).is_absolute() {
+        Ok(PathBuf::from(path))
+    } else {
+        resolve_in_vault(config, path)
+    }
+}
+
+//INFO: Checks that the folders above `path` would land inside the vault, without creating anything
+fn check_parent_in_vault(config: &ObsidianConfig, path: &Path) -> Result<()> {
+    if path.components().any(|c| matches!(c, Component::ParentDir)) {
+        return Err(anyhow!("'{}' must not contain '..'", path.display()));
+    }
+    let parent = path
+        .parent()
+        .ok_or_else(|| anyhow!("'{}' is not a file path", path.display()))?;
+    let vault = config
+        .vault_path
+        .canonicalize()
+        .with_context(|| format!("Vault not found at {}", config.vault_path.display()))?;
+    let existing = parent
+        .ancestors()
+        .find(|a| a.is_dir())
+        .ok_or_else(|| anyhow!("No folder of {} exists", path.display()))?
+        .canonicalize()?;
+    if !existing.starts_with(&vault) {
+        return Err(anyhow!("{} is outside the Obsidian vault", path.display()));
+    }
+    Ok(())
+}
+
+//INFO: Creates the missing folders above `path`, returns the topmost folder it created (if any)
+fn create_parent_in_vault(config: &ObsidianConfig, path: &Path) -> Result<Option<PathBuf>> {
+    check_parent_in_vault(config, path)?;
+    let parent = path
+        .parent()
+        .ok_or_else(|| anyhow!("'{}' is not a file path", path.display()))?;
+    let first_created = parent
+        .ancestors()
+        .take_while(|a| !a.is_dir())
+        .last()
+        .map(Path::to_path_buf);
+    fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+    Ok(first_created)
+}
+
+//INFO: Removes the folders `create_parent_in_vault` made for `path`, as long as they're still empty
+fn remove_created_folders(path: &Path, first_created: &Path) {
+    for folder in path.ancestors().skip(1) {
+        if fs::remove_dir(folder).is_err() || folder == first_created {
+            break;
+        }
+    }
+}
+
+//INFO: Path relative to the vault root with `/` separators, as Obsidian writes it in links
+fn vault_relative(vault: &Path, path: &Path) -> String {
+    path.strip_prefix(vault)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn strip_md(s: String) -> String {
+    match s.strip_suffix(".md") {
+        Some(stripped) => stripped.to_string(),
+        None => s,
+    }
+}
+
+//INFO: How a file is referred to in a bare [[wikilink]]: its name, without `.md` for notes
+//NOTE: Attachments keep their extension since Obsidian links them by full file name
+fn link_name(path: &Path) -> String {
+    strip_md(path.file_name().unwrap_or_default().to_string_lossy().into_owned())
+}
+
+//INFO: How a note is referred to in [[wikilinks]]: (bare name, vault-relative path), both without `.md`
+fn link_targets(vault: &Path, path: &Path) -> (String, String) {
+    (link_name(path), strip_md(vault_relative(vault, path)))
+}
+
+//INFO: Rewrites [[wikilinks]] pointing at `old` so they point at `new`, keeping any #heading or |alias
+//NOTE: `old` and `new` are (bare name, path) pairs from `link_targets`; bare links become `new.0` and path
+//      links become `new.1`. A None bare name leaves bare links alone (the name is shared, so they may
+//      mean another file). Matching is case-insensitive like Obsidian's. None when nothing changed.
+pub fn rewrite_wikilinks(text: &str, old: (Option<&str>, &str), new: (&str, &str)) -> Option<String> {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut changed = false;
+
+    while let Some(open) = rest.find("[[") {
+        let after = &rest[open + 2..];
+        let Some(close) = after.find("]]") else {
+            break;
+        };
+        let inner = &after[..close];
+        let target_end = inner.find(['#', '|']).unwrap_or(inner.len());
+        let target = inner[..target_end].trim();
+        let target = target.strip_suffix(".md").unwrap_or(target);
+
+        output.push_str(&rest[..open + 2]);
+        let replacement = if !target.contains('/') {
+            old.0.filter(|name| target.eq_ignore_ascii_case(name)).map(|_| new.0)
+        } else if target.eq_ignore_ascii_case(old.1) {
+            Some(new.1)
+        } else {
+            None
+        };
+        match replacement {
+            Some(new_target) if new_target != target => {
+                output.push_str(new_target);
+                output.push_str(&inner[target_end..]);
+                changed = true;
+            }
+            _ => output.push_str(inner),
+        }
+        output.push_str("]]");
+        rest = &after[close + 2..];
+    }
+
+    output.push_str(rest);
+    changed.then_some(output)
+}
+
+//INFO: Result of `move_file`
+#[derive(Debug)]
+pub struct MovedFile {
+    pub path: PathBuf,
+    //INFO: Notes whose links now point at the new path
+    pub links_updated: Vec<String>,
+    //INFO: Why some links couldn't be updated; the file itself was still moved
+    pub link_errors: Vec<String>,
+}
+
+//INFO: Moves or renames a file inside the vault, then optionally rewrites links to it
+//NOTE: Refuses to replace an existing file unless `overwrite` is set, before creating any folders. Falls
+//      back to copy + delete when a rename isn't possible (e.g. across filesystems). With `update_links`,
+//      every note's [[wikilinks]] to the old name or path are rewritten to the new one; a failure there
+//      is reported in `link_errors` rather than failing the move that already happened.
+pub fn move_file(
+    config: &ObsidianConfig,
+    from: &str,
+    to: &str,
+    overwrite: bool,
+    update_links: bool,
+) -> Result<MovedFile> {
+    let from = ensure_in_vault(config, &vault_file_path(config, from)?)?;
+    if !from.is_file() {
+        return Err(anyhow!("{} does not exist or is not a file", from.display()));
+    }
+
+    let to = vault_file_path(config, to)?;
+    check_parent_in_vault(config, &to)?;
+    if to.exists() {
+        if to.canonicalize()? == from {
+            return Err(anyhow!("{} is already at that path", from.display()));
+        }
+        if !overwrite {
+            return Err(anyhow!("{} already exists (set overwrite to replace it)", to.display()));
+        }
+    }
+    let created = create_parent_in_vault(config, &to)?;
+    let to = ensure_in_vault(config, &to)?;
+
+    if let Err(rename_error) = fs::rename(&from, &to) {
+        if let Err(copy_error) = fs::copy(&from, &to) {
+            if let Some(first_created) = &created {
+                remove_created_folders(&to, first_created);
+            }
+            return Err(anyhow!("Failed to move {} ({}; {})", from.display(), rename_error, copy_error));
+        }
+        fs::remove_file(&from)
+            .with_context(|| format!("Copied to {} but failed to remove {}", to.display(), from.display()))?;
+    }
+
+    let mut moved = MovedFile {
+        path: to,
+        links_updated: Vec::new(),
+        link_errors: Vec::new(),
+    };
+    if update_links {
+        if let Err(e) = update_links_after_move(config, &from, &mut moved) {
+            moved.link_errors.push(format!("{:#}", e));
+        }
+    }
+    Ok(moved)
+}
+
+//INFO: Rewrites every note's [[wikilinks]] from `from` to `moved.path`
+//NOTE: Bare [[Name]] links are only rewritten when no other file in the vault shares the old name, since
+//      they may mean that other file. If another file already has the new name, they become path links.
+fn update_links_after_move(config: &ObsidianConfig, from: &Path, moved: &mut MovedFile) -> Result<()> {
+    let vault = config.vault_path.canonicalize()?;
+    let to = moved.path.clone();
+    let (old_name, old_path) = link_targets(&vault, from);
+    let (new_name, new_path) = link_targets(&vault, &to);
+
+    let files: Vec<PathBuf> = walkdir::WalkDir::new(&vault)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.into_path())
+        .collect();
+    let name_taken = |name: &str| {
+        files
+            .iter()
+            .any(|f| *f != to && link_name(f).eq_ignore_ascii_case(name))
+    };
+    let old_bare = (!name_taken(&old_name)).then_some(old_name.as_str());
+    let new_bare = if name_taken(&new_name) { new_path.as_str() } else { new_name.as_str() };
+
+    for file in files.iter().filter(|f| f.extension().is_some_and(|ext| ext == "md")) {
+        let Ok(text) = fs::read_to_string(file) else {
+            continue;
+        };
+        if let Some(rewritten) = rewrite_wikilinks(&text, (old_bare, &old_path), (new_bare, &new_path)) {
+            let relative = vault_relative(&vault, file);
+            match fs::write(file, rewritten) {
+                Ok(()) => moved.links_updated.push(relative),
+                Err(e) => moved.link_errors.push(format!("Failed to update links in {}: {}", relative, e)),
+            }
+        }
+    }
+    Ok(())
+}
+
+//INFO: Line ending used by existing text: CRLF if it has any, LF otherwise
+fn line_ending_of(text: &str) -> &'static str {
+    if text.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    }
+}
+
+//INFO: Adds `addition` at the end of `existing` on its own line(s), always ending with a newline
+pub fn append_text(existing: &str, addition: &str) -> String {
+    let eol = line_ending_of(existing);
+    let mut output = existing.to_string();
+    if !output.is_empty() && !output.ends_with('\n') {
+        output.push_str(eol);
+    }
+    output.push_str(addition.trim_end_matches(['\r', '\n']));
+    output.push_str(eol);
+    output
+}
+
+//INFO: Adds `addition` at the top of `existing`, below any YAML frontmatter so Obsidian still reads it
+pub fn prepend_text(existing: &str, addition: &str) -> String {
+    let eol = line_ending_of(existing);
+    let addition = format!("{}{}", addition.trim_end_matches(['\r', '\n']), eol);
+
+    let split_at = frontmatter_end(existing).unwrap_or(0);
+    let (head, tail) = existing.split_at(split_at);
+    format!("{}{}{}", head, addition, tail)
+}
+
+//INFO: Byte offset just past the closing `---` line of leading YAML frontmatter, if there is any
+fn frontmatter_end(text: &str) -> Option<usize> {
+    let mut offset = 0;
+    for (index, line) in text.split_inclusive('\n').enumerate() {
+        offset += line.len();
+        match (index, line.trim_end() == "---") {
+            (0, false) => return None,
+            (0, true) => {}
+            (_, true) => return Some(offset),
+            _ => {}
+        }
+    }
+    None
+}
+
+//INFO: Appends or prepends text to a file inside the vault, creating the file if it's missing
+pub fn add_to_file(
+    config: &ObsidianConfig,
+    path: &Path,
+    addition: &str,
+    prepend: bool,
+) -> Result<PathBuf> {
+    let path = ensure_in_vault(config, path)?;
+    let existing = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(anyhow!("Failed to read {}: {}", path.display(), e)),
+    };
+
+    let updated = if prepend {
+        prepend_text(&existing, addition)
+    } else {
+        append_text(&existing, addition)
+    };
+    fs::write(&path, updated).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+//INFO: Level and text of a markdown heading line ("## Log" -> (2, "Log"))
+fn parse_heading(line: &str) -> Option<(usize, &str)> {
+    let line = line.trim_end();
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let rest = &line[level..];
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with(' ')) {
+        return None;
+    }
+    Some((level, rest.trim()))
+}
+
+//INFO: Adds `addition` as the last line of the section under `heading`, creating the heading at the end if missing
+//NOTE: `heading` matches by text at any level ("Log" or "## Log" both match "## Log"); the section ends at
+//      the next heading of the same or a higher level, and trailing blank lines stay after the new line
+pub fn insert_under_heading(existing: &str, heading: &str, addition: &str) -> String {
+    let eol = line_ending_of(existing);
+    let addition = addition.trim_end_matches(['\r', '\n']);
+    let wanted = parse_heading(heading).map_or(heading.trim(), |(_, text)| text);
+
+    let lines: Vec<&str> = existing.split_inclusive('\n').collect();
+    let found = lines.iter().enumerate().find_map(|(index, line)| {
+        parse_heading(line)
+            .filter(|(_, text)| text.eq_ignore_ascii_case(wanted))
+            .map(|(level, _)| (index, level))
+    });
+
+    let Some((start, level)) = found else {
+        let heading_line = if heading.trim_start().starts_with('#') {
+            heading.trim().to_string()
+        } else {
+            format!("## {}", heading.trim())
+        };
+        let mut output = existing.to_string();
+        if !output.is_empty() {
+            if !output.ends_with('\n') {
+                output.push_str(eol);
+            }
+            output.push_str(eol);
+        }
+        return format!("{}{}{}{}{}", output, heading_line, eol, addition, eol);
+    };
+
+    let end = lines[start + 1..]
+        .iter()
+        .position(|line| parse_heading(line).is_some_and(|(l, _)| l <= level))
+        .map_or(lines.len(), |offset| start + 1 + offset);
+    let insert_at = (start + 1..end)
+        .rev()
+        .find(|&index| !lines[index].trim().is_empty())
+        .map_or(start + 1, |index| index + 1);
+
+    let mut output: String = lines[..insert_at].concat();
+    if !output.ends_with('\n') {
+        output.push_str(eol);
+    }
+    output.push_str(addition);
+    output.push_str(eol);
+    output.push_str(&lines[insert_at..].concat());
+    output
+}
+
+//INFO: Works out where a quick capture goes: (daily note path, current text, updated text, line written)
+//NOTE: The note may not exist yet, in which case the current text is empty
+pub fn plan_quick_capture(
+    config: &ObsidianConfig,
+    text: &str,
+    now: DateTime<Local>,
+) -> Result<(PathBuf, String, String, String)> {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+        return Err(anyhow!("Nothing to capture"));
+    }
+
+    let path = resolve_daily_note_path(config, now.date_naive());
+    let existing = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(anyhow!("Failed to read {}: {}", path.display(), e)),
+    };
+
+    let line = format!("- {} {}", now.format("%H:%M"), text);
+    let updated = if config.quick_capture_heading.is_empty() {
+        append_text(&existing, &line)
+    } else {
+        insert_under_heading(&existing, &config.quick_capture_heading, &line)
+    };
+    Ok((path, existing, updated, line))
+}
+
+//INFO: Appends "- HH:MM text" to today's daily note, creating the note (and its folder) if needed
+//NOTE: Returns the note path and the line written
+pub fn quick_capture(config: &ObsidianConfig, text: &str, now: DateTime<Local>) -> Result<(PathBuf, String)> {
+    if !config.vault_path.is_dir() {
+        return Err(anyhow!("Vault not found at {}", config.vault_path.display()));
+    }
+    let (path, _, updated, line) = plan_quick_capture(config, text, now)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+
+    let path = ensure_in_vault(config, &path)?;
+    fs::write(&path, updated).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok((path, line))
+}
+
+//INFO: How far back `check_config` looks for any daily note when today's doesn't exist
+const CHECK_LOOKBACK_DAYS: i64 = 30;
+
+//INFO: Result of validating an Obsidian config, for the setup wizard to display
+#[derive(Debug, Serialize)]
+pub struct ObsidianConfigCheck {
+    pub vault_exists: bool,
+    //INFO: Whether the folder has a `.obsidian` directory (i.e. has been opened in Obsidian)
+    pub is_obsidian_vault: bool,
+    pub daily_notes_folder_exists: bool,
+    pub todays_note_path: String,
+    pub todays_note_exists: bool,
+    //INFO: Newest daily note within the last CHECK_LOOKBACK_DAYS, to confirm the format matches real files
+    pub latest_daily_note: Option<String>,
+    //INFO: Human-readable problems, empty when everything looks right
+    pub problems: Vec<String>,
+}
+
+//INFO: Checks that the vault, the daily-notes folder and today's note resolve to real paths
+pub fn check_config(config: &ObsidianConfig, today: NaiveDate) -> ObsidianConfigCheck {
+    let vault_exists = config.vault_path.is_dir();
+    let is_obsidian_vault = config.vault_path.join(".obsidian").is_dir();
+    let daily_folder = config.vault_path.join(&config.daily_notes_folder);
+    let daily_notes_folder_exists = daily_folder.is_dir();
+    let todays_note = resolve_daily_note_path(config, today);
+    let todays_note_exists = todays_note.is_file();
+    let latest_daily_note = find_recent_daily_note(config, today, CHECK_LOOKBACK_DAYS)
+        .map(|note| note.path.to_string_lossy().into_owned());
+
+    let mut problems = Vec::new();
+    if !vault_exists {
+        problems.push(format!("Vault folder not found: {}", config.vault_path.display()));
+    } else {
+        if !is_obsidian_vault {
+            problems.push("This folder has no .obsidian directory - is it the vault root?".to_string());
+        }
+        if !daily_notes_folder_exists {
+            problems.push(format!("Daily notes folder not found: {}", daily_folder.display()));
+        } else if latest_daily_note.is_none() {
+            problems.push(format!(
+                "No daily notes matching \"{}\" in the last {} days - check the date format",
+                config.daily_notes_format, CHECK_LOOKBACK_DAYS
+            ));
+        }
+    }
+
+    ObsidianConfigCheck {
+        vault_exists,
+        is_obsidian_vault,
+        daily_notes_folder_exists,
+        todays_note_path: todays_note.to_string_lossy().into_owned(),
+        todays_note_exists,
+        latest_daily_note,
+        problems,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, 7).unwrap()
+    }
+
+    #[test]
+    fn test_moment_to_chrono_basic_formats() {
+        assert_eq!(moment_to_chrono("YYYY-MM-DD"), "%Y-%m-%d");
+        assert_eq!(format_moment_date("YYYY-MM-DD", date()), "2025-03-07");
+        assert_eq!(format_moment_date("DD-MM-YYYY", date()), "07-03-2025");
+        assert_eq!(format_moment_date("YYYY/MM/DD", date()), "2025/03/07");
+    }
+
+    #[test]
+    fn test_moment_to_chrono_literal_text() {
+        assert_eq!(format_moment_date("[Journal] YYYY-MM-DD", date()), "Journal 2025-03-07");
+        //INFO: Bracketed tokens stay literal, and stray % doesn't become a chrono specifier
+        assert_eq!(format_moment_date("[YYYY] DD", date()), "YYYY 07");
+        assert_eq!(format_moment_date("YYYY-MM-DD 100%", date()), "2025-03-07 100%");
+    }
+
+    #[test]
+    fn test_moment_to_chrono_named_tokens() {
+        assert_eq!(format_moment_date("YY", date()), "25");
+        assert_eq!(format_moment_date("MMMM", date()), "March");
+        assert_eq!(format_moment_date("MMM", date()), "Mar");
+        assert_eq!(format_moment_date("M", date()), "3");
+        assert_eq!(format_moment_date("dddd", date()), "Friday");
+        assert_eq!(format_moment_date("ddd", date()), "Fri");
+        assert_eq!(format_moment_date("D", date()), "7");
+        assert_eq!(format_moment_date("Do", date()), "7th");
+    }
+
+    #[test]
+    fn test_moment_to_chrono_ordinals() {
+        let day = |d| NaiveDate::from_ymd_opt(2025, 1, d).unwrap();
+        let ordinals: Vec<String> = [1, 2, 3, 4, 11, 12, 13, 21, 22, 23, 31]
+            .into_iter()
+            .map(|d| format_moment_date("Do", day(d)))
+            .collect();
+        assert_eq!(
+            ordinals,
+            ["1st", "2nd", "3rd", "4th", "11th", "12th", "13th", "21st", "22nd", "23rd", "31st"]
+        );
+    }
+
+    #[test]
+    fn test_moment_to_chrono_mixed_formats() {
+        assert_eq!(format_moment_date("YYYY-MM-DD dddd", date()), "2025-03-07 Friday");
+        assert_eq!(format_moment_date("MMMM Do YYYY", date()), "March 7th 2025");
+        assert_eq!(format_moment_date("[Week of] MMM D, YY", date()), "Week of Mar 7, 25");
+    }
+
+    #[test]
+    fn test_resolve_daily_note_path() {
+        let config = ObsidianConfig::from_json(&serde_json::json!({
+            "vault_path": "/vault",
+            "daily_notes_path": "Daily",
+        }))
+        .unwrap();
+        assert_eq!(
+            resolve_daily_note_path(&config, date()),
+            Path::new("/vault").join("Daily").join("2025-03-07.md")
+        );
+    }
+
+    #[test]
+    fn test_slugify_title() {
+        assert_eq!(slugify_title("Project Kickoff: Q3 / 2025!"), "project-kickoff-q3-2025");
+        assert_eq!(slugify_title("  ---  "), "");
+    }
+
+    #[test]
+    fn test_create_note_refuses_overwrite_and_escaping_vault() {
+        let vault = std::env::temp_dir().join(format!("lumen-vault-{}", std::process::id()));
+        let config = ObsidianConfig {
+            vault_path: vault.clone(),
+            daily_notes_folder: String::new(),
+            daily_notes_format: DEFAULT_DAILY_NOTES_FORMAT.to_string(),
+            quick_capture_heading: String::new(),
+        };
+        let note = NewNote {
+            title: "Weekly Review",
+            folder: Some("Reviews"),
+            content: Some("- [ ] inbox zero"),
+            tags: vec!["#review".to_string()],
+            ..Default::default()
+        };
+
+        let path = create_note(&config, &note).unwrap();
+        assert_eq!(path, vault.join("Reviews").join("weekly-review.md"));
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(text.starts_with("---\ncreated: "));
+        assert!(text.contains("tags:\n  - review\n---\n- [ ] inbox zero\n"));
+
+        assert!(create_note(&config, &note).is_err());
+        let escaping = NewNote {
+            folder: Some("../outside"),
+            ..note
+        };
+        assert!(create_note(&config, &escaping).is_err());
+
+        fs::remove_dir_all(&vault).unwrap();
+    }
+
+    #[test]
+    fn test_recent_notes_sorts_newest_first_and_skips_hidden() {
+        let vault = std::env::temp_dir().join(format!("lumen-recent-{}", std::process::id()));
+        let config = ObsidianConfig {
+            vault_path: vault.clone(),
+            daily_notes_folder: String::new(),
+            daily_notes_format: DEFAULT_DAILY_NOTES_FORMAT.to_string(),
+            quick_capture_heading: String::new(),
+        };
+        fs::create_dir_all(vault.join("Projects")).unwrap();
+        fs::create_dir_all(vault.join(".obsidian")).unwrap();
+
+        let now = std::time::SystemTime::now();
+        for (name, age_secs) in [("old.md", 300), ("Projects/new.md", 10), ("image.png", 0), (".obsidian/app.md", 0)] {
+            let path = vault.join(name);
+            fs::write(&path, "x").unwrap();
+            let file = fs::File::options().write(true).open(&path).unwrap();
+            file.set_modified(now - std::time::Duration::from_secs(age_secs)).unwrap();
+        }
+
+        let (notes, truncated) = recent_notes(&config, "", 10).unwrap();
+        let paths: Vec<_> = notes.iter().map(|n| n.path.replace('\\', "/")).collect();
+        assert_eq!(paths, vec!["Projects/new.md", "old.md"]);
+        assert!(!truncated);
+
+        let (notes, _) = recent_notes(&config, "Projects", 10).unwrap();
+        assert_eq!(notes.len(), 1);
+        assert!(recent_notes(&config, "../", 10).is_err());
+
+        fs::remove_dir_all(&vault).unwrap();
+    }
+
+    #[test]
+    fn test_check_config_reports_missing_pieces() {
+        let vault = std::env::temp_dir().join(format!("lumen-check-{}", std::process::id()));
+        let config = ObsidianConfig {
+            vault_path: vault.clone(),
+            daily_notes_folder: "Daily".to_string(),
+            daily_notes_format: DEFAULT_DAILY_NOTES_FORMAT.to_string(),
+            quick_capture_heading: String::new(),
+        };
+
+        let missing = check_config(&config, date());
+        assert!(!missing.vault_exists);
+        assert_eq!(missing.problems.len(), 1);
+
+        fs::create_dir_all(vault.join(".obsidian")).unwrap();
+        fs::create_dir_all(vault.join("Daily")).unwrap();
+        fs::write(vault.join("Daily").join("2025-03-05.md"), "old note").unwrap();
+
+        let check = check_config(&config, date());
+        assert!(check.vault_exists && check.is_obsidian_vault && check.daily_notes_folder_exists);
+        assert!(!check.todays_note_exists);
+        assert!(check.todays_note_path.ends_with("2025-03-07.md"));
+        assert!(check.latest_daily_note.unwrap().ends_with("2025-03-05.md"));
+        assert!(check.problems.is_empty());
+
+        fs::remove_dir_all(&vault).unwrap();
+    }
+
+    #[test]
+    fn test_append_text_newlines() {
+        assert_eq!(append_text("", "- [ ] task"), "- [ ] task\n");
+        assert_eq!(append_text("a\n", "- [ ] task\n"), "a\n- [ ] task\n");
+        assert_eq!(append_text("a", "- [ ] task"), "a\n- [ ] task\n");
+        assert_eq!(append_text("a\r\nb", "c"), "a\r\nb\r\nc\r\n");
+    }
+
+    #[test]
+    fn test_prepend_text_keeps_frontmatter_first() {
+        assert_eq!(prepend_text("body\n", "top"), "top\nbody\n");
+        assert_eq!(
+            prepend_text("---\ntags: [x]\n---\nbody\n", "top"),
+            "---\ntags: [x]\n---\ntop\nbody\n"
+        );
+        //INFO: An unterminated frontmatter block is just text
+        assert_eq!(prepend_text("---\nbody", "top"), "top\n---\nbody");
+    }
+
+    #[test]
+    fn test_rewrite_wikilinks() {
+        let text = "See [[Old]], [[projects/old#Goals|goals]], [[Old.md]] and [[Other]]";
+        assert_eq!(
+            rewrite_wikilinks(text, (Some("Old"), "Projects/Old"), ("New", "Archive/New")).unwrap(),
+            "See [[New]], [[Archive/New#Goals|goals]], [[New]] and [[Other]]"
+        );
+        assert!(rewrite_wikilinks("[[Other]] [[broken", (Some("Old"), "Old"), ("New", "New")).is_none());
+    }
+
+    #[test]
+    fn test_move_file_updates_links_and_refuses_overwrite() {
+        let vault = std::env::temp_dir().join(format!("lumen-move-{}", std::process::id()));
+        fs::create_dir_all(vault.join("Projects")).unwrap();
+        let config = ObsidianConfig {
+            vault_path: vault.clone(),
+            daily_notes_folder: String::new(),
+            daily_notes_format: DEFAULT_DAILY_NOTES_FORMAT.to_string(),
+            quick_capture_heading: String::new(),
+        };
+        fs::write(vault.join("Projects/Old.md"), "plan").unwrap();
+        fs::write(vault.join("index.md"), "- [[Old]]\n- [[Projects/Old|plan]]\n").unwrap();
+        fs::write(vault.join("taken.md"), "x").unwrap();
+
+        let moved = move_file(&config, "Projects/Old.md", "Archive/New.md", false, true).unwrap();
+        assert!(moved.path.ends_with("Archive/New.md"));
+        assert!(!vault.join("Projects/Old.md").exists());
+        assert_eq!(moved.links_updated, vec!["index.md".to_string()]);
+        assert!(moved.link_errors.is_empty());
+        assert_eq!(
+            fs::read_to_string(vault.join("index.md")).unwrap(),
+            "- [[New]]\n- [[Archive/New|plan]]\n"
+        );
+
+        assert!(move_file(&config, "Archive/New.md", "taken.md", false, false).is_err());
+        assert!(move_file(&config, "Archive/New.md", "../escaped.md", false, false).is_err());
+        move_file(&config, "Archive/New.md", "taken.md", true, false).unwrap();
+        assert_eq!(fs::read_to_string(vault.join("taken.md")).unwrap(), "plan");
+
+        fs::remove_dir_all(&vault).unwrap();
+    }
+
+    #[test]
+    fn test_move_file_leaves_bare_links_to_a_shared_name() {
+        let vault = std::env::temp_dir().join(format!("lumen-move-shared-{}", std::process::id()));
+        fs::create_dir_all(vault.join("Work")).unwrap();
+        fs::create_dir_all(vault.join("Home")).unwrap();
+        let config = ObsidianConfig {
+            vault_path: vault.clone(),
+            daily_notes_folder: String::new(),
+            daily_notes_format: DEFAULT_DAILY_NOTES_FORMAT.to_string(),
+            quick_capture_heading: String::new(),
+        };
+        fs::write(vault.join("Work/Plan.md"), "work").unwrap();
+        fs::write(vault.join("Home/Plan.md"), "home").unwrap();
+        fs::write(vault.join("index.md"), "[[Plan]] [[Work/Plan]]\n").unwrap();
+
+        let moved = move_file(&config, "Work/Plan.md", "Work/Roadmap.md", false, true).unwrap();
+        assert_eq!(moved.links_updated, vec!["index.md".to_string()]);
+        assert_eq!(fs::read_to_string(vault.join("index.md")).unwrap(), "[[Plan]] [[Work/Roadmap]]\n");
+
+        fs::remove_dir_all(&vault).unwrap();
+    }
+
+    #[test]
+    fn test_insert_under_heading() {
+        let note = "# Friday\n## Log\n- 09:00 standup\n\n## Tasks\n- [ ] ship\n";
+        assert_eq!(
+            insert_under_heading(note, "Log", "- 10:15 idea"),
+            "# Friday\n## Log\n- 09:00 standup\n- 10:15 idea\n\n## Tasks\n- [ ] ship\n"
+        );
+        //INFO: Subheadings stay inside the section, a missing heading is added at the end
+        assert_eq!(
+            insert_under_heading("## Log\n### Morning\na", "## Log", "b"),
+            "## Log\n### Morning\na\nb\n"
+        );
+        assert_eq!(insert_under_heading("body\n", "Inbox", "x"), "body\n\n## Inbox\nx\n");
+        assert_eq!(insert_under_heading("", "### Inbox", "x"), "### Inbox\nx\n");
+    }
+
+    #[test]
+    fn test_quick_capture_creates_todays_note() {
+        use chrono::TimeZone;
+        let vault = std::env::temp_dir().join(format!("lumen-capture-{}", std::process::id()));
+        fs::create_dir_all(&vault).unwrap();
+        let config = ObsidianConfig {
+            vault_path: vault.clone(),
+            daily_notes_folder: "Daily".to_string(),
+            daily_notes_format: "DD-MM-YYYY".to_string(),
+            quick_capture_heading: "Captures".to_string(),
+        };
+        let at = |h, m| Local.with_ymd_and_hms(2025, 3, 7, h, m, 0).unwrap();
+
+        let (path, line) = quick_capture(&config, "call  the\nbank", at(9, 5)).unwrap();
+        assert!(path.ends_with("Daily/07-03-2025.md"));
+        assert_eq!(line, "- 09:05 call the bank");
+        quick_capture(&config, "buy milk", at(17, 30)).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "## Captures\n- 09:05 call the bank\n- 17:30 buy milk\n"
+        );
+        assert!(quick_capture(&config, "  ", at(18, 0)).is_err());
+
+        fs::remove_dir_all(&vault).unwrap();
+    }
+}
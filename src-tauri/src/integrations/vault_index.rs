@@ -0,0 +1,231 @@
+//INFO: Embeddings index over Obsidian notes, for semantic search
+//NOTE: Notes are split into paragraph-sized chunks and embedded with Gemini. Each file's mtime and content
+//      hash are recorded as soon as it's embedded, so a re-index (or one resumed after cancelling) only
+//      re-embeds files that actually changed.
+
+use crate::database::{queries, Database};
+use crate::gemini::GeminiClient;
+use crate::integrations::obsidian::ObsidianConfig;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use walkdir::WalkDir;
+
+//INFO: Chunks are built from whole paragraphs up to about this size
+const CHUNK_MAX_CHARS: usize = 1500;
+
+//INFO: Folders that hold app data rather than notes
+const SKIPPED_DIRS: &[&str] = &[".obsidian", ".trash", ".git"];
+
+//INFO: Outcome of a re-index run
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct IndexStats {
+    pub indexed: usize,
+    pub unchanged: usize,
+    pub removed: usize,
+    pub failed: usize,
+    pub cancelled: bool,
+}
+
+//INFO: Payload of the `vault-index-progress` event
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexProgress {
+    pub done: usize,
+    pub total: usize,
+    pub current_file: String,
+}
+
+//INFO: A chunk returned by semantic search
+#[derive(Debug, Clone, Serialize)]
+pub struct NoteMatch {
+    pub path: String,
+    pub chunk_index: i64,
+    pub content: String,
+    pub score: f32,
+}
+
+//INFO: Splits a note into chunks of whole paragraphs; a single oversized paragraph is split by characters
+pub fn chunk_note(content: &str) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+
+    for paragraph in content.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+        if !current.is_empty() && current.chars().count() + paragraph.chars().count() + 2 > CHUNK_MAX_CHARS {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if paragraph.chars().count() > CHUNK_MAX_CHARS {
+            let chars: Vec<char> = paragraph.chars().collect();
+            for piece in chars.chunks(CHUNK_MAX_CHARS) {
+                chunks.push(piece.iter().collect());
+            }
+            continue;
+        }
+        if !current.is_empty() {
+            current.push_str("\n\n");
+        }
+        current.push_str(paragraph);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+//INFO: Hex SHA-256 of a note's content
+pub fn hash_content(content: &str) -> String {
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+//INFO: Every markdown file in the vault, skipping Obsidian's own folders
+pub fn list_vault_notes(vault: &Path) -> Vec<PathBuf> {
+    WalkDir::new(vault)
+        .into_iter()
+        .filter_entry(|e| {
+            !(e.file_type().is_dir()
+                && e.file_name().to_str().is_some_and(|n| SKIPPED_DIRS.contains(&n)))
+        })
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && e.path().extension().is_some_and(|ext| ext == "md"))
+        .map(|e| e.into_path())
+        .collect()
+}
+
+//INFO: A file's modification time as a comparable string
+fn file_mtime(path: &Path) -> Result<String> {
+    let modified = std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .with_context(|| format!("Cannot read {}", path.display()))?;
+    Ok(chrono::DateTime::<chrono::Utc>::from(modified).to_rfc3339())
+}
+
+//INFO: Re-embeds one file if it changed; returns false when it was already up to date
+async fn index_note(database: &Database, client: &GeminiClient, path: &Path) -> Result<bool> {
+    let key = path.to_string_lossy().to_string();
+    let mtime = file_mtime(path)?;
+    let recorded = {
+        let connection = database.connection.lock()?;
+        queries::get_indexed_note(&connection, &key)?
+    };
+    if recorded.as_ref().is_some_and(|(m, _)| *m == mtime) {
+        return Ok(false);
+    }
+
+    let content = std::fs::read_to_string(path).with_context(|| format!("Cannot read {}", path.display()))?;
+    let hash = hash_content(&content);
+    if recorded.as_ref().is_some_and(|(_, h)| *h == hash) {
+        let connection = database.connection.lock()?;
+        queries::touch_indexed_note(&connection, &key, &mtime)?;
+        return Ok(false);
+    }
+
+    let mut chunks = Vec::new();
+    for chunk in chunk_note(&content) {
+        let embedding = client
+            .generate_embedding(&chunk)
+            .await
+            .with_context(|| format!("Failed to embed {}", path.display()))?;
+        chunks.push((chunk, embedding));
+    }
+
+    let connection = database.connection.lock()?;
+    queries::save_note_chunks(&connection, &key, &mtime, &hash, &chunks)?;
+    Ok(true)
+}
+
+//INFO: Brings the index in line with the vault: embeds new/changed notes and drops deleted ones
+//NOTE: `on_progress` is called before each file; setting `cancel` stops after the current file
+pub async fn reindex_vault(
+    database: &Database,
+    config: &ObsidianConfig,
+    client: &GeminiClient,
+    cancel: &AtomicBool,
+    mut on_progress: impl FnMut(&IndexProgress),
+) -> Result<IndexStats> {
+    let notes = list_vault_notes(&config.vault_path);
+    let mut stats = IndexStats::default();
+
+    for (done, path) in notes.iter().enumerate() {
+        if cancel.load(Ordering::SeqCst) {
+            stats.cancelled = true;
+            tracing::info!("📚 Vault Index: Cancelled after {} of {} notes", done, notes.len());
+            return Ok(stats);
+        }
+        on_progress(&IndexProgress {
+            done,
+            total: notes.len(),
+            current_file: path
+                .strip_prefix(&config.vault_path)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .to_string(),
+        });
+
+        match index_note(database, client, path).await {
+            Ok(true) => stats.indexed += 1,
+            Ok(false) => stats.unchanged += 1,
+            Err(e) => {
+                tracing::warn!("⚠️ Vault Index: {:#}", e);
+                stats.failed += 1;
+            }
+        }
+    }
+
+    let on_disk: std::collections::HashSet<String> =
+        notes.iter().map(|p| p.to_string_lossy().to_string()).collect();
+    let connection = database.connection.lock()?;
+    for path in queries::list_indexed_notes(&connection)? {
+        if !on_disk.contains(&path) {
+            queries::remove_indexed_note(&connection, &path)?;
+            stats.removed += 1;
+        }
+    }
+
+    tracing::info!(
+        "📚 Vault Index: {} indexed, {} unchanged, {} removed, {} failed",
+        stats.indexed,
+        stats.unchanged,
+        stats.removed,
+        stats.failed
+    );
+    Ok(stats)
+}
+
+//INFO: Returns the chunks closest to a query embedding by cosine similarity
+pub fn search_notes(connection: &rusqlite::Connection, query_embedding: &[f32], limit: usize) -> Result<Vec<NoteMatch>> {
+    let mut matches: Vec<NoteMatch> = queries::get_note_chunks(connection)?
+        .into_iter()
+        .map(|chunk| NoteMatch {
+            score: crate::memory::core::cosine_similarity(query_embedding, &chunk.embedding),
+            path: chunk.path,
+            chunk_index: chunk.chunk_index,
+            content: chunk.content,
+        })
+        .collect();
+    matches.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+    matches.truncate(limit);
+    Ok(matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_note() {
+        assert!(chunk_note("  \n\n ").is_empty());
+        assert_eq!(chunk_note("# Title\n\nFirst.\n\nSecond."), vec!["# Title\n\nFirst.\n\nSecond."]);
+
+        let paragraph = "word ".repeat(200);
+        let chunks = chunk_note(&format!("{}\n\n{}", paragraph, paragraph));
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks.iter().all(|c| c.chars().count() <= CHUNK_MAX_CHARS));
+
+        let huge = "x".repeat(CHUNK_MAX_CHARS * 2 + 1);
+        assert_eq!(chunk_note(&huge).len(), 3);
+    }
+}
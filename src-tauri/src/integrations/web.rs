@@ -0,0 +1,273 @@
+//INFO: Web page fetching for the fetch_url tool
+//NOTE: Only public http(s) URLs are fetched (no localhost or private networks) so the model can't be
+//      used to probe the user's LAN or local services
+
+use anyhow::{anyhow, Context, Result};
+use regex::Regex;
+use serde::Serialize;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(15);
+
+//INFO: Bodies larger than this are cut off while downloading
+const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+//INFO: Roughly 5k tokens of page text
+const MAX_TEXT_CHARS: usize = 20_000;
+
+const MAX_REDIRECTS: usize = 5;
+
+//INFO: Readable text extracted from a page
+#[derive(Debug, Serialize)]
+pub struct FetchedPage {
+    pub url: String,
+    pub title: Option<String>,
+    pub text: String,
+    pub truncated: bool,
+}
+
+fn is_private_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                //INFO: Carrier-grade NAT (100.64.0.0/10)
+                || (ip.octets()[0] == 100 && (ip.octets()[1] & 0xc0) == 64)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(v4) = ip.to_ipv4_mapped() {
+                return is_private_ip(IpAddr::V4(v4));
+            }
+            let first = ip.segments()[0];
+            ip.is_loopback()
+                || ip.is_unspecified()
+                //INFO: Unique local (fc00::/7) and link-local (fe80::/10)
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80
+        }
+    }
+}
+
+//INFO: Rejects non-http(s) schemes, localhost and private IP literals
+fn check_url(url: &url::Url) -> Result<()> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(anyhow!("Only http and https URLs can be fetched"));
+    }
+    match url.host() {
+        None => Err(anyhow!("URL has no host")),
+        Some(url::Host::Domain(domain)) => {
+            let domain = domain.trim_end_matches('.').to_lowercase();
+            if domain == "localhost" || domain.ends_with(".localhost") || domain.ends_with(".local") {
+                Err(anyhow!("Local addresses can't be fetched"))
+            } else {
+                Ok(())
+            }
+        }
+        Some(url::Host::Ipv4(ip)) if is_private_ip(IpAddr::V4(ip)) => {
+            Err(anyhow!("Private network addresses can't be fetched"))
+        }
+        Some(url::Host::Ipv6(ip)) if is_private_ip(IpAddr::V6(ip)) => {
+            Err(anyhow!("Private network addresses can't be fetched"))
+        }
+        Some(_) => Ok(()),
+    }
+}
+
+//INFO: DNS resolver for fetch_url that refuses names pointing at private addresses
+//NOTE: reqwest resolves through this for the first request, every redirect hop and every new
+//      connection, so a public name that resolves (or re-resolves) to 127.0.0.1 or a LAN address
+//      never gets connected to. IP literals skip DNS and are covered by `check_url`.
+struct PublicOnlyResolver;
+
+impl reqwest::dns::Resolve for PublicOnlyResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            //NOTE: reqwest fills in the real port after resolving
+            let addresses: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .collect();
+            if addresses.is_empty() {
+                return Err(format!("Could not resolve {}", host).into());
+            }
+            if addresses.iter().any(|address| is_private_ip(address.ip())) {
+                return Err(format!("{} resolves to a private network address", host).into());
+            }
+            let addresses: reqwest::dns::Addrs = Box::new(addresses.into_iter());
+            Ok(addresses)
+        })
+    }
+}
+
+fn block_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"(?is)<!--.*?-->|<(head|script|style|noscript|svg|nav|header|footer|aside|form|iframe)\b.*?</(head|script|style|noscript|svg|nav|header|footer|aside|form|iframe)\s*>").unwrap()
+    })
+}
+
+fn break_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?i)<br\s*/?>|</(p|div|li|tr|h[1-6]|section|article|blockquote|pre)\s*>").unwrap())
+}
+
+fn tag_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?s)<[^>]*>").unwrap())
+}
+
+fn title_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?is)<title[^>]*>(.*?)</title>").unwrap())
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+//INFO: Pulls the <title> out of an HTML document
+pub fn html_title(html: &str) -> Option<String> {
+    title_regex()
+        .captures(html)
+        .map(|c| decode_entities(c[1].trim()))
+        .filter(|t| !t.is_empty())
+}
+
+//INFO: Simple HTML-to-text: drops the head, scripts, styles and navigation, keeps paragraph breaks, strips the rest of the tags
+pub fn html_to_text(html: &str) -> String {
+    let without_blocks = block_regex().replace_all(html, " ");
+    let with_breaks = break_regex().replace_all(&without_blocks, "\n");
+    let text = decode_entities(&tag_regex().replace_all(&with_breaks, " "));
+
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !line.is_empty() {
+            lines.push(line);
+        }
+    }
+    lines.join("\n")
+}
+
+//INFO: Cuts text to at most `max_chars`, preferring to end on a line break
+fn truncate_text(text: &str, max_chars: usize) -> (String, bool) {
+    match text.char_indices().nth(max_chars) {
+        None => (text.to_string(), false),
+        Some((cut, _)) => {
+            let head = &text[..cut];
+            let head = head.rfind('\n').map(|i| &head[..i]).unwrap_or(head);
+            (head.to_string(), true)
+        }
+    }
+}
+
+//INFO: GETs a public page and returns its readable text, truncated to a token budget
+pub async fn fetch_url(raw_url: &str) -> Result<FetchedPage> {
+    let url = url::Url::parse(raw_url.trim()).context("Invalid URL")?;
+    check_url(&url)?;
+
+    //INFO: Re-check every redirect hop's scheme and host; the resolver checks where it points
+    let redirect_policy = reqwest::redirect::Policy::custom(|attempt| {
+        if attempt.previous().len() >= MAX_REDIRECTS {
+            attempt.error("Too many redirects")
+        } else if let Err(e) = check_url(attempt.url()) {
+            attempt.error(e.to_string())
+        } else {
+            attempt.follow()
+        }
+    });
+    //NOTE: No proxy, so the connection goes to the address the resolver approved
+    let client = reqwest::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .redirect(redirect_policy)
+        .dns_resolver(Arc::new(PublicOnlyResolver))
+        .no_proxy()
+        .user_agent("Mozilla/5.0 (compatible; Lumen)")
+        .build()?;
+
+    let mut response = client.get(url.clone()).send().await.context("Request failed")?;
+    if !response.status().is_success() {
+        return Err(anyhow!("Page returned {}", response.status()));
+    }
+
+    let final_url = response.url().to_string();
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("text/html")
+        .to_lowercase();
+    let is_html = content_type.contains("html");
+    if !is_html && !content_type.starts_with("text/") && !content_type.contains("json") {
+        return Err(anyhow!("Can't read pages of type {}", content_type));
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.context("Failed to read page")? {
+        let remaining = MAX_BODY_BYTES - body.len();
+        body.extend_from_slice(&chunk[..chunk.len().min(remaining)]);
+        if body.len() >= MAX_BODY_BYTES {
+            break;
+        }
+    }
+    let body = String::from_utf8_lossy(&body);
+
+    let (title, text) = if is_html {
+        (html_title(&body), html_to_text(&body))
+    } else {
+        (None, body.to_string())
+    };
+    let (text, truncated) = truncate_text(&text, MAX_TEXT_CHARS);
+
+    Ok(FetchedPage {
+        url: final_url,
+        title,
+        text,
+        truncated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_url() {
+        let ok = |u: &str| check_url(&url::Url::parse(u).unwrap()).is_ok();
+        assert!(ok("https://example.com/article"));
+        assert!(!ok("file:///etc/passwd"));
+        assert!(!ok("http://localhost:1420"));
+        assert!(!ok("http://127.0.0.1/"));
+        assert!(!ok("http://192.168.1.1/admin"));
+        assert!(!ok("http://[::1]/"));
+        assert!(!ok("http://169.254.169.254/latest/meta-data"));
+    }
+
+    #[tokio::test]
+    async fn test_resolver_refuses_names_pointing_at_loopback() {
+        use reqwest::dns::Resolve;
+        let name: reqwest::dns::Name = "localhost".parse().unwrap();
+        assert!(PublicOnlyResolver.resolve(name).await.is_err());
+    }
+
+    #[test]
+    fn test_html_to_text() {
+        let html = "<html><head><title>Hello &amp; bye</title><style>p{}</style></head><body>\
+            <nav><a href='/'>Home</a></nav><h1>Heading</h1><p>First   paragraph.</p>\
+            <script>alert(1)</script><p>Second<br>line</p></body></html>";
+        assert_eq!(html_title(html).as_deref(), Some("Hello & bye"));
+        assert_eq!(html_to_text(html), "Heading\nFirst paragraph.\nSecond\nline");
+    }
+}
@@ -2,21 +2,34 @@
 //NOTE: This file wires together all modules and registers Tauri commands
 
 pub mod agent;
+pub mod calc;
 pub mod commands;
 pub mod crypto;
 pub mod database;
+pub mod error;
 pub mod gemini;
+pub mod hotkey;
 pub mod integrations;
+pub mod llm;
+pub mod local_api;
+pub mod logging;
 pub mod memory;
 pub mod oauth;
+pub mod offline;
+pub mod text;
 
-use commands::{auth, calendar, chat, dashboard, settings, setup, vision, window};
+use commands::{
+    auth, calendar, chat, clipboard, dashboard, deep_link, diagnostics, notes, proactive,
+    reminders, settings, setup, vision, window,
+};
 use database::{initialize_database, Database};
 use tauri::Manager;
 
 //INFO: Main run function that initializes and starts the Tauri application
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    logging::init();
+
     tauri::Builder::default()
         //INFO: Initialize Tauri plugins
         .plugin(tauri_plugin_opener::init())
@@ -28,12 +41,14 @@ pub fn run() {
             Some(vec!["--minimized"]),
         ))
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        //NOTE: Registered before deep-link so a second launch with a lumen:// URL is forwarded here
         .plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
             let _ = app.get_webview_window("main").map(|w| {
                 let _ = w.show();
                 let _ = w.set_focus();
             });
         }))
+        .plugin(tauri_plugin_deep_link::init())
         //INFO: Setup hook to initialize database and other resources
         .setup(|app| {
             //INFO: Initialize the database connection
@@ -41,13 +56,34 @@ pub fn run() {
 
             //INFO: Initialize database schema (create tables if not exist)
             {
-                let connection = database.connection.lock();
+                let connection = database.connection.lock()?;
                 initialize_database(&connection).expect("Failed to initialize database schema");
+                logging::apply_saved_level(&connection);
+                //INFO: A key rotation cut short by a crash must be settled before anything decrypts
+                if let Err(e) = crypto::recover_interrupted_rotation(&connection) {
+                    tracing::error!("❌ Encryption: Failed to recover an interrupted key rotation: {:#}", e);
+                }
+                if let Err(e) = crypto::encrypt_plaintext_clipboard(&connection) {
+                    tracing::error!("❌ Encryption: Failed to encrypt old clipboard entries: {:#}", e);
+                }
+                //NOTE: The windows ask get_credentials_health on load and offer reset_credentials
+                match crypto::recovery::check_credentials(&connection) {
+                    Ok(health) if !health.is_healthy() => tracing::warn!(
+                        "⚠️ Encryption: Can't decrypt stored credentials for {:?} (key error: {:?})",
+                        health.unreadable,
+                        health.key_error
+                    ),
+                    Ok(_) => {}
+                    Err(e) => tracing::error!("❌ Encryption: Failed to check stored credentials: {:#}", e),
+                }
             }
 
             //INFO: Store database in app state for access from commands
             let db_clone = database.clone();
             app.manage(database);
+            app.manage(chat::ChatCancellation::default());
+            app.manage(local_api::LocalApiServer::default());
+            app.manage(notes::VaultIndexer::default());
 
             // Start clipboard manager
             let db_clipboard = db_clone.clone();
@@ -55,6 +91,46 @@ pub fn run() {
                 agent::clipboard::start_clipboard_manager(db_clipboard).await;
             });
 
+            //INFO: Start the reminder scheduler so due reminders fire notifications
+            let db_reminders = db_clone.clone();
+            let reminders_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                agent::reminders::start_reminder_scheduler(reminders_handle, db_reminders).await;
+            });
+
+            //INFO: Start the proactive agent (upcoming meetings, etc.)
+            let db_proactive = db_clone.clone();
+            let proactive_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                agent::proactive::start_proactive_agent(proactive_handle, db_proactive).await;
+            });
+
+            //INFO: Keep the Google access token fresh so requests after idle don't wait on a refresh
+            let db_tokens = db_clone.clone();
+            tauri::async_runtime::spawn(async move {
+                agent::token_refresh::start_token_refresher(db_tokens).await;
+            });
+
+            //INFO: Mark each finished day's last briefing as final
+            let db_briefing = db_clone.clone();
+            tauri::async_runtime::spawn(async move {
+                agent::briefing::start_briefing_finalizer(db_briefing).await;
+            });
+
+            //INFO: Run the local startup checks and log anything that needs attention
+            let db_diagnostics = db_clone.clone();
+            tauri::async_runtime::spawn(async move {
+                diagnostics::run_startup_diagnostics(db_diagnostics).await;
+            });
+
+            //INFO: Start the local HTTP API if the user turned it on
+            if let Err(e) = local_api::start_if_enabled(app.handle()) {
+                tracing::error!("❌ Local API: {:#}", e);
+            }
+
+            //INFO: Handle lumen:// links from other apps
+            setup_deep_links(app);
+
             //INFO: Setup global hotkey listener
             let _ = setup_global_hotkey(app);
 
@@ -73,7 +149,7 @@ pub fn run() {
 
             //INFO: Pre-position overlay so it's ready in the corner
             if let Some(overlay) = app.get_webview_window("overlay") {
-                let _ = crate::commands::window::position_overlay_bottom_left(&overlay);
+                let _ = crate::commands::window::position_overlay(&overlay);
             }
 
             Ok(())
@@ -95,6 +171,9 @@ pub fn run() {
             setup::setup_save_hotkey,
             setup::setup_save_api_key,
             setup::test_gemini_api_key,
+            setup::test_obsidian_config,
+            notes::index_vault,
+            notes::cancel_vault_index,
             setup::setup_save_integration,
             setup::complete_setup,
             // Settings commands
@@ -104,130 +183,168 @@ pub fn run() {
             settings::update_hotkey,
             settings::get_api_key_status,
             settings::update_api_key,
+            settings::rotate_encryption_key,
+            settings::get_credentials_health,
+            settings::reset_credentials,
+            settings::get_max_tool_rounds,
+            settings::set_max_tool_rounds,
             settings::get_integrations,
             settings::get_integration_by_name,
             settings::update_integration,
+            settings::reset_integration_cache,
+            settings::check_integrations_health,
+            diagnostics::run_diagnostics,
+            settings::get_log_path,
+            settings::set_log_level,
             settings::get_database_path,
+            settings::export_database,
+            settings::import_database,
+            settings::import_lumen_backup,
             settings::get_app_setting,
             settings::save_app_setting,
+            settings::get_usage_stats,
+            settings::get_persona_presets,
+            settings::get_system_persona,
+            settings::set_system_persona,
+            settings::get_offline_mode,
+            settings::set_offline_mode,
+            settings::get_llm_settings,
+            settings::set_llm_provider,
+            settings::test_llm_provider,
+            settings::get_safety_thresholds,
+            settings::get_safety_threshold,
+            settings::set_safety_threshold,
+            settings::get_local_api_status,
+            settings::set_local_api_enabled,
             // Chat commands
             chat::send_chat_message,
             chat::get_chat_history,
             chat::clear_chat_history,
+            chat::delete_chat_message,
+            chat::delete_last_chat_messages,
+            chat::dry_run_tool,
+            chat::preview_chat_context,
+            chat::cancel_chat,
+            chat::get_tool_audit,
+            chat::confirm_tool_call,
+            chat::get_confirmation_tools,
+            chat::set_confirmation_tools,
+            chat::create_chat_session,
+            chat::list_chat_sessions,
+            chat::rename_chat_session,
+            chat::delete_chat_session,
+            chat::export_session_markdown,
             // Window commands
             window::show_overlay,
             window::hide_overlay,
             window::toggle_overlay,
             window::is_overlay_visible,
-            window::position_overlay_bottom_left_command,
+            window::position_overlay_command,
+            window::get_overlay_anchor,
+            window::set_overlay_anchor,
+            window::set_overlay_size,
+            window::get_overlay_appearance,
+            window::set_overlay_opacity,
+            window::set_overlay_always_on_top,
             window::resize_overlay,
             window::show_main_window,
             window::hide_main_window,
             window::open_path,
+            deep_link::open_lumen_link,
+            deep_link::confirm_open_link,
             // Dashboard commands
             dashboard::get_dashboard_briefing,
             dashboard::refresh_dashboard_briefing,
+            dashboard::generate_focused_briefing,
+            dashboard::list_briefings,
+            dashboard::get_briefing,
             // Auth commands
             auth::get_google_auth_status,
             auth::save_google_config,
             auth::start_google_auth,
+            auth::cancel_google_auth,
+            auth::disconnect_google,
             // Vision commands
             vision::capture_primary_screen,
+            vision::capture_screen,
+            vision::list_screens,
+            vision::list_windows,
+            vision::capture_window,
             vision::start_snipping,
             vision::capture_region,
+            vision::analyze_snipped_region,
             vision::close_snipper,
             calendar::get_calendar_events_for_range,
+            // Reminder commands
+            reminders::list_pending_reminders,
+            reminders::complete_reminder,
+            reminders::promote_reminder_to_event,
+            // Proactive agent commands
+            proactive::get_notification_rules,
+            proactive::save_notification_rules,
+            proactive::get_dnd_schedule,
+            proactive::set_dnd_schedule,
+            proactive::prune_notifications,
+            proactive::open_notification_item,
+            proactive::start_focus_mode,
+            proactive::end_focus_mode,
+            proactive::get_focus_mode,
+            proactive::get_daily_digest,
+            proactive::set_daily_digest,
+            // Clipboard commands
+            clipboard::get_clipboard_history,
+            clipboard::pin_clipboard_item,
+            clipboard::unpin_clipboard_item,
+            clipboard::get_clipboard_image,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
 
-//INFO: Sets up the global hotkey listener
-//NOTE: Uses the hotkey configured by the user to toggle the overlay
 //INFO: Sets up the global hotkey listener
 //NOTE: Uses the hotkey configured by the user to toggle the overlay
 fn setup_global_hotkey(app: &tauri::App) -> Result<(), Box<dyn std::error::Error>> {
-    use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
-
     //INFO: Get the database to read hotkey configuration
     let database = app.state::<Database>();
-    let connection = database.connection.lock();
-
-    //INFO: Try to get the user's configured hotkey
-    let hotkey_config = database::queries::get_hotkey_config(&connection)
-        .ok()
-        .flatten();
-
-    drop(connection); // Release the lock before async operations
-
-    let mut main_shortcut_str = "Super+L".to_string();
-    let mut snip_shortcut_str = "Super+Shift+S".to_string();
-    let mut main_enabled = true;
-    let mut snip_enabled = true;
-
-    if let Some(config) = hotkey_config {
-        main_enabled = config.enabled;
-        if main_enabled {
-            let modifiers = config.modifier_keys.join("+");
-            main_shortcut_str = if modifiers.is_empty() {
-                config.key
-            } else {
-                format!("{}+{}", modifiers, config.key)
-            };
-        }
-
-        snip_enabled = config.snipper_enabled;
-        if snip_enabled {
-            let snip_modifiers = config.snipper_modifier_keys.join("+");
-            snip_shortcut_str = if snip_modifiers.is_empty() {
-                config.snipper_key
-            } else {
-                format!("{}+{}", snip_modifiers, config.snipper_key)
-            };
-        }
-    }
+    let hotkey_config = {
+        let connection = database.connection.lock()?;
+        database::queries::get_hotkey_config(&connection)
+            .ok()
+            .flatten()
+    };
 
-    //INFO: Parse and register the shortcuts independently
-
-    let app_handle = app.app_handle().clone();
-
-    // 1. Register Main Toggle Shortcut
-    if main_enabled {
-        if let Ok(shortcut) = main_shortcut_str.parse::<Shortcut>() {
-            let handle = app_handle.clone();
-            app.global_shortcut()
-                .on_shortcut(shortcut, move |_app, _sc, event| {
-                    if event.state == ShortcutState::Pressed {
-                        let h = handle.clone();
-                        let _ = handle.run_on_main_thread(move || {
-                            tauri::async_runtime::block_on(async move {
-                                let _ = window::toggle_overlay(h).await;
-                            });
-                        });
-                    }
-                })?;
-        }
+    hotkey::register_hotkey(app.handle(), hotkey_config.as_ref())?;
+    Ok(())
+}
+
+//INFO: Routes lumen:// URLs (including the one the app was launched with) to the deep link handler
+fn setup_deep_links(app: &tauri::App) {
+    use tauri_plugin_deep_link::DeepLinkExt;
+
+    //NOTE: Linux and Windows only learn about the scheme at runtime (macOS reads it from the bundle)
+    #[cfg(any(target_os = "linux", windows))]
+    if let Err(e) = app.deep_link().register_all() {
+        tracing::warn!("⚠️ Deep link: Failed to register lumen:// scheme: {}", e);
     }
 
-    // 2. Register Snipper Shortcut
-    if snip_enabled {
-        if let Ok(shortcut) = snip_shortcut_str.parse::<Shortcut>() {
-            let handle = app_handle.clone();
-            app.global_shortcut()
-                .on_shortcut(shortcut, move |_app, _sc, event| {
-                    if event.state == ShortcutState::Pressed {
-                        let h = handle.clone();
-                        let _ = handle.run_on_main_thread(move || {
-                            tauri::async_runtime::block_on(async move {
-                                let _ = vision::start_snipping(h).await;
-                            });
-                        });
-                    }
-                })?;
-        }
+    let dispatch = |app: tauri::AppHandle, urls: Vec<url::Url>| {
+        tauri::async_runtime::spawn(async move {
+            for url in urls {
+                if let Err(e) = deep_link::handle_lumen_link(&app, url.as_str()).await {
+                    tracing::error!("❌ Deep link: {}", e);
+                }
+            }
+        });
+    };
+
+    if let Ok(Some(urls)) = app.deep_link().get_current() {
+        dispatch(app.handle().clone(), urls);
     }
 
-    Ok(())
+    let handle = app.handle().clone();
+    app.deep_link().on_open_url(move |event| {
+        dispatch(handle.clone(), event.urls());
+    });
 }
 
 //INFO: Sets up the system tray icon and menu
@@ -238,10 +355,18 @@ fn setup_system_tray(app: &tauri::App) -> Result<(), Box<dyn std::error::Error>>
     //INFO: Create tray menu items
     let show_item = MenuItem::with_id(app, "show", "Show Lumen", true, None::<&str>)?;
     let chat_item = MenuItem::with_id(app, "chat", "Open Chat", true, None::<&str>)?;
+    let focus_label = format!("Focus for {} min", agent::focus::TRAY_FOCUS_MINUTES);
+    let focus_item = MenuItem::with_id(app, "focus", focus_label, true, None::<&str>)?;
     let quit_item = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
 
     //INFO: Build the tray menu
-    let menu = Menu::with_items(app, &[&show_item, &chat_item, &quit_item])?;
+    let menu = Menu::with_items(app, &[&show_item, &chat_item, &focus_item, &quit_item])?;
+
+    //INFO: Keep the focus item so its label can show whether focus is on
+    app.manage(agent::focus::FocusMenuItem(focus_item));
+    if let Some(database) = app.try_state::<Database>() {
+        agent::focus::sync_tray(app.handle(), &database);
+    }
 
     //INFO: Build the tray icon
     let _tray = TrayIconBuilder::new()
@@ -264,6 +389,12 @@ fn setup_system_tray(app: &tauri::App) -> Result<(), Box<dyn std::error::Error>>
                         let _ = window::toggle_overlay(app_handle).await;
                     });
                 }
+                "focus" => {
+                    //INFO: Start a focus session, or end the running one
+                    if let Some(database) = app.try_state::<Database>() {
+                        agent::focus::toggle_from_tray(app, &database);
+                    }
+                }
                 "quit" => {
                     //INFO: Quit the application
                     app.exit(0);
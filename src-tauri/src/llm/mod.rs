@@ -0,0 +1,143 @@
+//INFO: Chat model providers - Gemini by default, or any OpenAI-compatible server
+//NOTE: Conversations are built with the Gemini types (contents, parts, function declarations) everywhere;
+//      each provider translates them to its own wire format. Embeddings, TTS and vision helpers stay on Gemini.
+
+pub mod openai;
+pub mod tool_loop;
+
+use crate::database::queries;
+use crate::error::LumenError;
+use crate::gemini::client::{GeminiChatResponse, GeminiContent, GeminiTool, GenerationConfig};
+use crate::gemini::GeminiClient;
+use anyhow::Result;
+use futures::future::BoxFuture;
+use rusqlite::Connection;
+use std::sync::Arc;
+
+//INFO: Settings keys for picking and configuring the chat provider
+pub const LLM_PROVIDER_SETTING: &str = "llm_provider";
+pub const OPENAI_BASE_URL_SETTING: &str = "openai_base_url";
+pub const OPENAI_MODEL_SETTING: &str = "openai_model";
+
+//INFO: Providers the `llm_provider` setting accepts; the first is the default
+pub const LLM_PROVIDERS: &[&str] = &["gemini", "openai"];
+
+pub const DEFAULT_OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+pub const DEFAULT_OPENAI_MODEL: &str = "gpt-4o-mini";
+
+//INFO: A chat model Lumen can talk to
+pub trait LlmProvider: Send + Sync {
+    //INFO: Provider name as stored in the `llm_provider` setting
+    fn name(&self) -> &'static str;
+
+    //INFO: Plain chat, no tools
+    fn chat<'a>(
+        &'a self,
+        messages: Vec<GeminiContent>,
+        system_instruction: Option<&'a str>,
+        generation_config: Option<GenerationConfig>,
+    ) -> BoxFuture<'a, Result<GeminiChatResponse>>;
+
+    //INFO: Chat where the model may answer with function calls instead of text
+    fn chat_with_tools<'a>(
+        &'a self,
+        messages: Vec<GeminiContent>,
+        system_instruction: Option<&'a str>,
+        tools: Vec<GeminiTool>,
+        generation_config: Option<GenerationConfig>,
+    ) -> BoxFuture<'a, Result<GeminiChatResponse>>;
+
+    //INFO: Sends a tiny request to check the credentials/endpoint work
+    fn test_connection(&self) -> BoxFuture<'_, Result<bool>>;
+}
+
+impl LlmProvider for GeminiClient {
+    fn name(&self) -> &'static str {
+        "gemini"
+    }
+
+    fn chat<'a>(
+        &'a self,
+        messages: Vec<GeminiContent>,
+        system_instruction: Option<&'a str>,
+        generation_config: Option<GenerationConfig>,
+    ) -> BoxFuture<'a, Result<GeminiChatResponse>> {
+        Box::pin(self.send_chat(messages, system_instruction, None, generation_config))
+    }
+
+    fn chat_with_tools<'a>(
+        &'a self,
+        messages: Vec<GeminiContent>,
+        system_instruction: Option<&'a str>,
+        tools: Vec<GeminiTool>,
+        generation_config: Option<GenerationConfig>,
+    ) -> BoxFuture<'a, Result<GeminiChatResponse>> {
+        Box::pin(self.send_chat(messages, system_instruction, Some(tools), generation_config))
+    }
+
+    fn test_connection(&self) -> BoxFuture<'_, Result<bool>> {
+        Box::pin(GeminiClient::test_connection(self))
+    }
+}
+
+//INFO: The selected provider name, falling back to the default for missing or unknown values
+pub fn selected_provider(connection: &Connection) -> &'static str {
+    let saved = queries::get_setting(connection, LLM_PROVIDER_SETTING).ok().flatten();
+    LLM_PROVIDERS
+        .iter()
+        .copied()
+        .find(|p| saved.as_deref().is_some_and(|s| s.eq_ignore_ascii_case(p)))
+        .unwrap_or(LLM_PROVIDERS[0])
+}
+
+//INFO: The decrypted Gemini API key, if one is saved
+pub fn gemini_api_key(connection: &Connection) -> Result<Option<String>, LumenError> {
+    use crate::error::CommandContext;
+
+    queries::get_api_token(connection, "gemini")
+        .command_context("Failed to get API key")?
+        .map(|encrypted| crate::crypto::decrypt_token(&encrypted).command_context("Failed to decrypt API key"))
+        .transpose()
+}
+
+//INFO: The Gemini key for side work (embeddings, memory extraction, daily summaries), if it may be used
+//NOTE: Offline mode only lets the chat provider through, so side work gets no key while it's on
+pub fn background_gemini_key(connection: &Connection) -> Result<Option<String>, LumenError> {
+    if crate::offline::is_offline(connection) {
+        return Ok(None);
+    }
+    gemini_api_key(connection)
+}
+
+//INFO: Builds the chat provider chosen in Settings
+//NOTE: Gemini needs its API key (MissingApiKey otherwise); OpenAI-compatible servers may run without one
+pub fn provider_from_settings(connection: &Connection) -> Result<Arc<dyn LlmProvider>, LumenError> {
+    use crate::error::CommandContext;
+
+    match selected_provider(connection) {
+        "openai" => {
+            let setting = |key: &str, default: &str| {
+                queries::get_setting(connection, key)
+                    .ok()
+                    .flatten()
+                    .filter(|v| !v.trim().is_empty())
+                    .unwrap_or_else(|| default.to_string())
+            };
+            let api_key = queries::get_api_token(connection, "openai")
+                .command_context("Failed to get API key")?
+                .map(|encrypted| crate::crypto::decrypt_token(&encrypted).command_context("Failed to decrypt API key"))
+                .transpose()?;
+            Ok(Arc::new(openai::OpenAiClient::new(
+                &setting(OPENAI_BASE_URL_SETTING, DEFAULT_OPENAI_BASE_URL),
+                &setting(OPENAI_MODEL_SETTING, DEFAULT_OPENAI_MODEL),
+                api_key,
+            )))
+        }
+        _ => {
+            let api_key = gemini_api_key(connection)?.ok_or(LumenError::MissingApiKey)?;
+            Ok(Arc::new(GeminiClient::new(api_key).with_safety_settings(
+                crate::gemini::safety::safety_settings_from_settings(connection),
+            )))
+        }
+    }
+}
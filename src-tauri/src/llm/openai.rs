@@ -0,0 +1,359 @@
+//INFO: OpenAI-compatible chat client (OpenAI, Ollama, LM Studio, anything serving /v1/chat/completions)
+//NOTE: Lumen's conversations are built from Gemini contents; this module translates them to OpenAI
+//      messages on the way out and the reply back into Gemini parts
+
+use super::LlmProvider;
+use crate::error::RateLimitExceeded;
+use crate::gemini::client::{
+    GeminiChatResponse, GeminiContent, GeminiFunctionCall, GeminiPart, GeminiTool, GenerationConfig,
+    UsageMetadata,
+};
+use anyhow::{anyhow, Context, Result};
+use futures::future::BoxFuture;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::sync::OnceLock;
+
+//INFO: Local models can take a while on modest hardware, so this is longer than Gemini's timeout
+const REQUEST_TIMEOUT_SECS: u64 = 120;
+
+static SHARED_HTTP_CLIENT: OnceLock<Client> = OnceLock::new();
+
+//INFO: Client for an OpenAI-compatible chat completions endpoint
+pub struct OpenAiClient {
+    http_client: Client,
+    base_url: String,
+    model: String,
+    api_key: Option<String>,
+}
+
+impl OpenAiClient {
+    //INFO: `base_url` is the API root (e.g. https://api.openai.com/v1 or http://localhost:11434/v1)
+    //NOTE: The API key is optional because local servers usually don't want one
+    pub fn new(base_url: &str, model: &str, api_key: Option<String>) -> Self {
+        let http_client = SHARED_HTTP_CLIENT
+            .get_or_init(|| {
+                Client::builder()
+                    .timeout(std::time::Duration::from_secs(REQUEST_TIMEOUT_SECS))
+                    .build()
+                    .unwrap_or_else(|_| Client::new())
+            })
+            .clone();
+
+        Self {
+            http_client,
+            base_url: base_url.trim().trim_end_matches('/').to_string(),
+            model: model.trim().to_string(),
+            api_key: api_key.filter(|k| !k.trim().is_empty()),
+        }
+    }
+
+    async fn send(
+        &self,
+        messages: Vec<GeminiContent>,
+        system_instruction: Option<&str>,
+        tools: Option<Vec<GeminiTool>>,
+        generation_config: Option<GenerationConfig>,
+    ) -> Result<GeminiChatResponse> {
+        let json_mode = generation_config
+            .as_ref()
+            .and_then(|c| c.response_mime_type.as_deref())
+            .is_some_and(|m| m == "application/json");
+
+        //INFO: OpenAI's json_object mode takes no schema, so the schema is spelled out in the system prompt
+        let mut system = system_instruction.map(String::from);
+        if let Some(schema) = generation_config.as_ref().and_then(|c| c.response_schema.as_ref()) {
+            let instruction = format!("Reply with a single JSON object matching this JSON schema:\n{}", schema);
+            system = Some(match system {
+                Some(s) => format!("{}\n\n{}", s, instruction),
+                None => instruction,
+            });
+        } else if json_mode {
+            system = Some(format!("{}\n\nReply with valid JSON only.", system.unwrap_or_default()).trim().to_string());
+        }
+
+        let mut body = json!({
+            "model": self.model,
+            "messages": to_openai_messages(&messages, system.as_deref()),
+        });
+        let tools = tools.map(|t| to_openai_tools(&t)).unwrap_or_default();
+        if !tools.is_empty() {
+            body["tools"] = Value::Array(tools);
+        }
+        if json_mode {
+            body["response_format"] = json!({ "type": "json_object" });
+        }
+
+        let mut request = self
+            .http_client
+            .post(format!("{}/chat/completions", self.base_url))
+            .json(&body);
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key);
+        }
+
+        let response = request.send().await.context("Failed to send request to the model server")?;
+        let status = response.status();
+        let response_text = response
+            .text()
+            .await
+            .context("Failed to get text from the model server response")?;
+        let json: Value = serde_json::from_str(&response_text)
+            .with_context(|| format!("Failed to parse model server response ({}). Raw: {}", status, response_text))?;
+
+        if let Some(error) = json.get("error") {
+            let message = error
+                .get("message")
+                .and_then(|m| m.as_str())
+                .map(String::from)
+                .unwrap_or_else(|| error.to_string());
+            if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                return Err(RateLimitExceeded(format!("Model server error ({}): {}", status.as_u16(), message)).into());
+            }
+            return Err(anyhow!("Model server error ({}): {}", status.as_u16(), message));
+        }
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(RateLimitExceeded(format!("Model server error ({}): {}", status.as_u16(), response_text)).into());
+        }
+        if !status.is_success() {
+            return Err(anyhow!("Model server error ({}): {}", status.as_u16(), response_text));
+        }
+
+        from_openai_response(&json)
+    }
+}
+
+impl LlmProvider for OpenAiClient {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    fn chat<'a>(
+        &'a self,
+        messages: Vec<GeminiContent>,
+        system_instruction: Option<&'a str>,
+        generation_config: Option<GenerationConfig>,
+    ) -> BoxFuture<'a, Result<GeminiChatResponse>> {
+        Box::pin(self.send(messages, system_instruction, None, generation_config))
+    }
+
+    fn chat_with_tools<'a>(
+        &'a self,
+        messages: Vec<GeminiContent>,
+        system_instruction: Option<&'a str>,
+        tools: Vec<GeminiTool>,
+        generation_config: Option<GenerationConfig>,
+    ) -> BoxFuture<'a, Result<GeminiChatResponse>> {
+        Box::pin(self.send(messages, system_instruction, Some(tools), generation_config))
+    }
+
+    fn test_connection(&self) -> BoxFuture<'_, Result<bool>> {
+        Box::pin(async move {
+            let request = vec![GeminiContent {
+                role: Some("user".to_string()),
+                parts: vec![GeminiPart::text("Say 'Hello' in one word.".to_string())],
+            }];
+            Ok(self.send(request, None, None, None).await.is_ok())
+        })
+    }
+}
+
+//INFO: Gemini function declarations as OpenAI tools (both use JSON schema for parameters)
+fn to_openai_tools(tools: &[GeminiTool]) -> Vec<Value> {
+    tools
+        .iter()
+        .flat_map(|t| t.function_declarations.iter())
+        .map(|declaration| {
+            json!({
+                "type": "function",
+                "function": {
+                    "name": declaration.name,
+                    "description": declaration.description,
+                    "parameters": declaration
+                        .parameters
+                        .clone()
+                        .unwrap_or_else(|| json!({ "type": "object", "properties": {} })),
+                }
+            })
+        })
+        .collect()
+}
+
+//INFO: Converts a Gemini conversation into OpenAI chat messages
+//NOTE: Gemini matches function responses to calls by name and order, OpenAI by id. Ids are made up per
+//      model turn and handed to the following function responses in order.
+fn to_openai_messages(contents: &[GeminiContent], system_instruction: Option<&str>) -> Vec<Value> {
+    let mut messages = Vec::new();
+    if let Some(system) = system_instruction.filter(|s| !s.is_empty()) {
+        messages.push(json!({ "role": "system", "content": system }));
+    }
+
+    let mut pending_calls: VecDeque<(String, String)> = VecDeque::new();
+    for (turn, content) in contents.iter().enumerate() {
+        let is_model = content.role.as_deref() == Some("model");
+        let text = content
+            .parts
+            .iter()
+            .filter_map(|p| p.text.as_deref())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        if is_model {
+            let tool_calls: Vec<Value> = content
+                .parts
+                .iter()
+                .filter_map(|p| p.function_call.as_ref())
+                .enumerate()
+                .map(|(index, call)| {
+                    let id = format!("call_{}_{}", turn, index);
+                    pending_calls.push_back((id.clone(), call.name.clone()));
+                    json!({
+                        "id": id,
+                        "type": "function",
+                        "function": { "name": call.name, "arguments": call.args.to_string() }
+                    })
+                })
+                .collect();
+
+            let text = if text.is_empty() { Value::Null } else { Value::String(text) };
+            let mut message = json!({ "role": "assistant", "content": text });
+            if !tool_calls.is_empty() {
+                message["tool_calls"] = Value::Array(tool_calls);
+            }
+            messages.push(message);
+            continue;
+        }
+
+        for response in content.parts.iter().filter_map(|p| p.function_response.as_ref()) {
+            let position = pending_calls.iter().position(|(_, name)| *name == response.name).unwrap_or(0);
+            let id = pending_calls
+                .remove(position)
+                .map(|(id, _)| id)
+                .unwrap_or_else(|| format!("call_{}_orphan", turn));
+            messages.push(json!({
+                "role": "tool",
+                "tool_call_id": id,
+                "content": response.response.to_string(),
+            }));
+        }
+
+        let images: Vec<Value> = content
+            .parts
+            .iter()
+            .filter_map(|p| p.inline_data.as_ref())
+            .map(|image| {
+                json!({
+                    "type": "image_url",
+                    "image_url": { "url": format!("data:{};base64,{}", image.mime_type, image.data) }
+                })
+            })
+            .collect();
+
+        if images.is_empty() {
+            if !text.is_empty() {
+                messages.push(json!({ "role": "user", "content": text }));
+            }
+        } else {
+            let mut parts = vec![json!({ "type": "text", "text": text })];
+            parts.extend(images);
+            messages.push(json!({ "role": "user", "content": parts }));
+        }
+    }
+    messages
+}
+
+//INFO: Turns the first choice of a chat completion into Gemini parts
+fn from_openai_response(json: &Value) -> Result<GeminiChatResponse> {
+    let choice = json
+        .get("choices")
+        .and_then(|c| c.get(0))
+        .ok_or_else(|| anyhow!("Empty response choices from the model server"))?;
+    let message = choice.get("message").cloned().unwrap_or(Value::Null);
+
+    let mut parts = Vec::new();
+    if let Some(text) = message.get("content").and_then(|c| c.as_str()).filter(|t| !t.is_empty()) {
+        parts.push(GeminiPart::text(text.to_string()));
+    }
+    for call in message.get("tool_calls").and_then(|c| c.as_array()).into_iter().flatten() {
+        let Some(function) = call.get("function") else {
+            continue;
+        };
+        let name = function.get("name").and_then(|n| n.as_str()).unwrap_or_default().to_string();
+        //NOTE: Arguments arrive as a JSON string; some local servers send an object instead
+        let args = match function.get("arguments") {
+            Some(Value::String(raw)) => serde_json::from_str(raw).unwrap_or_else(|_| json!({})),
+            Some(value) => value.clone(),
+            None => json!({}),
+        };
+        parts.push(GeminiPart::function_call(GeminiFunctionCall { name, args }));
+    }
+
+    if parts.is_empty() {
+        if choice.get("finish_reason").and_then(|r| r.as_str()) == Some("content_filter") {
+            return Err(anyhow!("The model server withheld its reply (content_filter). Try rephrasing."));
+        }
+        return Err(anyhow!("Empty response from the model server"));
+    }
+
+    let usage = json.get("usage").map(|u| {
+        let count = |key: &str| u.get(key).and_then(|v| v.as_i64()).unwrap_or(0) as i32;
+        UsageMetadata {
+            prompt_token_count: count("prompt_tokens"),
+            candidates_token_count: count("completion_tokens"),
+            total_token_count: count("total_tokens"),
+        }
+    });
+
+    Ok(GeminiChatResponse { parts, usage })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_calls_round_trip() {
+        let reply = json!({
+            "choices": [{
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "abc",
+                        "type": "function",
+                        "function": { "name": "get_weather", "arguments": "{\"city\":\"Lagos\"}" }
+                    }]
+                },
+                "finish_reason": "tool_calls"
+            }],
+            "usage": { "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15 }
+        });
+        let response = from_openai_response(&reply).unwrap();
+        let call = response.parts[0].function_call.as_ref().unwrap();
+        assert_eq!(call.name, "get_weather");
+        assert_eq!(call.args["city"], "Lagos");
+        assert_eq!(response.usage.unwrap().total_token_count, 15);
+
+        let conversation = vec![
+            GeminiContent {
+                role: Some("user".to_string()),
+                parts: vec![GeminiPart::text("Weather?".to_string())],
+            },
+            GeminiContent {
+                role: Some("model".to_string()),
+                parts: response.parts,
+            },
+            GeminiContent {
+                role: Some("user".to_string()),
+                parts: vec![GeminiPart::function_response("get_weather".to_string(), json!({ "temp": 31 }))],
+            },
+        ];
+        let messages = to_openai_messages(&conversation, Some("Be brief."));
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[0]["role"], "system");
+        assert_eq!(messages[2]["tool_calls"][0]["function"]["name"], "get_weather");
+        assert_eq!(messages[3]["role"], "tool");
+        assert_eq!(messages[3]["tool_call_id"], messages[2]["tool_calls"][0]["id"]);
+    }
+}
@@ -0,0 +1,503 @@
+//INFO: The chat tool loop - ask the model, run the tools it calls, feed the results back, repeat
+//NOTE: Lives outside `send_chat_message` so it only depends on an LlmProvider and a ToolExecutor,
+//      which lets tests script multi-turn function calls without a network or a Tauri app
+
+use super::LlmProvider;
+use crate::gemini::client::{GeminiContent, GeminiPart, GeminiTool, GenerationConfig, UsageMetadata};
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use std::collections::HashMap;
+
+//INFO: Per-tool call limit within one user message, to stop runaway loops
+pub const MAX_CALLS_PER_TOOL: usize = 5;
+//INFO: Most model turns one user message may take before the loop gives up on tools (see `run_tool_loop`)
+pub const DEFAULT_MAX_TOOL_ROUNDS: usize = 7;
+//NOTE: Upper bound for the user's setting, so a typo can't let one message run up a huge bill
+pub const MAX_TOOL_ROUNDS_LIMIT: usize = 10;
+
+//INFO: Sent when the round cap is hit, before the final text-only call
+const ROUND_LIMIT_NOTICE: &str = "[TOOL LIMIT REACHED] You can't call any more tools for this message. Summarize what you did and found so far, and say what's left if the task isn't finished.";
+
+//INFO: Tools whose image result is attached to the conversation as a picture rather than sent as JSON
+const SCREENSHOT_TOOLS: &[&str] = &["take_screenshot", "take_window_screenshot"];
+
+//INFO: Runs the tools the model asks for, and hears about the loop's progress
+pub trait ToolExecutor: Send + Sync {
+    //INFO: Runs one tool call; Err(reason) when it wasn't allowed to run (e.g. the user declined it)
+    fn execute<'a>(
+        &'a self,
+        name: &'a str,
+        args: &'a serde_json::Value,
+    ) -> BoxFuture<'a, std::result::Result<serde_json::Value, String>>;
+
+    //INFO: Raw text the model produced in a turn (usually the JSON reply), for live display
+    //NOTE: Called once per turn with all of that turn's text, and not again for a turn that repeats the last one
+    fn on_reply_text(&self, _text: &str) {}
+
+    //INFO: A turn called tools, so any text shown for it is stale
+    fn on_tool_round(&self) {}
+
+    //INFO: Token usage of each model call
+    fn on_usage(&self, _usage: Option<&UsageMetadata>) {}
+}
+
+//INFO: What the loop ended with
+#[derive(Debug)]
+pub struct ToolLoopOutcome {
+    //INFO: Raw text of each model turn that said something, oldest first (see `push_turn_text`)
+    pub turns: Vec<String>,
+    pub cancelled: bool,
+    //INFO: Model turns taken, not counting the forced text-only call
+    pub rounds: usize,
+    //INFO: The model still wanted tools after `max_rounds` turns and was asked to wrap up
+    pub hit_round_limit: bool,
+    //INFO: The conversation as sent to the model, including tool calls and results
+    pub messages: Vec<GeminiContent>,
+}
+
+//INFO: Runs the tool loop for one user message
+//NOTE: `messages` must already end with the user's message. `is_cancelled` is checked before each turn
+//      and after each model reply; a cancelled turn keeps its text but doesn't run its tools.
+//      `max_rounds` is clamped to 1..=MAX_TOOL_ROUNDS_LIMIT; if the model is still calling tools when
+//      it runs out, it gets one more call with tools disabled to summarize where it got to.
+#[allow(clippy::too_many_arguments)]
+pub async fn run_tool_loop(
+    provider: &dyn LlmProvider,
+    executor: &dyn ToolExecutor,
+    mut messages: Vec<GeminiContent>,
+    system_instruction: &str,
+    tools: Vec<GeminiTool>,
+    config: GenerationConfig,
+    max_rounds: usize,
+    is_cancelled: &(dyn Fn() -> bool + Send + Sync),
+) -> Result<ToolLoopOutcome> {
+    let max_rounds = max_rounds.clamp(1, MAX_TOOL_ROUNDS_LIMIT);
+    let mut turns: Vec<String> = Vec::new();
+    let mut cancelled = false;
+    let mut rounds = 0;
+    let mut hit_round_limit = false;
+    let mut tool_call_counts: HashMap<String, usize> = HashMap::new();
+
+    for round in 0..max_rounds {
+        tracing::debug!(round, "Chat: Starting tool round");
+        if is_cancelled() {
+            cancelled = true;
+            break;
+        }
+
+        rounds += 1;
+        let response = provider
+            .chat_with_tools(messages.clone(), Some(system_instruction), tools.clone(), Some(config.clone()))
+            .await
+            .context("Failed to get AI response")?;
+        executor.on_usage(response.usage.as_ref());
+        let response_parts = response.parts;
+
+        //INFO: Stopped while the model was thinking - keep its text but don't run any of the tools it asked for
+        if is_cancelled() {
+            cancelled = true;
+            push_turn_text(&mut turns, &response_parts);
+            break;
+        }
+
+        messages.push(GeminiContent {
+            role: Some("model".to_string()),
+            parts: response_parts.clone(),
+        });
+
+        let mut has_function_calls = false;
+        let mut function_responses = Vec::new();
+
+        if let Some(text) = push_turn_text(&mut turns, &response_parts) {
+            executor.on_reply_text(text);
+        }
+
+        for call in response_parts.iter().filter_map(|p| p.function_call.as_ref()) {
+            let count = tool_call_counts.entry(call.name.clone()).or_insert(0);
+            *count += 1;
+
+            let result = if *count > MAX_CALLS_PER_TOOL {
+                tracing::debug!("⚠️ Tool '{}' hit call limit ({}), skipping.", call.name, MAX_CALLS_PER_TOOL);
+                serde_json::json!({ "error": format!("Tool '{}' has already been called {} times this turn. Please provide your response now using the information you already have.", call.name, MAX_CALLS_PER_TOOL) })
+            } else {
+                has_function_calls = true;
+                //INFO: Declined or unanswered - tell the model instead of running the tool
+                executor
+                    .execute(&call.name, &call.args)
+                    .await
+                    .unwrap_or_else(|reason| serde_json::json!({ "error": reason }))
+            };
+            function_responses.push(GeminiPart::function_response(call.name.clone(), result));
+        }
+
+        if !has_function_calls {
+            break;
+        }
+        hit_round_limit = round + 1 == max_rounds;
+
+        executor.on_tool_round();
+        let screenshot = take_screenshot_data(&mut function_responses);
+        messages.push(GeminiContent {
+            role: Some("user".to_string()),
+            parts: function_responses,
+        });
+        if let Some((data, mime_type)) = screenshot {
+            messages.push(GeminiContent {
+                role: Some("user".to_string()),
+                parts: vec![
+                    GeminiPart::text("[VISUAL CONTEXT ATTACHED]".to_string()),
+                    GeminiPart::inline_data(mime_type, data),
+                ],
+            });
+        }
+    }
+
+    //INFO: Out of rounds mid-task - tell the model, then let it summarize without tools
+    if hit_round_limit && !cancelled {
+        tracing::debug!("⚠️ Tool loop hit its {} round limit. Asking for a summary...", max_rounds);
+        messages.push(GeminiContent {
+            role: Some("user".to_string()),
+            parts: vec![GeminiPart::text(ROUND_LIMIT_NOTICE.to_string())],
+        });
+    }
+
+    //INFO: Safety net - if the model used tools but never produced text (or ran out of rounds),
+    //      force one last call WITHOUT tools so it MUST reply with text.
+    if (hit_round_limit || turns.is_empty()) && !cancelled {
+        tracing::debug!("⚠️ Forcing a final text-only call...");
+
+        let forced = provider
+            .chat(messages.clone(), Some(system_instruction), Some(config))
+            .await
+            .context("Failed to get forced response")?;
+        executor.on_usage(forced.usage.as_ref());
+
+        if let Some(text) = push_turn_text(&mut turns, &forced.parts) {
+            executor.on_reply_text(text);
+        }
+    }
+
+    Ok(ToolLoopOutcome {
+        turns,
+        cancelled,
+        rounds,
+        hit_round_limit,
+        messages,
+    })
+}
+
+//INFO: Records a turn's text (all its text parts, in order) and returns it if it was kept
+//NOTE: Each turn is kept whole and separately - a model that repeats a word or phrase from an earlier
+//      turn isn't cut short. Only a turn whose text is identical to the previous one is dropped, which
+//      is the model restating itself after a tool call and would otherwise show up as a second bubble.
+fn push_turn_text<'a>(turns: &'a mut Vec<String>, parts: &[GeminiPart]) -> Option<&'a str> {
+    let text: String = parts.iter().filter_map(|p| p.text.as_deref()).collect();
+    if text.trim().is_empty() || turns.last().is_some_and(|last| last.trim() == text.trim()) {
+        return None;
+    }
+    turns.push(text);
+    turns.last().map(String::as_str)
+}
+
+//INFO: Pulls the image out of a screenshot tool's result so it can be attached as inline data
+//NOTE: The JSON result keeps a short note instead, so the base64 isn't sent twice
+fn take_screenshot_data(function_responses: &mut [GeminiPart]) -> Option<(String, String)> {
+    let mut screenshot = None;
+    for response in function_responses.iter_mut().filter_map(|p| p.function_response.as_mut()) {
+        if !SCREENSHOT_TOOLS.contains(&response.name.as_str()) {
+            continue;
+        }
+        let Some(obj) = response.response.as_object_mut() else {
+            continue;
+        };
+        let Some(data) = obj.get("image_data").and_then(|v| v.as_str()).map(String::from) else {
+            continue;
+        };
+
+        let mime_type = obj
+            .remove("mime_type")
+            .and_then(|v| v.as_str().map(String::from))
+            .unwrap_or_else(|| "image/png".to_string());
+        obj.remove("image_data");
+        obj.insert(
+            "info".into(),
+            serde_json::json!("Screenshot captured successfully. You can now see the image in this Turn."),
+        );
+        screenshot = Some((data, mime_type));
+    }
+    screenshot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gemini::client::{GeminiChatResponse, GeminiFunctionCall};
+    use parking_lot::Mutex;
+    use std::collections::VecDeque;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    //INFO: Provider that replays scripted responses and records what it was sent
+    #[derive(Default)]
+    struct ScriptedProvider {
+        tool_turns: Mutex<VecDeque<Vec<GeminiPart>>>,
+        forced_turns: Mutex<VecDeque<Vec<GeminiPart>>>,
+        tool_requests: Mutex<Vec<Vec<GeminiContent>>>,
+        forced_requests: Mutex<usize>,
+    }
+
+    impl ScriptedProvider {
+        fn new(tool_turns: Vec<Vec<GeminiPart>>, forced_turns: Vec<Vec<GeminiPart>>) -> Self {
+            Self {
+                tool_turns: Mutex::new(tool_turns.into()),
+                forced_turns: Mutex::new(forced_turns.into()),
+                ..Default::default()
+            }
+        }
+
+        fn reply(turns: &Mutex<VecDeque<Vec<GeminiPart>>>) -> Result<GeminiChatResponse> {
+            let parts = turns
+                .lock()
+                .pop_front()
+                .ok_or_else(|| anyhow::anyhow!("script ran out of turns"))?;
+            Ok(GeminiChatResponse { parts, usage: None })
+        }
+    }
+
+    impl LlmProvider for ScriptedProvider {
+        fn name(&self) -> &'static str {
+            "scripted"
+        }
+
+        fn chat<'a>(
+            &'a self,
+            _messages: Vec<GeminiContent>,
+            _system_instruction: Option<&'a str>,
+            _generation_config: Option<GenerationConfig>,
+        ) -> BoxFuture<'a, Result<GeminiChatResponse>> {
+            *self.forced_requests.lock() += 1;
+            Box::pin(async move { Self::reply(&self.forced_turns) })
+        }
+
+        fn chat_with_tools<'a>(
+            &'a self,
+            messages: Vec<GeminiContent>,
+            _system_instruction: Option<&'a str>,
+            _tools: Vec<GeminiTool>,
+            _generation_config: Option<GenerationConfig>,
+        ) -> BoxFuture<'a, Result<GeminiChatResponse>> {
+            self.tool_requests.lock().push(messages);
+            Box::pin(async move { Self::reply(&self.tool_turns) })
+        }
+
+        fn test_connection(&self) -> BoxFuture<'_, Result<bool>> {
+            Box::pin(async { Ok(true) })
+        }
+    }
+
+    //INFO: Tool runner that records calls and replies, and answers screenshots with a fake image
+    #[derive(Default)]
+    struct FakeTools {
+        calls: Mutex<Vec<String>>,
+        replies: Mutex<Vec<String>>,
+    }
+
+    impl ToolExecutor for FakeTools {
+        fn execute<'a>(
+            &'a self,
+            name: &'a str,
+            _args: &'a serde_json::Value,
+        ) -> BoxFuture<'a, std::result::Result<serde_json::Value, String>> {
+            self.calls.lock().push(name.to_string());
+            Box::pin(async move {
+                Ok(match name {
+                    "take_screenshot" => serde_json::json!({ "image_data": "aW1hZ2U=", "mime_type": "image/jpeg" }),
+                    _ => serde_json::json!({ "status": "success" }),
+                })
+            })
+        }
+
+        fn on_reply_text(&self, text: &str) {
+            self.replies.lock().push(text.to_string());
+        }
+    }
+
+    fn call(name: &str) -> GeminiPart {
+        GeminiPart::function_call(GeminiFunctionCall {
+            name: name.to_string(),
+            args: serde_json::json!({}),
+        })
+    }
+
+    fn text(t: &str) -> GeminiPart {
+        GeminiPart::text(t.to_string())
+    }
+
+    async fn run(provider: &ScriptedProvider, tools: &FakeTools) -> ToolLoopOutcome {
+        run_with_rounds(provider, tools, DEFAULT_MAX_TOOL_ROUNDS).await
+    }
+
+    async fn run_with_rounds(provider: &ScriptedProvider, tools: &FakeTools, max_rounds: usize) -> ToolLoopOutcome {
+        let messages = vec![GeminiContent {
+            role: Some("user".to_string()),
+            parts: vec![text("hi")],
+        }];
+        run_tool_loop(provider, tools, messages, "system", Vec::new(), GenerationConfig::default(), max_rounds, &|| false)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_then_text_answer() {
+        let provider = ScriptedProvider::new(vec![vec![call("get_weather")], vec![text("Sunny")]], vec![]);
+        let tools = FakeTools::default();
+
+        let outcome = run(&provider, &tools).await;
+        assert_eq!(outcome.turns, vec!["Sunny"]);
+        assert_eq!(outcome.rounds, 2);
+        assert_eq!(*tools.calls.lock(), vec!["get_weather"]);
+        assert_eq!(*provider.forced_requests.lock(), 0);
+
+        //INFO: The second turn saw the call and its result
+        let requests = provider.tool_requests.lock();
+        let result = requests[1].last().unwrap().parts[0].function_response.as_ref().unwrap();
+        assert_eq!(result.name, "get_weather");
+        assert_eq!(result.response["status"], "success");
+    }
+
+    #[tokio::test]
+    async fn test_screenshot_is_attached_as_inline_image() {
+        let provider = ScriptedProvider::new(vec![vec![call("take_screenshot")], vec![text("A code editor")]], vec![]);
+        let tools = FakeTools::default();
+
+        let outcome = run(&provider, &tools).await;
+        assert_eq!(outcome.turns, vec!["A code editor"]);
+
+        let requests = provider.tool_requests.lock();
+        let [.., results, visual] = requests[1].as_slice() else {
+            panic!("expected the tool result and an image turn");
+        };
+        let response = &results.parts[0].function_response.as_ref().unwrap().response;
+        assert!(response.get("image_data").is_none());
+        assert!(response["info"].as_str().unwrap().contains("Screenshot captured"));
+
+        assert_eq!(visual.parts[0].text.as_deref(), Some("[VISUAL CONTEXT ATTACHED]"));
+        let image = visual.parts[1].inline_data.as_ref().unwrap();
+        assert_eq!(image.mime_type, "image/jpeg");
+        assert_eq!(image.data, "aW1hZ2U=");
+    }
+
+    #[tokio::test]
+    async fn test_stops_after_max_rounds_and_forces_text() {
+        //NOTE: A different tool each turn, so the per-tool limit never kicks in first
+        let turns = (0..DEFAULT_MAX_TOOL_ROUNDS).map(|i| vec![call(&format!("tool_{}", i))]).collect();
+        let provider = ScriptedProvider::new(turns, vec![vec![text("Here's what I found")]]);
+        let tools = FakeTools::default();
+
+        let outcome = run(&provider, &tools).await;
+        assert_eq!(outcome.rounds, DEFAULT_MAX_TOOL_ROUNDS);
+        assert!(outcome.hit_round_limit);
+        assert_eq!(tools.calls.lock().len(), DEFAULT_MAX_TOOL_ROUNDS);
+        assert_eq!(*provider.forced_requests.lock(), 1);
+        assert_eq!(outcome.turns, vec!["Here's what I found"]);
+    }
+
+    #[tokio::test]
+    async fn test_round_limit_asks_for_summary_even_after_text() {
+        let provider = ScriptedProvider::new(
+            vec![
+                vec![text("Reading the note"), call("read_file")],
+                vec![text("Editing line 3"), call("edit_file_line")],
+            ],
+            vec![vec![text("I read the note and fixed line 3; line 7 still needs the same fix")]],
+        );
+        let tools = FakeTools::default();
+
+        let outcome = run_with_rounds(&provider, &tools, 2).await;
+        assert!(outcome.hit_round_limit);
+        assert_eq!(outcome.rounds, 2);
+        assert_eq!(*provider.forced_requests.lock(), 1);
+        assert_eq!(outcome.turns.last().unwrap(), "I read the note and fixed line 3; line 7 still needs the same fix");
+        let notice = outcome.messages.last().unwrap().parts[0].text.as_deref().unwrap();
+        assert!(notice.starts_with("[TOOL LIMIT REACHED]"));
+
+        //NOTE: Out-of-range settings are clamped rather than disabling the loop
+        let provider = ScriptedProvider::new(vec![vec![text("Done")]], vec![]);
+        let outcome = run_with_rounds(&provider, &FakeTools::default(), 0).await;
+        assert_eq!((outcome.rounds, outcome.hit_round_limit), (1, false));
+    }
+
+    #[tokio::test]
+    async fn test_tools_without_text_fall_back_to_forced_call() {
+        //INFO: The model calls a tool, then replies with nothing at all
+        let provider = ScriptedProvider::new(vec![vec![call("list_google_tasks")], vec![]], vec![vec![text("You have 2 tasks")]]);
+        let tools = FakeTools::default();
+
+        let outcome = run(&provider, &tools).await;
+        assert_eq!(outcome.rounds, 2);
+        assert_eq!(*provider.forced_requests.lock(), 1);
+        assert_eq!(outcome.turns, vec!["You have 2 tasks"]);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_turn_keeps_text_and_skips_tools() {
+        let provider = ScriptedProvider::new(vec![vec![text("Let me check"), call("get_weather")]], vec![]);
+        let tools = FakeTools::default();
+        let stop = AtomicBool::new(false);
+        let messages = vec![GeminiContent {
+            role: Some("user".to_string()),
+            parts: vec![text("weather?")],
+        }];
+
+        //NOTE: Flips to cancelled once the first turn is in flight
+        let is_cancelled = || stop.swap(true, Ordering::SeqCst);
+        let outcome = run_tool_loop(
+            &provider,
+            &tools,
+            messages,
+            "system",
+            Vec::new(),
+            GenerationConfig::default(),
+            DEFAULT_MAX_TOOL_ROUNDS,
+            &is_cancelled,
+        )
+            .await
+            .unwrap();
+
+        assert!(outcome.cancelled);
+        assert_eq!(outcome.turns, vec!["Let me check"]);
+        assert!(tools.calls.lock().is_empty());
+        assert_eq!(*provider.forced_requests.lock(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_repeated_words_across_turns_are_kept() {
+        //INFO: A substring check against the earlier "Yes, let me check" would have silenced the final "Yes"
+        let provider = ScriptedProvider::new(
+            vec![vec![text("Yes, let me check"), call("get_weather")], vec![text("Yes")]],
+            vec![],
+        );
+        let tools = FakeTools::default();
+
+        let outcome = run(&provider, &tools).await;
+        assert_eq!(outcome.turns, vec!["Yes, let me check", "Yes"]);
+        assert_eq!(*tools.replies.lock(), vec!["Yes, let me check", "Yes"]);
+    }
+
+    #[tokio::test]
+    async fn test_identical_turn_after_tool_call_is_not_repeated() {
+        let provider = ScriptedProvider::new(
+            vec![
+                vec![text("Checking your calendar"), call("get_google_calendar_events")],
+                vec![text("Checking your calendar "), call("list_calendars")],
+                vec![text("Ha"), text("ha, you're free all day")],
+            ],
+            vec![],
+        );
+        let tools = FakeTools::default();
+
+        let outcome = run(&provider, &tools).await;
+        assert_eq!(outcome.turns, vec!["Checking your calendar", "Haha, you're free all day"]);
+        assert_eq!(tools.replies.lock().len(), 2);
+        assert_eq!(tools.calls.lock().len(), 2);
+    }
+}
@@ -0,0 +1,291 @@
+//INFO: Optional local HTTP API so scripts and other apps can drive Lumen (e.g. a "remind me" shell alias)
+//NOTE: Off unless the `local_api_enabled` setting is "true". Binds to 127.0.0.1 only, and every request
+//NOTE: must carry `Authorization: Bearer <token>`; the token is generated on enable, stored encrypted
+//NOTE: in api_tokens as "local_api", and shown (and copyable) in Settings. Anything without it gets a 401.
+//
+//  POST /v1/chat               {"message": "...", "session_id": "..."?}  -> SendMessageResponse
+//  POST /v1/reminders          {"content": "...", "due_at": "..."?}       -> {"id": 1}
+//  POST /v1/briefing/refresh                                             -> DashboardBriefing
+//
+//  curl -H "Authorization: Bearer $LUMEN_TOKEN" -d '{"content":"Stretch"}' http://127.0.0.1:18260/v1/reminders
+
+use crate::commands::chat::{ChatCancellation, SendMessageRequest};
+use crate::database::{queries, Database};
+use anyhow::{anyhow, Context, Result};
+use parking_lot::Mutex;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::io::Read;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+pub const LOCAL_API_ENABLED_SETTING: &str = "local_api_enabled";
+pub const LOCAL_API_PORT_SETTING: &str = "local_api_port";
+pub const DEFAULT_LOCAL_API_PORT: u16 = 18260;
+
+//INFO: api_tokens provider name the bearer token is stored under
+const TOKEN_PROVIDER: &str = "local_api";
+const TOKEN_BYTES: usize = 32;
+const MAX_BODY_BYTES: u64 = 64 * 1024;
+
+//INFO: Handle to the running server, kept in app state so it can be stopped when disabled
+#[derive(Default)]
+pub struct LocalApiServer {
+    server: Mutex<Option<Arc<Server>>>,
+}
+
+//INFO: What the settings page shows about the local API
+#[derive(Debug, Serialize)]
+pub struct LocalApiStatus {
+    pub enabled: bool,
+    pub running: bool,
+    pub port: u16,
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatBody {
+    message: String,
+    session_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReminderBody {
+    content: String,
+    due_at: Option<String>,
+}
+
+pub fn is_enabled(connection: &rusqlite::Connection) -> bool {
+    queries::get_setting(connection, LOCAL_API_ENABLED_SETTING)
+        .ok()
+        .flatten()
+        .is_some_and(|v| v == "true")
+}
+
+pub fn port(connection: &rusqlite::Connection) -> u16 {
+    queries::get_setting(connection, LOCAL_API_PORT_SETTING)
+        .ok()
+        .flatten()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_LOCAL_API_PORT)
+}
+
+//INFO: Reads the stored bearer token, if one has been generated
+pub fn get_token(connection: &rusqlite::Connection) -> Result<Option<String>> {
+    queries::get_api_token(connection, TOKEN_PROVIDER)?
+        .map(|encrypted| crate::crypto::decrypt_token(&encrypted))
+        .transpose()
+        .context("Failed to decrypt local API token")
+}
+
+//INFO: Returns the existing token or generates (and stores) a new one
+pub fn get_or_create_token(connection: &rusqlite::Connection) -> Result<String> {
+    if let Some(token) = get_token(connection)? {
+        return Ok(token);
+    }
+
+    let mut bytes = [0u8; TOKEN_BYTES];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let token: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+
+    let _key = crate::crypto::hold_encryption_key();
+    let encrypted = crate::crypto::encrypt_token(&token)?;
+    queries::save_api_token(connection, TOKEN_PROVIDER, &encrypted, "bearer")?;
+    Ok(token)
+}
+
+pub fn status(app: &AppHandle, connection: &rusqlite::Connection) -> LocalApiStatus {
+    LocalApiStatus {
+        enabled: is_enabled(connection),
+        running: app.state::<LocalApiServer>().server.lock().is_some(),
+        port: port(connection),
+        token: get_token(connection).ok().flatten(),
+    }
+}
+
+//INFO: Starts the server if the setting is on (no-op if it's already running)
+pub fn start_if_enabled(app: &AppHandle) -> Result<()> {
+    let (token, port) = {
+        let database = app.state::<Database>();
+        let connection = database.connection.lock()?;
+        if !is_enabled(&connection) {
+            return Ok(());
+        }
+        (get_or_create_token(&connection)?, port(&connection))
+    };
+
+    let state = app.state::<LocalApiServer>();
+    let mut slot = state.server.lock();
+    if slot.is_some() {
+        return Ok(());
+    }
+
+    let server = Arc::new(
+        Server::http(("127.0.0.1", port))
+            .map_err(|e| anyhow!("Failed to bind local API on 127.0.0.1:{}: {}", port, e))?,
+    );
+    *slot = Some(server.clone());
+
+    tracing::info!("🔌 Local API: Listening on http://127.0.0.1:{}", port);
+    //NOTE: The token is a secret and logs end up in bug reports, so it's only shown in Settings
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let app = app.clone();
+            let token = token.clone();
+            //INFO: Chat and briefing calls can take a while, so don't block other requests on them
+            tauri::async_runtime::spawn(async move {
+                handle_request(&app, &token, request).await;
+            });
+        }
+        tracing::info!("🔌 Local API: Stopped");
+    });
+
+    Ok(())
+}
+
+//INFO: Stops the server if it's running
+pub fn stop(app: &AppHandle) {
+    if let Some(server) = app.state::<LocalApiServer>().server.lock().take() {
+        server.unblock();
+    }
+}
+
+//INFO: Compares tokens without bailing out at the first differing byte
+fn token_matches(expected: &str, provided: &str) -> bool {
+    expected.len() == provided.len()
+        && expected
+            .bytes()
+            .zip(provided.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+fn bearer_token(request: &Request) -> Option<String> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Authorization"))
+        .and_then(|h| h.value.as_str().strip_prefix("Bearer "))
+        .map(|t| t.trim().to_string())
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(request: &mut Request) -> Result<T> {
+    let mut body = String::new();
+    request
+        .as_reader()
+        .take(MAX_BODY_BYTES)
+        .read_to_string(&mut body)
+        .context("Failed to read request body")?;
+    serde_json::from_str(&body).context("Invalid JSON body")
+}
+
+fn respond(request: Request, status: u16, body: serde_json::Value) {
+    let header = Header::from_bytes("Content-Type", "application/json").expect("valid header");
+    let response = Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(header);
+    if let Err(e) = request.respond(response) {
+        tracing::error!("❌ Local API: Failed to send response: {}", e);
+    }
+}
+
+async fn handle_request(app: &AppHandle, token: &str, mut request: Request) {
+    if !bearer_token(&request).is_some_and(|provided| token_matches(token, &provided)) {
+        respond(request, 401, json!({ "error": "Missing or invalid bearer token" }));
+        return;
+    }
+
+    let method = request.method().clone();
+    let path = request.url().split('?').next().unwrap_or("").to_string();
+    tracing::info!("🔌 Local API: {} {}", method, path);
+
+    match (method, path.as_str()) {
+        (Method::Post, "/v1/chat") => {
+            let body = match read_json::<ChatBody>(&mut request) {
+                Ok(body) => body,
+                Err(e) => return respond(request, 400, json!({ "error": format!("{:#}", e) })),
+            };
+
+            let result = crate::commands::chat::send_chat_message(
+                app.clone(),
+                app.state::<Database>(),
+                app.state::<ChatCancellation>(),
+                SendMessageRequest {
+                    message: body.message,
+                    session_id: body.session_id,
+                    base64_image: None,
+                },
+            )
+            .await;
+
+            match result {
+                Ok(response) => {
+                    //INFO: Show the exchange in the overlay like any other message
+                    let _ = app.emit("assistant-message", &response.user_message);
+                    let _ = app.emit("assistant-message", &response.assistant_message);
+                    respond(request, 200, json!(response));
+                }
+                Err(e) => respond(request, 500, json!(e)),
+            }
+        }
+        (Method::Post, "/v1/reminders") => {
+            let body = match read_json::<ReminderBody>(&mut request) {
+                Ok(body) if !body.content.trim().is_empty() => body,
+                Ok(_) => return respond(request, 400, json!({ "error": "Reminder content cannot be empty" })),
+                Err(e) => return respond(request, 400, json!({ "error": format!("{:#}", e) })),
+            };
+            if let Err(e) = body.due_at.as_deref().map_or(Ok(()), crate::agent::reminders::check_due_at) {
+                return respond(request, 400, json!({ "error": e }));
+            }
+
+            let result = {
+                let database = app.state::<Database>();
+                database
+                    .connection
+                    .lock()
+                    .and_then(|connection| queries::add_reminder(&connection, body.content.trim(), body.due_at.as_deref()))
+            };
+
+            match result {
+                Ok(id) => {
+                    let _ = app.emit(crate::agent::reminders::REMINDERS_CHANGED_EVENT, id);
+                    respond(request, 200, json!({ "id": id }))
+                }
+                Err(e) => respond(request, 500, json!({ "error": format!("{:#}", e) })),
+            }
+        }
+        (Method::Post, "/v1/briefing/refresh") => {
+            let result = crate::commands::dashboard::refresh_dashboard_briefing(
+                app.state::<Database>(),
+                app.clone(),
+            )
+            .await;
+
+            match result {
+                Ok(briefing) => {
+                    let _ = app.emit("briefing-updated", &briefing);
+                    respond(request, 200, json!(briefing));
+                }
+                Err(e) => respond(request, 500, json!(e)),
+            }
+        }
+        _ => respond(request, 404, json!({ "error": "Not found" })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_matches() {
+        assert!(token_matches("abc123", "abc123"));
+        assert!(!token_matches("abc123", "abc124"));
+        assert!(!token_matches("abc123", "abc12"));
+        assert!(!token_matches("abc123", ""));
+    }
+}
@@ -0,0 +1,102 @@
+//INFO: Structured logging for Lumen
+//NOTE: Logs go to a daily rolling file under the config dir (release builds have no console) and to
+//      stdout; the level can be changed at runtime and is persisted in the `log_level` setting
+
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, Registry};
+
+//INFO: Settings key holding one of LOG_LEVELS
+pub const LOG_LEVEL_SETTING: &str = "log_level";
+
+pub const LOG_LEVELS: &[&str] = &["error", "warn", "info", "debug", "trace"];
+
+const DEFAULT_LOG_LEVEL: LevelFilter = LevelFilter::INFO;
+
+//INFO: Number of daily log files kept before the oldest is deleted
+const MAX_LOG_FILES: usize = 7;
+
+static LEVEL_HANDLE: OnceLock<reload::Handle<LevelFilter, Registry>> = OnceLock::new();
+
+//INFO: Keeps the background writer alive for the whole process so buffered lines get flushed
+static WRITER_GUARD: OnceLock<WorkerGuard> = OnceLock::new();
+
+//INFO: Directory holding the rolling log files
+pub fn log_directory() -> Result<PathBuf> {
+    let config_dir = dirs::config_dir().context("Failed to get config directory")?;
+    Ok(config_dir.join("lumen").join("logs"))
+}
+
+//INFO: Parses a level name (case-insensitive); None for unknown names
+pub fn parse_level(level: &str) -> Option<LevelFilter> {
+    match level.trim().to_lowercase().as_str() {
+        "error" => Some(LevelFilter::ERROR),
+        "warn" => Some(LevelFilter::WARN),
+        "info" => Some(LevelFilter::INFO),
+        "debug" => Some(LevelFilter::DEBUG),
+        "trace" => Some(LevelFilter::TRACE),
+        _ => None,
+    }
+}
+
+//INFO: Installs the global subscriber; call once, before anything logs
+//NOTE: If the log directory can't be created we still log to stdout
+pub fn init() {
+    let (level_layer, handle) = reload::Layer::new(DEFAULT_LOG_LEVEL);
+
+    let file_layer = match log_directory().and_then(|dir| {
+        std::fs::create_dir_all(&dir).context("Failed to create log directory")?;
+        RollingFileAppender::builder()
+            .rotation(Rotation::DAILY)
+            .filename_prefix("lumen")
+            .filename_suffix("log")
+            .max_log_files(MAX_LOG_FILES)
+            .build(dir)
+            .context("Failed to create log file")
+    }) {
+        Ok(appender) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            let _ = WRITER_GUARD.set(guard);
+            Some(fmt::layer().with_writer(writer).with_ansi(false))
+        }
+        Err(e) => {
+            eprintln!("❌ Logging: {:#}", e);
+            None
+        }
+    };
+
+    let initialized = tracing_subscriber::registry()
+        .with(level_layer)
+        .with(file_layer)
+        .with(fmt::layer())
+        .try_init();
+
+    if initialized.is_ok() {
+        let _ = LEVEL_HANDLE.set(handle);
+    }
+}
+
+//INFO: Changes the active log level
+pub fn set_level(level: LevelFilter) -> Result<()> {
+    let handle = LEVEL_HANDLE.get().context("Logging is not initialized")?;
+    handle.reload(level).context("Failed to change log level")
+}
+
+//INFO: Applies the persisted level (called once the database is open)
+pub fn apply_saved_level(connection: &rusqlite::Connection) {
+    let saved = crate::database::queries::get_setting(connection, LOG_LEVEL_SETTING)
+        .ok()
+        .flatten()
+        .and_then(|level| parse_level(&level));
+    if let Some(level) = saved {
+        if let Err(e) = set_level(level) {
+            tracing::warn!("⚠️ Logging: {:#}", e);
+        }
+    }
+}
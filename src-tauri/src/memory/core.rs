@@ -20,6 +20,9 @@ pub enum MemoryType {
     Preference,
     #[serde(rename = "daily_summary")]
     DailySummary,
+    //INFO: Something the user explicitly asked Lumen to remember
+    #[serde(rename = "fact")]
+    Fact,
 }
 
 impl MemoryType {
@@ -30,6 +33,7 @@ impl MemoryType {
             MemoryType::Entity => "entity",
             MemoryType::Preference => "preference",
             MemoryType::DailySummary => "daily_summary",
+            MemoryType::Fact => "fact",
         }
     }
 
@@ -40,6 +44,7 @@ impl MemoryType {
             "entity" => Some(MemoryType::Entity),
             "preference" => Some(MemoryType::Preference),
             "daily_summary" => Some(MemoryType::DailySummary),
+            "fact" => Some(MemoryType::Fact),
             _ => None,
         }
     }
@@ -88,7 +93,7 @@ pub fn store_memory(conn: &Connection, memory: &MemoryItem) -> Result<()> {
             rusqlite::params![memory.id, embedding.as_bytes()],
         )
         .context("Failed to insert memory embedding")?;
-        println!("DEBUG: 🧠 DB: Saved embedding for memory: {}", memory.id);
+        tracing::debug!("🧠 DB: Saved embedding for memory: {}", memory.id);
     }
 
     tx.commit().context("Failed to commit memory transaction")?;
@@ -147,7 +152,7 @@ pub fn retrieve_memories(
     situation_embedding: &[f32],
     top_k: usize,
 ) -> Result<Vec<MemoryItem>> {
-    println!("DEBUG: 🧠 PULSE: Retrieval engine scanning 1000 most recent memories...");
+    tracing::debug!("🧠 PULSE: Retrieval engine scanning 1000 most recent memories...");
     // Step 1: Fetch the most recent 1000 memories (all types)
     let mut stmt = conn
         .prepare(
@@ -272,7 +277,7 @@ fn min_max(values: &[f64]) -> (f64, f64) {
     (min, max)
 }
 
-fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
     if a.len() != b.len() || a.is_empty() {
         return 0.0;
     }
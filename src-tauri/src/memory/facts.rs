@@ -0,0 +1,154 @@
+//INFO: Facts the user explicitly asks Lumen to remember ("I'm allergic to peanuts", "my manager is Sam")
+//NOTE: Stored in the memories table as type 'fact'. Recall is keyword-based for now; `recall_facts` is the
+//      single entry point, so an embedding-backed ranking can replace it without touching callers
+
+use super::core::{MemoryItem, MemoryType};
+use super::extractor::create_memory;
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+
+//INFO: Explicit facts outrank anything the extractor guessed
+const FACT_IMPORTANCE: f64 = 10.0;
+
+//INFO: Facts are short statements, not notes
+const MAX_FACT_CHARS: usize = 500;
+
+//INFO: Words too common to say anything about relevance
+const STOP_WORDS: &[&str] = &[
+    "the", "and", "for", "are", "was", "you", "your", "what", "who", "when", "where", "how", "why",
+    "does", "did", "with", "that", "this", "have", "has", "about", "from", "can", "will", "should",
+    "would", "tell", "know", "remember", "recall",
+];
+
+//INFO: Stores a fact, with its embedding when one could be generated (so retrieve_past_memories finds it too)
+pub fn remember_fact(conn: &Connection, content: &str, embedding: Option<Vec<f32>>) -> Result<MemoryItem> {
+    let content = content.trim();
+    if content.is_empty() {
+        return Err(anyhow!("Nothing to remember"));
+    }
+    if content.chars().count() > MAX_FACT_CHARS {
+        return Err(anyhow!("Facts are limited to {} characters", MAX_FACT_CHARS));
+    }
+
+    let mut memory = create_memory(MemoryType::Fact, content.to_string(), FACT_IMPORTANCE);
+    memory.embedding = embedding;
+    super::core::store_memory(conn, &memory)?;
+    Ok(memory)
+}
+
+//INFO: Lowercased search words from a query, minus short and stop words
+fn keywords(query: &str) -> Vec<String> {
+    let mut words: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .map(|w| w.to_lowercase())
+        .filter(|w| w.chars().count() >= 3 && !STOP_WORDS.contains(&w.as_str()))
+        .collect();
+    words.sort();
+    words.dedup();
+    words
+}
+
+//INFO: Escapes LIKE wildcards so user text matches literally
+fn like_pattern(word: &str) -> String {
+    format!("%{}%", word.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"))
+}
+
+//INFO: Returns the facts most relevant to `query`, ranked by how many of its keywords they contain
+//NOTE: An empty query (or one made only of stop words) returns the most recent facts
+pub fn recall_facts(conn: &Connection, query: &str, limit: usize) -> Result<Vec<MemoryItem>> {
+    let words = keywords(query);
+
+    let mut sql = String::from(
+        "SELECT id, type, content, importance, created_at, last_accessed, access_count
+         FROM memories WHERE type = 'fact'",
+    );
+    if !words.is_empty() {
+        let clauses: Vec<String> = (1..=words.len())
+            .map(|i| format!("LOWER(content) LIKE ?{} ESCAPE '\\'", i))
+            .collect();
+        sql.push_str(&format!(" AND ({})", clauses.join(" OR ")));
+    }
+    sql.push_str(" ORDER BY created_at DESC");
+
+    let patterns: Vec<String> = words.iter().map(|w| like_pattern(w)).collect();
+    let mut stmt = conn.prepare(&sql).context("Failed to prepare fact recall query")?;
+    let mut facts: Vec<MemoryItem> = stmt
+        .query_map(rusqlite::params_from_iter(patterns.iter()), |row| {
+            let parse_time = |value: String| {
+                DateTime::parse_from_rfc3339(&value)
+                    .map(|dt| dt.with_timezone(&Utc))
+                    .unwrap_or_else(|_| Utc::now())
+            };
+            Ok(MemoryItem {
+                id: row.get(0)?,
+                memory_type: MemoryType::Fact,
+                content: row.get(2)?,
+                importance: row.get(3)?,
+                created_at: parse_time(row.get(4)?),
+                last_accessed: parse_time(row.get(5)?),
+                access_count: row.get(6)?,
+                embedding: None,
+                score: 0.0,
+            })
+        })
+        .context("Failed to recall facts")?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    for fact in &mut facts {
+        let content = fact.content.to_lowercase();
+        fact.score = words.iter().filter(|w| content.contains(w.as_str())).count() as f64;
+    }
+    //NOTE: Stable sort keeps newest-first among facts with the same score
+    facts.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+    facts.truncate(limit);
+    Ok(facts)
+}
+
+//INFO: Facts relevant to a chat message, for chat context
+//NOTE: Unlike recall_facts, a message with no keywords ("hi") gets no facts rather than the newest ones
+pub fn facts_for_message(conn: &Connection, message: &str, limit: usize) -> Result<Vec<MemoryItem>> {
+    if keywords(message).is_empty() {
+        return Ok(Vec::new());
+    }
+    recall_facts(conn, message, limit)
+}
+
+//INFO: Formats recalled facts as a chat context section
+pub fn format_facts_for_context(facts: &[MemoryItem]) -> Option<String> {
+    if facts.is_empty() {
+        return None;
+    }
+    let mut output = String::from("Things the user asked you to remember:\n");
+    for fact in facts {
+        output.push_str(&format!("- {}\n", fact.content));
+    }
+    Some(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::initialize_database;
+
+    #[test]
+    fn test_remember_and_recall_facts() {
+        let connection = Connection::open_in_memory().unwrap();
+        initialize_database(&connection).unwrap();
+
+        remember_fact(&connection, "I'm allergic to peanuts", None).unwrap();
+        remember_fact(&connection, "My manager is Sam", None).unwrap();
+        assert!(remember_fact(&connection, "   ", None).is_err());
+
+        let recalled = recall_facts(&connection, "Who is my manager?", 5).unwrap();
+        assert_eq!(recalled.len(), 1);
+        assert_eq!(recalled[0].content, "My manager is Sam");
+
+        //INFO: Wildcards in the query are matched literally
+        assert!(recall_facts(&connection, "100%_", 5).unwrap().is_empty());
+
+        assert_eq!(recall_facts(&connection, "", 5).unwrap().len(), 2);
+        assert!(facts_for_message(&connection, "hi!", 5).unwrap().is_empty());
+    }
+}
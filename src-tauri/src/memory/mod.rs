@@ -3,4 +3,5 @@
 
 pub mod core;
 pub mod extractor;
+pub mod facts;
 pub mod reflection;
@@ -1,15 +1,31 @@
 // src-tauri/src/auth/google.rs
 use anyhow::{anyhow, Result};
-use oauth2::basic::BasicClient;
+use oauth2::basic::{BasicClient, BasicErrorResponseType};
 use oauth2::reqwest::async_http_client;
 use oauth2::{
     AuthUrl, AuthorizationCode, ClientId, ClientSecret, CsrfToken, RedirectUrl, Scope,
     TokenResponse, TokenUrl,
 };
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 use tiny_http::{Response, Server};
 use url::Url;
 
+const REVOKE_URL: &str = "https://oauth2.googleapis.com/revoke";
+
+//INFO: Preferred loopback ports for the OAuth callback, tried in order before falling back to any free port
+const CALLBACK_PORTS: &[u16] = &[18247, 18248, 18249, 18250];
+
+//INFO: How long to wait for the user to finish signing in before giving up
+pub const DEFAULT_CALLBACK_TIMEOUT: Duration = Duration::from_secs(120);
+const CALLBACK_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+//INFO: Google answered a refresh with invalid_grant: the grant was revoked or expired, so only signing in again helps
+#[derive(Debug, thiserror::Error)]
+#[error("Failed to refresh token: {0}")]
+pub struct GrantRevoked(pub String);
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GoogleTokens {
     pub access_token: String,
@@ -28,8 +44,40 @@ impl GoogleAuth {
         Self {
             client_id,
             client_secret,
-            redirect_url: "http://localhost:18247".to_string(), // Random port
+            redirect_url: format!("http://localhost:{}", CALLBACK_PORTS[0]),
+        }
+    }
+
+    //INFO: Points the redirect URL at the port the callback server actually bound
+    pub fn with_redirect_port(mut self, port: u16) -> Self {
+        self.redirect_url = format!("http://localhost:{}", port);
+        self
+    }
+
+    //INFO: Binds the local callback server on the first free candidate port
+    //NOTE: Google accepts any loopback port for desktop clients, so port 0 (OS-assigned) is the last resort
+    pub fn bind_callback_server() -> Result<(Server, u16)> {
+        let mut last_error = None;
+
+        for port in CALLBACK_PORTS.iter().copied().chain(std::iter::once(0)) {
+            match Server::http(("127.0.0.1", port)) {
+                Ok(server) => {
+                    let bound_port = server
+                        .server_addr()
+                        .to_ip()
+                        .map(|addr| addr.port())
+                        .ok_or_else(|| anyhow!("Callback server is not bound to an IP address"))?;
+                    return Ok((server, bound_port));
+                }
+                Err(e) => last_error = Some(e.to_string()),
+            }
         }
+
+        Err(anyhow!(
+            "Could not open a local port for the Google sign-in callback (tried {:?} and an OS-assigned port): {}",
+            CALLBACK_PORTS,
+            last_error.unwrap_or_default()
+        ))
     }
 
     fn get_client(&self) -> Result<BasicClient> {
@@ -58,6 +106,13 @@ impl GoogleAuth {
             .add_scope(Scope::new(
                 "https://www.googleapis.com/auth/gmail.readonly".to_string(),
             ))
+            //NOTE: compose covers drafts; modify covers label changes (mark read, archive)
+            .add_scope(Scope::new(
+                "https://www.googleapis.com/auth/gmail.compose".to_string(),
+            ))
+            .add_scope(Scope::new(
+                "https://www.googleapis.com/auth/gmail.modify".to_string(),
+            ))
             .add_scope(Scope::new(
                 "https://www.googleapis.com/auth/tasks".to_string(),
             ))
@@ -71,25 +126,59 @@ impl GoogleAuth {
         Ok((auth_url.to_string(), csrf_token.secret().to_string()))
     }
 
-    pub fn listen_for_code(&self, expected_state: String) -> Result<String> {
-        let server = Server::http("127.0.0.1:18247")
-            .map_err(|e| anyhow!("Failed to start local server: {}", e))?;
+    //INFO: Waits for the OAuth redirect and returns the authorization code
+    //NOTE: Stray requests (favicon, probes, mismatched state) are answered and ignored; the loop only ends on
+    //      the real callback, an explicit OAuth error, the timeout, or `cancelled` being set.
+    pub fn listen_for_code(
+        &self,
+        server: Server,
+        expected_state: String,
+        timeout: Duration,
+        cancelled: &AtomicBool,
+    ) -> Result<String> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            if cancelled.load(Ordering::SeqCst) {
+                return Err(anyhow!("Google sign-in was cancelled"));
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(anyhow!(
+                    "Timed out after {}s waiting for Google sign-in to complete",
+                    timeout.as_secs()
+                ));
+            }
 
-        if let Some(request) = server.incoming_requests().next() {
-            let url = format!("http://localhost:18247{}", request.url());
-            let parsed_url = Url::parse(&url)?;
+            //INFO: Wake up periodically so cancellation is noticed promptly
+            let Some(request) = server.recv_timeout(remaining.min(CALLBACK_POLL_INTERVAL))? else {
+                continue;
+            };
 
-            let code = parsed_url
-                .query_pairs()
-                .find(|(key, _)| key == "code")
-                .map(|(_, value)| value.into_owned());
+            let url = format!("{}{}", self.redirect_url, request.url());
+            let Ok(parsed_url) = Url::parse(&url) else {
+                let _ = request.respond(Response::empty(400));
+                continue;
+            };
 
-            let state = parsed_url
-                .query_pairs()
-                .find(|(key, _)| key == "state")
-                .map(|(_, value)| value.into_owned());
+            let param = |name: &str| {
+                parsed_url
+                    .query_pairs()
+                    .find(|(key, _)| key == name)
+                    .map(|(_, value)| value.into_owned())
+            };
 
-            match (code, state) {
+            //INFO: Google redirects with ?error=... when the user denies consent
+            if let Some(error) = param("error") {
+                let response = Response::from_string(
+                    "Authentication was not completed. You can close this window now.",
+                );
+                let _ = request.respond(response);
+                return Err(anyhow!("Google sign-in failed: {}", error));
+            }
+
+            match (param("code"), param("state")) {
                 (Some(c), Some(s)) if s == expected_state => {
                     let response = Response::from_string(
                         "Authentication successful! You can close this window now.",
@@ -97,16 +186,16 @@ impl GoogleAuth {
                     request.respond(response)?;
                     return Ok(c);
                 }
+                (Some(_), _) => {
+                    let response = Response::from_string("Authentication failed. State mismatch.")
+                        .with_status_code(400);
+                    let _ = request.respond(response);
+                }
                 _ => {
-                    let response = Response::from_string(
-                        "Authentication failed. State mismatch or no code received.",
-                    );
-                    request.respond(response)?;
-                    return Err(anyhow!("OAuth callback failed"));
+                    let _ = request.respond(Response::empty(404));
                 }
             }
         }
-        Err(anyhow!("No request received"))
     }
 
     pub async fn exchange_code(&self, code: String) -> Result<GoogleTokens> {
@@ -136,7 +225,18 @@ impl GoogleAuth {
             .exchange_refresh_token(&oauth2::RefreshToken::new(refresh_token))
             .request_async(async_http_client)
             .await
-            .map_err(|e| anyhow!("Failed to refresh token: {}", e))?;
+            .map_err(|e| match e {
+                //NOTE: Keeps Google's error code (e.g. invalid_grant) instead of "Server returned error response"
+                oauth2::RequestTokenError::ServerResponse(response)
+                    if *response.error() == BasicErrorResponseType::InvalidGrant =>
+                {
+                    GrantRevoked(response.to_string()).into()
+                }
+                oauth2::RequestTokenError::ServerResponse(response) => {
+                    anyhow!("Failed to refresh token: {}", response)
+                }
+                e => anyhow!("Failed to refresh token: {}", e),
+            })?;
 
         let expires_at = token_result.expires_in().map(|d| {
             chrono::Utc::now() + chrono::Duration::from_std(d).unwrap_or(chrono::Duration::zero())
@@ -150,3 +250,73 @@ impl GoogleAuth {
         })
     }
 }
+
+//INFO: Revokes a Google access or refresh token
+//NOTE: Revoking the refresh token invalidates the whole grant, including any live access tokens
+pub async fn revoke_token(token: &str) -> Result<()> {
+    let response = crate::integrations::http::shared_client()
+        .post(REVOKE_URL)
+        .form(&[("token", token)])
+        .send()
+        .await
+        .map_err(|e| anyhow!("Failed to reach Google revoke endpoint: {}", e))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(anyhow!("Google revoke failed ({}): {}", status, body));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    fn get(port: u16, path: &str) -> String {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        write!(
+            stream,
+            "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+            path
+        )
+        .unwrap();
+        let mut response = String::new();
+        let _ = stream.read_to_string(&mut response);
+        response
+    }
+
+    #[test]
+    fn test_listen_for_code_ignores_stray_requests_until_callback() {
+        let (server, port) = GoogleAuth::bind_callback_server().unwrap();
+        let auth = GoogleAuth::new("id".into(), "secret".into()).with_redirect_port(port);
+
+        let client = std::thread::spawn(move || {
+            assert!(get(port, "/favicon.ico").contains("404"));
+            assert!(get(port, "/?code=evil&state=wrong").contains("400"));
+            get(port, "/?code=abc&state=xyz");
+        });
+
+        let cancelled = AtomicBool::new(false);
+        let code = auth
+            .listen_for_code(server, "xyz".into(), Duration::from_secs(10), &cancelled)
+            .unwrap();
+        client.join().unwrap();
+        assert_eq!(code, "abc");
+    }
+
+    #[test]
+    fn test_listen_for_code_times_out() {
+        let (server, port) = GoogleAuth::bind_callback_server().unwrap();
+        let auth = GoogleAuth::new("id".into(), "secret".into()).with_redirect_port(port);
+
+        let cancelled = AtomicBool::new(false);
+        let err = auth
+            .listen_for_code(server, "xyz".into(), Duration::from_millis(200), &cancelled)
+            .unwrap_err();
+        assert!(err.to_string().contains("Timed out"));
+    }
+}
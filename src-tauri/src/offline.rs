@@ -0,0 +1,37 @@
+//INFO: Offline mode - a single switch that stops Lumen's own outbound requests
+//NOTE: Covers web tools (weather, search, fetch_url), every Google API call, the proactive agent and
+//      Gemini side work (embeddings, memory extraction, image descriptions, briefing audio). The chosen
+//      chat model is still reachable; local tools (files, clipboard, notes) are unaffected.
+
+use crate::database::queries;
+use anyhow::Result;
+use rusqlite::Connection;
+
+//INFO: Settings key; "true" turns offline mode on
+pub const OFFLINE_MODE_SETTING: &str = "offline_mode";
+
+//INFO: Returned (as an error) by anything that would have gone online
+#[derive(Debug, thiserror::Error)]
+#[error("{0} is disabled in offline mode")]
+pub struct OfflineBlocked(pub String);
+
+//INFO: Whether offline mode is on
+pub fn is_offline(connection: &Connection) -> bool {
+    queries::get_setting(connection, OFFLINE_MODE_SETTING)
+        .ok()
+        .flatten()
+        .is_some_and(|v| v == "true")
+}
+
+//INFO: Errors with OfflineBlocked when offline mode is on; `feature` names what was blocked
+pub fn ensure_online(connection: &Connection, feature: &str) -> Result<()> {
+    if is_offline(connection) {
+        return Err(OfflineBlocked(feature.to_string()).into());
+    }
+    Ok(())
+}
+
+//INFO: Tool result for a tool that offline mode blocked
+pub fn offline_tool_result(feature: &str) -> serde_json::Value {
+    serde_json::json!({ "error": OfflineBlocked(feature.to_string()).to_string(), "offline": true })
+}
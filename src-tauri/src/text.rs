@@ -0,0 +1,96 @@
+//INFO: Plain-text helpers shared by anything that shows or speaks model output outside the chat UI
+//NOTE: Used for TTS input and notification bodies, where markdown would be read out or shown raw
+
+use regex::Regex;
+use std::sync::OnceLock;
+
+fn regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).expect("valid markdown regex"))
+}
+
+//INFO: Turns markdown into readable plain text
+//NOTE: Keeps the words (link text, code, emphasised text) and drops the syntax around them.
+//      `lumen://open` links become the file name they point at.
+pub fn strip_markdown(text: &str) -> String {
+    static FENCE: OnceLock<Regex> = OnceLock::new();
+    static LUMEN_AUTOLINK: OnceLock<Regex> = OnceLock::new();
+    static LINK: OnceLock<Regex> = OnceLock::new();
+    static INLINE_CODE: OnceLock<Regex> = OnceLock::new();
+    static HEADING: OnceLock<Regex> = OnceLock::new();
+    static QUOTE: OnceLock<Regex> = OnceLock::new();
+    static RULE: OnceLock<Regex> = OnceLock::new();
+    static BULLET: OnceLock<Regex> = OnceLock::new();
+    static BOLD: OnceLock<Regex> = OnceLock::new();
+    static ITALIC: OnceLock<Regex> = OnceLock::new();
+    static UNDERSCORE: OnceLock<Regex> = OnceLock::new();
+    static STRIKE: OnceLock<Regex> = OnceLock::new();
+    static BLANK_LINES: OnceLock<Regex> = OnceLock::new();
+
+    // 1. Code fences: keep the code, drop the ``` lines
+    let text = regex(&FENCE, r"(?m)^[ \t]*```[^\n]*\n?").replace_all(text, "");
+
+    // 2. Links and images: keep the label; bare lumen:// links become the file name
+    let text = regex(&LINK, r"!?\[([^\]]*)\]\((?:<[^>]*>|[^)\s]*)(?:\s+[^)]*)?\)").replace_all(&text, "$1");
+    let text = regex(&LUMEN_AUTOLINK, r"<(lumen://[^>\s]*)>")
+        .replace_all(&text, |caps: &regex::Captures| lumen_link_label(&caps[1]));
+    let text = regex(&INLINE_CODE, r"`([^`\n]*)`").replace_all(&text, "$1");
+
+    // 3. Block syntax
+    let text = regex(&HEADING, r"(?m)^[ \t]*#{1,6}[ \t]+").replace_all(&text, "");
+    let text = regex(&QUOTE, r"(?m)^[ \t]*>[ \t]?").replace_all(&text, "");
+    let text = regex(&RULE, r"(?m)^[ \t]*([-*_][ \t]*){3,}$").replace_all(&text, "");
+    let text = regex(&BULLET, r"(?m)^([ \t]*)[-*+][ \t]+").replace_all(&text, "$1• ");
+
+    // 4. Emphasis (bold before italic so `**x**` isn't read as two italics)
+    let text = regex(&BOLD, r"\*\*([^*\n]+)\*\*|__([^_\n]+)__").replace_all(&text, "$1$2");
+    let text = regex(&ITALIC, r"\*([^*\n]+)\*").replace_all(&text, "$1");
+    //NOTE: Only underscores at word edges, so snake_case names survive
+    let text = regex(&UNDERSCORE, r"(^|[\s(])_([^_\n]+)_([\s).,!?:;]|$)").replace_all(&text, "$1$2$3");
+    let text = regex(&STRIKE, r"~~([^~\n]+)~~").replace_all(&text, "$1");
+
+    regex(&BLANK_LINES, r"\n{3,}").replace_all(&text, "\n\n").trim().to_string()
+}
+
+//INFO: Readable name for a `lumen://open?path=...` link: the last path component
+fn lumen_link_label(url: &str) -> String {
+    url.split_once("path=")
+        .map(|(_, path)| path.split('&').next().unwrap_or(path))
+        .map(|path| urlencoding::decode(path).map(|p| p.into_owned()).unwrap_or_else(|_| path.to_string()))
+        .and_then(|path| {
+            path.trim_end_matches('/')
+                .rsplit(['/', '\\'])
+                .next()
+                .filter(|name| !name.is_empty())
+                .map(String::from)
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_markdown_headings_and_emphasis() {
+        let input = "## Today\n\n**Standup** at *10am*, then __review__ ~~maybe~~ lunch.";
+        assert_eq!(strip_markdown(input), "Today\n\nStandup at 10am, then review maybe lunch.");
+    }
+
+    #[test]
+    fn test_strip_markdown_code_and_lists() {
+        let input = "Run this:\n```bash\ncargo test\n```\n- use `my_var` here\n* second";
+        assert_eq!(strip_markdown(input), "Run this:\ncargo test\n• use my_var here\n• second");
+    }
+
+    #[test]
+    fn test_strip_markdown_links() {
+        let input = "See [notes](<lumen://open?path=/home/me/vault/Plan%20B.md>), <lumen://open?path=/tmp/report.pdf> and [docs](https://example.com).";
+        assert_eq!(strip_markdown(input), "See notes, report.pdf and docs.");
+    }
+
+    #[test]
+    fn test_strip_markdown_keeps_snake_case() {
+        assert_eq!(strip_markdown("call get_due_reminders now"), "call get_due_reminders now");
+        assert_eq!(strip_markdown("this is _really_ it"), "this is really it");
+    }
+}
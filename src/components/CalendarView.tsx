@@ -35,6 +35,8 @@ const CalendarView: React.FC<CalendarViewProps> = ({ isExpanded, onToggleExpand,
     const [selectedDate, setSelectedDate] = useState(new Date());
     const [events, setEvents] = useState<CalendarEvent[]>([]);
     const [isLoading, setIsLoading] = useState(false);
+    const [cachedAt, setCachedAt] = useState<string | null>(null);
+    const [isOffline, setIsOffline] = useState(false);
 
     // Sync with initialDate if provided
     useEffect(() => {
@@ -56,11 +58,13 @@ const CalendarView: React.FC<CalendarViewProps> = ({ isExpanded, onToggleExpand,
         try {
             const start = startOfMonth(currentDate).toISOString();
             const end = endOfMonth(currentDate).toISOString();
-            const data = await invoke<CalendarEvent[]>('get_calendar_events_for_range', {
+            const data = await invoke<{ events: CalendarEvent[]; source: 'live' | 'cache'; cached_at: string | null }>('get_calendar_events_for_range', {
                 startIso: start,
                 endIso: end
             });
-            setEvents(data || []);
+            setEvents(data.events || []);
+            setIsOffline(data.source === 'cache');
+            setCachedAt(data.cached_at);
         } catch (err) {
             console.error('Failed to fetch calendar events:', err);
         } finally {
@@ -176,6 +180,11 @@ const CalendarView: React.FC<CalendarViewProps> = ({ isExpanded, onToggleExpand,
                     <span className="sticky-day-title">{format(selectedDate, 'EEEE, MMM do')}</span>
                     {isLoading && <Loader2 className="animate-spin opacity-50" size={14} />}
                 </div>
+                {isOffline && (
+                    <div style={{ fontSize: '0.7rem', color: 'var(--color-text-tertiary)', marginBottom: '6px' }}>
+                        Offline, showing cached events{cachedAt ? ` from ${format(parseISO(cachedAt), 'MMM d, h:mm a')}` : ''}
+                    </div>
+                )}
 
                 <div className="sexy-event-list">
                     {selectedEvents.length === 0 ? (
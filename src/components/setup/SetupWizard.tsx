@@ -22,6 +22,7 @@ function SetupWizard({ onComplete }: SetupWizardProps) {
     const [testingApi, setTestingApi] = useState(false);
     const [apiValid, setApiValid] = useState<boolean | null>(null);
     const [vaultPath, setVaultPath] = useState<string | null>(null);
+    const [vaultProblems, setVaultProblems] = useState<string[]>([]);
     const [saving, setSaving] = useState(false);
     const [error, setError] = useState<string | null>(null);
 
@@ -61,7 +62,12 @@ function SetupWizard({ onComplete }: SetupWizardProps) {
     async function selectVault() {
         try {
             const path = await open({ directory: true, title: 'Select Obsidian Vault' });
-            if (path && typeof path === 'string') setVaultPath(path);
+            if (path && typeof path === 'string') {
+                setVaultPath(path);
+                //INFO: Catch a wrong folder now rather than at the first empty briefing
+                const check = await invoke<{ problems: string[] }>('test_obsidian_config', { vaultPath: path });
+                setVaultProblems(check.problems);
+            }
         } catch (err) {
             console.error(err);
         }
@@ -71,7 +77,7 @@ function SetupWizard({ onComplete }: SetupWizardProps) {
         setSaving(true);
         setError(null);
         try {
-            await invoke('setup_save_profile', { request: { display_name: displayName, location: null, theme: 'light' } });
+            await invoke('setup_save_profile', { request: { display_name: displayName, location: null, theme: 'light', timezone: Intl.DateTimeFormat().resolvedOptions().timeZone } });
             await invoke('setup_save_hotkey', { request: { modifier_keys: hotkeyMods, key: hotkeyKey } });
             if (apiKey.trim()) {
                 await invoke('setup_save_api_key', { request: { provider: 'gemini', api_key: apiKey } });
@@ -212,7 +218,11 @@ function SetupWizard({ onComplete }: SetupWizardProps) {
                                     <div className="integration-name">Obsidian</div>
                                     <div className="integration-status">
                                         {vaultPath ? (
-                                            <span style={{ fontSize: 'var(--font-size-xs)', color: 'var(--color-success)' }}>Selected</span>
+                                            vaultProblems.length > 0 ? (
+                                                <span style={{ fontSize: 'var(--font-size-xs)', color: 'var(--color-warning)' }}>{vaultProblems[0]}</span>
+                                            ) : (
+                                                <span style={{ fontSize: 'var(--font-size-xs)', color: 'var(--color-success)' }}>Selected</span>
+                                            )
                                         ) : 'Not connected'}
                                     </div>
                                 </div>
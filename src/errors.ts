@@ -0,0 +1,24 @@
+//INFO: Errors from Tauri commands that return a structured LumenError
+export type LumenErrorCode =
+    | 'missing_api_key'
+    | 'rate_limited'
+    | 'integration_disabled'
+    | 'content_blocked'
+    | 'network'
+    | 'credentials_unreadable'
+    | 'db'
+    | 'other';
+
+export interface LumenError {
+    code: LumenErrorCode;
+    message: string;
+}
+
+export function isLumenError(err: unknown): err is LumenError {
+    return typeof err === 'object' && err !== null && 'code' in err && 'message' in err;
+}
+
+//INFO: Human-readable message for any invoke() rejection (structured or plain string)
+export function errorMessage(err: unknown): string {
+    return isLumenError(err) ? err.message : String(err);
+}
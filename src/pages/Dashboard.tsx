@@ -148,6 +148,22 @@ function Dashboard({ userName }: DashboardProps) {
         loadBriefing();
     }, []);
 
+    //INFO: Pick up briefings refreshed from outside the dashboard (e.g. the local API)
+    useEffect(() => {
+        let unlisten: (() => void) | null = null;
+        async function setupListener() {
+            // @ts-ignore
+            const { listen } = await import('@tauri-apps/api/event');
+            unlisten = await listen<Briefing>('briefing-updated', (event) => {
+                setBriefing(event.payload);
+            });
+        }
+        setupListener();
+        return () => {
+            if (unlisten) unlisten();
+        };
+    }, []);
+
     async function loadBriefing() {
         try {
             setLoading(true);
@@ -268,23 +284,14 @@ function Dashboard({ userName }: DashboardProps) {
                                     // Strip angle brackets that may be added by the pre-processor or AI
                                     const href = (props.href || '').replace(/^<|>$/g, '');
 
-                                    if (href.startsWith('lumen://open')) {
+                                    if (href.startsWith('lumen://')) {
                                         return (
                                             <a
                                                 {...props}
                                                 href="#"
                                                 onClick={(e) => {
                                                     e.preventDefault();
-                                                    try {
-                                                        const url = new URL(href);
-                                                        const rawPath = url.searchParams.get('path');
-                                                        if (rawPath) {
-                                                            const path = decodeURIComponent(rawPath);
-                                                            invoke('open_path', { path });
-                                                        }
-                                                    } catch (err) {
-                                                        console.error('Failed to parse lumen link', err);
-                                                    }
+                                                    invoke('open_lumen_link', { url: href }).catch(console.error);
                                                 }}
                                                 className="lumen-pill"
                                             >
@@ -5,6 +5,7 @@ import { useState, useEffect } from 'react';
 import { Check, ChevronDown, ChevronUp, FolderOpen, AlertCircle } from 'lucide-react';
 import { invoke } from '@tauri-apps/api/core';
 import { open } from '@tauri-apps/plugin-dialog';
+import { errorMessage } from '../errors';
 
 //INFO: Integration type
 interface Integration {
@@ -13,6 +14,32 @@ interface Integration {
     config: string | null;
     last_sync: string | null;
     status: string;
+    last_error?: string | null;
+    last_error_at?: string | null;
+}
+
+//INFO: Short label for an integration's last error, with a hint when signing in again is the fix
+function describeIntegrationError(error: string): string {
+    const reauth = /invalid_grant|insufficient_scope|unauthorized|401/i.test(error);
+    const code = error.match(/invalid_grant|insufficient_scope|invalid_client|unauthorized_client/)?.[0];
+    const label = code ?? (error.length > 80 ? `${error.slice(0, 80)}…` : error);
+    return reauth ? `${label} (re-authenticate)` : label;
+}
+
+//INFO: Payloads of the vault indexer's events
+interface IndexProgress {
+    done: number;
+    total: number;
+    current_file: string;
+}
+
+interface IndexComplete {
+    indexed?: number;
+    unchanged?: number;
+    removed?: number;
+    failed?: number;
+    cancelled?: boolean;
+    error?: string;
 }
 
 //INFO: Brand Icons
@@ -35,11 +62,49 @@ function IntegrationsPage() {
     const [integrations, setIntegrations] = useState<Integration[]>([]);
     const [error, setError] = useState<string | null>(null);
     const [expandedMap, setExpandedMap] = useState<Record<string, boolean>>({});
+    const [indexProgress, setIndexProgress] = useState<IndexProgress | null>(null);
+    const [indexResult, setIndexResult] = useState<string | null>(null);
 
     useEffect(() => {
         loadIntegrations();
     }, []);
 
+    //INFO: Follow the background vault indexer
+    useEffect(() => {
+        let unlistenProgress: (() => void) | undefined;
+        let unlistenComplete: (() => void) | undefined;
+        (async () => {
+            const { listen } = await import('@tauri-apps/api/event');
+            unlistenProgress = await listen<IndexProgress>('vault-index-progress', (event) => {
+                setIndexProgress(event.payload);
+            });
+            unlistenComplete = await listen<IndexComplete>('vault-index-complete', (event) => {
+                const result = event.payload;
+                setIndexProgress(null);
+                if (result.error) {
+                    setIndexResult(`Indexing failed: ${result.error}`);
+                } else {
+                    setIndexResult(`${result.cancelled ? 'Stopped' : 'Done'}: ${result.indexed ?? 0} indexed, ${result.unchanged ?? 0} unchanged, ${result.removed ?? 0} removed${result.failed ? `, ${result.failed} failed` : ''}`);
+                }
+            });
+        })();
+        return () => {
+            if (unlistenProgress) unlistenProgress();
+            if (unlistenComplete) unlistenComplete();
+        };
+    }, []);
+
+    async function startVaultIndex() {
+        try {
+            setIndexResult(null);
+            const started = await invoke<boolean>('index_vault');
+            if (!started) setIndexResult('Indexing is already running');
+            else setIndexProgress({ done: 0, total: 0, current_file: '' });
+        } catch (err) {
+            setError(`Failed to index vault: ${errorMessage(err)}`);
+        }
+    }
+
     async function loadIntegrations() {
         try {
             const data = await invoke<Integration[]>('get_integrations');
@@ -55,7 +120,7 @@ function IntegrationsPage() {
                 } catch (e) { console.error('Failed to parse Google config', e); }
             }
         } catch (err) {
-            setError(`Failed to load integrations: ${err}`);
+            setError(`Failed to load integrations: ${errorMessage(err)}`);
         }
     }
 
@@ -71,6 +136,23 @@ function IntegrationsPage() {
     const [googleClientId, setGoogleClientId] = useState('');
     const [googleClientSecret, setGoogleClientSecret] = useState('');
     const [isAuthenticating, setIsAuthenticating] = useState(false);
+    const [googleAutoRefresh, setGoogleAutoRefresh] = useState(true);
+
+    useEffect(() => {
+        invoke<string | null>('get_app_setting', { key: 'google_auto_refresh' })
+            .then(value => setGoogleAutoRefresh(value !== 'false'))
+            .catch(err => console.error('Failed to load token refresh setting', err));
+    }, []);
+
+    async function toggleGoogleAutoRefresh(enabled: boolean) {
+        setGoogleAutoRefresh(enabled);
+        try {
+            await invoke('save_app_setting', { key: 'google_auto_refresh', value: enabled ? 'true' : 'false' });
+        } catch (err) {
+            setGoogleAutoRefresh(!enabled);
+            setError(`Failed to save token refresh setting: ${errorMessage(err)}`);
+        }
+    }
 
     async function handleGoogleAuth() {
         if (!googleClientId.trim() || !googleClientSecret.trim()) {
@@ -91,7 +173,7 @@ function IntegrationsPage() {
             await invoke('start_google_auth');
             await loadIntegrations();
         } catch (err) {
-            setError(`Google Authentication failed: ${err}`);
+            setError(`Google Authentication failed: ${errorMessage(err)}`);
         } finally {
             setIsAuthenticating(false);
         }
@@ -113,7 +195,7 @@ function IntegrationsPage() {
                 });
                 await loadIntegrations();
             } catch (err) {
-                setError(`Failed to disconnect: ${err}`);
+                setError(`Failed to disconnect: ${errorMessage(err)}`);
             }
         }
     }
@@ -130,7 +212,7 @@ function IntegrationsPage() {
                 });
                 await loadIntegrations();
             } catch (err) {
-                setError(`Failed: ${err}`);
+                setError(`Failed: ${errorMessage(err)}`);
             }
         }
     }
@@ -153,7 +235,7 @@ function IntegrationsPage() {
                 setExpandedMap(prev => ({ ...prev, obsidian: true }));
             }
         } catch (err) {
-            setError(`Failed: ${err}`);
+            setError(`Failed: ${errorMessage(err)}`);
         }
     }
 
@@ -223,7 +305,15 @@ function IntegrationsPage() {
                         </div>
                     </div>
                     <div style={{ display: 'flex', alignItems: 'center', gap: 'var(--spacing-3)' }}>
-                        {google?.enabled && (
+                        {google?.enabled && google.last_error && (
+                            <div
+                                style={{ fontSize: '0.7rem', color: 'var(--color-error)', fontWeight: 500, display: 'flex', alignItems: 'center', gap: '4px' }}
+                                title={`${google.last_error}${google.last_error_at ? ` (${new Date(google.last_error_at).toLocaleString()})` : ''}`}
+                            >
+                                <AlertCircle size={12} /> Error — {describeIntegrationError(google.last_error)}
+                            </div>
+                        )}
+                        {google?.enabled && !google.last_error && (
                             <div style={{ fontSize: '0.7rem', color: 'var(--color-success)', fontWeight: 500, display: 'flex', alignItems: 'center', gap: '4px' }}>
                                 <Check size={12} /> Active
                             </div>
@@ -289,7 +379,29 @@ function IntegrationsPage() {
                             </div>
                         ) : (
                             <div style={{ fontSize: '0.8rem', color: 'var(--color-text-secondary)' }}>
+                                {google.status === 'needs_reauth' && (
+                                    <div style={{ display: 'flex', alignItems: 'center', gap: 'var(--spacing-2)', marginBottom: 'var(--spacing-3)', color: 'var(--color-error)' }}>
+                                        <AlertCircle size={14} />
+                                        <span style={{ flex: 1 }}>Google needs you to sign in again to keep working.</span>
+                                        <button
+                                            className="btn btn-primary btn-sm"
+                                            onClick={handleGoogleAuth}
+                                            disabled={isAuthenticating}
+                                            style={{ fontSize: '0.8rem' }}
+                                        >
+                                            {isAuthenticating ? 'Authenticating...' : 'Reconnect'}
+                                        </button>
+                                    </div>
+                                )}
                                 Connected as user. <span style={{ textDecoration: 'underline', cursor: 'pointer' }} onClick={() => toggleGoogle(false)}>Disconnect</span>
+                                <label style={{ display: 'flex', alignItems: 'center', gap: '6px', marginTop: 'var(--spacing-3)', cursor: 'pointer' }}>
+                                    <input
+                                        type="checkbox"
+                                        checked={googleAutoRefresh}
+                                        onChange={(e) => toggleGoogleAutoRefresh(e.target.checked)}
+                                    />
+                                    Refresh the access token in the background before it expires
+                                </label>
                             </div>
                         )}
                     </div>
@@ -395,12 +507,54 @@ function IntegrationsPage() {
                                                     });
                                                     loadIntegrations();
                                                 }
-                                            } catch (err) { setError(`Failed to select folder: ${err}`); }
+                                            } catch (err) { setError(`Failed to select folder: ${errorMessage(err)}`); }
                                         }}>
                                             <FolderOpen size={14} />
                                         </button>
                                     </div>
                                 </div>
+
+                                <div>
+                                    <label className="input-label" style={{ marginBottom: '4px', display: 'block' }}>Quick Capture Heading</label>
+                                    <input
+                                        type="text"
+                                        className="input"
+                                        style={{ fontSize: '0.8rem', padding: '6px', width: '100%' }}
+                                        defaultValue={(() => {
+                                            try { return JSON.parse(obsidian.config || '{}').quick_capture_heading || '' } catch { return '' }
+                                        })()}
+                                        placeholder="End of today's note"
+                                        onBlur={async (e) => {
+                                            try {
+                                                const config = JSON.parse(obsidian.config || '{}');
+                                                if ((config.quick_capture_heading || '') === e.target.value.trim()) return;
+                                                await invoke('update_integration', {
+                                                    integration: {
+                                                        ...obsidian,
+                                                        config: JSON.stringify({ ...config, quick_capture_heading: e.target.value.trim() })
+                                                    }
+                                                });
+                                                loadIntegrations();
+                                            } catch (err) { setError(`Failed to save heading: ${errorMessage(err)}`); }
+                                        }}
+                                    />
+                                </div>
+
+                                <div>
+                                    <label className="input-label" style={{ marginBottom: '4px', display: 'block' }}>Search Index</label>
+                                    <div style={{ display: 'flex', gap: '8px', alignItems: 'center' }}>
+                                        <span style={{ flex: 1, fontSize: '0.75rem', color: 'var(--color-text-secondary)', overflow: 'hidden', textOverflow: 'ellipsis', whiteSpace: 'nowrap' }}>
+                                            {indexProgress
+                                                ? `Indexing ${indexProgress.done}/${indexProgress.total}${indexProgress.current_file ? ` · ${indexProgress.current_file}` : ''}`
+                                                : indexResult || 'Lets Lumen search your notes by meaning. Only changed notes are re-indexed.'}
+                                        </span>
+                                        {indexProgress ? (
+                                            <button className="btn btn-sm" style={{ fontSize: '0.75rem' }} onClick={() => invoke('cancel_vault_index')}>Stop</button>
+                                        ) : (
+                                            <button className="btn btn-sm" style={{ fontSize: '0.75rem' }} onClick={startVaultIndex}>Index Notes</button>
+                                        )}
+                                    </div>
+                                </div>
                             </div>
                         </div>
                     </div>
@@ -2,12 +2,29 @@ import { useState, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { enable, disable, isEnabled } from '@tauri-apps/plugin-autostart';
 import { CheckCircle2, AlertCircle } from 'lucide-react';
+import { errorMessage } from '../errors';
 
 //INFO: Types
+interface LocalApiStatus {
+    enabled: boolean;
+    running: boolean;
+    port: number;
+    token: string | null;
+}
+
+interface CredentialsHealth {
+    key_error: string | null;
+    unreadable: string[];
+    total: number;
+}
+
 interface UserProfile {
     display_name: string;
     location: string | null;
     theme: string;
+    timezone: string | null;
+    work_start: string | null;
+    work_end: string | null;
 }
 
 interface HotkeyConfig {
@@ -25,14 +42,25 @@ interface ApiKeyStatus {
     masked_key: string | null;
 }
 
+interface LlmSettings {
+    provider: string;
+    providers: string[];
+    openai_base_url: string;
+    openai_model: string;
+    openai_key_configured: boolean;
+}
+
 function SettingsPage() {
     //INFO: State
     const [displayName, setDisplayName] = useState('');
     const [location, setLocation] = useState('');
+    const [workStart, setWorkStart] = useState('');
+    const [workEnd, setWorkEnd] = useState('');
 
     // Main Hotkey
     const [hotkeyModifiers, setHotkeyModifiers] = useState<string[]>(['Super']);
     const [hotkeyKey, setHotkeyKey] = useState('L');
+    const [hotkeyWarnings, setHotkeyWarnings] = useState<string[]>([]);
 
     // Snipper Hotkey
     const [snipperModifiers, setSnipperModifiers] = useState<string[]>(['Super', 'Shift']);
@@ -40,8 +68,23 @@ function SettingsPage() {
 
     const [geminiApiKey, setGeminiApiKey] = useState('');
     const [geminiKeyConfigured, setGeminiKeyConfigured] = useState(false);
+    const [llmProvider, setLlmProvider] = useState('gemini');
+    const [llmProviders, setLlmProviders] = useState<string[]>(['gemini']);
+    const [openaiBaseUrl, setOpenaiBaseUrl] = useState('');
+    const [openaiModel, setOpenaiModel] = useState('');
+    const [openaiApiKey, setOpenaiApiKey] = useState('');
+    const [openaiKeyConfigured, setOpenaiKeyConfigured] = useState(false);
+    const [maxToolRounds, setMaxToolRounds] = useState(7);
     const [databasePath, setDatabasePath] = useState('');
     const [autostartEnabled, setAutostartEnabled] = useState(false);
+    const [offlineMode, setOfflineMode] = useState(false);
+    const [localApi, setLocalApi] = useState<LocalApiStatus | null>(null);
+    const [showLocalApiToken, setShowLocalApiToken] = useState(false);
+    const [credentialsHealth, setCredentialsHealth] = useState<CredentialsHealth | null>(null);
+    const [confirmCredentialsReset, setConfirmCredentialsReset] = useState(false);
+    const [overlayAnchor, setOverlayAnchor] = useState('bottom-left');
+    const [overlayOpacity, setOverlayOpacity] = useState(1);
+    const [overlayOnTop, setOverlayOnTop] = useState(true);
 
     //INFO: UI state
     const [saving, setSaving] = useState(false);
@@ -67,6 +110,8 @@ function SettingsPage() {
             if (profile) {
                 setDisplayName(profile.display_name);
                 setLocation(profile.location || '');
+                setWorkStart(profile.work_start || '');
+                setWorkEnd(profile.work_end || '');
             }
 
             const hotkey = await invoke<HotkeyConfig | null>('get_hotkey');
@@ -81,11 +126,28 @@ function SettingsPage() {
             const geminiStatus = await invoke<ApiKeyStatus>('get_api_key_status', { provider: 'gemini' });
             setGeminiKeyConfigured(geminiStatus.is_configured);
 
+            const llm = await invoke<LlmSettings>('get_llm_settings');
+            setLlmProvider(llm.provider);
+            setLlmProviders(llm.providers);
+            setOpenaiBaseUrl(llm.openai_base_url);
+            setOpenaiModel(llm.openai_model);
+            setOpenaiKeyConfigured(llm.openai_key_configured);
+            setMaxToolRounds(await invoke<number>('get_max_tool_rounds'));
+
             const dbPath = await invoke<string>('get_database_path');
             setDatabasePath(dbPath);
 
             const isAutostart = await isEnabled();
             setAutostartEnabled(isAutostart);
+
+            setOfflineMode(await invoke<boolean>('get_offline_mode'));
+            setLocalApi(await invoke<LocalApiStatus>('get_local_api_status'));
+            setCredentialsHealth(await invoke<CredentialsHealth>('get_credentials_health'));
+
+            setOverlayAnchor(await invoke<string>('get_overlay_anchor'));
+            const appearance = await invoke<{ opacity: number; always_on_top: boolean }>('get_overlay_appearance');
+            setOverlayOpacity(appearance.opacity);
+            setOverlayOnTop(appearance.always_on_top);
         } catch (err) {
             setError(`Failed to load settings: ${err}`);
         }
@@ -105,11 +167,52 @@ function SettingsPage() {
         }
     }
 
+    async function toggleOfflineMode() {
+        try {
+            await invoke('set_offline_mode', { enabled: !offlineMode });
+            setOfflineMode(!offlineMode);
+            setSuccess(offlineMode ? 'Offline mode disabled' : 'Offline mode enabled');
+        } catch (err) {
+            setError(`Failed to update offline mode: ${errorMessage(err)}`);
+        }
+    }
+
+    async function resetCredentials() {
+        setConfirmCredentialsReset(false);
+        try {
+            const cleared = await invoke<string[]>('reset_credentials');
+            setCredentialsHealth(await invoke<CredentialsHealth>('get_credentials_health'));
+            setSuccess(cleared.length > 0 ? `Cleared ${cleared.join(', ')} - re-enter them below` : 'Credentials reset');
+        } catch (err) {
+            setError(`Failed to reset credentials: ${errorMessage(err)}`);
+        }
+    }
+
+    async function toggleLocalApi() {
+        const enabled = !localApi?.enabled;
+        try {
+            setLocalApi(await invoke<LocalApiStatus>('set_local_api_enabled', { enabled }));
+            setSuccess(enabled ? 'Local API enabled' : 'Local API disabled');
+        } catch (err) {
+            setError(`Failed to update local API: ${errorMessage(err)}`);
+        }
+    }
+
+    async function copyLocalApiToken() {
+        if (!localApi?.token) return;
+        try {
+            await navigator.clipboard.writeText(localApi.token);
+            setSuccess('Token copied');
+        } catch (err) {
+            setError(`Failed to copy token: ${errorMessage(err)}`);
+        }
+    }
+
     async function saveProfile() {
         setSaving(true);
         setError(null);
         try {
-            await invoke('update_profile', { request: { display_name: displayName, location: location || null, theme: 'light' } });
+            await invoke('update_profile', { request: { display_name: displayName, location: location || null, theme: 'light', timezone: Intl.DateTimeFormat().resolvedOptions().timeZone, work_start: workStart, work_end: workEnd } });
             setSuccess('Profile saved');
         } catch (err) {
             setError(`Failed to save profile: ${err}`);
@@ -118,11 +221,48 @@ function SettingsPage() {
         }
     }
 
+    async function saveMaxToolRounds(rounds: number) {
+        try {
+            setMaxToolRounds(await invoke<number>('set_max_tool_rounds', { rounds }));
+            setSuccess('Tool step limit updated');
+        } catch (err) {
+            setError(`Failed to update tool step limit: ${errorMessage(err)}`);
+        }
+    }
+
+    async function saveOverlayAnchor(corner: string) {
+        setError(null);
+        try {
+            await invoke('set_overlay_anchor', { corner });
+            setOverlayAnchor(corner);
+            setSuccess('Overlay position updated');
+        } catch (err) {
+            setError(`Failed to move overlay: ${err}`);
+        }
+    }
+
+    async function saveOverlayOpacity(opacity: number) {
+        try {
+            setOverlayOpacity(await invoke<number>('set_overlay_opacity', { opacity }));
+        } catch (err) {
+            setError(`Failed to set overlay opacity: ${err}`);
+        }
+    }
+
+    async function saveOverlayOnTop(enabled: boolean) {
+        try {
+            await invoke('set_overlay_always_on_top', { enabled });
+            setOverlayOnTop(enabled);
+        } catch (err) {
+            setError(`Failed to update overlay: ${err}`);
+        }
+    }
+
     async function saveHotkey() {
         setSaving(true);
         setError(null);
         try {
-            await invoke('update_hotkey', {
+            const warnings = await invoke<string[]>('update_hotkey', {
                 request: {
                     modifier_keys: hotkeyModifiers,
                     key: hotkeyKey,
@@ -132,6 +272,7 @@ function SettingsPage() {
                     snipper_enabled: true
                 }
             });
+            setHotkeyWarnings(warnings);
             setSuccess('Shortcuts saved (restart to apply)');
         } catch (err) {
             setError(`Failed to save hotkey: ${err}`);
@@ -150,7 +291,34 @@ function SettingsPage() {
             setGeminiKeyConfigured(true);
             setSuccess('API key saved');
         } catch (err) {
-            setError(`Failed to save API key: ${err}`);
+            setError(`Failed to save API key: ${errorMessage(err)}`);
+        } finally {
+            setSaving(false);
+        }
+    }
+
+    async function saveLlmProvider() {
+        setSaving(true);
+        setError(null);
+        try {
+            await invoke('set_llm_provider', {
+                provider: llmProvider,
+                openaiBaseUrl: llmProvider === 'openai' ? openaiBaseUrl : null,
+                openaiModel: llmProvider === 'openai' ? openaiModel : null,
+            });
+            if (llmProvider === 'openai' && openaiApiKey.trim()) {
+                await invoke('update_api_key', { request: { provider: 'openai', api_key: openaiApiKey } });
+                setOpenaiApiKey('');
+                setOpenaiKeyConfigured(true);
+            }
+            const ok = await invoke<boolean>('test_llm_provider');
+            if (ok) {
+                setSuccess('Chat provider saved');
+            } else {
+                setError('Chat provider saved, but a test request failed. Check the URL, model and key.');
+            }
+        } catch (err) {
+            setError(`Failed to save chat provider: ${errorMessage(err)}`);
         } finally {
             setSaving(false);
         }
@@ -210,6 +378,32 @@ function SettingsPage() {
                 )}
             </div>
 
+            {/* Unreadable credentials (lost or replaced encryption key) */}
+            {credentialsHealth && (credentialsHealth.key_error || credentialsHealth.unreadable.length > 0) && (
+                <section style={{ marginBottom: 'var(--spacing-6)' }}>
+                    <div className="settings-card" style={{ padding: 'var(--spacing-3) var(--spacing-4)' }}>
+                        <div className="settings-row">
+                            <div className="settings-row-info">
+                                <span className="settings-row-title" style={{ fontSize: '0.9rem', color: 'var(--color-error)' }}>Saved credentials can't be read</span>
+                                <span className="settings-row-description" style={{ fontSize: '0.8rem' }}>
+                                    {confirmCredentialsReset
+                                        ? 'This clears the unreadable credentials and disconnects their integrations. You will need to re-enter them.'
+                                        : `The encryption key is missing or was replaced${credentialsHealth.unreadable.length > 0 ? `, so ${credentialsHealth.unreadable.join(', ')} can't be decrypted` : ''}.`}
+                                </span>
+                            </div>
+                            {confirmCredentialsReset ? (
+                                <div style={{ display: 'flex', gap: 'var(--spacing-2)' }}>
+                                    <button className="btn btn-secondary btn-sm" onClick={resetCredentials}>Reset</button>
+                                    <button className="btn btn-ghost btn-sm" onClick={() => setConfirmCredentialsReset(false)}>Cancel</button>
+                                </div>
+                            ) : (
+                                <button className="btn btn-secondary btn-sm" onClick={() => setConfirmCredentialsReset(true)}>Reset credentials</button>
+                            )}
+                        </div>
+                    </div>
+                </section>
+            )}
+
             {/* General Settings */}
             <section style={{ marginBottom: 'var(--spacing-6)' }}>
                 <h4 style={{
@@ -238,6 +432,56 @@ function SettingsPage() {
                         </label>
                     </div>
 
+                    <div className="settings-row" style={{ marginTop: 'var(--spacing-3)' }}>
+                        <div className="settings-row-info">
+                            <span className="settings-row-title" style={{ fontSize: '0.9rem' }}>Offline Mode</span>
+                            <span className="settings-row-description" style={{ fontSize: '0.8rem' }}>No web, weather or Google requests, and background checks pause. Only your chat model is contacted.</span>
+                        </div>
+                        <label className="switch" style={{ transform: 'scale(0.8)' }}>
+                            <input
+                                type="checkbox"
+                                checked={offlineMode}
+                                onChange={toggleOfflineMode}
+                            />
+                            <span className="slider"></span>
+                        </label>
+                    </div>
+
+                    <div className="settings-row" style={{ marginTop: 'var(--spacing-3)' }}>
+                        <div className="settings-row-info">
+                            <span className="settings-row-title" style={{ fontSize: '0.9rem' }}>Local API</span>
+                            <span className="settings-row-description" style={{ fontSize: '0.8rem' }}>
+                                Let scripts on this computer add reminders, chat or refresh the briefing over http://127.0.0.1{localApi ? `:${localApi.port}` : ''}.
+                            </span>
+                        </div>
+                        <label className="switch" style={{ transform: 'scale(0.8)' }}>
+                            <input
+                                type="checkbox"
+                                checked={localApi?.enabled || false}
+                                onChange={toggleLocalApi}
+                            />
+                            <span className="slider"></span>
+                        </label>
+                    </div>
+                    {localApi?.enabled && (
+                        <div style={{ marginTop: 'var(--spacing-2)', display: 'flex', alignItems: 'center', gap: 'var(--spacing-2)' }}>
+                            <span style={{ fontSize: '0.75rem', color: localApi.running ? 'var(--color-success)' : 'var(--color-error)' }}>
+                                {localApi.running ? 'Running' : 'Not running'}
+                            </span>
+                            <input
+                                type={showLocalApiToken ? 'text' : 'password'}
+                                className="input"
+                                value={localApi.token || ''}
+                                readOnly
+                                style={{ flex: 1, fontSize: '0.8rem', padding: '4px 8px', fontFamily: 'monospace' }}
+                            />
+                            <button className="btn btn-ghost btn-sm" onClick={() => setShowLocalApiToken(!showLocalApiToken)}>
+                                {showLocalApiToken ? 'Hide' : 'Show'}
+                            </button>
+                            <button className="btn btn-secondary btn-sm" onClick={copyLocalApiToken}>Copy</button>
+                        </div>
+                    )}
+
                 </div>
             </section>
 
@@ -279,6 +523,29 @@ function SettingsPage() {
                             Used for local weather updates.
                         </div>
                     </div>
+                    <div style={{ marginBottom: 'var(--spacing-4)' }}>
+                        <label style={{ display: 'block', fontSize: '0.8rem', fontWeight: 500, marginBottom: '4px', color: 'var(--color-text-secondary)' }}>Working Hours</label>
+                        <div style={{ display: 'flex', gap: '8px', alignItems: 'center' }}>
+                            <input
+                                type="time"
+                                className="input"
+                                value={workStart}
+                                onChange={(e) => setWorkStart(e.target.value)}
+                                style={{ fontSize: '0.9rem', padding: '6px 10px' }}
+                            />
+                            <span style={{ color: 'var(--color-text-tertiary)' }}>to</span>
+                            <input
+                                type="time"
+                                className="input"
+                                value={workEnd}
+                                onChange={(e) => setWorkEnd(e.target.value)}
+                                style={{ fontSize: '0.9rem', padding: '6px 10px' }}
+                            />
+                        </div>
+                        <div style={{ fontSize: '0.75rem', color: 'var(--color-text-tertiary)', marginTop: '4px' }}>
+                            Shapes your briefing, and non-urgent notifications wait until you're working.
+                        </div>
+                    </div>
                     <div style={{ display: 'flex', justifyContent: 'flex-end' }}>
                         <button className="btn btn-primary btn-sm" onClick={saveProfile} disabled={saving} style={{ fontSize: '0.8rem' }}>
                             Save Changes
@@ -395,6 +662,46 @@ function SettingsPage() {
                         </div>
                     </div>
 
+                    {/* Overlay Corner */}
+                    <div style={{ marginBottom: 'var(--spacing-3)' }}>
+                        <label style={{ display: 'block', fontSize: '0.8rem', fontWeight: 500, marginBottom: '6px', color: 'var(--color-text-secondary)' }}>Overlay Corner</label>
+                        <select
+                            className="input"
+                            value={overlayAnchor}
+                            onChange={(e) => saveOverlayAnchor(e.target.value)}
+                            style={{ width: '180px', fontSize: '0.85rem' }}
+                        >
+                            <option value="bottom-left">Bottom left</option>
+                            <option value="bottom-right">Bottom right</option>
+                            <option value="top-left">Top left</option>
+                            <option value="top-right">Top right</option>
+                        </select>
+                    </div>
+
+                    {/* Overlay Appearance */}
+                    <div style={{ marginBottom: 'var(--spacing-3)' }}>
+                        <label style={{ display: 'block', fontSize: '0.8rem', fontWeight: 500, marginBottom: '6px', color: 'var(--color-text-secondary)' }}>Overlay Opacity ({Math.round(overlayOpacity * 100)}%)</label>
+                        <input
+                            type="range"
+                            min={0.3}
+                            max={1}
+                            step={0.05}
+                            value={overlayOpacity}
+                            onChange={(e) => saveOverlayOpacity(Number(e.target.value))}
+                            style={{ width: '180px' }}
+                        />
+                        <label style={{ display: 'flex', alignItems: 'center', gap: '8px', fontSize: '0.8rem', marginTop: 'var(--spacing-2)', color: 'var(--color-text-secondary)' }}>
+                            <input type="checkbox" checked={overlayOnTop} onChange={(e) => saveOverlayOnTop(e.target.checked)} />
+                            Keep overlay above other windows
+                        </label>
+                    </div>
+
+                    {hotkeyWarnings.map((warning) => (
+                        <p key={warning} style={{ fontSize: '0.75rem', color: 'var(--color-warning)', margin: 'var(--spacing-2) 0 0' }}>
+                            {warning}
+                        </p>
+                    ))}
+
                     <div style={{ display: 'flex', justifyContent: 'flex-end', alignItems: 'center', marginTop: 'var(--spacing-3)' }}>
                         <button className="btn btn-primary btn-sm" onClick={saveHotkey} disabled={saving} style={{ fontSize: '0.8rem' }}>
                             Update Shortcuts
@@ -451,6 +758,79 @@ function SettingsPage() {
                         </button>
                     </div>
                 </div>
+
+                <div className="settings-card" style={{ padding: 'var(--spacing-4)', marginTop: 'var(--spacing-3)' }}>
+                    <div className="settings-row" style={{ marginBottom: 'var(--spacing-3)' }}>
+                        <div className="settings-row-info">
+                            <span className="settings-row-title" style={{ fontSize: '0.9rem' }}>Chat Provider</span>
+                            <span style={{ fontSize: '0.75rem', color: 'var(--color-text-tertiary)' }}>
+                                OpenAI-compatible servers include Ollama and LM Studio. Memory and voice still use Gemini.
+                            </span>
+                        </div>
+                        <select
+                            className="input"
+                            value={llmProvider}
+                            onChange={(e) => setLlmProvider(e.target.value)}
+                            style={{ fontSize: '0.85rem', padding: '4px 8px', width: 'auto' }}
+                        >
+                            {llmProviders.map(p => (
+                                <option key={p} value={p}>{p === 'openai' ? 'OpenAI-compatible' : 'Gemini'}</option>
+                            ))}
+                        </select>
+                    </div>
+
+                    {llmProvider === 'openai' && (
+                        <div style={{ display: 'flex', flexDirection: 'column', gap: 'var(--spacing-2)', marginBottom: 'var(--spacing-3)' }}>
+                            <input
+                                className="input"
+                                value={openaiBaseUrl}
+                                onChange={(e) => setOpenaiBaseUrl(e.target.value)}
+                                placeholder="https://api.openai.com/v1 or http://localhost:11434/v1"
+                                style={{ fontSize: '0.9rem', padding: '6px 10px', fontFamily: 'monospace' }}
+                            />
+                            <input
+                                className="input"
+                                value={openaiModel}
+                                onChange={(e) => setOpenaiModel(e.target.value)}
+                                placeholder="Model, e.g. gpt-4o-mini or llama3.1"
+                                style={{ fontSize: '0.9rem', padding: '6px 10px', fontFamily: 'monospace' }}
+                            />
+                            <input
+                                type="password"
+                                className="input"
+                                value={openaiApiKey}
+                                onChange={(e) => setOpenaiApiKey(e.target.value)}
+                                placeholder={openaiKeyConfigured ? '••••••••••••••••••••••••' : 'API Key (optional for local servers)'}
+                                style={{ fontSize: '0.9rem', padding: '6px 10px', fontFamily: 'monospace' }}
+                            />
+                        </div>
+                    )}
+
+                    <div className="settings-row" style={{ marginBottom: 'var(--spacing-3)' }}>
+                        <div className="settings-row-info">
+                            <span className="settings-row-title" style={{ fontSize: '0.9rem' }}>Max Tool Steps</span>
+                            <span style={{ fontSize: '0.75rem', color: 'var(--color-text-tertiary)' }}>
+                                How many rounds of tool calls one message may take before Lumen wraps up.
+                            </span>
+                        </div>
+                        <select
+                            className="input"
+                            value={maxToolRounds}
+                            onChange={(e) => saveMaxToolRounds(Number(e.target.value))}
+                            style={{ fontSize: '0.85rem', padding: '4px 8px', width: 'auto' }}
+                        >
+                            {Array.from({ length: 10 }, (_, i) => i + 1).map(n => (
+                                <option key={n} value={n}>{n}</option>
+                            ))}
+                        </select>
+                    </div>
+
+                    <div style={{ display: 'flex', justifyContent: 'flex-end' }}>
+                        <button className="btn btn-primary btn-sm" onClick={saveLlmProvider} disabled={saving} style={{ fontSize: '0.8rem' }}>
+                            Save Provider
+                        </button>
+                    </div>
+                </div>
             </section>
 
             {/* Data */}
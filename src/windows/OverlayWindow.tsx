@@ -4,9 +4,10 @@
 import { useState, useEffect, useRef } from 'react';
 import ReactMarkdown from 'react-markdown';
 import remarkGfm from 'remark-gfm';
-import { Send, X, Loader2, FileText, Scan, CalendarDays, LayoutDashboard, MessageSquare } from 'lucide-react';
+import { Send, Square, X, Loader2, FileText, Scan, CalendarDays, LayoutDashboard, MessageSquare } from 'lucide-react';
 import { invoke } from '@tauri-apps/api/core';
 import CalendarView from '../components/CalendarView';
+import { errorMessage } from '../errors';
 
 //INFO: Chat message type
 interface ChatMessage {
@@ -22,6 +23,21 @@ interface SendMessageResponse {
     assistant_message: ChatMessage;
     suggested_view?: 'chat' | 'calendar';
     suggested_date?: string;
+    history_summarized?: boolean;
+    cancelled?: boolean;
+}
+
+interface CredentialsHealth {
+    key_error: string | null;
+    unreadable: string[];
+    total: number;
+}
+
+interface NotificationTarget {
+    provider: 'gmail' | 'calendar';
+    item_id: string;
+    title: string;
+    url: string | null;
 }
 
 function OverlayWindow() {
@@ -35,6 +51,10 @@ function OverlayWindow() {
     const [transitionView, setTransitionView] = useState<'chat' | 'calendar'>('chat');
     const [isCalendarExpanded, setIsCalendarExpanded] = useState(false);
     const [suggestedDate, setSuggestedDate] = useState<string | undefined>(undefined);
+    const [lastNotification, setLastNotification] = useState<NotificationTarget | null>(null);
+    const [pendingOpen, setPendingOpen] = useState<string | null>(null);
+    const [unreadableCredentials, setUnreadableCredentials] = useState<string[] | null>(null);
+    const [confirmCredentialsReset, setConfirmCredentialsReset] = useState(false);
     const messagesEndRef = useRef<HTMLDivElement>(null);
     const inputRef = useRef<HTMLTextAreaElement>(null);
 
@@ -146,6 +166,130 @@ function OverlayWindow() {
         };
     }, []);
 
+    //INFO: Apply the overlay opacity chosen in settings (the window itself is transparent)
+    useEffect(() => {
+        let unlisten: (() => void) | null = null;
+        const applyOpacity = (opacity: number) => {
+            document.body.style.opacity = String(opacity);
+        };
+
+        async function setupListener() {
+            // @ts-ignore
+            const { listen } = await import('@tauri-apps/api/event');
+            unlisten = await listen<{ opacity: number }>('overlay-appearance', (event) => {
+                applyOpacity(event.payload.opacity);
+            });
+            invoke<{ opacity: number }>('get_overlay_appearance')
+                .then((appearance) => applyOpacity(appearance.opacity))
+                .catch(console.error);
+        }
+
+        setupListener();
+        return () => {
+            if (unlisten) unlisten();
+        };
+    }, []);
+
+    //INFO: lumen://chat?message=... links pre-fill the input (the user still sends it)
+    useEffect(() => {
+        let unlisten: (() => void) | null = null;
+
+        async function setupListener() {
+            // @ts-ignore
+            const { listen } = await import('@tauri-apps/api/event');
+            unlisten = await listen<string>('overlay-prefill', (event) => {
+                setTransitionView('chat');
+                setCurrentView('chat');
+                setInputValue(event.payload);
+                inputRef.current?.focus();
+            });
+        }
+
+        setupListener();
+        return () => {
+            if (unlisten) unlisten();
+        };
+    }, []);
+
+    //INFO: lumen://open links wait here until the user confirms (links can come from any app or web page)
+    useEffect(() => {
+        let unlisten: (() => void) | null = null;
+
+        async function setupListener() {
+            // @ts-ignore
+            const { listen } = await import('@tauri-apps/api/event');
+            unlisten = await listen<string>('overlay-confirm-open', (event) => {
+                setPendingOpen(event.payload);
+            });
+        }
+
+        setupListener();
+        return () => {
+            if (unlisten) unlisten();
+        };
+    }, []);
+
+    const confirmOpen = async (path: string) => {
+        setPendingOpen(null);
+        try {
+            await invoke('confirm_open_link', { path });
+        } catch (err) {
+            setError(String(err));
+        }
+    };
+
+    //INFO: Saved credentials that no longer decrypt (lost or replaced encryption key) need a reset before anything works
+    useEffect(() => {
+        invoke<CredentialsHealth>('get_credentials_health')
+            .then(health => {
+                if (health.key_error || health.unreadable.length > 0) {
+                    setUnreadableCredentials(health.unreadable);
+                }
+            })
+            .catch(err => console.error('Failed to check credentials:', err));
+    }, []);
+
+    const resetCredentials = async () => {
+        setConfirmCredentialsReset(false);
+        try {
+            await invoke<string[]>('reset_credentials');
+            setUnreadableCredentials(null);
+        } catch (err) {
+            setError(errorMessage(err));
+        }
+    };
+
+    //INFO: The latest proactive notification, so it can be opened from here (desktop notifications aren't clickable)
+    useEffect(() => {
+        let unlisten: (() => void) | null = null;
+
+        async function setupListener() {
+            // @ts-ignore
+            const { listen } = await import('@tauri-apps/api/event');
+            unlisten = await listen<NotificationTarget>('notification-shown', (event) => {
+                setLastNotification(event.payload);
+            });
+        }
+
+        setupListener();
+        return () => {
+            if (unlisten) unlisten();
+        };
+    }, []);
+
+    const openNotification = async (target: NotificationTarget) => {
+        try {
+            await invoke('open_notification_item', {
+                provider: target.provider,
+                itemId: target.item_id,
+                url: target.url,
+            });
+            setLastNotification(null);
+        } catch (err) {
+            setError(String(err));
+        }
+    };
+
     //INFO: Listen for window focus events
     useEffect(() => {
         let unlisten: (() => void) | null = null;
@@ -214,6 +358,7 @@ function OverlayWindow() {
     //INFO: Listen for snipped images from the snipper window
     useEffect(() => {
         let unlisten: (() => void) | null = null;
+        let unlistenChat: (() => void) | null = null;
         async function setupSnipListener() {
             // @ts-ignore
             const { listen } = await import('@tauri-apps/api/event');
@@ -221,10 +366,20 @@ function OverlayWindow() {
                 setCapturedImage(event.payload);
                 switchView('chat');
             });
+            //INFO: "Snip and ask" answers arrive here instead of through handleSendMessage
+            unlistenChat = await listen<SendMessageResponse>('snipped-chat', (event) => {
+                setMessages(prev => [
+                    ...prev.filter(m => m.id !== null && m.id !== -1),
+                    event.payload.user_message,
+                    event.payload.assistant_message
+                ]);
+                switchView('chat');
+            });
         }
         setupSnipListener();
         return () => {
             if (unlisten) unlisten();
+            if (unlistenChat) unlistenChat();
         };
     }, [transitionView]);
 
@@ -295,13 +450,22 @@ function OverlayWindow() {
                 }
             }
         } catch (err) {
-            setError(String(err));
+            setError(errorMessage(err));
             setMessages(prev => prev.filter(m => m.id !== null && m.id !== -1));
         } finally {
             setIsLoading(false);
         }
     }
 
+    //INFO: Stops the in-flight request; send_chat_message still resolves with the partial reply
+    async function stopGeneration() {
+        try {
+            await invoke('cancel_chat', { sessionId: null });
+        } catch (err) {
+            console.error('Failed to stop generation:', err);
+        }
+    }
+
     function handleKeyDown(event: React.KeyboardEvent<HTMLTextAreaElement>) {
         if (event.key === 'Enter' && !event.shiftKey) {
             event.preventDefault();
@@ -367,23 +531,14 @@ function OverlayWindow() {
                                                 },
                                                 a: ({ node, ...props }) => {
                                                     const href = props.href || '';
-                                                    if (href.startsWith('lumen://open')) {
+                                                    if (href.startsWith('lumen://')) {
                                                         return (
                                                             <a
                                                                 {...props}
                                                                 href="#"
                                                                 onClick={(e) => {
                                                                     e.preventDefault();
-                                                                    try {
-                                                                        const url = new URL(href);
-                                                                        const rawPath = url.searchParams.get('path');
-                                                                        if (rawPath) {
-                                                                            const path = decodeURIComponent(rawPath);
-                                                                            invoke('open_path', { path });
-                                                                        }
-                                                                    } catch (err) {
-                                                                        console.error('Failed to parse lumen link', err);
-                                                                    }
+                                                                    invoke('open_lumen_link', { url: href }).catch(console.error);
                                                                 }}
                                                                 className="lumen-pill"
                                                             >
@@ -416,6 +571,49 @@ function OverlayWindow() {
 
                             {error && <div className="error-message">{error}</div>}
 
+                            {pendingOpen && (
+                                <div className="notification-banner" style={{ display: 'flex', alignItems: 'center', gap: 'var(--spacing-2)', fontSize: 'var(--font-size-sm)' }}>
+                                    <span style={{ flex: 1, overflow: 'hidden', textOverflow: 'ellipsis', whiteSpace: 'nowrap' }} title={pendingOpen}>
+                                        Open {pendingOpen}?
+                                    </span>
+                                    <button className="btn btn-secondary btn-sm" onClick={() => confirmOpen(pendingOpen)}>Open</button>
+                                    <button className="btn btn-ghost btn-sm" onClick={() => setPendingOpen(null)}>Cancel</button>
+                                </div>
+                            )}
+
+                            {unreadableCredentials && (
+                                <div className="notification-banner" style={{ display: 'flex', alignItems: 'center', gap: 'var(--spacing-2)', fontSize: 'var(--font-size-sm)' }}>
+                                    <span style={{ flex: 1 }}>
+                                        {confirmCredentialsReset
+                                            ? 'Clear the unreadable credentials? You will need to re-enter them.'
+                                            : `Saved credentials can't be read${unreadableCredentials.length > 0 ? ` (${unreadableCredentials.join(', ')})` : ''}.`}
+                                    </span>
+                                    {confirmCredentialsReset ? (
+                                        <>
+                                            <button className="btn btn-secondary btn-sm" onClick={resetCredentials}>Reset</button>
+                                            <button className="btn btn-ghost btn-sm" onClick={() => setConfirmCredentialsReset(false)}>Cancel</button>
+                                        </>
+                                    ) : (
+                                        <>
+                                            <button className="btn btn-secondary btn-sm" onClick={() => setConfirmCredentialsReset(true)}>Reset credentials</button>
+                                            <button className="btn btn-ghost btn-sm" onClick={() => setUnreadableCredentials(null)}>Dismiss</button>
+                                        </>
+                                    )}
+                                </div>
+                            )}
+
+                            {lastNotification && (
+                                <div className="notification-banner" style={{ display: 'flex', alignItems: 'center', gap: 'var(--spacing-2)', fontSize: 'var(--font-size-sm)' }}>
+                                    <span style={{ flex: 1, overflow: 'hidden', textOverflow: 'ellipsis', whiteSpace: 'nowrap' }}>
+                                        {lastNotification.title}
+                                    </span>
+                                    <button className="btn btn-secondary btn-sm" onClick={() => openNotification(lastNotification)}>
+                                        {lastNotification.provider === 'gmail' ? 'Open email' : 'Open event'}
+                                    </button>
+                                    <button className="btn btn-ghost btn-sm" onClick={() => setLastNotification(null)}>Dismiss</button>
+                                </div>
+                            )}
+
                             <div className="chat-spacer" />
                             <div ref={messagesEndRef} />
                         </div>
@@ -518,10 +716,11 @@ function OverlayWindow() {
                         />
                         <button
                             className="chat-send-btn"
-                            onClick={handleSendMessage}
-                            disabled={(!inputValue.trim() && !capturedImage) || isLoading}
+                            onClick={isLoading ? stopGeneration : handleSendMessage}
+                            disabled={!isLoading && !inputValue.trim() && !capturedImage}
+                            title={isLoading ? 'Stop' : 'Send'}
                         >
-                            {isLoading ? <Loader2 size={16} className="loading-spinner" /> : <Send size={16} />}
+                            {isLoading ? <Square size={14} /> : <Send size={16} />}
                         </button>
                     </div>
                 </div>